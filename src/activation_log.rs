@@ -0,0 +1,33 @@
+//! What NetworkManager logged during a start attempt.
+//!
+//! "Activation failed" rarely says why; the reason (a driver refusing AP
+//! mode, missing firmware, a regulatory block) is in NetworkManager's and
+//! wpa_supplicant's journal. Each start keeps the window of journal it ran
+//! in, so "Details" and the self-test can show it.
+
+use std::process::Command;
+
+/// Lines kept per attempt; a failing driver can log a lot
+pub const MAX_LINES: usize = 200;
+
+/// The last `max` non-empty lines of `log`
+pub fn last_lines(log: &str, max: usize) -> Vec<String> {
+    let lines: Vec<&str> = log.lines().filter(|line| !line.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(max)..].iter().map(|line| line.to_string()).collect()
+}
+
+/// NetworkManager and wpa_supplicant journal entries between the Unix times
+/// `since` and `until`. Empty if the journal isn't readable (the user needs
+/// to be in the systemd-journal or adm group).
+pub fn capture(since: u64, until: u64) -> Vec<String> {
+    let Ok(output) = Command::new("journalctl")
+        .args(["-u", "NetworkManager", "-u", "wpa_supplicant", "-o", "short-precise", "-q", "--no-pager"])
+        .arg(format!("--since=@{since}"))
+        // Entries from the final second too
+        .arg(format!("--until=@{}", until + 1))
+        .output()
+    else {
+        return Vec::new();
+    };
+    last_lines(&String::from_utf8_lossy(&output.stdout), MAX_LINES)
+}
@@ -0,0 +1,384 @@
+//! WiFi adapter capability detection.
+//!
+//! NetworkManager reports whether a device supports AP mode
+//! (`WIFI-PROPERTIES.AP`); `iw phy` interface combinations tell whether it
+//! can host an AP while staying connected as a client on the same radio.
+//! `details` adds what the settings page's "Adapter details" shows: driver,
+//! bands and channels, AP client limit and rfkill state.
+
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::nat;
+use crate::nm_dbus;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WifiAdapter {
+    pub name: String,
+    /// False only when NM positively reports no AP support
+    pub ap_capable: bool,
+    /// Can run an AP and a client connection at the same time
+    pub concurrent_ap_sta: bool,
+}
+
+impl WifiAdapter {
+    /// Dropdown label, e.g. "wlan0 (AP + client)"
+    pub fn label(&self) -> String {
+        if self.concurrent_ap_sta {
+            format!("{} (AP + client)", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// What an adapter can do, for the settings page and bug reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterDetails {
+    /// Kernel driver, e.g. "mt7921u"
+    pub driver: Option<String>,
+    pub bands: Vec<BandInfo>,
+    /// Most clients the driver accepts in AP mode, if it says
+    pub max_ap_clients: Option<u32>,
+    pub concurrent_ap_sta: bool,
+    pub rfkill: Rfkill,
+}
+
+/// One band from `iw phy info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BandInfo {
+    /// "2.4 GHz", "5 GHz", "6 GHz" or "60 GHz"
+    pub name: &'static str,
+    /// Channels an AP may be started on
+    pub ap_channels: Vec<u32>,
+    /// Channels the regulatory domain allows for clients only ("no IR")
+    pub client_only_channels: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rfkill {
+    Unblocked,
+    /// Blocked in software (`rfkill unblock wifi` lifts it)
+    SoftBlocked,
+    /// Blocked by a switch or the firmware
+    HardBlocked,
+    Unknown,
+}
+
+impl Rfkill {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Unblocked => "Not blocked",
+            Self::SoftBlocked => "Soft-blocked (rfkill unblock wifi)",
+            Self::HardBlocked => "Hard-blocked (hardware switch or firmware)",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+/// Everything `AdapterDetails` covers for `device`; runs `iw`
+pub fn details(device: &str) -> AdapterDetails {
+    let phy = phy_name(device);
+    let info = phy.as_deref().and_then(iw_phy_info).unwrap_or_default();
+    AdapterDetails {
+        driver: std::fs::read_link(format!("/sys/class/net/{device}/device/driver"))
+            .ok()
+            .and_then(|path| path.file_name().map(|name| name.to_string_lossy().into_owned())),
+        bands: parse_bands(&info),
+        max_ap_clients: parse_max_ap_clients(&info),
+        concurrent_ap_sta: supports_concurrent_ap_sta(&info),
+        rfkill: phy.as_deref().map_or(Rfkill::Unknown, rfkill_state),
+    }
+}
+
+/// The bands and channels in `iw phy <phy> info`. Disabled channels are left
+/// out; "no IR" ones can't host an AP.
+pub fn parse_bands(iw_info: &str) -> Vec<BandInfo> {
+    let mut bands: Vec<BandInfo> = Vec::new();
+    for line in iw_info.lines() {
+        let line = line.trim();
+        // "* 2412 MHz [1] (22.0 dBm)", "* 5260 MHz [52] (20.0 dBm) (no IR, radar detection)"
+        let Some(entry) = line.strip_prefix("* ").filter(|l| l.contains(" MHz [")) else {
+            continue;
+        };
+        if entry.contains("(disabled)") {
+            continue;
+        }
+        // Newer iw prints "2412.0 MHz"
+        let Some(mhz) = entry
+            .split_whitespace()
+            .next()
+            .and_then(|f| f.split('.').next()?.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Some(channel) = entry
+            .split_once('[')
+            .and_then(|(_, rest)| rest.split_once(']'))
+            .and_then(|(channel, _)| channel.parse().ok())
+        else {
+            continue;
+        };
+        let name = band_name(mhz);
+        let index = match bands.iter().position(|b| b.name == name) {
+            Some(index) => index,
+            None => {
+                bands.push(BandInfo { name, ap_channels: Vec::new(), client_only_channels: Vec::new() });
+                bands.len() - 1
+            }
+        };
+        if entry.contains("no IR") {
+            bands[index].client_only_channels.push(channel);
+        } else {
+            bands[index].ap_channels.push(channel);
+        }
+    }
+    bands
+}
+
+/// A channel list with runs collapsed: "1-11, 36, 40"
+pub fn format_channels(channels: &[u32]) -> String {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for &channel in channels {
+        match runs.last_mut() {
+            Some((_, end)) if channel == *end + 1 => *end = channel,
+            _ => runs.push((channel, channel)),
+        }
+    }
+    runs.iter()
+        .map(|&(start, end)| match end - start {
+            0 => start.to_string(),
+            1 => format!("{start}, {end}"),
+            _ => format!("{start}-{end}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// "Maximum associated stations in AP mode: 32" from `iw phy <phy> info`
+pub fn parse_max_ap_clients(iw_info: &str) -> Option<u32> {
+    iw_info
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Maximum associated stations in AP mode:"))
+        .and_then(|n| n.trim().parse().ok())
+}
+
+fn band_name(mhz: u32) -> &'static str {
+    match mhz {
+        0..=3000 => "2.4 GHz",
+        3001..=5925 => "5 GHz",
+        5926..=7125 => "6 GHz",
+        _ => "60 GHz",
+    }
+}
+
+/// The phy's rfkill switch: /sys/class/ieee80211/<phy>/rfkillN/{soft,hard}
+fn rfkill_state(phy: &str) -> Rfkill {
+    let Some(switch) = std::fs::read_dir(format!("/sys/class/ieee80211/{phy}"))
+        .ok()
+        .and_then(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("rfkill")))
+        })
+    else {
+        return Rfkill::Unknown;
+    };
+    let blocked = |attr: &str| {
+        std::fs::read_to_string(switch.join(attr)).map(|v| v.trim() == "1")
+    };
+    match (blocked("hard"), blocked("soft")) {
+        (Ok(true), _) => Rfkill::HardBlocked,
+        (_, Ok(true)) => Rfkill::SoftBlocked,
+        (Ok(false), Ok(false)) => Rfkill::Unblocked,
+        _ => Rfkill::Unknown,
+    }
+}
+
+/// The USB device of `interface` (its sysfs name, e.g. "1-2") if the kernel
+/// may autosuspend it, which takes many USB adapters' access points down
+/// after a few idle minutes. `None` for built-in adapters and for USB ones
+/// already kept powered.
+pub fn autosuspending_usb_device(interface: &str) -> Option<String> {
+    let path = std::fs::canonicalize(format!("/sys/class/net/{interface}/device")).ok()?;
+    let device = usb_device_name(&path)?;
+    let control = std::fs::read_to_string(format!("{}/{device}/power/control", nat::USB_DEVICES_DIR)).ok()?;
+    (control.trim() == "auto").then_some(device)
+}
+
+/// The USB device a network device's sysfs path belongs to. For USB
+/// adapters the path ends in the device's interface, e.g.
+/// ".../usb1/1-2/1-2:1.0" for device "1-2".
+pub fn usb_device_name(device_path: &std::path::Path) -> Option<String> {
+    let interface = device_path.file_name()?.to_str()?;
+    let (device, _) = interface.split_once(':')?;
+    let parent = device_path.parent()?.file_name()?.to_str()?;
+    (parent == device).then(|| nat::validate_usb_device(device).ok())?
+}
+
+/// Explains which adapters were left out of the hotspot interface list
+pub fn exclusion_hint(adapters: &[WifiAdapter]) -> Option<String> {
+    let excluded: Vec<&str> = adapters
+        .iter()
+        .filter(|a| !a.ap_capable)
+        .map(|a| a.name.as_str())
+        .collect();
+    (!excluded.is_empty()).then(|| {
+        format!("Not shown (no access point support): {}", excluded.join(", "))
+    })
+}
+
+/// Probe one WiFi device's capabilities
+pub fn probe(device: &str) -> WifiAdapter {
+    WifiAdapter {
+        name: device.to_string(),
+        // Unknown to NM counts as capable; the start itself will tell
+        ap_capable: nm_dbus::ap_capable(device).unwrap_or(true),
+        concurrent_ap_sta: phy_name(device)
+            .and_then(|phy| iw_phy_info(&phy))
+            .is_some_and(|info| supports_concurrent_ap_sta(&info)),
+    }
+}
+
+fn phy_name(device: &str) -> Option<String> {
+    std::fs::read_to_string(format!("/sys/class/net/{device}/phy80211/name"))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+fn iw_phy_info(phy: &str) -> Option<String> {
+    let output = Command::new("iw").args(["phy", phy, "info"]).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether any "valid interface combinations" entry of `iw phy <phy> info`
+/// allows one managed (client) and one AP interface at once
+pub fn supports_concurrent_ap_sta(iw_info: &str) -> bool {
+    let Some(start) = iw_info.find("valid interface combinations:") else {
+        return false;
+    };
+    let section = &iw_info[start..];
+    // Combinations start with "*"; each may wrap onto continuation lines
+    section
+        .split('*')
+        .skip(1)
+        .map(|combo| {
+            combo
+                .lines()
+                .take_while(|line| !line.trim_end().ends_with(':') || line.contains('#'))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .any(|combo| combination_allows_ap_sta(&combo))
+}
+
+/// Parse "#{ managed } <= 1, #{ AP, P2P-GO } <= 1, total <= 2, #channels <= 1"
+fn combination_allows_ap_sta(combo: &str) -> bool {
+    let mut managed_group = None;
+    let mut ap_group = None;
+    let mut groups = Vec::new();
+
+    for part in combo.split("#{").skip(1) {
+        let Some((types, rest)) = part.split_once('}') else {
+            continue;
+        };
+        let limit = parse_limit(rest).unwrap_or(0);
+        let types: Vec<&str> = types.split(',').map(str::trim).collect();
+        if types.contains(&"managed") {
+            managed_group = Some(groups.len());
+        }
+        if types.contains(&"AP") {
+            ap_group = Some(groups.len());
+        }
+        groups.push(limit);
+    }
+
+    let total = combo
+        .split("total")
+        .nth(1)
+        .and_then(parse_limit)
+        .unwrap_or(0);
+
+    match (managed_group, ap_group) {
+        (Some(m), Some(a)) if m == a => groups[m] >= 2 && total >= 2,
+        (Some(m), Some(a)) => groups[m] >= 1 && groups[a] >= 1 && total >= 2,
+        _ => false,
+    }
+}
+
+/// The number after the first "<=" in `text`
+fn parse_limit(text: &str) -> Option<u32> {
+    let after = text.split_once("<=")?.1.trim_start();
+    let digits: String = after.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Whether `id` is a MAC address ("00:c0:ca:12:34:56") or a USB
+/// vendor:product id ("0e8d:7612")
+pub fn is_adapter_id(id: &str) -> bool {
+    let groups: Vec<&str> = id.trim().split(':').collect();
+    let hex = |g: &&str, len| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit());
+    match groups.len() {
+        6 => groups.iter().all(|g| hex(g, 2)),
+        2 => groups.iter().all(|g| hex(g, 4)),
+        _ => false,
+    }
+}
+
+/// Whether an adapter with this MAC address and USB id (if it's a USB
+/// device) is the one `id` names
+pub fn matches_adapter_id(id: &str, mac: &str, usb_id: Option<&str>) -> bool {
+    let id = id.trim();
+    id.eq_ignore_ascii_case(mac.trim()) || usb_id.is_some_and(|u| id.eq_ignore_ascii_case(u))
+}
+
+/// The WiFi interface `id` (MAC or USB id) refers to, if it's plugged in.
+/// A virtual AP shares its parent's MAC; the parent sorts first.
+pub fn find_by_id(id: &str) -> Option<String> {
+    let mut names: Vec<String> = std::fs::read_dir("/sys/class/net")
+        .ok()?
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| phy_name(name).is_some())
+        .collect();
+    names.sort();
+    names.into_iter().find(|name| {
+        let mac = std::fs::read_to_string(format!("/sys/class/net/{name}/address")).unwrap_or_default();
+        matches_adapter_id(id, &mac, usb_id(name).as_deref())
+    })
+}
+
+/// The MAC address of the network interface `name`, if it exists
+pub fn interface_mac(name: &str) -> Option<String> {
+    let mac = std::fs::read_to_string(format!("/sys/class/net/{name}/address")).ok()?;
+    let mac = mac.trim().to_lowercase();
+    (!mac.is_empty() && mac != "00:00:00:00:00:00").then_some(mac)
+}
+
+/// The network interface with MAC address `mac`, if it's present. A virtual
+/// AP shares its parent's MAC; the parent sorts first.
+pub fn find_by_mac(mac: &str) -> Option<String> {
+    let mut names: Vec<String> = std::fs::read_dir("/sys/class/net")
+        .ok()?
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names.into_iter().find(|name| interface_mac(name).is_some_and(|m| m.eq_ignore_ascii_case(mac)))
+}
+
+/// "vendor:product" of the USB device behind `device`, if it is one
+fn usb_id(device: &str) -> Option<String> {
+    // `device` links to the USB interface; its parent is the USB device
+    let read = |attr: &str| {
+        std::fs::read_to_string(format!("/sys/class/net/{device}/device/../{attr}"))
+            .ok()
+            .map(|s| s.trim().to_string())
+    };
+    Some(format!("{}:{}", read("idVendor")?, read("idProduct")?))
+}
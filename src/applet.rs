@@ -6,24 +6,152 @@ use cosmic::iced_runtime::core::window;
 use cosmic::surface::action::{app_popup, destroy_popup};
 use cosmic::widget::{self, text};
 use cosmic::Element;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
-use crate::config::Config;
-use crate::hotspot;
+use crate::applet_state::AppletState;
+use crate::backend;
+use crate::card;
+use crate::clipboard;
+use crate::clients::{self, ClientInfo, ClientSort, ClientState, ClientTracker, IdleTimer};
+use crate::config::{ClientPolicy, Config};
+use crate::dbus_service::{self, SharedClients};
+use crate::errors::{ErrorKind, HotspotError};
+use crate::event_log::{self, Level};
+use crate::adapters::{self, Rfkill};
+use crate::auth_log;
+use crate::automation;
+use crate::dns_log::{self, ClientLookups};
+use crate::health::{self, RepairHistory};
+use crate::hotplug;
+use crate::hotspot::{self, BusyResolution, ForeignHotspot};
+use crate::interface_cache;
+use crate::ip_conflicts::{Conflict, ConflictTracker};
+use crate::modem::{self, CellularStatus};
+use crate::nm_dbus;
+use crate::notify;
+use crate::password;
+use crate::progress::{self, Step};
+use crate::qr;
+use crate::run_state;
+use crate::repeater::{ClientLink, ScannedNetwork};
+use crate::schedule;
+use crate::stats;
+use crate::supervisor;
+use crate::stations::{self, Radio};
+use crate::usage::{self, Quota};
+use crate::wol;
 
 const APP_ID: &str = "io.github.reality2_roycdavies.cosmic-hotspot";
 
+/// Besides a command, what can end the background loop's wait before the
+/// next poll
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Wakeup {
+    /// An interface appeared or disappeared
+    Hotplug,
+    /// NetworkManager's state, devices or connections changed
+    NetworkManager,
+}
+
 enum HotspotCommand {
     Toggle,
+    StartResolving(BusyResolution),
+    /// Take over another tool's hotspot connection
+    Adopt(String),
+    /// Stop and start again so changed settings take effect
+    Restart,
+    /// Start after a nearby network was found using the SSID, optionally
+    /// renaming ours first
+    StartDespiteCollision { rename: bool },
+    /// Swap one client's access rules on the running hotspot
+    UpdateClientPolicy {
+        previous: Option<ClientPolicy>,
+        current: Option<ClientPolicy>,
+    },
+    /// Save the printable credentials card and open it
+    ExportCard,
+    /// Open (true) or cancel WPS push-button pairing
+    Wps(bool),
+    /// Keep the USB adapter from autosuspending while the hotspot runs
+    HoldUsbPower,
+    /// Deauthenticate a client: its MAC address, and the name to report it by
+    DisconnectClient { mac: String, name: String },
+    /// Try a failed autostart again, from its notification
+    RetryAutostart,
+    /// Stop the running hotspot and start it again with this profile
+    SwitchProfile(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 enum HotspotEvent {
     StatusUpdate {
         active: bool,
-        clients: Vec<String>,
+        clients: Vec<ClientInfo>,
+        /// Whether any WiFi adapter is present to host the hotspot
+        wifi_available: bool,
+        /// What the internet interface is connected to
+        upstream: Option<String>,
+        /// Upstream modem status, when sharing a cellular connection
+        cellular: Option<CellularUpstream>,
+        /// Client-side link of the upstream adapter, in repeater mode
+        client_link: Option<ClientLink>,
+        /// Another tool's hotspot running on the adapter, while ours is off
+        foreign: Option<ForeignHotspot>,
+        /// Wrong-password join attempts this session, per device
+        failed_joins: Vec<(String, usize)>,
+        /// Recent DNS lookups per client, while the query log is on
+        dns_lookups: Vec<ClientLookups>,
+        /// Traffic through the hotspot interface since the previous poll
+        throughput_bps: Option<u64>,
+        /// How long the hotspot has been running, if its start was recorded
+        uptime: Option<Duration>,
+        /// The data quota and bytes used in its current period, if one is set
+        quota: Option<(Quota, u64)>,
+        /// The running hotspot was started with older settings than the saved ones
+        outdated: bool,
+        /// USB device of the hotspot adapter that the kernel may autosuspend,
+        /// while the hotspot runs and it isn't kept powered
+        autosuspend: Option<String>,
+        /// Devices using the gateway's or each other's address, recently
+        ip_conflicts: Vec<Conflict>,
     },
     ToggleStarted,
-    ToggleComplete(Result<String, String>),
+    /// The start/stop in progress reached this step
+    Progress(Step),
+    ToggleComplete(Result<String, HotspotError>),
+    /// Start was refused because the adapter is connected as a client
+    InterfaceBusy(BusyConflict),
+    /// Start was held back because a nearby network already uses the SSID
+    SsidCollision(ScannedNetwork),
+    /// Outcome of `HotspotCommand::UpdateClientPolicy`
+    ClientPolicyUpdated(Result<(), String>),
+    /// Outcome of `HotspotCommand::ExportCard`: the PDF's path
+    CardExported(Result<std::path::PathBuf, String>),
+    /// Outcome of `HotspotCommand::Wps`, with whether it opened pairing
+    WpsChanged { enable: bool, result: Result<(), String> },
+    /// Outcome of `HotspotCommand::HoldUsbPower`
+    UsbPowerHeld(Result<(), String>),
+    /// The background loop panicked and was started again; the panic message
+    BackendRestarted(String),
+    /// Outcome of `HotspotCommand::DisconnectClient`, with the client's name
+    ClientDisconnected { name: String, result: Result<(), String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CellularUpstream {
+    status: CellularStatus,
+    /// Bytes through the modem since the hotspot started
+    session_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BusyConflict {
+    interface: String,
+    /// The client connection occupying the adapter
+    connection: String,
+    /// Whether the adapter can host a virtual AP alongside the client link
+    virtual_ap_supported: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -31,27 +159,174 @@ pub enum Message {
     PollStatus,
     AnimationTick,
     ToggleHotspot,
+    CopyNetworkInfo,
+    /// The clear timeout of the copy with this number ran out
+    ClipboardExpired(u64),
+    /// What the clipboard holds when a copy's timeout ran out
+    ClipboardChecked(Option<String>),
+    ExportCard,
+    /// Open WPS push-button pairing (true) or cancel it
+    Wps(bool),
+    ClientFilterChanged(String),
+    CycleClientSort,
+    ToggleErrorDetail,
+    ToggleDnsLookups,
+    DismissStatus,
+    ResolveBusy(BusyResolution),
+    ResolveSsidCollision { rename: bool },
+    /// Open the access policy editor for the client with this IP
+    EditClientPolicy(String),
+    ClientAccessSelected(usize),
+    ClientPortsChanged(String),
+    ClientDaysChanged(String),
+    ClientFromChanged(String),
+    ClientUntilChanged(String),
+    SaveClientPolicy,
+    CloseClientPolicy,
+    /// Flip queueing priority for the client with this IP
+    ToggleClientPriority(String),
+    /// Kick the client with this IP off the hotspot
+    DisconnectClient(String),
+    /// Send a Wake-on-LAN packet to the device with this MAC address
+    WakeClient(String),
+    ToggleKnownClients,
+    AdoptForeign,
+    /// Restart so the saved settings apply to the running hotspot
+    RestartHotspot,
+    /// Stop and start the running hotspot, e.g. to clear a driver glitch
+    Restart,
+    /// Picked in the popup's profile switcher, by index into the profiles
+    ProfileSelected(usize),
+    /// Keep the USB adapter powered, now and on later starts
+    KeepUsbPowered,
+    DismissAutosuspend,
+    RegeneratePassword,
     OpenSettings,
+    OpenPopup,
+    /// Move the popup's content into a regular window that stays open
+    PinPopup,
+    UnpinPopup,
     PopupClosed(Id),
     Surface(cosmic::surface::Action),
 }
 
-/// Animation: dot → 1 ring → 2 rings → 3 rings → 2 rings → 1 ring → dot → repeat
-const ANIM_FRAMES: u8 = 7;
+/// Show the filter/sort controls once the client list grows beyond this
+const CLIENT_CONTROLS_THRESHOLD: usize = 5;
+/// Client rows listed in compact mode before "and N more…"
+const COMPACT_CLIENT_ROWS: usize = 5;
+/// Most height the popup's scrolling middle (details, QR code, clients)
+/// takes before it scrolls
+const POPUP_BODY_HEIGHT: f32 = 420.0;
+/// How long "Network info copied" stays up
+const COPIED_HOLD: Duration = Duration::from_secs(4);
+/// How long WPS push-button pairing stays open (the standard's walk time)
+const WPS_WALK_TIME: Duration = Duration::from_secs(120);
+/// Side of the "scan to join" QR code, in pixels
+const QR_SIZE: u32 = 200;
+/// Side of the QR code in the main popup
+const POPUP_QR_SIZE: f32 = 140.0;
+/// Initial size of the pinned popup window
+const PINNED_SIZE: (f32, f32) = (320.0, 520.0);
+/// Poll clients and traffic this often while the hotspot is on
+const ACTIVE_POLL: Duration = Duration::from_secs(2);
+/// While it's off, NetworkManager signals and hotplug events wake the
+/// background loop; this is the fallback if neither is available
+const IDLE_POLL: Duration = Duration::from_secs(30);
+/// First wait before restarting a crashed background loop, doubling while it
+/// keeps crashing
+const BACKEND_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+/// Only an applet started this soon after boot checks that "Start at boot"
+/// worked; later starts are panel restarts, after the user may have stopped it
+const BOOT_START_WINDOW: Duration = Duration::from_secs(600);
+/// How long after boot NetworkManager gets to bring the hotspot up
+const BOOT_START_GRACE: Duration = Duration::from_secs(90);
+/// Read the journal for failed join attempts every N polls while active (~10s)
+const FAILED_JOIN_CHECK_POLLS: u32 = 5;
+/// Read dnsmasq's query log every N polls while it's on (~10s)
+const DNS_LOG_CHECK_POLLS: u32 = 5;
+/// Check the running hotspot's forwarding and NAT rules every N polls (~60s)
+const HEALTH_CHECK_POLLS: u32 = 30;
+/// Write the quota usage to disk every N polls while traffic flows (~30s)
+const USAGE_SAVE_POLLS: u32 = 15;
+/// Probe for another device using the gateway address every N polls (~30s)
+const GATEWAY_PROBE_POLLS: u32 = 15;
+/// Distinct names listed per client in the DNS lookups section
+const DNS_NAMES_PER_CLIENT: usize = 8;
+
+/// Client access choices in the policy editor; "full" means no policy
+const CLIENT_ACCESS_OPTIONS: &[&str] = &["full", "no-internet", "web-only", "block-ports"];
+const CLIENT_ACCESS_LABELS: &[&str] = &["Full access", "No internet (LAN only)", "Web only", "Block ports"];
+
+/// Access policy being edited for one client in the popup
+#[derive(Debug, Clone)]
+struct PolicyEditor {
+    ip: String,
+    mac: Option<String>,
+    /// Index into CLIENT_ACCESS_OPTIONS
+    access: usize,
+    ports: String,
+    days: String,
+    from: String,
+    until: String,
+}
+
+/// What the open popup shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PopupKind {
+    Main,
+    /// Just the "scan to join" QR code
+    Qr,
+}
 
 pub struct HotspotApplet {
     core: Core,
     popup: Option<Id>,
-    hotspot_active: bool,
-    is_toggling: bool,
-    status_message: String,
-    /// Counts down from N to 0; while > 0, status_message is preserved (not overwritten by polls)
-    status_hold_ticks: u8,
-    connected_clients: Vec<String>,
+    popup_kind: PopupKind,
+    /// Regular window holding the popup content while it's pinned
+    pinned_window: Option<Id>,
+    /// Status line, toggling and icon animation
+    state: AppletState,
+    /// Set when a start was refused because the adapter is in use
+    busy_conflict: Option<BusyConflict>,
+    foreign_hotspot: Option<ForeignHotspot>,
+    /// Nearby network already using our SSID, from the last start attempt
+    ssid_collision: Option<ScannedNetwork>,
+    connected_clients: Vec<ClientInfo>,
+    upstream: Option<String>,
+    cellular: Option<CellularUpstream>,
+    client_link: Option<ClientLink>,
+    failed_joins: Vec<(String, usize)>,
+    dns_lookups: Vec<ClientLookups>,
+    show_dns_lookups: bool,
+    /// Profile names, for the popup's profile switcher
+    profile_labels: Vec<String>,
+    /// Network info last copied, until it's cleared from the clipboard, and
+    /// a count of copies so an older copy's timeout doesn't clear a newer one
+    copied_secret: Option<String>,
+    copies: u64,
+    throughput_bps: Option<u64>,
+    uptime: Option<Duration>,
+    quota: Option<(Quota, u64)>,
+    /// The running hotspot uses older settings than the saved ones
+    running_outdated: bool,
+    /// USB device of the hotspot adapter that may be autosuspended
+    autosuspend: Option<String>,
+    /// The autosuspend offer was turned down this session
+    autosuspend_dismissed: bool,
+    ip_conflicts: Vec<Conflict>,
+    /// When WPS push-button pairing closes, while it's open
+    wps_until: Option<Instant>,
+    show_known_clients: bool,
+    client_filter: String,
+    policy_editor: Option<PolicyEditor>,
+    /// NetworkManager's log of the last failed start, under "Details"
+    activation_log: Vec<String>,
+    /// "Scan to join" code with the `WIFI:` URI it encodes, rebuilt only when
+    /// the SSID or password change; no image if they're too long to encode
+    join_code: (String, Option<svg::Handle>),
     config: Config,
-    cmd_tx: std::sync::mpsc::Sender<HotspotCommand>,
+    cmd_tx: UnboundedSender<HotspotCommand>,
     event_rx: std::sync::mpsc::Receiver<HotspotEvent>,
-    anim_frame: u8,
 }
 
 impl cosmic::Application for HotspotApplet {
@@ -70,36 +345,70 @@ impl cosmic::Application for HotspotApplet {
     }
 
     fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
-        let (cmd_tx, cmd_rx) = std::sync::mpsc::channel();
+        let (cmd_tx, mut cmd_rx) = tokio::sync::mpsc::unbounded_channel();
         let (event_tx, event_rx) = std::sync::mpsc::channel();
 
         let config = Config::load();
         // Save default config if it doesn't exist yet
         let _ = config.save();
 
-        let initial_active = hotspot::is_hotspot_active(&config);
+        let initial_active = backend::active().is_active(&config);
+        let wifi_available = initial_active || backend::active().has_wifi_hardware();
 
+        let retry_tx = cmd_tx.clone();
         std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-            rt.block_on(run_background(cmd_rx, event_tx));
+            // Restarted if it panics; the channels outlive each run
+            supervisor::supervise(
+                || {
+                    let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+                    rt.block_on(run_background(&mut cmd_rx, retry_tx.clone(), event_tx.clone()));
+                },
+                |message, crashes| {
+                    event_log::record(
+                        Level::Error,
+                        format!("Background task crashed ({message}); restarted (crash {crashes} in a row)"),
+                    );
+                    let _ = event_tx.send(HotspotEvent::BackendRestarted(message.to_string()));
+                },
+                BACKEND_RESTART_BACKOFF,
+            );
         });
 
         let applet = Self {
             core,
             popup: None,
-            hotspot_active: initial_active,
-            is_toggling: false,
-            status_hold_ticks: 0,
-            status_message: if initial_active {
-                "Active".to_string()
-            } else {
-                "Inactive".to_string()
-            },
+            popup_kind: PopupKind::Main,
+            pinned_window: None,
+            state: AppletState::new(initial_active, wifi_available),
+            busy_conflict: None,
+            foreign_hotspot: None,
+            ssid_collision: None,
             connected_clients: Vec::new(),
+            upstream: None,
+            cellular: None,
+            client_link: None,
+            failed_joins: Vec::new(),
+            dns_lookups: Vec::new(),
+            show_dns_lookups: false,
+            profile_labels: profile_labels(&config),
+            copied_secret: None,
+            copies: 0,
+            throughput_bps: None,
+            uptime: None,
+            quota: None,
+            running_outdated: false,
+            autosuspend: None,
+            autosuspend_dismissed: false,
+            ip_conflicts: Vec::new(),
+            wps_until: None,
+            show_known_clients: false,
+            client_filter: String::new(),
+            policy_editor: None,
+            activation_log: Vec::new(),
+            join_code: join_code(&config),
             config,
             cmd_tx,
             event_rx,
-            anim_frame: 0,
         };
 
         (applet, Task::none())
@@ -111,55 +420,179 @@ impl cosmic::Application for HotspotApplet {
 
     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
         match message {
-            Message::AnimationTick => {
-                if self.hotspot_active {
-                    self.anim_frame = (self.anim_frame + 1) % ANIM_FRAMES;
-                }
-            }
+            Message::AnimationTick => self.state.animation_tick(),
 
             Message::PollStatus => {
                 while let Ok(event) = self.event_rx.try_recv() {
                     match event {
-                        HotspotEvent::StatusUpdate { active, clients } => {
-                            self.hotspot_active = active;
-                            self.connected_clients = clients;
+                        HotspotEvent::StatusUpdate {
+                            active,
+                            clients,
+                            wifi_available,
+                            upstream,
+                            cellular,
+                            client_link,
+                            foreign,
+                            failed_joins,
+                            dns_lookups,
+                            throughput_bps,
+                            uptime,
+                            quota,
+                            outdated,
+                            autosuspend,
+                            ip_conflicts,
+                        } => {
+                            self.throughput_bps = throughput_bps;
+                            self.uptime = uptime;
+                            self.quota = quota;
+                            self.running_outdated = outdated;
+                            self.autosuspend = autosuspend;
+                            self.ip_conflicts = ip_conflicts;
+                            self.foreign_hotspot = foreign;
+                            self.failed_joins = failed_joins;
+                            self.dns_lookups = dns_lookups;
+                            self.upstream = upstream;
+                            self.cellular = cellular;
+                            self.client_link = client_link;
                             // Reload config so popup reflects settings changes
                             self.config = Config::load();
-                            if self.status_hold_ticks > 0 {
-                                self.status_hold_ticks -= 1;
-                            } else if !self.is_toggling {
-                                self.status_message = if active {
-                                    "Active".to_string()
-                                } else {
-                                    "Inactive".to_string()
-                                };
+                            self.profile_labels = profile_labels(&self.config);
+                            self.refresh_join_code();
+                            self.state.status_update(
+                                active,
+                                wifi_available,
+                                &self.connected_clients,
+                                &clients,
+                                self.config.reduce_motion,
+                                Instant::now(),
+                            );
+                            if !active || self.wps_until.is_some_and(|until| Instant::now() >= until) {
+                                self.wps_until = None;
                             }
+                            self.connected_clients = clients;
                         }
-                        HotspotEvent::ToggleStarted => {
-                            self.is_toggling = true;
-                            self.status_message = if self.hotspot_active {
-                                "Stopping...".to_string()
-                            } else {
-                                "Starting...".to_string()
+                        HotspotEvent::InterfaceBusy(conflict) => {
+                            self.busy_conflict = Some(conflict);
+                        }
+                        HotspotEvent::SsidCollision(network) => {
+                            self.ssid_collision = Some(network);
+                        }
+                        HotspotEvent::ClientPolicyUpdated(result) => {
+                            self.state.status_message = match result {
+                                Ok(()) => "Client access updated".to_string(),
+                                Err(e) => format!("Client access not applied: {e}"),
                             };
+                            self.hold_status(Duration::from_secs(self.config.status_hold_secs));
                         }
-                        HotspotEvent::ToggleComplete(result) => {
-                            self.is_toggling = false;
-                            // Hold the result message for ~10 seconds (5 poll cycles at 2s)
-                            self.status_hold_ticks = 5;
+                        HotspotEvent::WpsChanged { enable, result } => {
                             match result {
-                                Ok(msg) => self.status_message = msg,
-                                Err(e) => self.status_message = format!("Error: {e}"),
+                                Ok(()) if enable => {
+                                    self.wps_until = Some(Instant::now() + WPS_WALK_TIME);
+                                    self.state.status_message =
+                                        "WPS open: press the WPS button on the device".to_string();
+                                }
+                                Ok(()) => {
+                                    self.wps_until = None;
+                                    self.state.status_message = "WPS pairing cancelled".to_string();
+                                }
+                                Err(e) => {
+                                    self.wps_until = None;
+                                    self.state.status_message = format!("WPS not available: {e}");
+                                }
                             }
+                            self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                        }
+                        HotspotEvent::BackendRestarted(message) => {
+                            // A start or stop it was running won't report back;
+                            // the next status update shows where it got to
+                            self.state.is_toggling = false;
+                            self.state.restarting = false;
+                            self.state.status_message = format!("Background task restarted after a crash: {message}");
+                            self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                        }
+                        HotspotEvent::ClientDisconnected { name, result } => {
+                            self.state.status_message = match result {
+                                Ok(()) => format!("Disconnected {name}; it can rejoin with the password"),
+                                Err(e) => format!("Couldn't disconnect {name}: {e}"),
+                            };
+                            self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                        }
+                        HotspotEvent::UsbPowerHeld(result) => {
+                            self.state.status_message = match result {
+                                Ok(()) => "USB adapter kept powered while the hotspot runs".to_string(),
+                                Err(e) => format!("Couldn't keep the USB adapter powered: {e}"),
+                            };
+                            self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                        }
+                        HotspotEvent::CardExported(result) => {
+                            self.state.status_message = match result {
+                                Ok(path) => format!("Card saved to {}", path.display()),
+                                Err(e) => format!("Card not saved: {e}"),
+                            };
+                            self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                        }
+                        HotspotEvent::ToggleStarted => {
+                            self.state.toggle_started();
+                            self.busy_conflict = None;
+                            self.ssid_collision = None;
+                        }
+                        HotspotEvent::Progress(step) => self.state.progress(step),
+                        HotspotEvent::ToggleComplete(result) => {
+                            let hold = Duration::from_secs(self.config.status_hold_secs);
+                            self.activation_log = match &result {
+                                Err(_) => run_state::load().failed_activation_log().unwrap_or_default().to_vec(),
+                                Ok(_) => Vec::new(),
+                            };
+                            self.state.toggle_complete(result, hold, Instant::now());
                         }
                     }
                 }
             }
 
+            Message::DismissStatus => self.state.dismiss(),
+
+            Message::OpenPopup => {
+                let action = match self.popup {
+                    Some(id) => destroy_popup(id),
+                    None => open_popup(PopupKind::Main, None),
+                };
+                return cosmic::task::message(cosmic::Action::Cosmic(
+                    cosmic::app::Action::Surface(action),
+                ));
+            }
+
+            Message::PinPopup => {
+                let (id, open) = cosmic::iced::window::open(cosmic::iced::window::Settings {
+                    size: cosmic::iced::Size::new(PINNED_SIZE.0, PINNED_SIZE.1),
+                    min_size: Some(cosmic::iced::Size::new(260.0, 240.0)),
+                    decorations: true,
+                    exit_on_close_request: false,
+                    ..Default::default()
+                });
+                self.pinned_window = Some(id);
+                let mut tasks = vec![open.discard()];
+                if let Some(popup) = self.popup.take() {
+                    tasks.push(cosmic::task::message(cosmic::Action::Cosmic(
+                        cosmic::app::Action::Surface(destroy_popup(popup)),
+                    )));
+                }
+                return Task::batch(tasks);
+            }
+
+            Message::UnpinPopup => {
+                if let Some(id) = self.pinned_window.take() {
+                    return cosmic::iced::window::close(id);
+                }
+            }
+
             Message::PopupClosed(id) => {
                 if self.popup == Some(id) {
                     self.popup = None;
                 }
+                if self.pinned_window == Some(id) {
+                    self.pinned_window = None;
+                    return cosmic::iced::window::close(id);
+                }
             }
 
             Message::Surface(action) => {
@@ -168,14 +601,276 @@ impl cosmic::Application for HotspotApplet {
                 ));
             }
 
-            Message::ToggleHotspot => {
-                let _ = self.cmd_tx.send(HotspotCommand::Toggle);
-                self.is_toggling = true;
-                self.status_message = if self.hotspot_active {
-                    "Stopping...".to_string()
+            Message::ToggleErrorDetail => {
+                self.state.show_error_detail = !self.state.show_error_detail;
+            }
+
+            Message::ToggleDnsLookups => {
+                self.show_dns_lookups = !self.show_dns_lookups;
+            }
+
+            Message::ToggleKnownClients => {
+                self.show_known_clients = !self.show_known_clients;
+            }
+
+            Message::WakeClient(mac) => {
+                self.state.status_message = match wol::wake(&mac, &self.config.profile().gateway_ip) {
+                    Ok(()) => format!("Wake-up sent to {mac}"),
+                    Err(e) => format!("Couldn't wake {mac}: {e}"),
+                };
+                self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+            }
+
+            Message::ResolveBusy(resolution) => {
+                let _ = self.cmd_tx.send(HotspotCommand::StartResolving(resolution));
+                self.state.begin_toggle("Starting...");
+                self.busy_conflict = None;
+            }
+
+            Message::ResolveSsidCollision { rename } => {
+                let _ = self.cmd_tx.send(HotspotCommand::StartDespiteCollision { rename });
+                self.state.begin_toggle("Starting...");
+                self.ssid_collision = None;
+            }
+
+            Message::EditClientPolicy(ip) => {
+                let mac = self
+                    .connected_clients
+                    .iter()
+                    .find(|c| c.ip == ip)
+                    .and_then(|c| c.mac.clone());
+                let policy = mac.as_deref().and_then(|mac| self.config.client_policy(mac));
+                self.policy_editor = Some(PolicyEditor {
+                    access: policy
+                        .and_then(|p| CLIENT_ACCESS_OPTIONS.iter().position(|&o| o == p.access))
+                        .unwrap_or(0),
+                    ports: policy.map(|p| p.blocked_ports.clone()).unwrap_or_default(),
+                    days: policy.map(|p| p.allowed_days.clone()).unwrap_or_default(),
+                    from: policy.map(|p| p.allowed_from.clone()).unwrap_or_default(),
+                    until: policy.map(|p| p.allowed_until.clone()).unwrap_or_default(),
+                    ip,
+                    mac,
+                });
+            }
+
+            Message::ClientAccessSelected(idx) => {
+                if let Some(editor) = self.policy_editor.as_mut().filter(|_| idx < CLIENT_ACCESS_OPTIONS.len()) {
+                    editor.access = idx;
+                }
+            }
+
+            Message::ClientPortsChanged(ports) => {
+                if let Some(editor) = self.policy_editor.as_mut() {
+                    editor.ports = ports;
+                }
+            }
+
+            Message::ClientDaysChanged(days) => {
+                if let Some(editor) = self.policy_editor.as_mut() {
+                    editor.days = days;
+                }
+            }
+
+            Message::ClientFromChanged(from) => {
+                if let Some(editor) = self.policy_editor.as_mut() {
+                    editor.from = from;
+                }
+            }
+
+            Message::ClientUntilChanged(until) => {
+                if let Some(editor) = self.policy_editor.as_mut() {
+                    editor.until = until;
+                }
+            }
+
+            Message::CloseClientPolicy => {
+                self.policy_editor = None;
+            }
+
+            Message::SaveClientPolicy => {
+                let Some(editor) = self.policy_editor.take() else {
+                    return Task::none();
+                };
+                let Some(mac) = editor.mac.clone() else {
+                    return Task::none();
+                };
+                let policy = ClientPolicy {
+                    mac: mac.clone(),
+                    access: CLIENT_ACCESS_OPTIONS[editor.access].to_string(),
+                    blocked_ports: editor.ports.trim().to_string(),
+                    allowed_days: editor.days.trim().to_string(),
+                    allowed_from: editor.from.trim().to_string(),
+                    allowed_until: editor.until.trim().to_string(),
+                    priority: self.config.client_policy(&mac).is_some_and(|p| p.priority),
+                };
+                if let Err(e) = policy.client_access().and(policy.schedule()) {
+                    self.state.status_message = e;
+                    self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                    self.policy_editor = Some(editor);
+                    return Task::none();
+                }
+                self.store_client_policy(&editor.ip, policy);
+            }
+
+            Message::ToggleClientPriority(ip) => {
+                let Some(mac) = self
+                    .connected_clients
+                    .iter()
+                    .find(|c| c.ip == ip)
+                    .and_then(|c| c.mac.clone())
+                else {
+                    return Task::none();
+                };
+                let mut policy = self
+                    .config
+                    .client_policy(&mac)
+                    .cloned()
+                    .unwrap_or_else(|| ClientPolicy { mac, ..ClientPolicy::default() });
+                policy.priority = !policy.priority;
+                self.store_client_policy(&ip, policy);
+            }
+
+            Message::DisconnectClient(ip) => {
+                let Some(client) = self.connected_clients.iter().find(|c| c.ip == ip) else {
+                    return Task::none();
+                };
+                let Some(mac) = client.mac.clone() else {
+                    return Task::none();
+                };
+                let name = client.hostname.clone().unwrap_or(ip);
+                let _ = self.cmd_tx.send(HotspotCommand::DisconnectClient { mac, name });
+            }
+
+            Message::RegeneratePassword => {
+                self.config.password = password::generate();
+                self.refresh_join_code();
+                if let Err(e) = self.config.save() {
+                    self.state.status_message = format!("Couldn't save the new password: {e}");
+                    self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                } else if self.state.hotspot_active {
+                    let _ = self.cmd_tx.send(HotspotCommand::Restart);
+                    self.state.begin_restart("Restarting with the new password...");
                 } else {
-                    "Starting...".to_string()
+                    self.state.status_message = "New password saved".to_string();
+                    self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                }
+            }
+
+            Message::RestartHotspot => {
+                let _ = self.cmd_tx.send(HotspotCommand::Restart);
+                self.state.begin_restart("Restarting with the new settings...");
+                self.running_outdated = false;
+            }
+
+            Message::Restart => {
+                let _ = self.cmd_tx.send(HotspotCommand::Restart);
+                self.state.begin_restart("Restarting...");
+            }
+
+            Message::ProfileSelected(idx) => {
+                let Some(name) = self.config.profiles.get(idx).map(|p| p.name.clone()) else {
+                    return Task::none();
                 };
+                if self.state.is_toggling || name == self.config.profile().name {
+                    return Task::none();
+                }
+                if self.state.hotspot_active {
+                    let _ = self.cmd_tx.send(HotspotCommand::SwitchProfile(name.clone()));
+                    self.state.begin_restart(&format!("Switching to {name}..."));
+                } else {
+                    // Nothing running to move over, so just make it the active one
+                    let result = self.config.switch_profile(&name).and_then(|()| self.config.save());
+                    self.state.status_message = match result {
+                        Ok(()) => format!("Profile: {name}"),
+                        Err(e) => format!("Couldn't switch profile: {e}"),
+                    };
+                    self.config = Config::load();
+                    self.profile_labels = profile_labels(&self.config);
+                    self.refresh_join_code();
+                    self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                }
+            }
+
+            Message::KeepUsbPowered => {
+                self.config.keep_usb_adapter_powered = true;
+                if let Err(e) = self.config.save() {
+                    self.state.status_message = format!("Couldn't save the setting: {e}");
+                    self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+                } else {
+                    let _ = self.cmd_tx.send(HotspotCommand::HoldUsbPower);
+                }
+                self.autosuspend = None;
+            }
+
+            Message::DismissAutosuspend => self.autosuspend_dismissed = true,
+
+            Message::AdoptForeign => {
+                if let Some(foreign) = self.foreign_hotspot.take() {
+                    let _ = self.cmd_tx.send(HotspotCommand::Adopt(foreign.connection));
+                    self.state.begin_toggle("Adopting...");
+                }
+            }
+
+            Message::ToggleHotspot => {
+                let _ = self.cmd_tx.send(HotspotCommand::Toggle);
+                self.state.begin_toggle(if self.state.hotspot_active { "Stopping..." } else { "Starting..." });
+                self.busy_conflict = None;
+            }
+
+            Message::CopyNetworkInfo => {
+                let info = format!(
+                    "SSID: {}\nPassword: {}",
+                    self.config.ssid, self.config.password,
+                );
+                self.state.status_message = "Network info copied".to_string();
+                self.hold_status(COPIED_HOLD);
+                let write = cosmic::iced::clipboard::write_data(clipboard::Secret {
+                    text: info.clone(),
+                    sensitive: self.config.clipboard_sensitive,
+                });
+                if self.config.clipboard_clear_secs == 0 {
+                    return write;
+                }
+                self.copies += 1;
+                self.copied_secret = Some(info);
+                let (copy, after) = (self.copies, Duration::from_secs(self.config.clipboard_clear_secs));
+                return Task::batch([write, Task::perform(tokio::time::sleep(after), move |()| Message::ClipboardExpired(copy))]);
+            }
+
+            Message::ClipboardExpired(copy) => {
+                if copy == self.copies && self.copied_secret.is_some() {
+                    return cosmic::iced::clipboard::read().map(Message::ClipboardChecked);
+                }
+            }
+
+            Message::ClipboardChecked(current) => {
+                // Only clear our own copy, not something copied since
+                if self.copied_secret.take().is_some_and(|copied| current == Some(copied)) {
+                    self.state.status_message = "Copied password cleared from the clipboard".to_string();
+                    self.hold_status(COPIED_HOLD);
+                    return cosmic::iced::clipboard::write(String::new());
+                }
+            }
+
+            Message::ExportCard => {
+                let _ = self.cmd_tx.send(HotspotCommand::ExportCard);
+            }
+
+            Message::Wps(enable) => {
+                let _ = self.cmd_tx.send(HotspotCommand::Wps(enable));
+                self.state.status_message = if enable { "Opening WPS pairing..." } else { "Cancelling WPS..." }.to_string();
+            }
+
+            Message::ClientFilterChanged(filter) => {
+                self.client_filter = filter;
+            }
+
+            Message::CycleClientSort => {
+                let sort = ClientSort::from_config(&self.config.client_sort).next();
+                self.config.client_sort = sort.as_config().to_string();
+                if let Err(e) = self.config.save() {
+                    eprintln!("Failed to save client sort: {e}");
+                }
             }
 
             Message::OpenSettings => {
@@ -202,16 +897,16 @@ impl cosmic::Application for HotspotApplet {
     }
 
     fn subscription(&self) -> cosmic::iced::Subscription<Self::Message> {
-        let poll = cosmic::iced::time::every(std::time::Duration::from_secs(2))
-            .map(|_| Message::PollStatus);
-
-        if self.hotspot_active {
-            // Slow pulse: one frame every 400ms → ~2.8s full cycle
-            let anim = cosmic::iced::time::every(std::time::Duration::from_millis(400))
-                .map(|_| Message::AnimationTick);
-            cosmic::iced::Subscription::batch(vec![poll, anim])
-        } else {
-            poll
+        let poll = cosmic::iced::time::every(self.state.poll_interval()).map(|_| Message::PollStatus);
+        match self
+            .state
+            .animation_interval(&self.config.icon_style, self.config.reduce_motion)
+        {
+            Some(frame) => {
+                let anim = cosmic::iced::time::every(frame).map(|_| Message::AnimationTick);
+                cosmic::iced::Subscription::batch(vec![poll, anim])
+            }
+            None => poll,
         }
     }
 
@@ -219,76 +914,88 @@ impl cosmic::Application for HotspotApplet {
         let suggested = self.core.applet.suggested_size(true);
         let icon_size = suggested.0 as f32;
 
-        let icon: Element<Message> = if self.hotspot_active {
-            // Get the theme foreground color so the SVG matches other panel icons
-            let theme = cosmic::theme::active();
-            let cosmic_theme = theme.cosmic();
-            let fg = cosmic_theme.background.on;
-            let color = format!(
-                "rgb({},{},{})",
-                (fg.red * 255.0) as u8,
-                (fg.green * 255.0) as u8,
-                (fg.blue * 255.0) as u8,
-            );
+        // Resolve colors against the theme so the SVG follows theme changes
+        let theme = cosmic::theme::active();
+        let cosmic_theme = theme.cosmic();
+        let foreground = css_rgb(cosmic_theme.background.on);
+        let style = self.config.icon_style.as_str();
 
-            let svg_data = ripple_svg(self.anim_frame, &color);
-            let handle = svg::Handle::from_memory(svg_data.into_bytes());
-            cosmic::iced::widget::svg(handle)
-                .width(Length::Fixed(icon_size))
-                .height(Length::Fixed(icon_size))
-                .into()
+        let svg_data = if self.state.hotspot_active {
+            let color = css_rgb(match self.config.icon_color.as_str() {
+                "accent" => cosmic_theme.accent_color(),
+                "success" => cosmic_theme.success_color(),
+                "warning" => cosmic_theme.warning_color(),
+                _ => cosmic_theme.background.on,
+            });
+            Some(match style {
+                "high_contrast" => {
+                    high_contrast_svg(true, &foreground, &css_rgb(cosmic_theme.background.base), icon_size)
+                }
+                "filled" => filled_svg(true, &color, icon_size),
+                "monochrome" => monochrome_svg(true, &foreground, icon_size),
+                _ if self.state.attention_ticks > 0 => ripple_svg(self.state.anim_frame, &color, icon_size),
+                "broadcast" => broadcast_svg(&color, icon_size),
+                // Static full-ripple frame
+                _ if self.config.reduce_motion => ripple_svg(3, &color, icon_size),
+                _ => ripple_svg(self.state.anim_frame, &color, icon_size),
+            })
         } else {
-            widget::icon::from_name(
+            match style {
+                "high_contrast" => Some(high_contrast_svg(
+                    false,
+                    &foreground,
+                    &css_rgb(cosmic_theme.background.base),
+                    icon_size,
+                )),
+                "filled" => Some(filled_svg(false, &foreground, icon_size)),
+                "monochrome" => Some(monochrome_svg(false, &foreground, icon_size)),
+                _ => None,
+            }
+        };
+        let icon: Element<Message> = match svg_data {
+            Some(svg_data) => {
+                let handle = svg::Handle::from_memory(svg_data.into_bytes());
+                cosmic::iced::widget::svg(handle)
+                    .width(Length::Fixed(icon_size))
+                    .height(Length::Fixed(icon_size))
+                    .into()
+            }
+            None => widget::icon::from_name(
                 "io.github.reality2_roycdavies.cosmic-hotspot-inactive-symbolic",
             )
             .symbolic(true)
-            .into()
+            .into(),
         };
 
         let have_popup = self.popup;
+        let click_action = self.config.click_action.clone();
         let btn = self
             .core
             .applet
             .button_from_element(icon, true)
             .on_press_with_rectangle(move |offset, bounds| {
                 if let Some(id) = have_popup {
-                    Message::Surface(destroy_popup(id))
-                } else {
-                    Message::Surface(app_popup::<HotspotApplet>(
-                        move |state: &mut HotspotApplet| {
-                            let new_id = Id::unique();
-                            state.popup = Some(new_id);
-
-                            let popup_width = 280u32;
-                            let popup_height = 300u32;
-
-                            let mut popup_settings = state.core.applet.get_popup_settings(
-                                state.core.main_window_id().unwrap(),
-                                new_id,
-                                Some((popup_width, popup_height)),
-                                None,
-                                None,
-                            );
-                            popup_settings.positioner.anchor_rect = Rectangle {
-                                x: (bounds.x - offset.x) as i32,
-                                y: (bounds.y - offset.y) as i32,
-                                width: bounds.width as i32,
-                                height: bounds.height as i32,
-                            };
-                            popup_settings
-                        },
-                        Some(Box::new(|state: &HotspotApplet| {
-                            Element::from(state.core.applet.popup_container(
-                                state.popup_content(),
-                            ))
-                            .map(cosmic::Action::App)
-                        })),
-                    ))
+                    return Message::Surface(destroy_popup(id));
+                }
+                let anchor = Rectangle {
+                    x: (bounds.x - offset.x) as i32,
+                    y: (bounds.y - offset.y) as i32,
+                    width: bounds.width as i32,
+                    height: bounds.height as i32,
+                };
+                match click_action.as_str() {
+                    "toggle" => Message::ToggleHotspot,
+                    "qr" => Message::Surface(open_popup(PopupKind::Qr, Some(anchor))),
+                    _ => Message::Surface(open_popup(PopupKind::Main, Some(anchor))),
                 }
             });
+        // Right click always reaches the popup, whatever left click does
+        let btn = widget::mouse_area(btn).on_right_press(Message::OpenPopup);
 
-        let tooltip = if self.hotspot_active {
+        let tooltip = if self.state.hotspot_active {
             "Hotspot (ON)"
+        } else if !self.state.wifi_available {
+            "Hotspot unavailable: no WiFi adapter found"
         } else {
             "Hotspot (OFF)"
         };
@@ -302,8 +1009,14 @@ impl cosmic::Application for HotspotApplet {
         ))
     }
 
-    fn view_window(&self, _id: Id) -> Element<'_, Message> {
-        "".into()
+    fn view_window(&self, id: Id) -> Element<'_, Message> {
+        if self.pinned_window != Some(id) {
+            return "".into();
+        }
+        widget::container(widget::scrollable(self.popup_content()))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
     }
 
     fn style(&self) -> Option<cosmic::iced_runtime::Appearance> {
@@ -312,64 +1025,567 @@ impl cosmic::Application for HotspotApplet {
 }
 
 impl HotspotApplet {
+    /// Keep the current status message from being replaced by polls for `duration`
+    fn hold_status(&mut self, duration: Duration) {
+        self.state.hold_status(duration, Instant::now());
+    }
+
+    /// Save `policy` for the client at `ip`, replacing its previous one, and
+    /// swap the helper's rules if the hotspot is running
+    fn store_client_policy(&mut self, ip: &str, policy: ClientPolicy) {
+        let mac = policy.mac.clone();
+        let current = policy.restricts().then_some(policy);
+        let previous = self.config.client_policy(&mac).cloned();
+        self.config.client_policies.retain(|p| !p.mac.eq_ignore_ascii_case(&mac));
+        self.config.client_policies.extend(current.clone());
+        if let Err(e) = self.config.save() {
+            self.state.status_message = format!("Couldn't save client access: {e}");
+        } else if self.state.hotspot_active {
+            let _ = self.cmd_tx.send(HotspotCommand::UpdateClientPolicy { previous, current });
+            self.state.status_message = format!("Updating access for {ip}...");
+        } else {
+            self.state.status_message = format!("Access for {ip} saved");
+        }
+        self.hold_status(Duration::from_secs(self.config.status_hold_secs));
+    }
+
+    /// Recent lookups per client from dnsmasq's query log, collapsed by default
+    fn dns_lookups_view(&self) -> widget::Column<'_, Message> {
+        use cosmic::iced::widget::{column, container, horizontal_space, row};
+
+        let mut dns_col = column![row![
+            text::caption(format!("DNS lookups ({} clients)", self.dns_lookups.len())),
+            horizontal_space(),
+            widget::button::text(if self.show_dns_lookups { "Hide" } else { "Show" })
+                .on_press(Message::ToggleDnsLookups),
+        ]
+        .align_y(cosmic::iced::Alignment::Center)]
+        .spacing(2);
+        if !self.show_dns_lookups {
+            return dns_col;
+        }
+        dns_col = dns_col.push(text::caption(
+            "Privacy: every name clients look up is recorded in the system journal while this is on",
+        ));
+        let server = self.config.profile().dns_server.trim();
+        if !server.is_empty() {
+            return dns_col.push(text::caption(format!(
+                "  Not logged: this profile sends clients' DNS to {server}"
+            )));
+        }
+        if !dns_log::is_enabled() {
+            return dns_col.push(text::caption("  Logging starts the next time the hotspot starts"));
+        }
+        if self.dns_lookups.is_empty() {
+            return dns_col.push(text::caption("  (none yet)"));
+        }
+        let mut lookup_rows = column![].spacing(2);
+        for group in &self.dns_lookups {
+            lookup_rows = lookup_rows.push(text::caption(group.client.clone()));
+            for (name, count) in &group.names {
+                lookup_rows = lookup_rows.push(text::caption(if *count > 1 {
+                    format!("    {name} ×{count}")
+                } else {
+                    format!("    {name}")
+                }));
+            }
+        }
+        dns_col.push(container(widget::scrollable(lookup_rows).width(Length::Fill)).max_height(160.0))
+    }
+
+    /// Devices seen before that aren't connected now, each with a wake
+    /// button; `None` if there aren't any
+    fn known_clients_view(&self) -> Option<widget::Column<'_, Message>> {
+        use cosmic::iced::widget::{column, container, horizontal_space, row};
+
+        let away: Vec<&String> = self
+            .config
+            .known_clients
+            .iter()
+            .rev()
+            .filter(|mac| {
+                !self
+                    .connected_clients
+                    .iter()
+                    .any(|c| c.mac.as_deref().is_some_and(|m| m.eq_ignore_ascii_case(mac)))
+            })
+            .collect();
+        if away.is_empty() {
+            return None;
+        }
+        let known_col = column![row![
+            text::caption(format!("Other known devices ({})", away.len())),
+            horizontal_space(),
+            widget::button::text(if self.show_known_clients { "Hide" } else { "Show" })
+                .on_press(Message::ToggleKnownClients),
+        ]
+        .align_y(cosmic::iced::Alignment::Center)]
+        .spacing(2);
+        if !self.show_known_clients {
+            return Some(known_col);
+        }
+        let mut rows = column![].spacing(2);
+        for mac in away {
+            rows = rows.push(
+                row![
+                    text::caption(format!("  {mac}")),
+                    horizontal_space(),
+                    widget::button::text("Wake").on_press(Message::WakeClient(mac.clone())),
+                ]
+                .align_y(cosmic::iced::Alignment::Center),
+            );
+        }
+        Some(known_col.push(container(widget::scrollable(rows).width(Length::Fill)).max_height(120.0)))
+    }
+
+    /// Access controls for the client picked from the list
+    fn policy_editor_view(&self, editor: &PolicyEditor) -> widget::Column<'_, Message> {
+        use cosmic::iced::widget::{column, horizontal_space, row};
+
+        let Some(mac) = &editor.mac else {
+            return column![
+                text::caption(format!("{}: MAC address unknown, so access can't be restricted", editor.ip)),
+                widget::button::standard("Close").on_press(Message::CloseClientPolicy),
+            ]
+            .spacing(4);
+        };
+        let mut editor_col = column![
+            text::caption(format!("Access for {} ({mac})", editor.ip)),
+            widget::dropdown(CLIENT_ACCESS_LABELS, Some(editor.access), Message::ClientAccessSelected),
+        ]
+        .spacing(4);
+        if CLIENT_ACCESS_OPTIONS[editor.access] == "block-ports" {
+            editor_col = editor_col.push(
+                widget::text_input("tcp:22, udp:3478", &editor.ports)
+                    .on_input(Message::ClientPortsChanged)
+                    .width(Length::Fill),
+            );
+        }
+        editor_col = editor_col
+            .push(text::caption("Internet allowed (empty = always):"))
+            .push(
+                row![
+                    widget::text_input("07:00", &editor.from)
+                        .on_input(Message::ClientFromChanged)
+                        .width(Length::Fixed(64.0)),
+                    text::caption("to"),
+                    widget::text_input("21:00", &editor.until)
+                        .on_input(Message::ClientUntilChanged)
+                        .width(Length::Fixed(64.0)),
+                    widget::text_input("mon-fri,sun", &editor.days)
+                        .on_input(Message::ClientDaysChanged)
+                        .width(Length::Fill),
+                ]
+                .spacing(4)
+                .align_y(cosmic::iced::Alignment::Center),
+            );
+        if !self.state.hotspot_active {
+            editor_col = editor_col.push(text::caption("Applied the next time the hotspot starts"));
+        }
+        editor_col.push(
+            row![
+                widget::button::suggested("Apply").on_press(Message::SaveClientPolicy),
+                widget::button::standard("Cancel").on_press(Message::CloseClientPolicy),
+                horizontal_space(),
+                widget::button::standard("Wake device")
+                    .on_press_maybe(self.state.hotspot_active.then(|| Message::WakeClient(mac.clone()))),
+            ]
+            .spacing(8),
+        )
+    }
+
+    fn popup_view(&self) -> widget::Column<'_, Message> {
+        match self.popup_kind {
+            PopupKind::Main => self.popup_content(),
+            PopupKind::Qr => self.qr_content(),
+        }
+    }
+
+    /// "Scan to join" code for the hotspot network
+    fn qr_content(&self) -> widget::Column<'_, Message> {
+        use cosmic::iced::widget::column;
+        use cosmic::iced::Alignment;
+
+        let code = self.join_code_view(QR_SIZE as f32);
+
+        let mut content = column![
+            text::body("Scan to join"),
+            code,
+            text::caption(format!("SSID: {}", self.config.ssid)),
+        ]
+        .spacing(8)
+        .padding(12)
+        .align_x(Alignment::Center);
+        if !self.state.hotspot_active {
+            content = content.push(text::caption("The hotspot is off"));
+        }
+        content
+    }
+
+    fn join_code_view(&self, size: f32) -> Element<'_, Message> {
+        match &self.join_code.1 {
+            Some(handle) => cosmic::iced::widget::svg(handle.clone())
+                .width(Length::Fixed(size))
+                .height(Length::Fixed(size))
+                .into(),
+            None => text::caption("Network details are too long for a QR code").into(),
+        }
+    }
+
+    fn refresh_join_code(&mut self) {
+        if qr::wifi_uri(&self.config.ssid, &self.config.password) != self.join_code.0 {
+            self.join_code = join_code(&self.config);
+        }
+    }
+
     fn popup_content(&self) -> widget::Column<'_, Message> {
-        use cosmic::iced::widget::{column, container, horizontal_space, row, Space};
+        use cosmic::iced::widget::{column, container, horizontal_space, progress_bar, row, Space};
         use cosmic::iced::{Alignment, Color};
 
+        let pin_button = if self.pinned_window.is_some() {
+            widget::button::text("Unpin").on_press(Message::UnpinPopup)
+        } else {
+            widget::button::text("Pin").on_press(Message::PinPopup)
+        };
         let title_row = row![
             text::body("WiFi Hotspot"),
             horizontal_space(),
+            pin_button,
         ]
         .spacing(8)
         .align_y(Alignment::Center);
 
-        let status_text = format!("Status: {}", self.status_message);
+        let status_text = format!("Status: {}", self.state.status_message);
         let ssid_text = format!("SSID: {}", self.config.ssid);
+        let nat_text = format!("NAT: {}", hotspot::nat_strategy_label(&self.config));
 
-        let info_section = column![
-            text::body(status_text),
-            text::caption(ssid_text),
-        ]
-        .spacing(2);
+        // A held error can be dismissed by clicking it
+        let dismissable = self.state.error_detail.is_some() && self.state.status_held(Instant::now());
+        let status_line: Element<'_, Message> = if dismissable {
+            widget::mouse_area(text::body(status_text))
+                .on_press(Message::DismissStatus)
+                .into()
+        } else {
+            text::body(status_text).into()
+        };
+        let mut info_section = column![status_line].spacing(2);
+        if let Some(detail) = &self.state.error_detail {
+            info_section = info_section.push(
+                widget::button::text(if self.state.show_error_detail { "Hide details" } else { "Details" })
+                    .on_press(Message::ToggleErrorDetail),
+            );
+            if self.state.show_error_detail {
+                info_section = info_section.push(text::caption(detail.clone()));
+                if !self.activation_log.is_empty() {
+                    info_section = info_section.push(text::caption("NetworkManager log:")).push(
+                        widget::scrollable(text::caption(self.activation_log.join("\n")))
+                            .height(Length::Fixed(160.0)),
+                    );
+                }
+            }
+        }
+        if let Some(conflict) = &self.busy_conflict {
+            info_section = info_section.push(text::caption(format!(
+                "{} is connected to '{}'. Choose how to continue:",
+                conflict.interface, conflict.connection
+            )));
+            let mut choices = row![widget::button::standard("Disconnect")
+                .on_press(Message::ResolveBusy(BusyResolution::Disconnect))]
+            .spacing(8);
+            if conflict.virtual_ap_supported {
+                choices = choices.push(
+                    widget::button::standard("Use virtual AP")
+                        .on_press(Message::ResolveBusy(BusyResolution::VirtualAp)),
+                );
+            }
+            choices = choices
+                .push(widget::button::standard("Other adapter...").on_press(Message::OpenSettings));
+            info_section = info_section.push(choices);
+        }
+        if let Some(network) = &self.ssid_collision {
+            info_section = info_section
+                .push(text::caption(format!(
+                    "'{}' is already broadcast nearby ({}% signal); devices may join the wrong network.",
+                    network.ssid, network.signal
+                )))
+                .push(
+                    row![
+                        widget::button::standard("Append suffix")
+                            .on_press(Message::ResolveSsidCollision { rename: true }),
+                        widget::button::standard("Start anyway")
+                            .on_press(Message::ResolveSsidCollision { rename: false }),
+                    ]
+                    .spacing(8),
+                );
+        }
+        if let Some(foreign) = self.foreign_hotspot.as_ref().filter(|_| self.busy_conflict.is_none()) {
+            info_section = info_section
+                .push(text::caption(format!(
+                    "Another hotspot is active ({}, SSID '{}')",
+                    foreign.connection, foreign.ssid
+                )))
+                .push(
+                    row![
+                        widget::button::standard("Adopt").on_press(Message::AdoptForeign),
+                        widget::button::standard("Stop it and start mine")
+                            .on_press(Message::ResolveBusy(BusyResolution::Disconnect)),
+                    ]
+                    .spacing(8),
+                );
+        }
+        if self.running_outdated && self.state.hotspot_active && !self.state.is_toggling {
+            info_section = info_section
+                .push(text::caption("Running hotspot uses old settings — Restart now?"))
+                .push(widget::button::standard("Restart now").on_press(Message::RestartHotspot));
+        }
+        if self.state.hotspot_active {
+            for conflict in &self.ip_conflicts {
+                info_section = info_section.push(text::caption(format!("Address conflict: {}", conflict.message())));
+            }
+        }
+        if let Some(device) = self.autosuspend.as_ref().filter(|_| !self.autosuspend_dismissed) {
+            info_section = info_section
+                .push(text::caption(format!(
+                    "{} is a USB adapter (device {device}) the kernel may suspend to save power, \
+                     which can take the hotspot down after a few minutes.",
+                    self.config.hotspot_interface
+                )))
+                .push(
+                    row![
+                        widget::button::standard("Keep it powered").on_press(Message::KeepUsbPowered),
+                        widget::button::standard("Not now").on_press(Message::DismissAutosuspend),
+                    ]
+                    .spacing(8),
+                );
+        }
+        if self.config.repeater_mode {
+            info_section = info_section
+                .push(text::caption(match &self.client_link {
+                    Some(link) => format!(
+                        "Client link: {} → {} ({}%)",
+                        self.config.internet_interface, link.ssid, link.signal
+                    ),
+                    None => format!("Client link: {} not connected", self.config.internet_interface),
+                }))
+                .push(text::caption(format!(
+                    "AP link: {} {}",
+                    self.config.hotspot_interface,
+                    if self.state.hotspot_active { "up" } else { "down" }
+                )));
+        } else if self.state.hotspot_active {
+            info_section = info_section.push(text::caption(match &self.upstream {
+                Some(upstream) => format!("Sharing: {upstream}"),
+                None => format!("Sharing: nothing ({} is not connected)", self.config.internet_interface),
+            }));
+        }
+        if let Some((mac, count)) = self.failed_joins.first().filter(|_| self.state.hotspot_active) {
+            let total: usize = self.failed_joins.iter().map(|(_, n)| n).sum();
+            info_section = info_section.push(text::caption(if self.failed_joins.len() == 1 {
+                format!("Wrong-password join attempts: {total} from {mac}")
+            } else {
+                format!(
+                    "Wrong-password join attempts: {total} from {} devices (most from {mac}: {count})",
+                    self.failed_joins.len()
+                )
+            }));
+        }
+        if let Some(cellular) = &self.cellular {
+            info_section = info_section.push(text::caption(format!(
+                "Upstream: {} {} {}% · {} this session",
+                cellular.status.technology,
+                cellular.status.bars(),
+                cellular.status.signal_percent,
+                modem::format_bytes(cellular.session_bytes),
+            )));
+        }
+        let info_section = info_section
+            .push(text::caption(ssid_text))
+            .push(text::caption(nat_text));
+
+        let compact = self.config.popup_density == "compact";
 
         // Connected clients section
-        let mut clients_col = column![text::caption("Connected clients:")].spacing(2);
+        let sort = ClientSort::from_config(&self.config.client_sort);
+        let mut clients_col = column![text::caption(format!(
+            "Connected clients ({}):",
+            self.connected_clients.len()
+        ))]
+        .spacing(2);
+        if self.connected_clients.len() > CLIENT_CONTROLS_THRESHOLD || !self.client_filter.is_empty() {
+            let controls = row![
+                widget::text_input("Filter", &self.client_filter)
+                    .on_input(Message::ClientFilterChanged)
+                    .width(Length::Fill),
+                widget::button::standard(format!("Sort: {}", sort.label()))
+                    .on_press(Message::CycleClientSort),
+            ]
+            .spacing(4)
+            .align_y(Alignment::Center);
+            clients_col = clients_col.push(controls);
+        }
+
+        let mut client_rows = column![].spacing(2);
+        let (weekday, minute) = schedule::local_now();
+        let visible = clients::filter_and_sort(&self.connected_clients, &self.client_filter, sort);
+        let serves_names = self.config.serves_local_domain();
+        // Compact mode lists the first few and counts the rest
+        let shown = if compact { COMPACT_CLIENT_ROWS } else { visible.len() };
+        let hidden = visible.len().saturating_sub(shown);
         if self.connected_clients.is_empty() {
-            clients_col = clients_col.push(text::caption("  (none)"));
+            client_rows = client_rows.push(text::caption("  (none)"));
+        } else if visible.is_empty() {
+            client_rows = client_rows.push(text::caption("  (no matches)"));
         } else {
-            for client in &self.connected_clients {
-                clients_col = clients_col.push(text::caption(format!("  {client}")));
+            for client in visible.into_iter().take(shown) {
+                let name = match client.vendor {
+                    Some(vendor) => format!("{} · {vendor}", client.display_name(serves_names)),
+                    None => client.display_name(serves_names),
+                };
+                let mut label = match (client.state, client.connected_for(SystemTime::now())) {
+                    (ClientState::Active, Some(time)) => {
+                        format!("{name} · {}", schedule::format_duration((time.as_secs() / 60) as u32))
+                    }
+                    (ClientState::Active, None) => name,
+                    (ClientState::Idle, _) => format!("{name} (idle)"),
+                };
+                // Which band it joined and how fast, e.g. a headset stuck on 2.4 GHz
+                if let Some(radio) = client.radio.as_ref().map(Radio::summary).filter(|s| !s.is_empty()) {
+                    label = format!("{label} · {radio}");
+                }
+                // E.g. a headset at the edge of range, about to drop out
+                let signal = client.radio.as_ref().and_then(|r| Some((r.signal_bars()?, r.signal_dbm?)));
+                if let Some((bars, dbm)) = signal.filter(|&(bars, _)| bars <= 1) {
+                    label = format!("{label} · weak signal ({dbm} dBm)");
+                }
+                let policy = client.mac.as_deref().and_then(|mac| self.config.client_policy(mac));
+                if let Some(idx) = policy
+                    .and_then(|p| CLIENT_ACCESS_OPTIONS.iter().position(|&o| o == p.access))
+                    .filter(|&idx| idx != 0)
+                {
+                    label = format!("{label} · {}", CLIENT_ACCESS_LABELS[idx]);
+                }
+                if policy.is_some_and(|p| p.priority) {
+                    label = format!("{label} · Priority");
+                }
+                if let Some(schedule) = policy.and_then(|p| p.schedule().ok().flatten()) {
+                    let change = schedule.minutes_until_change(weekday, minute);
+                    label = match (schedule.allows(weekday, minute), change) {
+                        (true, Some(m)) => format!("{label} · {} left", schedule::format_duration(m)),
+                        (true, None) => label,
+                        (false, Some(m)) => format!("{label} · blocked for {}", schedule::format_duration(m)),
+                        (false, None) => format!("{label} · blocked"),
+                    };
+                }
+                let prioritized = policy.is_some_and(|p| p.priority);
+                client_rows = client_rows.push(
+                    row![
+                        widget::button::text(label)
+                            .on_press(Message::EditClientPolicy(client.ip.clone()))
+                            .width(Length::Fill),
+                        text::caption(signal.map(|(bars, _)| stations::bars_glyph(bars)).unwrap_or_default()),
+                        // Needs the MAC address, which the rules follow
+                        widget::button::text(if prioritized { "★" } else { "☆" })
+                            .on_press_maybe(
+                                client.mac.is_some().then(|| Message::ToggleClientPriority(client.ip.clone())),
+                            ),
+                        // Disconnect; like ☆ it needs the MAC address
+                        widget::button::text("✕").on_press_maybe(
+                            client.mac.is_some().then(|| Message::DisconnectClient(client.ip.clone())),
+                        ),
+                    ]
+                    .align_y(Alignment::Center),
+                );
+            }
+            if hidden > 0 {
+                client_rows = client_rows.push(text::caption(format!("  and {hidden} more…")));
+            }
+        }
+        clients_col = clients_col.push(
+            container(widget::scrollable(client_rows).width(Length::Fill)).max_height(160.0),
+        );
+        if let Some(editor) = &self.policy_editor {
+            clients_col = clients_col.push(self.policy_editor_view(editor));
+        }
+        if self.state.hotspot_active {
+            if let Some(known) = self.known_clients_view() {
+                clients_col = clients_col.push(known);
             }
         }
+        if self.config.dns_query_log && self.state.hotspot_active {
+            clients_col = clients_col.push(self.dns_lookups_view());
+        }
 
         // Toggle row
-        let toggle_label = if self.hotspot_active { "Hotspot" } else { "Hotspot" };
-        let toggle_btn: Element<Message> = if self.is_toggling {
-            widget::button::standard(if self.hotspot_active {
+        let toggle_label = if self.state.hotspot_active { "Hotspot" } else { "Hotspot" };
+        let toggle_btn: Element<Message> = if self.state.is_toggling {
+            widget::button::standard(if self.state.restarting {
+                "Restarting..."
+            } else if self.state.hotspot_active {
                 "Stopping..."
             } else {
                 "Starting..."
             })
             .into()
-        } else if self.hotspot_active {
+        } else if self.state.hotspot_active {
             widget::button::destructive("Turn Off")
                 .on_press(Message::ToggleHotspot)
                 .into()
+        } else if !self.state.wifi_available {
+            // No on_press: rendered disabled until an adapter shows up
+            widget::button::suggested("Turn On").into()
         } else {
             widget::button::suggested("Turn On")
                 .on_press(Message::ToggleHotspot)
                 .into()
         };
 
-        let toggle_row = row![
-            text::body(toggle_label),
-            horizontal_space(),
-            toggle_btn,
-        ]
-        .spacing(8)
-        .align_y(Alignment::Center);
+        let mut toggle_row = row![text::body(toggle_label), horizontal_space()];
+        if self.state.hotspot_active && !self.state.is_toggling {
+            toggle_row = toggle_row.push(widget::button::standard("Restart").on_press(Message::Restart));
+        }
+        let toggle_row = toggle_row.push(toggle_btn).spacing(8).align_y(Alignment::Center);
+
+        // Only worth the space once there's another profile to switch to
+        let profile_row = (self.config.profiles.len() > 1).then(|| {
+            row![
+                text::body("Profile"),
+                horizontal_space(),
+                widget::dropdown(
+                    &self.profile_labels,
+                    self.config.profiles.iter().position(|p| p.name == self.config.profile().name),
+                    Message::ProfileSelected,
+                ),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center)
+        });
+
+        // For devices like old printers that can't have a password typed in
+        let wps_row: Option<Element<'_, Message>> = match self.wps_until {
+            _ if !self.state.hotspot_active || !backend::active().supports_wps(&self.config) => None,
+            Some(until) => {
+                let left = until.saturating_duration_since(Instant::now()).as_secs();
+                Some(
+                    row![
+                        text::caption(format!(
+                            "WPS open for {}:{:02}: press the device's WPS button",
+                            left / 60,
+                            left % 60
+                        )),
+                        horizontal_space(),
+                        widget::button::standard("Cancel").on_press(Message::Wps(false)),
+                    ]
+                    .spacing(8)
+                    .align_y(Alignment::Center)
+                    .into(),
+                )
+            }
+            None => Some(widget::button::standard("WPS connect (2 min)").on_press(Message::Wps(true)).into()),
+        };
 
         let settings_row = row![
+            widget::button::standard("Copy network info").on_press(Message::CopyNetworkInfo),
+            widget::button::standard("Export card").on_press(Message::ExportCard),
             horizontal_space(),
             widget::button::standard("Settings...").on_press(Message::OpenSettings),
         ]
@@ -390,60 +1606,859 @@ impl HotspotApplet {
             )
         };
 
-        column![
-            title_row,
-            divider(),
-            info_section,
-            divider(),
-            clients_col,
-            divider(),
-            toggle_row,
-            divider(),
-            settings_row,
-        ]
-        .spacing(8)
-        .padding(12)
+        let (spacing, padding) = if compact {
+            (4, 8)
+        } else {
+            (8, 12)
+        };
+
+        let mut content = column![title_row].spacing(spacing).padding(padding);
+        // Shown in compact mode too: the one line worth a glance
+        if self.state.hotspot_active && self.config.popup_show_stats {
+            content = content.push(text::caption(stats::summary(
+                self.connected_clients.len(),
+                self.throughput_bps,
+                self.uptime,
+            )));
+        }
+        if let Some((quota, used)) = self.quota {
+            content = content.push(
+                column![
+                    text::caption(format!(
+                        "{} quota: {} of {}",
+                        quota.period.label(),
+                        modem::format_bytes(used),
+                        modem::format_bytes(quota.limit_bytes)
+                    )),
+                    progress_bar(0.0..=1.0, (used as f32 / quota.limit_bytes as f32).min(1.0)),
+                ]
+                .spacing(4),
+            );
+        }
+        content = content.push(divider());
+
+        // Scrolls when it's too long for the popup, so the toggle and
+        // settings rows below always stay in view
+        let mut body = column![].spacing(spacing);
+        if let Some(weakness) = password::audit(&self.config.password) {
+            body = body.push(
+                column![
+                    text::body(format!("⚠ Weak hotspot password: {}", weakness.message())),
+                    widget::button::standard("Generate new password")
+                        .on_press(Message::RegeneratePassword),
+                ]
+                .spacing(4),
+            );
+        }
+        body = body.push(info_section);
+
+        // Nothing to join while the hotspot is off
+        if self.state.hotspot_active && self.config.popup_show_qr {
+            body = body.push(divider()).push(
+                column![text::caption("Scan to join"), self.join_code_view(POPUP_QR_SIZE)]
+                    .spacing(4)
+                    .width(Length::Fill)
+                    .align_x(Alignment::Center),
+            );
+        }
+
+        if self.config.popup_show_clients {
+            body = body.push(divider()).push(clients_col);
+        }
+        content = content.push(container(widget::scrollable(body).width(Length::Fill)).max_height(POPUP_BODY_HEIGHT));
+
+        content = content.push(divider());
+        if let Some(profile_row) = profile_row {
+            content = content.push(profile_row);
+        }
+        if self.config.popup_show_schedule {
+            let (weekday, minute) = schedule::local_now();
+            if let Some(line) = automation::summary(&self.config, weekday, minute) {
+                content = content.push(text::caption(line));
+            }
+        }
+        content = content.push(toggle_row);
+        if let Some(wps_row) = wps_row {
+            content = content.push(wps_row);
+        }
+        content.push(divider()).push(settings_row)
     }
 }
 
+fn profile_labels(config: &Config) -> Vec<String> {
+    config.profiles.iter().map(|p| p.name.clone()).collect()
+}
+
+/// Move the running hotspot to profile `name`: stop it as the current
+/// profile has it (its interface and SSID may differ), then start the new one
+fn switch_profile(name: &str) -> Result<String, HotspotError> {
+    let mut config = Config::load();
+    let backend = backend::active();
+    if backend.is_active(&config) {
+        backend.stop(&config)?;
+    }
+    config
+        .switch_profile(name)
+        .map_err(|e| HotspotError::new(ErrorKind::InvalidSetting, e))?;
+    // Saved before the start, so status polls look at the new profile
+    config.save().map_err(|e| HotspotError::new(ErrorKind::SaveFailed, e))?;
+    backend.start(&config).map(|message| format!("Switched to {name}: {message}"))
+}
+
+/// The hotspot's "scan to join" code, see `HotspotApplet::join_code`
+fn join_code(config: &Config) -> (String, Option<svg::Handle>) {
+    let uri = qr::wifi_uri(&config.ssid, &config.password);
+    let image = qr::svg(&uri, QR_SIZE).map(|image| svg::Handle::from_memory(image.into_bytes()));
+    (uri, image)
+}
+
+/// `retry_tx` feeds the Retry button of a failed autostart's notification
+/// back into the loop
 async fn run_background(
-    cmd_rx: std::sync::mpsc::Receiver<HotspotCommand>,
+    cmd_rx: &mut UnboundedReceiver<HotspotCommand>,
+    retry_tx: UnboundedSender<HotspotCommand>,
     event_tx: std::sync::mpsc::Sender<HotspotEvent>,
 ) {
+    // Kept for as long as the loop runs; scripts can do without it
+    let shared_clients = SharedClients::default();
+    let dbus = match dbus_service::serve(shared_clients.clone()).await {
+        Ok(conn) => Some(conn),
+        Err(e) => {
+            eprintln!("Session D-Bus service unavailable: {e}");
+            None
+        }
+    };
+    let mut last_sweep: Option<std::time::Instant> = None;
+    // Byte counter of the hotspot interface at the previous poll
+    let mut last_traffic: Option<(u64, std::time::Instant)> = None;
+    let mut tracker = ClientTracker::new();
+    let mut wifi_available = true;
+    let (wakeup_tx, mut wakeups) = tokio::sync::mpsc::unbounded_channel();
+    forward_wakeups(hotplug::subscribe(), wakeup_tx.clone(), Wakeup::Hotplug);
+    forward_wakeups(nm_dbus::subscribe(), wakeup_tx, Wakeup::NetworkManager);
+    // A command or hotplug event that ended the previous wait
+    let mut pending: Option<HotspotCommand> = None;
+    let mut hotplugged = false;
+    // The last status sent to the UI and the config it was built from; the
+    // UI reloads the config with each update, so either changing sends one
+    let mut last_status: Option<(HotspotEvent, Config)> = None;
+    // The plug-in autostart adapter's interface while it's present
+    let mut autostart_present = plugged_autostart_adapter(&Config::load());
+    // Logged in soon after boot with "Start at boot" on: check that
+    // NetworkManager brought the hotspot up
+    let mut boot_check = Config::load().autoconnect && system_uptime().is_some_and(|up| up < BOOT_START_WINDOW);
+    // Interface the hotspot was last seen on. Kept while that interface is
+    // gone, since NM may drop the connection before the hotplug event arrives
+    let mut hotspot_on: Option<String> = None;
+    let progress_tx = event_tx.clone();
+    progress::set_sink(Some(Box::new(move |step| {
+        let _ = progress_tx.send(HotspotEvent::Progress(step));
+    })));
+    // (data interface, byte counter) when the hotspot session started
+    let mut cellular_baseline: Option<(String, u64)> = None;
+    // When the hotspot was first seen active, and the failed join attempts
+    // since then
+    let mut session_start: Option<SystemTime> = None;
+    let mut failed_joins: Vec<(String, usize)> = Vec::new();
+    let mut polls_since_join_check = FAILED_JOIN_CHECK_POLLS;
+    let mut dns_lookups: Vec<ClientLookups> = Vec::new();
+    let mut polls_since_dns_check = DNS_LOG_CHECK_POLLS;
+    // Counts up from the start, so rules that were just set up aren't
+    // checked right away
+    let mut polls_since_health_check = 0;
+    let mut repairs = RepairHistory::default();
+    let mut idle = IdleTimer::default();
+    // Profile whose schedule window was open at the last poll, and the one
+    // the schedule started the hotspot with, to stop it when the window ends
+    let mut last_scheduled: Option<String> = None;
+    let mut schedule_started: Option<String> = None;
+    let mut conflicts = ConflictTracker::new();
+    let mut polls_since_gateway_probe = GATEWAY_PROBE_POLLS;
+    let mut usage = usage::load();
+    // Whether the previous poll saw the hotspot up; starts false so a hotspot
+    // already running when the applet starts is checked for adoption
+    let mut was_active = false;
+    let mut polls_since_usage_save = 0;
+
     loop {
         // Check for commands from the UI
-        if let Ok(cmd) = cmd_rx.try_recv() {
+        if let Some(cmd) = pending.take().or_else(|| cmd_rx.try_recv().ok()) {
             match cmd {
                 HotspotCommand::Toggle => {
                     let _ = event_tx.send(HotspotEvent::ToggleStarted);
 
                     let config = Config::load();
-                    let active = hotspot::is_hotspot_active(&config);
+                    let active = backend::active().is_active(&config);
 
                     let result = if active {
-                        hotspot::stop_hotspot(&config)
+                        backend::active().stop(&config)
                     } else {
-                        hotspot::start_hotspot(&config)
+                        start_checked(&config, &event_tx)
                     };
 
+                    if let Err(e) = &result {
+                        if e.kind == ErrorKind::InterfaceBusy {
+                            let _ = event_tx.send(HotspotEvent::InterfaceBusy(BusyConflict {
+                                interface: config.hotspot_interface.clone(),
+                                connection: e.detail.clone(),
+                                virtual_ap_supported: adapters::probe(&config.hotspot_interface)
+                                    .concurrent_ap_sta,
+                            }));
+                        }
+                    }
+                    let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                }
+                HotspotCommand::StartResolving(resolution) => {
+                    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                    let config = Config::load();
+                    let result = backend::active().start_resolving(&config, resolution);
+                    let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                }
+                HotspotCommand::StartDespiteCollision { rename } => {
+                    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                    let mut config = Config::load();
+                    if rename {
+                        let suffix = hotspot::ssid_suffix(&config.hotspot_interface);
+                        config.ssid = hotspot::ssid_with_suffix(&config.ssid, &suffix);
+                        if let Err(e) = config.save() {
+                            eprintln!("Failed to save renamed SSID: {e}");
+                        }
+                    }
+                    let result = backend::active().start(&config);
+                    let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                }
+                HotspotCommand::Restart => {
+                    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                    let result = backend::restart(&Config::load());
+                    let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                }
+                HotspotCommand::SwitchProfile(name) => {
+                    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                    let result = switch_profile(&name);
                     let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
                 }
+                HotspotCommand::UpdateClientPolicy { previous, current } => {
+                    let config = Config::load();
+                    let result = backend::active().update_client_policy(
+                        &config,
+                        previous.as_ref(),
+                        current.as_ref(),
+                    );
+                    let _ = event_tx.send(HotspotEvent::ClientPolicyUpdated(result));
+                }
+                HotspotCommand::Adopt(connection) => {
+                    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                    let result = backend::active().adopt(&connection);
+                    let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                }
+                HotspotCommand::ExportCard => {
+                    let _ = event_tx.send(HotspotEvent::CardExported(export_card(&Config::load())));
+                }
+                HotspotCommand::Wps(enable) => {
+                    let result = backend::active().wps_push_button(&Config::load(), enable);
+                    let _ = event_tx.send(HotspotEvent::WpsChanged { enable, result });
+                }
+                HotspotCommand::HoldUsbPower => {
+                    let result = backend::active().hold_usb_power(&Config::load());
+                    let _ = event_tx.send(HotspotEvent::UsbPowerHeld(result));
+                }
+                HotspotCommand::DisconnectClient { mac, name } => {
+                    let result = backend::active().disconnect_client(&Config::load(), &mac);
+                    match &result {
+                        Ok(()) => event_log::record(Level::Info, format!("Disconnected client {name} ({mac})")),
+                        Err(e) => event_log::record(Level::Warning, format!("Failed to disconnect {name} ({mac}): {e}")),
+                    }
+                    let _ = event_tx.send(HotspotEvent::ClientDisconnected { name, result });
+                }
+                HotspotCommand::RetryAutostart => match plugged_autostart_adapter(&Config::load()) {
+                    Some(interface) => autostart(&interface, &event_tx, &retry_tx),
+                    None => start_unattended(&Config::load(), "Hotspot autostart failed", &event_tx, &retry_tx),
+                },
+            }
+        }
+
+        // An interface came or went: re-check the hardware, and follow the
+        // plug-in autostart adapter
+        let queued_hotplug = std::iter::from_fn(|| wakeups.try_recv().ok())
+            .filter(|wakeup| *wakeup == Wakeup::Hotplug)
+            .count()
+            > 0;
+        if std::mem::take(&mut hotplugged) || queued_hotplug {
+            interface_cache::invalidate();
+            let present = plugged_autostart_adapter(&Config::load());
+            match (&autostart_present, &present) {
+                (None, Some(interface)) => autostart(interface, &event_tx, &retry_tx),
+                (Some(interface), None) if hotspot_on.as_ref() == Some(interface) => {
+                    autostop(&event_tx);
+                    hotspot_on = None;
+                }
+                _ => {}
             }
+            autostart_present = present;
         }
 
         // Poll current status
         let config = Config::load();
-        let active = hotspot::is_hotspot_active(&config);
+        let active = backend::active().is_active(&config);
+        // Take over a hotspot that autoconnect or nmcli brought up, and clean
+        // up after one taken down that way
+        if active && !was_active {
+            if let Some(message) = backend::active().adopt_running(&config) {
+                let _ = event_tx.send(HotspotEvent::ToggleComplete(Ok(message)));
+            }
+        } else if !active && was_active && backend::active().release_stopped(&config) {
+            let _ = event_tx.send(HotspotEvent::ToggleComplete(Ok("Hotspot stopped outside the applet".to_string())));
+        }
+        was_active = active;
+        if boot_check && (active || system_uptime().is_none_or(|up| up >= BOOT_START_GRACE)) {
+            boot_check = false;
+            if !active {
+                notify_start_failed(
+                    "Hotspot didn't start at boot",
+                    &config,
+                    "Start at boot is on, but the hotspot isn't running",
+                    &retry_tx,
+                );
+            }
+        }
+        if active {
+            hotspot_on = Some(config.hotspot_interface.clone());
+        } else if hotspot_on
+            .as_ref()
+            .is_some_and(|i| std::path::Path::new("/sys/class/net").join(i).exists())
+        {
+            hotspot_on = None;
+        }
         let clients = if active {
-            hotspot::get_connected_clients(&config)
+            let present = backend::active().connected_clients(&config);
+            let mut clients =
+                tracker.update(&present, std::time::Duration::from_secs(config.client_grace_secs));
+            clients::fill_details(&mut clients, &config);
+            // Remember who joined, so a device can still be woken after it
+            // has gone to sleep and dropped off the list
+            let mut remembered = config.clone();
+            if wol::remember(&mut remembered.known_clients, clients.iter().filter_map(|c| c.mac.as_deref())) {
+                if let Err(e) = remembered.save() {
+                    eprintln!("Failed to remember clients: {e}");
+                }
+            }
+            clients
         } else {
+            tracker.clear();
             Vec::new()
         };
 
-        let _ = event_tx.send(HotspotEvent::StatusUpdate { active, clients });
+        // Turn off a hotspot nobody has used for a while
+        let idle_mins = config.idle_timeout_mins();
+        if active && idle_mins > 0 {
+            let timeout = std::time::Duration::from_secs(idle_mins.saturating_mul(60));
+            if idle.observe(clients.len(), timeout, std::time::Instant::now()) {
+                let message = format!("Turned off after {idle_mins} min idle");
+                let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                let result = backend::active().stop(&config).map(|_| message.clone());
+                if result.is_ok() {
+                    event_log::record(Level::Info, &message);
+                }
+                let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                // The next poll picks up the stopped hotspot
+                continue;
+            }
+        } else {
+            idle.clear();
+        }
+
+        // Start a profile as its window opens, and stop the hotspot as the
+        // window it was started for closes. Only acting on changes leaves
+        // the user free to turn it on or off in between.
+        let (weekday, minute) = schedule::local_now();
+        let now_scheduled = automation::scheduled(&config, weekday, minute).map(|p| p.name.clone());
+        if now_scheduled != last_scheduled {
+            match &now_scheduled {
+                Some(name) => {
+                    scheduled_start(name, &event_tx, &retry_tx);
+                    schedule_started = Some(name.clone());
+                }
+                None => {
+                    if schedule_started.take().is_some() && active {
+                        let _ = event_tx.send(HotspotEvent::ToggleStarted);
+                        let result = backend::active()
+                            .stop(&config)
+                            .map(|_| format!("Turned off: {}'s schedule ended", config.profile().name));
+                        let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+                    }
+                }
+            }
+            last_scheduled = now_scheduled;
+            continue;
+        }
+
+        // Keep the battery for the machine itself once it runs low
+        if active && automation::battery_blocks(config.profile(), automation::battery()) {
+            let message = format!("Turned off: battery below {}%", config.profile().battery_min_percent);
+            let _ = event_tx.send(HotspotEvent::ToggleStarted);
+            let result = backend::active().stop(&config).map(|_| message.clone());
+            if result.is_ok() {
+                event_log::record(Level::Info, &message);
+                notify::send("Hotspot turned off", &message);
+            }
+            let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+            continue;
+        }
+
+        // Kick off a rate-limited subnet sweep so idle clients show up on the next polls
+        if active && config.client_sweep {
+            let interval = std::time::Duration::from_secs(config.client_sweep_interval_secs.max(10));
+            if last_sweep.is_none_or(|t| t.elapsed() >= interval) {
+                last_sweep = Some(std::time::Instant::now());
+                let sweep_config = config.clone();
+                std::thread::spawn(move || backend::active().sweep_clients(&sweep_config));
+            }
+        }
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        // Hardware can only disappear/appear while we're not using it; while
+        // off, the loop only runs on events and the idle fallback, so re-check
+        // each time
+        wifi_available = active || backend::active().has_wifi_hardware();
+
+        let upstream = if active { backend::active().upstream(&config) } else { None };
+        let cellular = if active {
+            cellular_upstream(&config, &mut cellular_baseline)
+        } else {
+            cellular_baseline = None;
+            None
+        };
+        let foreign = if active { None } else { backend::active().foreign_hotspot(&config) };
+        let client_link = if config.repeater_mode {
+            backend::active().client_link(&config.internet_interface)
+        } else {
+            None
+        };
+
+        if active {
+            let since = *session_start.get_or_insert_with(SystemTime::now);
+            if polls_since_join_check >= FAILED_JOIN_CHECK_POLLS {
+                let attempts = backend::active().failed_joins(&config.hotspot_interface, since);
+                let threshold = config.failed_join_notify_threshold as usize;
+                let before: usize = failed_joins.iter().map(|(_, n)| n).sum();
+                let counts = auth_log::count_by_device(&attempts);
+                if threshold > 0 && before < threshold && attempts.len() >= threshold {
+                    notify::send(
+                        "Failed hotspot join attempts",
+                        &format!(
+                            "{} wrong-password attempts from {} device(s) since the hotspot started",
+                            attempts.len(),
+                            counts.len()
+                        ),
+                    );
+                }
+                failed_joins = counts;
+                polls_since_join_check = 0;
+            }
+            polls_since_join_check += 1;
+        } else {
+            session_start = None;
+            failed_joins.clear();
+            polls_since_join_check = FAILED_JOIN_CHECK_POLLS;
+        }
+
+        if let Some(since) = session_start.filter(|_| config.dns_query_log) {
+            if polls_since_dns_check >= DNS_LOG_CHECK_POLLS {
+                let lookups = backend::active().dns_lookups(since);
+                dns_lookups = dns_log::by_client(&lookups, DNS_NAMES_PER_CLIENT);
+                polls_since_dns_check = 0;
+            }
+            polls_since_dns_check += 1;
+        } else {
+            dns_lookups.clear();
+            polls_since_dns_check = DNS_LOG_CHECK_POLLS;
+        }
+
+        // Put back forwarding and NAT rules that something else removed
+        if active && config.repair_sharing {
+            if polls_since_health_check >= HEALTH_CHECK_POLLS {
+                let problems = backend::active().sharing_problems(&config);
+                if !problems.is_empty() {
+                    let found: Vec<&str> = problems.iter().map(|p| p.label()).collect();
+                    eprintln!("Connection sharing broke ({}); repairing", found.join(", "));
+                    match backend::active().repair_sharing(&config) {
+                        Ok(()) => event_log::record(
+                            Level::Warning,
+                            format!("Connection sharing repaired: {}", found.join(", ")),
+                        ),
+                        Err(e) => event_log::record(Level::Error, format!("Connection sharing repair failed: {e}")),
+                    }
+                    if repairs.record(std::time::Instant::now()) {
+                        notify::send(
+                            "Hotspot sharing keeps breaking",
+                            &format!(
+                                "Repaired {} times in {} minutes: a VPN, Docker or firewall reload keeps resetting forwarding or NAT rules",
+                                health::RECURRING_REPAIRS,
+                                health::RECURRING_WINDOW.as_secs() / 60
+                            ),
+                        );
+                    }
+                }
+                polls_since_health_check = 0;
+            }
+            polls_since_health_check += 1;
+        } else {
+            repairs.clear();
+            polls_since_health_check = 0;
+        }
+
+        // Look for devices using the gateway's address or each other's,
+        // which otherwise looks like the hotspot randomly failing
+        if active {
+            let claimants = if polls_since_gateway_probe >= GATEWAY_PROBE_POLLS {
+                polls_since_gateway_probe = 0;
+                backend::active().gateway_claimants(&config)
+            } else {
+                Vec::new()
+            };
+            polls_since_gateway_probe += 1;
+            let gateway = hotspot::parse_gateway_ip(&config.profile().gateway_ip)
+                .map(|(ip, _)| ip.to_string())
+                .unwrap_or_default();
+            let neighbours = backend::active().neighbours(&config);
+            for conflict in conflicts.observe(&neighbours, &gateway, &claimants, std::time::Instant::now()) {
+                let message = conflict.message();
+                event_log::record(Level::Warning, &message);
+                notify::send("Address conflict on the hotspot", &message);
+            }
+        } else {
+            conflicts.clear();
+            polls_since_gateway_probe = GATEWAY_PROBE_POLLS;
+        }
+
+        let counters = if active { backend::active().traffic_bytes(&config) } else { None };
+        let reading = counters.map(|(rx, tx)| rx + tx);
+        let now = std::time::Instant::now();
+        let throughput_bps = match (last_traffic, reading) {
+            (Some((previous, at)), Some(current)) => stats::bitrate(previous, current, now - at),
+            _ => None,
+        };
+        // Count traffic toward the data quota
+        let quota = Quota::from_config(&config);
+        let quota_status = quota.map(|quota| {
+            let period_start = usage::current_period_start(run_state::now(), &quota);
+            if let (Some((previous, _)), Some(current)) = (last_traffic, reading) {
+                // A counter that went backwards belongs to a recreated interface
+                let bytes = current.checked_sub(previous).unwrap_or(current);
+                if usage.add(bytes, &quota, period_start) {
+                    let message = format!(
+                        "{} of the {} quota of {} used",
+                        modem::format_bytes(usage.bytes),
+                        quota.period.label().to_lowercase(),
+                        modem::format_bytes(quota.limit_bytes)
+                    );
+                    event_log::record(Level::Warning, &message);
+                    notify::send("Hotspot data quota at 80%", &message);
+                }
+                polls_since_usage_save += 1;
+            }
+            (quota, usage.used(period_start))
+        });
+        // Save periodically, and when the hotspot stops
+        if polls_since_usage_save >= USAGE_SAVE_POLLS || (polls_since_usage_save > 0 && reading.is_none()) {
+            usage::save(&usage);
+            polls_since_usage_save = 0;
+        }
+        last_traffic = reading.map(|bytes| (bytes, now));
+        let uptime = if active {
+            backend::active().started_at(&config).and_then(|t| t.elapsed().ok())
+        } else {
+            None
+        };
+
+        if let Ok(mut shared) = shared_clients.lock() {
+            shared.clone_from(&clients);
+        }
+        if let Some(conn) = &dbus {
+            let (rx_bytes, tx_bytes) = counters.unwrap_or_default();
+            let status = dbus_service::Status {
+                active,
+                ssid: config.ssid.clone(),
+                client_count: clients.len() as u32,
+                rx_bytes,
+                tx_bytes,
+                uptime: uptime.map_or(0, |u| u.as_secs()),
+            };
+            if let Err(e) = dbus_service::publish(conn, status).await {
+                eprintln!("Failed to update D-Bus properties: {e}");
+            }
+        }
+        let status = HotspotEvent::StatusUpdate {
+            active,
+            clients,
+            wifi_available,
+            upstream,
+            cellular,
+            client_link,
+            foreign,
+            failed_joins: failed_joins.clone(),
+            dns_lookups: dns_lookups.clone(),
+            throughput_bps,
+            uptime,
+            quota: quota_status,
+            outdated: active && backend::active().uses_old_settings(&config),
+            ip_conflicts: conflicts.current(),
+            autosuspend: if active && !config.keep_usb_adapter_powered {
+                backend::active().autosuspending_usb_device(&config)
+            } else {
+                None
+            },
+        };
+        if last_status.as_ref().is_none_or(|(sent, sent_config)| *sent != status || *sent_config != config) {
+            let _ = event_tx.send(status.clone());
+            last_status = Some((status, config));
+        }
+
+        // Wait for the next poll, or until the UI, NetworkManager or a hotplug
+        // event has something; only a running hotspot needs regular polls
+        tokio::select! {
+            () = tokio::time::sleep(if active { ACTIVE_POLL } else { IDLE_POLL }) => {}
+            Some(cmd) = cmd_rx.recv() => pending = Some(cmd),
+            Some(wakeup) = wakeups.recv() => hotplugged = wakeup == Wakeup::Hotplug,
+        }
+    }
+}
+
+/// Pass each `()` from a hotplug or NetworkManager subscription on to the
+/// background loop's wait, until either side goes away
+fn forward_wakeups(rx: std::sync::mpsc::Receiver<()>, tx: UnboundedSender<Wakeup>, wakeup: Wakeup) {
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            if tx.send(wakeup).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Save the credentials card as a PDF and a PNG next to it, and open the PDF
+/// for printing
+fn export_card(config: &Config) -> Result<std::path::PathBuf, String> {
+    let pdf = card::default_path(&config.ssid, "pdf");
+    card::export(&config.ssid, &config.password, &pdf)?;
+    card::export(&config.ssid, &config.password, &pdf.with_extension("png"))?;
+    let _ = std::process::Command::new("xdg-open").arg(&pdf).spawn();
+    Ok(pdf)
+}
+
+/// Start, unless a nearby network already broadcasts the SSID; then the
+/// popup offers to rename ours or start anyway
+fn start_checked(
+    config: &Config,
+    event_tx: &std::sync::mpsc::Sender<HotspotEvent>,
+) -> Result<String, HotspotError> {
+    if config.check_ssid_collisions {
+        progress::report(Step::CheckingSsid);
+        if let Some(network) = backend::active()
+            .scan_networks(&config.hotspot_interface)
+            .into_iter()
+            .find(|n| n.ssid == config.ssid)
+        {
+            let _ = event_tx.send(HotspotEvent::SsidCollision(network));
+            return Err(HotspotError::new(
+                ErrorKind::SsidInUse,
+                format!("'{}' is already broadcast nearby", config.ssid),
+            ));
+        }
+    }
+    backend::active().start(config)
+}
+
+/// Interface of the configured autostart adapter, if it's plugged in
+fn plugged_autostart_adapter(config: &Config) -> Option<String> {
+    if config.autostart_adapter.is_empty() {
+        return None;
     }
+    adapters::find_by_id(&config.autostart_adapter)
+}
+
+/// The autostart adapter was plugged in: host the hotspot on it, with the
+/// profile marked for plug-in start
+fn autostart(
+    interface: &str,
+    event_tx: &std::sync::mpsc::Sender<HotspotEvent>,
+    retry_tx: &UnboundedSender<HotspotCommand>,
+) {
+    let mut config = Config::load();
+    let (weekday, minute) = schedule::local_now();
+    if let Some(name) = automation::autostart_profile(&config, weekday, minute).map(|p| p.name.clone()) {
+        if let Err(e) = config.switch_profile(&name) {
+            eprintln!("Autostart profile: {e}");
+        }
+    }
+    if automation::battery_blocks(config.profile(), automation::battery()) {
+        event_log::record(
+            Level::Info,
+            format!("Autostart skipped: battery below {}%", config.profile().battery_min_percent),
+        );
+        return;
+    }
+    config.hotspot_interface = interface.to_string();
+    // Saved so status polls and the eventual stop use the same settings
+    if let Err(e) = config.save() {
+        eprintln!("Failed to save autostart settings: {e}");
+    }
+    if backend::active().is_active(&config) {
+        return;
+    }
+    start_unattended(&config, "Hotspot autostart failed", event_tx, retry_tx);
+}
+
+/// A profile's schedule window opened: move the hotspot to that profile and
+/// start it, unless it's already running or the battery is too low
+fn scheduled_start(
+    name: &str,
+    event_tx: &std::sync::mpsc::Sender<HotspotEvent>,
+    retry_tx: &UnboundedSender<HotspotCommand>,
+) {
+    let mut config = Config::load();
+    let backend = backend::active();
+    let active = backend.is_active(&config);
+    if active && config.profile().name == name {
+        return;
+    }
+    if let Some(profile) = config.profiles.iter().find(|p| p.name == name) {
+        if automation::battery_blocks(profile, automation::battery()) {
+            event_log::record(
+                Level::Info,
+                format!("Scheduled start of {name} skipped: battery below {}%", profile.battery_min_percent),
+            );
+            return;
+        }
+    }
+    if active {
+        // Only one profile runs at a time
+        let _ = event_tx.send(HotspotEvent::ToggleStarted);
+        if let Err(e) = backend.stop(&config) {
+            let _ = event_tx.send(HotspotEvent::ToggleComplete(Err(e)));
+            return;
+        }
+    }
+    if let Err(e) = config.switch_profile(name) {
+        eprintln!("Scheduled profile: {e}");
+        return;
+    }
+    // Saved so status polls and the eventual stop use the same settings
+    if let Err(e) = config.save() {
+        eprintln!("Failed to save scheduled profile: {e}");
+    }
+    event_log::record(Level::Info, format!("Schedule: starting {name}"));
+    start_unattended(&config, "Scheduled hotspot start failed", event_tx, retry_tx);
+}
+
+/// Start with nobody at the popup to see a failure, so report one with a
+/// notification that stays up and offers Retry
+fn start_unattended(
+    config: &Config,
+    summary: &str,
+    event_tx: &std::sync::mpsc::Sender<HotspotEvent>,
+    retry_tx: &UnboundedSender<HotspotCommand>,
+) {
+    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+    let result = backend::active().start(config);
+    if let Err(e) = &result {
+        notify_start_failed(summary, config, e.message(), retry_tx);
+    }
+    let _ = event_tx.send(HotspotEvent::ToggleComplete(result));
+}
+
+/// Log a failed autostart and notify it, naming the cause when it's one the
+/// user can fix; Retry sends `HotspotCommand::RetryAutostart`
+fn notify_start_failed(
+    summary: &str,
+    config: &Config,
+    message: &str,
+    retry_tx: &UnboundedSender<HotspotCommand>,
+) {
+    let body = match start_blocker(&config.hotspot_interface) {
+        Some(blocker) => format!("{message}: {blocker}"),
+        None => message.to_string(),
+    };
+    event_log::record(Level::Error, format!("{summary}: {body}"));
+    let retry_tx = retry_tx.clone();
+    notify::send_with_action(summary, &body, "Retry", move || {
+        let _ = retry_tx.send(HotspotCommand::RetryAutostart);
+    });
+}
+
+/// Why `interface` can't host the hotspot, if it's missing or blocked
+fn start_blocker(interface: &str) -> Option<String> {
+    if !backend::active().wifi_adapters().iter().any(|adapter| adapter.name == interface) {
+        return Some(format!("{interface} isn't plugged in"));
+    }
+    match backend::active().adapter_details(interface).rfkill {
+        rfkill @ (Rfkill::SoftBlocked | Rfkill::HardBlocked) => {
+            Some(format!("{interface} is {}", rfkill.label().to_lowercase()))
+        }
+        _ => None,
+    }
+}
+
+/// Time since the system booted, from /proc/uptime
+fn system_uptime() -> Option<Duration> {
+    let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+    let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// The autostart adapter was removed: NM drops the connection with the
+/// device, so tear down what's left (NAT, DNS redirect, wired share)
+fn autostop(event_tx: &std::sync::mpsc::Sender<HotspotEvent>) {
+    let config = Config::load();
+    let _ = event_tx.send(HotspotEvent::ToggleStarted);
+    let _ = event_tx.send(HotspotEvent::ToggleComplete(backend::active().stop(&config)));
+}
+
+/// Surface action opening a popup, anchored to the panel button if its
+/// bounds are known
+fn open_popup(kind: PopupKind, anchor: Option<Rectangle<i32>>) -> cosmic::surface::Action {
+    app_popup::<HotspotApplet>(
+        move |state: &mut HotspotApplet| {
+            let new_id = Id::unique();
+            state.popup = Some(new_id);
+            state.popup_kind = kind;
+
+            let popup_width = 280u32;
+            let popup_height = 300u32;
+
+            let mut popup_settings = state.core.applet.get_popup_settings(
+                state.core.main_window_id().unwrap(),
+                new_id,
+                Some((popup_width, popup_height)),
+                None,
+                None,
+            );
+            if let Some(anchor) = anchor {
+                popup_settings.positioner.anchor_rect = anchor;
+            }
+            popup_settings
+        },
+        Some(Box::new(|state: &HotspotApplet| {
+            Element::from(state.core.applet.popup_container(state.popup_view()))
+                .map(cosmic::Action::App)
+        })),
+    )
+}
+
+/// Modem status plus bytes used since the first poll of this session
+fn cellular_upstream(
+    config: &Config,
+    baseline: &mut Option<(String, u64)>,
+) -> Option<CellularUpstream> {
+    let status = modem::status_for(&config.internet_interface)?;
+    let bytes = modem::interface_bytes(&status.data_interface).unwrap_or(0);
+    let start = match baseline {
+        Some((iface, start)) if *iface == status.data_interface => *start,
+        _ => {
+            *baseline = Some((status.data_interface.clone(), bytes));
+            bytes
+        }
+    };
+    Some(CellularUpstream { status, session_bytes: bytes.saturating_sub(start) })
 }
 
 /// Generate an SVG showing concentric rings that grow then shrink.
@@ -455,7 +2470,7 @@ async fn run_background(
 /// Frame 4: dot + inner + middle ring
 /// Frame 5: dot + inner ring
 /// Frame 6: dot only
-fn ripple_svg(frame: u8, color: &str) -> String {
+fn ripple_svg(frame: u8, color: &str, size: f32) -> String {
     // How many rings to show (0..=3) based on frame
     let ring_count = match frame {
         0 | 6 => 0,
@@ -467,10 +2482,11 @@ fn ripple_svg(frame: u8, color: &str) -> String {
 
     let mut elements = String::new();
     let radii = [3.0_f32, 5.0, 7.0];
+    let stroke = stroke_width(1.2, size);
     for i in 0..ring_count {
         let r = radii[i];
         elements.push_str(&format!(
-            r#"<circle cx="8" cy="8" r="{r}" fill="none" stroke="{color}" stroke-width="1.2"/>"#,
+            r#"<circle cx="8" cy="8" r="{r}" fill="none" stroke="{color}" stroke-width="{stroke}"/>"#,
         ));
     }
     // Center dot always visible
@@ -478,8 +2494,88 @@ fn ripple_svg(frame: u8, color: &str) -> String {
         r#"<circle cx="8" cy="8" r="1.5" fill="{color}"/>"#,
     ));
 
+    icon_document(&elements, size)
+}
+
+/// Static "broadcast" glyph: a center dot flanked by two pairs of arcs.
+fn broadcast_svg(color: &str, size: f32) -> String {
+    icon_document(&broadcast_glyph(color, stroke_width(1.2, size)), size)
+}
+
+fn broadcast_glyph(color: &str, stroke: f32) -> String {
+    format!(
+        r#"<circle cx="8" cy="8" r="1.5" fill="{color}"/><g fill="none" stroke="{color}" stroke-width="{stroke}" stroke-linecap="round"><path d="M5.2 5.2a4 4 0 0 0 0 5.6"/><path d="M10.8 5.2a4 4 0 0 1 0 5.6"/><path d="M3.1 3.1a7 7 0 0 0 0 9.8"/><path d="M12.9 3.1a7 7 0 0 1 0 9.8"/></g>"#
+    )
+}
+
+/// Bold rings drawn over a halo in the panel background color, so the icon
+/// stays legible over busy wallpapers. Off: the same rings, struck through.
+fn high_contrast_svg(active: bool, color: &str, halo: &str, size: f32) -> String {
+    let stroke = stroke_width(2.0, size);
+    let halo_stroke = stroke + stroke_width(1.5, size);
+    let mut shapes = String::from(
+        r#"<circle cx="8" cy="8" r="6.5" fill="none"/><circle cx="8" cy="8" r="3.5" fill="none"/>"#,
+    );
+    if !active {
+        shapes.push_str(r#"<path d="M2.5 13.5L13.5 2.5"/>"#);
+    }
+    let dot = if active { r#"<circle cx="8" cy="8" r="1.6"/>"# } else { "" };
+    icon_document(
+        &format!(
+            r#"<g stroke="{halo}" stroke-width="{halo_stroke}" stroke-linecap="round">{shapes}</g><g fill="{halo}" stroke="{halo}" stroke-width="{h}">{dot}</g><g stroke="{color}" stroke-width="{stroke}" stroke-linecap="round">{shapes}</g><g fill="{color}">{dot}</g>"#,
+            h = halo_stroke - stroke,
+        ),
+        size,
+    )
+}
+
+/// A solid disc with a ring cut out when on; just the outline when off
+fn filled_svg(active: bool, color: &str, size: f32) -> String {
+    let body = if active {
+        format!(
+            r#"<path fill="{color}" fill-rule="evenodd" d="M8 0.5a7.5 7.5 0 1 0 0 15a7.5 7.5 0 1 0 0-15zM8 3.5a4.5 4.5 0 1 1 0 9a4.5 4.5 0 1 1 0-9zM8 5a3 3 0 1 0 0 6a3 3 0 1 0 0-6z"/>"#
+        )
+    } else {
+        format!(
+            r#"<circle cx="8" cy="8" r="6.75" fill="none" stroke="{color}" stroke-width="{s}"/><circle cx="8" cy="8" r="2" fill="{color}"/>"#,
+            s = stroke_width(1.5, size),
+        )
+    };
+    icon_document(&body, size)
+}
+
+/// The broadcast glyph in the panel foreground, never animated or tinted;
+/// faded when off
+fn monochrome_svg(active: bool, color: &str, size: f32) -> String {
+    let opacity = if active { 1.0 } else { 0.45 };
+    icon_document(
+        &format!(
+            r#"<g opacity="{opacity}">{}</g>"#,
+            broadcast_glyph(color, stroke_width(1.4, size))
+        ),
+        size,
+    )
+}
+
+/// A 16-unit icon drawing rendered at the panel's pixel size
+fn icon_document(body: &str, size: f32) -> String {
+    format!(
+        r#"<svg width="{size}" height="{size}" viewBox="0 0 16 16" xmlns="http://www.w3.org/2000/svg">{body}</svg>"#
+    )
+}
+
+/// `width` (in 16-unit icon space), but never thinner than one device pixel
+/// at `size`
+fn stroke_width(width: f32, size: f32) -> f32 {
+    width.max(16.0 / size.max(1.0))
+}
+
+fn css_rgb(color: cosmic::cosmic_theme::palette::Srgba) -> String {
     format!(
-        r#"<svg width="16" height="16" viewBox="0 0 16 16" xmlns="http://www.w3.org/2000/svg">{elements}</svg>"#
+        "rgb({},{},{})",
+        (color.red * 255.0) as u8,
+        (color.green * 255.0) as u8,
+        (color.blue * 255.0) as u8,
     )
 }
 
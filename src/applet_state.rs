@@ -0,0 +1,215 @@
+//! The popup's status logic, apart from iced.
+//!
+//! Status messages are held for a while so polls don't replace them, a
+//! start/stop in progress owns the status line until it completes, and the
+//! icon plays a fast animation when a client joins. These interact in subtle
+//! ways, so they live here as plain state transitions that take the current
+//! time, and the applet only forwards its messages and events.
+
+use std::time::{Duration, Instant};
+
+use crate::clients::{ClientInfo, ClientState};
+use crate::errors::HotspotError;
+use crate::progress::Step;
+
+/// Animation: dot → 1 ring → 2 rings → 3 rings → 2 rings → 1 ring → dot → repeat
+pub const ANIM_FRAMES: u8 = 7;
+/// Fast animation ticks (100ms each) played when a new client joins
+pub const ATTENTION_TICKS: u8 = 28;
+/// Status poll interval while a start/stop is in progress, so progress
+/// steps show up as they happen
+pub const TOGGLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Icon animation speeds: the fast attention pulse and the slow idle one
+/// (one frame every 400ms → ~2.8s full cycle)
+pub const ATTENTION_FRAME: Duration = Duration::from_millis(100);
+pub const PULSE_FRAME: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone)]
+pub struct AppletState {
+    pub hotspot_active: bool,
+    /// False when no WiFi adapter is present; the toggle is disabled until one appears
+    pub wifi_available: bool,
+    pub is_toggling: bool,
+    /// The toggle in progress is a restart: a stop and a start shown as one
+    pub restarting: bool,
+    /// Step the start/stop in progress has reached
+    pub toggle_step: Option<Step>,
+    pub status_message: String,
+    /// Until then, status_message is preserved (not overwritten by polls)
+    status_hold_until: Option<Instant>,
+    /// Technical detail of the last toggle failure, shown on request
+    pub error_detail: Option<String>,
+    pub show_error_detail: bool,
+    pub anim_frame: u8,
+    /// Counts down while the icon plays its fast "client joined" animation
+    pub attention_ticks: u8,
+    /// Set after the first status update, so clients present at startup don't trigger attention
+    status_received: bool,
+}
+
+impl AppletState {
+    pub fn new(active: bool, wifi_available: bool) -> Self {
+        Self {
+            hotspot_active: active,
+            wifi_available,
+            is_toggling: false,
+            restarting: false,
+            toggle_step: None,
+            status_message: idle_status(active, wifi_available),
+            status_hold_until: None,
+            error_detail: None,
+            show_error_detail: false,
+            anim_frame: 0,
+            attention_ticks: 0,
+            status_received: false,
+        }
+    }
+
+    /// Keep the current status message from being replaced by polls for `duration`
+    pub fn hold_status(&mut self, duration: Duration, now: Instant) {
+        self.status_hold_until = Some(now + duration);
+    }
+
+    pub fn status_held(&self, now: Instant) -> bool {
+        self.status_hold_until.is_some_and(|until| now < until)
+    }
+
+    /// A start, stop or restart was requested; `message` shows until it completes
+    pub fn begin_toggle(&mut self, message: &str) {
+        self.is_toggling = true;
+        self.restarting = false;
+        self.error_detail = None;
+        self.status_message = message.to_string();
+    }
+
+    /// A restart was requested; its stop and start steps show as one operation
+    pub fn begin_restart(&mut self, message: &str) {
+        self.begin_toggle(message);
+        self.restarting = true;
+    }
+
+    /// The background thread polled the hotspot. `previous` is the client
+    /// list shown until now.
+    pub fn status_update(
+        &mut self,
+        active: bool,
+        wifi_available: bool,
+        previous: &[ClientInfo],
+        clients: &[ClientInfo],
+        reduce_motion: bool,
+        now: Instant,
+    ) {
+        let client_joined = clients.iter().any(|c| {
+            c.state == ClientState::Active && !previous.iter().any(|old| old.ip == c.ip)
+        });
+        if client_joined && self.status_received && !reduce_motion {
+            self.attention_ticks = ATTENTION_TICKS;
+        } else if !active {
+            self.attention_ticks = 0;
+        }
+        self.status_received = true;
+        self.hotspot_active = active;
+        self.wifi_available = wifi_available;
+        if !self.status_held(now) && !self.is_toggling {
+            self.status_message = idle_status(active, wifi_available);
+        }
+    }
+
+    /// The background thread began a start or stop (possibly one the
+    /// applet didn't ask for, e.g. autostart)
+    pub fn toggle_started(&mut self) {
+        self.is_toggling = true;
+        self.error_detail = None;
+        self.toggle_step = None;
+        self.status_message = if self.restarting {
+            "Restarting...".to_string()
+        } else if self.hotspot_active {
+            "Stopping...".to_string()
+        } else {
+            "Starting...".to_string()
+        };
+    }
+
+    pub fn progress(&mut self, step: Step) {
+        self.toggle_step = Some(step);
+        self.status_message = if self.restarting {
+            let phase = if step.is_stopping() { "stopping" } else { "starting" };
+            format!("Restarting ({phase}): {}...", step.label())
+        } else {
+            format!("{}...", step.label())
+        };
+    }
+
+    /// The start or stop finished; its outcome is held for `hold`
+    pub fn toggle_complete(&mut self, result: Result<String, HotspotError>, hold: Duration, now: Instant) {
+        self.is_toggling = false;
+        self.restarting = false;
+        self.hold_status(hold, now);
+        match result {
+            Ok(msg) => self.status_message = msg,
+            Err(e) => {
+                eprintln!("Toggle failed: {e}");
+                // Name the step it failed in
+                self.status_message = match self.toggle_step {
+                    Some(step) => format!("{} failed: {}", step.label(), e.message()),
+                    None => e.message().to_string(),
+                };
+                self.error_detail = (!e.detail.is_empty()).then_some(e.detail);
+                self.show_error_detail = false;
+            }
+        }
+    }
+
+    /// Clear a held message or error and go back to the plain status
+    pub fn dismiss(&mut self) {
+        self.status_hold_until = None;
+        self.error_detail = None;
+        self.show_error_detail = false;
+        self.status_message = idle_status(self.hotspot_active, self.wifi_available);
+    }
+
+    pub fn animation_tick(&mut self) {
+        if self.hotspot_active {
+            self.anim_frame = (self.anim_frame + 1) % ANIM_FRAMES;
+        }
+        self.attention_ticks = self.attention_ticks.saturating_sub(1);
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        if self.is_toggling {
+            TOGGLE_POLL_INTERVAL
+        } else {
+            POLL_INTERVAL
+        }
+    }
+
+    /// How often the icon animates with this style, if at all
+    pub fn animation_interval(&self, icon_style: &str, reduce_motion: bool) -> Option<Duration> {
+        if !self.hotspot_active {
+            None
+        } else if self.attention_ticks > 0 && style_animates(icon_style) {
+            // Fast pulse while drawing attention to a newly joined client
+            Some(ATTENTION_FRAME)
+        } else if icon_style == "ripple" && !reduce_motion {
+            Some(PULSE_FRAME)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn idle_status(active: bool, wifi_available: bool) -> String {
+    if active {
+        "Active".to_string()
+    } else if !wifi_available {
+        "No WiFi adapter found".to_string()
+    } else {
+        "Inactive".to_string()
+    }
+}
+
+/// Whether an icon style shows the ripple animation when a client joins
+pub fn style_animates(style: &str) -> bool {
+    matches!(style, "ripple" | "broadcast")
+}
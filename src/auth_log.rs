@@ -0,0 +1,48 @@
+//! Failed join attempts against the AP, read from the system journal.
+//!
+//! NetworkManager hosts the AP through wpa_supplicant, which (like hostapd)
+//! logs `<ifname>: AP-STA-POSSIBLE-PSK-MISMATCH <mac>` when a client's
+//! handshake fails — almost always a wrong password.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PSK_MISMATCH: &str = "AP-STA-POSSIBLE-PSK-MISMATCH";
+
+/// MAC address of each failed attempt on `interface` in `log`, in order
+pub fn parse_failures(log: &str, interface: &str) -> Vec<String> {
+    let prefix = format!("{interface}: {PSK_MISMATCH}");
+    log.lines()
+        .filter_map(|line| {
+            let rest = &line[line.find(&prefix)? + prefix.len()..];
+            rest.split_whitespace().next().map(str::to_lowercase)
+        })
+        .collect()
+}
+
+/// Attempts per device, most persistent first
+pub fn count_by_device(attempts: &[String]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for mac in attempts {
+        match counts.iter_mut().find(|(m, _)| m == mac) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((mac.clone(), 1)),
+        }
+    }
+    counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    counts
+}
+
+/// Failed attempts on `interface` since `since`. Empty if the journal isn't
+/// readable (the user needs to be in the systemd-journal or adm group).
+pub fn failed_attempts(interface: &str, since: SystemTime) -> Vec<String> {
+    let since = since.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let Ok(output) = Command::new("journalctl")
+        .args(["-u", "wpa_supplicant", "-u", "hostapd", "-o", "cat", "-q", "--no-pager"])
+        .arg(format!("--since=@{since}"))
+        .output()
+    else {
+        return Vec::new();
+    };
+    parse_failures(&String::from_utf8_lossy(&output.stdout), interface)
+}
@@ -0,0 +1,107 @@
+//! Which profile the automation starts: plug-in autostart and schedules.
+//!
+//! Each profile can be marked for plug-in start and carry a weekly window
+//! the hotspot runs in. Only one profile runs at a time, so where windows
+//! overlap the profile listed first wins, and a plug-in start prefers the
+//! profile scheduled right now over the other marked ones.
+
+use crate::config::{Config, Profile};
+use crate::schedule;
+
+/// Where the kernel lists batteries and chargers
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+const MINUTES_PER_WEEK: u32 = 7 * 24 * 60;
+
+/// The profile the automation will use, and in how many minutes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Upcoming<'a> {
+    pub profile: &'a Profile,
+    /// 0 while its window is open
+    pub in_minutes: u32,
+    /// Whether a schedule picks it, rather than plug-in start
+    pub scheduled: bool,
+}
+
+/// The profile whose schedule window is open on `weekday` (0 = Monday) at
+/// `minute`; the first listed if windows overlap
+pub fn scheduled(config: &Config, weekday: usize, minute: u32) -> Option<&Profile> {
+    config.profiles.iter().find(|profile| {
+        matches!(profile.schedule(), Ok(Some(schedule)) if schedule.allows(weekday, minute))
+    })
+}
+
+/// The profile to switch to when the autostart adapter is plugged in
+pub fn autostart_profile(config: &Config, weekday: usize, minute: u32) -> Option<&Profile> {
+    scheduled(config, weekday, minute)
+        .filter(|profile| profile.autostart)
+        .or_else(|| config.profiles.iter().find(|profile| profile.autostart))
+}
+
+/// The next profile a schedule will start, counting one whose window is
+/// open now; falls back to the plug-in start profile
+pub fn next(config: &Config, weekday: usize, minute: u32) -> Option<Upcoming<'_>> {
+    if let Some(profile) = scheduled(config, weekday, minute) {
+        return Some(Upcoming { profile, in_minutes: 0, scheduled: true });
+    }
+    let opening = config
+        .profiles
+        .iter()
+        .filter_map(|profile| {
+            let schedule = profile.schedule().ok()??;
+            let in_minutes = schedule.minutes_until_change(weekday, minute)?;
+            // A window shadowed by an earlier profile's doesn't start this one
+            let at = minute + in_minutes;
+            let winner = scheduled(config, weekday + (at / (24 * 60)) as usize, at % (24 * 60))?;
+            (winner.name == profile.name).then_some(Upcoming { profile, in_minutes, scheduled: true })
+        })
+        .filter(|upcoming| upcoming.in_minutes <= MINUTES_PER_WEEK)
+        .min_by_key(|upcoming| upcoming.in_minutes);
+    opening.or_else(|| {
+        config
+            .profiles
+            .iter()
+            .find(|profile| profile.autostart)
+            .filter(|_| !config.autostart_adapter.is_empty())
+            .map(|profile| Upcoming { profile, in_minutes: 0, scheduled: false })
+    })
+}
+
+/// A line for the popup saying which profile the automation uses next
+pub fn summary(config: &Config, weekday: usize, minute: u32) -> Option<String> {
+    let upcoming = next(config, weekday, minute)?;
+    let name = &upcoming.profile.name;
+    Some(match upcoming {
+        Upcoming { scheduled: false, .. } => format!("Plug-in start uses {name}"),
+        Upcoming { in_minutes: 0, .. } => format!("Scheduled now: {name}"),
+        Upcoming { in_minutes, .. } => {
+            format!("Next scheduled: {name} in {}", schedule::format_duration(in_minutes))
+        }
+    })
+}
+
+/// The battery's charge and whether it's running the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battery {
+    pub percent: u8,
+    pub discharging: bool,
+}
+
+/// The first battery's state, or `None` on machines without one
+pub fn battery() -> Option<Battery> {
+    std::fs::read_dir(POWER_SUPPLY_DIR).ok()?.flatten().find_map(|entry| {
+        let read = |file: &str| std::fs::read_to_string(entry.path().join(file)).ok();
+        if read("type")?.trim() != "Battery" {
+            return None;
+        }
+        Some(Battery {
+            percent: read("capacity")?.trim().parse().ok()?,
+            discharging: read("status")?.trim() == "Discharging",
+        })
+    })
+}
+
+/// Whether `profile`'s battery policy keeps the hotspot off
+pub fn battery_blocks(profile: &Profile, battery: Option<Battery>) -> bool {
+    profile.battery_min_percent > 0
+        && battery.is_some_and(|b| b.discharging && b.percent < profile.battery_min_percent)
+}
@@ -0,0 +1,507 @@
+//! Hotspot backend abstraction.
+//!
+//! Everything the UI and CLI do to the hotspot goes through a
+//! `HotspotBackend`, so the real NetworkManager implementation can be swapped
+//! for the simulated one in `mock_backend` on machines without AP hardware.
+//! `ConfiguredBackend` picks between NetworkManager and `hostapd_backend` per
+//! call, following the "backend" setting.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use crate::adapters::{self, AdapterDetails, WifiAdapter};
+use crate::auth_log;
+use crate::channels::{self, NearbyAp};
+use crate::clients::Client;
+use crate::config::{ClientPolicy, Config};
+use crate::dns_log::{self, Lookup};
+use crate::errors::HotspotError;
+use crate::health::{self, Problem};
+use crate::hostapd_backend::{self, HostapdBackend};
+use crate::hotspot::{self, BusyResolution, ForeignHotspot};
+use crate::local_dns;
+use crate::mock_backend::MockBackend;
+use crate::repeater::{self, ClientLink, ScannedNetwork};
+use crate::run_state;
+use crate::saved_networks::{self, SavedNetwork};
+use crate::stations::{self, Radio};
+
+/// Environment variable that selects the simulated backend
+pub const MOCK_ENV: &str = "COSMIC_HOTSPOT_MOCK";
+
+pub trait HotspotBackend: Send + Sync {
+    fn start(&self, config: &Config) -> Result<String, HotspotError>;
+    /// Start after the user chose how to free a busy adapter
+    fn start_resolving(
+        &self,
+        config: &Config,
+        resolution: BusyResolution,
+    ) -> Result<String, HotspotError>;
+    fn stop(&self, config: &Config) -> Result<String, HotspotError>;
+    fn is_active(&self, config: &Config) -> bool;
+    /// A hotspot another tool has running on the hotspot adapter
+    fn foreign_hotspot(&self, config: &Config) -> Option<ForeignHotspot>;
+    /// Make another tool's hotspot connection the configured one
+    fn adopt(&self, connection: &str) -> Result<String, HotspotError>;
+    /// Take over the configured hotspot if it was brought up without us
+    /// (autoconnect, nmcli); `None` if we started it
+    fn adopt_running(&self, config: &Config) -> Option<String>;
+    /// Clean up after the hotspot was taken down without us; false if we
+    /// stopped it
+    fn release_stopped(&self, config: &Config) -> bool;
+    /// Devices currently present on the hotspot interface
+    fn connected_clients(&self, config: &Config) -> Vec<Client>;
+    /// (IP, MAC) of the devices on the hotspot interface
+    fn neighbours(&self, config: &Config) -> Vec<(String, String)>;
+    /// MACs of other devices using the hotspot's gateway address
+    fn gateway_claimants(&self, config: &Config) -> Vec<String>;
+    /// What the internet side is connected to, for the popup
+    fn upstream(&self, config: &Config) -> Option<String>;
+    /// MAC address of the client with this IP
+    fn client_mac(&self, ip: &str) -> Option<String>;
+    /// IP → hostname of the clients that sent one with their DHCP request
+    fn client_hostnames(&self, config: &Config) -> HashMap<String, String>;
+    /// MAC (lowercase) → band and bitrate of the associated clients, where
+    /// the driver reports them
+    fn client_radios(&self, config: &Config) -> HashMap<String, Radio>;
+    /// Replace one client's access rules on the running hotspot
+    fn update_client_policy(
+        &self,
+        config: &Config,
+        previous: Option<&ClientPolicy>,
+        current: Option<&ClientPolicy>,
+    ) -> Result<(), String>;
+    /// Whether `wps_push_button` can pair clients with the hotspot `config` runs
+    fn supports_wps(&self, config: &Config) -> bool;
+    /// Open (or cancel) WPS push-button pairing on the running hotspot
+    fn wps_push_button(&self, config: &Config, enable: bool) -> Result<(), String>;
+    /// Kick the client with this MAC address off the running hotspot
+    fn disconnect_client(&self, config: &Config, mac: &str) -> Result<(), String>;
+    /// Nudge idle clients back into view; may block for several seconds
+    fn sweep_clients(&self, config: &Config);
+    fn has_wifi_hardware(&self) -> bool;
+    /// All WiFi adapters, including ones that can't host an AP
+    fn wifi_adapters(&self) -> Vec<WifiAdapter>;
+    fn network_interfaces(&self) -> Vec<String>;
+    /// Driver, bands, limits and rfkill state of a WiFi adapter
+    fn adapter_details(&self, device: &str) -> AdapterDetails;
+    /// WiFi networks visible from `interface`, for picking a repeater upstream
+    fn scan_networks(&self, interface: &str) -> Vec<ScannedNetwork>;
+    /// WPA-Personal networks saved in NetworkManager, for copying one's
+    /// credentials to the hotspot
+    fn saved_networks(&self) -> Vec<SavedNetwork>;
+    fn saved_network_password(&self, network: &SavedNetwork) -> Result<String, String>;
+    /// Channel and signal of every access point `interface` can see
+    fn scan_access_points(&self, interface: &str) -> Vec<NearbyAp>;
+    /// The network a client-mode adapter is associated with
+    fn client_link(&self, interface: &str) -> Option<ClientLink>;
+    /// MAC address of each wrong-password join attempt on `interface` since
+    /// `since`
+    fn failed_joins(&self, interface: &str, since: SystemTime) -> Vec<String>;
+    /// DNS lookups by hotspot clients since `since`, if query logging is on
+    fn dns_lookups(&self, since: SystemTime) -> Vec<Lookup>;
+    /// Bytes (received, sent) on the hotspot interface so far
+    fn traffic_bytes(&self, config: &Config) -> Option<(u64, u64)>;
+    /// When the running hotspot was started, if that was recorded
+    fn started_at(&self, config: &Config) -> Option<SystemTime>;
+    /// Whether the running hotspot was started with other settings than
+    /// `config`, e.g. saved since without a restart
+    fn uses_old_settings(&self, config: &Config) -> bool;
+    /// Forwarding or NAT rules of the running hotspot that went missing
+    fn sharing_problems(&self, config: &Config) -> Vec<Problem>;
+    /// Re-apply the running hotspot's forwarding and NAT rules
+    fn repair_sharing(&self, config: &Config) -> Result<(), String>;
+    /// The USB device of the hotspot adapter if the kernel may autosuspend it
+    fn autosuspending_usb_device(&self, config: &Config) -> Option<String>;
+    /// Keep the hotspot's USB adapter powered until the hotspot stops
+    fn hold_usb_power(&self, config: &Config) -> Result<(), String>;
+}
+
+/// The real backend: NetworkManager over D-Bus, plus the privileged helper
+pub struct NetworkManagerBackend;
+
+impl HotspotBackend for NetworkManagerBackend {
+    fn start(&self, config: &Config) -> Result<String, HotspotError> {
+        hotspot::start_hotspot(config)
+    }
+
+    fn start_resolving(
+        &self,
+        config: &Config,
+        resolution: BusyResolution,
+    ) -> Result<String, HotspotError> {
+        hotspot::start_hotspot_with(config, Some(resolution))
+    }
+
+    fn stop(&self, config: &Config) -> Result<String, HotspotError> {
+        hotspot::stop_hotspot(config)
+    }
+
+    fn is_active(&self, config: &Config) -> bool {
+        hotspot::is_hotspot_active(config)
+    }
+
+    fn foreign_hotspot(&self, config: &Config) -> Option<ForeignHotspot> {
+        hotspot::foreign_hotspot(config)
+    }
+
+    fn adopt(&self, connection: &str) -> Result<String, HotspotError> {
+        hotspot::adopt_hotspot(connection)
+    }
+
+    fn adopt_running(&self, config: &Config) -> Option<String> {
+        hotspot::adopt_running(config)
+    }
+
+    fn release_stopped(&self, config: &Config) -> bool {
+        hotspot::release_stopped(config)
+    }
+
+    fn connected_clients(&self, config: &Config) -> Vec<Client> {
+        hotspot::get_connected_clients(config)
+    }
+
+    fn neighbours(&self, config: &Config) -> Vec<(String, String)> {
+        hotspot::neighbours_with_macs(config)
+    }
+
+    fn gateway_claimants(&self, config: &Config) -> Vec<String> {
+        hotspot::gateway_claimants(config)
+    }
+
+    fn upstream(&self, config: &Config) -> Option<String> {
+        hotspot::upstream_description(config)
+    }
+
+    fn client_mac(&self, ip: &str) -> Option<String> {
+        hotspot::client_mac(ip)
+    }
+
+    fn client_hostnames(&self, config: &Config) -> HashMap<String, String> {
+        local_dns::hostnames(&hotspot::active_interface(config))
+    }
+
+    fn client_radios(&self, config: &Config) -> HashMap<String, Radio> {
+        stations::radios(&hotspot::active_interface(config))
+    }
+
+    fn update_client_policy(
+        &self,
+        config: &Config,
+        previous: Option<&ClientPolicy>,
+        current: Option<&ClientPolicy>,
+    ) -> Result<(), String> {
+        hotspot::update_client_policy(config, previous, current)
+    }
+
+    fn supports_wps(&self, _config: &Config) -> bool {
+        false
+    }
+
+    fn wps_push_button(&self, _config: &Config, _enable: bool) -> Result<(), String> {
+        Err("WPS pairing needs the hostapd backend".to_string())
+    }
+
+    fn disconnect_client(&self, config: &Config, mac: &str) -> Result<(), String> {
+        hotspot::disconnect_client(config, mac)
+    }
+
+    fn sweep_clients(&self, config: &Config) {
+        hotspot::sweep_subnet(config);
+    }
+
+    fn has_wifi_hardware(&self) -> bool {
+        hotspot::has_wifi_hardware()
+    }
+
+    fn wifi_adapters(&self) -> Vec<WifiAdapter> {
+        hotspot::list_wifi_adapters()
+    }
+
+    fn network_interfaces(&self) -> Vec<String> {
+        hotspot::list_network_interfaces()
+    }
+
+    fn adapter_details(&self, device: &str) -> AdapterDetails {
+        adapters::details(device)
+    }
+
+    fn scan_networks(&self, interface: &str) -> Vec<ScannedNetwork> {
+        repeater::scan(interface)
+    }
+
+    fn saved_networks(&self) -> Vec<SavedNetwork> {
+        saved_networks::list()
+    }
+
+    fn saved_network_password(&self, network: &SavedNetwork) -> Result<String, String> {
+        saved_networks::password(network)
+    }
+
+    fn scan_access_points(&self, interface: &str) -> Vec<NearbyAp> {
+        channels::scan(interface)
+    }
+
+    fn client_link(&self, interface: &str) -> Option<ClientLink> {
+        repeater::client_link(interface)
+    }
+
+    fn failed_joins(&self, interface: &str, since: SystemTime) -> Vec<String> {
+        auth_log::failed_attempts(interface, since)
+    }
+
+    fn dns_lookups(&self, since: SystemTime) -> Vec<Lookup> {
+        dns_log::recent_lookups(since)
+    }
+
+    fn traffic_bytes(&self, config: &Config) -> Option<(u64, u64)> {
+        hotspot::traffic_bytes(config)
+    }
+
+    fn started_at(&self, _config: &Config) -> Option<SystemTime> {
+        let started = run_state::load().started_at?;
+        Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(started))
+    }
+
+    fn uses_old_settings(&self, config: &Config) -> bool {
+        run_state::load().uses_old_settings(config)
+    }
+
+    fn sharing_problems(&self, config: &Config) -> Vec<Problem> {
+        health::check(config)
+    }
+
+    fn repair_sharing(&self, config: &Config) -> Result<(), String> {
+        health::repair(config)
+    }
+
+    fn autosuspending_usb_device(&self, config: &Config) -> Option<String> {
+        adapters::autosuspending_usb_device(&config.hotspot_interface)
+    }
+
+    fn hold_usb_power(&self, config: &Config) -> Result<(), String> {
+        hotspot::hold_usb_power(config)
+    }
+}
+
+/// Dispatches to the backend the "backend" setting chooses for starts, and to
+/// whichever one is hosting the hotspot for everything else, so changing the
+/// setting while it runs still stops it the right way
+pub struct ConfiguredBackend;
+
+impl ConfiguredBackend {
+    fn configured(config: &Config) -> &'static dyn HotspotBackend {
+        if config.backend == "hostapd" {
+            &HostapdBackend
+        } else {
+            &NetworkManagerBackend
+        }
+    }
+
+    fn running() -> &'static dyn HotspotBackend {
+        if hostapd_backend::is_running() {
+            &HostapdBackend
+        } else {
+            &NetworkManagerBackend
+        }
+    }
+}
+
+impl HotspotBackend for ConfiguredBackend {
+    fn start(&self, config: &Config) -> Result<String, HotspotError> {
+        Self::configured(config).start(config)
+    }
+
+    fn start_resolving(
+        &self,
+        config: &Config,
+        resolution: BusyResolution,
+    ) -> Result<String, HotspotError> {
+        Self::configured(config).start_resolving(config, resolution)
+    }
+
+    fn stop(&self, config: &Config) -> Result<String, HotspotError> {
+        Self::running().stop(config)
+    }
+
+    fn is_active(&self, config: &Config) -> bool {
+        Self::running().is_active(config)
+    }
+
+    fn foreign_hotspot(&self, config: &Config) -> Option<ForeignHotspot> {
+        Self::configured(config).foreign_hotspot(config)
+    }
+
+    fn adopt(&self, connection: &str) -> Result<String, HotspotError> {
+        NetworkManagerBackend.adopt(connection)
+    }
+
+    fn adopt_running(&self, config: &Config) -> Option<String> {
+        Self::running().adopt_running(config)
+    }
+
+    fn release_stopped(&self, config: &Config) -> bool {
+        Self::running().release_stopped(config)
+    }
+
+    fn connected_clients(&self, config: &Config) -> Vec<Client> {
+        Self::running().connected_clients(config)
+    }
+
+    fn neighbours(&self, config: &Config) -> Vec<(String, String)> {
+        Self::running().neighbours(config)
+    }
+
+    fn gateway_claimants(&self, config: &Config) -> Vec<String> {
+        Self::running().gateway_claimants(config)
+    }
+
+    fn upstream(&self, config: &Config) -> Option<String> {
+        Self::running().upstream(config)
+    }
+
+    fn client_mac(&self, ip: &str) -> Option<String> {
+        NetworkManagerBackend.client_mac(ip)
+    }
+
+    fn client_hostnames(&self, config: &Config) -> HashMap<String, String> {
+        Self::running().client_hostnames(config)
+    }
+
+    fn client_radios(&self, config: &Config) -> HashMap<String, Radio> {
+        Self::running().client_radios(config)
+    }
+
+    fn update_client_policy(
+        &self,
+        config: &Config,
+        previous: Option<&ClientPolicy>,
+        current: Option<&ClientPolicy>,
+    ) -> Result<(), String> {
+        Self::running().update_client_policy(config, previous, current)
+    }
+
+    fn supports_wps(&self, config: &Config) -> bool {
+        Self::running().supports_wps(config)
+    }
+
+    fn wps_push_button(&self, config: &Config, enable: bool) -> Result<(), String> {
+        Self::running().wps_push_button(config, enable)
+    }
+
+    fn disconnect_client(&self, config: &Config, mac: &str) -> Result<(), String> {
+        Self::running().disconnect_client(config, mac)
+    }
+
+    fn sweep_clients(&self, config: &Config) {
+        Self::running().sweep_clients(config);
+    }
+
+    fn has_wifi_hardware(&self) -> bool {
+        NetworkManagerBackend.has_wifi_hardware()
+    }
+
+    fn wifi_adapters(&self) -> Vec<WifiAdapter> {
+        NetworkManagerBackend.wifi_adapters()
+    }
+
+    fn network_interfaces(&self) -> Vec<String> {
+        NetworkManagerBackend.network_interfaces()
+    }
+
+    fn adapter_details(&self, device: &str) -> AdapterDetails {
+        NetworkManagerBackend.adapter_details(device)
+    }
+
+    fn scan_networks(&self, interface: &str) -> Vec<ScannedNetwork> {
+        NetworkManagerBackend.scan_networks(interface)
+    }
+
+    fn saved_networks(&self) -> Vec<SavedNetwork> {
+        NetworkManagerBackend.saved_networks()
+    }
+
+    fn saved_network_password(&self, network: &SavedNetwork) -> Result<String, String> {
+        NetworkManagerBackend.saved_network_password(network)
+    }
+
+    fn scan_access_points(&self, interface: &str) -> Vec<NearbyAp> {
+        NetworkManagerBackend.scan_access_points(interface)
+    }
+
+    fn client_link(&self, interface: &str) -> Option<ClientLink> {
+        NetworkManagerBackend.client_link(interface)
+    }
+
+    fn failed_joins(&self, interface: &str, since: SystemTime) -> Vec<String> {
+        NetworkManagerBackend.failed_joins(interface, since)
+    }
+
+    fn dns_lookups(&self, since: SystemTime) -> Vec<Lookup> {
+        NetworkManagerBackend.dns_lookups(since)
+    }
+
+    fn traffic_bytes(&self, config: &Config) -> Option<(u64, u64)> {
+        Self::running().traffic_bytes(config)
+    }
+
+    fn started_at(&self, config: &Config) -> Option<SystemTime> {
+        Self::running().started_at(config)
+    }
+
+    fn uses_old_settings(&self, config: &Config) -> bool {
+        Self::running().uses_old_settings(config)
+    }
+
+    fn sharing_problems(&self, config: &Config) -> Vec<Problem> {
+        Self::running().sharing_problems(config)
+    }
+
+    fn repair_sharing(&self, config: &Config) -> Result<(), String> {
+        Self::running().repair_sharing(config)
+    }
+
+    fn autosuspending_usb_device(&self, config: &Config) -> Option<String> {
+        NetworkManagerBackend.autosuspending_usb_device(config)
+    }
+
+    fn hold_usb_power(&self, config: &Config) -> Result<(), String> {
+        Self::running().hold_usb_power(config)
+    }
+}
+
+/// Stop the hotspot and start it again as one operation, so new settings
+/// apply or a wedged driver gets a fresh start. A failed stop only aborts
+/// the restart if the hotspot is still up.
+pub fn restart(config: &Config) -> Result<String, HotspotError> {
+    let backend = active();
+    if backend.is_active(config) {
+        if let Err(e) = backend.stop(config) {
+            if backend.is_active(config) {
+                return Err(e);
+            }
+        }
+    }
+    backend.start(config).map(|message| format!("Restarted: {message}"))
+}
+
+/// Whether the environment asks for the simulated backend
+pub fn mock_requested() -> bool {
+    std::env::var_os(MOCK_ENV).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// The backend for this process, chosen once from the environment
+pub fn active() -> &'static dyn HotspotBackend {
+    static BACKEND: OnceLock<Box<dyn HotspotBackend>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| {
+            if mock_requested() {
+                eprintln!("Using simulated hotspot backend ({MOCK_ENV} is set)");
+                Box::new(MockBackend::from_env())
+            } else {
+                Box::new(ConfiguredBackend)
+            }
+        })
+        .as_ref()
+}
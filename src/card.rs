@@ -0,0 +1,254 @@
+//! Printable "join this network" card: SSID, password and QR code.
+//!
+//! One layout is drawn two ways: as an SVG (rendered to PNG with resvg) and
+//! as a one-page PDF written by hand with the standard PDF fonts, so there's
+//! nothing to install to print it.
+
+use std::path::{Path, PathBuf};
+
+use crate::qr;
+
+/// Card size in SVG pixels and PDF points (about 21 x 13 cm)
+const WIDTH: f32 = 600.0;
+const HEIGHT: f32 = 380.0;
+const MARGIN: f32 = 32.0;
+/// QR code square, quiet zone included
+const QR_X: f32 = 316.0;
+const QR_Y: f32 = 52.0;
+const QR_SIZE: f32 = 252.0;
+const QUIET_MODULES: usize = 4;
+/// Room for the SSID and password left of the QR code
+const TEXT_WIDTH: f32 = QR_X - MARGIN - 12.0;
+/// PNG pixels per card pixel
+const PNG_SCALE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+/// One line of text; `y` is the baseline, from the top
+struct Line {
+    y: f32,
+    size: f32,
+    font: Font,
+    muted: bool,
+    text: String,
+}
+
+struct Layout {
+    lines: Vec<Line>,
+    qr_width: usize,
+    qr_dark: Vec<bool>,
+}
+
+fn layout(ssid: &str, password: &str) -> Option<Layout> {
+    let (qr_width, qr_dark) = qr::modules(&qr::wifi_uri(ssid, password))?;
+    let line = |y, size, font, muted, text: &str| Line { y, size, font, muted, text: text.to_string() };
+    // Shrink long values to fit next to the QR code; glyphs average about
+    // 0.6 em
+    let fit = |text: &str| (TEXT_WIDTH / (0.6 * text.chars().count().max(1) as f32)).min(26.0);
+    let password_text = if password.is_empty() { "(none)" } else { password };
+    Some(Layout {
+        lines: vec![
+            line(76.0, 34.0, Font::Bold, false, "WiFi"),
+            line(132.0, 13.0, Font::Regular, true, "Network"),
+            line(162.0, fit(ssid), Font::Bold, false, ssid),
+            line(212.0, 13.0, Font::Regular, true, "Password"),
+            line(242.0, fit(password_text), Font::Mono, false, password_text),
+            line(HEIGHT - MARGIN, 12.0, Font::Regular, true, "Scan the code with a phone camera to join"),
+        ],
+        qr_width,
+        qr_dark,
+    })
+}
+
+impl Layout {
+    /// Each dark run of modules in a QR row as (x, y, width, height)
+    fn qr_rects(&self) -> Vec<(f32, f32, f32, f32)> {
+        let module = QR_SIZE / (self.qr_width + 2 * QUIET_MODULES) as f32;
+        let origin = |i: usize| (i + QUIET_MODULES) as f32 * module;
+        let mut rects = Vec::new();
+        for (y, row) in self.qr_dark.chunks(self.qr_width).enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                if !row[x] {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < row.len() && row[x] {
+                    x += 1;
+                }
+                rects.push((
+                    QR_X + origin(start),
+                    QR_Y + origin(y),
+                    (x - start) as f32 * module,
+                    module,
+                ));
+            }
+        }
+        rects
+    }
+}
+
+/// The card as an SVG document, or `None` if the QR code can't be encoded
+pub fn svg(ssid: &str, password: &str) -> Option<String> {
+    let layout = layout(ssid, password)?;
+    let mut body = format!(
+        r##"<rect x="1" y="1" width="{w}" height="{h}" rx="16" fill="#ffffff" stroke="#000000" stroke-width="2"/>"##,
+        w = WIDTH - 2.0,
+        h = HEIGHT - 2.0,
+    );
+    for line in &layout.lines {
+        let (family, weight) = match line.font {
+            Font::Regular => ("sans-serif", "normal"),
+            Font::Bold => ("sans-serif", "bold"),
+            Font::Mono => ("monospace", "normal"),
+        };
+        body.push_str(&format!(
+            r#"<text x="{MARGIN}" y="{y}" font-family="{family}" font-weight="{weight}" font-size="{size:.1}" fill="{fill}">{text}</text>"#,
+            y = line.y,
+            size = line.size,
+            fill = if line.muted { "#555555" } else { "#000000" },
+            text = xml_escape(&line.text),
+        ));
+    }
+    for (x, y, w, h) in layout.qr_rects() {
+        body.push_str(&format!(r##"<rect x="{x:.2}" y="{y:.2}" width="{w:.2}" height="{h:.2}" fill="#000000"/>"##));
+    }
+    Some(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">{body}</svg>"#
+    ))
+}
+
+/// The card as a one-page PDF, or `None` if the QR code can't be encoded.
+/// The standard fonts only cover Latin-1; other characters print as '?'.
+pub fn pdf(ssid: &str, password: &str) -> Option<Vec<u8>> {
+    let layout = layout(ssid, password)?;
+    let mut content = String::from("q\n");
+    // Border, in PDF's bottom-up coordinates
+    content.push_str(&format!("2 w 1 1 {} {} re S\n", WIDTH - 2.0, HEIGHT - 2.0));
+    for line in &layout.lines {
+        let font = match line.font {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Mono => "F3",
+        };
+        let gray = if line.muted { 0.33 } else { 0.0 };
+        content.push_str(&format!(
+            "BT {gray} g /{font} {size:.1} Tf {MARGIN} {y} Td ({text}) Tj ET\n",
+            size = line.size,
+            y = HEIGHT - line.y,
+            text = pdf_escape(&line.text),
+        ));
+    }
+    content.push_str("0 g\n");
+    for (x, y, w, h) in layout.qr_rects() {
+        content.push_str(&format!("{x:.2} {:.2} {w:.2} {h:.2} re\n", HEIGHT - y - h));
+    }
+    content.push_str("f\nQ\n");
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {WIDTH} {HEIGHT}] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R /F2 6 0 R /F3 7 0 R >> >> >>"
+        ),
+        format!("<< /Length {} >>\nstream\n{content}endstream", latin1(&content).count()),
+        pdf_font("Helvetica"),
+        pdf_font("Helvetica-Bold"),
+        pdf_font("Courier"),
+    ];
+    let mut out: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend(latin1(object));
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    out.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n", objects.len() + 1)
+            .as_bytes(),
+    );
+    Some(out)
+}
+
+/// The card as a PNG, rendered from `svg` with the system fonts
+pub fn png(ssid: &str, password: &str) -> Result<Vec<u8>, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let svg = svg(ssid, password).ok_or("The network details are too long for a QR code")?;
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(&svg, &options).map_err(|e| format!("Failed to render card: {e}"))?;
+    let mut pixmap = tiny_skia::Pixmap::new((WIDTH * PNG_SCALE) as u32, (HEIGHT * PNG_SCALE) as u32)
+        .ok_or("Failed to allocate the card image")?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(PNG_SCALE, PNG_SCALE), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|e| format!("Failed to encode card: {e}"))
+}
+
+/// Write the card to `path`, as a PDF or PNG depending on its extension
+pub fn export(ssid: &str, password: &str, path: &Path) -> Result<(), String> {
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    let data = match extension.as_deref() {
+        Some("pdf") => pdf(ssid, password).ok_or("The network details are too long for a QR code")?,
+        Some("png") => png(ssid, password)?,
+        _ => return Err(format!("Unsupported card format for {}: use .pdf or .png", path.display())),
+    };
+    std::fs::write(path, data).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Where the popup saves the card: "<ssid> WiFi card.<extension>" in the
+/// documents folder (or home)
+pub fn default_path(ssid: &str, extension: &str) -> PathBuf {
+    let name: String = ssid
+        .chars()
+        .map(|c| if c == '/' || c.is_control() { '_' } else { c })
+        .collect();
+    dirs::document_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join(format!("{} WiFi card.{extension}", name.trim()))
+}
+
+fn pdf_font(name: &str) -> String {
+    format!("<< /Type /Font /Subtype /Type1 /BaseFont /{name} /Encoding /WinAnsiEncoding >>")
+}
+
+/// A PDF literal string body: Latin-1 only, with `\`, `(` and `)` escaped
+fn pdf_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if (c as u32) < 0x20 || (c as u32) > 0xff => escaped.push('?'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `text` as Latin-1 bytes (it's already limited to Latin-1 by `pdf_escape`)
+fn latin1(text: &str) -> impl Iterator<Item = u8> + '_ {
+    text.chars().map(|c| u8::try_from(c as u32).unwrap_or(b'?'))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,124 @@
+//! WiFi channel selection and congestion.
+//!
+//! A scan from the hotspot adapter gives the channel and signal of every
+//! access point in range; grouping them per channel shows where the air is
+//! crowded before a manual channel is picked.
+
+use crate::nm_dbus;
+
+/// 2.4 GHz channels usable in most regulatory domains
+pub const CHANNELS_2GHZ: &[u32] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
+/// 5 GHz channels (20 MHz), UNII-1 through UNII-3
+pub const CHANNELS_5GHZ: &[u32] = &[
+    36, 40, 44, 48, 52, 56, 60, 64, 100, 104, 108, 112, 116, 120, 124, 128, 132, 136, 140, 144,
+    149, 153, 157, 161, 165,
+];
+
+/// Channels for an NM band ("bg" or "a")
+pub fn for_band(band: &str) -> &'static [u32] {
+    if band == "a" {
+        CHANNELS_5GHZ
+    } else {
+        CHANNELS_2GHZ
+    }
+}
+
+/// Whether `channel` can be used on `band`; 0 (automatic) always can
+pub fn is_valid(band: &str, channel: u32) -> bool {
+    channel == 0 || for_band(band).contains(&channel)
+}
+
+/// One access point seen in a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearbyAp {
+    pub channel: u32,
+    /// Signal strength, 0–100
+    pub signal: u32,
+}
+
+/// How busy one channel is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelUsage {
+    pub channel: u32,
+    pub access_points: usize,
+    /// Signal of the strongest access point on the channel, 0 if none
+    pub strongest: u32,
+}
+
+/// Usage of every channel on `band`, in channel order
+pub fn usage(band: &str, aps: &[NearbyAp]) -> Vec<ChannelUsage> {
+    for_band(band)
+        .iter()
+        .map(|&channel| {
+            let on_channel = aps.iter().filter(|ap| ap.channel == channel);
+            ChannelUsage {
+                channel,
+                access_points: on_channel.clone().count(),
+                strongest: on_channel.map(|ap| ap.signal).max().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// The channel with the fewest access points, weaker ones breaking ties
+pub fn least_busy(usage: &[ChannelUsage]) -> Option<u32> {
+    usage
+        .iter()
+        .min_by_key(|u| (u.access_points, u.strongest))
+        .map(|u| u.channel)
+}
+
+/// Every access point `interface` can see, with its channel
+pub fn scan(interface: &str) -> Vec<NearbyAp> {
+    nm_dbus::access_points(interface, true)
+        .iter()
+        .filter(|ap| ap.channel() != 0)
+        .map(|ap| NearbyAp { channel: ap.channel(), signal: ap.strength })
+        .collect()
+}
+
+/// Bar chart of `usage`: one bar per channel, its height the number of
+/// access points and its opacity the strongest signal. `selected` (if not
+/// 0) is underlined.
+pub fn spectrum_svg(usage: &[ChannelUsage], selected: u32, color: &str) -> String {
+    const BAR: usize = 12;
+    const GAP: usize = 4;
+    const PLOT: usize = 48;
+    let busiest = usage.iter().map(|u| u.access_points).max().unwrap_or(0).max(1);
+    let width = usage.len() * (BAR + GAP);
+
+    let mut elements = String::new();
+    for (i, u) in usage.iter().enumerate() {
+        let x = i * (BAR + GAP);
+        let height = (u.access_points * PLOT / busiest).max(1);
+        let opacity = 0.25 + 0.75 * u.strongest.min(100) as f32 / 100.0;
+        elements.push_str(&format!(
+            r#"<rect x="{x}" y="{y}" width="{BAR}" height="{height}" fill="{color}" fill-opacity="{opacity:.2}"/>"#,
+            y = 10 + PLOT - height,
+        ));
+        if u.access_points > 0 {
+            elements.push_str(&format!(
+                r#"<text x="{cx}" y="{y}" font-size="8" text-anchor="middle" fill="{color}">{n}</text>"#,
+                cx = x + BAR / 2,
+                y = 8 + PLOT - height,
+                n = u.access_points,
+            ));
+        }
+        elements.push_str(&format!(
+            r#"<text x="{cx}" y="{y}" font-size="7" text-anchor="middle" fill="{color}">{ch}</text>"#,
+            cx = x + BAR / 2,
+            y = PLOT + 20,
+            ch = u.channel,
+        ));
+        if u.channel == selected {
+            elements.push_str(&format!(
+                r#"<rect x="{x}" y="{y}" width="{BAR}" height="2" fill="{color}"/>"#,
+                y = PLOT + 23,
+            ));
+        }
+    }
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{h}" viewBox="0 0 {width} {h}">{elements}</svg>"#,
+        h = PLOT + 26,
+    )
+}
@@ -0,0 +1,325 @@
+//! One-shot runtime commands (start, stop, toggle) for scripts and global shortcuts.
+
+use std::time::SystemTime;
+
+use crate::backend;
+use crate::card;
+use crate::clients::{self, ClientInfo, ClientTracker};
+use crate::config::Config;
+use crate::dbus_service;
+use crate::diagnostics::{self, Check};
+use crate::errors::{ErrorKind, HotspotError};
+use crate::event_log::{self, Entry};
+use crate::hotspot::BusyResolution;
+use crate::metrics;
+use crate::notify;
+use crate::profile_import;
+use crate::run_state;
+use crate::schedule;
+
+/// Entries `--logs` prints before following
+const RECENT_LOG_ENTRIES: usize = 50;
+
+/// Start the hotspot and print the outcome. `resolution` says how to free the
+/// adapter if it's connected to a network as a client.
+pub fn start(dry_run: bool, resolution: Option<BusyResolution>) {
+    let config = load(dry_run);
+    let result = match resolution {
+        Some(resolution) => backend::active().start_resolving(&config, resolution),
+        None => backend::active().start(&config),
+    };
+    if let Err(e) = &result {
+        if e.kind == ErrorKind::InterfaceBusy {
+            eprintln!(
+                "{} is connected to '{}'. Re-run with --disconnect-client to drop that \
+                 connection, or --virtual-ap to keep it and host the hotspot on a virtual \
+                 interface (if the adapter supports AP + client).",
+                config.hotspot_interface, e.detail
+            );
+        }
+    }
+    report(result, false);
+}
+
+/// Stop the hotspot and print the outcome
+pub fn stop(dry_run: bool) {
+    let config = load(dry_run);
+    report(backend::active().stop(&config), false);
+}
+
+/// Stop and start the hotspot as one operation and print the outcome
+pub fn restart(dry_run: bool) {
+    let config = load(dry_run);
+    report(backend::restart(&config), false);
+}
+
+/// Toggle the hotspot and report the outcome as a desktop notification,
+/// since the caller is usually a keyboard shortcut with no terminal attached.
+pub fn toggle(dry_run: bool) {
+    let config = load(dry_run);
+    let result = if backend::active().is_active(&config) {
+        backend::active().stop(&config)
+    } else {
+        backend::active().start(&config)
+    };
+    report(result, !config.dry_run);
+}
+
+/// Check the setup end to end without needing the hotspot: pre-flight
+/// checks, a start, the connectivity verification and a stop. Prints a
+/// pass/fail line per check and exits non-zero if any failed.
+pub fn self_test() {
+    let mut config = Config::load();
+    if backend::active().is_active(&config) {
+        eprintln!("The hotspot is running; stop it before running the self-test");
+        std::process::exit(1);
+    }
+    println!(
+        "Self-test of '{}' on {} (sharing {})\n",
+        config.connection_name, config.hotspot_interface, config.internet_interface
+    );
+
+    let mut checks = diagnostics::preflight(&config);
+    print_checks(&checks);
+    if diagnostics::first_failure(&checks).is_none() {
+        // Verified below, check by check
+        config.verify_after_start = false;
+        let started = match backend::active().start(&config) {
+            Ok(msg) => Check::pass("Activation", msg),
+            Err(e) => Check::fail("Activation", e.to_string()),
+        };
+        let mut cycle = vec![started];
+        if cycle[0].passed {
+            cycle.extend(diagnostics::verify_connectivity(&config));
+            cycle.push(match backend::active().stop(&config) {
+                Ok(_) if backend::active().is_active(&config) => {
+                    Check::fail("Deactivation", "still active after stopping")
+                }
+                Ok(msg) => Check::pass("Deactivation", msg),
+                Err(e) => Check::fail("Deactivation", e.to_string()),
+            });
+        }
+        print_checks(&cycle);
+        print_timings();
+        if !cycle[0].passed {
+            print_activation_log();
+        }
+        checks.extend(cycle);
+    } else {
+        println!("  (activation skipped: fix the failed pre-flight checks first)");
+    }
+
+    let failed = checks.iter().filter(|c| !c.passed).count();
+    if failed == 0 {
+        println!("\nSelf-test passed ({} checks)", checks.len());
+    } else {
+        println!("\nSelf-test failed: {failed} of {} checks", checks.len());
+        std::process::exit(1);
+    }
+}
+
+/// How long each step of the start took
+fn print_timings() {
+    let Some(timings) = run_state::load().timings.filter(|t| !t.steps.is_empty()) else {
+        return;
+    };
+    println!("\n  Start took {:.1} s:", timings.total_millis() as f64 / 1000.0);
+    for timing in &timings.steps {
+        println!("    {:>6.1} s  {}", timing.millis as f64 / 1000.0, timing.step.label());
+    }
+}
+
+/// What NetworkManager logged during the failed activation
+fn print_activation_log() {
+    let state = run_state::load();
+    let Some(lines) = state.failed_activation_log().filter(|lines| !lines.is_empty()) else {
+        return;
+    };
+    println!("\n  NetworkManager log of the activation:");
+    for line in lines {
+        println!("    {line}");
+    }
+}
+
+fn print_checks(checks: &[Check]) {
+    for check in checks {
+        let mark = if check.passed { "PASS" } else { "FAIL" };
+        println!("  {mark}  {}: {}", check.name, check.detail);
+    }
+}
+
+/// Move passwords from config.json into the keyring and report what moved
+pub fn migrate_secrets() {
+    match Config::migrate_secrets() {
+        Ok(fields) if fields.is_empty() => println!("Nothing to migrate: no passwords in config.json"),
+        Ok(fields) => println!("Moved to the keyring: {}", fields.join(", ")),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Add a profile from another tool's hotspot config at `path`, named `name`
+/// (or after the file's connection), and make it the active one
+pub fn import_profile(path: &str, name: &str) {
+    let imported = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {path}: {e}"))
+        .and_then(|text| profile_import::parse(&text, name));
+    let imported = match imported {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let notes = imported.notes.clone();
+    let mut config = Config::load();
+    let profile = imported.apply(&mut config);
+    if let Err(e) = config.save() {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    event_log::record(event_log::Level::Info, format!("Imported profile '{profile}' from {path}"));
+    println!("Imported profile '{profile}' (SSID '{}') and made it active", config.ssid);
+    for note in notes {
+        println!("  Note: {note}");
+    }
+}
+
+/// Put back the settings from before a reset: the backup at `path`, or the
+/// newest one
+pub fn restore_backup(path: Option<&str>) {
+    match Config::restore_backup(path.map(std::path::Path::new)) {
+        Ok(backup) => {
+            event_log::record(event_log::Level::Info, format!("Restored settings from {}", backup.display()));
+            println!("Restored settings from {}", backup.display());
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            let backups = Config::backups();
+            if !backups.is_empty() {
+                eprintln!("Backups:");
+                for backup in backups {
+                    eprintln!("  {}", backup.display());
+                }
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Save the printable credentials card to `path` (.pdf or .png)
+pub fn export_card(path: &str) {
+    let config = Config::load();
+    match card::export(&config.ssid, &config.password, std::path::Path::new(path)) {
+        Ok(()) => println!("Saved card for '{}' to {path}", config.ssid),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print the recent event log, then with `follow` keep printing new entries
+/// until interrupted
+pub fn logs(follow: bool, json: bool) {
+    let print = |entry: &Entry| {
+        if json {
+            println!("{}", serde_json::to_string(entry).unwrap_or_default());
+        } else {
+            println!("{}", entry.format());
+        }
+    };
+    event_log::recent(RECENT_LOG_ENTRIES).iter().for_each(print);
+    if !follow {
+        return;
+    }
+    let (_, mut offset) = event_log::read_from(0);
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        let (entries, next) = event_log::read_from(offset);
+        entries.iter().for_each(print);
+        offset = next;
+    }
+}
+
+/// `--clients`: the running applet's client list, or a one-off listing
+/// (without connection times) when the applet isn't running
+pub fn clients(json: bool) {
+    let clients = dbus_service::fetch_clients().unwrap_or_else(|_| {
+        let config = Config::load();
+        if !backend::active().is_active(&config) {
+            return Vec::new();
+        }
+        let present = backend::active().connected_clients(&config);
+        let mut clients = ClientTracker::new().update(&present, std::time::Duration::ZERO);
+        clients::fill_details(&mut clients, &config);
+        clients.iter_mut().for_each(|c| c.first_seen = None);
+        clients
+    });
+    let now = SystemTime::now();
+    for client in &clients {
+        if json {
+            println!("{}", client_json(client));
+            continue;
+        }
+        let connected = client
+            .connected_for(now)
+            .map_or_else(|| "-".to_string(), |time| schedule::format_duration((time.as_secs() / 60) as u32));
+        println!(
+            "{:<15}  {:<17}  {:<12}  {:<20}  {:<6}  {connected}",
+            client.ip,
+            client.mac.as_deref().unwrap_or("-"),
+            client.vendor.unwrap_or("-"),
+            client.hostname.as_deref().unwrap_or("-"),
+            client.state.as_str(),
+        );
+    }
+}
+
+fn client_json(client: &ClientInfo) -> serde_json::Value {
+    let (ip, mac, hostname, state, first_seen) = client.to_wire();
+    serde_json::json!({
+        "ip": ip,
+        "mac": (!mac.is_empty()).then_some(mac),
+        "vendor": client.vendor,
+        "hostname": (!hostname.is_empty()).then_some(hostname),
+        "state": state,
+        "first_seen": (first_seen > 0).then_some(first_seen),
+    })
+}
+
+/// Print the hotspot's state and the last start's step timings as
+/// Prometheus metrics
+pub fn metrics() {
+    let config = Config::load();
+    let active = backend::active().is_active(&config);
+    let clients = if active { backend::active().connected_clients(&config).len() } else { 0 };
+    print!("{}", metrics::render(active, clients, run_state::load().timings.as_ref()));
+}
+
+/// Load the config, with `--dry-run` overriding the saved setting for this run only
+fn load(dry_run: bool) -> Config {
+    let mut config = Config::load();
+    config.dry_run |= dry_run;
+    config
+}
+
+fn report(result: Result<String, HotspotError>, notify: bool) {
+    match result {
+        Ok(msg) => {
+            println!("{msg}");
+            if notify {
+                notify::send("WiFi Hotspot", &msg);
+            }
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            if notify {
+                notify::send("WiFi Hotspot failed", e.message());
+            }
+            std::process::exit(1);
+        }
+    }
+}
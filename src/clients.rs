@@ -0,0 +1,293 @@
+//! Connected client bookkeeping.
+//!
+//! The neighbour table flips entries between REACHABLE, STALE and gone as
+//! clients sleep and wake, which makes a raw per-poll client list jitter.
+//! `ClientTracker` remembers when each client was last seen and keeps it
+//! listed (marked idle) until a grace period has passed.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::backend;
+use crate::config::Config;
+use crate::local_dns;
+use crate::oui;
+use crate::stations::Radio;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientState {
+    /// Present in the latest neighbour table poll
+    Active,
+    /// Missing from the latest poll but still within the grace period
+    Idle,
+}
+
+impl ClientState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Idle => "idle",
+        }
+    }
+}
+
+/// A device in the hotspot's neighbour table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Client {
+    pub ip: String,
+    /// Lowercase MAC address, if the entry has one
+    pub mac: Option<String>,
+    /// Manufacturer from the MAC's OUI, e.g. "Apple"
+    pub vendor: Option<&'static str>,
+}
+
+impl Client {
+    pub fn new(ip: &str, mac: Option<&str>) -> Self {
+        let mac = mac.map(str::to_lowercase);
+        let vendor = mac.as_deref().and_then(oui::vendor);
+        Self { ip: ip.to_string(), mac, vendor }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub ip: String,
+    /// Filled in by `fill_details` where the backend knows it
+    pub mac: Option<String>,
+    /// Manufacturer from the MAC's OUI, filled in with `mac`
+    pub vendor: Option<&'static str>,
+    /// The hostname it sent with its DHCP request, filled in like `mac`
+    pub hostname: Option<String>,
+    /// Band and bitrate it's associated at, filled in like `mac`
+    pub radio: Option<Radio>,
+    pub state: ClientState,
+    /// When the tracker first saw it this session; `None` for a one-off
+    /// listing without the applet's history
+    pub first_seen: Option<SystemTime>,
+}
+
+/// A client as sent over D-Bus: (ip, mac, hostname, state, first seen as
+/// unix time). Unknown strings are empty and an unknown time is 0.
+pub type WireClient = (String, String, String, String, u64);
+
+impl ClientInfo {
+    pub fn to_wire(&self) -> WireClient {
+        (
+            self.ip.clone(),
+            self.mac.clone().unwrap_or_default(),
+            self.hostname.clone().unwrap_or_default(),
+            self.state.as_str().to_string(),
+            self.first_seen
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs()),
+        )
+    }
+
+    pub fn from_wire((ip, mac, hostname, state, first_seen): WireClient) -> Self {
+        Self {
+            ip,
+            vendor: oui::vendor(&mac),
+            mac: (!mac.is_empty()).then_some(mac),
+            hostname: (!hostname.is_empty()).then_some(hostname),
+            radio: None,
+            state: if state == "idle" { ClientState::Idle } else { ClientState::Active },
+            first_seen: (first_seen > 0).then(|| UNIX_EPOCH + Duration::from_secs(first_seen)),
+        }
+    }
+
+    /// "Pixel-7 (192.168.44.23)" from its DHCP hostname, or the name other
+    /// devices can reach it by, "pixel-7.hotspot (192.168.44.23)", when the
+    /// hotspot `serves_names`; the bare IP if it sent no hostname
+    pub fn display_name(&self, serves_names: bool) -> String {
+        let Some(hostname) = self.hostname.as_deref() else {
+            return self.ip.clone();
+        };
+        match local_dns::dns_name(hostname) {
+            Some(name) if serves_names => format!("{name} ({})", self.ip),
+            _ => format!("{hostname} ({})", self.ip),
+        }
+    }
+
+    /// How long it has been connected at `now`, if known
+    pub fn connected_for(&self, now: SystemTime) -> Option<Duration> {
+        now.duration_since(self.first_seen?).ok()
+    }
+}
+
+/// Look up the MAC address (where the neighbour table didn't have it), vendor,
+/// DHCP hostname and radio details of each client
+pub fn fill_details(clients: &mut [ClientInfo], config: &Config) {
+    let mut hostnames = backend::active().client_hostnames(config);
+    let mut radios = backend::active().client_radios(config);
+    for client in clients {
+        if client.mac.is_none() {
+            client.mac = backend::active().client_mac(&client.ip);
+        }
+        client.vendor = client.mac.as_deref().and_then(oui::vendor);
+        client.hostname = hostnames.remove(&client.ip);
+        client.radio = client.mac.as_ref().and_then(|mac| radios.remove(&mac.to_lowercase()));
+    }
+}
+
+/// Ordering for the popup client list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientSort {
+    Address,
+    /// By DHCP hostname, clients that sent none last
+    Name,
+    /// Strongest signal first, clients without a reading last
+    Signal,
+    /// Active clients first, then idle ones
+    Status,
+}
+
+impl ClientSort {
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "name" => Self::Name,
+            "signal" => Self::Signal,
+            "status" => Self::Status,
+            _ => Self::Address,
+        }
+    }
+
+    pub fn as_config(self) -> &'static str {
+        match self {
+            Self::Address => "address",
+            Self::Name => "name",
+            Self::Signal => "signal",
+            Self::Status => "status",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Address => "Address",
+            Self::Name => "Name",
+            Self::Signal => "Signal",
+            Self::Status => "Status",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Address => Self::Name,
+            Self::Name => Self::Signal,
+            Self::Signal => Self::Status,
+            Self::Status => Self::Address,
+        }
+    }
+}
+
+/// Return the clients whose address or hostname matches `filter`
+/// (case-insensitive substring), ordered by `sort`
+pub fn filter_and_sort<'a>(
+    clients: &'a [ClientInfo],
+    filter: &str,
+    sort: ClientSort,
+) -> Vec<&'a ClientInfo> {
+    let filter = filter.trim().to_lowercase();
+    let mut result: Vec<&ClientInfo> = clients
+        .iter()
+        .filter(|c| {
+            filter.is_empty()
+                || c.ip.to_lowercase().contains(&filter)
+                || c.hostname.as_ref().is_some_and(|h| h.to_lowercase().contains(&filter))
+                || c.vendor.is_some_and(|v| v.to_lowercase().contains(&filter))
+        })
+        .collect();
+
+    match sort {
+        ClientSort::Address => result.sort_by_key(|c| sort_address(c)),
+        ClientSort::Name => result.sort_by_key(|c| {
+            (c.hostname.is_none(), c.hostname.as_ref().map(|h| h.to_lowercase()), sort_address(c))
+        }),
+        ClientSort::Signal => result.sort_by_key(|c| {
+            (std::cmp::Reverse(c.radio.as_ref().and_then(|r| r.signal_dbm)), sort_address(c))
+        }),
+        ClientSort::Status => {
+            result.sort_by_key(|c| (c.state == ClientState::Idle, sort_address(c)))
+        }
+    }
+    result
+}
+
+fn sort_address(client: &ClientInfo) -> Option<std::net::Ipv4Addr> {
+    client.ip.parse().ok()
+}
+
+#[derive(Default)]
+pub struct ClientTracker {
+    /// Last poll each client was present in, and when it first appeared
+    seen: HashMap<String, (Instant, SystemTime)>,
+}
+
+impl ClientTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the clients present in this poll and return the smoothed list,
+    /// sorted by address so rows don't reshuffle between polls.
+    pub fn update(&mut self, present: &[Client], grace: Duration) -> Vec<ClientInfo> {
+        let now = Instant::now();
+        for client in present {
+            let first = self.seen.get(&client.ip).map_or_else(SystemTime::now, |(_, first)| *first);
+            self.seen.insert(client.ip.clone(), (now, first));
+        }
+        self.seen
+            .retain(|_, (last, _)| now.duration_since(*last) <= grace);
+
+        let mut clients: Vec<ClientInfo> = self
+            .seen
+            .iter()
+            .map(|(ip, (_, first))| {
+                let current = present.iter().find(|c| &c.ip == ip);
+                ClientInfo {
+                    ip: ip.clone(),
+                    mac: current.and_then(|c| c.mac.clone()),
+                    vendor: current.and_then(|c| c.vendor),
+                    hostname: None,
+                    radio: None,
+                    state: if current.is_some() { ClientState::Active } else { ClientState::Idle },
+                    first_seen: Some(*first),
+                }
+            })
+            .collect();
+        clients.sort_by_key(sort_address);
+        clients
+    }
+
+    /// Forget all clients, e.g. when the hotspot goes down
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
+/// How long a running hotspot has gone without clients, for the idle auto-off
+#[derive(Debug, Default)]
+pub struct IdleTimer {
+    empty_since: Option<Instant>,
+}
+
+impl IdleTimer {
+    /// Record a poll that saw `clients` clients at `now`. Returns true once
+    /// the list has been empty for `timeout`; counting then starts afresh.
+    pub fn observe(&mut self, clients: usize, timeout: Duration, now: Instant) -> bool {
+        if clients > 0 {
+            self.empty_since = None;
+            return false;
+        }
+        let since = *self.empty_since.get_or_insert(now);
+        if now.saturating_duration_since(since) >= timeout {
+            self.empty_since = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.empty_since = None;
+    }
+}
@@ -0,0 +1,39 @@
+//! Copying passwords without leaving them in clipboard history.
+//!
+//! Clipboard managers (Klipper, cliphist, the COSMIC clipboard history)
+//! skip entries that also offer `x-kde-passwordManagerHint` set to
+//! "secret", the hint password managers use. Managers that don't know it
+//! still keep the text, which is what the timed clear is for.
+
+use std::borrow::Cow;
+
+use cosmic::iced::clipboard::mime::AsMimeTypes;
+
+/// MIME type whose "secret" value asks clipboard managers not to keep an entry
+pub const PASSWORD_HINT_MIME: &str = "x-kde-passwordManagerHint";
+/// The text types a paste can ask for
+const TEXT_MIME_TYPES: [&str; 5] = ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING", "STRING", "TEXT"];
+
+/// Clipboard text, offered with the password hint when `sensitive`. No
+/// `Debug`, so the password can't end up in a log by accident.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret {
+    pub text: String,
+    pub sensitive: bool,
+}
+
+impl AsMimeTypes for Secret {
+    fn available(&self) -> Cow<'static, [String]> {
+        let hint = self.sensitive.then_some(PASSWORD_HINT_MIME);
+        Cow::Owned(TEXT_MIME_TYPES.into_iter().chain(hint).map(str::to_string).collect())
+    }
+
+    fn as_bytes(&self, mime_type: &str) -> Option<Cow<'static, [u8]>> {
+        if mime_type == PASSWORD_HINT_MIME {
+            return self.sensitive.then_some(Cow::Borrowed(b"secret".as_slice()));
+        }
+        TEXT_MIME_TYPES
+            .contains(&mime_type)
+            .then(|| Cow::Owned(self.text.clone().into_bytes()))
+    }
+}
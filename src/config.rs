@@ -1,15 +1,269 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::nat::ClientAccess;
+use crate::schedule::Schedule;
+use crate::secrets;
+
+/// The settings model. Fields with an `x-section` are shown in the settings
+/// hub, which builds its form from this type's JSON schema (see
+/// `settings_cli::describe`); the rest are internal state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct Config {
+    #[schemars(title = "Hotspot Interface", extend("x-section" = "Interfaces", "x-options-from" = "ap_adapters"))]
     pub hotspot_interface: String,
+    #[schemars(title = "Internet Interface", extend("x-section" = "Interfaces", "x-options-from" = "network_interfaces"))]
     pub internet_interface: String,
+    #[schemars(title = "Connection Name", extend("x-section" = "Advanced", "x-placeholder" = "NM connection name"))]
     pub connection_name: String,
+    #[schemars(title = "SSID", extend("x-section" = "Network", "x-placeholder" = "Network name"))]
     pub ssid: String,
+    #[schemars(title = "Password", extend("x-section" = "Network", "x-placeholder" = "WPA2 password"))]
     pub password: String,
+    /// "wpa2", "wpa3" or "wpa2-wpa3" (transition mode, for mixed clients;
+    /// hostapd backend only)
+    #[schemars(title = "Security", extend("x-section" = "Network", "x-options" = [
+        {"value": "wpa2", "label": "WPA2 Personal"},
+        {"value": "wpa3", "label": "WPA3 Personal"},
+        {"value": "wpa2-wpa3", "label": "WPA2/WPA3 Personal"}
+    ]))]
+    pub security: String,
+    #[schemars(title = "Band", extend("x-section" = "Network", "x-options" = [
+        {"value": "bg", "label": "2.4 GHz"},
+        {"value": "a", "label": "5 GHz"}
+    ]))]
     pub band: String,
-    pub gateway_ip: String,
+    /// WiFi channel within the band (0 = let NetworkManager choose)
+    #[schemars(title = "Channel", range(min = 0, max = 165), extend(
+        "x-section" = "Network",
+        "x-description" = "0 picks automatically; the settings page shows how busy each channel is"
+    ))]
+    pub channel: u32,
+    /// Named hotspot setups; the active one's network settings are the
+    /// fields above, the others keep theirs in `Profile::network`
+    pub profiles: Vec<Profile>,
+    /// Name of the profile in use
+    pub active_profile: String,
+    /// Periodically ping the hotspot subnet so idle clients stay in the neighbour table
+    #[schemars(title = "Sweep subnet for idle clients", extend("x-section" = "Advanced"))]
+    pub client_sweep: bool,
+    /// Minimum seconds between two subnet sweeps
+    #[schemars(title = "Sweep interval (seconds)", range(min = 10, max = 3600), extend("x-section" = "Advanced"))]
+    pub client_sweep_interval_secs: u64,
+    /// Seconds a client stays listed (as idle) after dropping out of the neighbour table
+    #[schemars(title = "Idle client grace period (seconds)", range(min = 0, max = 3600), extend("x-section" = "Advanced"))]
+    pub client_grace_secs: u64,
+    /// Global shortcut that toggles the hotspot, e.g. "Super+Shift+H" (empty = none)
+    #[schemars(title = "Toggle hotspot", extend("x-section" = "Shortcuts", "x-placeholder" = "e.g. Super+Shift+H"))]
+    pub toggle_shortcut: String,
+    // Popup layout: which optional sections to show and how tightly to pack them
+    #[schemars(title = "Show connected clients", extend("x-section" = "Display"))]
+    pub popup_show_clients: bool,
+    #[schemars(title = "Show QR code", extend("x-section" = "Display"))]
+    pub popup_show_qr: bool,
+    #[schemars(title = "Show statistics", extend("x-section" = "Display"))]
+    pub popup_show_stats: bool,
+    #[schemars(title = "Show schedule", extend("x-section" = "Display"))]
+    pub popup_show_schedule: bool,
+    /// "comfortable" or "compact"
+    #[schemars(title = "Popup density", extend("x-section" = "Display", "x-options" = [
+        {"value": "comfortable", "label": "Comfortable"},
+        {"value": "compact", "label": "Compact"}
+    ]))]
+    pub popup_density: String,
+    /// Active icon color: "foreground", "accent", "success" or "warning"
+    #[schemars(title = "Active icon color", extend("x-section" = "Display", "x-options" = [
+        {"value": "foreground", "label": "Panel foreground"},
+        {"value": "accent", "label": "Accent"},
+        {"value": "success", "label": "Success"},
+        {"value": "warning", "label": "Warning"}
+    ]))]
+    pub icon_color: String,
+    /// Panel icon glyph: "ripple" (animated), "broadcast" (static), or one of
+    /// the low-vision styles "high_contrast", "filled" and "monochrome", which
+    /// also replace the off icon
+    #[schemars(title = "Icon style", extend("x-section" = "Display", "x-options" = [
+        {"value": "ripple", "label": "Ripple (animated)"},
+        {"value": "broadcast", "label": "Broadcast (static)"},
+        {"value": "high_contrast", "label": "High contrast"},
+        {"value": "filled", "label": "Simple filled"},
+        {"value": "monochrome", "label": "Monochrome (static)"}
+    ]))]
+    pub icon_style: String,
+    /// Seconds a start/stop result (or error) stays in the popup before the
+    /// plain status returns
+    #[schemars(title = "Keep result messages for (seconds)", range(min = 1, max = 600), extend("x-section" = "Display"))]
+    pub status_hold_secs: u64,
+    /// Panel icon left click: "popup", "toggle" or "qr" (right click always
+    /// opens the popup)
+    #[schemars(title = "Clicking the panel icon", extend("x-section" = "Display", "x-options" = [
+        {"value": "popup", "label": "Opens the popup"},
+        {"value": "toggle", "label": "Toggles the hotspot"},
+        {"value": "qr", "label": "Shows the join QR code"}
+    ]))]
+    pub click_action: String,
+    /// Disable icon animations (static ripple, no "client joined" flash)
+    #[schemars(title = "Reduce motion", extend("x-section" = "Display"))]
+    pub reduce_motion: bool,
+    /// Popup client list ordering: "address", "name", "signal" or "status"
+    pub client_sort: String,
+    /// Access restrictions for individual clients, by MAC address; set from
+    /// the popup's client list
+    pub client_policies: Vec<ClientPolicy>,
+    /// MAC addresses of devices that joined the hotspot, most recent last, so
+    /// the popup can wake them after they've gone to sleep
+    pub known_clients: Vec<String>,
+    /// Check AP, DHCP, forwarding, NAT and an outbound probe after starting
+    #[schemars(title = "Verify connectivity after start", extend("x-section" = "Advanced"))]
+    pub verify_after_start: bool,
+    /// Scan before starting and warn if a nearby network already uses the SSID
+    #[schemars(title = "Warn about nearby networks with the same name", extend("x-section" = "Advanced"))]
+    pub check_ssid_collisions: bool,
+    /// Notify once this many wrong-password join attempts were seen in a
+    /// session (0 = never)
+    #[schemars(title = "Notify after failed join attempts", range(min = 0, max = 1000), extend(
+        "x-section" = "Advanced",
+        "x-placeholder" = "0 = off"
+    ))]
+    pub failed_join_notify_threshold: u64,
+    /// Warn in the event log when activating the hotspot takes longer than
+    /// this many seconds (0 = never), to spot driver regressions
+    #[schemars(title = "Warn when activation takes longer than (seconds)", range(min = 0, max = 600), extend(
+        "x-section" = "Advanced",
+        "x-placeholder" = "0 = off"
+    ))]
+    pub slow_activation_secs: u64,
+    /// Have the hotspot's dnsmasq log every DNS lookup, and list recent ones
+    /// per client in the popup. Only covers clients using the gateway for
+    /// DNS (no profile DNS server); takes effect at the next start.
+    #[schemars(title = "Log clients' DNS lookups (privacy: records every name they look up)", extend("x-section" = "Advanced"))]
+    pub dns_query_log: bool,
+    /// Register clients' hostnames in the hotspot's DNS as <name>.hotspot and
+    /// advertise the domain over DHCP. Like the query log, only for profiles
+    /// without a DNS server; takes effect at the next start.
+    #[schemars(title = "Resolve clients by name (<hostname>.hotspot)", extend("x-section" = "Advanced"))]
+    pub local_domain: bool,
+    /// Forward the hotspot's DNS through the host's systemd-resolved stub
+    /// rather than the upstream servers, so clients follow the host's VPN
+    /// and split DNS instead of leaking queries past them. Only for profiles
+    /// without a DNS server; takes effect at the next start.
+    #[schemars(title = "Resolve clients' DNS through this computer's resolver (follows VPN DNS)", extend("x-section" = "Advanced"))]
+    pub host_resolver_dns: bool,
+    /// Check the running hotspot's forwarding and explicit NAT rules every
+    /// minute and re-apply them if a firewall reload, VPN or Docker removed them
+    #[schemars(title = "Repair connection sharing when other tools reset it", extend("x-section" = "Advanced"))]
+    pub repair_sharing: bool,
+    /// Have the AP send multicast frames to each client as unicast, at that
+    /// client's rate; casting (Chromecast, AirPlay) and VR headset discovery
+    /// often stall without it. Takes effect at the next start.
+    #[schemars(title = "Convert multicast to unicast (helps casting and VR discovery)", extend("x-section" = "Advanced"))]
+    pub multicast_to_unicast: bool,
+    /// Rate multicast frames are sent at, in Mbit/s (0 = driver default,
+    /// usually the slowest); takes effect at the next start
+    #[schemars(title = "Multicast rate (Mbit/s)", range(min = 0, max = 54), extend(
+        "x-section" = "Advanced",
+        "x-description" = "0 keeps the driver's default; 6, 12 or 24 suit most casting setups"
+    ))]
+    pub multicast_rate_mbps: u32,
+    /// What hosts the access point: "networkmanager", or "hostapd" (hostapd
+    /// and dnsmasq run by the helper) for adapters whose AP mode is flaky
+    /// through NetworkManager
+    #[schemars(title = "Hotspot backend", extend("x-section" = "Advanced", "x-options" = [
+        {"value": "networkmanager", "label": "NetworkManager"},
+        {"value": "hostapd", "label": "hostapd + dnsmasq"}
+    ]))]
+    pub backend: String,
+    /// Data quota for traffic through the hotspot: "off", "daily" or "weekly".
+    /// Usage is kept across restarts; the popup shows progress and a
+    /// notification warns at 80%.
+    #[schemars(title = "Data quota", extend("x-section" = "Advanced", "x-options" = [
+        {"value": "off", "label": "Off"},
+        {"value": "daily", "label": "Daily"},
+        {"value": "weekly", "label": "Weekly"}
+    ]))]
+    pub quota_period: String,
+    /// Quota size in megabytes (0 = off)
+    #[schemars(title = "Quota (MB)", extend("x-section" = "Advanced", "x-placeholder" = "e.g. 5000"))]
+    pub quota_mb: u64,
+    /// Local time the quota resets, "HH:MM"; weekly quotas reset on Monday
+    #[schemars(title = "Quota resets at", extend("x-section" = "Advanced", "x-placeholder" = "00:00"))]
+    pub quota_reset_time: String,
+    /// Turn the hotspot off once it has had no clients for this many
+    /// minutes (0 = never)
+    #[schemars(title = "Turn off after minutes without clients", range(min = 0, max = 1440), extend(
+        "x-section" = "Advanced",
+        "x-placeholder" = "0 = off"
+    ))]
+    pub idle_off_mins: u64,
+    /// Offer copied passwords with the hint clipboard managers read as
+    /// "keep out of history"
+    #[schemars(title = "Keep copied passwords out of clipboard history", extend("x-section" = "Advanced"))]
+    pub clipboard_sensitive: bool,
+    /// Clear copied network info from the clipboard after this many seconds,
+    /// if it's still there (0 = never)
+    #[schemars(title = "Clear copied password after (seconds)", range(min = 0, max = 3600), extend(
+        "x-section" = "Advanced",
+        "x-placeholder" = "0 = never"
+    ))]
+    pub clipboard_clear_secs: u64,
+    /// Keep the hotspot's NM profile with autoconnect on, so NetworkManager
+    /// brings the AP back after a reboot, before anyone logs in. Turning it
+    /// off takes effect the next time the hotspot stops.
+    #[schemars(title = "Start at boot (keep the hotspot profile with autoconnect)", extend("x-section" = "Advanced"))]
+    pub autoconnect: bool,
+    /// Print state-changing commands instead of running them (debugging aid)
+    #[schemars(title = "Dry run (print commands instead of running them)", extend("x-section" = "Advanced"))]
+    pub dry_run: bool,
+    /// Take down a client connection on the hotspot interface before starting,
+    /// and bring it back up after stopping
+    #[schemars(title = "Disconnect WiFi client on this adapter when starting", extend("x-section" = "Interfaces"))]
+    pub auto_disconnect_client: bool,
+    /// Keep a USB hotspot adapter from being autosuspended while the hotspot
+    /// runs (through the privileged helper); suspend drops many USB APs
+    #[schemars(title = "Keep USB adapter powered while the hotspot runs", extend("x-section" = "Interfaces"))]
+    pub keep_usb_adapter_powered: bool,
+    /// Wired/USB interface to share the same upstream to ("" = WiFi only)
+    #[schemars(title = "Also share to wired interface", extend("x-section" = "Interfaces", "x-options-from" = "wired_interfaces"))]
+    pub share_wired_interface: String,
+    /// Join `repeater_ssid` on the internet interface (a second WiFi adapter)
+    /// before starting the AP
+    #[schemars(title = "Repeat a WiFi network (internet interface joins it as a client)", extend("x-section" = "Repeater"))]
+    pub repeater_mode: bool,
+    #[schemars(title = "Upstream network", extend(
+        "x-section" = "Repeater",
+        "x-options-from" = "scanned_networks",
+        "x-description" = "Networks seen by the internet interface; refresh to rescan"
+    ))]
+    pub repeater_ssid: String,
+    #[schemars(title = "Upstream password", extend("x-section" = "Repeater", "x-placeholder" = "Leave empty for open networks"))]
+    pub repeater_password: String,
+    /// MAC address or USB vendor:product id of an adapter that starts the
+    /// hotspot when plugged in and stops it when removed ("" = off)
+    #[schemars(title = "Start when this adapter is plugged in", extend(
+        "x-section" = "Interfaces",
+        "x-placeholder" = "MAC or USB id, e.g. 0e8d:7612"
+    ))]
+    pub autostart_adapter: String,
+    /// MAC address of the interfaces the hotspot last started with, by name,
+    /// to find a USB adapter again when it comes back under another name
+    pub interface_macs: BTreeMap<String, String>,
+    /// Stored form of passwords that couldn't be read at load (keyring
+    /// locked, unlock prompt dismissed), by field; `save` writes them back
+    /// untouched unless a new password was entered
+    #[serde(skip)]
+    pub unreadable_secrets: BTreeMap<String, String>,
+}
+
+/// A random password for new installs, the same for every default config
+/// this process builds
+fn default_password() -> String {
+    static PASSWORD: OnceLock<String> = OnceLock::new();
+    PASSWORD.get_or_init(crate::password::generate).clone()
 }
 
 impl Default for Config {
@@ -19,10 +273,295 @@ impl Default for Config {
             internet_interface: "wlan1".to_string(),
             connection_name: "CosmicHotspot".to_string(),
             ssid: "CosmicHotspot".to_string(),
-            password: "changeme123".to_string(),
+            password: default_password(),
+            security: "wpa2".to_string(),
             band: "bg".to_string(),
+            channel: 0,
+            profiles: vec![Profile::default()],
+            active_profile: DEFAULT_PROFILE.to_string(),
+            client_sweep: false,
+            client_sweep_interval_secs: 60,
+            client_grace_secs: 60,
+            toggle_shortcut: String::new(),
+            popup_show_clients: true,
+            popup_show_qr: true,
+            popup_show_stats: true,
+            popup_show_schedule: true,
+            popup_density: "comfortable".to_string(),
+            icon_color: "foreground".to_string(),
+            icon_style: "ripple".to_string(),
+            status_hold_secs: 10,
+            click_action: "popup".to_string(),
+            reduce_motion: false,
+            client_sort: "address".to_string(),
+            client_policies: Vec::new(),
+            known_clients: Vec::new(),
+            verify_after_start: true,
+            check_ssid_collisions: true,
+            failed_join_notify_threshold: 5,
+            slow_activation_secs: 20,
+            dns_query_log: false,
+            local_domain: false,
+            host_resolver_dns: false,
+            repair_sharing: true,
+            quota_period: "off".to_string(),
+            quota_mb: 0,
+            quota_reset_time: "00:00".to_string(),
+            idle_off_mins: 0,
+            clipboard_sensitive: true,
+            clipboard_clear_secs: 0,
+            autoconnect: false,
+            dry_run: false,
+            auto_disconnect_client: false,
+            multicast_to_unicast: false,
+            multicast_rate_mbps: 0,
+            backend: "networkmanager".to_string(),
+            keep_usb_adapter_powered: false,
+            share_wired_interface: String::new(),
+            repeater_mode: false,
+            repeater_ssid: String::new(),
+            repeater_password: String::new(),
+            autostart_adapter: String::new(),
+            interface_macs: BTreeMap::new(),
+            unreadable_secrets: BTreeMap::new(),
+        }
+    }
+}
+
+/// Name of the profile created for new and pre-profile configs
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// Per-profile addressing and sharing policy, so e.g. a "VR" and a "Guest"
+/// profile can use different subnets without re-editing settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    #[schemars(title = "Gateway IP", extend("x-section" = "Profile", "x-placeholder" = "192.168.44.1/24"))]
+    pub gateway_ip: String,
+    /// First/last address handed out by DHCP (both empty = NM's default range)
+    #[schemars(title = "DHCP range start", extend("x-section" = "Profile", "x-placeholder" = "NetworkManager default"))]
+    pub dhcp_range_start: String,
+    #[schemars(title = "DHCP range end", extend("x-section" = "Profile", "x-placeholder" = "NetworkManager default"))]
+    pub dhcp_range_end: String,
+    /// DNS server clients' queries are redirected to, e.g. a Pi-hole on the
+    /// hotspot subnet ("" = the gateway's resolver)
+    #[schemars(title = "DNS server for clients", extend("x-section" = "Profile", "x-placeholder" = "Gateway (default)"))]
+    pub dns_server: String,
+    /// "auto", "nm-shared", "iptables", "nftables", "firewalld" or "ufw"
+    #[schemars(title = "NAT strategy", extend("x-section" = "Profile", "x-options" = [
+        {"value": "auto", "label": "Auto-detect"},
+        {"value": "nm-shared", "label": "NetworkManager shared only"},
+        {"value": "iptables", "label": "Explicit iptables rules"},
+        {"value": "nftables", "label": "Explicit nftables rules"},
+        {"value": "firewalld", "label": "firewalld zone and policy"},
+        {"value": "ufw", "label": "ufw route rules"}
+    ]))]
+    pub nat_strategy: String,
+    /// Stop clients from reaching each other (AP isolation)
+    #[schemars(title = "Isolate clients from each other", extend("x-section" = "Profile"))]
+    pub client_isolation: bool,
+    /// Extra dnsmasq DHCP options, separated by ";", e.g.
+    /// "option:ntp-server,192.168.44.1; option:classless-static-route,10.20.0.0/16,192.168.44.2"
+    #[schemars(title = "Extra DHCP options", extend("x-section" = "Profile", "x-placeholder" = "option:ntp-server,192.168.44.1"))]
+    pub dhcp_options: String,
+    /// Switch to this profile when the autostart adapter is plugged in; if
+    /// several have it, a scheduled one wins, then the first
+    #[schemars(title = "Use for plug-in start", extend("x-section" = "Profile"))]
+    pub autostart: bool,
+    /// Weekdays and daily window the hotspot runs with this profile, e.g.
+    /// "mon-fri" from "09:00" until "17:00" (all empty = no schedule). Where
+    /// windows overlap, the profile listed first wins.
+    #[schemars(title = "Scheduled days", extend("x-section" = "Profile", "x-placeholder" = "e.g. mon-fri"))]
+    pub schedule_days: String,
+    #[schemars(title = "Scheduled from", extend("x-section" = "Profile", "x-placeholder" = "HH:MM"))]
+    pub schedule_from: String,
+    #[schemars(title = "Scheduled until", extend("x-section" = "Profile", "x-placeholder" = "HH:MM"))]
+    pub schedule_until: String,
+    /// Idle auto-off for this profile, in minutes (0 = the general setting)
+    #[schemars(title = "Turn off after minutes without clients", range(min = 0, max = 1440), extend(
+        "x-section" = "Profile",
+        "x-placeholder" = "0 = general setting"
+    ))]
+    pub idle_off_override_mins: u64,
+    /// On battery, turn the hotspot off (and don't start it automatically)
+    /// below this charge (0 = ignore the battery)
+    #[schemars(title = "On battery, turn off below (%)", range(min = 0, max = 100), extend(
+        "x-section" = "Profile",
+        "x-placeholder" = "0 = off"
+    ))]
+    pub battery_min_percent: u8,
+    /// SSID, password, band and interfaces while the profile isn't active
+    /// (`None` for the active one, whose settings are `Config`'s own, and for
+    /// a profile never switched away from, which takes them as they are)
+    #[schemars(skip)]
+    pub network: Option<ProfileNetwork>,
+}
+
+/// The network settings a profile carries with it
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileNetwork {
+    pub ssid: String,
+    pub password: String,
+    pub security: String,
+    pub band: String,
+    pub channel: u32,
+    pub hotspot_interface: String,
+    pub internet_interface: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
             gateway_ip: "192.168.44.1/24".to_string(),
+            dhcp_range_start: String::new(),
+            dhcp_range_end: String::new(),
+            dns_server: String::new(),
+            nat_strategy: "auto".to_string(),
+            client_isolation: false,
+            dhcp_options: String::new(),
+            autostart: false,
+            schedule_days: String::new(),
+            schedule_from: String::new(),
+            schedule_until: String::new(),
+            idle_off_override_mins: 0,
+            battery_min_percent: 0,
+            network: None,
+        }
+    }
+}
+
+impl Profile {
+    /// Check the addresses are well-formed and the DHCP range sits inside the
+    /// gateway's subnet
+    pub fn validate(&self) -> Result<(), String> {
+        let (gateway, prefix) = crate::hotspot::parse_gateway_ip(&self.gateway_ip)
+            .ok_or_else(|| format!("Invalid gateway IP '{}'", self.gateway_ip))?;
+        let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+        let in_subnet = |addr: Ipv4Addr| u32::from(addr) & mask == u32::from(gateway) & mask;
+
+        match (self.dhcp_range_start.trim(), self.dhcp_range_end.trim()) {
+            ("", "") => {}
+            ("", _) | (_, "") => {
+                return Err("DHCP range needs both a first and a last address".to_string())
+            }
+            (start, end) => {
+                let start: Ipv4Addr = start
+                    .parse()
+                    .map_err(|_| format!("Invalid DHCP range start '{start}'"))?;
+                let end: Ipv4Addr = end
+                    .parse()
+                    .map_err(|_| format!("Invalid DHCP range end '{end}'"))?;
+                if !in_subnet(start) || !in_subnet(end) {
+                    return Err(format!("DHCP range must be inside {}", self.gateway_ip));
+                }
+                if start > end {
+                    return Err("DHCP range start must not be after its end".to_string());
+                }
+            }
+        }
+
+        if !self.dns_server.trim().is_empty() {
+            let server: Ipv4Addr = self
+                .dns_server
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid DNS server '{}'", self.dns_server))?;
+            // The helper only redirects DNS to a host on the hotspot's own subnet
+            if !in_subnet(server) {
+                return Err(format!("DNS server must be inside {}", self.gateway_ip));
+            }
+        }
+        if !["auto", "nm-shared", "iptables", "nftables", "firewalld", "ufw"].contains(&self.nat_strategy.as_str()) {
+            return Err(format!("Invalid NAT strategy '{}'", self.nat_strategy));
+        }
+        self.schedule()?;
+        if self.battery_min_percent > 100 {
+            return Err(format!("Invalid battery threshold {}%", self.battery_min_percent));
+        }
+        self.dhcp_options()?;
+        Ok(())
+    }
+
+    /// The extra DHCP options, one dnsmasq `dhcp-option` value each
+    pub fn dhcp_options(&self) -> Result<Vec<String>, String> {
+        self.dhcp_options
+            .split(';')
+            .filter(|o| !o.trim().is_empty())
+            .map(crate::nat::validate_dhcp_option)
+            .collect()
+    }
+
+    /// When the hotspot should run with this profile, or `None` if it has no
+    /// schedule
+    pub fn schedule(&self) -> Result<Option<Schedule>, String> {
+        if [&self.schedule_days, &self.schedule_from, &self.schedule_until]
+            .iter()
+            .all(|s| s.trim().is_empty())
+        {
+            return Ok(None);
         }
+        Schedule::parse(&self.schedule_days, &self.schedule_from, &self.schedule_until).map(Some)
+    }
+}
+
+/// What one client (identified by MAC address) may reach
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ClientPolicy {
+    pub mac: String,
+    /// "full", "no-internet", "web-only" or "block-ports"
+    pub access: String,
+    /// For "block-ports": e.g. "tcp:22, udp:3478"
+    pub blocked_ports: String,
+    /// Days internet access is allowed, e.g. "mon-fri,sun" ("" = every day)
+    pub allowed_days: String,
+    /// Daily window internet access is allowed in, local "HH:MM" (both
+    /// empty = all day)
+    pub allowed_from: String,
+    pub allowed_until: String,
+    /// Queue traffic to this client ahead of other clients' (e.g. a VR
+    /// headset streaming from the PC)
+    pub priority: bool,
+}
+
+impl Default for ClientPolicy {
+    fn default() -> Self {
+        Self {
+            mac: String::new(),
+            access: "full".to_string(),
+            blocked_ports: String::new(),
+            allowed_days: String::new(),
+            allowed_from: String::new(),
+            allowed_until: String::new(),
+            priority: false,
+        }
+    }
+}
+
+impl ClientPolicy {
+    /// The firewall policy the helper enforces for this client, `None` for
+    /// full access
+    pub fn client_access(&self) -> Result<Option<ClientAccess>, String> {
+        if self.access == "full" {
+            return Ok(None);
+        }
+        let ports = (self.access == "block-ports").then_some(self.blocked_ports.as_str());
+        ClientAccess::parse(&self.access, ports).map(Some)
+    }
+
+    /// When the client may use the internet, `None` if always
+    pub fn schedule(&self) -> Result<Option<Schedule>, String> {
+        let schedule = Schedule::parse(&self.allowed_days, &self.allowed_from, &self.allowed_until)?;
+        Ok((schedule.days != [true; 7] || schedule.from != schedule.until).then_some(schedule))
+    }
+
+    /// Whether this policy restricts or prioritizes anything; one that
+    /// doesn't needn't be kept
+    pub fn restricts(&self) -> bool {
+        self.access != "full" || self.priority || matches!(self.schedule(), Ok(Some(_)))
     }
 }
 
@@ -31,11 +570,369 @@ impl Config {
         dirs::config_dir().map(|d| d.join("cosmic-hotspot").join("config.json"))
     }
 
+    /// The access policy set for the client with this MAC address
+    pub fn client_policy(&self, mac: &str) -> Option<&ClientPolicy> {
+        self.client_policies.iter().find(|p| p.mac.eq_ignore_ascii_case(mac))
+    }
+
     pub fn load() -> Self {
-        Self::config_path()
+        let path = Self::config_path();
+        let Some(mut config) = path
+            .as_ref()
             .and_then(|path| std::fs::read_to_string(path).ok())
-            .and_then(|content| serde_json::from_str(&content).ok())
-            .unwrap_or_default()
+            .and_then(|content| Self::from_json(&content).ok())
+        else {
+            let config = Self::default();
+            // First run: keep the generated password for later runs
+            if path.is_some_and(|path| !path.exists()) {
+                if let Err(e) = config.save() {
+                    eprintln!("Failed to save the initial config: {e}");
+                }
+            }
+            return config;
+        };
+
+        // Upgrade migration: plaintext from before encryption, and encrypted
+        // values once a keyring is running, are moved on first load
+        let mut pending = config.unmigrated_secrets();
+        let mut unreadable = BTreeMap::new();
+        for (field, secret) in config.secrets_mut() {
+            match secrets::open(&field, secret) {
+                Ok(plain) => *secret = plain,
+                Err(e) => {
+                    eprintln!("Password in config can't be read: {e}");
+                    unreadable.insert(field, std::mem::take(secret));
+                }
+            }
+        }
+        pending.retain(|field| !unreadable.contains_key(field));
+        config.unreadable_secrets = unreadable;
+        if !pending.is_empty() {
+            match config.save() {
+                Ok(()) => eprintln!(
+                    "Moved {} out of config.json ({})",
+                    pending.join(", "),
+                    if secrets::keyring_available() { "into the keyring" } else { "encrypted" }
+                ),
+                Err(e) => eprintln!("Failed to migrate passwords in config: {e}"),
+            }
+        }
+        config
+    }
+
+    /// Move passwords still written in config.json (plaintext or encrypted)
+    /// into the keyring, leaving markers in the file. Returns the fields moved.
+    pub fn migrate_secrets() -> Result<Vec<String>, String> {
+        let path = Self::config_path().ok_or("Could not determine config path")?;
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Ok(Vec::new());
+        };
+        let mut config = Self::from_json(&content)?;
+        let pending: Vec<String> = config
+            .secrets_mut()
+            .into_iter()
+            .filter(|(_, s)| !s.is_empty() && !secrets::in_keyring(s))
+            .map(|(field, _)| field)
+            .collect();
+        if pending.is_empty() {
+            return Ok(pending);
+        }
+        if !secrets::keyring_available() {
+            return Err("No Secret Service keyring is running; passwords stay encrypted in config.json".to_string());
+        }
+
+        for (field, secret) in config.secrets_mut() {
+            *secret = secrets::open(&field, secret)?;
+        }
+        config.save()?;
+
+        // save() falls back to encryption if the keyring refuses a write
+        let written = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read config: {e}"))?;
+        let mut written = Self::from_json(&written)?;
+        if written.secrets_mut().iter().any(|(_, s)| !s.is_empty() && !secrets::in_keyring(s)) {
+            return Err("The keyring refused some passwords; they stay encrypted in config.json".to_string());
+        }
+        Ok(pending)
+    }
+
+    /// Secrets not yet in their preferred storage: the keyring if one is
+    /// running, otherwise encrypted in the file
+    fn unmigrated_secrets(&mut self) -> Vec<String> {
+        self.secrets_mut()
+            .into_iter()
+            .filter(|(_, s)| {
+                !s.is_empty()
+                    && !secrets::in_keyring(s)
+                    && (!secrets::is_encrypted(s) || secrets::keyring_available())
+            })
+            .map(|(field, _)| field)
+            .collect()
+    }
+
+    /// Fields kept out of config.json in plaintext, by name; inactive
+    /// profiles' passwords are named after their profile
+    fn secrets_mut(&mut self) -> Vec<(String, &mut String)> {
+        let mut fields = vec![
+            ("password".to_string(), &mut self.password),
+            ("repeater_password".to_string(), &mut self.repeater_password),
+        ];
+        for profile in &mut self.profiles {
+            if let Some(network) = &mut profile.network {
+                fields.push((format!("profile:{}:password", profile.name), &mut network.password));
+            }
+        }
+        fields
+    }
+
+    /// Parse a config file, moving pre-profile top-level `gateway_ip` and
+    /// `nat_strategy` into the default profile
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("Invalid config: {e}"))?;
+        let legacy = value.get("profiles").is_none();
+        let mut config: Config =
+            serde_json::from_value(value.clone()).map_err(|e| format!("Invalid config: {e}"))?;
+
+        if legacy {
+            let profile = &mut config.profiles[0];
+            if let Some(gateway_ip) = value.get("gateway_ip").and_then(|v| v.as_str()) {
+                profile.gateway_ip = gateway_ip.to_string();
+            }
+            if let Some(strategy) = value.get("nat_strategy").and_then(|v| v.as_str()) {
+                profile.nat_strategy = strategy.to_string();
+            }
+        }
+        if config.profiles.is_empty() {
+            config.profiles.push(Profile::default());
+        }
+        // Plug-in start used to name one profile; it's now a flag on each
+        let legacy_autostart = value.get("autostart_profile").and_then(|v| v.as_str());
+        if let Some(profile) = config.profiles.iter_mut().find(|p| Some(p.name.as_str()) == legacy_autostart) {
+            profile.autostart = true;
+        }
+        Ok(config)
+    }
+
+    /// The active profile, falling back to the first one if the name is stale
+    pub fn profile(&self) -> &Profile {
+        static FALLBACK: OnceLock<Profile> = OnceLock::new();
+        self.profiles
+            .iter()
+            .find(|p| p.name == self.active_profile)
+            .or(self.profiles.first())
+            .unwrap_or_else(|| FALLBACK.get_or_init(Profile::default))
+    }
+
+    pub fn profile_mut(&mut self) -> &mut Profile {
+        if self.profiles.is_empty() {
+            self.profiles.push(Profile::default());
+        }
+        let idx = self
+            .profiles
+            .iter()
+            .position(|p| p.name == self.active_profile)
+            .unwrap_or(0);
+        &mut self.profiles[idx]
+    }
+
+    /// Make `name` the active profile: the current SSID, password, band and
+    /// interfaces are kept with the profile being left, and the new one's
+    /// replace them
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let target = self
+            .profiles
+            .iter()
+            .position(|p| p.name == name)
+            .ok_or_else(|| format!("No profile named '{name}'"))?;
+        let current = self.profile().name.clone();
+        if current == name {
+            self.active_profile = current;
+            return Ok(());
+        }
+
+        let leaving = self.network();
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == current) {
+            profile.network = Some(leaving);
+        }
+        if let Some(network) = self.profiles[target].network.take() {
+            self.set_network(network);
+        }
+        self.active_profile = name.to_string();
+        Ok(())
+    }
+
+    /// Add a profile named `name` with the active profile's settings
+    pub fn add_profile(&mut self, name: &str) -> Result<(), String> {
+        let name = Self::profile_name(name)?;
+        if self.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("A profile named '{name}' already exists"));
+        }
+        let profile = Profile { name, network: Some(self.network()), ..self.profile().clone() };
+        self.profiles.push(profile);
+        Ok(())
+    }
+
+    /// Remove a profile, switching to the first remaining one if it was active
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        let idx = self
+            .profiles
+            .iter()
+            .position(|p| p.name == name)
+            .ok_or_else(|| format!("No profile named '{name}'"))?;
+        if self.profiles.len() == 1 {
+            return Err("The last profile can't be deleted".to_string());
+        }
+        if self.profile().name == name {
+            let next = self.profiles[if idx == 0 { 1 } else { 0 }].name.clone();
+            self.switch_profile(&next)?;
+        }
+        self.profiles.remove(idx);
+        Ok(())
+    }
+
+    /// Rename a profile, following it in the active profile
+    pub fn rename_profile(&mut self, old: &str, new: &str) -> Result<(), String> {
+        let new = Self::profile_name(new)?;
+        if new != old && self.profiles.iter().any(|p| p.name == new) {
+            return Err(format!("A profile named '{new}' already exists"));
+        }
+        let was_active = self.profile().name == old;
+        let profile = self
+            .profiles
+            .iter_mut()
+            .find(|p| p.name == old)
+            .ok_or_else(|| format!("No profile named '{old}'"))?;
+        profile.name = new.clone();
+        if was_active {
+            self.active_profile = new;
+        }
+        Ok(())
+    }
+
+    fn profile_name(name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name can't be empty".to_string());
+        }
+        Ok(name.to_string())
+    }
+
+    /// The active profile's network settings
+    fn network(&self) -> ProfileNetwork {
+        ProfileNetwork {
+            ssid: self.ssid.clone(),
+            password: self.password.clone(),
+            security: self.security.clone(),
+            band: self.band.clone(),
+            channel: self.channel,
+            hotspot_interface: self.hotspot_interface.clone(),
+            internet_interface: self.internet_interface.clone(),
+        }
+    }
+
+    fn set_network(&mut self, network: ProfileNetwork) {
+        self.ssid = network.ssid;
+        self.password = network.password;
+        self.security = network.security;
+        self.band = network.band;
+        self.channel = network.channel;
+        self.hotspot_interface = network.hotspot_interface;
+        self.internet_interface = network.internet_interface;
+    }
+
+    /// Whether clients' DNS queries reach the hotspot's own dnsmasq, i.e.
+    /// the active profile doesn't redirect them to another server
+    pub fn uses_gateway_dns(&self) -> bool {
+        self.profile().dns_server.trim().is_empty()
+    }
+
+    /// Whether client names should resolve under `.hotspot`
+    pub fn serves_local_domain(&self) -> bool {
+        self.local_domain && self.uses_gateway_dns()
+    }
+
+    /// Minutes without clients before the hotspot turns off (0 = never): the
+    /// active profile's own setting, or the general one
+    pub fn idle_timeout_mins(&self) -> u64 {
+        match self.profile().idle_off_override_mins {
+            0 => self.idle_off_mins,
+            mins => mins,
+        }
+    }
+
+    /// Whether the gateway's dnsmasq should forward through the host's resolver
+    pub fn uses_host_resolver(&self) -> bool {
+        self.host_resolver_dns && self.uses_gateway_dns()
+    }
+
+    /// Follow interfaces renamed since the last start (e.g. a USB adapter
+    /// replugged as another wlx… name): a missing hotspot or internet
+    /// interface is replaced by whichever interface now has its recorded
+    /// MAC, and present ones have their MAC recorded. `mac_of` reads an
+    /// interface's MAC and `find_mac` finds the interface with one. Returns
+    /// the renames, old name first.
+    pub fn resolve_interfaces(
+        &mut self,
+        mac_of: impl Fn(&str) -> Option<String>,
+        find_mac: impl Fn(&str) -> Option<String>,
+    ) -> Vec<(String, String)> {
+        let mut renames = Vec::new();
+        for name in [&mut self.hotspot_interface, &mut self.internet_interface] {
+            if name.is_empty() {
+                continue;
+            }
+            let mac = match mac_of(name) {
+                Some(mac) => mac,
+                None => {
+                    let Some(mac) = self.interface_macs.get(name.as_str()).cloned() else {
+                        continue;
+                    };
+                    let Some(renamed) = find_mac(&mac) else {
+                        continue;
+                    };
+                    renames.push((name.clone(), renamed.clone()));
+                    *name = renamed;
+                    mac
+                }
+            };
+            // One name per adapter, so the map doesn't grow with each replug
+            self.interface_macs.retain(|_, recorded| *recorded != mac);
+            self.interface_macs.insert(name.clone(), mac);
+        }
+        renames
+    }
+
+    /// Fingerprint of the settings applied when the hotspot starts. A running
+    /// hotspot started with a different one uses old settings until restarted.
+    ///
+    /// FNV-1a rather than `DefaultHasher`, so the applet and the settings
+    /// hub (built separately) agree; a hash, so no password is written out.
+    pub fn start_fingerprint(&self) -> u64 {
+        let applied = serde_json::json!([
+            self.hotspot_interface,
+            self.internet_interface,
+            self.connection_name,
+            self.ssid,
+            self.password,
+            self.security,
+            self.band,
+            self.channel,
+            self.profile(),
+            self.dns_query_log,
+            self.serves_local_domain(),
+            self.uses_host_resolver(),
+            self.multicast_to_unicast,
+            self.multicast_rate_mbps,
+            self.backend,
+            self.share_wired_interface,
+            self.repeater_mode,
+            self.repeater_ssid,
+            self.repeater_password,
+        ]);
+        applied
+            .to_string()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
     }
 
     pub fn save(&self) -> Result<(), String> {
@@ -47,7 +944,22 @@ impl Config {
                 .map_err(|e| format!("Failed to create config dir: {e}"))?;
         }
 
-        let content = serde_json::to_string_pretty(self)
+        // Passwords unchanged since the file was written keep their marker
+        let mut previous = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| Self::from_json(&content).ok())
+            .unwrap_or_default();
+        let previous: BTreeMap<String, String> =
+            previous.secrets_mut().into_iter().map(|(field, s)| (field, s.clone())).collect();
+
+        let mut stored = self.clone();
+        for (field, secret) in stored.secrets_mut() {
+            *secret = match self.unreadable_secrets.get(&field) {
+                Some(original) if secret.is_empty() => original.clone(),
+                _ => secrets::reseal(&field, secret, previous.get(&field).map_or("", String::as_str))?,
+            };
+        }
+        let content = serde_json::to_string_pretty(&stored)
             .map_err(|e| format!("Failed to serialize config: {e}"))?;
 
         std::fs::write(path, content)
@@ -55,4 +967,119 @@ impl Config {
 
         Ok(())
     }
+
+    /// Back up the saved config and replace it with the defaults. Returns the
+    /// backup's path, or `None` if nothing had been saved yet.
+    pub fn reset_to_defaults() -> Result<Option<PathBuf>, String> {
+        let backup = Self::backup()?;
+        Self::default().save()?;
+        Ok(backup)
+    }
+
+    /// Copy the saved config file to a timestamped backup next to it, keeping
+    /// the newest `MAX_BACKUPS`. Returns `None` if nothing was saved yet.
+    ///
+    /// Keyring markers would point at items the next save overwrites, so the
+    /// backup holds the passwords themselves, encrypted with the config key.
+    pub fn backup() -> Result<Option<PathBuf>, String> {
+        let path = Self::config_path().ok_or("Could not determine config path")?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut stamp = crate::run_state::now();
+        let backup = loop {
+            let backup = path.with_file_name(format!("{BACKUP_PREFIX}{stamp}.json"));
+            if !backup.exists() {
+                break backup;
+            }
+            stamp += 1;
+        };
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read config: {e}"))?;
+        let content = match Self::from_json(&content) {
+            Ok(mut config) => {
+                for (field, secret) in config.secrets_mut() {
+                    // One that can't be read now is kept as stored
+                    if let Ok(plain) = secrets::open(&field, secret) {
+                        *secret = secrets::encrypt(&plain)?;
+                    }
+                }
+                serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to serialize config: {e}"))?
+            }
+            // A file that doesn't parse is still worth keeping as it was
+            Err(_) => content,
+        };
+        std::fs::write(&backup, content).map_err(|e| format!("Failed to back up config: {e}"))?;
+
+        let backups = Self::backups();
+        for old in backups.iter().take(backups.len().saturating_sub(MAX_BACKUPS)) {
+            let _ = std::fs::remove_file(old);
+        }
+        Ok(Some(backup))
+    }
+
+    /// Config backups, oldest first
+    pub fn backups() -> Vec<PathBuf> {
+        let Some(dir) = Self::config_path().and_then(|p| p.parent().map(Path::to_path_buf)) else {
+            return Vec::new();
+        };
+        let mut backups: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let stamp = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".json")?.parse().ok()?;
+                Some((stamp, entry.path()))
+            })
+            .collect();
+        backups.sort();
+        backups.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Put a backup back as the saved config: `path`, or the newest backup.
+    /// Returns the backup restored.
+    pub fn restore_backup(path: Option<&Path>) -> Result<PathBuf, String> {
+        let backup = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::backups().pop().ok_or("No config backup to restore")?,
+        };
+        let content = std::fs::read_to_string(&backup)
+            .map_err(|e| format!("Failed to read {}: {e}", backup.display()))?;
+        // Refuse anything that wouldn't load, rather than silently falling
+        // back to the defaults
+        Self::from_json(&content)?;
+        let target = Self::config_path().ok_or("Could not determine config path")?;
+        std::fs::write(target, content).map_err(|e| format!("Failed to write config: {e}"))?;
+        Ok(backup)
+    }
+}
+
+/// File name prefix of config backups, followed by the Unix time
+const BACKUP_PREFIX: &str = "config.backup-";
+/// Backups kept; older ones are removed when a new one is made
+const MAX_BACKUPS: usize = 5;
+
+/// Longest connection name accepted; NM allows more, but it has to fit in the UI
+const MAX_CONNECTION_NAME_LEN: usize = 64;
+
+/// Normalize a NetworkManager connection name.
+///
+/// Spaces, quotes and shell metacharacters are fine — names are only ever
+/// passed as separate argv entries, never through a shell. What's rejected is
+/// what breaks nmcli itself: empty names, control characters (which corrupt
+/// terse output), and overly long names. Surrounding whitespace is trimmed.
+pub fn normalize_connection_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Connection name must not be empty".to_string());
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Connection name must not contain control characters".to_string());
+    }
+    if name.chars().count() > MAX_CONNECTION_NAME_LEN {
+        return Err(format!(
+            "Connection name must be at most {MAX_CONNECTION_NAME_LEN} characters"
+        ));
+    }
+    Ok(name.to_string())
 }
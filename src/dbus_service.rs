@@ -0,0 +1,147 @@
+//! Session D-Bus service of the running applet, for scripts and other panels.
+//!
+//! Owned by the applet process while it runs; unlike the system helper
+//! service it needs no authorization, as it only reads the user's own state.
+//!
+//! Besides the methods, the hotspot's state is published as properties
+//! (Active, Ssid, ClientCount, RxBytes, TxBytes, Uptime) with
+//! PropertiesChanged signals, so other panels and widgets can follow it.
+
+use std::sync::{Arc, Mutex};
+
+use zbus::interface;
+
+use crate::clients::{ClientInfo, WireClient};
+use crate::event_log;
+
+// Distinct from the system helper's name and path, so the two never get mixed up
+pub const BUS_NAME: &str = "io.github.reality2_roycdavies.CosmicHotspot1.Applet";
+pub const OBJECT_PATH: &str = "/io/github/reality2_roycdavies/CosmicHotspot1/Applet";
+const INTERFACE: &str = "io.github.reality2_roycdavies.CosmicHotspot1.Applet";
+
+/// Most entries `GetRecentLogs` returns in one call
+const MAX_LOG_ENTRIES: u32 = 1000;
+
+/// The client list the background loop last polled, shared with the service
+pub type SharedClients = Arc<Mutex<Vec<ClientInfo>>>;
+
+/// What the properties report, as of the background loop's last poll
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub active: bool,
+    pub ssid: String,
+    pub client_count: u32,
+    /// Byte counters of the hotspot interface
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// Seconds since the hotspot started; 0 while it's off or unknown
+    pub uptime: u64,
+}
+
+struct Applet {
+    clients: SharedClients,
+    status: Status,
+}
+
+#[interface(name = "io.github.reality2_roycdavies.CosmicHotspot1.Applet")]
+impl Applet {
+    /// The last `count` event log entries, oldest first, as
+    /// (unix time, level, message)
+    fn get_recent_logs(&self, count: u32) -> Vec<(u64, String, String)> {
+        event_log::recent(count.min(MAX_LOG_ENTRIES) as usize)
+            .into_iter()
+            .map(|entry| (entry.at, entry.level.as_str().to_string(), entry.message))
+            .collect()
+    }
+
+    /// Clients of the running hotspot as (ip, mac, hostname, state, first
+    /// seen as unix time); unknown strings are empty and an unknown time is 0
+    fn get_clients(&self) -> Vec<WireClient> {
+        self.clients
+            .lock()
+            .map(|clients| clients.iter().map(ClientInfo::to_wire).collect())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    fn active(&self) -> bool {
+        self.status.active
+    }
+
+    #[zbus(property)]
+    fn ssid(&self) -> String {
+        self.status.ssid.clone()
+    }
+
+    #[zbus(property)]
+    fn client_count(&self) -> u32 {
+        self.status.client_count
+    }
+
+    #[zbus(property)]
+    fn rx_bytes(&self) -> u64 {
+        self.status.rx_bytes
+    }
+
+    #[zbus(property)]
+    fn tx_bytes(&self) -> u64 {
+        self.status.tx_bytes
+    }
+
+    #[zbus(property)]
+    fn uptime(&self) -> u64 {
+        self.status.uptime
+    }
+}
+
+/// Claim the bus name on the session bus; the service runs until the
+/// returned connection is dropped
+pub async fn serve(clients: SharedClients) -> zbus::Result<zbus::Connection> {
+    zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Applet { clients, status: Status::default() })?
+        .build()
+        .await
+}
+
+/// Update the properties to `status`, signalling the ones that changed
+pub async fn publish(conn: &zbus::Connection, status: Status) -> zbus::Result<()> {
+    let iface = conn.object_server().interface::<_, Applet>(OBJECT_PATH).await?;
+    let mut applet = iface.get_mut().await;
+    let previous = std::mem::replace(&mut applet.status, status);
+    let current = &applet.status;
+    let emitter = iface.signal_emitter();
+    if previous.active != current.active {
+        applet.active_changed(emitter).await?;
+    }
+    if previous.ssid != current.ssid {
+        applet.ssid_changed(emitter).await?;
+    }
+    if previous.client_count != current.client_count {
+        applet.client_count_changed(emitter).await?;
+    }
+    if previous.rx_bytes != current.rx_bytes {
+        applet.rx_bytes_changed(emitter).await?;
+    }
+    if previous.tx_bytes != current.tx_bytes {
+        applet.tx_bytes_changed(emitter).await?;
+    }
+    if previous.uptime != current.uptime {
+        applet.uptime_changed(emitter).await?;
+    }
+    Ok(())
+}
+
+/// The running applet's client list, for the CLI
+pub fn fetch_clients() -> zbus::Result<Vec<ClientInfo>> {
+    let conn = zbus::blocking::Connection::session()?;
+    let reply = conn.call_method(
+        Some(BUS_NAME),
+        OBJECT_PATH,
+        Some(INTERFACE),
+        "GetClients",
+        &(),
+    )?;
+    let clients: Vec<WireClient> = reply.body().deserialize()?;
+    Ok(clients.into_iter().map(ClientInfo::from_wire).collect())
+}
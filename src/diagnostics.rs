@@ -0,0 +1,204 @@
+//! Connectivity verification for a running hotspot.
+//!
+//! Walks the sharing chain in order — AP up, DHCP serving, forwarding
+//! enabled, NAT rule present, outbound traffic from the hotspot subnet — so a
+//! failure can name the exact broken link instead of a generic error. The
+//! pre-flight checks cover what must hold before a start is even attempted.
+
+use std::process::Command;
+use std::time::Duration;
+
+use crate::adapters;
+use crate::config::Config;
+use crate::hotspot;
+use crate::nat::NatRequest;
+use crate::nm_dbus;
+use crate::privileged;
+
+/// Address probed to confirm outbound connectivity
+const PROBE_TARGET: &str = "1.1.1.1";
+/// NM starts dnsmasq asynchronously after activation
+const DHCP_WAIT_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, passed: true, detail: detail.into() }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, passed: false, detail: detail.into() }
+    }
+}
+
+/// Run every link check in chain order
+pub fn verify_connectivity(config: &Config) -> Vec<Check> {
+    vec![
+        check_access_point(config),
+        check_dhcp(config),
+        check_ip_forward(),
+        check_nat_rule(config),
+        check_outbound(config),
+    ]
+}
+
+/// Everything a start depends on, checked without touching the hotspot
+pub fn preflight(config: &Config) -> Vec<Check> {
+    vec![
+        check_settings(config),
+        check_network_manager(),
+        check_adapter(config),
+        check_adapter_free(config),
+        check_upstream(config),
+    ]
+}
+
+/// The first failing check, if any
+pub fn first_failure(checks: &[Check]) -> Option<&Check> {
+    checks.iter().find(|c| !c.passed)
+}
+
+fn check_settings(config: &Config) -> Check {
+    match config.profile().validate() {
+        Ok(()) => Check::pass("Settings", format!("profile '{}' is valid", config.active_profile)),
+        Err(e) => Check::fail("Settings", e),
+    }
+}
+
+fn check_network_manager() -> Check {
+    if nm_dbus::running() {
+        Check::pass("NetworkManager", "running")
+    } else {
+        Check::fail("NetworkManager", "not running")
+    }
+}
+
+fn check_adapter(config: &Config) -> Check {
+    let interface = &config.hotspot_interface;
+    if !std::path::Path::new("/sys/class/net").join(interface).exists() {
+        return Check::fail("WiFi adapter", format!("{interface} not found"));
+    }
+    if adapters::probe(interface).ap_capable {
+        Check::pass("WiFi adapter", format!("{interface} supports AP mode"))
+    } else {
+        Check::fail("WiFi adapter", format!("{interface} can't host an access point"))
+    }
+}
+
+fn check_adapter_free(config: &Config) -> Check {
+    match hotspot::client_connection_on(&config.hotspot_interface, &config.connection_name) {
+        None => Check::pass("Adapter free", format!("{} is not in use", config.hotspot_interface)),
+        Some(connection) if config.auto_disconnect_client => Check::pass(
+            "Adapter free",
+            format!("'{connection}' will be disconnected on start"),
+        ),
+        Some(connection) => Check::fail(
+            "Adapter free",
+            format!("{} is connected to '{connection}'", config.hotspot_interface),
+        ),
+    }
+}
+
+fn check_upstream(config: &Config) -> Check {
+    let interface = &config.internet_interface;
+    if config.repeater_mode {
+        return Check::pass("Upstream", format!("{interface} joins '{}' on start", config.repeater_ssid));
+    }
+    match hotspot::client_connection_on(interface, &config.connection_name) {
+        Some(connection) => Check::pass("Upstream", format!("{interface} connected via '{connection}'")),
+        None => Check::fail("Upstream", format!("{interface} is not connected")),
+    }
+}
+
+fn check_access_point(config: &Config) -> Check {
+    if hotspot::is_hotspot_active(config) {
+        Check::pass("Access point", format!("'{}' activated", config.connection_name))
+    } else {
+        Check::fail("Access point", format!("'{}' is not activated", config.connection_name))
+    }
+}
+
+fn check_dhcp(config: &Config) -> Check {
+    let Some((gateway, _)) = hotspot::parse_gateway_ip(&config.profile().gateway_ip) else {
+        return Check::fail("DHCP server", format!("invalid gateway IP '{}'", config.profile().gateway_ip));
+    };
+    let listen = format!("--listen-address={gateway}");
+
+    for attempt in 0..DHCP_WAIT_ATTEMPTS {
+        if dnsmasq_running_with(&listen) {
+            return Check::pass("DHCP server", format!("dnsmasq serving on {gateway}"));
+        }
+        if attempt + 1 < DHCP_WAIT_ATTEMPTS {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+    Check::fail("DHCP server", format!("no dnsmasq listening on {gateway}"))
+}
+
+/// Look for a dnsmasq process whose command line contains `arg`
+fn dnsmasq_running_with(arg: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        std::fs::read(entry.path().join("cmdline"))
+            .map(|raw| {
+                let cmdline = String::from_utf8_lossy(&raw);
+                let mut args = cmdline.split('\0');
+                args.next().is_some_and(|exe| exe.ends_with("dnsmasq"))
+                    && args.any(|a| a == arg)
+            })
+            .unwrap_or(false)
+    })
+}
+
+fn check_ip_forward() -> Check {
+    match std::fs::read_to_string("/proc/sys/net/ipv4/ip_forward") {
+        Ok(value) if value.trim() == "1" => Check::pass("IP forwarding", "enabled"),
+        Ok(_) => Check::fail("IP forwarding", "net.ipv4.ip_forward is 0"),
+        Err(e) => Check::fail("IP forwarding", format!("cannot read sysctl: {e}")),
+    }
+}
+
+fn check_nat_rule(config: &Config) -> Check {
+    let Some(firewall) = hotspot::strategy_firewall(config) else {
+        return Check::pass("NAT rule", "managed by NetworkManager (not verified)");
+    };
+
+    let request = NatRequest::Check {
+        hotspot: config.hotspot_interface.clone(),
+        internet: config.internet_interface.clone(),
+        firewall,
+    };
+    match privileged::run(&request, config.dry_run) {
+        Ok(()) => Check::pass("NAT rule", format!("{} rules present", firewall.as_str())),
+        Err(e) => Check::fail("NAT rule", format!("{} rules missing: {e}", firewall.as_str())),
+    }
+}
+
+/// Ping out with the gateway address as source, so the packet takes the same
+/// NAT path as traffic from a hotspot client
+fn check_outbound(config: &Config) -> Check {
+    let Some((gateway, _)) = hotspot::parse_gateway_ip(&config.profile().gateway_ip) else {
+        return Check::fail("Internet probe", format!("invalid gateway IP '{}'", config.profile().gateway_ip));
+    };
+    let gateway = gateway.to_string();
+
+    let ok = Command::new("ping")
+        .args(["-c", "1", "-W", "3", "-q", "-I", &gateway, PROBE_TARGET])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+
+    if ok {
+        Check::pass("Internet probe", format!("{PROBE_TARGET} reachable from {gateway}"))
+    } else {
+        Check::fail("Internet probe", format!("{PROBE_TARGET} unreachable from {gateway}"))
+    }
+}
@@ -0,0 +1,89 @@
+//! Recent DNS lookups by hotspot clients, read from the system journal.
+//!
+//! NetworkManager answers DNS for shared connections with its own dnsmasq.
+//! With `log-queries` dropped into dnsmasq's config directory (the helper's
+//! `query-log` request) it logs `query[A] example.com from 10.42.0.23` for
+//! every lookup. That records every name a client looks up, so it's opt-in.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::nat;
+
+/// One lookup from the log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookup {
+    pub client: String,
+    pub name: String,
+}
+
+/// The names one client looked up, most recent first, with how often
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientLookups {
+    pub client: String,
+    pub names: Vec<(String, usize)>,
+}
+
+/// Queries in dnsmasq's log, in order
+pub fn parse_queries(log: &str) -> Vec<Lookup> {
+    log.lines()
+        .filter_map(|line| {
+            let rest = &line[line.find("query[")? + "query[".len()..];
+            let (_kind, rest) = rest.split_once("] ")?;
+            let mut words = rest.split_whitespace();
+            let name = words.next()?;
+            let client = match (words.next(), words.next()) {
+                (Some("from"), Some(client)) => client,
+                _ => return None,
+            };
+            Some(Lookup {
+                client: client.to_string(),
+                name: name.to_lowercase(),
+            })
+        })
+        .collect()
+}
+
+/// Lookups grouped per client, the most recently active client first. Each
+/// keeps its `limit` most recent distinct names.
+pub fn by_client(lookups: &[Lookup], limit: usize) -> Vec<ClientLookups> {
+    // Newest first, so clients and names come out in order of last use
+    let mut groups: Vec<ClientLookups> = Vec::new();
+    for lookup in lookups.iter().rev() {
+        let index = match groups.iter().position(|g| g.client == lookup.client) {
+            Some(index) => index,
+            None => {
+                groups.push(ClientLookups { client: lookup.client.clone(), names: Vec::new() });
+                groups.len() - 1
+            }
+        };
+        let names = &mut groups[index].names;
+        match names.iter_mut().find(|(name, _)| *name == lookup.name) {
+            Some((_, count)) => *count += 1,
+            None => names.push((lookup.name.clone(), 1)),
+        }
+    }
+    for group in &mut groups {
+        group.names.truncate(limit);
+    }
+    groups
+}
+
+/// Whether dnsmasq is set up to log queries (from the next hotspot start)
+pub fn is_enabled() -> bool {
+    std::path::Path::new(nat::QUERY_LOG_CONF).exists()
+}
+
+/// Lookups logged since `since`. Empty if the journal isn't readable (the
+/// user needs to be in the systemd-journal or adm group).
+pub fn recent_lookups(since: SystemTime) -> Vec<Lookup> {
+    let since = since.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let Ok(output) = Command::new("journalctl")
+        .args(["-t", "dnsmasq", "-o", "cat", "-q", "--no-pager"])
+        .arg(format!("--since=@{since}"))
+        .output()
+    else {
+        return Vec::new();
+    };
+    parse_queries(&String::from_utf8_lossy(&output.stdout))
+}
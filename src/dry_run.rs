@@ -0,0 +1,11 @@
+//! Dry-run mode: print the commands and D-Bus calls that would change the
+//! system instead of running them.
+//!
+//! Read-only queries (device lists, connection state, neighbour table) still
+//! run normally so the printed plan reflects the real machine. Callers pass
+//! the flag along from `Config::dry_run`.
+
+/// Print a planned action in a uniform format
+pub fn report(action: &str) {
+    println!("[dry-run] {action}");
+}
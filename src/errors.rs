@@ -0,0 +1,165 @@
+//! User-facing error catalog.
+//!
+//! Backend failures are reported as a `HotspotError`: a category that maps to
+//! a short, translated message for the status line, plus the raw technical
+//! detail (NetworkManager's D-Bus error, a helper's stderr etc.) for the log and the
+//! popup's details section.
+
+use std::fmt;
+
+use crate::nm_dbus::NmError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// NetworkManager couldn't be reached at all
+    NetworkManagerUnavailable,
+    /// NetworkManager rejected the new profile
+    CreateFailed,
+    /// The profile exists but activation failed
+    ActivationFailed,
+    /// The adapter is missing, busy or can't do AP mode
+    DeviceUnavailable,
+    /// The adapter is connected to a network as a client; detail is the connection name
+    InterfaceBusy,
+    /// Another tool's hotspot is active on the adapter; detail is its connection name
+    ForeignHotspot,
+    /// A nearby network already broadcasts the configured SSID
+    SsidInUse,
+    /// Repeater mode couldn't join the upstream network
+    UpstreamFailed,
+    /// A setting value was rejected
+    InvalidSetting,
+    /// The config file couldn't be written
+    SaveFailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotspotError {
+    pub kind: ErrorKind,
+    /// Technical detail, untranslated
+    pub detail: String,
+}
+
+impl HotspotError {
+    pub fn new(kind: ErrorKind, detail: impl Into<String>) -> Self {
+        Self { kind, detail: detail.into().trim().to_string() }
+    }
+
+    /// Classify a failed NetworkManager call: NM unreachable, a problem with
+    /// the device, or else `otherwise`
+    pub fn from_nm(error: NmError, otherwise: ErrorKind) -> Self {
+        let kind = match &error {
+            NmError::Unavailable(_) => ErrorKind::NetworkManagerUnavailable,
+            e if e.is_device_problem() => ErrorKind::DeviceUnavailable,
+            _ => otherwise,
+        };
+        Self::new(kind, error.to_string())
+    }
+
+    /// Short message in the user's language
+    pub fn message(&self) -> &'static str {
+        message(&language(), self.kind)
+    }
+}
+
+/// Localized message with the technical detail appended, for terminals and logs
+impl fmt::Display for HotspotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.detail.is_empty() {
+            write!(f, "{}", self.message())
+        } else {
+            write!(f, "{} ({})", self.message(), self.detail)
+        }
+    }
+}
+
+/// Two-letter language code from the usual locale variables
+fn language() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+        .map(|v| v.chars().take(2).collect::<String>().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn message(lang: &str, kind: ErrorKind) -> &'static str {
+    match lang {
+        "de" => german(kind),
+        "es" => spanish(kind),
+        "fr" => french(kind),
+        _ => english(kind),
+    }
+}
+
+fn english(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NetworkManagerUnavailable => "NetworkManager isn't available",
+        ErrorKind::CreateFailed => "Couldn't create the hotspot; check SSID, password and band",
+        ErrorKind::ActivationFailed => "The hotspot couldn't be started",
+        ErrorKind::DeviceUnavailable => "The WiFi adapter is busy, missing or can't host a hotspot",
+        ErrorKind::InterfaceBusy => "The WiFi adapter is connected to another network",
+        ErrorKind::ForeignHotspot => "Another hotspot is already active on the WiFi adapter",
+        ErrorKind::SsidInUse => "A nearby network already uses this hotspot name",
+        ErrorKind::UpstreamFailed => "Couldn't connect to the upstream network",
+        ErrorKind::InvalidSetting => "That value isn't valid for this setting",
+        ErrorKind::SaveFailed => "Settings couldn't be saved",
+    }
+}
+
+fn german(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NetworkManagerUnavailable => "NetworkManager ist nicht verfügbar",
+        ErrorKind::CreateFailed => {
+            "Hotspot konnte nicht angelegt werden; SSID, Passwort und Band prüfen"
+        }
+        ErrorKind::ActivationFailed => "Der Hotspot konnte nicht gestartet werden",
+        ErrorKind::DeviceUnavailable => {
+            "Der WLAN-Adapter ist belegt, fehlt oder unterstützt keinen Hotspot"
+        }
+        ErrorKind::InterfaceBusy => "Der WLAN-Adapter ist mit einem anderen Netzwerk verbunden",
+        ErrorKind::ForeignHotspot => "Auf dem WLAN-Adapter läuft bereits ein anderer Hotspot",
+        ErrorKind::SsidInUse => "Ein Netzwerk in der Nähe verwendet bereits diesen Hotspot-Namen",
+        ErrorKind::UpstreamFailed => "Verbindung zum Upstream-Netzwerk fehlgeschlagen",
+        ErrorKind::InvalidSetting => "Dieser Wert ist für diese Einstellung ungültig",
+        ErrorKind::SaveFailed => "Einstellungen konnten nicht gespeichert werden",
+    }
+}
+
+fn spanish(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NetworkManagerUnavailable => "NetworkManager no está disponible",
+        ErrorKind::CreateFailed => {
+            "No se pudo crear el punto de acceso; revisa el SSID, la contraseña y la banda"
+        }
+        ErrorKind::ActivationFailed => "No se pudo iniciar el punto de acceso",
+        ErrorKind::DeviceUnavailable => {
+            "El adaptador WiFi está ocupado, no está presente o no admite punto de acceso"
+        }
+        ErrorKind::InterfaceBusy => "El adaptador WiFi está conectado a otra red",
+        ErrorKind::ForeignHotspot => "Ya hay otro punto de acceso activo en el adaptador WiFi",
+        ErrorKind::SsidInUse => "Una red cercana ya usa este nombre de punto de acceso",
+        ErrorKind::UpstreamFailed => "No se pudo conectar a la red de origen",
+        ErrorKind::InvalidSetting => "Ese valor no es válido para este ajuste",
+        ErrorKind::SaveFailed => "No se pudieron guardar los ajustes",
+    }
+}
+
+fn french(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NetworkManagerUnavailable => "NetworkManager n'est pas disponible",
+        ErrorKind::CreateFailed => {
+            "Impossible de créer le point d'accès ; vérifiez le SSID, le mot de passe et la bande"
+        }
+        ErrorKind::ActivationFailed => "Le point d'accès n'a pas pu démarrer",
+        ErrorKind::DeviceUnavailable => {
+            "L'adaptateur WiFi est occupé, absent ou ne peut pas servir de point d'accès"
+        }
+        ErrorKind::InterfaceBusy => "L'adaptateur WiFi est connecté à un autre réseau",
+        ErrorKind::ForeignHotspot => "Un autre point d'accès est déjà actif sur l'adaptateur WiFi",
+        ErrorKind::SsidInUse => "Un réseau à proximité utilise déjà ce nom de point d'accès",
+        ErrorKind::UpstreamFailed => "Impossible de se connecter au réseau amont",
+        ErrorKind::InvalidSetting => "Cette valeur n'est pas valide pour ce paramètre",
+        ErrorKind::SaveFailed => "Les paramètres n'ont pas pu être enregistrés",
+    }
+}
@@ -0,0 +1,129 @@
+//! Structured history of what the hotspot did: starts, stops, failures and
+//! repairs, one JSON object per line in the user's state dir.
+//!
+//! Anything can append to it (the applet, the CLI, a shortcut); the CLI's
+//! `--logs` and the session D-Bus service's `GetRecentLogs` read it back, so
+//! nobody needs to know where it lives. When it grows past `MAX_BYTES` it's
+//! rotated to `events.jsonl.1`, keeping one previous file.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::run_state;
+
+/// Size at which the log is rotated
+pub const MAX_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unix time
+    pub at: u64,
+    pub level: Level,
+    pub message: String,
+}
+
+impl Entry {
+    /// "2026-03-14 09:26:53 [error] message", in local time
+    pub fn format(&self) -> String {
+        format!("{} [{}] {}", local_timestamp(self.at), self.level.as_str(), self.message)
+    }
+}
+
+/// Append an entry; failures to write are only reported on stderr
+pub fn record(level: Level, message: impl Into<String>) {
+    let entry = Entry { at: run_state::now(), level, message: message.into() };
+    if let Err(e) = append(&entry) {
+        eprintln!("Failed to write the event log: {e}");
+    }
+}
+
+/// The last `count` entries, oldest first
+pub fn recent(count: usize) -> Vec<Entry> {
+    let Some(path) = log_path() else {
+        return Vec::new();
+    };
+    let mut entries = parse(&std::fs::read_to_string(path.with_extension("jsonl.1")).unwrap_or_default());
+    entries.extend(parse(&std::fs::read_to_string(&path).unwrap_or_default()));
+    let skip = entries.len().saturating_sub(count);
+    entries.split_off(skip)
+}
+
+/// Entries appended after byte `offset` of the current file, and the offset
+/// to continue from. A file shorter than `offset` was rotated, so it's all new.
+pub fn read_from(offset: u64) -> (Vec<Entry>, u64) {
+    let contents = log_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    let start = if contents.len() as u64 >= offset { offset as usize } else { 0 };
+    // Only whole lines; a line still being written is picked up next time
+    let end = contents.rfind('\n').map_or(0, |i| i + 1).max(start);
+    (parse(contents.get(start..end).unwrap_or_default()), end as u64)
+}
+
+/// Entries in a log file; lines that don't parse are skipped
+pub fn parse(contents: &str) -> Vec<Entry> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn append(entry: &Entry) -> Result<(), String> {
+    let path = log_path().ok_or("No state directory")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_BYTES) {
+        std::fs::rename(&path, path.with_extension("jsonl.1")).map_err(|e| e.to_string())?;
+    }
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{line}").map_err(|e| e.to_string())
+}
+
+fn log_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("cosmic-hotspot").join("events.jsonl"))
+}
+
+fn local_timestamp(at: u64) -> String {
+    let time = at as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return at.to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
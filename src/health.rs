@@ -0,0 +1,102 @@
+//! Connection sharing health monitor.
+//!
+//! Firewall reloads, VPN clients and Docker restarts routinely reset
+//! `net.ipv4.ip_forward` or flush the NAT rules the helper added, and clients
+//! then silently lose internet. While the hotspot runs with explicit rules,
+//! the applet checks them now and then and re-applies them through the
+//! helper. A repair that keeps being needed means something keeps undoing
+//! them, which is worth telling the user about.
+
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::hotspot;
+use crate::nat::NatRequest;
+use crate::privileged;
+
+const IP_FORWARD: &str = "/proc/sys/net/ipv4/ip_forward";
+/// Repairs within `RECURRING_WINDOW` that count as recurring
+pub const RECURRING_REPAIRS: usize = 3;
+pub const RECURRING_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    ForwardingOff,
+    NatRulesMissing,
+}
+
+impl Problem {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ForwardingOff => "IP forwarding was turned off",
+            Self::NatRulesMissing => "NAT rules were removed",
+        }
+    }
+}
+
+/// Whether the contents of the ip_forward sysctl mean it's on
+pub fn forwarding_enabled(value: &str) -> bool {
+    value.trim() == "1"
+}
+
+/// What's broken about the running hotspot's sharing. Only explicit rules
+/// are checked: with NM shared mode alone there's nothing the helper added.
+pub fn check(config: &Config) -> Vec<Problem> {
+    let Some(firewall) = hotspot::strategy_firewall(config) else {
+        return Vec::new();
+    };
+    let mut problems = Vec::new();
+    if std::fs::read_to_string(IP_FORWARD).is_ok_and(|value| !forwarding_enabled(&value)) {
+        problems.push(Problem::ForwardingOff);
+    }
+    let request = NatRequest::Check {
+        hotspot: config.hotspot_interface.clone(),
+        internet: config.internet_interface.clone(),
+        firewall,
+    };
+    if privileged::run(&request, config.dry_run).is_err() {
+        problems.push(Problem::NatRulesMissing);
+    }
+    problems
+}
+
+/// Re-apply the sharing setup; it only adds what's missing
+pub fn repair(config: &Config) -> Result<(), String> {
+    let firewall = hotspot::strategy_firewall(config)
+        .ok_or_else(|| "No explicit NAT rules to repair".to_string())?;
+    privileged::run(
+        &NatRequest::Setup {
+            hotspot: config.hotspot_interface.clone(),
+            internet: config.internet_interface.clone(),
+            firewall,
+        },
+        config.dry_run,
+    )
+}
+
+/// When repairs happened, to spot ones that keep recurring
+#[derive(Debug, Default)]
+pub struct RepairHistory {
+    repairs: Vec<Instant>,
+}
+
+impl RepairHistory {
+    /// Record a repair at `now`. Returns true when it makes
+    /// `RECURRING_REPAIRS` within `RECURRING_WINDOW`; counting then starts
+    /// afresh, so the warning isn't repeated on every following repair.
+    pub fn record(&mut self, now: Instant) -> bool {
+        self.repairs
+            .retain(|&at| now.saturating_duration_since(at) < RECURRING_WINDOW);
+        self.repairs.push(now);
+        if self.repairs.len() >= RECURRING_REPAIRS {
+            self.repairs.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.repairs.clear();
+    }
+}
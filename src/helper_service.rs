@@ -0,0 +1,445 @@
+//! System D-Bus service wrapping the privileged helper.
+//!
+//! Runs as root (D-Bus activated, see resources/*.service) and exposes the
+//! same operations as the `cosmic-hotspot-nat` command line. Every call is
+//! checked against its polkit action before anything is executed: setting up,
+//! tearing down and checking the NAT rules need no password, everything else
+//! an admin's (kept for a few minutes).
+
+// D-Bus methods take their arguments one by one, plus the header and
+// connection zbus passes in
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::HashMap;
+
+use zbus::message::Header;
+use zbus::zvariant::Value;
+use zbus::{fdo, interface, Connection};
+
+use crate::nat::{self, ClientAccess, Firewall, NatRequest};
+use crate::privileged::{BUS_NAME, OBJECT_PATH};
+use crate::schedule::Schedule;
+
+/// The same actions pkexec picks by the helper's first argument, see
+/// resources/*.policy
+const POLKIT_SETUP_ACTION: &str = "io.github.reality2_roycdavies.cosmic-hotspot.nat";
+const POLKIT_TEARDOWN_ACTION: &str = "io.github.reality2_roycdavies.cosmic-hotspot.nat-teardown";
+const POLKIT_CHECK_ACTION: &str = "io.github.reality2_roycdavies.cosmic-hotspot.nat-check";
+const POLKIT_ADMIN_ACTION: &str = "io.github.reality2_roycdavies.cosmic-hotspot.admin";
+/// CheckAuthorization flag: allow an authentication dialog if required
+const POLKIT_ALLOW_INTERACTION: u32 = 1;
+
+struct Helper;
+
+#[interface(name = "io.github.reality2_roycdavies.CosmicHotspot1.Helper")]
+impl Helper {
+    async fn setup_nat(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        internet: &str,
+        firewall: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_SETUP_ACTION).await?;
+        execute(NatRequest::Setup {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            internet: nat::validate_interface(internet).map_err(fdo::Error::InvalidArgs)?,
+            firewall: Firewall::parse(firewall).map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    async fn teardown_nat(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        internet: &str,
+        firewall: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_TEARDOWN_ACTION).await?;
+        execute(NatRequest::Teardown {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            internet: nat::validate_interface(internet).map_err(fdo::Error::InvalidArgs)?,
+            firewall: Firewall::parse(firewall).map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    async fn check_nat(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        internet: &str,
+        firewall: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_CHECK_ACTION).await?;
+        execute(NatRequest::Check {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            internet: nat::validate_interface(internet).map_err(fdo::Error::InvalidArgs)?,
+            firewall: Firewall::parse(firewall).map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    async fn set_rate_limit(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        rate_kbit: u32,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::Limit {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            rate_kbit,
+        })
+    }
+
+    async fn add_port_forward(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        internet: &str,
+        protocol: &str,
+        port: u16,
+        destination: &str,
+        destination_port: u16,
+        subnet: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(port_forward(internet, protocol, port, destination, destination_port, subnet, false)?)
+    }
+
+    async fn remove_port_forward(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        internet: &str,
+        protocol: &str,
+        port: u16,
+        destination: &str,
+        destination_port: u16,
+        subnet: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(port_forward(internet, protocol, port, destination, destination_port, subnet, true)?)
+    }
+
+    async fn set_dns_redirect(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        server: &str,
+        subnet: &str,
+        remove: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        let args = ["dns", if remove { "remove" } else { "add" }, hotspot, server, subnet].map(String::from);
+        execute(NatRequest::parse(&args).map_err(fdo::Error::InvalidArgs)?)
+    }
+
+    async fn set_query_log(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        enable: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::QueryLog { enable })
+    }
+
+    async fn set_local_domain(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        enable: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::LocalDomain { enable })
+    }
+
+    async fn set_host_resolver(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        enable: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::HostResolver { enable })
+    }
+
+    async fn set_dhcp_options(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        options: Vec<String>,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::DhcpOptions {
+            options: options
+                .iter()
+                .map(|o| nat::validate_dhcp_option(o))
+                .collect::<Result<_, _>>()
+                .map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    /// `ports` is only used by the "block-ports" policy ("" otherwise)
+    async fn set_client_access(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        internet: &str,
+        mac: &str,
+        policy: &str,
+        ports: &str,
+        remove: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::ClientAccess {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            internet: nat::validate_interface(internet).map_err(fdo::Error::InvalidArgs)?,
+            mac: nat::validate_mac(mac).map_err(fdo::Error::InvalidArgs)?,
+            access: ClientAccess::parse(policy, Some(ports).filter(|p| !p.is_empty()))
+                .map_err(fdo::Error::InvalidArgs)?,
+            remove,
+        })
+    }
+
+    async fn set_client_schedule(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        internet: &str,
+        mac: &str,
+        days: &str,
+        from: &str,
+        until: &str,
+        remove: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::ClientSchedule {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            internet: nat::validate_interface(internet).map_err(fdo::Error::InvalidArgs)?,
+            mac: nat::validate_mac(mac).map_err(fdo::Error::InvalidArgs)?,
+            schedule: Schedule::parse(days, from, until).map_err(fdo::Error::InvalidArgs)?,
+            remove,
+        })
+    }
+
+    async fn set_client_priority(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        mac: &str,
+        remove: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::ClientPriority {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            mac: nat::validate_mac(mac).map_err(fdo::Error::InvalidArgs)?,
+            remove,
+        })
+    }
+
+    async fn set_wps(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        enable: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::Wps {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            enable,
+        })
+    }
+
+    async fn deauth(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        mac: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::Deauth {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            mac: nat::validate_mac(mac).map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    async fn set_rfkill(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        block: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::Rfkill { block })
+    }
+
+    async fn set_multicast(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        to_unicast: bool,
+        rate_mbps: u32,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::Multicast {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+            to_unicast,
+            rate_mbps: nat::validate_multicast_rate(&rate_mbps.to_string()).map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    async fn set_usb_autosuspend(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        device: &str,
+        enable: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::UsbAutosuspend {
+            device: nat::validate_usb_device(device).map_err(fdo::Error::InvalidArgs)?,
+            enable,
+        })
+    }
+
+    /// `gateway` is "address/prefix", e.g. "192.168.44.1/24"
+    async fn start_hostapd(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+        ssid: &str,
+        passphrase: &str,
+        security: &str,
+        band: &str,
+        channel: u32,
+        gateway: &str,
+        dhcp_start: &str,
+        dhcp_end: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        // Validated the same way as the command line's arguments
+        let channel = channel.to_string();
+        let args = [
+            "hostapd", "start", hotspot, ssid, passphrase, security, band, &channel, gateway, dhcp_start, dhcp_end,
+        ]
+        .map(String::from);
+        execute(NatRequest::parse(&args).map_err(fdo::Error::InvalidArgs)?)
+    }
+
+    async fn stop_hostapd(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        hotspot: &str,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::HostapdStop {
+            hotspot: nat::validate_interface(hotspot).map_err(fdo::Error::InvalidArgs)?,
+        })
+    }
+
+    async fn set_virtual_ap(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        parent: &str,
+        name: &str,
+        remove: bool,
+    ) -> fdo::Result<()> {
+        authorize(conn, &header, POLKIT_ADMIN_ACTION).await?;
+        execute(NatRequest::VirtualAp {
+            parent: nat::validate_interface(parent).map_err(fdo::Error::InvalidArgs)?,
+            name: nat::validate_interface(name).map_err(fdo::Error::InvalidArgs)?,
+            remove,
+        })
+    }
+}
+
+fn port_forward(
+    internet: &str,
+    protocol: &str,
+    port: u16,
+    destination: &str,
+    destination_port: u16,
+    subnet: &str,
+    remove: bool,
+) -> fdo::Result<NatRequest> {
+    // Reuse the command-line parser so both entry points validate identically
+    let port = port.to_string();
+    let destination_port = destination_port.to_string();
+    let args: Vec<String> = [
+        "forward",
+        if remove { "remove" } else { "add" },
+        internet,
+        protocol,
+        port.as_str(),
+        destination,
+        destination_port.as_str(),
+        subnet,
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    NatRequest::parse(&args).map_err(fdo::Error::InvalidArgs)
+}
+
+fn execute(request: NatRequest) -> fdo::Result<()> {
+    request.apply().map_err(fdo::Error::Failed)
+}
+
+/// Ask polkit whether the calling bus name may perform `action`
+async fn authorize(conn: &Connection, header: &Header<'_>, action: &str) -> fdo::Result<()> {
+    let sender = header
+        .sender()
+        .ok_or_else(|| fdo::Error::AccessDenied("Unknown caller".to_string()))?;
+
+    let mut subject_details: HashMap<&str, Value> = HashMap::new();
+    subject_details.insert("name", Value::from(sender.as_str()));
+    let subject = ("system-bus-name", subject_details);
+    let details: HashMap<&str, &str> = HashMap::new();
+
+    let reply = conn
+        .call_method(
+            Some("org.freedesktop.PolicyKit1"),
+            "/org/freedesktop/PolicyKit1/Authority",
+            Some("org.freedesktop.PolicyKit1.Authority"),
+            "CheckAuthorization",
+            &(subject, action, details, POLKIT_ALLOW_INTERACTION, ""),
+        )
+        .await?;
+    let (authorized, _challenge, _details): (bool, bool, HashMap<String, String>) =
+        reply.body().deserialize()?;
+
+    if authorized {
+        Ok(())
+    } else {
+        Err(fdo::Error::AccessDenied("Not authorized by polkit".to_string()))
+    }
+}
+
+/// Claim the bus name and serve requests until killed
+pub fn run() -> Result<(), String> {
+    zbus::block_on(async {
+        let _conn = zbus::connection::Builder::system()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, Helper)?
+            .build()
+            .await?;
+        std::future::pending::<()>().await;
+        Ok::<(), zbus::Error>(())
+    })
+    .map_err(|e| format!("D-Bus service failed: {e}"))
+}
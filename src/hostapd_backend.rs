@@ -0,0 +1,320 @@
+//! hostapd + dnsmasq backend, for adapters whose access point is unreliable
+//! through NetworkManager.
+//!
+//! Chosen with the "backend" setting. NetworkManager lets go of the hotspot
+//! interface, and the privileged helper runs hostapd for the access point and
+//! a dnsmasq of our own for DHCP and DNS. NAT always uses explicit rules, as
+//! there's no NM shared mode underneath. Whatever doesn't depend on who hosts
+//! the AP (adapters, scans, clients, sharing checks) is the NetworkManager
+//! backend's.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::SystemTime;
+
+use crate::adapters::{AdapterDetails, WifiAdapter};
+use crate::backend::{HotspotBackend, NetworkManagerBackend};
+use crate::channels::NearbyAp;
+use crate::clients::Client;
+use crate::config::{ClientPolicy, Config};
+use crate::dns_log::Lookup;
+use crate::errors::{ErrorKind, HotspotError};
+use crate::event_log::{self, Level};
+use crate::health::Problem;
+use crate::hotspot::{self, BusyResolution, ForeignHotspot};
+use crate::local_dns;
+use crate::nat::{self, AccessPoint, NatRequest};
+use crate::privileged;
+use crate::progress::{self, Step};
+use crate::repeater::{ClientLink, ScannedNetwork};
+use crate::run_state;
+use crate::saved_networks::SavedNetwork;
+use crate::stations::{self, Radio};
+
+pub struct HostapdBackend;
+
+/// Whether the hostapd we started is running
+pub fn is_running() -> bool {
+    let Some(pid) = std::fs::read_to_string(nat::HOSTAPD_PID).ok() else {
+        return false;
+    };
+    std::fs::read_to_string(format!("/proc/{}/comm", pid.trim()))
+        .is_ok_and(|comm| comm.trim() == "hostapd")
+}
+
+/// What hostapd and dnsmasq are started with for `config`
+pub fn access_point(config: &Config) -> Result<AccessPoint, String> {
+    let profile = config.profile();
+    profile.validate()?;
+    let (gateway, prefix) = hotspot::parse_gateway_ip(&profile.gateway_ip)
+        .ok_or_else(|| format!("Invalid gateway IP '{}'", profile.gateway_ip))?;
+    let (dhcp_start, dhcp_end) = match (profile.dhcp_range_start.trim(), profile.dhcp_range_end.trim()) {
+        ("", "") => default_dhcp_range(gateway, prefix),
+        (start, end) => (
+            start.parse().map_err(|_| format!("Invalid DHCP range start '{start}'"))?,
+            end.parse().map_err(|_| format!("Invalid DHCP range end '{end}'"))?,
+        ),
+    };
+    Ok(AccessPoint {
+        hotspot: nat::validate_interface(&config.hotspot_interface)?,
+        ssid: nat::validate_ssid(&config.ssid)?,
+        password: nat::validate_passphrase(&config.password)?,
+        security: config.security.clone(),
+        band: config.band.clone(),
+        channel: config.channel,
+        gateway,
+        prefix,
+        dhcp_start,
+        dhcp_end,
+    })
+}
+
+/// The range NetworkManager's shared mode would hand out: from the tenth
+/// address of the subnet (the second in tiny ones) to the last before the
+/// broadcast address
+pub fn default_dhcp_range(gateway: Ipv4Addr, prefix: u32) -> (Ipv4Addr, Ipv4Addr) {
+    let mask = u32::MAX.checked_shl(32 - prefix.min(30)).unwrap_or(0);
+    let network = u32::from(gateway) & mask;
+    let broadcast = network | !mask;
+    let offset = if broadcast - network > 16 { 10 } else { 2 };
+    (Ipv4Addr::from(network + offset), Ipv4Addr::from(broadcast - 1))
+}
+
+fn start(config: &Config) -> Result<String, HotspotError> {
+    let attempted_at = run_state::now();
+    progress::start_timing();
+    let result = try_start(config);
+    if !config.dry_run {
+        hotspot::record_timings(config, attempted_at, result.is_ok());
+        run_state::record_start(&result, None, config.start_fingerprint());
+        match &result {
+            Ok(msg) => event_log::record(Level::Info, msg.as_str()),
+            Err(e) => event_log::record(Level::Error, format!("Start failed: {e}")),
+        }
+    }
+    result
+}
+
+fn try_start(config: &Config) -> Result<String, HotspotError> {
+    let ap = access_point(config).map_err(|e| HotspotError::new(ErrorKind::InvalidSetting, e))?;
+    if is_running() {
+        // A restart: hostapd won't share the interface with its old self
+        let _ = privileged::run(&NatRequest::HostapdStop { hotspot: ap.hotspot.clone() }, config.dry_run);
+    }
+
+    // The helper builds dnsmasq's command line from the link's presence
+    if let Err(e) = hotspot::sync_host_resolver(config) {
+        eprintln!("Host resolver warning: {e}");
+    }
+
+    progress::report(Step::Activating);
+    privileged::run(&NatRequest::HostapdStart { ap }, config.dry_run)
+        .map_err(|e| HotspotError::new(ErrorKind::ActivationFailed, e))?;
+
+    if let Err(warning) = hotspot::apply_extras(config, &config.connection_name) {
+        return Ok(format!(
+            "Hotspot '{}' active on {} with hostapd ({warning})",
+            config.ssid, config.hotspot_interface,
+        ));
+    }
+    if config.dry_run {
+        return Ok(format!("Dry run: hotspot '{}' not started", config.ssid));
+    }
+    Ok(format!("Hotspot '{}' active on {} with hostapd", config.ssid, config.hotspot_interface))
+}
+
+fn stop(config: &Config) -> Result<String, HotspotError> {
+    // The setting may have changed since the start; the rules to remove are
+    // still the ones hostapd needed
+    let config = &Config { backend: "hostapd".to_string(), ..config.clone() };
+    progress::report(Step::RemovingNat);
+    hotspot::remove_extras(config, &config.hotspot_interface);
+
+    progress::report(Step::Deactivating);
+    let request = NatRequest::HostapdStop { hotspot: config.hotspot_interface.clone() };
+    if let Err(e) = privileged::run(&request, config.dry_run) {
+        eprintln!("hostapd stop warning: {e}");
+    }
+
+    if config.dry_run {
+        return Ok("Dry run: hotspot not stopped".to_string());
+    }
+    run_state::record_stop();
+    event_log::record(Level::Info, "Hotspot stopped");
+    Ok("Hotspot stopped".to_string())
+}
+
+impl HotspotBackend for HostapdBackend {
+    fn start(&self, config: &Config) -> Result<String, HotspotError> {
+        start(config)
+    }
+
+    /// hostapd needs the whole adapter, so there's nothing to resolve
+    fn start_resolving(
+        &self,
+        config: &Config,
+        _resolution: BusyResolution,
+    ) -> Result<String, HotspotError> {
+        start(config)
+    }
+
+    fn stop(&self, config: &Config) -> Result<String, HotspotError> {
+        stop(config)
+    }
+
+    fn is_active(&self, _config: &Config) -> bool {
+        is_running()
+    }
+
+    fn foreign_hotspot(&self, _config: &Config) -> Option<ForeignHotspot> {
+        None
+    }
+
+    fn adopt(&self, connection: &str) -> Result<String, HotspotError> {
+        Err(HotspotError::new(
+            ErrorKind::ForeignHotspot,
+            format!("'{connection}' is a NetworkManager hotspot; switch the backend to adopt it"),
+        ))
+    }
+
+    fn adopt_running(&self, _config: &Config) -> Option<String> {
+        None
+    }
+
+    fn release_stopped(&self, _config: &Config) -> bool {
+        false
+    }
+
+    fn connected_clients(&self, config: &Config) -> Vec<Client> {
+        NetworkManagerBackend.connected_clients(config)
+    }
+
+    fn neighbours(&self, config: &Config) -> Vec<(String, String)> {
+        NetworkManagerBackend.neighbours(config)
+    }
+
+    fn gateway_claimants(&self, config: &Config) -> Vec<String> {
+        NetworkManagerBackend.gateway_claimants(config)
+    }
+
+    fn upstream(&self, config: &Config) -> Option<String> {
+        NetworkManagerBackend.upstream(config)
+    }
+
+    fn client_mac(&self, ip: &str) -> Option<String> {
+        NetworkManagerBackend.client_mac(ip)
+    }
+
+    fn client_hostnames(&self, _config: &Config) -> HashMap<String, String> {
+        std::fs::read_to_string(nat::HOSTAPD_LEASES)
+            .map(|leases| local_dns::parse_leases(&leases))
+            .unwrap_or_default()
+    }
+
+    fn client_radios(&self, config: &Config) -> HashMap<String, Radio> {
+        stations::radios(&config.hotspot_interface)
+    }
+
+    fn update_client_policy(
+        &self,
+        config: &Config,
+        previous: Option<&ClientPolicy>,
+        current: Option<&ClientPolicy>,
+    ) -> Result<(), String> {
+        NetworkManagerBackend.update_client_policy(config, previous, current)
+    }
+
+    fn supports_wps(&self, config: &Config) -> bool {
+        access_point(config).is_ok_and(|ap| ap.supports_wps())
+    }
+
+    fn wps_push_button(&self, config: &Config, enable: bool) -> Result<(), String> {
+        if enable && !self.supports_wps(config) {
+            return Err("WPS pairing doesn't work with WPA3-only security".to_string());
+        }
+        privileged::run(&NatRequest::Wps { hotspot: config.hotspot_interface.clone(), enable }, config.dry_run)
+    }
+
+    fn disconnect_client(&self, config: &Config, mac: &str) -> Result<(), String> {
+        privileged::run(
+            &NatRequest::Deauth { hotspot: config.hotspot_interface.clone(), mac: nat::validate_mac(mac)? },
+            config.dry_run,
+        )
+    }
+
+    fn sweep_clients(&self, config: &Config) {
+        NetworkManagerBackend.sweep_clients(config);
+    }
+
+    fn has_wifi_hardware(&self) -> bool {
+        NetworkManagerBackend.has_wifi_hardware()
+    }
+
+    fn wifi_adapters(&self) -> Vec<WifiAdapter> {
+        NetworkManagerBackend.wifi_adapters()
+    }
+
+    fn network_interfaces(&self) -> Vec<String> {
+        NetworkManagerBackend.network_interfaces()
+    }
+
+    fn adapter_details(&self, device: &str) -> AdapterDetails {
+        NetworkManagerBackend.adapter_details(device)
+    }
+
+    fn scan_networks(&self, interface: &str) -> Vec<ScannedNetwork> {
+        NetworkManagerBackend.scan_networks(interface)
+    }
+
+    fn saved_networks(&self) -> Vec<SavedNetwork> {
+        NetworkManagerBackend.saved_networks()
+    }
+
+    fn saved_network_password(&self, network: &SavedNetwork) -> Result<String, String> {
+        NetworkManagerBackend.saved_network_password(network)
+    }
+
+    fn scan_access_points(&self, interface: &str) -> Vec<NearbyAp> {
+        NetworkManagerBackend.scan_access_points(interface)
+    }
+
+    fn client_link(&self, interface: &str) -> Option<ClientLink> {
+        NetworkManagerBackend.client_link(interface)
+    }
+
+    fn failed_joins(&self, interface: &str, since: SystemTime) -> Vec<String> {
+        NetworkManagerBackend.failed_joins(interface, since)
+    }
+
+    fn dns_lookups(&self, since: SystemTime) -> Vec<Lookup> {
+        NetworkManagerBackend.dns_lookups(since)
+    }
+
+    fn traffic_bytes(&self, config: &Config) -> Option<(u64, u64)> {
+        NetworkManagerBackend.traffic_bytes(config)
+    }
+
+    fn started_at(&self, config: &Config) -> Option<SystemTime> {
+        NetworkManagerBackend.started_at(config)
+    }
+
+    fn uses_old_settings(&self, config: &Config) -> bool {
+        NetworkManagerBackend.uses_old_settings(config)
+    }
+
+    fn sharing_problems(&self, config: &Config) -> Vec<Problem> {
+        NetworkManagerBackend.sharing_problems(config)
+    }
+
+    fn repair_sharing(&self, config: &Config) -> Result<(), String> {
+        NetworkManagerBackend.repair_sharing(config)
+    }
+
+    fn autosuspending_usb_device(&self, config: &Config) -> Option<String> {
+        NetworkManagerBackend.autosuspending_usb_device(config)
+    }
+
+    fn hold_usb_power(&self, config: &Config) -> Result<(), String> {
+        NetworkManagerBackend.hold_usb_power(config)
+    }
+}
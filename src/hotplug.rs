@@ -0,0 +1,95 @@
+//! Network interface hotplug notifications.
+//!
+//! Listens for rtnetlink link messages and reports when the set of
+//! interfaces in /sys/class/net changes, so device lists refresh as soon as
+//! a USB adapter is plugged in or pulled out instead of on the next poll.
+
+use std::collections::BTreeSet;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// NetworkManager needs a moment to take over a new device before it
+/// lists it
+const SETTLE: Duration = Duration::from_millis(1500);
+
+/// A receiver that gets `()` each time an interface appears or disappears.
+/// It disconnects if netlink isn't available, so callers should keep their
+/// periodic refresh as a fallback.
+pub fn subscribe() -> Receiver<()> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let socket = match link_socket() {
+            Ok(socket) => socket,
+            Err(e) => {
+                eprintln!("Interface hotplug detection unavailable: {e}");
+                return;
+            }
+        };
+        let mut known = interface_names();
+        let mut buf = [0u8; 8192];
+        loop {
+            // The messages only wake us up; link state changes (carrier,
+            // up/down) arrive too, so compare the interface set instead
+            let n = unsafe {
+                libc::recv(socket.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0)
+            };
+            if n < 0 {
+                let err = std::io::Error::last_os_error();
+                // ENOBUFS: messages were dropped, which still means a change
+                if err.kind() != std::io::ErrorKind::Interrupted
+                    && err.raw_os_error() != Some(libc::ENOBUFS)
+                {
+                    eprintln!("Interface hotplug detection stopped: {err}");
+                    return;
+                }
+            }
+            if interface_names() == known {
+                continue;
+            }
+            std::thread::sleep(SETTLE);
+            known = interface_names();
+            if tx.send(()).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+/// Netlink route socket subscribed to link add/remove/change messages
+fn link_socket() -> std::io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_ROUTE)
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_groups = libc::RTMGRP_LINK as u32;
+    let rc = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            (&addr as *const libc::sockaddr_nl).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if rc < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+fn interface_names() -> BTreeSet<String> {
+    std::fs::read_dir("/sys/class/net")
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
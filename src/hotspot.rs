@@ -1,50 +1,484 @@
-use crate::config::Config;
+use crate::activation_log;
+use crate::adapters::{self, WifiAdapter};
+use crate::clients::Client;
+use crate::config::{self, ClientPolicy, Config};
+use crate::diagnostics;
+use crate::dns_log;
+use crate::local_dns;
+use crate::errors::{ErrorKind, HotspotError};
+use crate::event_log::{self, Level};
+use crate::ip_conflicts;
+use crate::nat::{self, Firewall, NatRequest};
+use crate::nm_dbus::{self, Settings};
+use crate::privileged;
+use crate::progress::{self, Step};
+use crate::repeater;
+use crate::run_state::{self, ActivationLog, Timings};
+use std::collections::HashMap;
 use std::process::Command;
+use zbus::zvariant::Value;
+
+/// How to free an adapter that's connected to a network as a WiFi client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyResolution {
+    /// Disconnect the client connection, then start on the adapter
+    Disconnect,
+    /// Keep the client connection and host the AP on a virtual interface
+    VirtualAp,
+}
+
+/// How long to wait for NetworkManager to pick up a new virtual interface
+const VIRTUAL_AP_WAIT_ATTEMPTS: u32 = 10;
+const FIREWALLD_CGROUP: &str = "/sys/fs/cgroup/system.slice/firewalld.service";
+const UFW_CONF: &str = "/etc/ufw/ufw.conf";
+
+pub fn start_hotspot(config: &Config) -> Result<String, HotspotError> {
+    start_hotspot_with(config, None)
+}
+
+/// Start the hotspot, resolving an "interface busy" conflict as requested.
+///
+/// Without a resolution, an adapter that's connected as a client fails the
+/// pre-flight with `ErrorKind::InterfaceBusy` (detail: the connection name)
+/// instead of letting NM fail activation with a terse error.
+pub fn start_hotspot_with(
+    config: &Config,
+    resolution: Option<BusyResolution>,
+) -> Result<String, HotspotError> {
+    let config = &follow_renamed_interfaces(config);
+    let attempted_at = run_state::now();
+    progress::start_timing();
+    let result = try_start(config, resolution);
+    if !config.dry_run {
+        record_timings(config, attempted_at, result.is_ok());
+        run_state::record_start(&result, activation_path(config), config.start_fingerprint());
+        run_state::record_activation_log(ActivationLog {
+            at: attempted_at,
+            succeeded: result.is_ok(),
+            lines: activation_log::capture(attempted_at, run_state::now()),
+        });
+        match &result {
+            Ok(msg) => event_log::record(Level::Info, msg.as_str()),
+            Err(e) => event_log::record(Level::Error, format!("Start failed: {e}")),
+        }
+    }
+    result
+}
+
+/// Record how long each step of the start begun at `attempted_at` took, and
+/// warn in the event log when activation was slower than the configured
+/// threshold, e.g. after a kernel update regressed the driver
+pub fn record_timings(config: &Config, attempted_at: u64, succeeded: bool) {
+    let timings = Timings { at: attempted_at, succeeded, steps: progress::finish_timing() };
+    let threshold = config.slow_activation_secs.saturating_mul(1000);
+    if let Some(millis) = timings.millis(Step::Activating).filter(|&ms| threshold > 0 && ms > threshold) {
+        event_log::record(
+            Level::Warning,
+            format!(
+                "Activation took {:.1} s, over the {} s warning threshold",
+                millis as f64 / 1000.0,
+                config.slow_activation_secs
+            ),
+        );
+    }
+    run_state::record_timings(timings);
+}
+
+/// `config` with interfaces renamed since the last start replaced by their
+/// new names, saved so the stop and status polls use them too
+fn follow_renamed_interfaces(config: &Config) -> Config {
+    let mut resolved = config.clone();
+    let renames = resolved.resolve_interfaces(adapters::interface_mac, adapters::find_by_mac);
+    if resolved.interface_macs == config.interface_macs || config.dry_run {
+        return resolved;
+    }
+    for (old, new) in &renames {
+        event_log::record(Level::Info, format!("Interface {old} is now called {new}; using it"));
+    }
+    if let Err(e) = resolved.save() {
+        eprintln!("Failed to save the renamed interfaces: {e}");
+    }
+    resolved
+}
+
+fn try_start(
+    config: &Config,
+    resolution: Option<BusyResolution>,
+) -> Result<String, HotspotError> {
+    if config.repeater_mode {
+        progress::report(Step::JoiningUpstream);
+        repeater::ensure_upstream(config)?;
+    }
+
+    let Some(busy_with) = client_connection_on(&config.hotspot_interface, &config.connection_name)
+    else {
+        return activate_hotspot(config);
+    };
+    // Another tool's hotspot: never replaced without being asked to, and not
+    // brought back on stop
+    let foreign = access_point_ssid(&busy_with).is_some();
+    if foreign && resolution.is_none() {
+        return Err(HotspotError::new(ErrorKind::ForeignHotspot, busy_with));
+    }
+
+    let resolution = resolution
+        .or(config.auto_disconnect_client.then_some(BusyResolution::Disconnect));
+
+    match resolution {
+        None => Err(HotspotError::new(ErrorKind::InterfaceBusy, busy_with)),
+        Some(BusyResolution::Disconnect) => {
+            progress::report(Step::FreeingAdapter);
+            nm_dbus::disconnect_device(&config.hotspot_interface, config.dry_run)
+                .map_err(|e| HotspotError::from_nm(e, ErrorKind::DeviceUnavailable))?;
+            if !foreign && !config.dry_run {
+                remember_displaced_connection(&busy_with);
+            }
+            activate_hotspot(config)
+        }
+        Some(BusyResolution::VirtualAp) => {
+            progress::report(Step::FreeingAdapter);
+            let virtual_if = create_virtual_ap(&config.hotspot_interface, config.dry_run)?;
+            activate_hotspot(&Config { hotspot_interface: virtual_if, ..config.clone() })
+        }
+    }
+}
+
+/// The client connection currently active on `device`, ignoring our own hotspot
+pub fn client_connection_on(device: &str, own_connection: &str) -> Option<String> {
+    let (state, connection) = nm_dbus::device_state(device)?;
+    (state == nm_dbus::DEVICE_ACTIVATED && !connection.is_empty() && connection != own_connection.trim())
+        .then_some(connection)
+}
+
+/// Longest SSID 802.11 allows, in bytes
+const MAX_SSID_BYTES: usize = 32;
+
+/// `ssid` with "-suffix" appended, shortening the name if needed to stay
+/// within the 32-byte SSID limit
+pub fn ssid_with_suffix(ssid: &str, suffix: &str) -> String {
+    let room = MAX_SSID_BYTES.saturating_sub(suffix.len() + 1);
+    let mut end = ssid.len().min(room);
+    while !ssid.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}-{suffix}", &ssid[..end])
+}
+
+/// A short suffix that tells this machine's hotspot apart: the end of the
+/// adapter's MAC address, or the time if that can't be read
+pub fn ssid_suffix(interface: &str) -> String {
+    std::fs::read_to_string(format!("/sys/class/net/{interface}/address"))
+        .ok()
+        .map(|mac| mac.trim().replace(':', ""))
+        .filter(|mac| mac.len() >= 4)
+        .map(|mac| mac[mac.len() - 4..].to_string())
+        .unwrap_or_else(|| {
+            let secs = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            format!("{:04x}", secs & 0xffff)
+        })
+}
+
+/// A hotspot started by another tool on the hotspot adapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignHotspot {
+    pub connection: String,
+    pub ssid: String,
+}
+
+/// An AP connection that GNOME Settings, plasma-nm or `nmcli device wifi
+/// hotspot` has active on the hotspot adapter
+pub fn foreign_hotspot(config: &Config) -> Option<ForeignHotspot> {
+    let connection = client_connection_on(&config.hotspot_interface, &config.connection_name)?;
+    let ssid = access_point_ssid(&connection)?;
+    Some(ForeignHotspot { connection, ssid })
+}
+
+/// The SSID of `connection` if it's an access point (not a client) profile
+fn access_point_ssid(connection: &str) -> Option<String> {
+    let settings = nm_dbus::connection_settings(connection, None).ok()?;
+    (nm_dbus::string_setting(&settings, "802-11-wireless", "mode").as_deref() == Some("ap"))
+        .then(|| nm_dbus::ssid_setting(&settings).unwrap_or_default())
+}
+
+/// Take over a hotspot another tool started: its connection becomes the
+/// configured one, so status, clients and stop apply to it
+pub fn adopt_hotspot(connection: &str) -> Result<String, HotspotError> {
+    let settings = nm_dbus::connection_settings(connection, Some("802-11-wireless-security"))
+        .map_err(|e| HotspotError::from_nm(e, ErrorKind::DeviceUnavailable))?;
+    let ssid = nm_dbus::ssid_setting(&settings).unwrap_or_default();
+    let psk = nm_dbus::string_setting(&settings, "802-11-wireless-security", "psk").unwrap_or_default();
+
+    let mut config = Config::load();
+    config.connection_name = connection.to_string();
+    if !ssid.is_empty() {
+        config.ssid = ssid;
+    }
+    if !psk.is_empty() {
+        config.password = psk;
+    }
+    config.save().map_err(|e| HotspotError::new(ErrorKind::SaveFailed, e))?;
+    Ok(format!("Adopted hotspot '{}'", config.ssid))
+}
+
+/// File recording the client connection we took down, so stop can restore it.
+/// Lives in the runtime dir since the CLI may start and the applet stop.
+fn displaced_connection_path() -> Option<std::path::PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join("cosmic-hotspot").join("displaced-connection"))
+}
+
+fn remember_displaced_connection(connection: &str) {
+    let Some(path) = displaced_connection_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = std::fs::write(&path, connection) {
+        eprintln!("Could not record displaced connection: {e}");
+    }
+}
+
+/// Bring back the client connection displaced at start, if any
+fn restore_displaced_connection(dry_run: bool) {
+    let Some(path) = displaced_connection_path() else {
+        return;
+    };
+    let Ok(connection) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    if !dry_run {
+        let _ = std::fs::remove_file(&path);
+    }
+    let connection = connection.trim();
+    if connection.is_empty() {
+        return;
+    }
+    progress::report(Step::RestoringClient);
+    if let Err(e) = nm_dbus::activate(connection, None, dry_run) {
+        eprintln!("Could not restore '{connection}': {e}");
+    }
+}
+
+/// NM connection name for the wired share that accompanies the hotspot
+fn wired_connection_name(hotspot_connection: &str) -> String {
+    format!("{} (wired)", hotspot_connection.trim())
+}
+
+/// Gateway for the wired share: the subnet right after the hotspot's, with
+/// the same host part, e.g. 192.168.44.1/24 → 192.168.45.1/24
+pub fn wired_gateway_ip(gateway_ip: &str) -> Option<String> {
+    let (gateway, prefix) = parse_gateway_ip(gateway_ip)?;
+    if !(8..=30).contains(&prefix) {
+        return None;
+    }
+    let shifted = u32::from(gateway).checked_add(1 << (32 - prefix))?;
+    Some(format!("{}/{prefix}", std::net::Ipv4Addr::from(shifted)))
+}
+
+/// Share the same upstream to a wired (or USB gadget) interface with NM
+/// shared mode, so plugged-in devices get NAT'd internet like WiFi clients
+fn start_wired_share(config: &Config, hotspot_connection: &str) -> Result<(), String> {
+    let wired = &config.share_wired_interface;
+    let name = wired_connection_name(hotspot_connection);
+    let gateway = wired_gateway_ip(&config.profile().gateway_ip)
+        .ok_or_else(|| format!("cannot derive a wired subnet from '{}'", config.profile().gateway_ip))?;
+
+    let _ = nm_dbus::delete_connection(&name, config.dry_run);
+    let settings = Settings::from([
+        ("connection", connection_setting(&name, "802-3-ethernet", wired, false)),
+        ("ipv4", shared_ipv4_setting(&gateway, None)),
+        ("ipv6", HashMap::from([("method", Value::from("disabled"))])),
+    ]);
+    nm_dbus::add_connection(settings, config.dry_run).map_err(|e| e.to_string())?;
+    nm_dbus::activate(&name, Some(wired), config.dry_run).map_err(|e| e.to_string())?;
+
+    setup_nat(&Config { hotspot_interface: wired.clone(), ..config.clone() })
+}
+
+fn stop_wired_share(config: &Config) {
+    teardown_nat(&Config {
+        hotspot_interface: config.share_wired_interface.clone(),
+        ..config.clone()
+    });
+    let name = wired_connection_name(&config.connection_name);
+    let _ = nm_dbus::deactivate(&name, config.dry_run);
+    let _ = nm_dbus::delete_connection(&name, config.dry_run);
+}
+
+/// Name of the virtual AP interface created on top of `parent`
+pub fn virtual_ap_name(parent: &str) -> String {
+    let stem: String = parent.chars().take(13).collect();
+    format!("{stem}ap")
+}
+
+/// The interface actually hosting the AP: the virtual one if it exists
+pub fn active_interface(config: &Config) -> String {
+    let virtual_if = virtual_ap_name(&config.hotspot_interface);
+    if std::path::Path::new("/sys/class/net").join(&virtual_if).exists() {
+        virtual_if
+    } else {
+        config.hotspot_interface.clone()
+    }
+}
+
+fn create_virtual_ap(parent: &str, dry_run: bool) -> Result<String, HotspotError> {
+    let name = virtual_ap_name(parent);
+    privileged::run(
+        &NatRequest::VirtualAp { parent: parent.to_string(), name: name.clone(), remove: false },
+        dry_run,
+    )
+    .map_err(|e| HotspotError::new(ErrorKind::DeviceUnavailable, e))?;
+
+    if dry_run {
+        return Ok(name);
+    }
+    for _ in 0..VIRTUAL_AP_WAIT_ATTEMPTS {
+        if list_wifi_interfaces().contains(&name) {
+            return Ok(name);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
+    Err(HotspotError::new(
+        ErrorKind::DeviceUnavailable,
+        format!("NetworkManager did not pick up virtual interface {name}"),
+    ))
+}
+
+/// NM_SETTING_WIRELESS_SECURITY_PMF_OPTIONAL / _REQUIRED
+const PMF_OPTIONAL: i32 = 2;
+const PMF_REQUIRED: i32 = 3;
+/// NM_TERNARY_TRUE
+const TERNARY_TRUE: i32 = 1;
+
+/// The `Config::security` modes a backend can host. NetworkManager has no
+/// key-mgmt value for WPA2/WPA3 transition mode, so only hostapd offers it.
+pub fn security_modes(backend: &str) -> &'static [&'static str] {
+    if backend == "hostapd" {
+        &["wpa2", "wpa3", "wpa2-wpa3"]
+    } else {
+        &["wpa2", "wpa3"]
+    }
+}
+
+/// The key management and PMF properties of the wireless security setting
+/// for a `Config::security` mode.
+///
+/// WPA3 (SAE) requires management frame protection. A "wpa2-wpa3" config
+/// left over from the hostapd backend gets plain WPA2 with optional PMF;
+/// clients see no SAE, so it is not offered with NetworkManager.
+pub fn security_settings(security: &str) -> Vec<(&'static str, Value<'static>)> {
+    match security {
+        "wpa3" => vec![("key-mgmt", Value::from("sae")), ("pmf", Value::from(PMF_REQUIRED))],
+        "wpa2-wpa3" => vec![("key-mgmt", Value::from("wpa-psk")), ("pmf", Value::from(PMF_OPTIONAL))],
+        _ => vec![("key-mgmt", Value::from("wpa-psk"))],
+    }
+}
+
+/// The NetworkManager connection for the hotspot, saved as `name`
+pub fn hotspot_settings(config: &Config, name: &str) -> Settings {
+    let profile = config.profile();
+    let mut wireless = HashMap::from([
+        ("ssid", Value::from(config.ssid.as_bytes().to_vec())),
+        ("mode", Value::from("ap")),
+        ("band", Value::from(config.band.clone())),
+    ]);
+    if config.channel != 0 {
+        wireless.insert("channel", Value::from(config.channel));
+    }
+    if profile.client_isolation {
+        wireless.insert("ap-isolation", Value::from(TERNARY_TRUE));
+    }
+    let mut security = HashMap::from([
+        ("proto", Value::from(vec!["rsn"])),
+        ("pairwise", Value::from(vec!["ccmp"])),
+        ("group", Value::from(vec!["ccmp"])),
+        ("psk", Value::from(config.password.clone())),
+    ]);
+    security.extend(security_settings(&config.security));
+    // Only set when set: the property is missing from older NM releases
+    let dhcp_range = (!profile.dhcp_range_start.is_empty() && !profile.dhcp_range_end.is_empty())
+        .then(|| format!("{},{}", profile.dhcp_range_start, profile.dhcp_range_end));
+
+    Settings::from([
+        // NM defaults autoconnect to on, which would bring back a hotspot
+        // that was still up at shutdown
+        (
+            "connection",
+            connection_setting(name, "802-11-wireless", &config.hotspot_interface, config.autoconnect),
+        ),
+        ("802-11-wireless", wireless),
+        ("802-11-wireless-security", security),
+        ("ipv4", shared_ipv4_setting(&profile.gateway_ip, dhcp_range)),
+        ("ipv6", HashMap::from([("method", Value::from("disabled"))])),
+    ])
+}
+
+fn connection_setting(
+    name: &str,
+    kind: &'static str,
+    interface: &str,
+    autoconnect: bool,
+) -> HashMap<&'static str, Value<'static>> {
+    HashMap::from([
+        ("id", Value::from(name.to_string())),
+        ("type", Value::from(kind)),
+        ("interface-name", Value::from(interface.to_string())),
+        ("autoconnect", Value::from(autoconnect)),
+    ])
+}
+
+/// IPv4 shared mode (DHCP, DNS and NAT by NM) on `gateway_ip`, e.g.
+/// "192.168.44.1/24", with an optional "first,last" DHCP range
+fn shared_ipv4_setting(gateway_ip: &str, dhcp_range: Option<String>) -> HashMap<&'static str, Value<'static>> {
+    let mut ipv4 = HashMap::from([("method", Value::from("shared"))]);
+    if let Some((address, prefix)) = parse_gateway_ip(gateway_ip) {
+        let address = HashMap::from([
+            ("address", Value::from(address.to_string())),
+            ("prefix", Value::from(prefix)),
+        ]);
+        ipv4.insert("address-data", Value::from(vec![address]));
+    }
+    if let Some(range) = dhcp_range {
+        ipv4.insert("shared-dhcp-range", Value::from(range));
+    }
+    ipv4
+}
+
+fn activate_hotspot(config: &Config) -> Result<String, HotspotError> {
+    let name = config::normalize_connection_name(&config.connection_name)
+        .map_err(|e| HotspotError::new(ErrorKind::InvalidSetting, e))?;
 
-pub fn start_hotspot(config: &Config) -> Result<String, String> {
     // Remove any existing connection with the same name
-    let _ = Command::new("nmcli")
-        .args(["connection", "delete", &config.connection_name])
-        .output();
+    progress::report(Step::RemovingOldProfile);
+    let _ = nm_dbus::delete_connection(&name, config.dry_run);
 
     // Create the AP connection
-    let output = Command::new("nmcli")
-        .args([
-            "connection", "add",
-            "type", "wifi",
-            "ifname", &config.hotspot_interface,
-            "con-name", &config.connection_name,
-            "ssid", &config.ssid,
-            "--",
-            "wifi.mode", "ap",
-            "wifi.band", &config.band,
-            "wifi-sec.key-mgmt", "wpa-psk",
-            "wifi-sec.proto", "rsn",
-            "wifi-sec.pairwise", "ccmp",
-            "wifi-sec.group", "ccmp",
-            "wifi-sec.psk", &config.password,
-            "ipv4.method", "shared",
-            "ipv4.addresses", &config.gateway_ip,
-            "ipv6.method", "disabled",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run nmcli: {e}"))?;
+    progress::report(Step::CreatingProfile);
+    nm_dbus::add_connection(hotspot_settings(config, &name), config.dry_run)
+        .map_err(|e| HotspotError::from_nm(e, ErrorKind::CreateFailed))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to create hotspot: {stderr}"));
+    // dnsmasq reads its config when the connection comes up
+    if let Err(e) = sync_query_log(config) {
+        eprintln!("DNS query log warning: {e}");
+    }
+    if let Err(e) = sync_local_domain(config) {
+        eprintln!("Local domain warning: {e}");
+    }
+    if let Err(e) = sync_host_resolver(config) {
+        eprintln!("Host resolver warning: {e}");
+    }
+    if let Err(e) = sync_dhcp_options(config) {
+        eprintln!("DHCP options warning: {e}");
     }
 
     // Activate the connection
-    let output = Command::new("nmcli")
-        .args(["connection", "up", &config.connection_name])
-        .output()
-        .map_err(|e| format!("Failed to run nmcli: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to activate hotspot: {stderr}"));
-    }
+    progress::report(Step::Activating);
+    nm_dbus::activate(&name, Some(&config.hotspot_interface), config.dry_run)
+        .map_err(|e| HotspotError::from_nm(e, ErrorKind::ActivationFailed))?;
 
     // NetworkManager's "ipv4.method shared" already handles:
     //   - DHCP server on the hotspot interface
@@ -52,10 +486,33 @@ pub fn start_hotspot(config: &Config) -> Result<String, String> {
     //   - iptables MASQUERADE NAT rule
     //
     // For cases where NM's built-in sharing isn't enough (e.g. Quest 3),
-    // explicit NAT rules can be set up if a polkit policy is installed:
-    //   sudo install -m644 resources/io.github.reality2_roycdavies.cosmic-hotspot.policy \
-    //     /usr/share/polkit-1/actions/
-    setup_nat_if_authorized(config);
+    // explicit NAT rules are added on top via the privileged helper,
+    // according to the configured NAT strategy.
+    if let Err(warning) = apply_extras(config, &name) {
+        return Ok(format!(
+            "Hotspot '{}' active on {} ({warning})",
+            config.ssid, config.hotspot_interface,
+        ));
+    }
+
+    if config.dry_run {
+        return Ok(format!("Dry run: hotspot '{}' not started", config.ssid));
+    }
+
+    if config.verify_after_start {
+        progress::report(Step::Verifying);
+        let checks = diagnostics::verify_connectivity(config);
+        if let Some(failed) = diagnostics::first_failure(&checks) {
+            return Ok(format!(
+                "Hotspot '{}' up, but {} check failed: {}",
+                config.ssid, failed.name, failed.detail,
+            ));
+        }
+        return Ok(format!(
+            "Hotspot '{}' active on {} (connectivity verified)",
+            config.ssid, config.hotspot_interface,
+        ));
+    }
 
     Ok(format!(
         "Hotspot '{}' active on {}",
@@ -63,66 +520,540 @@ pub fn start_hotspot(config: &Config) -> Result<String, String> {
     ))
 }
 
-pub fn stop_hotspot(config: &Config) -> Result<String, String> {
-    let _ = Command::new("nmcli")
-        .args(["connection", "down", &config.connection_name])
-        .output();
+/// The rules the helper adds on top of NM's shared mode: DNS redirect,
+/// explicit NAT, wired sharing and client policies. Stops at the first
+/// failure and returns it as a warning; the hotspot is up either way.
+pub fn apply_extras(config: &Config, connection: &str) -> Result<(), String> {
+    progress::report(Step::ApplyingNat);
+    redirect_dns(config, false).map_err(|e| format!("DNS warning: {e}"))?;
+    setup_nat(config).map_err(|e| format!("NAT warning: {e}"))?;
+    if !config.share_wired_interface.is_empty() {
+        progress::report(Step::SharingWired);
+        start_wired_share(config, connection).map_err(|e| format!("wired sharing failed: {e}"))?;
+    }
+    apply_client_policies(config).map_err(|e| format!("client policy warning: {e}"))?;
+    set_multicast(config, false).map_err(|e| format!("multicast warning: {e}"))?;
+    if config.keep_usb_adapter_powered {
+        hold_usb_power(config).map_err(|e| format!("USB power warning: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Undo `apply_extras` for the hotspot on `interface`
+pub fn remove_extras(config: &Config, interface: &str) {
+    let running = Config { hotspot_interface: interface.to_string(), ..config.clone() };
+    remove_client_policies(&running);
+    teardown_nat(&running);
+    if let Err(e) = redirect_dns(&running, true) {
+        eprintln!("DNS redirect teardown warning: {e}");
+    }
+    if !config.share_wired_interface.is_empty() {
+        stop_wired_share(config);
+    }
+    if let Err(e) = set_multicast(&running, true) {
+        eprintln!("Multicast reset warning: {e}");
+    }
+    release_usb_power(config.dry_run);
+}
+
+/// Keep the kernel from autosuspending the hotspot's USB adapter while it
+/// runs; nothing to do for built-in adapters or ones already kept powered
+pub fn hold_usb_power(config: &Config) -> Result<(), String> {
+    let Some(device) = adapters::autosuspending_usb_device(&config.hotspot_interface) else {
+        return Ok(());
+    };
+    privileged::run(&NatRequest::UsbAutosuspend { device: device.clone(), enable: false }, config.dry_run)?;
+    if !config.dry_run {
+        run_state::record_usb_power_held(Some(device));
+    }
+    Ok(())
+}
+
+/// Let the adapter `hold_usb_power` kept powered suspend again
+fn release_usb_power(dry_run: bool) {
+    let Some(device) = run_state::load().usb_power_held else {
+        return;
+    };
+    if let Err(e) = privileged::run(&NatRequest::UsbAutosuspend { device, enable: true }, dry_run) {
+        eprintln!("USB autosuspend restore warning: {e}");
+    }
+    if !dry_run {
+        run_state::record_usb_power_held(None);
+    }
+}
+
+/// NM's D-Bus path for the current activation of the hotspot connection. A
+/// connection gets a new one each time it comes up, so it tells our own
+/// start apart from a later `nmcli connection up`.
+fn activation_path(config: &Config) -> Option<String> {
+    nm_dbus::active_connections()
+        .into_iter()
+        .find(|active| active.id == config.connection_name.trim())
+        .map(|active| active.path)
+}
+
+/// Take over the running hotspot if NetworkManager brought it up without us
+/// (autoconnect at boot, `nmcli connection up`): add the helper's rules and
+/// record when it started, so it's shown and stopped like one we started.
+/// `None` if this activation is already ours.
+///
+/// dnsmasq options (query log, client names, DHCP options) are only read
+/// when the connection comes up, so they wait for the next start.
+pub fn adopt_running(config: &Config) -> Option<String> {
+    let activation = activation_path(config)?;
+    if run_state::load().activation.as_deref() == Some(activation.as_str()) {
+        return None;
+    }
+    let interface = active_interface(config);
+    let running = Config { hotspot_interface: interface.clone(), ..config.clone() };
+    let message = match apply_extras(&running, config.connection_name.trim()) {
+        Ok(()) => format!("Managing hotspot '{}' started outside the applet", config.ssid),
+        Err(warning) => format!("Managing hotspot '{}' started outside the applet ({warning})", config.ssid),
+    };
+    if config.dry_run {
+        return Some(format!("Dry run: hotspot '{}' not adopted", config.ssid));
+    }
+    let started = dnsmasq_started(&interface).unwrap_or_else(run_state::now);
+    run_state::record_adopted(started, activation);
+    event_log::record(Level::Info, message.as_str());
+    Some(message)
+}
+
+/// Remove the helper's rules after the hotspot was taken down without us.
+/// Returns false if there was nothing to clean up (we stopped it ourselves).
+pub fn release_stopped(config: &Config) -> bool {
+    if run_state::load().started_at.is_none() {
+        return false;
+    }
+    remove_extras(config, &active_interface(config));
+    if !config.dry_run {
+        run_state::record_stop();
+        event_log::record(Level::Info, "Hotspot stopped outside the applet");
+    }
+    true
+}
+
+/// When NM started the dnsmasq serving `interface`, which is as close to the
+/// activation time as NM lets us get
+fn dnsmasq_started(interface: &str) -> Option<u64> {
+    let pid = [
+        format!("/run/NetworkManager/dnsmasq-{interface}.pid"),
+        format!("/run/nm-dnsmasq-{interface}.pid"),
+    ]
+    .iter()
+    .find_map(|path| std::fs::read_to_string(path).ok())?;
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).ok()?;
+    let boot_time = std::fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()?;
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    process_start_time(&stat, boot_time, u64::try_from(ticks_per_sec).ok()?)
+}
+
+/// Unix start time of a process from its `/proc/<pid>/stat` line
+pub fn process_start_time(stat: &str, boot_time: u64, ticks_per_sec: u64) -> Option<u64> {
+    // The command name may contain spaces and parentheses; fields after it
+    // start at field 3, and the start time (in ticks since boot) is field 22
+    let (_, fields) = stat.rsplit_once(')')?;
+    let ticks: u64 = fields.split_whitespace().nth(19)?.parse().ok()?;
+    Some(boot_time + ticks / ticks_per_sec.max(1))
+}
+
+pub fn stop_hotspot(config: &Config) -> Result<String, HotspotError> {
+    let interface = active_interface(config);
+    let on_virtual_ap = interface != config.hotspot_interface;
+    progress::report(Step::RemovingNat);
+    remove_extras(config, &interface);
+
+    progress::report(Step::Deactivating);
+    let name = config.connection_name.trim();
+    let _ = nm_dbus::deactivate(name, config.dry_run);
+    // A kept profile stays down until the next boot: NM doesn't autoconnect a
+    // connection the user took down. One on a virtual AP can't come back, as
+    // the interface is removed below.
+    if !config.autoconnect || on_virtual_ap {
+        let _ = nm_dbus::delete_connection(name, config.dry_run);
+    }
 
-    let _ = Command::new("nmcli")
-        .args(["connection", "delete", &config.connection_name])
-        .output();
+    if on_virtual_ap {
+        let request = NatRequest::VirtualAp {
+            parent: config.hotspot_interface.clone(),
+            name: interface,
+            remove: true,
+        };
+        if let Err(e) = privileged::run(&request, config.dry_run) {
+            eprintln!("Virtual AP removal warning: {e}");
+        }
+    }
 
+    restore_displaced_connection(config.dry_run);
+
+    if config.dry_run {
+        return Ok("Dry run: hotspot not stopped".to_string());
+    }
+    run_state::record_stop();
+    event_log::record(Level::Info, "Hotspot stopped");
     Ok("Hotspot stopped".to_string())
 }
 
 pub fn is_hotspot_active(config: &Config) -> bool {
-    Command::new("nmcli")
-        .args(["-t", "-f", "GENERAL.STATE", "connection", "show", &config.connection_name])
-        .output()
-        .map(|o| {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout.contains("activated")
-        })
-        .unwrap_or(false)
+    nm_dbus::active_connections()
+        .iter()
+        .any(|active| active.id == config.connection_name.trim() && active.is_activated())
 }
 
-const NAT_HELPER: &str = "/usr/local/bin/cosmic-hotspot-nat";
+/// The NAT strategy actually in use: "nm-shared", "iptables", "nftables",
+/// "firewalld" or "ufw".
+///
+/// "auto" (the default) adds explicit rules when the privileged helper is
+/// installed and otherwise relies on NM shared mode alone. The rules go
+/// through firewalld or ufw when one of them manages the firewall, as their
+/// reloads would wipe raw iptables rules and cut clients off. The hostapd
+/// backend has no shared mode to rely on, so it always adds rules.
+pub fn effective_nat_strategy(config: &Config) -> &'static str {
+    match config.profile().nat_strategy.as_str() {
+        "iptables" => "iptables",
+        "nftables" => "nftables",
+        "firewalld" => "firewalld",
+        "ufw" => "ufw",
+        _ if config.backend == "hostapd" => managed_firewall().map_or("iptables", Firewall::as_str),
+        "nm-shared" => "nm-shared",
+        _ if privileged::is_available() => managed_firewall().map_or("iptables", Firewall::as_str),
+        _ => "nm-shared",
+    }
+}
 
-/// Set up explicit NAT rules using the helper script + polkit policy.
-/// If the helper script isn't installed, this is a no-op — NM shared mode still works.
+/// firewalld or ufw, if either manages the firewall
+pub fn managed_firewall() -> Option<Firewall> {
+    // The service's cgroup only exists while it runs; cheaper than asking
+    // systemctl on every popup redraw
+    if std::path::Path::new(FIREWALLD_CGROUP).exists() {
+        return Some(Firewall::Firewalld);
+    }
+    std::fs::read_to_string(UFW_CONF)
+        .is_ok_and(|conf| ufw_enabled(&conf))
+        .then_some(Firewall::Ufw)
+}
+
+/// Whether ufw's config file has it enabled ("ENABLED=yes")
+pub fn ufw_enabled(conf: &str) -> bool {
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("ENABLED="))
+        .any(|value| value.trim_matches(['"', '\'']).eq_ignore_ascii_case("yes"))
+}
+
+/// Human-readable description of the effective NAT strategy
+pub fn nat_strategy_label(config: &Config) -> String {
+    let sharing = if config.backend == "hostapd" { "hostapd" } else { "NM shared" };
+    let label = match effective_nat_strategy(config) {
+        "nm-shared" => "NM shared mode".to_string(),
+        strategy => format!("{sharing} + {strategy} rules"),
+    };
+    if config.profile().nat_strategy == "auto" {
+        format!("{label} (auto)")
+    } else {
+        label
+    }
+}
+
+/// The firewall the helper writes explicit NAT rules with, if any
+pub fn strategy_firewall(config: &Config) -> Option<Firewall> {
+    match effective_nat_strategy(config) {
+        "iptables" => Some(Firewall::Iptables),
+        "nftables" => Some(Firewall::Nftables),
+        "firewalld" => Some(Firewall::Firewalld),
+        "ufw" => Some(Firewall::Ufw),
+        _ => None,
+    }
+}
+
+/// Add explicit NAT rules via the privileged helper (D-Bus service or pkexec).
+///
+/// In auto mode a missing helper is fine — NM shared mode still works. An
+/// explicitly chosen iptables/nftables strategy reports a missing helper as
+/// an error so the user knows their workaround isn't in effect.
 ///
 /// Install with: just install-policy
-fn setup_nat_if_authorized(config: &Config) {
-    // Only attempt if the helper script is installed
-    if !std::path::Path::new(NAT_HELPER).exists() {
-        eprintln!("NAT helper not installed — relying on NM shared mode");
+fn setup_nat(config: &Config) -> Result<(), String> {
+    let Some(firewall) = strategy_firewall(config) else {
+        eprintln!("Relying on NM shared mode for NAT");
+        return Ok(());
+    };
+
+    let request = NatRequest::Setup {
+        hotspot: config.hotspot_interface.clone(),
+        internet: config.internet_interface.clone(),
+        firewall,
+    };
+    match privileged::run(&request, config.dry_run) {
+        Ok(()) => {
+            eprintln!("Explicit {} NAT rules applied via helper", firewall.as_str());
+            Ok(())
+        }
+        Err(e) if config.profile().nat_strategy == "auto" => {
+            eprintln!("NAT helper warning: {e}");
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Send clients' DNS queries to the profile's DNS server, if it sets one
+fn redirect_dns(config: &Config, remove: bool) -> Result<(), String> {
+    let server = config.profile().dns_server.trim();
+    if server.is_empty() {
+        return Ok(());
+    }
+    let server = server
+        .parse()
+        .map_err(|_| format!("invalid DNS server '{server}'"))?;
+    let (gateway, prefix) = parse_gateway_ip(&config.profile().gateway_ip)
+        .ok_or_else(|| format!("invalid gateway IP '{}'", config.profile().gateway_ip))?;
+    privileged::run(
+        &NatRequest::Dns { hotspot: config.hotspot_interface.clone(), server, gateway, prefix, remove },
+        config.dry_run,
+    )
+}
+
+/// Apply the multicast settings to the AP, or put back the defaults when
+/// `reset`; nothing to do if they're the defaults
+fn set_multicast(config: &Config, reset: bool) -> Result<(), String> {
+    if !config.multicast_to_unicast && config.multicast_rate_mbps == 0 {
+        return Ok(());
+    }
+    privileged::run(
+        &NatRequest::Multicast {
+            hotspot: config.hotspot_interface.clone(),
+            to_unicast: config.multicast_to_unicast && !reset,
+            rate_mbps: if reset { 0 } else { config.multicast_rate_mbps },
+        },
+        config.dry_run,
+    )
+}
+
+/// Turn dnsmasq's query log on or off to match the setting. Queries only
+/// reach dnsmasq when the profile doesn't redirect DNS elsewhere. The
+/// helper is only asked when the state has to change.
+fn sync_query_log(config: &Config) -> Result<(), String> {
+    let enable = config.dns_query_log && config.uses_gateway_dns();
+    if enable == dns_log::is_enabled() {
+        return Ok(());
+    }
+    privileged::run(&NatRequest::QueryLog { enable }, config.dry_run)
+}
+
+/// Serve client names under `.hotspot` or stop doing so, to match the
+/// setting. The helper is only asked when the state has to change.
+fn sync_local_domain(config: &Config) -> Result<(), String> {
+    let enable = config.serves_local_domain();
+    if enable == local_dns::is_enabled() {
+        return Ok(());
+    }
+    privileged::run(&NatRequest::LocalDomain { enable }, config.dry_run)
+}
+
+/// Forward the hotspot's DNS through systemd-resolved or stop doing so, to
+/// match the setting. Without a running systemd-resolved the link would
+/// leave clients with no DNS at all, so it's removed instead. The helper is
+/// only asked when the state has to change.
+pub fn sync_host_resolver(config: &Config) -> Result<(), String> {
+    let running = resolved_running();
+    let enable = config.uses_host_resolver() && running;
+    if enable != std::path::Path::new(nat::HOST_RESOLVER_CONF).exists() {
+        privileged::run(&NatRequest::HostResolver { enable }, config.dry_run)?;
+    }
+    if config.uses_host_resolver() && !running {
+        return Err("systemd-resolved isn't running; clients use the upstream DNS servers".to_string());
+    }
+    Ok(())
+}
+
+/// Whether systemd-resolved's stub is answering on the host
+pub fn resolved_running() -> bool {
+    std::path::Path::new(nat::RESOLVED_STUB_RESOLV_CONF).exists()
+}
+
+/// Write the profile's extra DHCP options for dnsmasq, or remove them. The
+/// helper is only asked when the file has to change.
+fn sync_dhcp_options(config: &Config) -> Result<(), String> {
+    let options = config.profile().dhcp_options()?;
+    let current = std::fs::read_to_string(nat::DHCP_OPTIONS_CONF).unwrap_or_default();
+    if current == nat::dhcp_options_conf(&options) {
+        return Ok(());
+    }
+    privileged::run(&NatRequest::DhcpOptions { options }, config.dry_run)
+}
+
+/// Remove the explicit NAT rules added by `setup_nat`
+fn teardown_nat(config: &Config) {
+    let Some(firewall) = strategy_firewall(config) else {
+        return;
+    };
+    if !privileged::is_available() {
         return;
     }
 
-    // pkexec with the helper script: the polkit policy (allow_active=yes) means
-    // no password dialog for active sessions
-    match Command::new("pkexec")
-        .args([NAT_HELPER, &config.hotspot_interface, &config.internet_interface])
-        .output()
-    {
-        Ok(output) if output.status.success() => {
-            eprintln!("Explicit NAT rules applied via helper");
-        }
-        Ok(output) => {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!("NAT helper warning: {stderr}");
+    let request = NatRequest::Teardown {
+        hotspot: config.hotspot_interface.clone(),
+        internet: config.internet_interface.clone(),
+        firewall,
+    };
+    if let Err(e) = privileged::run(&request, config.dry_run) {
+        eprintln!("NAT teardown warning: {e}");
+    }
+}
+
+/// Helper requests adding or removing the rules for one client policy: its
+/// access restriction, its schedule and its queueing priority
+fn client_policy_requests(config: &Config, policy: &ClientPolicy, remove: bool) -> Result<Vec<NatRequest>, String> {
+    let mut requests = Vec::new();
+    if let Some(access) = policy.client_access()? {
+        requests.push(NatRequest::ClientAccess {
+            hotspot: config.hotspot_interface.clone(),
+            internet: config.internet_interface.clone(),
+            mac: policy.mac.clone(),
+            access,
+            remove,
+        });
+    }
+    if let Some(schedule) = policy.schedule()? {
+        requests.push(NatRequest::ClientSchedule {
+            hotspot: config.hotspot_interface.clone(),
+            internet: config.internet_interface.clone(),
+            mac: policy.mac.clone(),
+            schedule,
+            remove,
+        });
+    }
+    if policy.priority {
+        requests.push(NatRequest::ClientPriority {
+            hotspot: config.hotspot_interface.clone(),
+            mac: policy.mac.clone(),
+            remove,
+        });
+    }
+    Ok(requests)
+}
+
+fn run_all(requests: &[NatRequest], dry_run: bool) -> Result<(), String> {
+    requests.iter().try_for_each(|request| privileged::run(request, dry_run))
+}
+
+/// Enforce every configured client policy; keeps going past failures and
+/// reports the first one
+fn apply_client_policies(config: &Config) -> Result<(), String> {
+    let mut first_error = None;
+    for policy in &config.client_policies {
+        if let Err(e) = client_policy_requests(config, policy, false).and_then(|r| run_all(&r, config.dry_run)) {
+            eprintln!("Client policy for {} not applied: {e}", policy.mac);
+            first_error.get_or_insert(format!("{}: {e}", policy.mac));
         }
-        Err(e) => {
-            eprintln!("NAT helper error: {e}");
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Remove the rules added by `apply_client_policies`
+fn remove_client_policies(config: &Config) {
+    if config.client_policies.is_empty() || !privileged::is_available() {
+        return;
+    }
+    for policy in &config.client_policies {
+        if let Err(e) = client_policy_requests(config, policy, true).and_then(|r| run_all(&r, config.dry_run)) {
+            eprintln!("Client policy teardown warning for {}: {e}", policy.mac);
         }
     }
 }
 
-pub fn get_connected_clients(config: &Config) -> Vec<String> {
+/// Swap one client's rules on the running hotspot: `previous` is what was
+/// enforced until now, `current` what should be from now on
+pub fn update_client_policy(
+    config: &Config,
+    previous: Option<&ClientPolicy>,
+    current: Option<&ClientPolicy>,
+) -> Result<(), String> {
+    let config = &Config { hotspot_interface: active_interface(config), ..config.clone() };
+    if let Some(previous) = previous {
+        run_all(&client_policy_requests(config, previous, true)?, config.dry_run)?;
+    }
+    if let Some(current) = current {
+        run_all(&client_policy_requests(config, current, false)?, config.dry_run)?;
+    }
+    Ok(())
+}
+
+/// Deauthenticate the client with this MAC address from the running AP
+pub fn disconnect_client(config: &Config, mac: &str) -> Result<(), String> {
+    privileged::run(
+        &NatRequest::Deauth { hotspot: active_interface(config), mac: nat::validate_mac(mac)? },
+        config.dry_run,
+    )
+}
+
+/// Bytes (received, sent) on the interface the hotspot runs on
+pub fn traffic_bytes(config: &Config) -> Option<(u64, u64)> {
+    let statistics = std::path::Path::new("/sys/class/net")
+        .join(active_interface(config))
+        .join("statistics");
+    let read = |name: &str| -> Option<u64> {
+        std::fs::read_to_string(statistics.join(name)).ok()?.trim().parse().ok()
+    };
+    Some((read("rx_bytes")?, read("tx_bytes")?))
+}
+
+/// MAC address of a hotspot client, from the kernel's ARP table
+pub fn client_mac(ip: &str) -> Option<String> {
+    let table = std::fs::read_to_string("/proc/net/arp").ok()?;
+    table.lines().skip(1).find_map(|line| {
+        // Format: IP HW-type Flags HW-address Mask Device
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        (parts.len() >= 6 && parts[0] == ip && parts[2] != "0x0").then(|| parts[3].to_lowercase())
+    })
+}
+
+/// (IP, MAC) of the hotspot interface's neighbours, for conflict detection
+pub fn neighbours_with_macs(config: &Config) -> Vec<(String, String)> {
+    Command::new("ip")
+        .args(["neigh", "show", "dev", &active_interface(config)])
+        .output()
+        .map(|o| ip_conflicts::parse_neighbours(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or_default()
+}
+
+/// MACs of other devices answering for the hotspot's gateway address. Needs
+/// iputils `arping` with raw socket rights, which distributions usually
+/// grant; without it nothing is found.
+pub fn gateway_claimants(config: &Config) -> Vec<String> {
+    let Some((gateway, _)) = parse_gateway_ip(&config.profile().gateway_ip) else {
+        return Vec::new();
+    };
+    // Duplicate address detection probes from 0.0.0.0, so only another
+    // device using the address answers
+    Command::new("arping")
+        .args(["-D", "-c", "1", "-w", "1", "-I", &active_interface(config), &gateway.to_string()])
+        .output()
+        .map(|o| ip_conflicts::parse_arping_replies(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or_default()
+}
+
+/// Clients on the AP, plus devices on the shared wired interface if enabled,
+/// with their MAC address and its vendor where the neighbour table has one
+pub fn get_connected_clients(config: &Config) -> Vec<Client> {
+    let mut clients = neighbours_on(&active_interface(config));
+    if !config.share_wired_interface.is_empty() {
+        clients.extend(neighbours_on(&config.share_wired_interface));
+    }
+    clients
+}
+
+fn neighbours_on(interface: &str) -> Vec<Client> {
     // Use "ip neigh show dev <interface>" which is more reliable than arp on modern Linux.
     // Output format: "192.168.44.2 lladdr aa:bb:cc:dd:ee:ff REACHABLE"
     let ip_result = Command::new("ip")
-        .args(["neigh", "show", "dev", &config.hotspot_interface])
+        .args(["neigh", "show", "dev", interface])
         .output()
         .map(|o| {
             let stdout = String::from_utf8_lossy(&o.stdout);
@@ -132,7 +1063,8 @@ pub fn get_connected_clients(config: &Config) -> Vec<String> {
                     let parts: Vec<&str> = line.split_whitespace().collect();
                     // Skip entries in FAILED state (stale/unreachable)
                     if parts.len() >= 4 && !line.contains("FAILED") {
-                        Some(parts[0].to_string())
+                        let mac = (parts[1] == "lladdr").then_some(parts[2]);
+                        Some(Client::new(parts[0], mac))
                     } else {
                         None
                     }
@@ -154,10 +1086,10 @@ pub fn get_connected_clients(config: &Config) -> Vec<String> {
                 .filter_map(|line| {
                     let parts: Vec<&str> = line.split_whitespace().collect();
                     // Format: IP HW-type Flags HW-address Mask Device
-                    if parts.len() >= 6 && parts[5] == config.hotspot_interface {
+                    if parts.len() >= 6 && parts[5] == interface {
                         // Skip incomplete entries (flags 0x0)
                         if parts[2] != "0x0" {
-                            Some(parts[0].to_string())
+                            Some(Client::new(parts[0], Some(parts[3])))
                         } else {
                             None
                         }
@@ -170,53 +1102,104 @@ pub fn get_connected_clients(config: &Config) -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// Number of pings in flight at once during a sweep
+const SWEEP_BATCH: usize = 16;
+
+/// Ping every host in the hotspot subnet once so that idle clients (phones with
+/// the screen off, headsets in standby) re-enter the neighbour table.
+///
+/// Pings are sent in small batches to keep the burst rate low. This blocks for
+/// several seconds on a /24, so callers should run it off the polling path.
+pub fn sweep_subnet(config: &Config) {
+    let Some((gateway, prefix)) = parse_gateway_ip(&config.profile().gateway_ip) else {
+        eprintln!("Client sweep skipped: invalid gateway IP '{}'", config.profile().gateway_ip);
+        return;
+    };
+    // Anything larger than a /22 would take minutes to sweep
+    if !(22..=30).contains(&prefix) {
+        eprintln!("Client sweep skipped: /{prefix} subnet not supported");
+        return;
+    }
+
+    let mask = u32::MAX << (32 - prefix);
+    let network = u32::from(gateway) & mask;
+    let broadcast = network | !mask;
+
+    let interface = active_interface(config);
+    let targets: Vec<String> = (network + 1..broadcast)
+        .map(std::net::Ipv4Addr::from)
+        .filter(|ip| *ip != gateway)
+        .map(|ip| ip.to_string())
+        .collect();
+
+    for batch in targets.chunks(SWEEP_BATCH) {
+        let children: Vec<_> = batch
+            .iter()
+            .filter_map(|ip| {
+                Command::new("ping")
+                    .args(["-c", "1", "-W", "1", "-q", "-I", &interface, ip])
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .spawn()
+                    .ok()
+            })
+            .collect();
+        for mut child in children {
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Split a gateway address like "192.168.44.1/24" into its address and prefix length
+pub fn parse_gateway_ip(gateway_ip: &str) -> Option<(std::net::Ipv4Addr, u32)> {
+    let (addr, prefix) = gateway_ip.split_once('/').unwrap_or((gateway_ip, "24"));
+    let addr = addr.trim().parse().ok()?;
+    let prefix = prefix.trim().parse().ok().filter(|p| *p <= 32)?;
+    Some((addr, prefix))
+}
+
+/// Whether NetworkManager knows about at least one WiFi adapter
+pub fn has_wifi_hardware() -> bool {
+    !list_wifi_interfaces().is_empty()
+}
+
 /// List available WiFi interfaces from NetworkManager
 pub fn list_wifi_interfaces() -> Vec<String> {
-    Command::new("nmcli")
-        .args(["-t", "-f", "DEVICE,TYPE", "device"])
-        .output()
-        .map(|o| {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout
-                .lines()
-                .filter_map(|line| {
-                    let parts: Vec<&str> = line.split(':').collect();
-                    if parts.len() >= 2 && parts[1] == "wifi" {
-                        Some(parts[0].to_string())
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        })
-        .unwrap_or_default()
+    nm_dbus::devices()
+        .into_iter()
+        .filter(|(_, kind)| *kind == nm_dbus::DEVICE_TYPE_WIFI)
+        .map(|(interface, _)| interface)
+        .collect()
+}
+
+/// WiFi interfaces with their AP / AP+client capabilities
+pub fn list_wifi_adapters() -> Vec<WifiAdapter> {
+    list_wifi_interfaces().iter().map(|name| adapters::probe(name)).collect()
+}
+
+/// What the internet interface is connected to, e.g. "Café-WiFi via wlp99s0"
+/// or "Ethernet"; `None` if it has no active connection
+pub fn upstream_description(config: &Config) -> Option<String> {
+    let active = nm_dbus::active_connections()
+        .into_iter()
+        .find(|active| active.interfaces.contains(&config.internet_interface))?;
+    let (name, device) = (&active.id, &config.internet_interface);
+    Some(match active.kind.as_str() {
+        "802-11-wireless" => format!("{name} via {device}"),
+        "802-3-ethernet" => "Ethernet".to_string(),
+        "gsm" | "cdma" => format!("Mobile broadband ({name})"),
+        _ => format!("{name} via {device}"),
+    })
 }
 
 /// List all network interfaces (for internet interface selection)
 pub fn list_network_interfaces() -> Vec<String> {
-    Command::new("nmcli")
-        .args(["-t", "-f", "DEVICE,TYPE,STATE", "device"])
-        .output()
-        .map(|o| {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout
-                .lines()
-                .filter_map(|line| {
-                    let parts: Vec<&str> = line.split(':').collect();
-                    if parts.len() >= 2 {
-                        let device = parts[0];
-                        let dev_type = parts[1];
-                        // Include wifi and ethernet devices, skip loopback and bridge
-                        if dev_type == "wifi" || dev_type == "ethernet" {
-                            Some(device.to_string())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect()
+    nm_dbus::devices()
+        .into_iter()
+        // Include wifi, ethernet and modem devices, skip loopback and bridge
+        .filter(|(_, kind)| {
+            matches!(*kind, nm_dbus::DEVICE_TYPE_WIFI | nm_dbus::DEVICE_TYPE_ETHERNET | nm_dbus::DEVICE_TYPE_MODEM)
         })
-        .unwrap_or_default()
+        .map(|(interface, _)| interface)
+        .collect()
 }
@@ -0,0 +1,77 @@
+//! Short-lived, cross-process cache of the interface lists.
+//!
+//! Listing WiFi adapters and network interfaces asks NetworkManager (and iw
+//! per adapter), which made opening the settings page and every
+//! `--settings-describe` noticeably slow. The lists are kept in the runtime
+//! dir for a few seconds so the applet, the settings page and the hub's
+//! helper processes share one enumeration; hotplug events drop the cache.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::WifiAdapter;
+use crate::backend;
+
+/// How long a listing is reused
+pub const TTL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interfaces {
+    /// All WiFi adapters, including ones that can't host an AP
+    pub wifi_adapters: Vec<WifiAdapter>,
+    pub network_interfaces: Vec<String>,
+}
+
+/// The cached lists if they're recent enough
+pub fn cached() -> Option<Interfaces> {
+    let path = cache_path()?;
+    let age = std::fs::metadata(&path).ok()?.modified().ok()?.elapsed().ok()?;
+    if age > TTL {
+        return None;
+    }
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+/// The cached lists, or a fresh listing if they're stale
+pub fn get() -> Interfaces {
+    cached().unwrap_or_else(refresh)
+}
+
+/// List the interfaces now and update the cache
+pub fn refresh() -> Interfaces {
+    let interfaces = Interfaces {
+        wifi_adapters: backend::active().wifi_adapters(),
+        network_interfaces: backend::active().network_interfaces(),
+    };
+    if let Some(path) = cache_path() {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&interfaces) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+    interfaces
+}
+
+/// Drop the cache, e.g. after an adapter was plugged in or removed
+pub fn invalidate() {
+    if let Some(path) = cache_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Separate files for the real and simulated backends, so switching between
+/// them never shows the other's devices
+fn cache_path() -> Option<PathBuf> {
+    let name = if backend::mock_requested() {
+        "interfaces-mock.json"
+    } else {
+        "interfaces.json"
+    };
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join("cosmic-hotspot").join(name))
+}
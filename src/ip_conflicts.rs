@@ -0,0 +1,131 @@
+//! IP address conflicts on the hotspot subnet.
+//!
+//! A client with a static address, or one that took the gateway's, breaks
+//! the hotspot for whoever else uses that address, which looks like the
+//! hotspot randomly stopping. Two things give it away: the neighbour table
+//! flipping an address between two MACs, and another device answering an
+//! ARP probe for the gateway's own address (`arping -D`).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a conflict stays listed after it was last seen, and how soon an
+/// address may change hands before that counts as a conflict
+pub const CONFLICT_WINDOW: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// `mac` answers for the hotspot's own gateway address
+    Gateway { ip: String, mac: String },
+    /// Two devices use the same address; MACs in order
+    Duplicate { ip: String, macs: [String; 2] },
+}
+
+impl Conflict {
+    /// For the popup and the notification, naming the offending MAC(s)
+    pub fn message(&self) -> String {
+        match self {
+            Self::Gateway { ip, mac } => {
+                format!("{mac} is using the hotspot's gateway address {ip}; clients lose their connection")
+            }
+            Self::Duplicate { ip, macs: [a, b] } => {
+                format!("{a} and {b} both use {ip}; one of them has a static address")
+            }
+        }
+    }
+}
+
+/// (IP, MAC) pairs of `ip neigh show` output, e.g.
+/// "192.168.44.2 lladdr aa:bb:cc:dd:ee:ff REACHABLE"; entries without a MAC
+/// (INCOMPLETE, FAILED) are skipped
+pub fn parse_neighbours(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter(|line| !line.contains("FAILED"))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let mac = parts.iter().position(|p| *p == "lladdr").and_then(|i| parts.get(i + 1))?;
+            Some((parts.first()?.to_string(), mac.to_lowercase()))
+        })
+        .collect()
+}
+
+/// MACs of the devices that answered `arping -D` for an address, i.e. that
+/// already use it: "Unicast reply from 192.168.44.1 [AA:BB:CC:DD:EE:FF]  1.2ms"
+pub fn parse_arping_replies(output: &str) -> Vec<String> {
+    let mut macs: Vec<String> = output
+        .lines()
+        .filter(|line| line.contains("reply from"))
+        .filter_map(|line| {
+            let (_, rest) = line.split_once('[')?;
+            let (mac, _) = rest.split_once(']')?;
+            Some(mac.to_lowercase())
+        })
+        .collect();
+    macs.dedup();
+    macs
+}
+
+/// Follows which MAC uses each address across polls
+#[derive(Default)]
+pub struct ConflictTracker {
+    /// Address → MAC last seen using it, and when
+    owners: HashMap<String, (String, Instant)>,
+    /// Conflicts and when they were last seen
+    conflicts: Vec<(Conflict, Instant)>,
+}
+
+impl ConflictTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record this poll's neighbours and the devices that answered for the
+    /// gateway address. Returns the conflicts not already listed, to notify
+    /// about.
+    pub fn observe(
+        &mut self,
+        neighbours: &[(String, String)],
+        gateway: &str,
+        gateway_claimants: &[String],
+        now: Instant,
+    ) -> Vec<Conflict> {
+        let mut found: Vec<Conflict> = gateway_claimants
+            .iter()
+            .map(|mac| Conflict::Gateway { ip: gateway.to_string(), mac: mac.clone() })
+            .collect();
+        for (ip, mac) in neighbours {
+            if let Some((previous, at)) = self.owners.get(ip) {
+                if previous != mac && now.duration_since(*at) < CONFLICT_WINDOW {
+                    let mut macs = [previous.clone(), mac.clone()];
+                    macs.sort();
+                    found.push(Conflict::Duplicate { ip: ip.clone(), macs });
+                }
+            }
+            self.owners.insert(ip.clone(), (mac.clone(), now));
+        }
+
+        self.conflicts.retain(|(_, seen)| now.duration_since(*seen) < CONFLICT_WINDOW);
+        let mut new = Vec::new();
+        for conflict in found {
+            match self.conflicts.iter_mut().find(|(c, _)| *c == conflict) {
+                Some((_, seen)) => *seen = now,
+                None => {
+                    self.conflicts.push((conflict.clone(), now));
+                    new.push(conflict);
+                }
+            }
+        }
+        new
+    }
+
+    /// Conflicts seen within `CONFLICT_WINDOW`
+    pub fn current(&self) -> Vec<Conflict> {
+        self.conflicts.iter().map(|(c, _)| c.clone()).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.owners.clear();
+        self.conflicts.clear();
+    }
+}
@@ -0,0 +1,203 @@
+//! Minimal Secret Service (GNOME Keyring / KWallet / KeePassXC) client.
+//!
+//! Items are found by attribute and stored in the default collection, using
+//! the "plain" session algorithm — the value only ever crosses the session
+//! bus, which is already private to the user.
+
+use std::collections::HashMap;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const BUS_NAME: &str = "org.freedesktop.secrets";
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const SERVICE_INTERFACE: &str = "org.freedesktop.Secret.Service";
+const DEFAULT_COLLECTION: &str = "/org/freedesktop/secrets/aliases/default";
+const COLLECTION_INTERFACE: &str = "org.freedesktop.Secret.Collection";
+const ITEM_INTERFACE: &str = "org.freedesktop.Secret.Item";
+const PROMPT_INTERFACE: &str = "org.freedesktop.Secret.Prompt";
+
+/// Secret Service `Secret` struct: (session, parameters, value, content type)
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+/// The stored value for `attributes`, `None` if there's no such item, or an
+/// error if no Secret Service is reachable
+pub fn lookup(attributes: &[(&str, &str)]) -> Result<Option<Vec<u8>>, String> {
+    let attributes = owned(attributes);
+    // Fresh thread: zbus' blocking API must not run inside the tokio worker
+    std::thread::spawn(move || {
+        let conn = connect()?;
+        let session = open_session(&conn)?;
+        let Some(item) = find_item(&conn, &attributes)? else {
+            return Ok(None);
+        };
+        let reply = conn
+            .call_method(Some(BUS_NAME), item.as_str(), Some(ITEM_INTERFACE), "GetSecret", &(&session,))
+            .map_err(|e| format!("Keyring read failed: {e}"))?;
+        let (_session, _params, value, _content_type): Secret =
+            reply.body().deserialize().map_err(|e| format!("Keyring read failed: {e}"))?;
+        Ok(Some(value))
+    })
+    .join()
+    .unwrap_or_else(|_| Err("Keyring lookup panicked".to_string()))
+}
+
+/// Create or replace the item for `attributes`
+pub fn store(label: &str, attributes: &[(&str, &str)], value: &[u8]) -> Result<(), String> {
+    let label = label.to_string();
+    let attributes = owned(attributes);
+    let value = value.to_vec();
+    std::thread::spawn(move || {
+        let conn = connect()?;
+        let session = open_session(&conn)?;
+        let mut properties: HashMap<&str, Value> = HashMap::new();
+        properties.insert("org.freedesktop.Secret.Item.Label", Value::from(label.as_str()));
+        properties.insert("org.freedesktop.Secret.Item.Attributes", Value::from(attributes.clone()));
+        let secret: Secret = (session, Vec::new(), value, "application/octet-stream".to_string());
+
+        let reply = conn
+            .call_method(
+                Some(BUS_NAME),
+                DEFAULT_COLLECTION,
+                Some(COLLECTION_INTERFACE),
+                "CreateItem",
+                &(properties, secret, true),
+            )
+            .map_err(|e| format!("Keyring write failed: {e}"))?;
+        let (_item, prompt): (OwnedObjectPath, OwnedObjectPath) =
+            reply.body().deserialize().map_err(|e| format!("Keyring write failed: {e}"))?;
+        run_prompt(&conn, &prompt)
+    })
+    .join()
+    .unwrap_or_else(|_| Err("Keyring store panicked".to_string()))
+}
+
+/// Remove the item for `attributes`, if there is one
+pub fn delete(attributes: &[(&str, &str)]) -> Result<(), String> {
+    let attributes = owned(attributes);
+    std::thread::spawn(move || {
+        let conn = connect()?;
+        let Some(item) = find_item(&conn, &attributes)? else {
+            return Ok(());
+        };
+        let reply = conn
+            .call_method(Some(BUS_NAME), item.as_str(), Some(ITEM_INTERFACE), "Delete", &())
+            .map_err(|e| format!("Keyring delete failed: {e}"))?;
+        let prompt: OwnedObjectPath =
+            reply.body().deserialize().map_err(|e| format!("Keyring delete failed: {e}"))?;
+        run_prompt(&conn, &prompt)
+    })
+    .join()
+    .unwrap_or_else(|_| Err("Keyring delete panicked".to_string()))
+}
+
+/// Whether a Secret Service is running or can be started on demand. Tells a
+/// failed lookup (locked, prompt dismissed, not up yet) apart from a session
+/// that has no keyring at all.
+pub fn installed() -> bool {
+    std::thread::spawn(|| {
+        let Ok(conn) = connect() else {
+            return false;
+        };
+        let owned = bus_call::<bool, _>(&conn, "NameHasOwner", &(BUS_NAME,)).unwrap_or(false);
+        owned
+            || bus_call::<Vec<String>, _>(&conn, "ListActivatableNames", &())
+                .is_some_and(|names| names.iter().any(|n| n == BUS_NAME))
+    })
+    .join()
+    .unwrap_or(false)
+}
+
+/// Call a method on the bus daemon itself
+fn bus_call<T, B>(conn: &Connection, method: &str, body: &B) -> Option<T>
+where
+    T: for<'de> serde::Deserialize<'de> + zbus::zvariant::Type,
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let reply = conn
+        .call_method(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            Some("org.freedesktop.DBus"),
+            method,
+            body,
+        )
+        .ok()?;
+    reply.body().deserialize().ok()
+}
+
+fn owned(attributes: &[(&str, &str)]) -> HashMap<String, String> {
+    attributes
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn connect() -> Result<Connection, String> {
+    Connection::session().map_err(|e| format!("No session bus: {e}"))
+}
+
+fn open_session(conn: &Connection) -> Result<OwnedObjectPath, String> {
+    let reply = conn
+        .call_method(
+            Some(BUS_NAME),
+            SERVICE_PATH,
+            Some(SERVICE_INTERFACE),
+            "OpenSession",
+            &("plain", Value::from("")),
+        )
+        .map_err(|e| format!("Keyring unavailable: {e}"))?;
+    let (_output, session): (OwnedValue, OwnedObjectPath) =
+        reply.body().deserialize().map_err(|e| format!("Keyring unavailable: {e}"))?;
+    Ok(session)
+}
+
+/// The first item matching `attributes`, unlocking it if needed
+fn find_item(
+    conn: &Connection,
+    attributes: &HashMap<String, String>,
+) -> Result<Option<OwnedObjectPath>, String> {
+    let reply = conn
+        .call_method(Some(BUS_NAME), SERVICE_PATH, Some(SERVICE_INTERFACE), "SearchItems", &(attributes,))
+        .map_err(|e| format!("Keyring search failed: {e}"))?;
+    let (unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) =
+        reply.body().deserialize().map_err(|e| format!("Keyring search failed: {e}"))?;
+    if let Some(item) = unlocked.into_iter().next() {
+        return Ok(Some(item));
+    }
+    let Some(item) = locked.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let reply = conn
+        .call_method(Some(BUS_NAME), SERVICE_PATH, Some(SERVICE_INTERFACE), "Unlock", &(vec![&item],))
+        .map_err(|e| format!("Keyring unlock failed: {e}"))?;
+    let (_unlocked, prompt): (Vec<OwnedObjectPath>, OwnedObjectPath) =
+        reply.body().deserialize().map_err(|e| format!("Keyring unlock failed: {e}"))?;
+    run_prompt(conn, &prompt)?;
+    Ok(Some(item))
+}
+
+/// Show a keyring prompt (e.g. the unlock dialog) and wait for the user.
+/// The path "/" means no prompt is needed.
+fn run_prompt(conn: &Connection, prompt: &ObjectPath<'_>) -> Result<(), String> {
+    if prompt.as_str() == "/" {
+        return Ok(());
+    }
+    let proxy = zbus::blocking::Proxy::new(conn, BUS_NAME, prompt.as_str(), PROMPT_INTERFACE)
+        .map_err(|e| format!("Keyring prompt failed: {e}"))?;
+    let mut completed = proxy
+        .receive_signal("Completed")
+        .map_err(|e| format!("Keyring prompt failed: {e}"))?;
+    proxy
+        .call_method("Prompt", &("",))
+        .map_err(|e| format!("Keyring prompt failed: {e}"))?;
+    let signal = completed.next().ok_or("Keyring prompt closed")?;
+    let (dismissed, _result): (bool, OwnedValue) =
+        signal.body().deserialize().map_err(|e| format!("Keyring prompt failed: {e}"))?;
+    if dismissed {
+        Err("Keyring prompt was dismissed".to_string())
+    } else {
+        Ok(())
+    }
+}
@@ -1,3 +1,55 @@
+pub mod activation_log;
+pub mod adapters;
+pub mod applet;
+pub mod applet_state;
+pub mod auth_log;
+pub mod automation;
+pub mod backend;
+pub mod card;
+pub mod channels;
+pub mod cli;
+pub mod clipboard;
+pub mod clients;
 pub mod config;
+pub mod dbus_service;
+pub mod diagnostics;
+pub mod dns_log;
+pub mod dry_run;
+pub mod errors;
+pub mod event_log;
+pub mod health;
+pub mod helper_service;
+pub mod hostapd_backend;
+pub mod hotplug;
 pub mod hotspot;
+pub mod interface_cache;
+pub mod ip_conflicts;
+pub mod keyring;
+pub mod local_dns;
+pub mod metrics;
+pub mod mock_backend;
+pub mod modem;
+pub mod nat;
+pub mod nm_dbus;
+pub mod notify;
+pub mod oui;
+pub mod password;
+pub mod privileged;
+pub mod profile_import;
+pub mod progress;
+pub mod qr;
+pub mod repeater;
+pub mod run_state;
+pub mod saved_networks;
+pub mod schedule;
+pub mod secrets;
+pub mod settings;
+pub mod settings_cli;
 pub mod settings_page;
+pub mod settings_path;
+pub mod shortcut;
+pub mod stats;
+pub mod stations;
+pub mod supervisor;
+pub mod usage;
+pub mod wol;
@@ -0,0 +1,54 @@
+//! Client names under the hotspot's own `.hotspot` domain.
+//!
+//! With `resources/local-domain.conf` dropped into dnsmasq's config directory
+//! (the helper's `local-domain` request), NetworkManager's dnsmasq registers
+//! the hostname a client sends with its DHCP request as `<hostname>.hotspot`
+//! and advertises the domain, so clients can reach each other by name. The
+//! hostnames themselves come from dnsmasq's lease file.
+
+use std::collections::HashMap;
+
+use crate::nat;
+
+/// The domain clients are registered under
+pub const DOMAIN: &str = "hotspot";
+/// Where NetworkManager keeps the leases of its shared dnsmasq instances
+const LEASES_DIR: &str = "/var/lib/NetworkManager";
+
+/// Whether dnsmasq is set up to serve the domain (from the next start)
+pub fn is_enabled() -> bool {
+    std::path::Path::new(nat::LOCAL_DOMAIN_CONF).exists()
+}
+
+/// IP → hostname from a dnsmasq lease file, whose lines read
+/// "<expiry> <mac> <ip> <hostname|*> <client-id|*>"
+pub fn parse_leases(leases: &str) -> HashMap<String, String> {
+    leases
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                [_, _, ip, hostname, ..] if *hostname != "*" => Some((ip.to_string(), hostname.to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Hostnames of the clients that got a lease on `interface`
+pub fn hostnames(interface: &str) -> HashMap<String, String> {
+    std::fs::read_to_string(format!("{LEASES_DIR}/dnsmasq-{interface}.leases"))
+        .map(|leases| parse_leases(&leases))
+        .unwrap_or_default()
+}
+
+/// The name a client resolves as, e.g. "quest.hotspot"; `None` if dnsmasq
+/// wouldn't register its hostname (not a valid DNS label)
+pub fn dns_name(hostname: &str) -> Option<String> {
+    let valid = !hostname.is_empty()
+        && hostname.len() <= 63
+        && !hostname.starts_with('-')
+        && !hostname.ends_with('-')
+        && hostname.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then(|| format!("{}.{DOMAIN}", hostname.to_lowercase()))
+}
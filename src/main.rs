@@ -1,9 +1,4 @@
-mod applet;
-mod config;
-mod hotspot;
-mod settings;
-mod settings_cli;
-mod settings_page;
+use cosmic_hotspot::{applet, cli, hotspot, settings, settings_cli};
 
 const APPLET_ID: &str = "io.github.reality2_roycdavies.cosmic-hotspot";
 
@@ -22,10 +17,74 @@ fn main() -> cosmic::iced::Result {
                 println!("cosmic-hotspot {}", env!("CARGO_PKG_VERSION"));
                 Ok(())
             }
+            "--start" => {
+                cli::start(has_flag(&args, "--dry-run"), busy_resolution_flag(&args));
+                Ok(())
+            }
+            "--stop" => {
+                cli::stop(has_flag(&args, "--dry-run"));
+                Ok(())
+            }
+            "--restart" | "restart" => {
+                cli::restart(has_flag(&args, "--dry-run"));
+                Ok(())
+            }
+            "--toggle" => {
+                cli::toggle(has_flag(&args, "--dry-run"));
+                Ok(())
+            }
+            "--self-test" => {
+                cli::self_test();
+                Ok(())
+            }
+            "--logs" | "logs" => {
+                cli::logs(has_flag(&args, "--follow"), has_flag(&args, "--json"));
+                Ok(())
+            }
+            "--clients" => {
+                cli::clients(has_flag(&args, "--json"));
+                Ok(())
+            }
+            "--metrics" => {
+                cli::metrics();
+                Ok(())
+            }
+            "--export-card" => {
+                if args.len() < 3 {
+                    eprintln!("Usage: cosmic-hotspot --export-card <file.pdf|file.png>");
+                    std::process::exit(1);
+                }
+                cli::export_card(&args[2]);
+                Ok(())
+            }
+            "--import-profile" => {
+                if args.len() < 3 {
+                    eprintln!("Usage: cosmic-hotspot --import-profile <file> [name]");
+                    std::process::exit(1);
+                }
+                cli::import_profile(&args[2], args.get(3).map_or("", String::as_str));
+                Ok(())
+            }
+            "--restore-backup" => {
+                cli::restore_backup(args.get(2).map(String::as_str));
+                Ok(())
+            }
+            "--migrate-secrets" => {
+                cli::migrate_secrets();
+                Ok(())
+            }
             "--settings-describe" => {
                 settings_cli::describe();
                 Ok(())
             }
+            "--settings-serve" => {
+                settings_cli::serve();
+                Ok(())
+            }
+            "--settings-status" => {
+                settings_cli::status();
+                Ok(())
+            }
             "--settings-set" => {
                 if args.len() < 4 {
                     eprintln!("Usage: cosmic-hotspot --settings-set <key> <json_value>");
@@ -53,6 +112,22 @@ fn main() -> cosmic::iced::Result {
     }
 }
 
+/// Whether `flag` follows the command argument
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args[2..].iter().any(|a| a == flag)
+}
+
+/// How `--start` should free an adapter that's connected as a client
+fn busy_resolution_flag(args: &[String]) -> Option<hotspot::BusyResolution> {
+    if has_flag(args, "--virtual-ap") {
+        Some(hotspot::BusyResolution::VirtualAp)
+    } else if has_flag(args, "--disconnect-client") {
+        Some(hotspot::BusyResolution::Disconnect)
+    } else {
+        None
+    }
+}
+
 /// Try to open settings via cosmic-applet-settings hub; fall back to standalone.
 fn open_settings() -> cosmic::iced::Result {
     use std::process::Command;
@@ -74,6 +149,25 @@ fn print_help(program: &str) {
     println!("  (none)             Run as COSMIC panel applet");
     println!("  --settings, -s     Open settings (via hub or standalone)");
     println!("  --settings-standalone  Open standalone settings window");
+    println!("  --start            Start the hotspot");
+    println!("    --disconnect-client  Disconnect the adapter's WiFi client connection first");
+    println!("    --virtual-ap     Keep the client connection; host the AP on a virtual interface");
+    println!("  --stop             Stop the hotspot");
+    println!("  --restart          Stop and start the hotspot again (also: restart)");
+    println!("  --toggle           Toggle the hotspot and notify the result");
+    println!("    --dry-run        Print the commands instead of running them");
+    println!("  --self-test        Check the setup and run a start/verify/stop cycle");
+    println!("  --export-card FILE Save a printable SSID/password/QR card (.pdf or .png)");
+    println!("  --logs             Show recent hotspot events (starts, stops, failures, repairs)");
+    println!("    --follow         Keep printing new events as they happen");
+    println!("    --json           One JSON object per event, for scripts");
+    println!("  --clients          List the hotspot's clients (--json for scripts)");
+    println!("  --metrics          Print state and last start's step timings for Prometheus");
+    println!("  --import-profile FILE [NAME]");
+    println!("                     Add a profile from a hostapd.conf, wpa_supplicant.conf or NM keyfile");
+    println!("  --restore-backup [FILE]");
+    println!("                     Undo a reset: restore the newest settings backup, or FILE");
+    println!("  --migrate-secrets  Move passwords from config.json into the keyring");
     println!("  --version, -v      Show version information");
     println!("  --help, -h         Show this help message");
     println!();
@@ -0,0 +1,58 @@
+//! The hotspot's state and start timings in Prometheus' text format.
+//!
+//! `cosmic-hotspot --metrics` prints these for node_exporter's textfile
+//! collector (or any scraper that runs a command), so step times can be
+//! graphed across kernel and driver updates.
+
+use std::fmt::Write;
+
+use crate::run_state::Timings;
+
+/// The metrics text for a hotspot that is `active` with `clients` clients,
+/// and the timings of the last start attempt
+pub fn render(active: bool, clients: usize, timings: Option<&Timings>) -> String {
+    let mut out = String::new();
+    gauge(&mut out, "cosmic_hotspot_up", "Whether the hotspot is running", &[(String::new(), u8::from(active).to_string())]);
+    gauge(&mut out, "cosmic_hotspot_clients", "Devices connected to the hotspot", &[(String::new(), clients.to_string())]);
+    let Some(timings) = timings else {
+        return out;
+    };
+    let steps: Vec<(String, String)> = timings
+        .steps
+        .iter()
+        .map(|t| (format!("{{step=\"{}\"}}", t.step.id()), seconds(t.millis)))
+        .collect();
+    gauge(&mut out, "cosmic_hotspot_start_step_seconds", "How long each step of the last start took", &steps);
+    gauge(
+        &mut out,
+        "cosmic_hotspot_start_seconds",
+        "How long the last start took",
+        &[(String::new(), seconds(timings.total_millis()))],
+    );
+    gauge(
+        &mut out,
+        "cosmic_hotspot_start_succeeded",
+        "Whether the last start succeeded",
+        &[(String::new(), u8::from(timings.succeeded).to_string())],
+    );
+    gauge(
+        &mut out,
+        "cosmic_hotspot_start_timestamp_seconds",
+        "Unix time of the last start attempt",
+        &[(String::new(), timings.at.to_string())],
+    );
+    out
+}
+
+/// A gauge's HELP and TYPE lines and one sample per (labels, value)
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(String, String)]) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{name}{labels} {value}");
+    }
+}
+
+fn seconds(millis: u64) -> String {
+    format!("{:.3}", millis as f64 / 1000.0)
+}
@@ -0,0 +1,457 @@
+//! Simulated hotspot backend for UI development, demos and screenshots.
+//!
+//! Enabled by setting `COSMIC_HOTSPOT_MOCK=1`. Nothing touches the system:
+//! activation takes a couple of seconds, fails at a configurable rate
+//! (`COSMIC_HOTSPOT_MOCK_FAILURE_RATE`, 0.0–1.0, default 0.1), and clients
+//! join and leave at random while the hotspot is up.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::adapters::{AdapterDetails, BandInfo, Rfkill, WifiAdapter};
+use crate::channels::NearbyAp;
+use crate::clients::Client;
+use crate::backend::HotspotBackend;
+use crate::config::{ClientPolicy, Config};
+use crate::dns_log::Lookup;
+use crate::errors::{ErrorKind, HotspotError};
+use crate::health::Problem;
+use crate::hotspot::{self, BusyResolution, ForeignHotspot};
+use crate::progress::{self, Step};
+use crate::repeater::{ClientLink, ScannedNetwork};
+use crate::saved_networks::SavedNetwork;
+use crate::stations::Radio;
+
+/// Environment variable holding the simulated activation failure rate
+pub const FAILURE_RATE_ENV: &str = "COSMIC_HOTSPOT_MOCK_FAILURE_RATE";
+
+const DEFAULT_FAILURE_RATE: f64 = 0.1;
+const MAX_CLIENTS: usize = 8;
+/// Per-poll chance that a new client joins / an existing one leaves
+const JOIN_CHANCE: f64 = 0.25;
+const LEAVE_CHANCE: f64 = 0.1;
+/// A neighbour with the old password retries about once a minute
+const FAILED_JOIN_INTERVAL: Duration = Duration::from_secs(60);
+const FAILED_JOIN_MAC: &str = "8a:2f:41:0c:77:e3";
+const MOCK_HOSTNAMES: &[&str] = &["Pixel-7", "quest", "iPad", "raspberrypi"];
+/// (connection name, SSID) of the simulated saved networks
+const MOCK_SAVED_NETWORKS: &[(&str, &str)] = &[("Home", "Home-WiFi"), ("Office-5G", "Office-5G")];
+/// Upper bound of simulated traffic per client between status polls
+const BYTES_PER_CLIENT_POLL: u64 = 1_500_000;
+/// Names the simulated clients look up
+const MOCK_LOOKUPS: &[&str] = &[
+    "connectivitycheck.gstatic.com",
+    "api.smartthings.com",
+    "time.cloudflare.com",
+    "mqtt.tuyaeu.com",
+    "www.wikipedia.org",
+];
+
+pub struct MockBackend {
+    failure_rate: f64,
+    state: Mutex<MockState>,
+}
+
+struct MockState {
+    active: bool,
+    started: Option<SystemTime>,
+    /// `Config::start_fingerprint` of the last start
+    settings: Option<u64>,
+    clients: Vec<String>,
+    /// Simulated byte counter of the hotspot interface
+    traffic: u64,
+    rng: u64,
+}
+
+impl MockState {
+    /// xorshift64* — plenty for jitter, and avoids a dependency
+    fn next_u64(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl MockBackend {
+    pub fn from_env() -> Self {
+        let failure_rate = std::env::var(FAILURE_RATE_ENV)
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|r| (0.0..=1.0).contains(r))
+            .unwrap_or(DEFAULT_FAILURE_RATE);
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+            | 1;
+        Self {
+            failure_rate,
+            state: Mutex::new(MockState {
+                active: false,
+                started: None,
+                settings: None,
+                clients: Vec::new(),
+                traffic: 0,
+                rng: seed,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Random join/leave churn, applied once per status poll
+    fn churn(&self, config: &Config, state: &mut MockState) {
+        if state.clients.len() < MAX_CLIENTS && state.next_f64() < JOIN_CHANCE {
+            if let Some(ip) = random_client_ip(config, state) {
+                if !state.clients.contains(&ip) {
+                    state.clients.push(ip);
+                }
+            }
+        }
+        if !state.clients.is_empty() && state.next_f64() < LEAVE_CHANCE {
+            let idx = (state.next_u64() % state.clients.len() as u64) as usize;
+            state.clients.remove(idx);
+        }
+    }
+}
+
+/// A host address inside the configured gateway subnet
+fn random_client_ip(config: &Config, state: &mut MockState) -> Option<String> {
+    let (gateway, prefix) = hotspot::parse_gateway_ip(&config.profile().gateway_ip)?;
+    let host_bits = 32 - prefix.clamp(8, 30);
+    let mask = u32::MAX << host_bits;
+    let network = u32::from(gateway) & mask;
+    let hosts = (1u32 << host_bits) - 2;
+    let host = 1 + (state.next_u64() % hosts as u64) as u32;
+    let ip = std::net::Ipv4Addr::from(network | host);
+    (ip != gateway).then(|| ip.to_string())
+}
+
+impl HotspotBackend for MockBackend {
+    fn start(&self, config: &Config) -> Result<String, HotspotError> {
+        let (delay, fail) = {
+            let mut state = self.lock();
+            let delay = Duration::from_millis(1500 + state.next_u64() % 1500);
+            (delay, state.next_f64() < self.failure_rate)
+        };
+        // Hold no lock while "activating" so status polls keep answering
+        for step in [Step::RemovingOldProfile, Step::CreatingProfile, Step::Activating, Step::ApplyingNat] {
+            progress::report(step);
+            std::thread::sleep(delay / 4);
+            if fail && step == Step::Activating {
+                return Err(HotspotError::new(
+                    ErrorKind::ActivationFailed,
+                    format!("Simulated activation failure on {}", config.hotspot_interface),
+                ));
+            }
+        }
+        let mut state = self.lock();
+        state.active = true;
+        state.started = Some(SystemTime::now());
+        state.settings = Some(config.start_fingerprint());
+        state.clients.clear();
+        Ok(format!(
+            "Hotspot '{}' active on {} (simulated)",
+            config.ssid, config.hotspot_interface
+        ))
+    }
+
+    fn start_resolving(
+        &self,
+        config: &Config,
+        _resolution: BusyResolution,
+    ) -> Result<String, HotspotError> {
+        self.start(config)
+    }
+
+    fn stop(&self, _config: &Config) -> Result<String, HotspotError> {
+        progress::report(Step::Deactivating);
+        std::thread::sleep(Duration::from_millis(500));
+        let mut state = self.lock();
+        state.active = false;
+        state.started = None;
+        state.clients.clear();
+        Ok("Hotspot stopped (simulated)".to_string())
+    }
+
+    fn is_active(&self, _config: &Config) -> bool {
+        self.lock().active
+    }
+
+    fn connected_clients(&self, config: &Config) -> Vec<Client> {
+        let mut state = self.lock();
+        if !state.active {
+            return Vec::new();
+        }
+        self.churn(config, &mut state);
+        state.clients.iter().map(|ip| Client::new(ip, self.client_mac(ip).as_deref())).collect()
+    }
+
+    fn neighbours(&self, _config: &Config) -> Vec<(String, String)> {
+        let clients = self.lock().clients.clone();
+        clients
+            .into_iter()
+            .filter_map(|ip| Some((ip.clone(), self.client_mac(&ip)?)))
+            .collect()
+    }
+
+    fn gateway_claimants(&self, _config: &Config) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn foreign_hotspot(&self, _config: &Config) -> Option<ForeignHotspot> {
+        None
+    }
+
+    fn adopt(&self, connection: &str) -> Result<String, HotspotError> {
+        Ok(format!("Adopted '{connection}' (simulated)"))
+    }
+
+    fn adopt_running(&self, _config: &Config) -> Option<String> {
+        // The simulated hotspot only ever starts through this backend
+        None
+    }
+
+    fn release_stopped(&self, _config: &Config) -> bool {
+        false
+    }
+
+    fn upstream(&self, config: &Config) -> Option<String> {
+        Some(format!("Café-WiFi via {}", config.internet_interface))
+    }
+
+    fn client_mac(&self, ip: &str) -> Option<String> {
+        // Stable per address: a locally administered MAC ending in the last octet
+        let last: u8 = ip.rsplit('.').next()?.parse().ok()?;
+        Some(format!("02:00:5e:c0:a8:{last:02x}"))
+    }
+
+    fn client_radios(&self, _config: &Config) -> HashMap<String, Radio> {
+        // Mostly 5 GHz, with every third client stuck on 2.4 GHz at a legacy-ish
+        // rate and a weak signal
+        self.lock()
+            .clients
+            .iter()
+            .filter_map(|ip| {
+                let last: u32 = ip.rsplit('.').next()?.parse().ok()?;
+                let radio = if last.is_multiple_of(3) {
+                    Radio { frequency_mhz: Some(2437), tx_rate_mbps: Some(72), generation: Some(4), signal_dbm: Some(-78) }
+                } else {
+                    Radio { frequency_mhz: Some(5180), tx_rate_mbps: Some(867), generation: Some(5), signal_dbm: Some(-52) }
+                };
+                Some((self.client_mac(ip)?, radio))
+            })
+            .collect()
+    }
+
+    fn client_hostnames(&self, _config: &Config) -> HashMap<String, String> {
+        // Every other client sends a hostname, like a mix of phones and IoT gadgets
+        self.lock()
+            .clients
+            .iter()
+            .filter_map(|ip| {
+                let last: usize = ip.rsplit('.').next()?.parse().ok()?;
+                let name = MOCK_HOSTNAMES.get(last % (2 * MOCK_HOSTNAMES.len()))?;
+                Some((ip.clone(), name.to_string()))
+            })
+            .collect()
+    }
+
+    fn update_client_policy(
+        &self,
+        _config: &Config,
+        _previous: Option<&ClientPolicy>,
+        current: Option<&ClientPolicy>,
+    ) -> Result<(), String> {
+        if let Some(policy) = current {
+            policy.client_access()?;
+            policy.schedule()?;
+        }
+        Ok(())
+    }
+
+    fn disconnect_client(&self, _config: &Config, mac: &str) -> Result<(), String> {
+        // Gone until churn happens to bring the address back
+        let mut state = self.lock();
+        let before = state.clients.len();
+        state.clients.retain(|ip| self.client_mac(ip).as_deref() != Some(mac));
+        if state.clients.len() == before {
+            return Err(format!("No client with MAC {mac}"));
+        }
+        Ok(())
+    }
+
+    fn supports_wps(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn wps_push_button(&self, _config: &Config, _enable: bool) -> Result<(), String> {
+        if self.lock().active {
+            Ok(())
+        } else {
+            Err("The hotspot isn't running".to_string())
+        }
+    }
+
+    fn sweep_clients(&self, _config: &Config) {}
+
+    fn has_wifi_hardware(&self) -> bool {
+        true
+    }
+
+    fn wifi_adapters(&self) -> Vec<WifiAdapter> {
+        vec![
+            WifiAdapter {
+                name: "wlan0".to_string(),
+                ap_capable: true,
+                concurrent_ap_sta: true,
+            },
+            WifiAdapter {
+                name: "wlx00c0ca000001".to_string(),
+                ap_capable: true,
+                concurrent_ap_sta: false,
+            },
+            WifiAdapter {
+                name: "wlx7cdd90000002".to_string(),
+                ap_capable: false,
+                concurrent_ap_sta: false,
+            },
+        ]
+    }
+
+    fn network_interfaces(&self) -> Vec<String> {
+        vec!["wlan0".to_string(), "wlx00c0ca000001".to_string(), "enp3s0".to_string()]
+    }
+
+    fn adapter_details(&self, device: &str) -> AdapterDetails {
+        let usb = device.starts_with("wlx");
+        AdapterDetails {
+            driver: Some(if usb { "mt7921u" } else { "iwlwifi" }.to_string()),
+            bands: vec![
+                BandInfo {
+                    name: "2.4 GHz",
+                    ap_channels: (1..=11).collect(),
+                    client_only_channels: vec![12, 13],
+                },
+                BandInfo {
+                    name: "5 GHz",
+                    ap_channels: vec![36, 40, 44, 48, 149, 153, 157, 161, 165],
+                    client_only_channels: vec![52, 56, 60, 64, 100, 104, 108, 112],
+                },
+            ],
+            max_ap_clients: usb.then_some(16),
+            concurrent_ap_sta: !usb,
+            rfkill: Rfkill::Unblocked,
+        }
+    }
+
+    fn saved_networks(&self) -> Vec<SavedNetwork> {
+        MOCK_SAVED_NETWORKS
+            .iter()
+            .enumerate()
+            .map(|(i, (name, ssid))| SavedNetwork {
+                uuid: format!("00000000-0000-4000-8000-{i:012}"),
+                name: name.to_string(),
+                ssid: ssid.to_string(),
+            })
+            .collect()
+    }
+
+    fn saved_network_password(&self, network: &SavedNetwork) -> Result<String, String> {
+        Ok(format!("{}-mock-password", network.name.to_lowercase()))
+    }
+
+    fn scan_access_points(&self, _interface: &str) -> Vec<NearbyAp> {
+        [(1, 62), (1, 35), (6, 81), (6, 44), (6, 20), (11, 57), (36, 48), (44, 30)]
+            .into_iter()
+            .map(|(channel, signal)| NearbyAp { channel, signal })
+            .collect()
+    }
+
+    fn scan_networks(&self, _interface: &str) -> Vec<ScannedNetwork> {
+        [("Café-WiFi", 78, true), ("Library-Guest", 54, false), ("DIRECT-printer", 31, true)]
+            .into_iter()
+            .map(|(ssid, signal, secured)| ScannedNetwork {
+                ssid: ssid.to_string(),
+                signal,
+                secured,
+            })
+            .collect()
+    }
+
+    fn client_link(&self, _interface: &str) -> Option<ClientLink> {
+        let signal = 60 + (self.lock().next_u64() % 20) as u32;
+        Some(ClientLink {
+            ssid: "Café-WiFi".to_string(),
+            signal,
+        })
+    }
+
+    fn failed_joins(&self, _interface: &str, since: SystemTime) -> Vec<String> {
+        let elapsed = since.elapsed().unwrap_or_default();
+        let attempts = (elapsed.as_secs() / FAILED_JOIN_INTERVAL.as_secs()) as usize;
+        vec![FAILED_JOIN_MAC.to_string(); attempts]
+    }
+
+    fn dns_lookups(&self, _since: SystemTime) -> Vec<Lookup> {
+        let mut state = self.lock();
+        let clients = state.clients.clone();
+        clients
+            .into_iter()
+            .flat_map(|client| {
+                let count = 1 + (state.next_u64() % 3) as usize;
+                let first = state.next_u64() as usize;
+                (0..count)
+                    .map(|i| Lookup {
+                        client: client.clone(),
+                        name: MOCK_LOOKUPS[(first + i) % MOCK_LOOKUPS.len()].to_string(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn traffic_bytes(&self, _config: &Config) -> Option<(u64, u64)> {
+        let mut state = self.lock();
+        // Up to 1.5 MB per client between 2s polls: about 3 Mbit/s each on average
+        let per_client = state.next_u64() % BYTES_PER_CLIENT_POLL;
+        state.traffic += per_client * state.clients.len() as u64;
+        // Clients mostly download, so the hotspot mostly sends
+        let received = state.traffic / 4;
+        Some((received, state.traffic - received))
+    }
+
+    fn started_at(&self, _config: &Config) -> Option<SystemTime> {
+        self.lock().started
+    }
+
+    fn uses_old_settings(&self, config: &Config) -> bool {
+        let state = self.lock();
+        state.active && state.settings.is_some_and(|s| s != config.start_fingerprint())
+    }
+
+    fn sharing_problems(&self, _config: &Config) -> Vec<Problem> {
+        Vec::new()
+    }
+
+    fn repair_sharing(&self, _config: &Config) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn autosuspending_usb_device(&self, _config: &Config) -> Option<String> {
+        None
+    }
+
+    fn hold_usb_power(&self, _config: &Config) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Cellular upstream status from ModemManager.
+//!
+//! When the internet interface belongs to a WWAN modem, the popup shows
+//! signal strength, access technology and data used this hotspot session —
+//! the constrained resources when tethering on the road.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+const MM_BUS_NAME: &str = "org.freedesktop.ModemManager1";
+const MM_PATH: &str = "/org/freedesktop/ModemManager1";
+const MM_MODEM_INTERFACE: &str = "org.freedesktop.ModemManager1.Modem";
+/// MM_MODEM_PORT_TYPE_NET: the port carrying IP traffic (e.g. wwan0)
+const PORT_TYPE_NET: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellularStatus {
+    /// Signal quality, 0–100
+    pub signal_percent: u32,
+    /// Most capable current access technology, e.g. "LTE" or "5G"
+    pub technology: String,
+    /// Network interface carrying the data, for byte counters
+    pub data_interface: String,
+}
+
+impl CellularStatus {
+    /// Four-step signal bars, e.g. "▂▄▆_"
+    pub fn bars(&self) -> String {
+        let filled = match self.signal_percent {
+            0 => 0,
+            1..=25 => 1,
+            26..=50 => 2,
+            51..=75 => 3,
+            _ => 4,
+        };
+        ["▂", "▄", "▆", "█"]
+            .iter()
+            .enumerate()
+            .map(|(i, bar)| if i < filled { *bar } else { "_" })
+            .collect()
+    }
+}
+
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+/// Status of the modem owning `interface`, or `None` if it isn't a modem
+/// (or ModemManager isn't running)
+pub fn status_for(interface: &str) -> Option<CellularStatus> {
+    let interface = interface.to_string();
+    // Fresh thread: zbus' blocking API must not run inside the tokio worker
+    std::thread::spawn(move || query(&interface))
+        .join()
+        .ok()
+        .flatten()
+}
+
+fn query(interface: &str) -> Option<CellularStatus> {
+    let conn = zbus::blocking::Connection::system().ok()?;
+    let reply = conn
+        .call_method(
+            Some(MM_BUS_NAME),
+            MM_PATH,
+            Some("org.freedesktop.DBus.ObjectManager"),
+            "GetManagedObjects",
+            &(),
+        )
+        .ok()?;
+    let objects: ManagedObjects = reply.body().deserialize().ok()?;
+
+    objects.values().find_map(|interfaces| {
+        let modem = interfaces.get(MM_MODEM_INTERFACE)?;
+        let ports: Vec<(String, u32)> = modem.get("Ports")?.try_clone().ok()?.try_into().ok()?;
+        if !ports.iter().any(|(name, _)| name == interface) {
+            return None;
+        }
+        let (signal_percent, _recent): (u32, bool) =
+            modem.get("SignalQuality")?.try_clone().ok()?.try_into().ok()?;
+        let access: u32 = modem
+            .get("AccessTechnologies")
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| v.try_into().ok())
+            .unwrap_or(0);
+        let data_interface = ports
+            .iter()
+            .find(|(_, kind)| *kind == PORT_TYPE_NET)
+            .map_or_else(|| interface.to_string(), |(name, _)| name.clone());
+
+        Some(CellularStatus {
+            signal_percent,
+            technology: technology_name(access).to_string(),
+            data_interface,
+        })
+    })
+}
+
+/// Name of the most capable technology in an MMModemAccessTechnology mask
+fn technology_name(mask: u32) -> &'static str {
+    const NAMES: &[(u32, &str)] = &[
+        (1 << 15, "5G"),
+        (1 << 14, "LTE"),
+        (1 << 16, "LTE-M"),
+        (1 << 17, "NB-IoT"),
+        (1 << 9, "HSPA+"),
+        ((1 << 8) | (1 << 7) | (1 << 6), "HSPA"),
+        (1 << 5, "3G"),
+        ((1 << 13) | (1 << 12) | (1 << 11), "EV-DO"),
+        (1 << 10, "1xRTT"),
+        (1 << 4, "EDGE"),
+        (1 << 3, "GPRS"),
+        ((1 << 2) | (1 << 1), "GSM"),
+    ];
+    NAMES
+        .iter()
+        .find(|(bits, _)| mask & bits != 0)
+        .map_or("Cellular", |(_, name)| name)
+}
+
+/// Combined rx+tx byte counter for an interface
+pub fn interface_bytes(interface: &str) -> Option<u64> {
+    let read = |counter: &str| -> Option<u64> {
+        std::fs::read_to_string(format!("/sys/class/net/{interface}/statistics/{counter}"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    Some(read("rx_bytes")? + read("tx_bytes")?)
+}
+
+/// Human-readable byte count, e.g. "154 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 100.0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
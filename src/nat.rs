@@ -0,0 +1,1417 @@
+//! NAT / forwarding / traffic-shaping rules applied by the privileged helper.
+//!
+//! The helper binary (`cosmic-hotspot-nat`) runs as root via pkexec, so every
+//! argument is validated here and rules are built as plain argv lists — no
+//! shell is ever involved. Keeping rule construction separate from execution
+//! also means the exact commands can be inspected without root.
+
+use std::process::Command;
+
+use crate::schedule::{self, Schedule};
+
+/// Name of the nftables table holding all hotspot rules
+const NFT_TABLE: &str = "cosmic_hotspot";
+/// firewalld zone the hotspot interface is put in, and the policy that
+/// forwards and masquerades its traffic
+const FIREWALLD_ZONE: &str = "cosmic-hotspot";
+const FIREWALLD_POLICY: &str = "cosmic-hotspot";
+/// The catch-all of firewalld's nm-shared zone: whatever the zone's services
+/// don't allow is rejected
+const FIREWALLD_REJECT_REST: &str = r#"--add-rich-rule=rule priority="32767" reject"#;
+/// Extra config read by the dnsmasq NetworkManager starts for shared
+/// connections
+const DNSMASQ_SHARED_DIR: &str = "/etc/NetworkManager/dnsmasq-shared.d";
+/// Link that turns on dnsmasq's query log, and the file it points to
+/// (installed with the helper)
+pub const QUERY_LOG_CONF: &str = "/etc/NetworkManager/dnsmasq-shared.d/cosmic-hotspot-query-log.conf";
+const QUERY_LOG_SOURCE: &str = "/usr/local/share/cosmic-hotspot/query-log.conf";
+/// Link that has dnsmasq serve client names under `.hotspot`, and the file
+/// it points to (installed with the helper)
+pub const LOCAL_DOMAIN_CONF: &str = "/etc/NetworkManager/dnsmasq-shared.d/cosmic-hotspot-local-domain.conf";
+const LOCAL_DOMAIN_SOURCE: &str = "/usr/local/share/cosmic-hotspot/local-domain.conf";
+/// Link that has dnsmasq forward DNS through systemd-resolved's stub, and
+/// the file it points to (installed with the helper)
+pub const HOST_RESOLVER_CONF: &str = "/etc/NetworkManager/dnsmasq-shared.d/cosmic-hotspot-host-resolver.conf";
+const HOST_RESOLVER_SOURCE: &str = "/usr/local/share/cosmic-hotspot/host-resolver.conf";
+/// Where systemd-resolved answers on the host, and the file that exists
+/// while it does
+pub const RESOLVED_STUB: &str = "127.0.0.53";
+pub const RESOLVED_STUB_RESOLV_CONF: &str = "/run/systemd/resolve/stub-resolv.conf";
+/// Extra DHCP options for shared connections, written by the `dhcp-options`
+/// request from the active profile
+pub const DHCP_OPTIONS_CONF: &str = "/etc/NetworkManager/dnsmasq-shared.d/cosmic-hotspot-dhcp-options.conf";
+/// Where each USB device's `power/control` (autosuspend) lives
+pub const USB_DEVICES_DIR: &str = "/sys/bus/usb/devices";
+/// Config, pid and lease files of the hostapd backend's hostapd and dnsmasq
+pub const HOSTAPD_RUN_DIR: &str = "/run/cosmic-hotspot";
+pub const HOSTAPD_CONF: &str = "/run/cosmic-hotspot/hostapd.conf";
+pub const HOSTAPD_PID: &str = "/run/cosmic-hotspot/hostapd.pid";
+pub const HOSTAPD_DNSMASQ_PID: &str = "/run/cosmic-hotspot/dnsmasq.pid";
+pub const HOSTAPD_LEASES: &str = "/run/cosmic-hotspot/dnsmasq.leases";
+/// hostapd's control sockets, for `hostapd_cli`
+pub const HOSTAPD_CTRL_DIR: &str = "/run/cosmic-hotspot/hostapd";
+
+/// Which firewall tool the NAT rules are written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Firewall {
+    Iptables,
+    Nftables,
+    /// A firewalld zone for the hotspot interface and a permanent policy
+    /// that forwards and masquerades from it, so a `firewall-cmd --reload`
+    /// doesn't wipe them
+    Firewalld,
+    /// ufw route/allow rules, plus an iptables MASQUERADE rule. ufw only
+    /// flushes and reloads its own `ufw-*` chains (with `iptables-restore
+    /// -n`), so `ufw reload` leaves a rule in the built-in nat POSTROUTING
+    /// chain alone; the `check` request notices if anything else drops it.
+    Ufw,
+}
+
+impl Firewall {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "iptables" => Ok(Self::Iptables),
+            "nftables" => Ok(Self::Nftables),
+            "firewalld" => Ok(Self::Firewalld),
+            "ufw" => Ok(Self::Ufw),
+            _ => Err(format!("Invalid firewall: {value:?}")),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Iptables => "iptables",
+            Self::Nftables => "nftables",
+            Self::Firewalld => "firewalld",
+            Self::Ufw => "ufw",
+        }
+    }
+}
+
+/// What a hotspot client may reach through the internet interface. The
+/// gateway and other hotspot clients stay reachable whatever the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientAccess {
+    /// Nothing is forwarded to the internet
+    NoInternet,
+    /// Only DNS and web traffic (HTTP, HTTPS, QUIC) is forwarded
+    WebOnly,
+    /// Forwarding to these (protocol, port) destinations is dropped
+    BlockPorts(Vec<(String, u16)>),
+}
+
+/// Destinations a `WebOnly` client may still reach
+const WEB_PORTS: &[(&str, u16)] = &[("udp", 53), ("tcp", 53), ("tcp", 80), ("tcp", 443), ("udp", 443)];
+
+impl ClientAccess {
+    /// Parse the helper's policy argument, with the port list for `block-ports`
+    pub fn parse(policy: &str, ports: Option<&str>) -> Result<Self, String> {
+        match (policy, ports) {
+            ("no-internet", None) => Ok(Self::NoInternet),
+            ("web-only", None) => Ok(Self::WebOnly),
+            ("block-ports", Some(ports)) => Ok(Self::BlockPorts(parse_port_list(ports)?)),
+            _ => Err(format!("Invalid client policy: {policy:?}")),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoInternet => "no-internet",
+            Self::WebOnly => "web-only",
+            Self::BlockPorts(_) => "block-ports",
+        }
+    }
+
+    /// The port list argument for `block-ports`, e.g. "tcp:22,udp:3478"
+    pub fn ports_arg(&self) -> Option<String> {
+        match self {
+            Self::BlockPorts(ports) => Some(
+                ports
+                    .iter()
+                    .map(|(protocol, port)| format!("{protocol}:{port}"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// What the hostapd backend hosts: hostapd's access point, and a dnsmasq
+/// serving DHCP and DNS on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPoint {
+    pub hotspot: String,
+    pub ssid: String,
+    pub password: String,
+    /// "wpa2", "wpa3" or "wpa2-wpa3"
+    pub security: String,
+    /// "bg" or "a"
+    pub band: String,
+    /// 0 = the band's usual default (6 or 36)
+    pub channel: u32,
+    pub gateway: std::net::Ipv4Addr,
+    pub prefix: u32,
+    pub dhcp_start: std::net::Ipv4Addr,
+    pub dhcp_end: std::net::Ipv4Addr,
+}
+
+impl AccessPoint {
+    /// hostapd.conf lines. Every value is validated to a single line, and
+    /// hostapd takes the rest of the line as the value.
+    pub fn hostapd_conf(&self) -> Vec<String> {
+        let (key_mgmt, pmf) = match self.security.as_str() {
+            "wpa3" => ("SAE", "2"),
+            "wpa2-wpa3" => ("WPA-PSK SAE", "1"),
+            _ => ("WPA-PSK", "0"),
+        };
+        let (hw_mode, default_channel) = if self.band == "a" { ("a", 36) } else { ("g", 6) };
+        let channel = if self.channel == 0 { default_channel } else { self.channel };
+        let mut lines = vec![
+            format!("interface={}", self.hotspot),
+            "driver=nl80211".to_string(),
+            format!("ssid={}", self.ssid),
+            "utf8_ssid=1".to_string(),
+            format!("hw_mode={hw_mode}"),
+            format!("channel={channel}"),
+            "ieee80211n=1".to_string(),
+            "wmm_enabled=1".to_string(),
+            "auth_algs=1".to_string(),
+            "wpa=2".to_string(),
+            format!("wpa_passphrase={}", self.password),
+            format!("wpa_key_mgmt={key_mgmt}"),
+            "rsn_pairwise=CCMP".to_string(),
+            format!("ieee80211w={pmf}"),
+            format!("ctrl_interface={HOSTAPD_CTRL_DIR}"),
+        ];
+        // WPS can't hand out SAE-only credentials
+        if self.supports_wps() {
+            lines.extend(["wps_state=2", "eap_server=1", "config_methods=push_button"].map(String::from));
+        }
+        lines
+    }
+
+    /// Whether push-button pairing can be offered on this access point
+    pub fn supports_wps(&self) -> bool {
+        self.security != "wpa3"
+    }
+
+    /// dnsmasq serving DHCP (and forwarding DNS) on the hotspot interface
+    /// only; through systemd-resolved while the host-resolver link is in place
+    pub fn dnsmasq_command(&self) -> Vec<String> {
+        let mut command = vec![
+            "dnsmasq".to_string(),
+            "--conf-file=/dev/null".to_string(),
+            format!("--interface={}", self.hotspot),
+            "--bind-interfaces".to_string(),
+            format!("--listen-address={}", self.gateway),
+            format!("--dhcp-range={},{},12h", self.dhcp_start, self.dhcp_end),
+            format!("--dhcp-option=option:router,{}", self.gateway),
+            format!("--dhcp-leasefile={HOSTAPD_LEASES}"),
+            format!("--pid-file={HOSTAPD_DNSMASQ_PID}"),
+        ];
+        if std::path::Path::new(HOST_RESOLVER_CONF).exists() {
+            command.push("--no-resolv".to_string());
+            command.push(format!("--server={RESOLVED_STUB}"));
+        }
+        command
+    }
+
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        let &[hotspot, ssid, password, security, band, channel, gateway, dhcp_start, dhcp_end] = args else {
+            return Err(usage());
+        };
+        let (gateway, prefix) = crate::hotspot::parse_gateway_ip(gateway)
+            .ok_or_else(|| format!("Invalid gateway address: {gateway}"))?;
+        let address = |a: &str| a.parse().map_err(|_| format!("Invalid DHCP range address: {a}"));
+        Ok(Self {
+            hotspot: validate_interface(hotspot)?,
+            ssid: validate_ssid(ssid)?,
+            password: validate_passphrase(password)?,
+            security: match security {
+                "wpa2" | "wpa3" | "wpa2-wpa3" => security.to_string(),
+                _ => return Err(format!("Invalid security mode: {security:?}")),
+            },
+            band: match band {
+                "bg" | "a" => band.to_string(),
+                _ => return Err(format!("Invalid band: {band:?}")),
+            },
+            channel: channel
+                .parse()
+                .ok()
+                .filter(|c| *c <= 196)
+                .ok_or_else(|| format!("Invalid channel: {channel}"))?,
+            gateway,
+            prefix,
+            dhcp_start: address(dhcp_start)?,
+            dhcp_end: address(dhcp_end)?,
+        })
+    }
+
+    fn to_args(&self) -> Vec<String> {
+        vec![
+            self.hotspot.clone(),
+            self.ssid.clone(),
+            self.password.clone(),
+            self.security.clone(),
+            self.band.clone(),
+            self.channel.to_string(),
+            format!("{}/{}", self.gateway, self.prefix),
+            self.dhcp_start.to_string(),
+            self.dhcp_end.to_string(),
+        ]
+    }
+}
+
+/// A validated helper request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NatRequest {
+    /// Enable forwarding and add MASQUERADE/FORWARD rules
+    Setup { hotspot: String, internet: String, firewall: Firewall },
+    /// Remove the rules added by `Setup`
+    Teardown { hotspot: String, internet: String, firewall: Firewall },
+    /// Succeed only if the rules added by `Setup` are all present
+    Check { hotspot: String, internet: String, firewall: Firewall },
+    /// Cap egress towards hotspot clients; a rate of 0 removes the limit
+    Limit { hotspot: String, rate_kbit: u32 },
+    /// DNAT a port on the internet interface to a hotspot client, which must
+    /// be in the hotspot subnet `gateway/prefix`
+    PortForward {
+        internet: String,
+        protocol: String,
+        port: u16,
+        destination: std::net::Ipv4Addr,
+        destination_port: u16,
+        gateway: std::net::Ipv4Addr,
+        prefix: u32,
+        remove: bool,
+    },
+    /// Soft-block or unblock all WiFi radios
+    Rfkill { block: bool },
+    /// Multicast handling on the AP: sending it to each client as unicast,
+    /// and the rate for what's still multicast (0 = driver default)
+    Multicast { hotspot: String, to_unicast: bool, rate_mbps: u32 },
+    /// Let the kernel autosuspend the USB device (e.g. "1-2"), or keep it
+    /// powered; autosuspend drops many USB adapters' access points
+    UsbAutosuspend { device: String, enable: bool },
+    /// Take the interface from NetworkManager and host the access point with
+    /// hostapd and dnsmasq
+    HostapdStart { ap: AccessPoint },
+    /// Stop what `HostapdStart` started and give the interface back
+    HostapdStop { hotspot: String },
+    /// Add (or remove) a virtual AP interface on the same radio as `parent`
+    VirtualAp { parent: String, name: String, remove: bool },
+    /// Redirect DNS queries from hotspot clients to `server`, which must be in
+    /// the hotspot subnet `gateway/prefix`
+    Dns { hotspot: String, server: std::net::Ipv4Addr, gateway: std::net::Ipv4Addr, prefix: u32, remove: bool },
+    /// Have dnsmasq log every client DNS lookup, from the next time a shared
+    /// connection comes up
+    QueryLog { enable: bool },
+    /// Have dnsmasq register client hostnames under `.hotspot` and advertise
+    /// the domain, from the next time a shared connection comes up
+    LocalDomain { enable: bool },
+    /// Have dnsmasq forward DNS through systemd-resolved instead of the
+    /// upstream servers, from the next time a shared connection comes up
+    HostResolver { enable: bool },
+    /// Serve these extra DHCP options (dnsmasq `dhcp-option` values) from the
+    /// next time a shared connection comes up; none removes them
+    DhcpOptions { options: Vec<String> },
+    /// Restrict what the client with this MAC address may reach
+    ClientAccess {
+        hotspot: String,
+        internet: String,
+        mac: String,
+        access: ClientAccess,
+        remove: bool,
+    },
+    /// Drop the client's internet traffic outside its allowed days and hours
+    ClientSchedule {
+        hotspot: String,
+        internet: String,
+        mac: String,
+        schedule: Schedule,
+        remove: bool,
+    },
+    /// Queue traffic to the client ahead of other clients' traffic
+    ClientPriority { hotspot: String, mac: String, remove: bool },
+    /// Open WPS push-button pairing on the AP (closes by itself after two
+    /// minutes), or cancel it
+    Wps { hotspot: String, enable: bool },
+    /// Deauthenticate the client with this MAC address from the AP; it may
+    /// rejoin, as it still knows the password
+    Deauth { hotspot: String, mac: String },
+}
+
+impl NatRequest {
+    /// Parse helper arguments (excluding argv[0])
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            ["setup", hotspot, internet, firewall @ ..] if firewall.len() <= 1 => Ok(Self::Setup {
+                hotspot: validate_interface(hotspot)?,
+                internet: validate_interface(internet)?,
+                firewall: firewall.first().map_or(Ok(Firewall::Iptables), |f| Firewall::parse(f))?,
+            }),
+            ["teardown", hotspot, internet, firewall @ ..] if firewall.len() <= 1 => Ok(Self::Teardown {
+                hotspot: validate_interface(hotspot)?,
+                internet: validate_interface(internet)?,
+                firewall: firewall.first().map_or(Ok(Firewall::Iptables), |f| Firewall::parse(f))?,
+            }),
+            ["check", hotspot, internet, firewall @ ..] if firewall.len() <= 1 => Ok(Self::Check {
+                hotspot: validate_interface(hotspot)?,
+                internet: validate_interface(internet)?,
+                firewall: firewall.first().map_or(Ok(Firewall::Iptables), |f| Firewall::parse(f))?,
+            }),
+            ["limit", hotspot, rate] => Ok(Self::Limit {
+                hotspot: validate_interface(hotspot)?,
+                rate_kbit: rate
+                    .parse()
+                    .map_err(|_| format!("Invalid rate: {rate}"))?,
+            }),
+            ["forward", action @ ("add" | "remove"), internet, protocol, port, destination, destination_port, subnet] => {
+                let (gateway, prefix) = parse_subnet(subnet)?;
+                Ok(Self::PortForward {
+                    internet: validate_interface(internet)?,
+                    protocol: validate_protocol(protocol)?,
+                    port: parse_port(port)?,
+                    destination: parse_subnet_host(destination, gateway, prefix)
+                        .map_err(|e| format!("Invalid destination address: {e}"))?,
+                    destination_port: parse_port(destination_port)?,
+                    gateway,
+                    prefix,
+                    remove: *action == "remove",
+                })
+            }
+            ["virtual-ap", action @ ("add" | "remove"), parent, name] => Ok(Self::VirtualAp {
+                parent: validate_interface(parent)?,
+                name: validate_interface(name)?,
+                remove: *action == "remove",
+            }),
+            ["dns", action @ ("add" | "remove"), hotspot, server, subnet] => {
+                let (gateway, prefix) = parse_subnet(subnet)?;
+                Ok(Self::Dns {
+                    hotspot: validate_interface(hotspot)?,
+                    server: parse_subnet_host(server, gateway, prefix)
+                        .map_err(|e| format!("Invalid DNS server address: {e}"))?,
+                    gateway,
+                    prefix,
+                    remove: *action == "remove",
+                })
+            }
+            ["client", action @ ("add" | "remove"), hotspot, internet, mac, policy, ports @ ..]
+                if ports.len() <= 1 =>
+            {
+                Ok(Self::ClientAccess {
+                    hotspot: validate_interface(hotspot)?,
+                    internet: validate_interface(internet)?,
+                    mac: validate_mac(mac)?,
+                    access: ClientAccess::parse(policy, ports.first().copied())?,
+                    remove: *action == "remove",
+                })
+            }
+            ["schedule", action @ ("add" | "remove"), hotspot, internet, mac, days, from, until] => {
+                Ok(Self::ClientSchedule {
+                    hotspot: validate_interface(hotspot)?,
+                    internet: validate_interface(internet)?,
+                    mac: validate_mac(mac)?,
+                    schedule: Schedule::parse(days, from, until)?,
+                    remove: *action == "remove",
+                })
+            }
+            ["priority", action @ ("add" | "remove"), hotspot, mac] => Ok(Self::ClientPriority {
+                hotspot: validate_interface(hotspot)?,
+                mac: validate_mac(mac)?,
+                remove: *action == "remove",
+            }),
+            ["wps", action @ ("on" | "off"), hotspot] => Ok(Self::Wps {
+                hotspot: validate_interface(hotspot)?,
+                enable: *action == "on",
+            }),
+            ["deauth", hotspot, mac] => Ok(Self::Deauth {
+                hotspot: validate_interface(hotspot)?,
+                mac: validate_mac(mac)?,
+            }),
+            ["dhcp-options", options @ ..] => Ok(Self::DhcpOptions {
+                options: options
+                    .iter()
+                    .map(|o| validate_dhcp_option(o))
+                    .collect::<Result<_, _>>()?,
+            }),
+            ["query-log", "on"] => Ok(Self::QueryLog { enable: true }),
+            ["query-log", "off"] => Ok(Self::QueryLog { enable: false }),
+            ["local-domain", "on"] => Ok(Self::LocalDomain { enable: true }),
+            ["local-domain", "off"] => Ok(Self::LocalDomain { enable: false }),
+            ["host-resolver", "on"] => Ok(Self::HostResolver { enable: true }),
+            ["host-resolver", "off"] => Ok(Self::HostResolver { enable: false }),
+            ["rfkill", "block"] => Ok(Self::Rfkill { block: true }),
+            ["rfkill", "unblock"] => Ok(Self::Rfkill { block: false }),
+            ["multicast", hotspot, mode @ ("unicast" | "multicast"), rate] => Ok(Self::Multicast {
+                hotspot: validate_interface(hotspot)?,
+                to_unicast: *mode == "unicast",
+                rate_mbps: validate_multicast_rate(rate)?,
+            }),
+            ["autosuspend", action @ ("on" | "off"), device] => Ok(Self::UsbAutosuspend {
+                device: validate_usb_device(device)?,
+                enable: *action == "on",
+            }),
+            ["hostapd", "start", ap @ ..] => Ok(Self::HostapdStart { ap: AccessPoint::parse(ap)? }),
+            ["hostapd", "stop", hotspot] => Ok(Self::HostapdStop { hotspot: validate_interface(hotspot)? }),
+            _ => Err(usage()),
+        }
+    }
+
+    /// The commands this request runs, in order
+    pub fn rules(&self) -> Vec<Rule> {
+        match self {
+            Self::Setup { hotspot, internet, firewall: Firewall::Iptables } => {
+                let mut rules = vec![Rule::always(&["sysctl", "-w", "net.ipv4.ip_forward=1"])];
+                rules.extend(
+                    forward_rules(hotspot, internet)
+                        .into_iter()
+                        .map(|r| Rule::iptables_ensure(&r)),
+                );
+                rules
+            }
+            Self::Setup { hotspot, internet, firewall: Firewall::Nftables } => {
+                let mut rules = vec![Rule::always(&["sysctl", "-w", "net.ipv4.ip_forward=1"])];
+                rules.extend(nft_setup_rules(hotspot, internet));
+                rules
+            }
+            Self::Setup { hotspot, internet, firewall: Firewall::Firewalld } => {
+                let mut rules = vec![Rule::always(&["sysctl", "-w", "net.ipv4.ip_forward=1"])];
+                rules.extend(firewalld_setup_rules(hotspot, internet));
+                rules
+            }
+            Self::Setup { hotspot, internet, firewall: Firewall::Ufw } => {
+                let mut rules = vec![Rule::always(&["sysctl", "-w", "net.ipv4.ip_forward=1"])];
+                rules.extend(forward_rules(hotspot, internet).first().map(|r| Rule::iptables_ensure(r)));
+                rules.extend(
+                    ufw_rules(hotspot, internet)
+                        .iter()
+                        .map(|r| Rule::always_owned(ufw_verb(r, false))),
+                );
+                rules
+            }
+            // The zone and policy stay defined; without the interface they
+            // match nothing
+            Self::Teardown { hotspot, firewall: Firewall::Firewalld, .. } => {
+                let zone = format!("--zone={FIREWALLD_ZONE}");
+                let interface = format!("--remove-interface={hotspot}");
+                vec![Rule::always(&["firewall-cmd", zone.as_str(), interface.as_str()]).ignore_failure()]
+            }
+            Self::Teardown { hotspot, internet, firewall: Firewall::Ufw } => {
+                let mut rules: Vec<Rule> = ufw_rules(hotspot, internet)
+                    .iter()
+                    .map(|r| Rule::always_owned(ufw_verb(r, true)).ignore_failure())
+                    .collect();
+                rules.extend(forward_rules(hotspot, internet).first().map(|r| Rule::iptables_remove(r)));
+                rules
+            }
+            Self::Teardown { hotspot, internet, firewall: Firewall::Iptables } => {
+                forward_rules(hotspot, internet)
+                    .into_iter()
+                    .map(|r| Rule::iptables_remove(&r))
+                    .collect()
+            }
+            Self::Teardown { firewall: Firewall::Nftables, .. } => {
+                vec![Rule::always(&["nft", "delete", "table", "ip", NFT_TABLE]).ignore_failure()]
+            }
+            Self::Check { hotspot, internet, firewall: Firewall::Iptables } => {
+                forward_rules(hotspot, internet)
+                    .iter()
+                    .map(|r| Rule::always_owned(iptables_verb("-C", r)))
+                    .collect()
+            }
+            Self::Check { firewall: Firewall::Nftables, .. } => {
+                vec![Rule::always(&["nft", "list", "table", "ip", NFT_TABLE])]
+            }
+            Self::Check { hotspot, firewall: Firewall::Firewalld, .. } => {
+                let zone = format!("--zone={FIREWALLD_ZONE}");
+                let interface = format!("--query-interface={hotspot}");
+                let policy = format!("--policy={FIREWALLD_POLICY}");
+                vec![
+                    Rule::always(&["firewall-cmd", zone.as_str(), interface.as_str()]),
+                    Rule::always(&["firewall-cmd", policy.as_str(), "--query-masquerade"]),
+                ]
+            }
+            // ufw has no scriptable query for single rules; the route rules
+            // persist with ufw's own, so only the MASQUERADE rule is checked
+            Self::Check { hotspot, internet, firewall: Firewall::Ufw } => {
+                forward_rules(hotspot, internet)
+                    .first()
+                    .map(|r| Rule::always_owned(iptables_verb("-C", r)))
+                    .into_iter()
+                    .collect()
+            }
+            Self::Limit { hotspot, rate_kbit: 0 } => {
+                vec![Rule::always(&["tc", "qdisc", "del", "dev", hotspot.as_str(), "root"]).ignore_failure()]
+            }
+            Self::Limit { hotspot, rate_kbit } => vec![Rule::always(&[
+                "tc",
+                "qdisc",
+                "replace",
+                "dev",
+                hotspot.as_str(),
+                "root",
+                "tbf",
+                "rate",
+                format!("{rate_kbit}kbit").as_str(),
+                "burst",
+                "32kbit",
+                "latency",
+                "400ms",
+            ])],
+            Self::PortForward {
+                internet,
+                protocol,
+                port,
+                destination,
+                destination_port,
+                remove,
+                ..
+            } => {
+                let port = port.to_string();
+                let target = format!("{destination}:{destination_port}");
+                let destination = destination.to_string();
+                let destination_port = destination_port.to_string();
+                let (internet, protocol) = (internet.as_str(), protocol.as_str());
+                let specs = [
+                    to_owned_args(&[
+                        "-t", "nat", "PREROUTING", "-i", internet, "-p", protocol,
+                        "--dport", port.as_str(), "-j", "DNAT", "--to-destination", target.as_str(),
+                    ]),
+                    to_owned_args(&[
+                        "FORWARD", "-i", internet, "-p", protocol, "-d", destination.as_str(),
+                        "--dport", destination_port.as_str(), "-j", "ACCEPT",
+                    ]),
+                ];
+                specs
+                    .iter()
+                    .map(|s| {
+                        if *remove {
+                            Rule::iptables_remove(s)
+                        } else {
+                            Rule::iptables_ensure(s)
+                        }
+                    })
+                    .collect()
+            }
+            Self::Dns { hotspot, server, remove, .. } => {
+                let target = format!("{server}:53");
+                ["udp", "tcp"]
+                    .into_iter()
+                    .map(|protocol| {
+                        let spec = to_owned_args(&[
+                            "-t", "nat", "PREROUTING", "-i", hotspot.as_str(), "-p", protocol,
+                            "--dport", "53", "-j", "DNAT", "--to-destination", target.as_str(),
+                        ]);
+                        if *remove {
+                            Rule::iptables_remove(&spec)
+                        } else {
+                            Rule::iptables_ensure(&spec)
+                        }
+                    })
+                    .collect()
+            }
+            Self::QueryLog { enable: true } => vec![
+                Rule::always(&["mkdir", "-p", DNSMASQ_SHARED_DIR]),
+                Rule::always(&["ln", "-sf", QUERY_LOG_SOURCE, QUERY_LOG_CONF]),
+            ],
+            Self::QueryLog { enable: false } => vec![Rule::always(&["rm", "-f", QUERY_LOG_CONF])],
+            Self::LocalDomain { enable: true } => vec![
+                Rule::always(&["mkdir", "-p", DNSMASQ_SHARED_DIR]),
+                Rule::always(&["ln", "-sf", LOCAL_DOMAIN_SOURCE, LOCAL_DOMAIN_CONF]),
+            ],
+            Self::LocalDomain { enable: false } => vec![Rule::always(&["rm", "-f", LOCAL_DOMAIN_CONF])],
+            Self::HostResolver { enable: true } => vec![
+                Rule::always(&["mkdir", "-p", DNSMASQ_SHARED_DIR]),
+                Rule::always(&["ln", "-sf", HOST_RESOLVER_SOURCE, HOST_RESOLVER_CONF]),
+            ],
+            Self::HostResolver { enable: false } => vec![Rule::always(&["rm", "-f", HOST_RESOLVER_CONF])],
+            Self::DhcpOptions { options } if options.is_empty() => {
+                vec![Rule::always(&["rm", "-f", DHCP_OPTIONS_CONF])]
+            }
+            Self::DhcpOptions { options } => {
+                // The options are arguments to printf, never part of the
+                // script, and are validated to a single line each
+                let mut write = to_owned_args(&[
+                    "sh",
+                    "-c",
+                    r#"printf 'dhcp-option=%s\n' "$@" > "$0""#,
+                    DHCP_OPTIONS_CONF,
+                ]);
+                write.extend(options.iter().cloned());
+                vec![
+                    Rule::always(&["mkdir", "-p", DNSMASQ_SHARED_DIR]),
+                    Rule::always_owned(write),
+                ]
+            }
+            Self::ClientAccess { hotspot, internet, mac, access, remove } => {
+                let specs = client_access_rules(hotspot, internet, mac, access);
+                if *remove {
+                    specs.iter().map(|s| Rule::iptables_remove(s)).collect()
+                } else {
+                    // Each rule is inserted at the top of FORWARD, ahead of
+                    // the hotspot's accept rules, so insert the last one first
+                    specs.iter().rev().map(|s| Rule::iptables_insert(s)).collect()
+                }
+            }
+            Self::ClientSchedule { hotspot, internet, mac, schedule, remove } => {
+                let specs = client_schedule_rules(hotspot, internet, mac, schedule);
+                if *remove {
+                    specs.iter().map(|s| Rule::iptables_remove(s)).collect()
+                } else {
+                    // Each rule is inserted at the top of FORWARD, ahead of
+                    // the hotspot's accept rules, so insert the last one first
+                    specs.iter().rev().map(|s| Rule::iptables_insert(s)).collect()
+                }
+            }
+            Self::ClientPriority { hotspot, mac, remove: false } => {
+                let handle = priority_filter_handle(mac);
+                vec![
+                    // A prio qdisc serves band 1:1 strictly first; it's shared
+                    // by all priority clients, so it may already be there
+                    Rule::always(&["tc", "qdisc", "add", "dev", hotspot.as_str(), "root", "handle", "1:", "prio"])
+                        .ignore_failure(),
+                    Rule::always(&[
+                        "tc", "filter", "replace", "dev", hotspot.as_str(), "parent", "1:", "protocol", "all",
+                        "prio", "1", "handle", handle.as_str(), "flower", "dst_mac", mac.as_str(),
+                        "classid", "1:1",
+                    ]),
+                ]
+            }
+            Self::ClientPriority { hotspot, mac, remove: true } => {
+                let handle = priority_filter_handle(mac);
+                vec![Rule::always(&[
+                    "tc", "filter", "del", "dev", hotspot.as_str(), "parent", "1:", "protocol", "all",
+                    "prio", "1", "handle", handle.as_str(), "flower",
+                ])
+                .ignore_failure()]
+            }
+            Self::Wps { hotspot, enable } => vec![Rule::always(&[
+                "hostapd_cli",
+                "-p",
+                HOSTAPD_CTRL_DIR,
+                "-i",
+                hotspot.as_str(),
+                if *enable { "wps_pbc" } else { "wps_cancel" },
+            ])],
+            // nl80211 does this for any AP, whether wpa_supplicant or hostapd runs it
+            Self::Deauth { hotspot, mac } => {
+                vec![Rule::always(&["iw", "dev", hotspot.as_str(), "station", "del", mac.as_str()])]
+            }
+            Self::Rfkill { block } => vec![Rule::always(&[
+                "rfkill",
+                if *block { "block" } else { "unblock" },
+                "wifi",
+            ])],
+            Self::Multicast { hotspot, to_unicast, rate_mbps } => {
+                let mut rules = vec![Rule::always(&[
+                    "iw",
+                    "dev",
+                    hotspot.as_str(),
+                    "set",
+                    "multicast_to_unicast",
+                    if *to_unicast { "on" } else { "off" },
+                ])];
+                if *rate_mbps > 0 {
+                    let rate = rate_mbps.to_string();
+                    rules.push(Rule::always(&["iw", "dev", hotspot.as_str(), "set", "mcast_rate", rate.as_str()]));
+                }
+                rules
+            }
+            Self::UsbAutosuspend { device, enable } => {
+                // The value is an argument to printf and the device name is
+                // validated, so nothing reaches the shell as script
+                let control = format!("{USB_DEVICES_DIR}/{device}/power/control");
+                vec![Rule::always(&[
+                    "sh",
+                    "-c",
+                    r#"printf '%s' "$1" > "$0""#,
+                    control.as_str(),
+                    if *enable { "auto" } else { "on" },
+                ])]
+            }
+            Self::HostapdStart { ap } => {
+                let hotspot = ap.hotspot.as_str();
+                let address = format!("{}/{}", ap.gateway, ap.prefix);
+                // The lines are arguments to printf, never part of the script;
+                // the file holds the passphrase, so only root may read it
+                let mut write = to_owned_args(&[
+                    "sh",
+                    "-c",
+                    r#"umask 077; printf '%s\n' "$@" > "$0""#,
+                    HOSTAPD_CONF,
+                ]);
+                write.extend(ap.hostapd_conf());
+                vec![
+                    Rule::always(&["mkdir", "-p", HOSTAPD_RUN_DIR]),
+                    Rule::always_owned(write),
+                    // Not running NetworkManager is fine
+                    Rule::always(&["nmcli", "device", "set", hotspot, "managed", "no"]).ignore_failure(),
+                    Rule::always(&["ip", "addr", "flush", "dev", hotspot]),
+                    Rule::always(&["hostapd", "-B", "-P", HOSTAPD_PID, HOSTAPD_CONF]),
+                    Rule::always(&["ip", "addr", "add", address.as_str(), "dev", hotspot]),
+                    Rule::always_owned(ap.dnsmasq_command()),
+                ]
+            }
+            Self::HostapdStop { hotspot } => {
+                let kill = |pid_file: &str| {
+                    Rule::always(&["sh", "-c", r#"kill "$(cat "$0")""#, pid_file]).ignore_failure()
+                };
+                vec![
+                    kill(HOSTAPD_DNSMASQ_PID),
+                    kill(HOSTAPD_PID),
+                    Rule::always(&["ip", "addr", "flush", "dev", hotspot.as_str()]).ignore_failure(),
+                    Rule::always(&["rm", "-f", HOSTAPD_CONF, HOSTAPD_PID, HOSTAPD_DNSMASQ_PID, HOSTAPD_LEASES]),
+                    Rule::always(&["nmcli", "device", "set", hotspot.as_str(), "managed", "yes"]).ignore_failure(),
+                ]
+            }
+            Self::VirtualAp { parent, name, remove: false } => vec![Rule {
+                unless: Some(to_owned_args(&["ip", "link", "show", name.as_str()])),
+                only_if: None,
+                command: to_owned_args(&[
+                    "iw", "dev", parent.as_str(), "interface", "add", name.as_str(), "type", "__ap",
+                ]),
+                ignore_failure: false,
+            }],
+            Self::VirtualAp { name, remove: true, .. } => vec![Rule {
+                unless: None,
+                only_if: Some(to_owned_args(&["ip", "link", "show", name.as_str()])),
+                command: to_owned_args(&["iw", "dev", name.as_str(), "del"]),
+                ignore_failure: false,
+            }],
+        }
+    }
+
+    /// The helper command-line arguments that encode this request
+    pub fn to_args(&self) -> Vec<String> {
+        match self {
+            Self::Setup { hotspot, internet, firewall } => vec![
+                "setup".into(),
+                hotspot.clone(),
+                internet.clone(),
+                firewall.as_str().into(),
+            ],
+            Self::Teardown { hotspot, internet, firewall } => vec![
+                "teardown".into(),
+                hotspot.clone(),
+                internet.clone(),
+                firewall.as_str().into(),
+            ],
+            Self::Check { hotspot, internet, firewall } => vec![
+                "check".into(),
+                hotspot.clone(),
+                internet.clone(),
+                firewall.as_str().into(),
+            ],
+            Self::Limit { hotspot, rate_kbit } => {
+                vec!["limit".into(), hotspot.clone(), rate_kbit.to_string()]
+            }
+            Self::PortForward {
+                internet,
+                protocol,
+                port,
+                destination,
+                destination_port,
+                gateway,
+                prefix,
+                remove,
+            } => vec![
+                "forward".into(),
+                if *remove { "remove" } else { "add" }.into(),
+                internet.clone(),
+                protocol.clone(),
+                port.to_string(),
+                destination.to_string(),
+                destination_port.to_string(),
+                format!("{gateway}/{prefix}"),
+            ],
+            Self::Dns { hotspot, server, gateway, prefix, remove } => vec![
+                "dns".into(),
+                if *remove { "remove" } else { "add" }.into(),
+                hotspot.clone(),
+                server.to_string(),
+                format!("{gateway}/{prefix}"),
+            ],
+            Self::QueryLog { enable } => {
+                vec!["query-log".into(), if *enable { "on" } else { "off" }.into()]
+            }
+            Self::LocalDomain { enable } => {
+                vec!["local-domain".into(), if *enable { "on" } else { "off" }.into()]
+            }
+            Self::HostResolver { enable } => {
+                vec!["host-resolver".into(), if *enable { "on" } else { "off" }.into()]
+            }
+            Self::DhcpOptions { options } => {
+                let mut args = vec!["dhcp-options".to_string()];
+                args.extend(options.iter().cloned());
+                args
+            }
+            Self::ClientAccess { hotspot, internet, mac, access, remove } => {
+                let mut args = vec![
+                    "client".into(),
+                    if *remove { "remove" } else { "add" }.into(),
+                    hotspot.clone(),
+                    internet.clone(),
+                    mac.clone(),
+                    access.as_str().into(),
+                ];
+                args.extend(access.ports_arg());
+                args
+            }
+            Self::ClientSchedule { hotspot, internet, mac, schedule, remove } => vec![
+                "schedule".into(),
+                if *remove { "remove" } else { "add" }.into(),
+                hotspot.clone(),
+                internet.clone(),
+                mac.clone(),
+                schedule.days_arg(),
+                schedule::format_time(schedule.from),
+                schedule::format_time(schedule.until),
+            ],
+            Self::ClientPriority { hotspot, mac, remove } => vec![
+                "priority".into(),
+                if *remove { "remove" } else { "add" }.into(),
+                hotspot.clone(),
+                mac.clone(),
+            ],
+            Self::Wps { hotspot, enable } => {
+                vec!["wps".into(), if *enable { "on" } else { "off" }.into(), hotspot.clone()]
+            }
+            Self::Deauth { hotspot, mac } => vec!["deauth".into(), hotspot.clone(), mac.clone()],
+            Self::Rfkill { block } => {
+                vec!["rfkill".into(), if *block { "block" } else { "unblock" }.into()]
+            }
+            Self::Multicast { hotspot, to_unicast, rate_mbps } => vec![
+                "multicast".into(),
+                hotspot.clone(),
+                if *to_unicast { "unicast" } else { "multicast" }.into(),
+                rate_mbps.to_string(),
+            ],
+            Self::UsbAutosuspend { device, enable } => {
+                vec!["autosuspend".into(), if *enable { "on" } else { "off" }.into(), device.clone()]
+            }
+            Self::HostapdStart { ap } => {
+                let mut args = vec!["hostapd".to_string(), "start".to_string()];
+                args.extend(ap.to_args());
+                args
+            }
+            Self::HostapdStop { hotspot } => vec!["hostapd".into(), "stop".into(), hotspot.clone()],
+            Self::VirtualAp { parent, name, remove } => vec![
+                "virtual-ap".into(),
+                if *remove { "remove" } else { "add" }.into(),
+                parent.clone(),
+                name.clone(),
+            ],
+        }
+    }
+
+    /// Run every rule, stopping at the first failure
+    pub fn apply(&self) -> Result<(), String> {
+        for rule in self.rules() {
+            rule.run()?;
+        }
+        Ok(())
+    }
+}
+
+pub fn usage() -> String {
+    "Usage: cosmic-hotspot-nat setup <hotspot_if> <internet_if> [iptables|nftables|firewalld|ufw]\n       \
+     cosmic-hotspot-nat teardown <hotspot_if> <internet_if> [iptables|nftables|firewalld|ufw]\n       \
+     cosmic-hotspot-nat check <hotspot_if> <internet_if> [iptables|nftables|firewalld|ufw]\n       \
+     cosmic-hotspot-nat limit <hotspot_if> <rate_kbit>\n       \
+     cosmic-hotspot-nat forward add|remove <internet_if> tcp|udp <port> <client_ip> <client_port> <gateway/prefix>\n       \
+     cosmic-hotspot-nat dns add|remove <hotspot_if> <server_ip> <gateway/prefix>\n       \
+     cosmic-hotspot-nat query-log on|off\n       \
+     cosmic-hotspot-nat local-domain on|off\n       \
+     cosmic-hotspot-nat host-resolver on|off\n       \
+     cosmic-hotspot-nat dhcp-options [option:<name>,<value> ...]\n       \
+     cosmic-hotspot-nat client add|remove <hotspot_if> <internet_if> <mac> \
+     no-internet|web-only|block-ports [tcp:<port>,udp:<port>,...]\n       \
+     cosmic-hotspot-nat schedule add|remove <hotspot_if> <internet_if> <mac> <days> <HH:MM> <HH:MM>\n       \
+     cosmic-hotspot-nat priority add|remove <hotspot_if> <mac>\n       \
+     cosmic-hotspot-nat wps on|off <hotspot_if>\n       \
+     cosmic-hotspot-nat deauth <hotspot_if> <mac>\n       \
+     cosmic-hotspot-nat rfkill block|unblock\n       \
+     cosmic-hotspot-nat multicast <hotspot_if> unicast|multicast <rate_mbps>\n       \
+     cosmic-hotspot-nat autosuspend on|off <usb_device>\n       \
+     cosmic-hotspot-nat virtual-ap add|remove <parent_if> <virtual_if>\n       \
+     cosmic-hotspot-nat hostapd start <hotspot_if> <ssid> <passphrase> wpa2|wpa3|wpa2-wpa3 bg|a \
+     <channel> <gateway/prefix> <dhcp_start> <dhcp_end>\n       \
+     cosmic-hotspot-nat hostapd stop <hotspot_if>\n       \
+     cosmic-hotspot-nat --dbus-service"
+        .to_string()
+}
+
+/// The hotspot subnet as `gateway/prefix`. Anything wider than a /8 can't be
+/// a hotspot's, and would let forwards and DNS redirects reach anywhere.
+fn parse_subnet(subnet: &str) -> Result<(std::net::Ipv4Addr, u32), String> {
+    crate::hotspot::parse_gateway_ip(subnet)
+        .filter(|(_, prefix)| (8..=30).contains(prefix))
+        .ok_or_else(|| format!("Invalid hotspot subnet: {subnet}"))
+}
+
+/// An address in the subnet of `gateway/prefix`, other than its network and
+/// broadcast addresses
+fn parse_subnet_host(addr: &str, gateway: std::net::Ipv4Addr, prefix: u32) -> Result<std::net::Ipv4Addr, String> {
+    let parsed: std::net::Ipv4Addr = addr.parse().map_err(|_| addr.to_string())?;
+    let mask = u32::MAX << (32 - prefix);
+    let host = u32::from(parsed) & !mask;
+    if u32::from(parsed) & mask != u32::from(gateway) & mask || host == 0 || host == !mask {
+        return Err(format!("{addr} is outside the hotspot subnet {gateway}/{prefix}"));
+    }
+    Ok(parsed)
+}
+
+/// Linux interface names: 1-15 chars, no '/', whitespace or leading '-'.
+/// We're stricter than the kernel and only allow [A-Za-z0-9_.-].
+pub fn validate_interface(name: &str) -> Result<String, String> {
+    let valid = !name.is_empty()
+        && name.len() <= 15
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("Invalid interface name: {name:?}"))
+    }
+}
+
+/// Legacy rates a multicast rate can be set to, in Mbit/s (0 = driver default)
+pub const MULTICAST_RATES: &[u32] = &[0, 1, 2, 6, 9, 11, 12, 18, 24, 36, 48, 54];
+
+pub fn validate_multicast_rate(rate: &str) -> Result<u32, String> {
+    rate.parse()
+        .ok()
+        .filter(|r| MULTICAST_RATES.contains(r))
+        .ok_or_else(|| format!("Invalid multicast rate: {rate:?} (Mbit/s: {MULTICAST_RATES:?})"))
+}
+
+/// A USB device's sysfs name: bus, then the port path, e.g. "1-2" or "3-1.4"
+pub fn validate_usb_device(device: &str) -> Result<String, String> {
+    let numbers = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let valid = device
+        .split_once('-')
+        .is_some_and(|(bus, ports)| numbers(bus) && ports.split('.').all(numbers));
+    if valid {
+        Ok(device.to_string())
+    } else {
+        Err(format!("Invalid USB device: {device:?}"))
+    }
+}
+
+/// An SSID hostapd can read back from its config: 1-32 bytes, no control
+/// characters (so no line breaks)
+pub fn validate_ssid(ssid: &str) -> Result<String, String> {
+    if (1..=32).contains(&ssid.len()) && !ssid.chars().any(char::is_control) {
+        Ok(ssid.to_string())
+    } else {
+        Err(format!("Invalid SSID: {ssid:?}"))
+    }
+}
+
+/// A WPA passphrase: 8-63 printable ASCII characters
+pub fn validate_passphrase(passphrase: &str) -> Result<String, String> {
+    if (8..=63).contains(&passphrase.len()) && passphrase.chars().all(|c| (' '..='~').contains(&c)) {
+        Ok(passphrase.to_string())
+    } else {
+        Err("Invalid passphrase: must be 8-63 printable ASCII characters".to_string())
+    }
+}
+
+pub fn validate_protocol(protocol: &str) -> Result<String, String> {
+    match protocol {
+        "tcp" | "udp" => Ok(protocol.to_string()),
+        _ => Err(format!("Invalid protocol: {protocol:?}")),
+    }
+}
+
+/// A MAC address as six ':'-separated hex pairs, normalized to lowercase
+pub fn validate_mac(mac: &str) -> Result<String, String> {
+    let parts: Vec<&str> = mac.split(':').collect();
+    let valid = parts.len() == 6
+        && parts
+            .iter()
+            .all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()));
+    if valid {
+        Ok(mac.to_ascii_lowercase())
+    } else {
+        Err(format!("Invalid MAC address: {mac:?}"))
+    }
+}
+
+/// A dnsmasq `dhcp-option` value: "option:<name>,<value>" or
+/// "<number>,<value>", e.g. "option:ntp-server,192.168.44.1" or
+/// "option:classless-static-route,10.20.0.0/16,192.168.44.2". Values are
+/// limited to addresses, routes and domain names.
+pub fn validate_dhcp_option(option: &str) -> Result<String, String> {
+    let option = option.trim();
+    let invalid = || format!("Invalid DHCP option {option:?}: expected option:<name>,<value>");
+    let (key, value) = option.split_once(',').ok_or_else(invalid)?;
+    let key_valid = match key.strip_prefix("option:") {
+        Some(name) => !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'),
+        None => key.parse::<u8>().is_ok_and(|n| (1..=254).contains(&n)),
+    };
+    let value_valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | ',' | ':' | '/' | '-' | '_'));
+    if key_valid && value_valid {
+        Ok(option.to_string())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// What the `dhcp-options` request writes for `options`
+pub fn dhcp_options_conf(options: &[String]) -> String {
+    options.iter().map(|o| format!("dhcp-option={o}\n")).collect()
+}
+
+/// "tcp:22, udp:3478" → [("tcp", 22), ("udp", 3478)]
+pub fn parse_port_list(list: &str) -> Result<Vec<(String, u16)>, String> {
+    let ports = list
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (protocol, port) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("Invalid port entry {entry:?}: expected tcp:<port> or udp:<port>"))?;
+            Ok((validate_protocol(protocol.trim())?, parse_port(port.trim())?))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if ports.is_empty() {
+        return Err("No ports to block".to_string());
+    }
+    Ok(ports)
+}
+
+fn parse_port(port: &str) -> Result<u16, String> {
+    port.parse()
+        .ok()
+        .filter(|p| *p != 0)
+        .ok_or_else(|| format!("Invalid port: {port}"))
+}
+
+/// iptables rule specs (without the -A/-C/-D verb) for hotspot forwarding
+fn forward_rules(hotspot: &str, internet: &str) -> Vec<Vec<String>> {
+    vec![
+        to_owned_args(&["-t", "nat", "POSTROUTING", "-o", internet, "-j", "MASQUERADE"]),
+        to_owned_args(&["FORWARD", "-i", hotspot, "-o", internet, "-j", "ACCEPT"]),
+        to_owned_args(&[
+            "FORWARD", "-i", internet, "-o", hotspot,
+            "-m", "state", "--state", "RELATED,ESTABLISHED", "-j", "ACCEPT",
+        ]),
+    ]
+}
+
+/// Make the zone the internet interface (`$0`) is in, or the default zone,
+/// the only egress zone of the policy (`$1`), permanently and at runtime. The
+/// names are arguments, never part of the script.
+const FIREWALLD_SET_EGRESS: &str = r#"interface=$0 policy=$1
+zone=$(firewall-cmd --get-zone-of-interface="$interface") || zone=$(firewall-cmd --get-default-zone) || exit 1
+egress() {
+    for old in $(firewall-cmd "$@" --policy="$policy" --list-egress-zones); do
+        [ "$old" = "$zone" ] || firewall-cmd "$@" --policy="$policy" --remove-egress-zone="$old" || return 1
+    done
+    firewall-cmd "$@" --policy="$policy" --add-egress-zone="$zone"
+}
+egress --permanent && egress"#;
+
+/// Put `hotspot` in the hotspot zone, and define the zone and the policy
+/// forwarding and masquerading its traffic out through `internet`'s zone.
+/// Like firewalld's own nm-shared zone, the hotspot zone only lets clients
+/// reach DHCP and DNS on the gateway and rejects the rest; the policy doesn't
+/// reach the LAN or VPN zones. Both are permanent-only objects, so firewalld
+/// is reloaded when the runtime zone doesn't match yet.
+fn firewalld_setup_rules(hotspot: &str, internet: &str) -> Vec<Rule> {
+    let firewall_cmd = |args: &[&str]| {
+        let mut argv = vec!["firewall-cmd".to_string()];
+        argv.extend(to_owned_args(args));
+        argv
+    };
+    let zone = format!("--zone={FIREWALLD_ZONE}");
+    let policy = format!("--policy={FIREWALLD_POLICY}");
+    let ingress = format!("--add-ingress-zone={FIREWALLD_ZONE}");
+    let interface = format!("--change-interface={hotspot}");
+    let create = |kind: &str, name: &str| Rule {
+        unless: Some(firewall_cmd(&["--permanent", &format!("--info-{kind}={name}")])),
+        only_if: None,
+        command: firewall_cmd(&["--permanent", &format!("--new-{kind}={name}")]),
+        ignore_failure: false,
+    };
+    vec![
+        create("zone", FIREWALLD_ZONE),
+        create("policy", FIREWALLD_POLICY),
+        // Setting what's already set is only a warning
+        Rule::always_owned(firewall_cmd(&["--permanent", &zone, "--set-target=default"])),
+        Rule::always_owned(firewall_cmd(&["--permanent", &zone, "--add-service=dhcp"])),
+        Rule::always_owned(firewall_cmd(&["--permanent", &zone, "--add-service=dns"])),
+        Rule::always_owned(firewall_cmd(&["--permanent", &zone, FIREWALLD_REJECT_REST])),
+        Rule::always_owned(firewall_cmd(&["--permanent", &policy, &ingress])),
+        Rule::always_owned(firewall_cmd(&["--permanent", &policy, "--set-target=ACCEPT"])),
+        Rule::always_owned(firewall_cmd(&["--permanent", &policy, "--add-masquerade"])),
+        // Also brings zones set up by older versions, which accepted
+        // everything, up to date
+        Rule {
+            unless: Some(firewall_cmd(&[&zone, &FIREWALLD_REJECT_REST.replace("--add-", "--query-")])),
+            only_if: None,
+            command: firewall_cmd(&["--reload"]),
+            ignore_failure: false,
+        },
+        Rule::always(&["sh", "-c", FIREWALLD_SET_EGRESS, internet, FIREWALLD_POLICY]),
+        Rule::always_owned(firewall_cmd(&[&zone, &interface])),
+    ]
+}
+
+/// What ufw must allow for the hotspot: forwarding to the internet, plus
+/// DHCP and DNS to the gateway's dnsmasq (ufw drops incoming by default)
+fn ufw_rules(hotspot: &str, internet: &str) -> Vec<Vec<String>> {
+    vec![
+        to_owned_args(&["route", "allow", "in", "on", hotspot, "out", "on", internet]),
+        to_owned_args(&["allow", "in", "on", hotspot, "to", "any", "port", "67", "proto", "udp"]),
+        to_owned_args(&["allow", "in", "on", hotspot, "to", "any", "port", "53"]),
+    ]
+}
+
+/// "ufw <spec>", or the matching delete: "ufw [route] delete <rule>"
+fn ufw_verb(spec: &[String], delete: bool) -> Vec<String> {
+    let mut argv = vec!["ufw".to_string()];
+    let rest = match spec.split_first() {
+        Some((route, rest)) if route == "route" => {
+            argv.push(route.clone());
+            rest
+        }
+        _ => spec,
+    };
+    if delete {
+        argv.push("delete".to_string());
+    }
+    argv.extend_from_slice(rest);
+    argv
+}
+
+/// nftables equivalent of `forward_rules`, kept in a dedicated table so
+/// teardown is a single `delete table`
+fn nft_setup_rules(hotspot: &str, internet: &str) -> Vec<Rule> {
+    vec![
+        Rule::always(&["nft", "add", "table", "ip", NFT_TABLE]),
+        Rule::always(&[
+            "nft", "add", "chain", "ip", NFT_TABLE, "postrouting",
+            "{ type nat hook postrouting priority 100 ; }",
+        ]),
+        Rule::always(&[
+            "nft", "add", "chain", "ip", NFT_TABLE, "forward",
+            "{ type filter hook forward priority 0 ; }",
+        ]),
+        // Start from a clean table so repeated setups don't stack rules
+        Rule::always(&["nft", "flush", "table", "ip", NFT_TABLE]),
+        Rule::always(&[
+            "nft", "add", "rule", "ip", NFT_TABLE, "postrouting",
+            "oifname", internet, "masquerade",
+        ]),
+        Rule::always(&[
+            "nft", "add", "rule", "ip", NFT_TABLE, "forward",
+            "iifname", hotspot, "oifname", internet, "accept",
+        ]),
+        Rule::always(&[
+            "nft", "add", "rule", "ip", NFT_TABLE, "forward",
+            "iifname", internet, "oifname", hotspot,
+            "ct", "state", "related,established", "accept",
+        ]),
+    ]
+}
+
+/// FORWARD rule specs for a client policy, in the order they must match
+fn client_access_rules(hotspot: &str, internet: &str, mac: &str, access: &ClientAccess) -> Vec<Vec<String>> {
+    let from_client = ["FORWARD", "-i", hotspot, "-o", internet, "-m", "mac", "--mac-source", mac];
+    let with = |tail: &[&str]| {
+        let mut spec = to_owned_args(&from_client);
+        spec.extend(to_owned_args(tail));
+        spec
+    };
+    match access {
+        ClientAccess::NoInternet => vec![with(&["-j", "DROP"])],
+        ClientAccess::WebOnly => {
+            let mut specs: Vec<Vec<String>> = WEB_PORTS
+                .iter()
+                .map(|(protocol, port)| {
+                    with(&["-p", *protocol, "--dport", port.to_string().as_str(), "-j", "ACCEPT"])
+                })
+                .collect();
+            specs.push(with(&["-j", "DROP"]));
+            specs
+        }
+        ClientAccess::BlockPorts(ports) => ports
+            .iter()
+            .map(|(protocol, port)| {
+                with(&["-p", protocol.as_str(), "--dport", port.to_string().as_str(), "-j", "DROP"])
+            })
+            .collect(),
+    }
+}
+
+/// FORWARD rule specs dropping a client's traffic outside its schedule.
+/// `--kerneltz` makes the times local: systemd sets the kernel's timezone at
+/// boot and when it changes.
+fn client_schedule_rules(hotspot: &str, internet: &str, mac: &str, schedule: &Schedule) -> Vec<Vec<String>> {
+    let from_client = ["FORWARD", "-i", hotspot, "-o", internet, "-m", "mac", "--mac-source", mac];
+    let mut specs = Vec::new();
+    if let Some(days) = schedule.iptables_blocked_days() {
+        let mut spec = to_owned_args(&from_client);
+        spec.extend(to_owned_args(&["-m", "time", "--weekdays", days.as_str(), "--kerneltz", "-j", "DROP"]));
+        specs.push(spec);
+    }
+    if schedule.from != schedule.until {
+        // The blocked part of the day runs from the window's end to its start
+        // (wrapping past midnight when that's earlier)
+        let mut spec = to_owned_args(&from_client);
+        spec.extend(to_owned_args(&[
+            "-m", "time",
+            "--timestart", schedule::format_time(schedule.until).as_str(),
+            "--timestop", schedule::format_time(schedule.from).as_str(),
+            "--kerneltz", "-j", "DROP",
+        ]));
+        specs.push(spec);
+    }
+    specs
+}
+
+/// The tc filter handle for a priority client: the low 32 bits of its MAC
+/// address (already validated), so each client's filter can be removed alone
+fn priority_filter_handle(mac: &str) -> String {
+    let low: String = mac.split(':').skip(2).collect();
+    let handle = u32::from_str_radix(&low, 16).unwrap_or(0).max(1);
+    format!("{handle:#x}")
+}
+
+fn to_owned_args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+/// A single command, optionally guarded by a check command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// Only run `command` if this check fails (i.e. the rule isn't present yet)
+    pub unless: Option<Vec<String>>,
+    /// Only run `command` if this check succeeds (i.e. the rule is present)
+    pub only_if: Option<Vec<String>>,
+    pub command: Vec<String>,
+    pub ignore_failure: bool,
+}
+
+impl Rule {
+    fn always(argv: &[&str]) -> Self {
+        Self::always_owned(to_owned_args(argv))
+    }
+
+    fn always_owned(command: Vec<String>) -> Self {
+        Self {
+            unless: None,
+            only_if: None,
+            command,
+            ignore_failure: false,
+        }
+    }
+
+    fn ignore_failure(mut self) -> Self {
+        self.ignore_failure = true;
+        self
+    }
+
+    /// `iptables -C <spec> || iptables -A <spec>`
+    fn iptables_ensure(spec: &[String]) -> Self {
+        Self {
+            unless: Some(iptables_verb("-C", spec)),
+            only_if: None,
+            command: iptables_verb("-A", spec),
+            ignore_failure: false,
+        }
+    }
+
+    /// `iptables -C <spec> || iptables -I <spec>`: like `iptables_ensure`, but
+    /// ahead of the existing rules in the chain
+    fn iptables_insert(spec: &[String]) -> Self {
+        Self {
+            unless: Some(iptables_verb("-C", spec)),
+            only_if: None,
+            command: iptables_verb("-I", spec),
+            ignore_failure: false,
+        }
+    }
+
+    /// `iptables -C <spec> && iptables -D <spec>`
+    fn iptables_remove(spec: &[String]) -> Self {
+        Self {
+            unless: None,
+            only_if: Some(iptables_verb("-C", spec)),
+            command: iptables_verb("-D", spec),
+            ignore_failure: false,
+        }
+    }
+
+    /// Shell-equivalent rendering of this rule, for dry runs and logs
+    pub fn describe(&self) -> String {
+        let command = self.command.join(" ");
+        if let Some(check) = &self.unless {
+            format!("{} || {command}", check.join(" "))
+        } else if let Some(check) = &self.only_if {
+            format!("{} && {command}", check.join(" "))
+        } else if self.ignore_failure {
+            format!("{command} || true")
+        } else {
+            command
+        }
+    }
+
+    pub fn run(&self) -> Result<(), String> {
+        if let Some(check) = &self.unless {
+            if run_quiet(check) {
+                return Ok(());
+            }
+        }
+        if let Some(check) = &self.only_if {
+            if !run_quiet(check) {
+                return Ok(());
+            }
+        }
+
+        let output = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .output()
+            .map_err(|e| format!("Failed to run {}: {e}", self.command[0]))?;
+        if !output.status.success() && !self.ignore_failure {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{} failed: {stderr}", self.command.join(" ")));
+        }
+        Ok(())
+    }
+}
+
+/// Insert the iptables verb after the optional "-t <table>" prefix
+fn iptables_verb(verb: &str, spec: &[String]) -> Vec<String> {
+    let mut argv = vec!["iptables".to_string()];
+    let rest = if spec.first().map(String::as_str) == Some("-t") {
+        argv.extend_from_slice(&spec[..2]);
+        &spec[2..]
+    } else {
+        spec
+    };
+    argv.push(verb.to_string());
+    argv.extend_from_slice(rest);
+    argv
+}
+
+fn run_quiet(argv: &[String]) -> bool {
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
@@ -0,0 +1,32 @@
+//! Privileged NAT helper, run as root via pkexec or as a D-Bus activated
+//! system service (`--dbus-service`).
+//!
+//! Installed to /usr/local/bin/cosmic-hotspot-nat by `just install-policy`.
+
+use cosmic_hotspot::helper_service;
+use cosmic_hotspot::nat::NatRequest;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("--dbus-service") {
+        if let Err(e) = helper_service::run() {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let request = match NatRequest::parse(&args) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = request.apply() {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,668 @@
+//! NetworkManager client over D-Bus.
+//!
+//! Talks to `org.freedesktop.NetworkManager` on the system bus: connection
+//! settings go over as typed dictionaries rather than nmcli properties,
+//! answers come back as object paths and state codes rather than terse text
+//! to parse, and failures keep NM's D-Bus error name. Calls that change
+//! anything only print what they'd do in dry-run mode.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use zbus::blocking::{Connection, MessageIterator};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use crate::dry_run;
+
+const BUS_NAME: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const NM_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const DEVICE_INTERFACE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS_INTERFACE: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const AP_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const ACTIVE_INTERFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+/// NM_DEVICE_STATE_ACTIVATED
+pub const DEVICE_ACTIVATED: u32 = 100;
+/// NMDeviceType values
+pub const DEVICE_TYPE_ETHERNET: u32 = 1;
+pub const DEVICE_TYPE_WIFI: u32 = 2;
+pub const DEVICE_TYPE_MODEM: u32 = 8;
+/// NMDeviceWifiCapabilities flag for access point mode
+const WIFI_CAP_AP: u32 = 0x80;
+/// NM80211ApFlags privacy bit (WEP)
+const AP_FLAG_PRIVACY: u32 = 0x1;
+/// NM80211ApSecurityFlags key management bits
+const AP_SEC_PSK: u32 = 0x100;
+const AP_SEC_SAE: u32 = 0x400;
+/// How long a rescan may take before the previous results are used
+const SCAN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+/// NMActiveConnectionState values
+const ACTIVE_ACTIVATED: u32 = 2;
+const ACTIVE_DEACTIVATED: u32 = 4;
+/// NMActiveConnectionStateReason values meaning the device itself failed
+const DEVICE_REASONS: &[u32] = &[3, 13, 14];
+
+/// Errors meaning nothing owns NM's bus name
+const NOT_RUNNING_ERRORS: &[&str] =
+    &["org.freedesktop.DBus.Error.ServiceUnknown", "org.freedesktop.DBus.Error.NameHasNoOwner"];
+/// NM errors about the device rather than the connection
+const DEVICE_ERRORS: &[&str] = &[
+    "org.freedesktop.NetworkManager.UnknownDevice",
+    "org.freedesktop.NetworkManager.ConnectionNotAvailable",
+];
+const UNKNOWN_CONNECTION: &str = "org.freedesktop.NetworkManager.UnknownConnection";
+/// Signals queued per subscription before older ones are dropped; one is
+/// enough to wake the listener
+const SIGNAL_QUEUE: usize = 16;
+
+/// Connection settings as NM takes them: setting → property → value
+pub type Settings = HashMap<&'static str, HashMap<&'static str, Value<'static>>>;
+/// Connection settings as NM returns them
+pub type OwnedSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+/// A failed NetworkManager call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NmError {
+    /// The system bus or NetworkManager couldn't be reached
+    Unavailable(String),
+    /// NM refused the call: its D-Bus error name and message
+    Rejected { name: String, message: String },
+    /// The connection went down while activating; an NMActiveConnectionStateReason
+    ActivationFailed(u32),
+}
+
+impl NmError {
+    /// Whether the failure is about the device (missing, busy, gone) rather
+    /// than the connection
+    pub fn is_device_problem(&self) -> bool {
+        match self {
+            Self::Rejected { name, .. } => {
+                DEVICE_ERRORS.contains(&name.as_str())
+                    || name.starts_with("org.freedesktop.NetworkManager.Device.")
+            }
+            Self::ActivationFailed(reason) => DEVICE_REASONS.contains(reason),
+            Self::Unavailable(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for NmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable(detail) => write!(f, "{detail}"),
+            Self::Rejected { name, message } if message.is_empty() => write!(f, "{name}"),
+            Self::Rejected { message, .. } => write!(f, "{message}"),
+            Self::ActivationFailed(reason) => {
+                write!(f, "Connection activation failed: {}", reason_text(*reason))
+            }
+        }
+    }
+}
+
+impl From<zbus::Error> for NmError {
+    fn from(e: zbus::Error) -> Self {
+        match e {
+            zbus::Error::MethodError(name, _, _) if NOT_RUNNING_ERRORS.contains(&name.as_str()) => {
+                Self::Unavailable("NetworkManager is not running".to_string())
+            }
+            zbus::Error::MethodError(name, message, _) => {
+                Self::Rejected { name: name.to_string(), message: message.unwrap_or_default() }
+            }
+            other => Self::Unavailable(format!("NetworkManager call failed: {other}")),
+        }
+    }
+}
+
+/// What an NMActiveConnectionStateReason means
+pub fn reason_text(reason: u32) -> &'static str {
+    match reason {
+        2 => "deactivated by the user",
+        3 => "the device disconnected",
+        4 => "the service providing the connection stopped",
+        5 => "the IP configuration was invalid",
+        6 => "the connection timed out",
+        7 => "the service providing the connection timed out",
+        8 => "the service providing the connection failed to start",
+        9 => "secrets were required but not provided",
+        10 => "authentication failed",
+        11 => "the connection was removed",
+        12 => "a connection it depends on failed",
+        13 => "the device couldn't be created",
+        14 => "the device was removed",
+        _ => "unknown reason",
+    }
+}
+
+/// A connection NM has active (or is activating)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveConnection {
+    /// The activation's object path; new each time the connection comes up
+    pub path: String,
+    pub id: String,
+    /// Setting type, e.g. "802-11-wireless" or "802-3-ethernet"
+    pub kind: String,
+    /// NMActiveConnectionState
+    pub state: u32,
+    pub interfaces: Vec<String>,
+}
+
+impl ActiveConnection {
+    pub fn is_activated(&self) -> bool {
+        self.state == ACTIVE_ACTIVATED
+    }
+}
+
+/// An access point seen by a WiFi device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPoint {
+    /// Empty for hidden networks
+    pub ssid: String,
+    /// Signal strength, 0–100
+    pub strength: u32,
+    /// Center frequency in MHz
+    pub frequency: u32,
+    /// Whether joining needs a key (WEP, WPA or a login)
+    pub secured: bool,
+    /// Whether it only takes WPA3 SAE, not a WPA2 pre-shared key
+    pub sae_only: bool,
+}
+
+impl AccessPoint {
+    /// The channel number of the access point's frequency; 0 if unknown
+    pub fn channel(&self) -> u32 {
+        frequency_channel(self.frequency)
+    }
+}
+
+/// The WiFi channel of a center frequency in MHz; 0 if it isn't one
+pub fn frequency_channel(frequency: u32) -> u32 {
+    match frequency {
+        2484 => 14,
+        2412..=2472 => (frequency - 2407) / 5,
+        5955..=7115 => (frequency - 5950) / 5,
+        5000..=5925 => (frequency - 5000) / 5,
+        _ => 0,
+    }
+}
+
+/// Whether NetworkManager owns its bus name. Asks the bus rather than NM so
+/// a stopped NM isn't started by D-Bus activation.
+pub fn running() -> bool {
+    on_bus(|conn| {
+        let reply = conn.call_method(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            Some("org.freedesktop.DBus"),
+            "NameHasOwner",
+            &(BUS_NAME,),
+        )?;
+        Ok(reply.body().deserialize::<bool>()?)
+    })
+    .unwrap_or(false)
+}
+
+/// Every active connection; empty if NM can't be reached
+pub fn active_connections() -> Vec<ActiveConnection> {
+    on_bus(active_list).unwrap_or_default()
+}
+
+/// The state (an NMDeviceState) of `interface` and the id of its active
+/// connection ("" if none)
+pub fn device_state(interface: &str) -> Option<(u32, String)> {
+    let interface = interface.to_string();
+    on_bus(move |conn| {
+        let device = device_path(conn, &interface)?;
+        let state = property(conn, device.as_str(), DEVICE_INTERFACE, "State")?;
+        let active: OwnedObjectPath = property(conn, device.as_str(), DEVICE_INTERFACE, "ActiveConnection")?;
+        let id = match active.as_str() {
+            "/" => String::new(),
+            path => property(conn, path, ACTIVE_INTERFACE, "Id").unwrap_or_default(),
+        };
+        Ok((state, id))
+    })
+    .ok()
+}
+
+/// Interface name and NMDeviceType of every device NM knows
+pub fn devices() -> Vec<(String, u32)> {
+    on_bus(|conn| {
+        let reply = call(conn, NM_PATH, NM_INTERFACE, "GetDevices", &())?;
+        let paths: Vec<OwnedObjectPath> = reply.body().deserialize()?;
+        Ok(paths
+            .iter()
+            .filter_map(|path| {
+                let interface = property(conn, path.as_str(), DEVICE_INTERFACE, "Interface").ok()?;
+                let kind = property(conn, path.as_str(), DEVICE_INTERFACE, "DeviceType").ok()?;
+                Some((interface, kind))
+            })
+            .collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Whether the WiFi device `interface` can host an access point; `None`
+/// if NM doesn't know the device
+pub fn ap_capable(interface: &str) -> Option<bool> {
+    let interface = interface.to_string();
+    on_bus(move |conn| {
+        let device = device_path(conn, &interface)?;
+        let capabilities: u32 = property(conn, device.as_str(), WIRELESS_INTERFACE, "WirelessCapabilities")?;
+        Ok(capabilities & WIFI_CAP_AP != 0)
+    })
+    .ok()
+}
+
+/// Access points the WiFi device `interface` sees, after a fresh scan if
+/// `rescan`; empty if NM can't be reached
+pub fn access_points(interface: &str, rescan: bool) -> Vec<AccessPoint> {
+    let interface = interface.to_string();
+    on_bus(move |conn| {
+        let device = device_path(conn, &interface)?;
+        if rescan {
+            wait_for_scan(conn, device.as_str());
+        }
+        let reply = call(conn, device.as_str(), WIRELESS_INTERFACE, "GetAllAccessPoints", &())?;
+        let paths: Vec<OwnedObjectPath> = reply.body().deserialize()?;
+        // One may drop out of range between the listing and the reads; skip it
+        Ok(paths.iter().filter_map(|path| access_point(conn, path.as_str()).ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// The access point `interface` is associated with as a client, if any
+pub fn active_access_point(interface: &str) -> Option<AccessPoint> {
+    let interface = interface.to_string();
+    on_bus(move |conn| {
+        let device = device_path(conn, &interface)?;
+        let active: OwnedObjectPath = property(conn, device.as_str(), WIRELESS_INTERFACE, "ActiveAccessPoint")?;
+        match active.as_str() {
+            "/" => Ok(None),
+            path => Ok(Some(access_point(conn, path)?)),
+        }
+    })
+    .ok()
+    .flatten()
+}
+
+/// Settings of every saved connection (without secrets); empty if NM
+/// can't be reached
+pub fn saved_connections() -> Vec<OwnedSettings> {
+    on_bus(|conn| {
+        let reply = call(conn, SETTINGS_PATH, SETTINGS_INTERFACE, "ListConnections", &())?;
+        let paths: Vec<OwnedObjectPath> = reply.body().deserialize()?;
+        Ok(paths.iter().filter_map(|path| settings_of(conn, path.as_str()).ok()).collect())
+    })
+    .unwrap_or_default()
+}
+
+/// The secrets of the `setting` setting of the saved connection with UUID
+/// `uuid`. NM only reveals them to users allowed to see them.
+pub fn connection_secrets(uuid: &str, setting: &'static str) -> Result<OwnedSettings, NmError> {
+    let uuid = uuid.to_string();
+    on_bus(move |conn| {
+        let reply = call(conn, SETTINGS_PATH, SETTINGS_INTERFACE, "GetConnectionByUuid", &(uuid.as_str(),))?;
+        let path: OwnedObjectPath = reply.body().deserialize()?;
+        let reply = call(conn, path.as_str(), CONNECTION_INTERFACE, "GetSecrets", &(setting,))?;
+        Ok(reply.body().deserialize()?)
+    })
+}
+
+/// Settings of the saved connection `id`, including the secrets of the
+/// `secrets` setting (e.g. "802-11-wireless-security") if given
+pub fn connection_settings(id: &str, secrets: Option<&'static str>) -> Result<OwnedSettings, NmError> {
+    let id = id.to_string();
+    on_bus(move |conn| {
+        let path = connections_with_id(conn, &id)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| unknown_connection(&id))?;
+        let mut settings = settings_of(conn, path.as_str())?;
+        if let Some(setting) = secrets {
+            let reply = call(conn, path.as_str(), CONNECTION_INTERFACE, "GetSecrets", &(setting,))?;
+            let secret_settings: OwnedSettings = reply.body().deserialize()?;
+            for (name, values) in secret_settings {
+                settings.entry(name).or_default().extend(values);
+            }
+        }
+        Ok(settings)
+    })
+}
+
+/// A string property from connection settings
+pub fn string_setting(settings: &OwnedSettings, setting: &str, property: &str) -> Option<String> {
+    let value = settings.get(setting)?.get(property)?.try_clone().ok()?;
+    String::try_from(value).ok()
+}
+
+/// The SSID from connection settings (NM stores it as bytes)
+pub fn ssid_setting(settings: &OwnedSettings) -> Option<String> {
+    let value = settings.get("802-11-wireless")?.get("ssid")?.try_clone().ok()?;
+    let bytes = Vec::<u8>::try_from(value).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Save a new connection
+pub fn add_connection(mut settings: Settings, dry_run: bool) -> Result<(), NmError> {
+    if dry_run {
+        dry_run::report(&format!("NetworkManager AddConnection {}", describe(&settings)));
+        return Ok(());
+    }
+    // NM wants a UUID, which nmcli used to make up; the kernel hands out random ones
+    if let Ok(uuid) = std::fs::read_to_string("/proc/sys/kernel/random/uuid") {
+        settings.entry("connection").or_default().entry("uuid").or_insert_with(|| Value::from(uuid.trim().to_string()));
+    }
+    on_bus(move |conn| {
+        call(conn, SETTINGS_PATH, SETTINGS_INTERFACE, "AddConnection", &(settings,))?;
+        Ok(())
+    })
+}
+
+/// Delete every saved connection called `id`; none is fine
+pub fn delete_connection(id: &str, dry_run: bool) -> Result<(), NmError> {
+    if dry_run {
+        dry_run::report(&format!("NetworkManager Delete connection '{id}'"));
+        return Ok(());
+    }
+    let id = id.to_string();
+    on_bus(move |conn| {
+        for path in connections_with_id(conn, &id)? {
+            call(conn, path.as_str(), CONNECTION_INTERFACE, "Delete", &())?;
+        }
+        Ok(())
+    })
+}
+
+/// Bring up the saved connection `id` on `interface` (or wherever NM
+/// picks) and wait until it's up or has failed. Returns the activation's
+/// object path.
+pub fn activate(id: &str, interface: Option<&str>, dry_run: bool) -> Result<String, NmError> {
+    if dry_run {
+        dry_run::report(&format!("NetworkManager ActivateConnection '{id}'"));
+        return Ok(String::new());
+    }
+    let id = id.to_string();
+    let interface = interface.map(str::to_string);
+    on_bus(move |conn| {
+        let connection = connections_with_id(conn, &id)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| unknown_connection(&id))?;
+        let device = match &interface {
+            Some(interface) => device_path(conn, interface)?,
+            None => OwnedObjectPath::from(ObjectPath::from_static_str_unchecked("/")),
+        };
+        let reply = call(
+            conn,
+            NM_PATH,
+            NM_INTERFACE,
+            "ActivateConnection",
+            &(&connection, &device, ObjectPath::from_static_str_unchecked("/")),
+        )?;
+        let active: OwnedObjectPath = reply.body().deserialize()?;
+        wait_until_activated(conn, active.as_str())?;
+        Ok(active.to_string())
+    })
+}
+
+/// Take down the active connection `id`; not being active is fine
+pub fn deactivate(id: &str, dry_run: bool) -> Result<(), NmError> {
+    if dry_run {
+        dry_run::report(&format!("NetworkManager DeactivateConnection '{id}'"));
+        return Ok(());
+    }
+    let id = id.to_string();
+    on_bus(move |conn| {
+        for active in active_list(conn)?.into_iter().filter(|a| a.id == id) {
+            let path = ObjectPath::try_from(active.path.as_str()).map_err(zbus::Error::from)?;
+            call(conn, NM_PATH, NM_INTERFACE, "DeactivateConnection", &(path,))?;
+        }
+        Ok(())
+    })
+}
+
+/// Disconnect `interface`, keeping NM from autoconnecting it again
+pub fn disconnect_device(interface: &str, dry_run: bool) -> Result<(), NmError> {
+    if dry_run {
+        dry_run::report(&format!("NetworkManager Disconnect device {interface}"));
+        return Ok(());
+    }
+    let interface = interface.to_string();
+    on_bus(move |conn| {
+        let device = device_path(conn, &interface)?;
+        call(conn, device.as_str(), DEVICE_INTERFACE, "Disconnect", &())?;
+        Ok(())
+    })
+}
+
+/// A receiver that gets `()` each time NetworkManager's overall state, a
+/// device's or an activation's state, or its list of devices or active
+/// connections changes. It disconnects if the system bus isn't available, so
+/// callers should keep a periodic refresh as a fallback.
+pub fn subscribe() -> Receiver<()> {
+    let (tx, rx) = mpsc::channel();
+    // Fresh thread: zbus' blocking API must not run inside the tokio worker
+    std::thread::spawn(move || {
+        let conn = match bus() {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("NetworkManager change notifications unavailable: {e}");
+                return;
+            }
+        };
+        let rules = [
+            signal_rule(NM_INTERFACE, "StateChanged", None),
+            signal_rule(DEVICE_INTERFACE, "StateChanged", None),
+            signal_rule(ACTIVE_INTERFACE, "StateChanged", None),
+            signal_rule(PROPERTIES_INTERFACE, "PropertiesChanged", Some(NM_PATH)),
+        ];
+        for rule in rules {
+            let (conn, tx) = (conn.clone(), tx.clone());
+            std::thread::spawn(move || forward_signals(&conn, &rule, &tx));
+        }
+    });
+    rx
+}
+
+/// Match rule for `member` signals of `interface` from NM, optionally only
+/// from the object at `path`
+fn signal_rule(interface: &str, member: &str, path: Option<&str>) -> String {
+    let path = path.map(|p| format!(",path='{p}'")).unwrap_or_default();
+    format!("type='signal',sender='{BUS_NAME}',interface='{interface}',member='{member}'{path}")
+}
+
+/// Send `()` on `tx` for each signal matching `rule`, until the bus or the
+/// receiver goes away
+fn forward_signals(conn: &Connection, rule: &str, tx: &Sender<()>) {
+    let signals = zbus::MatchRule::try_from(rule)
+        .and_then(|rule| MessageIterator::for_match_rule(rule, conn, Some(SIGNAL_QUEUE)));
+    let signals = match signals {
+        Ok(signals) => signals,
+        Err(e) => {
+            eprintln!("Failed to watch NetworkManager signals ({rule}): {e}");
+            return;
+        }
+    };
+    for signal in signals {
+        if signal.is_err() || tx.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Run `calls` against the shared system bus connection
+fn on_bus<T: Send + 'static>(
+    calls: impl FnOnce(&Connection) -> Result<T, NmError> + Send + 'static,
+) -> Result<T, NmError> {
+    let run = move || {
+        let conn = bus()?;
+        let result = calls(&conn);
+        if matches!(result, Err(NmError::Unavailable(_))) {
+            // The connection may be what broke; open a new one next time
+            *bus_slot() = None;
+        }
+        result
+    };
+    if tokio::runtime::Handle::try_current().is_err() {
+        return run();
+    }
+    // zbus' blocking API must not run inside a tokio worker
+    std::thread::spawn(run)
+        .join()
+        .unwrap_or_else(|_| Err(NmError::Unavailable("NetworkManager call panicked".to_string())))
+}
+
+/// The system bus connection, opened on first use and after a failed call
+fn bus() -> Result<Connection, NmError> {
+    let mut slot = bus_slot();
+    if let Some(conn) = &*slot {
+        return Ok(conn.clone());
+    }
+    let conn = Connection::system()
+        .map_err(|e| NmError::Unavailable(format!("Failed to connect to system bus: {e}")))?;
+    *slot = Some(conn.clone());
+    Ok(conn)
+}
+
+fn bus_slot() -> MutexGuard<'static, Option<Connection>> {
+    static BUS: OnceLock<Mutex<Option<Connection>>> = OnceLock::new();
+    BUS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn call<B>(conn: &Connection, path: &str, interface: &str, method: &str, body: &B) -> Result<zbus::Message, NmError>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    Ok(conn.call_method(Some(BUS_NAME), path, Some(interface), method, body)?)
+}
+
+fn property<T: TryFrom<OwnedValue>>(conn: &Connection, path: &str, interface: &str, name: &str) -> Result<T, NmError> {
+    let reply = call(conn, path, PROPERTIES_INTERFACE, "Get", &(interface, name))?;
+    let value: OwnedValue = reply.body().deserialize()?;
+    T::try_from(value).map_err(|_| NmError::Rejected {
+        name: String::new(),
+        message: format!("Unexpected type for {interface}.{name}"),
+    })
+}
+
+fn device_path(conn: &Connection, interface: &str) -> Result<OwnedObjectPath, NmError> {
+    let reply = call(conn, NM_PATH, NM_INTERFACE, "GetDeviceByIpIface", &(interface,))?;
+    Ok(reply.body().deserialize()?)
+}
+
+fn settings_of(conn: &Connection, path: &str) -> Result<OwnedSettings, NmError> {
+    let reply = call(conn, path, CONNECTION_INTERFACE, "GetSettings", &())?;
+    Ok(reply.body().deserialize()?)
+}
+
+fn access_point(conn: &Connection, path: &str) -> Result<AccessPoint, NmError> {
+    let ssid: Vec<u8> = property(conn, path, AP_INTERFACE, "Ssid")?;
+    let strength: u8 = property(conn, path, AP_INTERFACE, "Strength")?;
+    let flags: u32 = property(conn, path, AP_INTERFACE, "Flags")?;
+    let wpa_flags: u32 = property(conn, path, AP_INTERFACE, "WpaFlags")?;
+    let rsn_flags: u32 = property(conn, path, AP_INTERFACE, "RsnFlags")?;
+    let key_mgmt = wpa_flags | rsn_flags;
+    Ok(AccessPoint {
+        ssid: String::from_utf8_lossy(&ssid).into_owned(),
+        strength: u32::from(strength),
+        frequency: property(conn, path, AP_INTERFACE, "Frequency")?,
+        secured: flags & AP_FLAG_PRIVACY != 0 || key_mgmt != 0,
+        sae_only: key_mgmt & AP_SEC_SAE != 0 && key_mgmt & AP_SEC_PSK == 0,
+    })
+}
+
+/// Ask `device` to scan and wait until its LastScan timestamp moves on. A
+/// refused request (one is already running, or scanning is rate-limited)
+/// leaves the latest results as they are.
+fn wait_for_scan(conn: &Connection, device: &str) {
+    let last_scan = || property::<i64>(conn, device, WIRELESS_INTERFACE, "LastScan").ok();
+    let before = last_scan();
+    let options: HashMap<&str, Value<'_>> = HashMap::new();
+    if call(conn, device, WIRELESS_INTERFACE, "RequestScan", &(options,)).is_err() {
+        return;
+    }
+    let deadline = std::time::Instant::now() + SCAN_TIMEOUT;
+    while std::time::Instant::now() < deadline && last_scan() == before {
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+}
+
+fn connections_with_id(conn: &Connection, id: &str) -> Result<Vec<OwnedObjectPath>, NmError> {
+    let reply = call(conn, SETTINGS_PATH, SETTINGS_INTERFACE, "ListConnections", &())?;
+    let paths: Vec<OwnedObjectPath> = reply.body().deserialize()?;
+    Ok(paths
+        .into_iter()
+        .filter(|path| {
+            settings_of(conn, path.as_str())
+                .is_ok_and(|settings| string_setting(&settings, "connection", "id").as_deref() == Some(id))
+        })
+        .collect())
+}
+
+fn active_list(conn: &Connection) -> Result<Vec<ActiveConnection>, NmError> {
+    let paths: Vec<OwnedObjectPath> = property(conn, NM_PATH, NM_INTERFACE, "ActiveConnections")?;
+    // One may go away between the listing and the reads; skip it
+    Ok(paths.iter().filter_map(|path| active_connection(conn, path.as_str()).ok()).collect())
+}
+
+fn active_connection(conn: &Connection, path: &str) -> Result<ActiveConnection, NmError> {
+    let devices: Vec<OwnedObjectPath> = property(conn, path, ACTIVE_INTERFACE, "Devices")?;
+    Ok(ActiveConnection {
+        path: path.to_string(),
+        id: property(conn, path, ACTIVE_INTERFACE, "Id")?,
+        kind: property(conn, path, ACTIVE_INTERFACE, "Type")?,
+        state: property(conn, path, ACTIVE_INTERFACE, "State")?,
+        interfaces: devices
+            .iter()
+            .filter_map(|device| property(conn, device.as_str(), DEVICE_INTERFACE, "Interface").ok())
+            .collect(),
+    })
+}
+
+/// Follow an activation's StateChanged signals until it's up or down. NM
+/// gives up on activations itself (with a timeout reason), so this ends.
+fn wait_until_activated(conn: &Connection, path: &str) -> Result<(), NmError> {
+    let proxy = zbus::blocking::Proxy::new(conn, BUS_NAME, path, ACTIVE_INTERFACE)?;
+    let mut changes = proxy.receive_signal("StateChanged")?;
+    // It may have settled before the subscription; a vanished activation failed
+    match property::<u32>(conn, path, ACTIVE_INTERFACE, "State") {
+        Ok(ACTIVE_ACTIVATED) => return Ok(()),
+        Ok(ACTIVE_DEACTIVATED) | Err(_) => return Err(NmError::ActivationFailed(0)),
+        Ok(_) => {}
+    }
+    for signal in changes.by_ref() {
+        let (state, reason): (u32, u32) = signal.body().deserialize()?;
+        match state {
+            ACTIVE_ACTIVATED => return Ok(()),
+            ACTIVE_DEACTIVATED => return Err(NmError::ActivationFailed(reason)),
+            _ => {}
+        }
+    }
+    Err(NmError::ActivationFailed(0))
+}
+
+fn unknown_connection(id: &str) -> NmError {
+    NmError::Rejected { name: UNKNOWN_CONNECTION.to_string(), message: format!("No connection '{id}'") }
+}
+
+/// Settings as `setting.property=value` pairs for dry-run output, with the
+/// password masked
+fn describe(settings: &Settings) -> String {
+    let mut pairs: Vec<String> = settings
+        .iter()
+        .flat_map(|(setting, properties)| {
+            properties.iter().map(move |(property, value)| {
+                let shown = if *property == "psk" { "'********'".to_string() } else { value.to_string() };
+                format!("{setting}.{property}={shown}")
+            })
+        })
+        .collect();
+    pairs.sort();
+    pairs.join(" ")
+}
@@ -0,0 +1,45 @@
+//! Desktop notifications via `notify-send`.
+
+use std::process::{Command, Stdio};
+
+const ICON: &str = "io.github.reality2_roycdavies.cosmic-hotspot-symbolic";
+/// What `notify-send` prints when the action button is clicked
+const ACTION_KEY: &str = "action";
+
+/// Show a transient desktop notification. Failures are logged, not fatal —
+/// a missing notification daemon shouldn't break the action that triggered it.
+pub fn send(summary: &str, body: &str) {
+    if let Err(e) = Command::new("notify-send")
+        .args(["--app-name=WiFi Hotspot", "--icon", ICON, summary, body])
+        .spawn()
+    {
+        eprintln!("Failed to send notification: {e}");
+    }
+}
+
+/// Show a notification that stays until dismissed, with one `action` button,
+/// and run `on_action` if it's clicked. `notify-send --wait` blocks until the
+/// notification closes, so this waits on a thread of its own.
+pub fn send_with_action(summary: &str, body: &str, action: &str, on_action: impl FnOnce() + Send + 'static) {
+    let child = Command::new("notify-send")
+        .args(["--app-name=WiFi Hotspot", "--icon", ICON, "--urgency=critical", "--wait"])
+        .arg(format!("--action={ACTION_KEY}={action}"))
+        .args([summary, body])
+        .stdout(Stdio::piped())
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to send notification: {e}");
+            return;
+        }
+    };
+    std::thread::spawn(move || {
+        // The key of the clicked action is printed; nothing if it's dismissed
+        if let Ok(output) = child.wait_with_output() {
+            if String::from_utf8_lossy(&output.stdout).trim() == ACTION_KEY {
+                on_action();
+            }
+        }
+    });
+}
@@ -0,0 +1,68 @@
+//! Device manufacturers from MAC addresses.
+//!
+//! The first three bytes of a globally administered MAC are the maker's
+//! IEEE OUI. The whole registry is read from the copy distributions ship
+//! (hwdata or ieee-data) when it's installed; the table in
+//! `resources/oui.txt` covers makers of the devices that usually join a
+//! hotspot, under their short names, and is all there is without one. Phones
+//! that join with a randomised ("private") address have the locally
+//! administered bit set and no OUI.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const TABLE: &str = include_str!("../resources/oui.txt");
+/// The IEEE MA-L registry as packaged by hwdata and ieee-data
+const SYSTEM_REGISTRIES: &[&str] =
+    &["/usr/share/hwdata/oui.txt", "/usr/share/ieee-data/oui.txt", "/usr/share/misc/oui.txt"];
+
+/// The manufacturer whose OUI starts `mac`, e.g. "Apple"
+pub fn vendor(mac: &str) -> Option<&'static str> {
+    static VENDORS: OnceLock<HashMap<String, &'static str>> = OnceLock::new();
+    let prefix: String = mac.split([':', '-']).take(3).collect::<String>().to_uppercase();
+    let first = u8::from_str_radix(prefix.get(..2)?, 16).ok()?;
+    if prefix.len() != 6 || is_local(first) {
+        return None;
+    }
+    VENDORS.get_or_init(load).get(&prefix).copied()
+}
+
+fn load() -> HashMap<String, &'static str> {
+    // Read once and kept for the life of the process
+    let mut vendors = SYSTEM_REGISTRIES
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|registry| parse_registry(Box::leak(registry.into_boxed_str())))
+        .unwrap_or_default();
+    // "Apple" rather than the registry's "Apple, Inc."
+    vendors.extend(parse_table(TABLE));
+    vendors
+}
+
+/// Whether a MAC starting with `first` is randomised or otherwise assigned
+/// locally rather than by the maker
+pub fn is_local(first: u8) -> bool {
+    first & 0x02 != 0
+}
+
+/// OUI (uppercase hex) → vendor from "<oui> <vendor>" lines; `#` starts a comment
+pub fn parse_table(table: &str) -> HashMap<String, &str> {
+    table
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(' '))
+        .map(|(oui, vendor)| (oui.to_uppercase(), vendor.trim()))
+        .collect()
+}
+
+/// OUI (uppercase hex) → vendor from the IEEE registry's
+/// "<oui>     (base 16)\t\t<vendor>" lines
+pub fn parse_registry(registry: &str) -> HashMap<String, &str> {
+    registry
+        .lines()
+        .filter_map(|line| line.split_once("(base 16)"))
+        .map(|(oui, vendor)| (oui.trim(), vendor.trim()))
+        .filter(|(oui, vendor)| oui.len() == 6 && oui.chars().all(|c| c.is_ascii_hexdigit()) && !vendor.is_empty())
+        .map(|(oui, vendor)| (oui.to_uppercase(), vendor))
+        .collect()
+}
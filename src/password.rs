@@ -0,0 +1,74 @@
+//! Hotspot password audit and generation.
+//!
+//! Anyone within radio range can try passwords offline once they've captured
+//! a handshake, so short, shipped-default and well-known passwords are
+//! flagged, and a strong random one is a click away.
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+
+/// Shortest password not flagged as weak
+pub const MIN_LENGTH: usize = 12;
+
+/// The password this app shipped as its default before generating one per
+/// install
+const SHIPPED_DEFAULTS: &[&str] = &["changeme123"];
+
+/// Widely used passwords, all at least 8 characters (WPA2's minimum)
+const COMMON: &[&str] = &[
+    "12345678", "123456789", "1234567890", "123123123", "11111111", "00000000",
+    "87654321", "password", "password1", "password123", "passw0rd", "qwertyuiop",
+    "qwerty123", "1q2w3e4r", "1qaz2wsx", "iloveyou", "sunshine", "princess",
+    "football", "baseball", "abc12345", "abcd1234", "letmein1", "welcome1",
+    "internet", "wireless", "hotspot1", "trustno1", "superman", "starwars",
+];
+
+/// Characters for generated passwords; no 0/O, 1/l/I to misread when
+/// typing it on a phone
+const ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Length of generated passwords (~94 bits)
+const GENERATED_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weakness {
+    TooShort,
+    ShippedDefault,
+    Common,
+}
+
+impl Weakness {
+    pub fn message(self) -> String {
+        match self {
+            Weakness::TooShort => format!("shorter than {MIN_LENGTH} characters"),
+            Weakness::ShippedDefault => "still the default every install shares".to_string(),
+            Weakness::Common => "on lists of common passwords".to_string(),
+        }
+    }
+}
+
+/// The most serious problem with `password`, if any
+pub fn audit(password: &str) -> Option<Weakness> {
+    if SHIPPED_DEFAULTS.contains(&password) {
+        Some(Weakness::ShippedDefault)
+    } else if COMMON.iter().any(|c| c.eq_ignore_ascii_case(password)) {
+        Some(Weakness::Common)
+    } else if password.chars().count() < MIN_LENGTH {
+        Some(Weakness::TooShort)
+    } else {
+        None
+    }
+}
+
+/// A random password from the OS RNG
+pub fn generate() -> String {
+    let mut password = String::with_capacity(GENERATED_LENGTH);
+    while password.len() < GENERATED_LENGTH {
+        // Reject the top of the byte range so every character is equally likely
+        let byte = (OsRng.next_u32() & 0xff) as usize;
+        if byte < 256 - 256 % ALPHABET.len() {
+            password.push(ALPHABET[byte % ALPHABET.len()] as char);
+        }
+    }
+    password
+}
@@ -0,0 +1,277 @@
+//! Client for privileged operations (NAT, port forwards, tc limits, rfkill,
+//! multicast, USB autosuspend, virtual AP interfaces, the hostapd backend,
+//! per-client access rules and schedules).
+//!
+//! Prefers the system D-Bus helper service when it's installed — it's
+//! activated on demand and authorizes each call through polkit, so there is
+//! no pkexec process per operation. Falls back to running the helper binary
+//! through pkexec, and finally reports that neither is available.
+
+use crate::dry_run;
+use crate::nat::NatRequest;
+use crate::schedule;
+use std::process::Command;
+
+pub const BUS_NAME: &str = "io.github.reality2_roycdavies.CosmicHotspot1";
+pub const OBJECT_PATH: &str = "/io/github/reality2_roycdavies/CosmicHotspot1";
+pub const INTERFACE: &str = "io.github.reality2_roycdavies.CosmicHotspot1.Helper";
+
+pub const NAT_HELPER: &str = "/usr/local/bin/cosmic-hotspot-nat";
+
+/// Whether any privileged path (D-Bus service or pkexec helper) is installed
+pub fn is_available() -> bool {
+    service_installed() || std::path::Path::new(NAT_HELPER).exists()
+}
+
+/// Run a privileged request through the best available path
+pub fn run(request: &NatRequest, dry_run: bool) -> Result<(), String> {
+    if dry_run {
+        report_dry_run(request);
+        return Ok(());
+    }
+    if service_installed() {
+        return call_service(request);
+    }
+    if std::path::Path::new(NAT_HELPER).exists() {
+        return run_pkexec(request);
+    }
+    Err("Privileged helper not installed (run: just install-policy)".to_string())
+}
+
+/// Print the call that would be made and the rules the helper would run
+fn report_dry_run(request: &NatRequest) {
+    let args = request.to_args().join(" ");
+    if service_installed() {
+        dry_run::report(&format!("D-Bus {BUS_NAME} {INTERFACE} <- {args}"));
+    } else {
+        dry_run::report(&format!("pkexec {NAT_HELPER} {args}"));
+    }
+    for rule in request.rules() {
+        dry_run::report(&format!("  (as root) {}", rule.describe()));
+    }
+}
+
+fn service_installed() -> bool {
+    ["/usr/share/dbus-1/system-services", "/usr/local/share/dbus-1/system-services"]
+        .iter()
+        .any(|dir| std::path::Path::new(dir).join(format!("{BUS_NAME}.service")).exists())
+}
+
+fn call_service(request: &NatRequest) -> Result<(), String> {
+    let request = request.clone();
+    // Run on a fresh thread: zbus' blocking API may start its own runtime,
+    // which must not happen inside the applet's tokio worker.
+    std::thread::spawn(move || -> Result<(), String> {
+        let conn = zbus::blocking::Connection::system()
+            .map_err(|e| format!("Failed to connect to system bus: {e}"))?;
+        let reply = match &request {
+            NatRequest::Setup { hotspot, internet, firewall } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetupNat",
+                &(hotspot.as_str(), internet.as_str(), firewall.as_str()),
+            ),
+            NatRequest::Teardown { hotspot, internet, firewall } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "TeardownNat",
+                &(hotspot.as_str(), internet.as_str(), firewall.as_str()),
+            ),
+            NatRequest::Check { hotspot, internet, firewall } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "CheckNat",
+                &(hotspot.as_str(), internet.as_str(), firewall.as_str()),
+            ),
+            NatRequest::Limit { hotspot, rate_kbit } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetRateLimit",
+                &(hotspot.as_str(), *rate_kbit),
+            ),
+            NatRequest::PortForward {
+                internet,
+                protocol,
+                port,
+                destination,
+                destination_port,
+                gateway,
+                prefix,
+                remove,
+            } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                if *remove { "RemovePortForward" } else { "AddPortForward" },
+                &(
+                    internet.as_str(),
+                    protocol.as_str(),
+                    *port,
+                    destination.to_string(),
+                    *destination_port,
+                    format!("{gateway}/{prefix}"),
+                ),
+            ),
+            NatRequest::Dns { hotspot, server, gateway, prefix, remove } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetDnsRedirect",
+                &(hotspot.as_str(), server.to_string(), format!("{gateway}/{prefix}"), *remove),
+            ),
+            NatRequest::QueryLog { enable } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetQueryLog",
+                &(*enable,),
+            ),
+            NatRequest::LocalDomain { enable } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetLocalDomain",
+                &(*enable,),
+            ),
+            NatRequest::HostResolver { enable } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetHostResolver",
+                &(*enable,),
+            ),
+            NatRequest::DhcpOptions { options } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetDhcpOptions",
+                &(options,),
+            ),
+            NatRequest::ClientAccess { hotspot, internet, mac, access, remove } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetClientAccess",
+                &(
+                    hotspot.as_str(),
+                    internet.as_str(),
+                    mac.as_str(),
+                    access.as_str(),
+                    access.ports_arg().unwrap_or_default(),
+                    *remove,
+                ),
+            ),
+            NatRequest::ClientSchedule { hotspot, internet, mac, schedule, remove } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetClientSchedule",
+                &(
+                    hotspot.as_str(),
+                    internet.as_str(),
+                    mac.as_str(),
+                    schedule.days_arg(),
+                    schedule::format_time(schedule.from),
+                    schedule::format_time(schedule.until),
+                    *remove,
+                ),
+            ),
+            NatRequest::ClientPriority { hotspot, mac, remove } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetClientPriority",
+                &(hotspot.as_str(), mac.as_str(), *remove),
+            ),
+            NatRequest::Wps { hotspot, enable } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetWps",
+                &(hotspot.as_str(), *enable),
+            ),
+            NatRequest::Deauth { hotspot, mac } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "Deauth",
+                &(hotspot.as_str(), mac.as_str()),
+            ),
+            NatRequest::Rfkill { block } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetRfkill",
+                &(*block,),
+            ),
+            NatRequest::Multicast { hotspot, to_unicast, rate_mbps } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetMulticast",
+                &(hotspot.as_str(), *to_unicast, *rate_mbps),
+            ),
+            NatRequest::UsbAutosuspend { device, enable } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetUsbAutosuspend",
+                &(device.as_str(), *enable),
+            ),
+            NatRequest::HostapdStart { ap } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "StartHostapd",
+                &(
+                    ap.hotspot.as_str(),
+                    ap.ssid.as_str(),
+                    ap.password.as_str(),
+                    ap.security.as_str(),
+                    ap.band.as_str(),
+                    ap.channel,
+                    format!("{}/{}", ap.gateway, ap.prefix),
+                    ap.dhcp_start.to_string(),
+                    ap.dhcp_end.to_string(),
+                ),
+            ),
+            NatRequest::HostapdStop { hotspot } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "StopHostapd",
+                &(hotspot.as_str(),),
+            ),
+            NatRequest::VirtualAp { parent, name, remove } => conn.call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(INTERFACE),
+                "SetVirtualAp",
+                &(parent.as_str(), name.as_str(), *remove),
+            ),
+        };
+        reply.map(|_| ()).map_err(|e| format!("Helper service error: {e}"))
+    })
+    .join()
+    .unwrap_or_else(|_| Err("Helper service call panicked".to_string()))
+}
+
+/// pkexec with the helper: the polkit policy (allow_active=yes) means
+/// no password dialog for active sessions
+fn run_pkexec(request: &NatRequest) -> Result<(), String> {
+    let output = Command::new("pkexec")
+        .arg(NAT_HELPER)
+        .args(request.to_args())
+        .output()
+        .map_err(|e| format!("Failed to run pkexec: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("NAT helper failed: {}", stderr.trim()))
+    }
+}
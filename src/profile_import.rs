@@ -0,0 +1,281 @@
+//! Profiles imported from other tools' access point configs.
+//!
+//! Reads a hostapd.conf, a wpa_supplicant.conf with an AP (`mode=2`) network
+//! block, or a NetworkManager keyfile such as the one GNOME's "Turn On WiFi
+//! Hotspot" writes. The addressing becomes a new profile; the network name,
+//! password, security and radio settings replace the current ones, as they
+//! aren't per-profile.
+
+use crate::config::{Config, Profile};
+
+/// What an imported file describes; `None` where it doesn't say
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Imported {
+    pub profile: Profile,
+    pub ssid: Option<String>,
+    pub password: Option<String>,
+    /// "wpa2", "wpa3" or "wpa2-wpa3"
+    pub security: Option<String>,
+    /// "bg" or "a"
+    pub band: Option<String>,
+    pub channel: Option<u32>,
+    pub interface: Option<String>,
+    /// Settings that couldn't be carried over
+    pub notes: Vec<String>,
+}
+
+/// Parse `text` in whichever supported format it's in. `name` names the new
+/// profile; a keyfile's connection id is used when it's empty.
+pub fn parse(text: &str, name: &str) -> Result<Imported, String> {
+    let mut imported = if text.lines().any(|l| l.trim() == "[wifi]") {
+        parse_nm_keyfile(text)?
+    } else if text.lines().any(|l| l.trim().starts_with("network={")) {
+        parse_wpa_supplicant(text)?
+    } else if text.lines().any(|l| l.trim().starts_with("ssid=")) {
+        parse_hostapd_conf(text)
+    } else {
+        return Err("Not a hostapd.conf, wpa_supplicant.conf or NetworkManager keyfile".to_string());
+    };
+    if !name.trim().is_empty() {
+        imported.profile.name = name.trim().to_string();
+    } else if imported.profile.name.trim().is_empty() {
+        imported.profile.name = "Imported".to_string();
+    }
+    imported.profile.validate()?;
+    Ok(imported)
+}
+
+/// hostapd.conf: `key=value` lines, `#` comments
+pub fn parse_hostapd_conf(text: &str) -> Imported {
+    let mut imported = unnamed();
+    let mut hw_mode = None;
+    let mut wpa = None;
+    let mut key_mgmt = None;
+    for (key, value) in key_values(text, '#') {
+        match key {
+            "interface" => imported.interface = Some(value.to_string()),
+            "ssid" => imported.ssid = Some(value.to_string()),
+            "ssid2" => imported.ssid = unquote(value).or_else(|| from_hex(value)),
+            "wpa_passphrase" => imported.password = Some(value.to_string()),
+            "wpa_psk" => imported.notes.push(raw_psk_note()),
+            "hw_mode" => hw_mode = Some(value),
+            "channel" => imported.channel = value.parse().ok(),
+            "wpa" => wpa = Some(value),
+            "wpa_key_mgmt" => key_mgmt = Some(value),
+            _ => {}
+        }
+    }
+    imported.band = hw_mode.map(|mode| if mode == "a" { "a" } else { "bg" }.to_string());
+    if wpa.is_none_or(|w| w == "0") {
+        imported.notes.push(open_network_note());
+    } else {
+        imported.security = key_mgmt.map(security_from_key_mgmt);
+    }
+    imported
+}
+
+/// wpa_supplicant.conf: the first `network={ ... }` block with `mode=2`
+pub fn parse_wpa_supplicant(text: &str) -> Result<Imported, String> {
+    let block = network_blocks(text)
+        .into_iter()
+        .find(|block| block.iter().any(|(key, value)| *key == "mode" && *value == "2"))
+        .ok_or("No access point (mode=2) network block")?;
+
+    let mut imported = unnamed();
+    for (key, value) in block {
+        match key {
+            "ssid" => imported.ssid = unquote(value).or_else(|| from_hex(value)),
+            // A quoted passphrase; unquoted it's the derived 256-bit key
+            "psk" => match unquote(value) {
+                Some(passphrase) => imported.password = Some(passphrase),
+                None => imported.notes.push(raw_psk_note()),
+            },
+            "key_mgmt" if value == "NONE" => imported.notes.push(open_network_note()),
+            "key_mgmt" => imported.security = Some(security_from_key_mgmt(value)),
+            "frequency" => {
+                if let Some((band, channel)) = value.parse().ok().and_then(channel_for_frequency) {
+                    imported.band = Some(band.to_string());
+                    imported.channel = Some(channel);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(imported)
+}
+
+/// NetworkManager keyfile: an ini file with [connection], [wifi],
+/// [wifi-security] and [ipv4] sections
+pub fn parse_nm_keyfile(text: &str) -> Result<Imported, String> {
+    let mut imported = unnamed();
+    let mut section = "";
+    let mut secured = false;
+    for line in text.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        match (section, key.trim(), value.trim()) {
+            ("connection", "id", id) => imported.profile.name = id.to_string(),
+            ("connection", "interface-name", name) => imported.interface = Some(name.to_string()),
+            ("wifi", "mode", mode) if mode != "ap" => {
+                return Err(format!("The keyfile is for a WiFi {mode} connection, not a hotspot"))
+            }
+            ("wifi", "ssid", ssid) => imported.ssid = Some(keyfile_ssid(ssid)),
+            ("wifi", "band", band) => imported.band = Some(band.to_string()),
+            ("wifi", "channel", channel) => imported.channel = channel.parse().ok(),
+            ("wifi-security", "key-mgmt", key_mgmt) => {
+                secured = true;
+                imported.security = Some(security_from_key_mgmt(key_mgmt));
+            }
+            ("wifi-security", "psk", psk) => imported.password = Some(psk.to_string()),
+            ("ipv4", "address1", address) => {
+                // "10.42.0.1/24" or "10.42.0.1/24,<gateway>"
+                let address = address.split(',').next().unwrap_or_default();
+                imported.profile.gateway_ip = address.to_string();
+            }
+            ("ipv4", "method", method) if method != "shared" => {
+                imported.notes.push(format!("IPv4 method '{method}' isn't imported; the hotspot always shares"))
+            }
+            _ => {}
+        }
+    }
+    if imported.ssid.is_none() {
+        return Err("The keyfile has no [wifi] ssid".to_string());
+    }
+    if !secured {
+        imported.notes.push(open_network_note());
+    }
+    Ok(imported)
+}
+
+impl Imported {
+    /// Add the profile to `config` (renamed if the name is taken) and make it
+    /// active, and take over the network settings the file gave. Returns the
+    /// profile's name.
+    pub fn apply(self, config: &mut Config) -> String {
+        let mut profile = self.profile;
+        let base = profile.name.clone();
+        let mut n = 2;
+        while config.profiles.iter().any(|p| p.name == profile.name) {
+            profile.name = format!("{base} {n}");
+            n += 1;
+        }
+        let name = profile.name.clone();
+        config.profiles.push(profile);
+        // Just pushed, so the switch can't fail
+        let _ = config.switch_profile(&name);
+
+        if let Some(ssid) = self.ssid {
+            config.ssid = ssid;
+        }
+        if let Some(password) = self.password {
+            config.password = password;
+        }
+        if let Some(security) = self.security {
+            config.security = security;
+        }
+        if let Some(band) = self.band {
+            config.band = band;
+            // A channel from another band would be rejected at start
+            config.channel = 0;
+        }
+        if let Some(channel) = self.channel.filter(|c| crate::channels::is_valid(&config.band, *c)) {
+            config.channel = channel;
+        }
+        if let Some(interface) = self.interface.filter(|i| !i.is_empty()) {
+            config.hotspot_interface = interface;
+        }
+        name
+    }
+}
+
+/// An import with a nameless profile, for `parse` to name
+fn unnamed() -> Imported {
+    Imported { profile: Profile { name: String::new(), ..Profile::default() }, ..Imported::default() }
+}
+
+/// Band and channel for a frequency in MHz
+pub fn channel_for_frequency(mhz: u32) -> Option<(&'static str, u32)> {
+    match mhz {
+        2484 => Some(("bg", 14)),
+        2412..=2472 => Some(("bg", (mhz - 2407) / 5)),
+        5160..=5885 => Some(("a", (mhz - 5000) / 5)),
+        _ => None,
+    }
+}
+
+/// "wpa2", "wpa3" or "wpa2-wpa3" from a hostapd/wpa_supplicant `WPA-PSK SAE`
+/// list or an NM `wpa-psk`/`sae` value
+fn security_from_key_mgmt(key_mgmt: &str) -> String {
+    let methods: Vec<String> = key_mgmt.split_whitespace().map(str::to_ascii_lowercase).collect();
+    let sae = methods.iter().any(|m| m == "sae");
+    let psk = methods.iter().any(|m| m.starts_with("wpa-psk"));
+    match (sae, psk) {
+        (true, true) => "wpa2-wpa3",
+        (true, false) => "wpa3",
+        _ => "wpa2",
+    }
+    .to_string()
+}
+
+/// `key=value` pairs of the lines outside blocks, skipping comments
+fn key_values(text: &str, comment: char) -> Vec<(&str, &str)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with(comment))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// The `key=value` pairs of each `network={ ... }` block
+fn network_blocks(text: &str) -> Vec<Vec<(&str, &str)>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<(&str, &str)>> = None;
+    for line in text.lines().map(str::trim).filter(|line| !line.starts_with('#')) {
+        if line.starts_with("network={") {
+            current = Some(Vec::new());
+        } else if line == "}" {
+            blocks.extend(current.take());
+        } else if let (Some(block), Some((key, value))) = (current.as_mut(), line.split_once('=')) {
+            block.push((key.trim(), value.trim()));
+        }
+    }
+    blocks
+}
+
+/// The text between double quotes
+fn unquote(value: &str) -> Option<String> {
+    value.strip_prefix('"')?.strip_suffix('"').map(str::to_string)
+}
+
+/// A hex-encoded UTF-8 string, as unquoted SSIDs are written
+fn from_hex(value: &str) -> Option<String> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// NM writes SSIDs it can't store as text as decimal bytes: "72;105;"
+fn keyfile_ssid(value: &str) -> String {
+    value
+        .strip_suffix(';')
+        .and_then(|list| list.split(';').map(|b| b.parse().ok()).collect::<Option<Vec<u8>>>())
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn raw_psk_note() -> String {
+    "The file only has the derived key, not the password; set the password in settings".to_string()
+}
+
+fn open_network_note() -> String {
+    "The network was open; the hotspot always needs a password, so keep or set one".to_string()
+}
@@ -0,0 +1,122 @@
+//! Step-by-step progress of starting and stopping the hotspot.
+//!
+//! The backends report each step as they reach it; whoever drives the toggle
+//! (the applet) installs a sink to show a live progress line, and to say
+//! which step a failure happened in. Each step is also timed until the next
+//! one starts, so slow activations can be told apart from slow NAT setup.
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    CheckingSsid,
+    JoiningUpstream,
+    FreeingAdapter,
+    RemovingOldProfile,
+    CreatingProfile,
+    Activating,
+    ApplyingNat,
+    SharingWired,
+    Verifying,
+    RemovingNat,
+    Deactivating,
+    RestoringClient,
+}
+
+impl Step {
+    pub fn label(self) -> &'static str {
+        match self {
+            Step::CheckingSsid => "Checking for nearby networks with the same name",
+            Step::JoiningUpstream => "Joining upstream network",
+            Step::FreeingAdapter => "Freeing the WiFi adapter",
+            Step::RemovingOldProfile => "Removing old profile",
+            Step::CreatingProfile => "Creating hotspot profile",
+            Step::Activating => "Activating",
+            Step::ApplyingNat => "Applying NAT",
+            Step::SharingWired => "Sharing to wired interface",
+            Step::Verifying => "Verifying connectivity",
+            Step::RemovingNat => "Removing NAT rules",
+            Step::Deactivating => "Deactivating",
+            Step::RestoringClient => "Reconnecting WiFi client",
+        }
+    }
+
+    /// Stable name for metrics, e.g. "creating_profile"
+    pub fn id(self) -> &'static str {
+        match self {
+            Step::CheckingSsid => "checking_ssid",
+            Step::JoiningUpstream => "joining_upstream",
+            Step::FreeingAdapter => "freeing_adapter",
+            Step::RemovingOldProfile => "removing_old_profile",
+            Step::CreatingProfile => "creating_profile",
+            Step::Activating => "activating",
+            Step::ApplyingNat => "applying_nat",
+            Step::SharingWired => "sharing_wired",
+            Step::Verifying => "verifying",
+            Step::RemovingNat => "removing_nat",
+            Step::Deactivating => "deactivating",
+            Step::RestoringClient => "restoring_client",
+        }
+    }
+
+    /// Whether the step is part of stopping the hotspot rather than starting it
+    pub fn is_stopping(self) -> bool {
+        matches!(self, Step::RemovingNat | Step::Deactivating | Step::RestoringClient)
+    }
+}
+
+/// How long one step of a start or stop took
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepTiming {
+    pub step: Step,
+    pub millis: u64,
+}
+
+type Sink = Box<dyn Fn(Step) + Send>;
+
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+/// The step being timed and the steps finished since `start_timing`
+type Timer = (Option<(Step, Instant)>, Vec<StepTiming>);
+
+static TIMER: Mutex<Timer> = Mutex::new((None, Vec::new()));
+
+/// Receive every reported step from now on (`None` to stop)
+pub fn set_sink(sink: Option<Sink>) {
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/// Announce that `step` is starting, which ends the step before it
+pub fn report(step: Step) {
+    {
+        let mut timer = TIMER.lock().unwrap_or_else(|e| e.into_inner());
+        end_step(&mut timer);
+        timer.0 = Some((step, Instant::now()));
+    }
+    if let Some(sink) = SINK.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        sink(step);
+    }
+}
+
+/// Time the steps reported from now on, forgetting earlier ones
+pub fn start_timing() {
+    *TIMER.lock().unwrap_or_else(|e| e.into_inner()) = (None, Vec::new());
+}
+
+/// End the step in progress and return how long each step took since
+/// `start_timing`, in order
+pub fn finish_timing() -> Vec<StepTiming> {
+    let mut timer = TIMER.lock().unwrap_or_else(|e| e.into_inner());
+    end_step(&mut timer);
+    std::mem::take(&mut timer.1)
+}
+
+fn end_step(timer: &mut (Option<(Step, Instant)>, Vec<StepTiming>)) {
+    if let Some((step, started)) = timer.0.take() {
+        timer.1.push(StepTiming { step, millis: started.elapsed().as_millis() as u64 });
+    }
+}
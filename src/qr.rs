@@ -0,0 +1,50 @@
+//! "Scan to join" QR codes for the hotspot network.
+//!
+//! Phones understand the `WIFI:` URI scheme from the ZXing barcode spec:
+//! `WIFI:T:WPA;S:<ssid>;P:<password>;;`, with `\`, `;`, `,`, `:` and `"`
+//! backslash-escaped inside values.
+
+use qrcode::render::svg;
+use qrcode::QrCode;
+
+/// The `WIFI:` string for joining `ssid`; an empty password means an open
+/// network
+pub fn wifi_uri(ssid: &str, password: &str) -> String {
+    if password.is_empty() {
+        format!("WIFI:T:nopass;S:{};;", escape(ssid))
+    } else {
+        format!("WIFI:T:WPA;S:{};P:{};;", escape(ssid), escape(password))
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Side length and row-major dark/light modules of `data`'s QR code, without
+/// the quiet zone, or `None` if it's too long to encode
+pub fn modules(data: &str) -> Option<(usize, Vec<bool>)> {
+    let code = QrCode::new(data.as_bytes()).ok()?;
+    let dark = code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect();
+    Some((code.width(), dark))
+}
+
+/// `data` as a black-on-white SVG QR code at least `size` pixels wide, or
+/// `None` if it's too long to encode
+pub fn svg(data: &str, size: u32) -> Option<String> {
+    let code = QrCode::new(data.as_bytes()).ok()?;
+    Some(
+        code.render::<svg::Color<'_>>()
+            .min_dimensions(size, size)
+            .dark_color(svg::Color("#000000"))
+            .light_color(svg::Color("#ffffff"))
+            .build(),
+    )
+}
@@ -0,0 +1,141 @@
+//! Repeater mode: one WiFi adapter joins an upstream network as a client
+//! while another hosts the hotspot.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+
+use crate::config::Config;
+use crate::errors::{ErrorKind, HotspotError};
+use crate::nm_dbus::{self, Settings};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedNetwork {
+    pub ssid: String,
+    /// Signal strength, 0–100
+    pub signal: u32,
+    pub secured: bool,
+}
+
+impl ScannedNetwork {
+    /// Dropdown label, e.g. "Café-WiFi (72%, secured)"
+    pub fn label(&self) -> String {
+        if self.secured {
+            format!("{} ({}%, secured)", self.ssid, self.signal)
+        } else {
+            format!("{} ({}%, open)", self.ssid, self.signal)
+        }
+    }
+}
+
+/// State of the client-side link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientLink {
+    pub ssid: String,
+    pub signal: u32,
+}
+
+/// Networks visible from `interface`, strongest first, one entry per SSID
+pub fn scan(interface: &str) -> Vec<ScannedNetwork> {
+    let mut networks: Vec<ScannedNetwork> = Vec::new();
+    for ap in nm_dbus::access_points(interface, true) {
+        // Hidden networks have no SSID and can't be picked from a list
+        if ap.ssid.is_empty() {
+            continue;
+        }
+        let network = ScannedNetwork { ssid: ap.ssid, signal: ap.strength, secured: ap.secured };
+        match networks.iter_mut().find(|n| n.ssid == network.ssid) {
+            Some(existing) if existing.signal < network.signal => *existing = network,
+            Some(_) => {}
+            None => networks.push(network),
+        }
+    }
+    networks.sort_by_key(|n| std::cmp::Reverse(n.signal));
+    networks
+}
+
+/// The network `interface` is currently associated with, if any
+pub fn client_link(interface: &str) -> Option<ClientLink> {
+    nm_dbus::active_access_point(interface).map(|ap| ClientLink { ssid: ap.ssid, signal: ap.strength })
+}
+
+/// Make sure the client adapter is on the configured upstream network,
+/// connecting (and saving the profile through NM) if it isn't
+pub fn ensure_upstream(config: &Config) -> Result<(), HotspotError> {
+    let client_if = &config.internet_interface;
+    if client_if == &config.hotspot_interface {
+        return Err(HotspotError::new(
+            ErrorKind::InvalidSetting,
+            "Repeater mode needs two different WiFi adapters",
+        ));
+    }
+    if config.repeater_ssid.is_empty() {
+        return Err(HotspotError::new(
+            ErrorKind::InvalidSetting,
+            "No upstream network selected for repeater mode",
+        ));
+    }
+    if client_link(client_if).is_some_and(|link| link.ssid == config.repeater_ssid) {
+        return Ok(());
+    }
+
+    // A profile saved for the network keeps its own settings; otherwise save
+    // one named after it, the way joining from the network menu does
+    let id = match saved_client_connection(&config.repeater_ssid) {
+        Some(id) => id,
+        None => {
+            let sae_only = nm_dbus::access_points(client_if, false)
+                .iter()
+                .any(|ap| ap.ssid == config.repeater_ssid && ap.sae_only);
+            let settings = upstream_settings(&config.repeater_ssid, &config.repeater_password, sae_only);
+            nm_dbus::add_connection(settings, config.dry_run)
+                .map_err(|e| HotspotError::from_nm(e, ErrorKind::UpstreamFailed))?;
+            config.repeater_ssid.clone()
+        }
+    };
+    nm_dbus::activate(&id, Some(client_if), config.dry_run)
+        .map(drop)
+        .map_err(|e| HotspotError::from_nm(e, ErrorKind::UpstreamFailed))
+}
+
+/// Settings of a new client connection to `ssid`: WPA3 SAE if the network
+/// takes nothing else, WPA2 otherwise, open without a password
+pub fn upstream_settings(ssid: &str, password: &str, sae_only: bool) -> Settings {
+    let mut settings = Settings::from([
+        (
+            "connection",
+            HashMap::from([
+                ("id", Value::from(ssid.to_string())),
+                ("type", Value::from("802-11-wireless")),
+            ]),
+        ),
+        (
+            "802-11-wireless",
+            HashMap::from([
+                ("ssid", Value::from(ssid.as_bytes().to_vec())),
+                ("mode", Value::from("infrastructure")),
+            ]),
+        ),
+    ]);
+    if !password.is_empty() {
+        settings.insert(
+            "802-11-wireless-security",
+            HashMap::from([
+                ("key-mgmt", Value::from(if sae_only { "sae" } else { "wpa-psk" })),
+                ("psk", Value::from(password.to_string())),
+            ]),
+        );
+    }
+    settings
+}
+
+/// The id of a saved client connection to `ssid`, if there is one
+fn saved_client_connection(ssid: &str) -> Option<String> {
+    nm_dbus::saved_connections().into_iter().find_map(|settings| {
+        let mode = nm_dbus::string_setting(&settings, "802-11-wireless", "mode").unwrap_or_default();
+        let client = mode.is_empty() || mode == "infrastructure";
+        (client && nm_dbus::ssid_setting(&settings).as_deref() == Some(ssid))
+            .then(|| nm_dbus::string_setting(&settings, "connection", "id"))
+            .flatten()
+    })
+}
@@ -0,0 +1,201 @@
+//! When the hotspot was started and why the last start failed.
+//!
+//! NetworkManager doesn't keep an activation time, and a failure reported in
+//! the popup is gone once the popup closes. The real backend records both in
+//! the runtime dir, so any process (e.g. the hub's `--settings-status`) can
+//! show uptime and the last error. How long each step of the last start took
+//! is kept for `--metrics` and the self-test. The hub's two-press reset keeps
+//! its first press here too, as each hub action runs in a new process.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::errors::HotspotError;
+use crate::progress::{Step, StepTiming};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunState {
+    /// Unix time of the last successful start, cleared on stop
+    pub started_at: Option<u64>,
+    /// NM's D-Bus path of the activation we started or adopted; a different
+    /// one means the hotspot was brought up without us
+    #[serde(default)]
+    pub activation: Option<String>,
+    /// `Config::start_fingerprint` of the settings we started with; unknown
+    /// for an adopted hotspot
+    #[serde(default)]
+    pub settings: Option<u64>,
+    /// USB device we keep from autosuspending while the hotspot runs, to let
+    /// it suspend again on stop
+    #[serde(default)]
+    pub usb_power_held: Option<String>,
+    pub last_error: Option<LastError>,
+    /// Journal of the last start attempt, successful or not
+    #[serde(default)]
+    pub activation_log: Option<ActivationLog>,
+    /// Unix time of a hub "Reset to Defaults" press awaiting its second press
+    #[serde(default)]
+    pub reset_requested_at: Option<u64>,
+    /// Step timings of the last start attempt
+    #[serde(default)]
+    pub timings: Option<Timings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastError {
+    /// The translated message the popup showed
+    pub message: String,
+    pub detail: String,
+    /// Unix time of the failure
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivationLog {
+    /// Unix time the attempt began
+    pub at: u64,
+    pub succeeded: bool,
+    /// NetworkManager and wpa_supplicant entries, see `activation_log`
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timings {
+    /// Unix time the attempt began
+    pub at: u64,
+    pub succeeded: bool,
+    /// In the order the steps ran; a failed attempt ends with the step it
+    /// failed in
+    pub steps: Vec<StepTiming>,
+}
+
+impl Timings {
+    /// How long `step` took, if it ran
+    pub fn millis(&self, step: Step) -> Option<u64> {
+        self.steps.iter().find(|t| t.step == step).map(|t| t.millis)
+    }
+
+    pub fn total_millis(&self) -> u64 {
+        self.steps.iter().map(|t| t.millis).sum()
+    }
+}
+
+impl RunState {
+    /// Seconds the hotspot has been up at `now`, if its start was recorded
+    pub fn uptime(&self, now: u64) -> Option<u64> {
+        self.started_at.map(|started| now.saturating_sub(started))
+    }
+
+    /// The journal of the last start attempt, if it failed
+    pub fn failed_activation_log(&self) -> Option<&[String]> {
+        self.activation_log.as_ref().filter(|log| !log.succeeded).map(|log| log.lines.as_slice())
+    }
+
+    /// Whether the running hotspot was started with other settings than
+    /// `config`, so it needs a restart for them to apply
+    pub fn uses_old_settings(&self, config: &Config) -> bool {
+        self.started_at.is_some() && self.settings.is_some_and(|s| s != config.start_fingerprint())
+    }
+}
+
+/// The recorded state; empty if nothing was recorded since boot
+pub fn load() -> RunState {
+    state_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Record the outcome of a start: the start time, NM activation and the
+/// settings' fingerprint, or the error (keeping the previous start, as a
+/// failed restart may leave the old one running)
+pub fn record_start(result: &Result<String, HotspotError>, activation: Option<String>, settings: u64) {
+    let mut state = load();
+    match result {
+        Ok(_) => {
+            state.started_at = Some(now());
+            state.activation = activation;
+            state.settings = Some(settings);
+            state.last_error = None;
+        }
+        Err(e) => {
+            state.last_error = Some(LastError {
+                message: e.message().to_string(),
+                detail: e.detail.clone(),
+                at: now(),
+            });
+        }
+    }
+    save(&state);
+}
+
+/// Record a hotspot started without us as ours, from `started_at`
+pub fn record_adopted(started_at: u64, activation: String) {
+    let mut state = load();
+    state.started_at = Some(started_at);
+    state.activation = Some(activation);
+    state.settings = None;
+    save(&state);
+}
+
+pub fn record_stop() {
+    let mut state = load();
+    state.started_at = None;
+    state.activation = None;
+    state.settings = None;
+    save(&state);
+}
+
+/// Record the USB device kept powered for the hotspot, or that it was let go
+pub fn record_usb_power_held(device: Option<String>) {
+    let mut state = load();
+    state.usb_power_held = device;
+    save(&state);
+}
+
+/// Record the journal of a start attempt, replacing the previous one
+pub fn record_activation_log(log: ActivationLog) {
+    let mut state = load();
+    state.activation_log = Some(log);
+    save(&state);
+}
+
+/// Record the step timings of a start attempt, replacing the previous ones
+pub fn record_timings(timings: Timings) {
+    let mut state = load();
+    state.timings = Some(timings);
+    save(&state);
+}
+
+/// Record a first "Reset to Defaults" press, or clear it
+pub fn record_reset_request(at: Option<u64>) {
+    let mut state = load();
+    state.reset_requested_at = at;
+    save(&state);
+}
+
+/// Current Unix time in seconds
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn save(state: &RunState) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(state) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+fn state_path() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join("cosmic-hotspot").join("run-state.json"))
+}
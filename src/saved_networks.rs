@@ -0,0 +1,61 @@
+//! WiFi networks saved in NetworkManager, for giving the hotspot the same
+//! name and password as one of them: devices that know the home network then
+//! roam onto the hotspot without being set up again.
+//!
+//! Only WPA-Personal client profiles are offered; enterprise and open
+//! networks have no password the hotspot could reuse.
+
+use crate::nm_dbus::{self, OwnedSettings};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedNetwork {
+    /// NM connection UUID
+    pub uuid: String,
+    /// NM connection name
+    pub name: String,
+    pub ssid: String,
+}
+
+impl SavedNetwork {
+    /// Picker label: the SSID, with the connection name if it differs
+    pub fn label(&self) -> String {
+        if self.name == self.ssid {
+            self.ssid.clone()
+        } else {
+            format!("{} ({})", self.ssid, self.name)
+        }
+    }
+}
+
+/// Saved WPA-Personal client networks, sorted by SSID
+pub fn list() -> Vec<SavedNetwork> {
+    let mut networks: Vec<SavedNetwork> = nm_dbus::saved_connections().iter().filter_map(personal_network).collect();
+    networks.sort_by_key(|n| n.ssid.to_lowercase());
+    networks
+}
+
+/// The network a saved connection's settings describe, if it's a WiFi
+/// client profile secured with a WPA-Personal password (WPA2 PSK or WPA3 SAE)
+pub fn personal_network(settings: &OwnedSettings) -> Option<SavedNetwork> {
+    let value = |setting, property| nm_dbus::string_setting(settings, setting, property).unwrap_or_default();
+    let mode = value("802-11-wireless", "mode");
+    let client = mode.is_empty() || mode == "infrastructure";
+    let personal = matches!(value("802-11-wireless-security", "key-mgmt").as_str(), "wpa-psk" | "sae");
+    if value("connection", "type") != "802-11-wireless" || !client || !personal {
+        return None;
+    }
+    let ssid = nm_dbus::ssid_setting(settings).filter(|ssid| !ssid.is_empty())?;
+    Some(SavedNetwork { uuid: value("connection", "uuid"), name: value("connection", "id"), ssid })
+}
+
+/// The saved password of a connection. NetworkManager only reveals it to
+/// users allowed to see the connection's secrets.
+pub fn password(network: &SavedNetwork) -> Result<String, String> {
+    let secrets = nm_dbus::connection_secrets(&network.uuid, "802-11-wireless-security")
+        .map_err(|e| format!("NetworkManager didn't reveal the password of '{}': {e}", network.name))?;
+    match nm_dbus::string_setting(&secrets, "802-11-wireless-security", "psk") {
+        Some(psk) if !psk.is_empty() => Ok(psk),
+        // Kept in a keyring agent rather than by NetworkManager
+        _ => Err(format!("NetworkManager didn't reveal the password of '{}'", network.name)),
+    }
+}
@@ -0,0 +1,146 @@
+//! Weekly access windows for hotspot clients.
+//!
+//! A schedule is a set of weekdays plus a daily time window, both in local
+//! time. The helper turns it into iptables `time` matches, so the kernel
+//! enforces it even while the applet isn't running; the popup uses the same
+//! model to show how long a client has left.
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+/// Day names as iptables' `--weekdays` spells them
+const IPTABLES_DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Allowed weekdays, Monday first
+    pub days: [bool; 7],
+    /// Start of the allowed window, in minutes after midnight
+    pub from: u32,
+    /// End of the window; before `from` means it runs past midnight, equal
+    /// to `from` means all day
+    pub until: u32,
+}
+
+impl Schedule {
+    /// Parse "mon-fri,sun" ("" = every day) and "07:00"/"21:00" ("" for both
+    /// = all day)
+    pub fn parse(days: &str, from: &str, until: &str) -> Result<Self, String> {
+        let (from, until) = match (from.trim(), until.trim()) {
+            ("", "") => (0, 0),
+            ("", _) | (_, "") => return Err("An access window needs both a start and an end time".to_string()),
+            (from, until) => (parse_time(from)?, parse_time(until)?),
+        };
+        Ok(Self { days: parse_days(days)?, from, until })
+    }
+
+    /// Whether access is allowed on `weekday` (0 = Monday) at `minute`
+    pub fn allows(&self, weekday: usize, minute: u32) -> bool {
+        let in_window = if self.from == self.until {
+            true
+        } else if self.from < self.until {
+            (self.from..self.until).contains(&minute)
+        } else {
+            minute >= self.from || minute < self.until
+        };
+        self.days[weekday % 7] && in_window
+    }
+
+    /// Minutes until `allows` flips, or `None` if it never does
+    pub fn minutes_until_change(&self, weekday: usize, minute: u32) -> Option<u32> {
+        let now = self.allows(weekday, minute);
+        (1..=7 * MINUTES_PER_DAY).find(|offset| {
+            let t = minute + offset;
+            self.allows(weekday + (t / MINUTES_PER_DAY) as usize, t % MINUTES_PER_DAY) != now
+        })
+    }
+
+    /// The blocked weekdays, e.g. "Sat,Sun", or `None` if every day is allowed
+    pub fn iptables_blocked_days(&self) -> Option<String> {
+        let blocked: Vec<&str> = (0..7)
+            .filter(|&d| !self.days[d])
+            .map(|d| IPTABLES_DAYS[d])
+            .collect();
+        (!blocked.is_empty()).then(|| blocked.join(","))
+    }
+
+    /// The helper's days argument, e.g. "mon,tue,wed"
+    pub fn days_arg(&self) -> String {
+        (0..7)
+            .filter(|&d| self.days[d])
+            .map(|d| DAY_NAMES[d])
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// "HH:MM" for minutes after midnight
+pub fn format_time(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+}
+
+/// A short countdown like "2h 14m" or "3d 4h"
+pub fn format_duration(minutes: u32) -> String {
+    match minutes {
+        m if m >= MINUTES_PER_DAY => format!("{}d {}h", m / MINUTES_PER_DAY, m % MINUTES_PER_DAY / 60),
+        m if m >= 60 => format!("{}h {}m", m / 60, m % 60),
+        m => format!("{m}m"),
+    }
+}
+
+/// Current local weekday (0 = Monday) and minute of the day
+pub fn local_now() -> (usize, u32) {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return (0, 0);
+    }
+    // tm_wday counts from Sunday
+    (((tm.tm_wday + 6) % 7) as usize, (tm.tm_hour * 60 + tm.tm_min) as u32)
+}
+
+/// Minutes after midnight for "HH:MM"
+pub fn parse_time(value: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid time '{value}': expected HH:MM");
+    let (hours, minutes) = value.split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+fn parse_days(value: &str) -> Result<[bool; 7], String> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() || value == "all" {
+        return Ok([true; 7]);
+    }
+    let day = |name: &str| {
+        DAY_NAMES
+            .iter()
+            .position(|&d| d == name.trim())
+            .ok_or_else(|| format!("Invalid day '{}': use mon, tue, ... sun", name.trim()))
+    };
+    let mut days = [false; 7];
+    for part in value.split(',').filter(|p| !p.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                // "fri-mon" wraps over the weekend
+                let mut d = first;
+                loop {
+                    days[d] = true;
+                    if d == last {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => days[day(part)?] = true,
+        }
+    }
+    if !days.contains(&true) {
+        return Err("No days given".to_string());
+    }
+    Ok(days)
+}
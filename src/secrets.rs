@@ -0,0 +1,239 @@
+//! Storage of the passwords referenced by config.json.
+//!
+//! With a Secret Service keyring running, passwords live in the keyring and
+//! config.json only holds a `keyring:` marker. Without one they're sealed
+//! with ChaCha20-Poly1305 under a random 256-bit key in a 0600 file in the
+//! data dir, which still keeps them out of config backups, dotfile repos and
+//! pasted bug reports. Plaintext values from older files are read as-is.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::keyring;
+
+/// Marks an encrypted value; anything else is read as plaintext
+const PREFIX: &str = "enc:v1:";
+/// Marks a value stored in the keyring. The suffix changes on every write so
+/// other processes know to re-read it.
+const KEYRING_PREFIX: &str = "keyring:";
+const NONCE_LEN: usize = 12;
+const KEY_ATTRIBUTES: &[(&str, &str)] = &[("application", "cosmic-hotspot"), ("kind", "config-key")];
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+pub fn in_keyring(value: &str) -> bool {
+    value.starts_with(KEYRING_PREFIX)
+}
+
+/// Whether a Secret Service keyring answers, checked once per process
+pub fn keyring_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| keyring::lookup(KEY_ATTRIBUTES).is_ok())
+}
+
+/// What to write to config.json for the secret `field`: a keyring marker if
+/// possible, otherwise the encrypted value. An empty value removes the item.
+pub fn seal(field: &str, plain: &str) -> Result<String, String> {
+    if !keyring_available() {
+        return encrypt(plain);
+    }
+    let attributes = password_attributes(field);
+    if plain.is_empty() {
+        let _ = keyring::delete(&attributes);
+        return Ok(String::new());
+    }
+    let label = format!("COSMIC Hotspot {}", field.replace('_', " "));
+    match keyring::store(&label, &attributes, plain.as_bytes()) {
+        Ok(()) => {
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            let marker = format!("{KEYRING_PREFIX}{stamp}");
+            cache().insert(format!("{field}/{marker}"), plain.to_string());
+            Ok(marker)
+        }
+        Err(e) => {
+            eprintln!("{e}; encrypting {field} in the config file instead");
+            encrypt(plain)
+        }
+    }
+}
+
+/// Like `seal`, but keeps `stored` (what config.json holds now) if it already
+/// has `plain` in the preferred storage, so saving a config doesn't write
+/// unchanged passwords to the keyring again
+pub fn reseal(field: &str, plain: &str, stored: &str) -> Result<String, String> {
+    if plain.is_empty() && stored.is_empty() {
+        return Ok(String::new());
+    }
+    let preferred = if keyring_available() { in_keyring(stored) } else { is_encrypted(stored) };
+    if preferred && open(field, stored).is_ok_and(|current| current == plain) {
+        return Ok(stored.to_string());
+    }
+    seal(field, plain)
+}
+
+/// The plaintext behind a value written by `seal` (or an older plaintext value).
+/// A value that couldn't be opened keeps failing for the rest of the process
+/// rather than raising another unlock prompt; a new value written by `seal`
+/// is tried afresh.
+pub fn open(field: &str, stored: &str) -> Result<String, String> {
+    let cache_key = format!("{field}/{stored}");
+    if let Some(e) = failures().get(&cache_key) {
+        return Err(e.clone());
+    }
+    let opened = if in_keyring(stored) {
+        open_keyring(field, &cache_key)
+    } else {
+        decrypt(stored)
+    };
+    if let Err(e) = &opened {
+        failures().insert(cache_key, e.clone());
+    }
+    opened
+}
+
+fn open_keyring(field: &str, cache_key: &str) -> Result<String, String> {
+    if let Some(plain) = cache().get(cache_key) {
+        return Ok(plain.clone());
+    }
+    let bytes = keyring::lookup(&password_attributes(field))?
+        .ok_or_else(|| format!("{field} is missing from the keyring"))?;
+    let plain = String::from_utf8(bytes).map_err(|_| format!("{field} in the keyring isn't UTF-8"))?;
+    cache().insert(cache_key.to_string(), plain.clone());
+    Ok(plain)
+}
+
+/// Keyring values already read, by field and marker; the applet reloads the
+/// config every poll and shouldn't hit D-Bus each time
+fn cache() -> std::sync::MutexGuard<'static, HashMap<String, String>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Why stored values couldn't be opened, by field and stored value
+fn failures() -> std::sync::MutexGuard<'static, HashMap<String, String>> {
+    static FAILURES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    FAILURES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn password_attributes(field: &str) -> [(&str, &str); 3] {
+    [("application", "cosmic-hotspot"), ("kind", "password"), ("field", field)]
+}
+
+/// Seal `plain`; the empty string stays empty
+pub fn encrypt(plain: &str) -> Result<String, String> {
+    if plain.is_empty() {
+        return Ok(String::new());
+    }
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = ChaCha20Poly1305::new(&key()?)
+        .encrypt(&nonce, plain.as_bytes())
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    let mut blob = nonce.to_vec();
+    blob.extend(sealed);
+    Ok(format!("{PREFIX}{}", STANDARD.encode(blob)))
+}
+
+/// Open a value written by `encrypt`; plaintext is returned unchanged
+pub fn decrypt(value: &str) -> Result<String, String> {
+    let Some(encoded) = value.strip_prefix(PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let blob = STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Corrupt encrypted secret: {e}"))?;
+    if blob.len() < NONCE_LEN {
+        return Err("Corrupt encrypted secret: too short".to_string());
+    }
+    let (nonce, sealed) = blob.split_at(NONCE_LEN);
+    let plain = ChaCha20Poly1305::new(&key()?)
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| "Secret can't be decrypted (config key changed or missing)".to_string())?;
+    String::from_utf8(plain).map_err(|_| "Decrypted secret isn't UTF-8".to_string())
+}
+
+/// The config key, loaded (or created) once per process
+fn key() -> Result<Key, String> {
+    static KEY: OnceLock<Key> = OnceLock::new();
+    if let Some(key) = KEY.get() {
+        return Ok(*key);
+    }
+    let key = load_or_create_key()?;
+    Ok(*KEY.get_or_init(|| key))
+}
+
+fn load_or_create_key() -> Result<Key, String> {
+    let file_key = read_key_file();
+    match keyring::lookup(KEY_ATTRIBUTES) {
+        Ok(Some(bytes)) if bytes.len() == 32 => return Ok(*Key::from_slice(&bytes)),
+        Ok(Some(_)) => return Err("Config key in the keyring has the wrong length".to_string()),
+        Ok(None) => {
+            // A key from a keyring-less session moves into the keyring, so
+            // values written back then still decrypt
+            let key = file_key.unwrap_or_else(|| ChaCha20Poly1305::generate_key(&mut OsRng));
+            match keyring::store("COSMIC Hotspot config key", KEY_ATTRIBUTES, &key) {
+                Ok(()) => {
+                    if let Some(path) = key_file_path() {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    return Ok(key);
+                }
+                Err(e) => eprintln!("{e}; keeping the config key in a file instead"),
+            }
+        }
+        // The keyring may hold the key but be locked or not up yet; a new
+        // key now would orphan every value sealed under the real one
+        Err(e) if file_key.is_none() && keyring::installed() => return Err(e),
+        Err(e) => eprintln!("{e}; keeping the config key in a file instead"),
+    }
+
+    if let Some(key) = file_key {
+        return Ok(key);
+    }
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    write_key_file(&key)?;
+    Ok(key)
+}
+
+fn key_file_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("cosmic-hotspot").join("config.key"))
+}
+
+fn read_key_file() -> Option<Key> {
+    let bytes = std::fs::read(key_file_path()?).ok()?;
+    (bytes.len() == 32).then(|| *Key::from_slice(&bytes))
+}
+
+fn write_key_file(key: &Key) -> Result<(), String> {
+    let path = key_file_path().ok_or("Could not determine data dir for the config key")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data dir: {e}"))?;
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut f| f.write_all(key))
+        .map_err(|e| format!("Failed to write config key: {e}"))
+}
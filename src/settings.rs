@@ -1,21 +1,65 @@
+//! Standalone settings window, for when the settings hub isn't installed.
+//!
+//! Wraps `settings_page` with a header bar holding the Save button, Ctrl+S to
+//! save and Ctrl+W or Escape to close, and asks before closing with unsaved
+//! changes.
+
 use cosmic::app::Core;
-use cosmic::iced::Length;
-use cosmic::widget::{self, container};
-use cosmic::{Action, Application, Element, Task};
+use cosmic::iced::keyboard::{self, key::Named, Key, Modifiers};
+use cosmic::iced::window::Id;
+use cosmic::iced::{Length, Subscription};
+use cosmic::widget::{self, button, container};
+use cosmic::{Action, Application, ApplicationExt, Element, Task};
 
-use crate::settings_page;
+use crate::settings_page::{self, Output};
 
 const APP_ID: &str = "io.github.reality2_roycdavies.cosmic-hotspot.settings";
+const TITLE: &str = "WiFi Hotspot Settings";
 
 pub struct SettingsApp {
     core: Core,
     page: settings_page::State,
+    /// The "unsaved changes" dialog is open
+    confirm_close: bool,
+    /// Close once the save in progress succeeds
+    close_after_save: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Page(settings_page::Message),
+    /// The window's close button, Ctrl+W or Escape
+    CloseRequested,
+    SaveAndClose,
+    /// Close without saving
+    Discard,
+    CancelClose,
+}
+
+impl SettingsApp {
+    fn update_page(&mut self, message: settings_page::Message) -> Task<Action<Message>> {
+        let (task, output) = settings_page::update(&mut self.page, message);
+        let task = task.map(|m| Action::App(Message::Page(m)));
+        match output {
+            Some(Output::ConfigSaved | Output::RequestRestart) if self.close_after_save => {
+                Task::batch([task, cosmic::iced::exit()])
+            }
+            Some(Output::Error(e)) => {
+                // Shown in the page's status line; the log is for running
+                // from a terminal
+                eprintln!("Settings: {e}");
+                self.close_after_save = false;
+                task
+            }
+            _ => task,
+        }
+    }
 }
 
 impl Application for SettingsApp {
     type Executor = cosmic::executor::Default;
     type Flags = ();
-    type Message = settings_page::Message;
+    type Message = Message;
 
     const APP_ID: &'static str = APP_ID;
 
@@ -27,22 +71,77 @@ impl Application for SettingsApp {
         &mut self.core
     }
 
-    fn header_start(&self) -> Vec<Element<'_, Self::Message>> {
-        vec![]
+    fn header_end(&self) -> Vec<Element<'_, Self::Message>> {
+        let saving = self.page.pending == Some(settings_page::Pending::Saving);
+        vec![button::suggested(if saving { "Saving..." } else { "Save" })
+            .on_press_maybe(
+                (self.page.pending.is_none() && settings_page::has_unsaved_changes(&self.page))
+                    .then_some(Message::Page(settings_page::Message::Save)),
+            )
+            .into()]
     }
 
     fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Action<Self::Message>>) {
-        let page = settings_page::init();
-        (Self { core, page }, Task::none())
+        // The header bar carries the title
+        let (page, task) = settings_page::init_with(settings_page::Options {
+            show_title: false,
+            ..settings_page::Options::default()
+        });
+        let mut app = Self { core, page, confirm_close: false, close_after_save: false };
+        app.set_header_title(TITLE.to_string());
+        let title = app.core.main_window_id().map(|id| app.set_window_title(TITLE.to_string(), id));
+        let task = task.map(|m| Action::App(Message::Page(m)));
+        (app, Task::batch(title.into_iter().chain([task])))
+    }
+
+    fn on_close_requested(&self, _id: Id) -> Option<Self::Message> {
+        Some(Message::CloseRequested)
     }
 
     fn update(&mut self, message: Self::Message) -> Task<Action<Self::Message>> {
-        settings_page::update(&mut self.page, message);
+        match message {
+            Message::Page(message) => return self.update_page(message),
+            Message::CloseRequested => {
+                if !settings_page::has_unsaved_changes(&self.page) {
+                    return cosmic::iced::exit();
+                }
+                self.confirm_close = true;
+            }
+            Message::SaveAndClose => {
+                self.confirm_close = false;
+                self.close_after_save = true;
+                return self.update_page(settings_page::Message::Save);
+            }
+            Message::Discard => return cosmic::iced::exit(),
+            Message::CancelClose => self.confirm_close = false,
+        }
         Task::none()
     }
 
+    fn dialog(&self) -> Option<Element<'_, Self::Message>> {
+        if !self.confirm_close {
+            return None;
+        }
+        Some(
+            widget::dialog()
+                .title("Save changes before closing?")
+                .body("The hotspot settings have unsaved changes.")
+                .primary_action(button::suggested("Save").on_press(Message::SaveAndClose))
+                .secondary_action(button::standard("Cancel").on_press(Message::CancelClose))
+                .tertiary_action(button::destructive("Discard").on_press(Message::Discard))
+                .into(),
+        )
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        Subscription::batch([
+            settings_page::subscription().map(Message::Page),
+            keyboard::on_key_press(shortcut),
+        ])
+    }
+
     fn view(&self) -> Element<'_, Self::Message> {
-        let content = settings_page::view(&self.page);
+        let content = settings_page::view(&self.page).map(Message::Page);
 
         widget::scrollable(
             container(container(content).max_width(800))
@@ -56,6 +155,16 @@ impl Application for SettingsApp {
     }
 }
 
+/// Ctrl+S saves; Ctrl+W and Escape close
+fn shortcut(key: Key, modifiers: Modifiers) -> Option<Message> {
+    match key.as_ref() {
+        Key::Character("s") if modifiers.command() => Some(Message::Page(settings_page::Message::Save)),
+        Key::Character("w") if modifiers.command() => Some(Message::CloseRequested),
+        Key::Named(Named::Escape) => Some(Message::CloseRequested),
+        _ => None,
+    }
+}
+
 pub fn run_settings() -> cosmic::iced::Result {
     let settings = cosmic::app::Settings::default()
         .size(cosmic::iced::Size::new(650.0, 600.0))
@@ -63,6 +172,8 @@ pub fn run_settings() -> cosmic::iced::Result {
             cosmic::iced::Limits::NONE
                 .min_width(500.0)
                 .min_height(450.0),
-        );
+        )
+        // Closing is decided in `update`, after asking about unsaved changes
+        .exit_on_close(false);
     cosmic::app::run::<SettingsApp>(settings, ())
 }
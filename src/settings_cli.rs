@@ -1,142 +1,579 @@
 //! CLI settings protocol for cosmic-applet-settings hub integration.
 
-use crate::config::Config;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+
+use crate::adapters;
+use crate::backend;
+use crate::channels;
+use crate::config::{self, Config, Profile};
+use crate::errors::{ErrorKind, HotspotError};
+use crate::hotplug;
 use crate::hotspot;
+use crate::interface_cache;
+use crate::nat;
+use crate::run_state;
+use crate::schedule;
+use crate::settings_path;
+use crate::shortcut;
+
+/// Seconds within which a second "Reset to Defaults" press confirms the first
+const RESET_CONFIRM_SECS: u64 = 10;
+
+/// Order of the hub's sections; fields name theirs with `x-section`
+const SECTIONS: &[&str] = &[
+    "Network",
+    "Interfaces",
+    "Repeater",
+    "Profile",
+    "Advanced",
+    "Display",
+    "Shortcuts",
+];
 
+/// How often `serve` checks for config changes, and for interface changes
+/// when no hotplug event arrives first
+const SERVE_POLL: Duration = Duration::from_secs(2);
+
+/// Print the hub schema
 pub fn describe() {
+    let schema = schema(&Config::load());
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
+
+/// `--settings-serve`: print the schema as one JSON line, then one line per
+/// change — `{"event": "schema", ...}` when the interface lists (and so the
+/// options) change, `{"event": "values", ...}` when the config is modified
+/// elsewhere. Runs until stdout is closed.
+pub fn serve() {
+    let hotplug = hotplug::subscribe();
+    let mut last_interfaces = None;
+    let mut last_values = None;
+    loop {
+        let config = Config::load();
+        // Listed fresh each time, which also keeps the shared cache warm for
+        // the schema below and for other processes
+        let interfaces = interface_cache::refresh();
+        let values = values(&config);
+
+        let event = if last_interfaces.as_ref() != Some(&interfaces) {
+            Some(json!({"event": "schema", "schema": schema(&config)}))
+        } else if last_values.as_ref() != Some(&values) {
+            Some(json!({"event": "values", "values": values}))
+        } else {
+            None
+        };
+        if let Some(event) = event {
+            let mut out = std::io::stdout().lock();
+            if writeln!(out, "{event}").and_then(|()| out.flush()).is_err() {
+                return;
+            }
+        }
+
+        last_interfaces = Some(interfaces);
+        last_values = Some(values);
+        if let Err(RecvTimeoutError::Disconnected) = hotplug.recv_timeout(SERVE_POLL) {
+            std::thread::sleep(SERVE_POLL);
+        }
+    }
+}
+
+/// `--settings-status`: the hotspot's runtime state as one JSON object, for
+/// the hub to show next to the settings. `uptime_secs` is null when the
+/// hotspot is down or was started outside cosmic-hotspot; `last_error` is
+/// the last failed start since boot, cleared by a successful one, and
+/// `activation_log` what NetworkManager logged during the last attempt and
+/// `timings` how long each of its steps took.
+pub fn status() {
     let config = Config::load();
+    let active = backend::active().is_active(&config);
+    let clients = if active { backend::active().connected_clients(&config).len() } else { 0 };
+    let state = run_state::load();
+    let status = json!({
+        "active": active,
+        "ssid": config.ssid,
+        "clients": clients,
+        "uptime_secs": if active { state.uptime(run_state::now()) } else { None },
+        "last_error": state.last_error,
+        "activation_log": state.activation_log,
+        "timings": state.timings,
+    });
+    println!("{status}");
+}
 
-    let wifi_interfaces = hotspot::list_wifi_interfaces();
-    let wifi_opts: Vec<serde_json::Value> = wifi_interfaces
-        .iter()
-        .map(|i| serde_json::json!({"value": i, "label": i}))
-        .collect();
+/// The current value of every item in the schema, by key
+fn values(config: &Config) -> serde_json::Map<String, Value> {
+    let mut values = serde_json::Map::new();
+    for (schema, current) in [
+        (schemars::schema_for!(Config), to_value(config)),
+        (schemars::schema_for!(Profile), to_value(config.profile())),
+    ] {
+        let Some(properties) = schema.as_value().get("properties").and_then(Value::as_object) else {
+            continue;
+        };
+        for (key, property) in properties {
+            if property.get("x-section").is_some() {
+                values.insert(key.clone(), current[key].clone());
+            }
+        }
+    }
+    values
+}
+
+/// The hub schema. Items are generated from the `Config` and `Profile` JSON
+/// schemas, so a new annotated field shows up without touching this file.
+fn schema(config: &Config) -> Value {
+    let sources = OptionSources::gather(config);
 
-    let net_interfaces = hotspot::list_network_interfaces();
-    let net_opts: Vec<serde_json::Value> = net_interfaces
-        .iter()
-        .map(|i| serde_json::json!({"value": i, "label": i}))
+    let mut sections: Vec<(&str, Vec<Value>)> = SECTIONS.iter().map(|s| (*s, Vec::new())).collect();
+    add_items(&mut sections, &schemars::schema_for!(Config), &to_value(&config), &sources);
+    add_items(&mut sections, &schemars::schema_for!(Profile), &to_value(config.profile()), &sources);
+
+    let sections: Vec<Value> = sections
+        .into_iter()
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(title, items)| {
+            let title = match title {
+                "Profile" => format!("Profile: {}", config.profile().name),
+                other => other.to_string(),
+            };
+            json!({"title": title, "items": items})
+        })
         .collect();
 
-    let schema = serde_json::json!({
+    json!({
         "title": "WiFi Hotspot Settings",
         "description": "Configure and manage a WiFi hotspot using NetworkManager.",
-        "sections": [
-            {
-                "title": "Network",
-                "items": [
-                    {
-                        "type": "text",
-                        "key": "ssid",
-                        "label": "SSID",
-                        "value": config.ssid,
-                        "placeholder": "Network name"
-                    },
-                    {
-                        "type": "text",
-                        "key": "password",
-                        "label": "Password",
-                        "value": config.password,
-                        "placeholder": "WPA2 password"
-                    },
-                    {
-                        "type": "select",
-                        "key": "band",
-                        "label": "Band",
-                        "value": config.band,
-                        "options": [
-                            {"value": "bg", "label": "2.4 GHz"},
-                            {"value": "a", "label": "5 GHz"}
-                        ]
-                    }
-                ]
-            },
-            {
-                "title": "Interfaces",
-                "items": [
-                    {
-                        "type": "select",
-                        "key": "hotspot_interface",
-                        "label": "Hotspot Interface",
-                        "value": config.hotspot_interface,
-                        "options": wifi_opts
-                    },
-                    {
-                        "type": "select",
-                        "key": "internet_interface",
-                        "label": "Internet Interface",
-                        "value": config.internet_interface,
-                        "options": net_opts
-                    }
-                ]
-            },
-            {
-                "title": "Advanced",
-                "items": [
-                    {
-                        "type": "text",
-                        "key": "connection_name",
-                        "label": "Connection Name",
-                        "value": config.connection_name,
-                        "placeholder": "NM connection name"
-                    },
-                    {
-                        "type": "text",
-                        "key": "gateway_ip",
-                        "label": "Gateway IP",
-                        "value": config.gateway_ip,
-                        "placeholder": "192.168.44.1/24"
-                    }
-                ]
-            }
-        ],
+        "sections": sections,
         "actions": [
             {"id": "reset", "label": "Reset to Defaults", "style": "destructive"},
+            {"id": "restore_backup", "label": "Restore Backup", "style": "standard"},
             {"id": "refresh_interfaces", "label": "Refresh Interfaces", "style": "standard"}
         ]
-    });
+    })
+}
 
-    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+fn to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// One hub item per schema property that names a section
+fn add_items(
+    sections: &mut [(&str, Vec<Value>)],
+    schema: &schemars::Schema,
+    values: &Value,
+    sources: &OptionSources,
+) {
+    let Some(properties) = schema.as_value().get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (key, property) in properties {
+        let Some(section) = property.get("x-section").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some((_, items)) = sections.iter_mut().find(|(title, _)| *title == section) else {
+            eprintln!("Setting {key} names unknown section {section}");
+            continue;
+        };
+        items.push(item(key, property, &values[key], sources));
+    }
+}
+
+/// A hub item: selects for fields with options, toggles for booleans,
+/// number inputs for integers and text inputs otherwise
+fn item(key: &str, property: &Value, value: &Value, sources: &OptionSources) -> Value {
+    let label = property.get("title").and_then(Value::as_str).unwrap_or(key);
+    let mut item = json!({"key": key, "label": label, "value": value});
+
+    let source = property.get("x-options-from").and_then(Value::as_str);
+    let options = property
+        .get("x-options")
+        .cloned()
+        .or_else(|| source.map(|s| sources.options(s, value)));
+    match (options, property.get("type").and_then(Value::as_str)) {
+        (Some(options), _) => {
+            item["type"] = json!("select");
+            item["options"] = options;
+        }
+        (None, Some("boolean")) => item["type"] = json!("toggle"),
+        (None, Some("integer" | "number")) => {
+            item["type"] = json!("number");
+            for (bound, name) in [("minimum", "min"), ("maximum", "max")] {
+                if let Some(limit) = property.get(bound) {
+                    item[name] = limit.clone();
+                }
+            }
+        }
+        (None, _) => {
+            item["type"] = json!("text");
+            if let Some(placeholder) = property.get("x-placeholder") {
+                item["placeholder"] = placeholder.clone();
+            }
+        }
+    }
+
+    let description = property
+        .get("x-description")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| {
+            if source == Some("ap_adapters") {
+                sources.ap_hint.clone()
+            } else {
+                None
+            }
+        });
+    if let Some(description) = description {
+        item["description"] = json!(description);
+    }
+    item
+}
+
+/// Option lists for fields with `x-options-from`, read from the backend
+struct OptionSources {
+    ap_adapters: Vec<Value>,
+    /// Why some adapters aren't offered, if any were left out
+    ap_hint: Option<String>,
+    network_interfaces: Vec<Value>,
+    scanned_networks: Vec<Value>,
+}
+
+impl OptionSources {
+    fn gather(config: &Config) -> Self {
+        let interfaces = interface_cache::get();
+        let ap_adapters = interfaces
+            .wifi_adapters
+            .iter()
+            .filter(|a| a.ap_capable)
+            .map(|a| json!({"value": a.name, "label": a.label()}))
+            .collect();
+
+        let network_interfaces = interfaces
+            .network_interfaces
+            .iter()
+            .map(|i| json!({"value": i, "label": i}))
+            .collect();
+
+        // Scanning takes a few seconds, so only do it once repeater mode is on
+        let scanned_networks = if config.repeater_mode {
+            backend::active()
+                .scan_networks(&config.internet_interface)
+                .iter()
+                .map(|n| json!({"value": n.ssid, "label": n.label()}))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            ap_adapters,
+            ap_hint: adapters::exclusion_hint(&interfaces.wifi_adapters),
+            network_interfaces,
+            scanned_networks,
+        }
+    }
+
+    /// The options for `source`, keeping the current value selectable even
+    /// if it isn't in the list (e.g. a network out of range right now)
+    fn options(&self, source: &str, current: &Value) -> Value {
+        let mut options = match source {
+            "ap_adapters" => self.ap_adapters.clone(),
+            "network_interfaces" => self.network_interfaces.clone(),
+            "wired_interfaces" => std::iter::once(json!({"value": "", "label": "None"}))
+                .chain(self.network_interfaces.iter().cloned())
+                .collect(),
+            "scanned_networks" => self.scanned_networks.clone(),
+            other => {
+                eprintln!("Unknown option source {other}");
+                Vec::new()
+            }
+        };
+        if source == "scanned_networks"
+            && current.as_str().is_some_and(|c| !c.is_empty())
+            && !options.iter().any(|o| o["value"] == *current)
+        {
+            options.push(json!({"value": current, "label": current}));
+        }
+        Value::Array(options)
+    }
 }
 
 pub fn set(key: &str, value: &str) {
+    if key.contains('.') || key.contains('[') {
+        set_nested(key, value);
+        return;
+    }
     let mut config = Config::load();
 
     let result: Result<&str, String> = match key {
         "ssid" => parse_string(value).map(|v| { config.ssid = v; "Updated SSID" }),
         "password" => parse_string(value).map(|v| { config.password = v; "Updated password" }),
+        "security" => parse_string(value).and_then(|v| {
+            if hotspot::security_modes(&config.backend).contains(&v.as_str()) {
+                config.security = v;
+                Ok("Updated security")
+            } else if v == "wpa2-wpa3" {
+                Err("WPA2/WPA3 transition mode needs the hostapd backend".to_string())
+            } else {
+                Err("Invalid security: must be 'wpa2', 'wpa3' or 'wpa2-wpa3'".to_string())
+            }
+        }),
         "band" => parse_string(value).and_then(|v| {
             if v == "bg" || v == "a" {
                 config.band = v;
+                if !channels::is_valid(&config.band, config.channel) {
+                    config.channel = 0;
+                }
                 Ok("Updated band")
             } else {
-                Err(format!("Invalid band: must be 'bg' or 'a'"))
+                Err("Invalid band: must be 'bg' or 'a'".to_string())
+            }
+        }),
+        "channel" => parse_u64(value).and_then(|v| {
+            let channel = u32::try_from(v).map_err(|_| format!("Invalid channel {v}"))?;
+            if !channels::is_valid(&config.band, channel) {
+                return Err(format!("Channel {channel} isn't in the selected band"));
             }
+            config.channel = channel;
+            Ok("Updated channel")
         }),
         "hotspot_interface" => parse_string(value).map(|v| { config.hotspot_interface = v; "Updated hotspot interface" }),
         "internet_interface" => parse_string(value).map(|v| { config.internet_interface = v; "Updated internet interface" }),
-        "connection_name" => parse_string(value).map(|v| { config.connection_name = v; "Updated connection name" }),
-        "gateway_ip" => parse_string(value).map(|v| { config.gateway_ip = v; "Updated gateway IP" }),
-        _ => Err(format!("Unknown key: {key}")),
+        "share_wired_interface" => parse_string(value).and_then(|v| {
+            if !v.is_empty() {
+                nat::validate_interface(&v)?;
+            }
+            config.share_wired_interface = v;
+            Ok("Updated wired sharing")
+        }),
+        "repeater_mode" => parse_bool(value).and_then(|v| {
+            if v && config.internet_interface == config.hotspot_interface {
+                return Err("Repeater mode needs an internet interface other than the hotspot interface".to_string());
+            }
+            config.repeater_mode = v;
+            Ok("Updated repeater mode")
+        }),
+        "repeater_ssid" => parse_string(value).map(|v| { config.repeater_ssid = v; "Updated upstream network" }),
+        "repeater_password" => parse_string(value).map(|v| { config.repeater_password = v; "Updated upstream password" }),
+        "autostart_adapter" => parse_string(value).and_then(|v| {
+            if !v.trim().is_empty() && !adapters::is_adapter_id(&v) {
+                return Err(format!("Invalid adapter '{v}': expected a MAC address or USB id like 0e8d:7612"));
+            }
+            config.autostart_adapter = v.trim().to_string();
+            Ok("Updated autostart adapter")
+        }),
+        "active_profile" => parse_string(value).and_then(|v| {
+            config.switch_profile(&v)?;
+            Ok("Switched profile")
+        }),
+        "connection_name" => parse_string(value).and_then(|v| {
+            config.connection_name = config::normalize_connection_name(&v)?;
+            Ok("Updated connection name")
+        }),
+        "gateway_ip" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.gateway_ip = v)?;
+            Ok("Updated gateway IP")
+        }),
+        "dhcp_range_start" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.dhcp_range_start = v)?;
+            Ok("Updated DHCP range")
+        }),
+        "dhcp_range_end" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.dhcp_range_end = v)?;
+            Ok("Updated DHCP range")
+        }),
+        "dns_server" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.dns_server = v)?;
+            Ok("Updated DNS server")
+        }),
+        "dhcp_options" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.dhcp_options = v)?;
+            Ok("Updated DHCP options")
+        }),
+        "nat_strategy" => parse_string(value).and_then(|v| {
+            if ["auto", "nm-shared", "iptables", "nftables", "firewalld", "ufw"].contains(&v.as_str()) {
+                set_profile(&mut config, |p| p.nat_strategy = v)?;
+                Ok("Updated NAT strategy")
+            } else {
+                Err("Invalid NAT strategy: must be 'auto', 'nm-shared', 'iptables', 'nftables', 'firewalld' or 'ufw'".to_string())
+            }
+        }),
+        "client_isolation" => parse_bool(value).and_then(|v| {
+            set_profile(&mut config, |p| p.client_isolation = v)?;
+            Ok("Updated client isolation")
+        }),
+        "autostart" => parse_bool(value).and_then(|v| {
+            set_profile(&mut config, |p| p.autostart = v)?;
+            Ok("Updated plug-in start profile")
+        }),
+        "schedule_days" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.schedule_days = v)?;
+            Ok("Updated scheduled days")
+        }),
+        "schedule_from" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.schedule_from = v)?;
+            Ok("Updated schedule start")
+        }),
+        "schedule_until" => parse_string(value).and_then(|v| {
+            set_profile(&mut config, |p| p.schedule_until = v)?;
+            Ok("Updated schedule end")
+        }),
+        "idle_off_override_mins" => parse_u64(value).and_then(|v| {
+            set_profile(&mut config, |p| p.idle_off_override_mins = v)?;
+            Ok("Updated profile idle auto-off")
+        }),
+        "battery_min_percent" => parse_u64(value).and_then(|v| {
+            let percent = u8::try_from(v).ok().filter(|&p| p <= 100).ok_or(format!("Invalid battery threshold {v}%"))?;
+            set_profile(&mut config, |p| p.battery_min_percent = percent)?;
+            Ok("Updated battery threshold")
+        }),
+        "verify_after_start" => parse_bool(value).map(|v| { config.verify_after_start = v; "Updated connectivity check" }),
+        "check_ssid_collisions" => parse_bool(value).map(|v| { config.check_ssid_collisions = v; "Updated SSID collision check" }),
+        "autoconnect" => parse_bool(value).map(|v| { config.autoconnect = v; "Updated start at boot" }),
+        "dns_query_log" => parse_bool(value).map(|v| { config.dns_query_log = v; "Updated DNS query log" }),
+        "local_domain" => parse_bool(value).map(|v| { config.local_domain = v; "Updated local client names" }),
+        "host_resolver_dns" => parse_bool(value).map(|v| { config.host_resolver_dns = v; "Updated client DNS resolver" }),
+        "repair_sharing" => parse_bool(value).map(|v| { config.repair_sharing = v; "Updated sharing repair" }),
+        "multicast_to_unicast" => parse_bool(value).map(|v| { config.multicast_to_unicast = v; "Updated multicast to unicast" }),
+        "backend" => parse_string(value).and_then(|v| {
+            if v != "networkmanager" && v != "hostapd" {
+                Err("Invalid backend: must be 'networkmanager' or 'hostapd'".to_string())
+            } else if !hotspot::security_modes(&v).contains(&config.security.as_str()) {
+                Err("NetworkManager can't host WPA2/WPA3 transition mode; set security to 'wpa2' or 'wpa3' first"
+                    .to_string())
+            } else {
+                config.backend = v;
+                Ok("Updated hotspot backend")
+            }
+        }),
+        "multicast_rate_mbps" => parse_u64(value).and_then(|v| {
+            let rate = nat::validate_multicast_rate(&v.to_string())?;
+            config.multicast_rate_mbps = rate;
+            Ok("Updated multicast rate")
+        }),
+        "quota_period" => parse_string(value).and_then(|v| {
+            if ["off", "daily", "weekly"].contains(&v.as_str()) {
+                config.quota_period = v;
+                Ok("Updated data quota")
+            } else {
+                Err("Invalid quota period: must be 'off', 'daily' or 'weekly'".to_string())
+            }
+        }),
+        "quota_mb" => parse_u64(value).map(|v| { config.quota_mb = v; "Updated quota size" }),
+        "quota_reset_time" => parse_string(value).and_then(|v| {
+            schedule::parse_time(&v)?;
+            config.quota_reset_time = v;
+            Ok("Updated quota reset time")
+        }),
+        "failed_join_notify_threshold" => parse_u64(value).map(|v| { config.failed_join_notify_threshold = v; "Updated failed join notification" }),
+        "clipboard_sensitive" => parse_bool(value).map(|v| { config.clipboard_sensitive = v; "Updated clipboard history hint" }),
+        "clipboard_clear_secs" => parse_u64(value).map(|v| { config.clipboard_clear_secs = v; "Updated clipboard clearing" }),
+        "idle_off_mins" => parse_u64(value).map(|v| { config.idle_off_mins = v; "Updated idle auto-off" }),
+        "slow_activation_secs" => parse_u64(value).map(|v| { config.slow_activation_secs = v; "Updated slow activation warning" }),
+        "auto_disconnect_client" => parse_bool(value).map(|v| { config.auto_disconnect_client = v; "Updated auto-disconnect" }),
+        "keep_usb_adapter_powered" => parse_bool(value).map(|v| { config.keep_usb_adapter_powered = v; "Updated USB adapter power" }),
+        "dry_run" => parse_bool(value).map(|v| { config.dry_run = v; "Updated dry run mode" }),
+        "client_sweep" => parse_bool(value).map(|v| { config.client_sweep = v; "Updated client sweep" }),
+        "client_sweep_interval_secs" => parse_u64(value).map(|v| { config.client_sweep_interval_secs = v; "Updated sweep interval" }),
+        "client_grace_secs" => parse_u64(value).map(|v| { config.client_grace_secs = v; "Updated client grace period" }),
+        "status_hold_secs" => parse_u64(value).map(|v| { config.status_hold_secs = v.max(1); "Updated result message duration" }),
+        "popup_density" => parse_string(value).and_then(|v| {
+            if v == "comfortable" || v == "compact" {
+                config.popup_density = v;
+                Ok("Updated popup density")
+            } else {
+                Err("Invalid density: must be 'comfortable' or 'compact'".to_string())
+            }
+        }),
+        "icon_style" => parse_string(value).and_then(|v| {
+            if ["ripple", "broadcast", "high_contrast", "filled", "monochrome"].contains(&v.as_str()) {
+                config.icon_style = v;
+                Ok("Updated icon style")
+            } else {
+                Err("Invalid icon style: must be 'ripple', 'broadcast', 'high_contrast', 'filled' or 'monochrome'".to_string())
+            }
+        }),
+        "click_action" => parse_string(value).and_then(|v| {
+            if ["popup", "toggle", "qr"].contains(&v.as_str()) {
+                config.click_action = v;
+                Ok("Updated click action")
+            } else {
+                Err("Invalid click action: must be 'popup', 'toggle' or 'qr'".to_string())
+            }
+        }),
+        "icon_color" => parse_string(value).and_then(|v| {
+            if ["foreground", "accent", "success", "warning"].contains(&v.as_str()) {
+                config.icon_color = v;
+                Ok("Updated icon color")
+            } else {
+                Err("Invalid icon color: must be 'foreground', 'accent', 'success' or 'warning'".to_string())
+            }
+        }),
+        "reduce_motion" => parse_bool(value).map(|v| { config.reduce_motion = v; "Updated reduce motion" }),
+        "popup_show_clients" => parse_bool(value).map(|v| { config.popup_show_clients = v; "Updated popup clients section" }),
+        "popup_show_qr" => parse_bool(value).map(|v| { config.popup_show_qr = v; "Updated popup QR section" }),
+        "popup_show_stats" => parse_bool(value).map(|v| { config.popup_show_stats = v; "Updated popup stats section" }),
+        "popup_show_schedule" => parse_bool(value).map(|v| { config.popup_show_schedule = v; "Updated popup schedule section" }),
+        "toggle_shortcut" => parse_string(value).and_then(|v| {
+            shortcut::apply(&v)?;
+            config.toggle_shortcut = v;
+            Ok("Updated toggle shortcut")
+        }),
+        // Settings without special validation go through the typed generic path
+        _ => {
+            set_nested(key, value);
+            return;
+        }
     };
 
     match result {
-        Ok(msg) => match config.save() {
-            Ok(()) => {
-                // Restart the hotspot if active so changes take effect immediately
-                if hotspot::is_hotspot_active(&config) {
-                    let _ = hotspot::stop_hotspot(&config);
-                    match hotspot::start_hotspot(&config) {
-                        Ok(_) => print_response(true, msg),
-                        Err(e) => print_response(false, &format!("{msg} (restart failed: {e})")),
-                    }
-                } else {
-                    print_response(true, msg);
+        Ok(msg) => save_and_apply(&config, msg),
+        Err(e) => print_hotspot_error(&HotspotError::new(ErrorKind::InvalidSetting, e)),
+    }
+}
+
+/// `--settings-set` with a dotted key such as `profiles.Guest.dns_server`
+fn set_nested(key: &str, value: &str) {
+    let config = Config::load();
+    let result = serde_json::to_value(&config)
+        .map_err(|e| format!("Failed to serialize config: {e}"))
+        .and_then(|mut tree| {
+            settings_path::set(&mut tree, key, value)?;
+            let updated: Config =
+                serde_json::from_value(tree).map_err(|e| format!("Invalid value for '{key}': {e}"))?;
+            for profile in &updated.profiles {
+                profile
+                    .validate()
+                    .map_err(|e| format!("Profile '{}': {e}", profile.name))?;
+            }
+            config::normalize_connection_name(&updated.connection_name)?;
+            // Not serialized; keeps passwords that couldn't be read intact
+            Ok(Config { unreadable_secrets: config.unreadable_secrets.clone(), ..updated })
+        });
+
+    match result {
+        Ok(updated) => save_and_apply(&updated, &format!("Updated {key}")),
+        Err(e) => print_hotspot_error(&HotspotError::new(ErrorKind::InvalidSetting, e)),
+    }
+}
+
+fn save_and_apply(config: &Config, msg: &str) {
+    match config.save() {
+        Ok(()) => {
+            // Restart the hotspot if active so changes take effect immediately
+            if backend::active().is_active(config) {
+                let _ = backend::active().stop(config);
+                match backend::active().start(config) {
+                    Ok(_) => print_response(true, msg),
+                    Err(e) => print_error(&format!("{msg}; {}", e.message()), &e.detail),
                 }
+            } else {
+                print_response(true, msg);
             }
-            Err(e) => print_response(false, &format!("Save failed: {e}")),
-        },
-        Err(e) => print_response(false, &e),
+        }
+        Err(e) => print_hotspot_error(&HotspotError::new(ErrorKind::SaveFailed, e)),
     }
 }
 
@@ -147,12 +584,32 @@ pub fn action(id: &str) {
             print_response(true, "Configuration saved");
         }
         "reset" => {
-            let config = Config::default();
-            match config.save() {
-                Ok(()) => print_response(true, "Reset to defaults"),
-                Err(e) => print_response(false, &format!("Reset failed: {e}")),
+            // Each action runs in its own process, so the first press is
+            // remembered in the run state until the second confirms it
+            let now = run_state::now();
+            let requested = run_state::load().reset_requested_at;
+            if requested.is_none_or(|at| now.saturating_sub(at) > RESET_CONFIRM_SECS) {
+                run_state::record_reset_request(Some(now));
+                let message = format!(
+                    "Press Reset to Defaults again within {RESET_CONFIRM_SECS} seconds to reset every setting and profile"
+                );
+                print_response(false, &message);
+                return;
+            }
+            run_state::record_reset_request(None);
+            match Config::reset_to_defaults() {
+                Ok(Some(backup)) => print_response(
+                    true,
+                    &format!("Reset to defaults; previous settings saved to {} (Restore Backup undoes it)", backup.display()),
+                ),
+                Ok(None) => print_response(true, "Reset to defaults"),
+                Err(e) => print_hotspot_error(&HotspotError::new(ErrorKind::SaveFailed, e)),
             }
         }
+        "restore_backup" => match Config::restore_backup(None) {
+            Ok(backup) => print_response(true, &format!("Restored settings from {}", backup.display())),
+            Err(e) => print_response(false, &e),
+        },
         "refresh_interfaces" => {
             // Just re-describe will show fresh interfaces
             print_response(true, "Interfaces refreshed");
@@ -161,11 +618,47 @@ pub fn action(id: &str) {
     }
 }
 
+/// Edit the active profile, rejecting the change if it leaves the profile invalid.
+///
+/// The hub sets one key at a time, so a DHCP range with only one end filled
+/// in is accepted for now and checked once the other end arrives.
+fn set_profile(config: &mut Config, edit: impl FnOnce(&mut config::Profile)) -> Result<(), String> {
+    let mut profile = config.profile().clone();
+    edit(&mut profile);
+    let mut check = profile.clone();
+    if check.dhcp_range_start.is_empty() != check.dhcp_range_end.is_empty() {
+        check.dhcp_range_start.clear();
+        check.dhcp_range_end.clear();
+    }
+    check.validate()?;
+    *config.profile_mut() = profile;
+    Ok(())
+}
+
 fn parse_string(value: &str) -> Result<String, String> {
     serde_json::from_str::<String>(value).map_err(|e| format!("Invalid string: {e}"))
 }
 
+fn parse_bool(value: &str) -> Result<bool, String> {
+    serde_json::from_str::<bool>(value).map_err(|e| format!("Invalid boolean: {e}"))
+}
+
+fn parse_u64(value: &str) -> Result<u64, String> {
+    serde_json::from_str::<u64>(value).map_err(|e| format!("Invalid number: {e}"))
+}
+
 fn print_response(ok: bool, message: &str) {
     let resp = serde_json::json!({"ok": ok, "message": message});
     println!("{}", resp);
 }
+
+/// Failure response with the technical detail in a separate field, so the hub
+/// shows the translated message and can reveal the detail on request
+fn print_error(message: &str, detail: &str) {
+    let resp = serde_json::json!({"ok": false, "message": message, "detail": detail});
+    println!("{}", resp);
+}
+
+fn print_hotspot_error(error: &HotspotError) {
+    print_error(error.message(), &error.detail);
+}
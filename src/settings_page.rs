@@ -3,70 +3,425 @@
 //! Provides the settings UI as standalone State/Message/init/update/view
 //! functions that can be embedded in cosmic-applet-settings or wrapped
 //! in a standalone Application window.
+//!
+//! A host passes `Options` to `init_with`, maps the `Task` that `update`
+//! returns into its own message type (slow work such as saving or scanning
+//! finishes there), and reacts to the `Output` it reports: a save, a restart
+//! the page left to the host, or an error.
+
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
 
-use cosmic::iced::Length;
+use cosmic::iced::widget::svg;
+use cosmic::iced::{Length, Subscription};
 use cosmic::widget::{self, button, settings, text, text_input};
-use cosmic::Element;
+use cosmic::{Element, Task};
 
-use crate::config::Config;
+use crate::adapters::{self, AdapterDetails, WifiAdapter};
+use crate::backend;
+use crate::channels::{self, ChannelUsage, NearbyAp};
+use crate::config::{self, Config};
+use crate::hotplug;
 use crate::hotspot;
+use crate::interface_cache::{self, Interfaces};
+use crate::password;
+use crate::repeater::ScannedNetwork;
+use crate::saved_networks::SavedNetwork;
+use crate::shortcut;
 
+const SECURITY_OPTIONS: &[&str] = &["wpa2", "wpa3", "wpa2-wpa3"];
+const SECURITY_LABELS: &[&str] =
+    &["WPA2 Personal", "WPA3 Personal (newer devices only)", "WPA2/WPA3 Personal"];
 const BAND_OPTIONS: &[&str] = &["bg", "a"];
 const BAND_LABELS: &[&str] = &["2.4 GHz (bg)", "5 GHz (a)"];
+const NAT_OPTIONS: &[&str] = &["auto", "nm-shared", "iptables", "nftables", "firewalld", "ufw"];
+const NAT_LABELS: &[&str] = &[
+    "Auto-detect",
+    "NetworkManager shared only",
+    "Explicit iptables rules",
+    "Explicit nftables rules",
+    "firewalld zone and policy",
+    "ufw route rules",
+];
+const DENSITY_OPTIONS: &[&str] = &["comfortable", "compact"];
+const DENSITY_LABELS: &[&str] = &["Comfortable", "Compact"];
+const ICON_COLOR_OPTIONS: &[&str] = &["foreground", "accent", "success", "warning"];
+const ICON_COLOR_LABELS: &[&str] = &["Panel foreground", "Accent", "Success", "Warning"];
+const ICON_STYLE_OPTIONS: &[&str] = &["ripple", "broadcast", "high_contrast", "filled", "monochrome"];
+const ICON_STYLE_LABELS: &[&str] = &[
+    "Ripple (animated)",
+    "Broadcast (static)",
+    "High contrast",
+    "Simple filled",
+    "Monochrome (static)",
+];
+const CLICK_ACTION_OPTIONS: &[&str] = &["popup", "toggle", "qr"];
+const CLICK_ACTION_LABELS: &[&str] = &["Opens the popup", "Toggles the hotspot", "Shows the join QR code"];
+const QUOTA_PERIOD_OPTIONS: &[&str] = &["off", "daily", "weekly"];
+const QUOTA_PERIOD_LABELS: &[&str] = &["Off", "Daily", "Weekly"];
+const BACKEND_OPTIONS: &[&str] = &["networkmanager", "hostapd"];
+const BACKEND_LABELS: &[&str] = &["NetworkManager", "hostapd + dnsmasq (needs the helper)"];
+const MULTICAST_RATE_OPTIONS: &[u32] = &[0, 1, 6, 12, 24, 54];
+const MULTICAST_RATE_LABELS: &[&str] =
+    &["Driver default", "1 Mbit/s", "6 Mbit/s", "12 Mbit/s", "24 Mbit/s", "54 Mbit/s"];
+
+/// How a host wants the page to look and behave
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Restart a running hotspot after saving so changes apply at once. Hosts
+    /// that manage the hotspot themselves turn this off and get
+    /// `Output::RequestRestart` instead.
+    pub restart_on_save: bool,
+    /// Show the page's own title; hosts with their own header turn it off
+    pub show_title: bool,
+    /// Width of dropdowns and text inputs
+    pub control_width: f32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            restart_on_save: true,
+            show_title: true,
+            control_width: 250.0,
+        }
+    }
+}
+
+/// What the host should know about after an `update`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// The config was written (and the hotspot restarted, if the page does that)
+    ConfigSaved,
+    /// The config was written while the hotspot runs, or the "Restart now"
+    /// banner was clicked, and `restart_on_save` is off: the host should
+    /// restart the hotspot for the changes to apply
+    RequestRestart,
+    /// Saving failed or the settings were rejected; also in the status line
+    Error(String),
+}
+
+/// Outcome of a save in the background
+#[derive(Debug, Clone)]
+pub struct Saved {
+    status: String,
+    restart_needed: bool,
+}
+
+/// A slow operation running in the background
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pending {
+    /// Saving, and restarting the hotspot if it's running
+    Saving,
+    ScanningNetworks,
+    ScanningChannels,
+    /// Listing saved networks, or reading the password of the one picked
+    ReadingSavedNetworks,
+    /// Restarting the hotspot from the banner
+    Restarting,
+}
 
 pub struct State {
     pub config: Config,
+    /// The config as last loaded or saved, to tell whether there are changes
+    saved_config: Config,
+    pub options: Options,
+    /// The running hotspot was started with older settings than the saved
+    /// ones; the page shows a banner offering to restart it
+    pub running_outdated: bool,
+    /// Reported by the next `update`
+    output: Option<Output>,
     pub status_message: String,
     pub selected_band_idx: usize,
+    /// AP-capable adapters only; indices match `wifi_labels`
     pub wifi_interfaces: Vec<String>,
+    pub wifi_labels: Vec<String>,
+    /// Why some adapters aren't offered, if any were left out
+    pub wifi_hint: Option<String>,
+    /// The "Adapter details" panel under the hotspot interface is open
+    pub show_adapter_details: bool,
+    /// Details of the adapter they were probed for
+    pub adapter_details: Option<(String, AdapterDetails)>,
     pub network_interfaces: Vec<String>,
     pub selected_hotspot_idx: Option<usize>,
     pub selected_internet_idx: Option<usize>,
+    /// "None" followed by `network_interfaces`, for the wired sharing dropdown
+    pub wired_labels: Vec<String>,
+    /// Every WiFi adapter, AP-capable or not, for the repeater's client side
+    pub client_adapters: Vec<String>,
+    /// Result of the last repeater scan; indices match `network_labels`
+    pub scanned_networks: Vec<ScannedNetwork>,
+    pub network_labels: Vec<String>,
+    /// Saved networks to copy the SSID and password from, while that picker
+    /// is open; indices match `saved_network_labels`
+    pub saved_networks: Option<Vec<SavedNetwork>>,
+    pub saved_network_labels: Vec<String>,
+    /// "Automatic" followed by the channels of the selected band
+    pub channel_labels: Vec<String>,
+    /// Per-channel congestion from the last scan
+    pub channel_usage: Vec<ChannelUsage>,
+    /// Profile names, for the profile switcher
+    pub profile_labels: Vec<String>,
+    /// Name typed for a new or renamed profile
+    pub profile_name: String,
+    /// The operation in progress, if any; its button is disabled meanwhile
+    pub pending: Option<Pending>,
+    /// "Reset to Defaults" was pressed; the reset waits for confirmation
+    pub confirm_reset: bool,
+    /// Interface listing in progress; the dropdowns show a loading state
+    pub interfaces_loading: bool,
+    /// Report what was found once the listing arrives (manual refresh)
+    announce_interfaces: bool,
+    /// Interface add/remove events, checked by `subscription`
+    hotplug: Receiver<()>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     SsidChanged(String),
     PasswordChanged(String),
+    GeneratePassword,
+    CopySavedNetwork,
+    SavedNetworksLoaded(Vec<SavedNetwork>),
+    SavedNetworkSelected(usize),
+    SavedPasswordLoaded(String, Result<String, String>),
+    CloseSavedNetworks,
     HotspotInterfaceSelected(usize),
+    ToggleAdapterDetails,
+    AdapterDetailsLoaded(String, AdapterDetails),
     InternetInterfaceSelected(usize),
+    WiredShareSelected(usize),
+    AutostartAdapterChanged(String),
+    ProfileSelected(usize),
+    ProfileNameChanged(String),
+    AddProfile,
+    RenameProfile,
+    DeleteProfile,
+    RepeaterModeToggled(bool),
+    RepeaterAdapterSelected(usize),
+    RepeaterNetworkSelected(usize),
+    RepeaterPasswordChanged(String),
+    ScanNetworks,
     ConnectionNameChanged(String),
     GatewayIpChanged(String),
+    DhcpRangeStartChanged(String),
+    DhcpRangeEndChanged(String),
+    DnsServerChanged(String),
+    DhcpOptionsChanged(String),
+    NatStrategySelected(usize),
+    ClientIsolationToggled(bool),
+    ProfileAutostartToggled(bool),
+    ScheduleDaysChanged(String),
+    ScheduleFromChanged(String),
+    ScheduleUntilChanged(String),
+    ProfileIdleOffChanged(String),
+    BatteryMinPercentChanged(String),
+    VerifyAfterStartToggled(bool),
+    CheckSsidCollisionsToggled(bool),
+    FailedJoinThresholdChanged(String),
+    SlowActivationSecsChanged(String),
+    DnsQueryLogToggled(bool),
+    LocalDomainToggled(bool),
+    HostResolverDnsToggled(bool),
+    RepairSharingToggled(bool),
+    MulticastToUnicastToggled(bool),
+    MulticastRateSelected(usize),
+    BackendSelected(usize),
+    QuotaPeriodSelected(usize),
+    QuotaMbChanged(String),
+    QuotaResetTimeChanged(String),
+    IdleOffMinsChanged(String),
+    ClipboardSensitiveToggled(bool),
+    ClipboardClearSecsChanged(String),
+    AutoconnectToggled(bool),
+    DryRunToggled(bool),
+    AutoDisconnectToggled(bool),
+    KeepUsbPoweredToggled(bool),
+    ClientSweepToggled(bool),
+    ClientSweepIntervalChanged(String),
+    ClientGraceChanged(String),
+    ToggleShortcutChanged(String),
+    PopupShowClientsToggled(bool),
+    PopupShowQrToggled(bool),
+    PopupShowStatsToggled(bool),
+    PopupShowScheduleToggled(bool),
+    PopupDensitySelected(usize),
+    IconColorSelected(usize),
+    IconStyleSelected(usize),
+    ClickActionSelected(usize),
+    StatusHoldChanged(String),
+    ReduceMotionToggled(bool),
+    SecuritySelected(usize),
     BandSelected(usize),
+    ChannelSelected(usize),
+    ScanChannels,
     Save,
+    /// Ask to confirm a reset
     ResetDefaults,
+    /// Back up the saved config and reset to defaults
+    ConfirmReset,
+    CancelReset,
     RefreshInterfaces,
+    CheckHotplug,
+    InterfacesLoaded(Interfaces),
+    NetworksScanned(Vec<ScannedNetwork>),
+    ChannelsScanned(Vec<NearbyAp>),
+    /// A save finished: the status line, or the error
+    Saved(Result<Saved, String>),
+    /// Whether the running hotspot uses older settings than the saved ones
+    RunningChecked(bool),
+    RestartHotspot,
+    /// The banner's restart finished
+    Restarted(Result<String, String>),
+}
+
+/// The page state, and the task that fills in the interface lists
+pub fn init() -> (State, Task<Message>) {
+    init_with(Options::default())
 }
 
-pub fn init() -> State {
+/// `init` for a host with its own `options`
+pub fn init_with(options: Options) -> (State, Task<Message>) {
     let config = Config::load();
     let selected_band_idx = BAND_OPTIONS
         .iter()
         .position(|&b| b == config.band)
         .unwrap_or(0);
 
-    let wifi_interfaces = hotspot::list_wifi_interfaces();
-    let network_interfaces = hotspot::list_network_interfaces();
-
-    let selected_hotspot_idx = wifi_interfaces
-        .iter()
-        .position(|i| *i == config.hotspot_interface);
-    let selected_internet_idx = network_interfaces
-        .iter()
-        .position(|i| *i == config.internet_interface);
+    let profile_labels = config.profiles.iter().map(|p| p.name.clone()).collect();
+    let channel_labels = channel_labels(&config.band);
 
-    State {
+    let mut state = State {
+        saved_config: config.clone(),
         config,
+        options,
+        running_outdated: false,
+        output: None,
         status_message: String::new(),
         selected_band_idx,
-        wifi_interfaces,
-        network_interfaces,
-        selected_hotspot_idx,
-        selected_internet_idx,
-    }
+        wifi_interfaces: Vec::new(),
+        wifi_labels: Vec::new(),
+        wifi_hint: None,
+        show_adapter_details: false,
+        adapter_details: None,
+        network_interfaces: Vec::new(),
+        selected_hotspot_idx: None,
+        selected_internet_idx: None,
+        wired_labels: wired_labels(&[]),
+        client_adapters: Vec::new(),
+        scanned_networks: Vec::new(),
+        network_labels: Vec::new(),
+        saved_networks: None,
+        saved_network_labels: Vec::new(),
+        channel_labels,
+        channel_usage: Vec::new(),
+        profile_labels,
+        profile_name: String::new(),
+        pending: None,
+        confirm_reset: false,
+        interfaces_loading: false,
+        announce_interfaces: false,
+        hotplug: hotplug::subscribe(),
+    };
+    // A recent listing (e.g. from the hub's describe call) shows straight
+    // away; otherwise the page opens with the dropdowns loading
+    let task = match interface_cache::cached() {
+        Some(interfaces) => {
+            apply_interfaces(&mut state, interfaces);
+            Task::none()
+        }
+        None => load_interfaces(&mut state, false),
+    };
+    let check = check_running(&state);
+    (state, Task::batch([task, check]))
 }
 
-pub fn update(state: &mut State, message: Message) {
+/// Picks up hotplug events so the device lists follow adapters being
+/// plugged in or removed; hosts embedding the page should include it
+pub fn subscription() -> Subscription<Message> {
+    cosmic::iced::time::every(Duration::from_secs(1)).map(|_| Message::CheckHotplug)
+}
+
+/// Run blocking `work` (NetworkManager, the keyring, the helper) on its own thread
+/// and deliver the result as a message, so the page never freezes
+fn background<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+    done: impl FnOnce(T) -> Message + Send + 'static,
+) -> Task<Message> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    Task::perform(rx, move |result| match result {
+        Ok(value) => done(value),
+        Err(_) => Message::Saved(Err("background task stopped unexpectedly".to_string())),
+    })
+}
+
+fn channel_labels(band: &str) -> Vec<String> {
+    std::iter::once("Automatic".to_string())
+        .chain(channels::for_band(band).iter().map(u32::to_string))
+        .collect()
+}
+
+/// Bring the dropdowns in line after the profile list or the active
+/// profile's network settings changed
+fn profiles_changed(state: &mut State) {
+    state.channel_labels = channel_labels(&state.config.band);
+    state.profile_labels = state.config.profiles.iter().map(|p| p.name.clone()).collect();
+    state.selected_band_idx = BAND_OPTIONS
+        .iter()
+        .position(|&b| b == state.config.band)
+        .unwrap_or(0);
+    state.selected_hotspot_idx = state.wifi_interfaces
+        .iter()
+        .position(|i| *i == state.config.hotspot_interface);
+    state.selected_internet_idx = state.network_interfaces
+        .iter()
+        .position(|i| *i == state.config.internet_interface);
+}
+
+fn wired_labels(network_interfaces: &[String]) -> Vec<String> {
+    std::iter::once("None".to_string())
+        .chain(network_interfaces.iter().cloned())
+        .collect()
+}
+
+/// AP-capable adapter names and dropdown labels, plus the exclusion hint
+fn split_adapters(adapters: Vec<WifiAdapter>) -> (Vec<String>, Vec<String>, Option<String>) {
+    let hint = adapters::exclusion_hint(&adapters);
+    let (names, labels) = adapters
+        .into_iter()
+        .filter(|a| a.ap_capable)
+        .map(|a| {
+            let label = a.label();
+            (a.name, label)
+        })
+        .unzip();
+    (names, labels, hint)
+}
+
+/// Handle `message`; slow work comes back later as a follow-up message
+/// through the returned task. Anything the host should act on is returned
+/// alongside it.
+pub fn update(state: &mut State, message: Message) -> (Task<Message>, Option<Output>) {
+    let task = handle(state, message);
+    (task, state.output.take())
+}
+
+/// Whether the page has edits that weren't saved yet
+pub fn has_unsaved_changes(state: &State) -> bool {
+    state.config != state.saved_config
+}
+
+/// Show a rejected save in the status line and report it to the host
+fn reject(state: &mut State, error: String) -> Task<Message> {
+    state.status_message = format!("Error: {error}");
+    state.output = Some(Output::Error(error));
+    Task::none()
+}
+
+fn handle(state: &mut State, message: Message) -> Task<Message> {
     match message {
         Message::SsidChanged(val) => {
             state.config.ssid = val;
@@ -76,13 +431,29 @@ pub fn update(state: &mut State, message: Message) {
             state.config.password = val;
             state.status_message = "Unsaved changes".to_string();
         }
+        Message::GeneratePassword => {
+            state.config.password = password::generate();
+            state.status_message = "New password generated (unsaved)".to_string();
+        }
         Message::HotspotInterfaceSelected(idx) => {
             if idx < state.wifi_interfaces.len() {
                 state.selected_hotspot_idx = Some(idx);
                 state.config.hotspot_interface = state.wifi_interfaces[idx].clone();
                 state.status_message = "Unsaved changes".to_string();
+                if state.show_adapter_details {
+                    return load_adapter_details(state);
+                }
             }
         }
+        Message::ToggleAdapterDetails => {
+            state.show_adapter_details = !state.show_adapter_details;
+            if state.show_adapter_details {
+                return load_adapter_details(state);
+            }
+        }
+        Message::AdapterDetailsLoaded(device, details) => {
+            state.adapter_details = Some((device, details));
+        }
         Message::InternetInterfaceSelected(idx) => {
             if idx < state.network_interfaces.len() {
                 state.selected_internet_idx = Some(idx);
@@ -90,87 +461,717 @@ pub fn update(state: &mut State, message: Message) {
                 state.status_message = "Unsaved changes".to_string();
             }
         }
+        Message::WiredShareSelected(idx) => {
+            // Index 0 is "None"
+            state.config.share_wired_interface = match idx {
+                0 => String::new(),
+                i => state.network_interfaces.get(i - 1).cloned().unwrap_or_default(),
+            };
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::AutostartAdapterChanged(val) => {
+            state.config.autostart_adapter = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ProfileSelected(idx) => {
+            let Some(name) = state.config.profiles.get(idx).map(|p| p.name.clone()) else {
+                return Task::none();
+            };
+            if let Err(e) = state.config.switch_profile(&name) {
+                return reject(state, e);
+            }
+            profiles_changed(state);
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ProfileNameChanged(val) => state.profile_name = val,
+        Message::AddProfile => {
+            let name = state.profile_name.trim().to_string();
+            if let Err(e) = state.config.add_profile(&name).and_then(|()| state.config.switch_profile(&name)) {
+                return reject(state, e);
+            }
+            state.profile_name.clear();
+            profiles_changed(state);
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::RenameProfile => {
+            let old = state.config.profile().name.clone();
+            if let Err(e) = state.config.rename_profile(&old, &state.profile_name) {
+                return reject(state, e);
+            }
+            state.profile_name.clear();
+            profiles_changed(state);
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DeleteProfile => {
+            let name = state.config.profile().name.clone();
+            if let Err(e) = state.config.delete_profile(&name) {
+                return reject(state, e);
+            }
+            profiles_changed(state);
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::RepeaterModeToggled(val) => {
+            state.config.repeater_mode = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::RepeaterAdapterSelected(idx) => {
+            if let Some(name) = state.client_adapters.get(idx) {
+                state.config.internet_interface = name.clone();
+                state.selected_internet_idx =
+                    state.network_interfaces.iter().position(|i| i == name);
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::RepeaterNetworkSelected(idx) => {
+            if let Some(network) = state.scanned_networks.get(idx) {
+                state.config.repeater_ssid = network.ssid.clone();
+                if !network.secured {
+                    state.config.repeater_password.clear();
+                }
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::RepeaterPasswordChanged(val) => {
+            state.config.repeater_password = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::CopySavedNetwork => {
+            state.pending = Some(Pending::ReadingSavedNetworks);
+            state.status_message = "Reading saved networks...".to_string();
+            return background(|| backend::active().saved_networks(), Message::SavedNetworksLoaded);
+        }
+        Message::SavedNetworksLoaded(networks) => {
+            state.pending = None;
+            if networks.is_empty() {
+                state.status_message = "No saved WPA-Personal networks found".to_string();
+                return Task::none();
+            }
+            state.saved_network_labels = networks.iter().map(|n| n.label()).collect();
+            state.saved_networks = Some(networks);
+            state.status_message = "Pick a network to copy its name and password".to_string();
+        }
+        Message::SavedNetworkSelected(idx) => {
+            let Some(network) = state.saved_networks.as_ref().and_then(|n| n.get(idx)).cloned() else {
+                return Task::none();
+            };
+            state.pending = Some(Pending::ReadingSavedNetworks);
+            state.status_message = format!("Reading the password of '{}'...", network.name);
+            return background(
+                move || {
+                    let password = backend::active().saved_network_password(&network);
+                    (network.ssid, password)
+                },
+                |(ssid, password)| Message::SavedPasswordLoaded(ssid, password),
+            );
+        }
+        Message::SavedPasswordLoaded(ssid, result) => {
+            state.pending = None;
+            match result {
+                Ok(password) if password.len() < 8 => {
+                    state.status_message =
+                        format!("The password of '{ssid}' is too short for a WPA2 hotspot (8 characters minimum)");
+                }
+                Ok(password) => {
+                    state.config.ssid = ssid;
+                    state.config.password = password;
+                    // Sharing the name is the point, so don't warn about it at start
+                    state.config.check_ssid_collisions = false;
+                    state.saved_networks = None;
+                    state.status_message = format!(
+                        "Copied the name and password of '{}' (same-name warning turned off). Unsaved changes",
+                        state.config.ssid
+                    );
+                }
+                Err(e) => state.status_message = e,
+            }
+        }
+        Message::CloseSavedNetworks => {
+            state.saved_networks = None;
+            state.status_message = String::new();
+        }
+        Message::ScanNetworks => {
+            let interface = state.config.internet_interface.clone();
+            state.pending = Some(Pending::ScanningNetworks);
+            state.status_message = format!("Scanning for networks on {interface}...");
+            return background(
+                move || backend::active().scan_networks(&interface),
+                Message::NetworksScanned,
+            );
+        }
+        Message::NetworksScanned(networks) => {
+            state.pending = None;
+            state.scanned_networks = networks;
+            state.network_labels = state.scanned_networks.iter().map(|n| n.label()).collect();
+            state.status_message = format!(
+                "Found {} networks on {}",
+                state.scanned_networks.len(),
+                state.config.internet_interface
+            );
+        }
         Message::ConnectionNameChanged(val) => {
             state.config.connection_name = val;
             state.status_message = "Unsaved changes".to_string();
         }
         Message::GatewayIpChanged(val) => {
-            state.config.gateway_ip = val;
+            state.config.profile_mut().gateway_ip = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DhcpRangeStartChanged(val) => {
+            state.config.profile_mut().dhcp_range_start = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DhcpRangeEndChanged(val) => {
+            state.config.profile_mut().dhcp_range_end = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DnsServerChanged(val) => {
+            state.config.profile_mut().dns_server = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DhcpOptionsChanged(val) => {
+            state.config.profile_mut().dhcp_options = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::NatStrategySelected(idx) => {
+            if idx < NAT_OPTIONS.len() {
+                state.config.profile_mut().nat_strategy = NAT_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::ClientIsolationToggled(val) => {
+            state.config.profile_mut().client_isolation = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ProfileAutostartToggled(val) => {
+            state.config.profile_mut().autostart = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ScheduleDaysChanged(val) => {
+            state.config.profile_mut().schedule_days = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ScheduleFromChanged(val) => {
+            state.config.profile_mut().schedule_from = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ScheduleUntilChanged(val) => {
+            state.config.profile_mut().schedule_until = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ProfileIdleOffChanged(val) => {
+            if let Ok(mins) = val.trim().parse::<u64>() {
+                state.config.profile_mut().idle_off_override_mins = mins;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::BatteryMinPercentChanged(val) => {
+            if let Ok(percent) = val.trim().parse::<u8>() {
+                if percent <= 100 {
+                    state.config.profile_mut().battery_min_percent = percent;
+                    state.status_message = "Unsaved changes".to_string();
+                }
+            }
+        }
+        Message::VerifyAfterStartToggled(val) => {
+            state.config.verify_after_start = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::CheckSsidCollisionsToggled(val) => {
+            state.config.check_ssid_collisions = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::FailedJoinThresholdChanged(val) => {
+            if let Ok(count) = val.trim().parse::<u64>() {
+                state.config.failed_join_notify_threshold = count;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::SlowActivationSecsChanged(val) => {
+            if let Ok(secs) = val.trim().parse::<u64>() {
+                state.config.slow_activation_secs = secs;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::RepairSharingToggled(val) => {
+            state.config.repair_sharing = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::MulticastToUnicastToggled(val) => {
+            state.config.multicast_to_unicast = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::MulticastRateSelected(idx) => {
+            if idx < MULTICAST_RATE_OPTIONS.len() {
+                state.config.multicast_rate_mbps = MULTICAST_RATE_OPTIONS[idx];
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::BackendSelected(idx) => {
+            if idx < BACKEND_OPTIONS.len() {
+                state.config.backend = BACKEND_OPTIONS[idx].to_string();
+                if !hotspot::security_modes(&state.config.backend).contains(&state.config.security.as_str()) {
+                    state.config.security = "wpa2".to_string();
+                }
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::QuotaPeriodSelected(idx) => {
+            if idx < QUOTA_PERIOD_OPTIONS.len() {
+                state.config.quota_period = QUOTA_PERIOD_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::QuotaMbChanged(val) => {
+            if let Ok(mb) = val.trim().parse::<u64>() {
+                state.config.quota_mb = mb;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::ClipboardSensitiveToggled(val) => {
+            state.config.clipboard_sensitive = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ClipboardClearSecsChanged(val) => {
+            if let Ok(secs) = val.trim().parse::<u64>() {
+                state.config.clipboard_clear_secs = secs;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::IdleOffMinsChanged(val) => {
+            if let Ok(mins) = val.trim().parse::<u64>() {
+                state.config.idle_off_mins = mins;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::QuotaResetTimeChanged(val) => {
+            state.config.quota_reset_time = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::LocalDomainToggled(val) => {
+            state.config.local_domain = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::HostResolverDnsToggled(val) => {
+            state.config.host_resolver_dns = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DnsQueryLogToggled(val) => {
+            state.config.dns_query_log = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::AutoconnectToggled(val) => {
+            state.config.autoconnect = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::AutoDisconnectToggled(val) => {
+            state.config.auto_disconnect_client = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::KeepUsbPoweredToggled(val) => {
+            state.config.keep_usb_adapter_powered = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::DryRunToggled(val) => {
+            state.config.dry_run = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ClientSweepToggled(val) => {
+            state.config.client_sweep = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ClientSweepIntervalChanged(val) => {
+            if let Ok(secs) = val.trim().parse::<u64>() {
+                state.config.client_sweep_interval_secs = secs;
+                state.status_message = "Unsaved changes".to_string();
+            } else if val.trim().is_empty() {
+                state.config.client_sweep_interval_secs = 0;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::ClientGraceChanged(val) => {
+            if let Ok(secs) = val.trim().parse::<u64>() {
+                state.config.client_grace_secs = secs;
+                state.status_message = "Unsaved changes".to_string();
+            } else if val.trim().is_empty() {
+                state.config.client_grace_secs = 0;
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::ToggleShortcutChanged(val) => {
+            state.config.toggle_shortcut = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::PopupShowClientsToggled(val) => {
+            state.config.popup_show_clients = val;
             state.status_message = "Unsaved changes".to_string();
         }
+        Message::PopupShowQrToggled(val) => {
+            state.config.popup_show_qr = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::PopupShowStatsToggled(val) => {
+            state.config.popup_show_stats = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::PopupShowScheduleToggled(val) => {
+            state.config.popup_show_schedule = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::PopupDensitySelected(idx) => {
+            if idx < DENSITY_OPTIONS.len() {
+                state.config.popup_density = DENSITY_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::IconColorSelected(idx) => {
+            if idx < ICON_COLOR_OPTIONS.len() {
+                state.config.icon_color = ICON_COLOR_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::IconStyleSelected(idx) => {
+            if idx < ICON_STYLE_OPTIONS.len() {
+                state.config.icon_style = ICON_STYLE_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::ClickActionSelected(idx) => {
+            if idx < CLICK_ACTION_OPTIONS.len() {
+                state.config.click_action = CLICK_ACTION_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::StatusHoldChanged(val) => {
+            if let Ok(secs) = val.trim().parse::<u64>() {
+                state.config.status_hold_secs = secs.max(1);
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
+        Message::ReduceMotionToggled(val) => {
+            state.config.reduce_motion = val;
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::SecuritySelected(idx) => {
+            if idx < hotspot::security_modes(&state.config.backend).len() {
+                state.config.security = SECURITY_OPTIONS[idx].to_string();
+                state.status_message = "Unsaved changes".to_string();
+            }
+        }
         Message::BandSelected(idx) => {
             if idx < BAND_OPTIONS.len() {
                 state.selected_band_idx = idx;
                 state.config.band = BAND_OPTIONS[idx].to_string();
+                if !channels::is_valid(&state.config.band, state.config.channel) {
+                    state.config.channel = 0;
+                }
+                state.channel_labels = channel_labels(&state.config.band);
+                state.channel_usage.clear();
                 state.status_message = "Unsaved changes".to_string();
             }
         }
+        Message::ChannelSelected(idx) => {
+            // Index 0 is "Automatic"
+            state.config.channel = match idx {
+                0 => 0,
+                i => channels::for_band(&state.config.band).get(i - 1).copied().unwrap_or(0),
+            };
+            state.status_message = "Unsaved changes".to_string();
+        }
+        Message::ScanChannels => {
+            let interface = state.config.hotspot_interface.clone();
+            state.pending = Some(Pending::ScanningChannels);
+            state.status_message = format!("Scanning channels from {interface}...");
+            return background(
+                move || backend::active().scan_access_points(&interface),
+                Message::ChannelsScanned,
+            );
+        }
+        Message::ChannelsScanned(aps) => {
+            state.pending = None;
+            state.channel_usage = channels::usage(&state.config.band, &aps);
+            state.status_message = format!(
+                "Found {} access points on {}",
+                aps.len(),
+                state.config.hotspot_interface
+            );
+        }
         Message::Save => {
-            match state.config.save() {
-                Ok(()) => {
-                    // Restart the hotspot if it's currently active so that
-                    // new settings (SSID, password, etc.) take effect immediately.
-                    if hotspot::is_hotspot_active(&state.config) {
-                        let _ = hotspot::stop_hotspot(&state.config);
-                        match hotspot::start_hotspot(&state.config) {
-                            Ok(msg) => state.status_message = format!("Saved & applied: {msg}"),
-                            Err(e) => state.status_message = format!("Saved but restart failed: {e}"),
-                        }
+            match config::normalize_connection_name(&state.config.connection_name) {
+                Ok(name) => state.config.connection_name = name,
+                Err(e) => return reject(state, e),
+            }
+            if let Err(e) = state.config.profile().validate() {
+                return reject(state, e);
+            }
+            if state.config.repeater_mode
+                && state.config.internet_interface == state.config.hotspot_interface
+            {
+                return reject(state, "repeater mode needs different client and hotspot adapters".to_string());
+            }
+            state.config.autostart_adapter = state.config.autostart_adapter.trim().to_string();
+            if !state.config.autostart_adapter.is_empty()
+                && !adapters::is_adapter_id(&state.config.autostart_adapter)
+            {
+                return reject(state, "the plug-in adapter must be a MAC address or USB id like 0e8d:7612".to_string());
+            }
+            if state.config.toggle_shortcut != state.saved_config.toggle_shortcut {
+                if let Err(e) = shortcut::apply(&state.config.toggle_shortcut) {
+                    return reject(state, e);
+                }
+            }
+            let config = state.config.clone();
+            let restart = state.options.restart_on_save;
+            state.pending = Some(Pending::Saving);
+            state.status_message = "Saving...".to_string();
+            return background(move || save_and_apply(&config, restart), Message::Saved);
+        }
+        Message::Saved(result) => {
+            state.pending = None;
+            match result {
+                Ok(saved) => {
+                    state.saved_config = state.config.clone();
+                    state.status_message = saved.status;
+                    state.output = Some(if saved.restart_needed {
+                        Output::RequestRestart
                     } else {
-                        state.status_message = "Settings saved".to_string();
-                    }
+                        Output::ConfigSaved
+                    });
+                    return check_running(state);
                 }
-                Err(e) => state.status_message = format!("Error: {e}"),
+                Err(e) => return reject(state, e),
             }
         }
-        Message::ResetDefaults => {
-            state.config = Config::default();
-            state.selected_band_idx = 0;
-            state.selected_hotspot_idx = state.wifi_interfaces
-                .iter()
-                .position(|i| *i == state.config.hotspot_interface);
-            state.selected_internet_idx = state.network_interfaces
-                .iter()
-                .position(|i| *i == state.config.internet_interface);
-            match state.config.save() {
-                Ok(()) => state.status_message = "Reset to defaults and saved".to_string(),
-                Err(e) => state.status_message = format!("Error: {e}"),
+        Message::RunningChecked(outdated) => state.running_outdated = outdated,
+        Message::RestartHotspot => {
+            if !state.options.restart_on_save {
+                // The host manages the hotspot
+                state.running_outdated = false;
+                state.output = Some(Output::RequestRestart);
+                return Task::none();
             }
+            let config = state.saved_config.clone();
+            state.pending = Some(Pending::Restarting);
+            state.status_message = "Restarting the hotspot...".to_string();
+            return background(
+                move || backend::restart(&config).map_err(|e| e.message().to_string()),
+                Message::Restarted,
+            );
         }
-        Message::RefreshInterfaces => {
-            state.wifi_interfaces = hotspot::list_wifi_interfaces();
-            state.network_interfaces = hotspot::list_network_interfaces();
-            state.selected_hotspot_idx = state.wifi_interfaces
-                .iter()
-                .position(|i| *i == state.config.hotspot_interface);
-            state.selected_internet_idx = state.network_interfaces
-                .iter()
-                .position(|i| *i == state.config.internet_interface);
-            state.status_message = format!(
-                "Found {} WiFi, {} network interfaces",
-                state.wifi_interfaces.len(),
-                state.network_interfaces.len()
+        Message::Restarted(result) => {
+            state.pending = None;
+            match result {
+                Ok(msg) => {
+                    state.running_outdated = false;
+                    state.status_message = msg;
+                }
+                Err(e) => return reject(state, format!("restart failed: {e}")),
+            }
+        }
+        Message::ResetDefaults => state.confirm_reset = true,
+        Message::CancelReset => state.confirm_reset = false,
+        Message::ConfirmReset => {
+            state.confirm_reset = false;
+            state.config = Config::default();
+            profiles_changed(state);
+            state.pending = Some(Pending::Saving);
+            state.status_message = "Saving...".to_string();
+            return background(
+                move || {
+                    let status = match Config::reset_to_defaults()? {
+                        Some(backup) => format!(
+                            "Reset to defaults; previous settings saved to {} (undo with cosmic-hotspot --restore-backup)",
+                            backup.display()
+                        ),
+                        None => "Reset to defaults and saved".to_string(),
+                    };
+                    Ok(Saved { status, restart_needed: false })
+                },
+                Message::Saved,
             );
         }
+        Message::RefreshInterfaces => {
+            state.announce_interfaces = true;
+            state.status_message = "Looking for devices...".to_string();
+            return load_interfaces(state, true);
+        }
+        Message::CheckHotplug => {
+            if state.hotplug.try_iter().count() > 0 {
+                interface_cache::invalidate();
+                return load_interfaces(state, true);
+            }
+        }
+        Message::InterfacesLoaded(interfaces) => {
+            state.interfaces_loading = false;
+            apply_interfaces(state, interfaces);
+            if std::mem::take(&mut state.announce_interfaces) {
+                state.status_message = format!(
+                    "Found {} WiFi, {} network interfaces",
+                    state.wifi_interfaces.len(),
+                    state.network_interfaces.len()
+                );
+            }
+        }
     }
+    Task::none()
+}
+
+/// Save `config`, restarting the hotspot if it's running (and `restart` is
+/// set) so that new settings (SSID, password, etc.) take effect immediately
+fn save_and_apply(config: &Config, restart: bool) -> Result<Saved, String> {
+    config.save()?;
+    let saved = |status: String, restart_needed| Ok(Saved { status, restart_needed });
+    if !backend::active().is_active(config) {
+        return saved("Settings saved".to_string(), false);
+    }
+    if !restart {
+        return saved("Settings saved; they apply when the hotspot restarts".to_string(), true);
+    }
+    let _ = backend::active().stop(config);
+    match backend::active().start(config) {
+        Ok(msg) => saved(format!("Saved & applied: {msg}"), false),
+        Err(e) => {
+            eprintln!("Restart after save failed: {e}");
+            saved(format!("Saved but restart failed: {}", e.message()), false)
+        }
+    }
+}
+
+/// Find out in the background whether the running hotspot uses older
+/// settings than the saved ones
+fn check_running(state: &State) -> Task<Message> {
+    let config = state.saved_config.clone();
+    background(
+        move || backend::active().is_active(&config) && backend::active().uses_old_settings(&config),
+        Message::RunningChecked,
+    )
+}
+
+/// Probe the selected hotspot adapter in the background (`iw` can be slow)
+fn load_adapter_details(state: &State) -> Task<Message> {
+    let device = state.config.hotspot_interface.clone();
+    if device.is_empty() || state.adapter_details.as_ref().is_some_and(|(d, _)| *d == device) {
+        return Task::none();
+    }
+    background(
+        move || {
+            let details = backend::active().adapter_details(&device);
+            (device, details)
+        },
+        |(device, details)| Message::AdapterDetailsLoaded(device, details),
+    )
+}
+
+/// Captions for the "Adapter details" panel
+fn adapter_details_lines(details: &AdapterDetails) -> Vec<String> {
+    let mut lines = vec![format!("Driver: {}", details.driver.as_deref().unwrap_or("unknown"))];
+    if details.bands.is_empty() {
+        lines.push("Bands: unknown (iw not installed?)".to_string());
+    }
+    for band in &details.bands {
+        let mut line = if band.ap_channels.is_empty() {
+            format!("{}: no channels allowed for an access point", band.name)
+        } else {
+            format!("{}: AP on channels {}", band.name, adapters::format_channels(&band.ap_channels))
+        };
+        if !band.client_only_channels.is_empty() {
+            line.push_str(&format!(
+                " (client only: {})",
+                adapters::format_channels(&band.client_only_channels)
+            ));
+        }
+        lines.push(line);
+    }
+    lines.push(match details.max_ap_clients {
+        Some(max) => format!("Max clients in AP mode: {max}"),
+        None => "Max clients in AP mode: not reported by the driver".to_string(),
+    });
+    lines.push(format!(
+        "AP and client at the same time: {}",
+        if details.concurrent_ap_sta { "yes" } else { "no" }
+    ));
+    lines.push(format!("Radio: {}", details.rfkill.label()));
+    lines
+}
+
+/// List the interfaces in the background; `fresh` skips the cache
+fn load_interfaces(state: &mut State, fresh: bool) -> Task<Message> {
+    state.interfaces_loading = true;
+    background(
+        move || if fresh { interface_cache::refresh() } else { interface_cache::get() },
+        Message::InterfacesLoaded,
+    )
+}
+
+fn apply_interfaces(state: &mut State, interfaces: Interfaces) {
+    let adapters = interfaces.wifi_adapters;
+    state.client_adapters = adapters.iter().map(|a| a.name.clone()).collect();
+    (state.wifi_interfaces, state.wifi_labels, state.wifi_hint) = split_adapters(adapters);
+    state.network_interfaces = interfaces.network_interfaces;
+    state.wired_labels = wired_labels(&state.network_interfaces);
+    state.selected_hotspot_idx = state.wifi_interfaces
+        .iter()
+        .position(|i| *i == state.config.hotspot_interface);
+    state.selected_internet_idx = state.network_interfaces
+        .iter()
+        .position(|i| *i == state.config.internet_interface);
 }
 
 pub fn view(state: &State) -> Element<'_, Message> {
     let page_title = text::title1("WiFi Hotspot Settings");
 
-    let network_section = settings::section()
+    let mut network_section = settings::section()
         .title("Network")
         .add(settings::item(
             "SSID",
             text_input("Network name", &state.config.ssid)
                 .on_input(Message::SsidChanged)
-                .width(Length::Fixed(250.0)),
+                .width(Length::Fixed(state.options.control_width)),
         ))
         .add(settings::item(
             "Password",
             text_input("WPA2 password", &state.config.password)
                 .on_input(Message::PasswordChanged)
-                .width(Length::Fixed(250.0)),
+                .width(Length::Fixed(state.options.control_width)),
+        ));
+    if let Some(weakness) = password::audit(&state.config.password) {
+        network_section = network_section
+            .add(text::caption(format!("Weak password: {}", weakness.message())))
+            .add(settings::item_row(vec![
+                button::standard("Generate strong password")
+                    .on_press(Message::GeneratePassword)
+                    .into(),
+            ]));
+    }
+    network_section = match &state.saved_networks {
+        Some(_) => network_section
+            .add(settings::item(
+                "Copy from saved network",
+                widget::dropdown(&state.saved_network_labels, None, Message::SavedNetworkSelected)
+                    .width(Length::Fixed(state.options.control_width)),
+            ))
+            .add(settings::item_row(vec![
+                button::standard("Cancel").on_press(Message::CloseSavedNetworks).into(),
+            ])),
+        None => network_section.add(settings::item_row(vec![
+            button::standard(if state.pending == Some(Pending::ReadingSavedNetworks) {
+                "Reading saved networks..."
+            } else {
+                "Copy from saved network"
+            })
+            .on_press_maybe(state.pending.is_none().then_some(Message::CopySavedNetwork))
+            .into(),
+        ])),
+    };
+    network_section = network_section
+        .add(settings::item(
+            "Security",
+            widget::dropdown(
+                // Transition mode is last, so hiding it keeps the indices
+                &SECURITY_LABELS[..hotspot::security_modes(&state.config.backend).len()],
+                SECURITY_OPTIONS.iter().position(|&s| s == state.config.security),
+                Message::SecuritySelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
         ))
         .add(settings::item(
             "Band",
@@ -179,22 +1180,67 @@ pub fn view(state: &State) -> Element<'_, Message> {
                 Some(state.selected_band_idx),
                 Message::BandSelected,
             )
-            .width(Length::Fixed(250.0)),
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Channel",
+            widget::dropdown(
+                &state.channel_labels,
+                Some(
+                    channels::for_band(&state.config.band)
+                        .iter()
+                        .position(|&c| c == state.config.channel)
+                        .map_or(0, |i| i + 1),
+                ),
+                Message::ChannelSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
         ));
+    if !state.channel_usage.is_empty() {
+        let theme = cosmic::theme::active();
+        let fg = theme.cosmic().accent_color();
+        let color = format!(
+            "rgb({},{},{})",
+            (fg.red * 255.0) as u8,
+            (fg.green * 255.0) as u8,
+            (fg.blue * 255.0) as u8,
+        );
+        let spectrum = channels::spectrum_svg(&state.channel_usage, state.config.channel, &color);
+        network_section = network_section.add(
+            cosmic::iced::widget::svg(svg::Handle::from_memory(spectrum.into_bytes()))
+                .width(Length::Fill)
+                .height(Length::Fixed(74.0)),
+        );
+        if let Some(channel) = channels::least_busy(&state.channel_usage) {
+            network_section = network_section.add(text::caption(format!(
+                "Access points per channel (brighter = stronger). Least busy: {channel}"
+            )));
+        }
+    }
+    let network_section = network_section.add(settings::item_row(vec![
+        button::standard("Scan channels")
+            .on_press_maybe(state.pending.is_none().then_some(Message::ScanChannels))
+            .into(),
+    ]));
 
-    let hotspot_dropdown: Element<'_, Message> = if state.wifi_interfaces.is_empty() {
-        text::caption("No WiFi interfaces found").into()
+    let loading = state.interfaces_loading;
+    let hotspot_dropdown: Element<'_, Message> = if state.wifi_interfaces.is_empty() && loading {
+        text::caption("Looking for WiFi interfaces...").into()
+    } else if state.wifi_interfaces.is_empty() {
+        text::caption("No AP-capable WiFi interfaces found").into()
     } else {
         widget::dropdown(
-            &state.wifi_interfaces,
+            &state.wifi_labels,
             state.selected_hotspot_idx,
             Message::HotspotInterfaceSelected,
         )
-        .width(Length::Fixed(250.0))
+        .width(Length::Fixed(state.options.control_width))
         .into()
     };
 
-    let internet_dropdown: Element<'_, Message> = if state.network_interfaces.is_empty() {
+    let internet_dropdown: Element<'_, Message> = if state.network_interfaces.is_empty() && loading {
+        text::caption("Looking for network interfaces...").into()
+    } else if state.network_interfaces.is_empty() {
         text::caption("No network interfaces found").into()
     } else {
         widget::dropdown(
@@ -202,55 +1248,546 @@ pub fn view(state: &State) -> Element<'_, Message> {
             state.selected_internet_idx,
             Message::InternetInterfaceSelected,
         )
-        .width(Length::Fixed(250.0))
+        .width(Length::Fixed(state.options.control_width))
         .into()
     };
 
-    let interfaces_section = settings::section()
+    let mut interfaces_section = settings::section()
         .title("Interfaces")
-        .add(settings::item("Hotspot interface", hotspot_dropdown))
+        .add(settings::item("Hotspot interface", hotspot_dropdown));
+    if let Some(hint) = &state.wifi_hint {
+        interfaces_section = interfaces_section.add(text::caption(hint.clone()));
+    }
+    if !state.config.hotspot_interface.is_empty() {
+        interfaces_section = interfaces_section.add(settings::item(
+            "Adapter details",
+            button::standard(if state.show_adapter_details { "Hide" } else { "Show" })
+                .on_press(Message::ToggleAdapterDetails),
+        ));
+    }
+    if state.show_adapter_details {
+        match &state.adapter_details {
+            Some((device, details)) if *device == state.config.hotspot_interface => {
+                for line in adapter_details_lines(details) {
+                    interfaces_section = interfaces_section.add(text::caption(line));
+                }
+            }
+            _ => {
+                interfaces_section =
+                    interfaces_section.add(text::caption("Reading adapter capabilities..."));
+            }
+        }
+    }
+    let interfaces_section = interfaces_section
+        .add(settings::item(
+            "Disconnect WiFi client on this adapter when starting",
+            widget::toggler(state.config.auto_disconnect_client)
+                .on_toggle(Message::AutoDisconnectToggled),
+        ))
+        .add(settings::item(
+            "Keep USB adapter powered while the hotspot runs",
+            widget::toggler(state.config.keep_usb_adapter_powered)
+                .on_toggle(Message::KeepUsbPoweredToggled),
+        ))
         .add(settings::item("Internet interface", internet_dropdown))
+        .add(settings::item(
+            "Also share to wired interface",
+            widget::dropdown(
+                &state.wired_labels,
+                Some(
+                    state
+                        .network_interfaces
+                        .iter()
+                        .position(|i| *i == state.config.share_wired_interface)
+                        .map_or(0, |i| i + 1),
+                ),
+                Message::WiredShareSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Start when this adapter is plugged in",
+            text_input("MAC or USB id, e.g. 0e8d:7612", &state.config.autostart_adapter)
+                .on_input(Message::AutostartAdapterChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
         .add(settings::item_row(vec![
-            button::standard("Refresh Devices")
-                .on_press(Message::RefreshInterfaces)
+            button::standard(if loading { "Refreshing..." } else { "Refresh Devices" })
+                .on_press_maybe((!loading).then_some(Message::RefreshInterfaces))
                 .into(),
         ]));
 
+    let mut repeater_section = settings::section()
+        .title("Repeater")
+        .add(settings::item(
+            "Repeat a WiFi network (client adapter → hotspot adapter)",
+            widget::toggler(state.config.repeater_mode).on_toggle(Message::RepeaterModeToggled),
+        ));
+    if state.config.repeater_mode {
+        repeater_section = repeater_section
+            .add(settings::item(
+                "Client adapter",
+                widget::dropdown(
+                    &state.client_adapters,
+                    state
+                        .client_adapters
+                        .iter()
+                        .position(|i| *i == state.config.internet_interface),
+                    Message::RepeaterAdapterSelected,
+                )
+                .width(Length::Fixed(state.options.control_width)),
+            ))
+            .add(settings::item(
+                "Upstream network",
+                if state.network_labels.is_empty() {
+                    Element::from(text::caption(if state.config.repeater_ssid.is_empty() {
+                        "Scan to choose a network".to_string()
+                    } else {
+                        state.config.repeater_ssid.clone()
+                    }))
+                } else {
+                    widget::dropdown(
+                        &state.network_labels,
+                        state
+                            .scanned_networks
+                            .iter()
+                            .position(|n| n.ssid == state.config.repeater_ssid),
+                        Message::RepeaterNetworkSelected,
+                    )
+                    .width(Length::Fixed(state.options.control_width))
+                    .into()
+                },
+            ))
+            .add(settings::item(
+                "Upstream password",
+                text_input("Leave empty for open networks", &state.config.repeater_password)
+                    .on_input(Message::RepeaterPasswordChanged)
+                    .width(Length::Fixed(state.options.control_width)),
+            ))
+            .add(settings::item_row(vec![
+                button::standard(if state.pending == Some(Pending::ScanningNetworks) { "Scanning..." } else { "Scan" })
+                    .on_press_maybe(state.pending.is_none().then_some(Message::ScanNetworks))
+                    .into(),
+            ]));
+        if state.config.internet_interface == state.config.hotspot_interface {
+            repeater_section = repeater_section.add(text::caption(
+                "Pick a client adapter other than the hotspot interface",
+            ));
+        }
+    }
+
+    let profile = state.config.profile();
+    let profile_section = settings::section()
+        .title(format!("Profile: {}", profile.name))
+        .add(settings::item(
+            "Active profile",
+            widget::dropdown(
+                &state.profile_labels,
+                state.config.profiles.iter().position(|p| p.name == profile.name),
+                Message::ProfileSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "New name",
+            text_input("Profile name", &state.profile_name)
+                .on_input(Message::ProfileNameChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item_row(vec![
+            button::standard("Add profile")
+                .on_press_maybe((!state.profile_name.trim().is_empty()).then_some(Message::AddProfile))
+                .into(),
+            button::standard("Rename")
+                .on_press_maybe((!state.profile_name.trim().is_empty()).then_some(Message::RenameProfile))
+                .into(),
+            button::destructive("Delete profile")
+                .on_press_maybe((state.config.profiles.len() > 1).then_some(Message::DeleteProfile))
+                .into(),
+        ]))
+        .add(text::caption(
+            "Each profile keeps its own SSID, password, band and interfaces; switching swaps them in",
+        ))
+        .add(settings::item(
+            "Gateway IP",
+            text_input("e.g. 192.168.44.1/24", &profile.gateway_ip)
+                .on_input(Message::GatewayIpChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "DHCP range start",
+            text_input("NetworkManager default", &profile.dhcp_range_start)
+                .on_input(Message::DhcpRangeStartChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "DHCP range end",
+            text_input("NetworkManager default", &profile.dhcp_range_end)
+                .on_input(Message::DhcpRangeEndChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "DNS server for clients",
+            text_input("Gateway (default)", &profile.dns_server)
+                .on_input(Message::DnsServerChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Extra DHCP options",
+            text_input("option:ntp-server,192.168.44.1", &profile.dhcp_options)
+                .on_input(Message::DhcpOptionsChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "NAT strategy",
+            widget::dropdown(
+                NAT_LABELS,
+                Some(
+                    NAT_OPTIONS
+                        .iter()
+                        .position(|&n| n == profile.nat_strategy)
+                        .unwrap_or(0),
+                ),
+                Message::NatStrategySelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Isolate clients from each other",
+            widget::toggler(profile.client_isolation).on_toggle(Message::ClientIsolationToggled),
+        ))
+        .add(settings::item(
+            "Use for plug-in start",
+            widget::toggler(profile.autostart).on_toggle(Message::ProfileAutostartToggled),
+        ))
+        .add(settings::item(
+            "Scheduled days",
+            text_input("e.g. mon-fri", &profile.schedule_days)
+                .on_input(Message::ScheduleDaysChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Scheduled from",
+            text_input("HH:MM", &profile.schedule_from)
+                .on_input(Message::ScheduleFromChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Scheduled until",
+            text_input("HH:MM", &profile.schedule_until)
+                .on_input(Message::ScheduleUntilChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Turn off after minutes without clients (0 = general setting)",
+            text_input("0", profile.idle_off_override_mins.to_string())
+                .on_input(Message::ProfileIdleOffChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "On battery, turn off below (%, 0 = off)",
+            text_input("0", profile.battery_min_percent.to_string())
+                .on_input(Message::BatteryMinPercentChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(text::caption(
+            "A profile with a schedule starts when its window opens and stops when it closes; where windows overlap, the profile listed first wins",
+        ));
+
+    let selected_quota_period_idx = QUOTA_PERIOD_OPTIONS
+        .iter()
+        .position(|&p| p == state.config.quota_period)
+        .unwrap_or(0);
+
     let advanced_section = settings::section()
         .title("Advanced")
+        .add(settings::item(
+            "Hotspot backend",
+            widget::dropdown(
+                BACKEND_LABELS,
+                BACKEND_OPTIONS.iter().position(|&b| b == state.config.backend),
+                Message::BackendSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
         .add(settings::item(
             "Connection name",
             text_input("NM connection name", &state.config.connection_name)
                 .on_input(Message::ConnectionNameChanged)
-                .width(Length::Fixed(250.0)),
+                .width(Length::Fixed(state.options.control_width)),
         ))
         .add(settings::item(
-            "Gateway IP",
-            text_input("e.g. 192.168.44.1/24", &state.config.gateway_ip)
-                .on_input(Message::GatewayIpChanged)
-                .width(Length::Fixed(250.0)),
+            "Start at boot (keep the hotspot profile with autoconnect)",
+            widget::toggler(state.config.autoconnect)
+                .on_toggle(Message::AutoconnectToggled),
+        ))
+        .add(settings::item(
+            "Verify connectivity after start",
+            widget::toggler(state.config.verify_after_start)
+                .on_toggle(Message::VerifyAfterStartToggled),
+        ))
+        .add(settings::item(
+            "Warn about nearby networks with the same name",
+            widget::toggler(state.config.check_ssid_collisions)
+                .on_toggle(Message::CheckSsidCollisionsToggled),
+        ))
+        .add(settings::item(
+            "Notify after failed join attempts (0 = off)",
+            text_input("5", state.config.failed_join_notify_threshold.to_string())
+                .on_input(Message::FailedJoinThresholdChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Warn when activation takes longer than (seconds, 0 = off)",
+            text_input("20", state.config.slow_activation_secs.to_string())
+                .on_input(Message::SlowActivationSecsChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Log clients' DNS lookups (privacy: records every name they look up)",
+            widget::toggler(state.config.dns_query_log)
+                .on_toggle(Message::DnsQueryLogToggled),
+        ))
+        .add(settings::item(
+            "Resolve clients by name (<hostname>.hotspot)",
+            widget::toggler(state.config.local_domain)
+                .on_toggle(Message::LocalDomainToggled),
+        ))
+        .add(settings::item(
+            if state.config.host_resolver_dns && !hotspot::resolved_running() {
+                "Resolve clients' DNS through this computer's resolver (needs systemd-resolved, which isn't running)"
+            } else {
+                "Resolve clients' DNS through this computer's resolver (follows VPN DNS)"
+            },
+            widget::toggler(state.config.host_resolver_dns)
+                .on_toggle(Message::HostResolverDnsToggled),
+        ))
+        .add(settings::item(
+            "Repair connection sharing when other tools reset it",
+            widget::toggler(state.config.repair_sharing)
+                .on_toggle(Message::RepairSharingToggled),
+        ))
+        .add(settings::item(
+            "Convert multicast to unicast (helps casting and VR discovery)",
+            widget::toggler(state.config.multicast_to_unicast)
+                .on_toggle(Message::MulticastToUnicastToggled),
+        ))
+        .add(settings::item(
+            "Multicast rate",
+            widget::dropdown(
+                MULTICAST_RATE_LABELS,
+                MULTICAST_RATE_OPTIONS.iter().position(|&r| r == state.config.multicast_rate_mbps),
+                Message::MulticastRateSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Data quota",
+            widget::dropdown(
+                QUOTA_PERIOD_LABELS,
+                Some(selected_quota_period_idx),
+                Message::QuotaPeriodSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Quota (MB)",
+            text_input("e.g. 5000", state.config.quota_mb.to_string())
+                .on_input(Message::QuotaMbChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Quota resets at (weekly: on Monday)",
+            text_input("00:00", &state.config.quota_reset_time)
+                .on_input(Message::QuotaResetTimeChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Turn off after minutes without clients (0 = off)",
+            text_input("0", state.config.idle_off_mins.to_string())
+                .on_input(Message::IdleOffMinsChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Keep copied passwords out of clipboard history",
+            widget::toggler(state.config.clipboard_sensitive)
+                .on_toggle(Message::ClipboardSensitiveToggled),
+        ))
+        .add(settings::item(
+            "Clear copied password after (seconds, 0 = never)",
+            text_input("0", state.config.clipboard_clear_secs.to_string())
+                .on_input(Message::ClipboardClearSecsChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Sweep subnet for idle clients",
+            widget::toggler(state.config.client_sweep)
+                .on_toggle(Message::ClientSweepToggled),
+        ))
+        .add(settings::item(
+            "Sweep interval (seconds)",
+            text_input("60", state.config.client_sweep_interval_secs.to_string())
+                .on_input(Message::ClientSweepIntervalChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Idle client grace period (seconds)",
+            text_input("60", state.config.client_grace_secs.to_string())
+                .on_input(Message::ClientGraceChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Dry run (print commands instead of running them)",
+            widget::toggler(state.config.dry_run).on_toggle(Message::DryRunToggled),
         ));
 
-    let save_btn = button::suggested("Save")
-        .on_press(Message::Save);
+    let shortcuts_section = settings::section()
+        .title("Shortcuts")
+        .add(settings::item(
+            "Toggle hotspot",
+            text_input("e.g. Super+Shift+H", &state.config.toggle_shortcut)
+                .on_input(Message::ToggleShortcutChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ));
+
+    let selected_density_idx = DENSITY_OPTIONS
+        .iter()
+        .position(|&d| d == state.config.popup_density)
+        .unwrap_or(0);
+    let selected_icon_color_idx = ICON_COLOR_OPTIONS
+        .iter()
+        .position(|&c| c == state.config.icon_color)
+        .unwrap_or(0);
+    let selected_icon_style_idx = ICON_STYLE_OPTIONS
+        .iter()
+        .position(|&s| s == state.config.icon_style)
+        .unwrap_or(0);
+    let selected_click_action_idx = CLICK_ACTION_OPTIONS
+        .iter()
+        .position(|&a| a == state.config.click_action)
+        .unwrap_or(0);
+
+    let display_section = settings::section()
+        .title("Display")
+        .add(settings::item(
+            "Icon style",
+            widget::dropdown(
+                ICON_STYLE_LABELS,
+                Some(selected_icon_style_idx),
+                Message::IconStyleSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Active icon color",
+            widget::dropdown(
+                ICON_COLOR_LABELS,
+                Some(selected_icon_color_idx),
+                Message::IconColorSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Clicking the panel icon",
+            widget::dropdown(
+                CLICK_ACTION_LABELS,
+                Some(selected_click_action_idx),
+                Message::ClickActionSelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Keep result messages for (seconds)",
+            text_input("10", state.config.status_hold_secs.to_string())
+                .on_input(Message::StatusHoldChanged)
+                .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Reduce motion",
+            widget::toggler(state.config.reduce_motion)
+                .on_toggle(Message::ReduceMotionToggled),
+        ))
+        .add(settings::item(
+            "Popup density",
+            widget::dropdown(
+                DENSITY_LABELS,
+                Some(selected_density_idx),
+                Message::PopupDensitySelected,
+            )
+            .width(Length::Fixed(state.options.control_width)),
+        ))
+        .add(settings::item(
+            "Show connected clients",
+            widget::toggler(state.config.popup_show_clients)
+                .on_toggle(Message::PopupShowClientsToggled),
+        ))
+        .add(settings::item(
+            "Show QR code",
+            widget::toggler(state.config.popup_show_qr)
+                .on_toggle(Message::PopupShowQrToggled),
+        ))
+        .add(settings::item(
+            "Show statistics",
+            widget::toggler(state.config.popup_show_stats)
+                .on_toggle(Message::PopupShowStatsToggled),
+        ))
+        .add(settings::item(
+            "Show schedule",
+            widget::toggler(state.config.popup_show_schedule)
+                .on_toggle(Message::PopupShowScheduleToggled),
+        ));
+
+    let idle = state.pending.is_none();
+    let save_btn = button::suggested(if state.pending == Some(Pending::Saving) { "Saving..." } else { "Save" })
+        .on_press_maybe(idle.then_some(Message::Save));
 
     let reset_btn = button::destructive("Reset to Defaults")
-        .on_press(Message::ResetDefaults);
+        .on_press_maybe(idle.then_some(Message::ResetDefaults));
 
-    let actions_section = settings::section()
+    let mut actions_section = settings::section()
         .title("Actions")
         .add(settings::item_row(vec![
             save_btn.into(),
             reset_btn.into(),
         ]));
+    if state.confirm_reset {
+        actions_section = actions_section
+            .add(text::body("Reset every setting and profile? The current settings are backed up first."))
+            .add(settings::item_row(vec![
+                button::destructive("Reset everything")
+                    .on_press_maybe(idle.then_some(Message::ConfirmReset))
+                    .into(),
+                button::standard("Cancel").on_press(Message::CancelReset).into(),
+            ]));
+    }
 
-    let mut content_items: Vec<Element<'_, Message>> = vec![
-        page_title.into(),
+    let mut content_items: Vec<Element<'_, Message>> = Vec::new();
+    if state.options.show_title {
+        content_items.push(page_title.into());
+    }
+    if state.running_outdated {
+        let restarting = state.pending == Some(Pending::Restarting);
+        content_items.push(
+            settings::section()
+                .add(text::body("Running hotspot uses old settings — Restart now?"))
+                .add(settings::item_row(vec![
+                    button::suggested(if restarting { "Restarting..." } else { "Restart now" })
+                        .on_press_maybe(idle.then_some(Message::RestartHotspot))
+                        .into(),
+                ]))
+                .into(),
+        );
+    }
+    content_items.extend([
         network_section.into(),
         interfaces_section.into(),
+        repeater_section.into(),
+        profile_section.into(),
         advanced_section.into(),
+        display_section.into(),
+        shortcuts_section.into(),
         actions_section.into(),
-    ];
+    ]);
 
     if !state.status_message.is_empty() {
         content_items.push(text::body(&state.status_message).into());
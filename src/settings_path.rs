@@ -0,0 +1,141 @@
+//! Dotted-key addressing into the config's JSON form, for `--settings-set`.
+//!
+//! `profiles.Guest.gateway_ip` picks the profile named "Guest",
+//! `profiles[0].dns_server` picks by position, and `list[+]` appends. Only
+//! existing keys can be set, and the new value must have the same JSON type
+//! as the old one — serde would otherwise silently drop a misspelt key.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Object field, or the element of an array of objects with this "name"
+    Key(String),
+    Index(usize),
+    /// `[+]`: a new element at the end of an array
+    Append,
+}
+
+/// Split `profiles.Guest.dhcp_range_start` or `a.b[2]` into segments
+pub fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if key.is_empty() && segments.is_empty() {
+            return Err(format!("Invalid key '{path}': must start with a name"));
+        }
+        if !key.is_empty() {
+            segments.push(Segment::Key(key.to_string()));
+        } else if rest.is_empty() {
+            return Err(format!("Invalid key '{path}': empty segment"));
+        }
+        while !rest.is_empty() {
+            let Some(end) = rest.find(']').filter(|_| rest.starts_with('[')) else {
+                return Err(format!("Invalid key '{path}': unbalanced brackets"));
+            };
+            let index = &rest[1..end];
+            segments.push(if index == "+" {
+                Segment::Append
+            } else {
+                Segment::Index(
+                    index
+                        .parse()
+                        .map_err(|_| format!("Invalid key '{path}': bad index '{index}'"))?,
+                )
+            });
+            rest = &rest[end + 1..];
+        }
+    }
+    if segments[..segments.len() - 1].contains(&Segment::Append) {
+        return Err(format!("Invalid key '{path}': [+] must come last"));
+    }
+    Ok(segments)
+}
+
+/// Set the value at `path` from `raw`. `raw` is JSON; for string settings a
+/// bare word that isn't valid JSON is taken as the string itself.
+pub fn set(root: &mut Value, path: &str, raw: &str) -> Result<(), String> {
+    let segments = parse(path)?;
+    let (last, parents) = segments.split_last().expect("parse returns at least one segment");
+
+    let mut node = root;
+    for segment in parents {
+        node = child(node, segment, path)?;
+    }
+
+    match last {
+        Segment::Append => {
+            let Value::Array(items) = node else {
+                return Err(format!("'{path}' doesn't refer to a list"));
+            };
+            // New elements match the type of the existing ones
+            let value = match items.first() {
+                Some(sample) => typed(raw, sample, path)?,
+                None => parse_raw(raw),
+            };
+            items.push(value);
+        }
+        segment => {
+            let target = child(node, segment, path)?;
+            *target = typed(raw, target, path)?;
+        }
+    }
+    Ok(())
+}
+
+fn child<'a>(node: &'a mut Value, segment: &Segment, path: &str) -> Result<&'a mut Value, String> {
+    match (node, segment) {
+        (Value::Object(map), Segment::Key(key)) => map
+            .get_mut(key)
+            .ok_or_else(|| format!("Unknown key '{key}' in '{path}'")),
+        (Value::Array(items), Segment::Key(name)) => items
+            .iter_mut()
+            .find(|item| item.get("name").and_then(Value::as_str) == Some(name.as_str()))
+            .ok_or_else(|| format!("No entry named '{name}' in '{path}'")),
+        (Value::Array(items), Segment::Index(i)) => {
+            let len = items.len();
+            items
+                .get_mut(*i)
+                .ok_or_else(|| format!("Index {i} out of range in '{path}' ({len} entries)"))
+        }
+        (_, Segment::Append) => Err(format!("Invalid key '{path}': [+] must come last")),
+        _ => Err(format!("'{path}' goes through a value that has no fields")),
+    }
+}
+
+fn parse_raw(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Parse `raw` as the same JSON type as `current`
+fn typed(raw: &str, current: &Value, path: &str) -> Result<Value, String> {
+    let value = match current {
+        Value::String(_) => parse_raw(raw),
+        _ => serde_json::from_str(raw).map_err(|e| format!("Invalid value for '{path}': {e}"))?,
+    };
+    let same_type = matches!(
+        (current, &value),
+        (Value::String(_), Value::String(_))
+            | (Value::Bool(_), Value::Bool(_))
+            | (Value::Number(_), Value::Number(_))
+            | (Value::Array(_), Value::Array(_))
+            | (Value::Object(_), Value::Object(_))
+            | (Value::Null, _)
+    );
+    if same_type {
+        Ok(value)
+    } else {
+        Err(format!("Invalid value for '{path}': expected {}", type_name(current)))
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
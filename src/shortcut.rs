@@ -0,0 +1,184 @@
+//! Global toggle shortcut registration.
+//!
+//! COSMIC stores user-defined shortcuts as a RON map of binding → action in
+//! the settings daemon's `custom` config entry. We add (or replace) a single
+//! entry that spawns `cosmic-hotspot --toggle`, leaving every other entry
+//! untouched, and refuse a binding another entry already uses.
+
+use std::path::PathBuf;
+
+const TOGGLE_COMMAND: &str = "cosmic-hotspot --toggle";
+
+fn custom_shortcuts_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| {
+        d.join("cosmic")
+            .join("com.system76.CosmicSettings.Shortcuts")
+            .join("v1")
+            .join("custom")
+    })
+}
+
+/// Register `binding` (e.g. "Super+Shift+H") as the hotspot toggle shortcut.
+/// An empty binding removes any previously registered shortcut.
+pub fn apply(binding: &str) -> Result<(), String> {
+    let binding_ron = if binding.trim().is_empty() {
+        None
+    } else {
+        Some(binding_to_ron(binding)?)
+    };
+
+    let path = custom_shortcuts_path().ok_or("Could not determine COSMIC config path")?;
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read shortcuts: {e}")),
+    };
+
+    // Rewriting a file we couldn't follow would drop the user's other shortcuts
+    let mut entries: Vec<String> = split_map_entries(&existing)
+        .map_err(|e| format!("Not changing {}: {e}", path.display()))?
+        .into_iter()
+        .filter(|entry| !entry.contains(&format!("Spawn(\"{TOGGLE_COMMAND}\")")))
+        .collect();
+    if let Some(binding_ron) = binding_ron {
+        // COSMIC keeps one action per binding; a second map key would be ambiguous
+        let ours = binding_keys(&binding_ron);
+        if let Some(taken) = entries.iter().find(|entry| binding_keys(entry) == ours) {
+            let action = taken.split_once("):").map_or(taken.as_str(), |(_, action)| action.trim());
+            return Err(format!("{} is already bound to {action}", binding.trim()));
+        }
+        entries.push(format!("{binding_ron}: Spawn(\"{TOGGLE_COMMAND}\")"));
+    }
+
+    let mut content = String::from("{\n");
+    for entry in &entries {
+        content.push_str("    ");
+        content.push_str(entry);
+        content.push_str(",\n");
+    }
+    content.push('}');
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create shortcuts dir: {e}"))?;
+    }
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write shortcuts: {e}"))
+}
+
+/// Convert "Super+Shift+H" into COSMIC's RON binding representation
+fn binding_to_ron(binding: &str) -> Result<String, String> {
+    let parts: Vec<&str> = binding.split('+').map(str::trim).collect();
+    let (key, modifier_names) = parts
+        .split_last()
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("Invalid shortcut: {binding}"))?;
+
+    let mut modifiers = Vec::new();
+    for name in modifier_names {
+        let modifier = match name.to_ascii_lowercase().as_str() {
+            "super" | "logo" | "meta" => "Super",
+            "ctrl" | "control" => "Ctrl",
+            "alt" => "Alt",
+            "shift" => "Shift",
+            _ => return Err(format!("Unknown modifier: {name}")),
+        };
+        modifiers.push(modifier);
+    }
+
+    // Single letters are lowercase keysyms; named keys (F9, Return) keep their case
+    let key = if key.chars().count() == 1 {
+        key.to_ascii_lowercase()
+    } else {
+        key.to_string()
+    };
+
+    Ok(format!(
+        "(modifiers: [{}], key: \"{}\", description: Some(\"Toggle WiFi hotspot\"))",
+        modifiers.join(", "),
+        key,
+    ))
+}
+
+/// The modifiers (sorted) and key of a RON binding or map entry, to compare
+/// bindings however they were written
+fn binding_keys(ron: &str) -> Option<(Vec<String>, String)> {
+    let binding = &ron[..ron.find(')')?];
+    let modifiers = binding.split_once("modifiers:")?.1.trim_start().strip_prefix('[')?;
+    let mut modifiers: Vec<String> = modifiers[..modifiers.find(']')?]
+        .split(',')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+        .collect();
+    modifiers.sort();
+    let key = binding.split_once("key:")?.1.trim_start();
+    let key = key.strip_prefix("Some(").unwrap_or(key).strip_prefix('"')?;
+    let key = &key[..key.find('"')?];
+    let key = if key.chars().count() == 1 { key.to_ascii_lowercase() } else { key.to_string() };
+    Some((modifiers, key))
+}
+
+/// Split the body of a RON map into its top-level `key: value` entries. An
+/// empty file is an empty map; anything else that isn't a balanced map is an
+/// error.
+fn split_map_entries(content: &str) -> Result<Vec<String>, String> {
+    let body = content.trim();
+    if body.is_empty() {
+        return Ok(Vec::new());
+    }
+    let body = body
+        .strip_prefix('{')
+        .and_then(|b| b.strip_suffix('}'))
+        .ok_or("the shortcuts file isn't a map")?;
+
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in body.chars() {
+        if in_string {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                current.push(c);
+            }
+            '(' | '[' | '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(format!("unmatched '{c}' in the shortcuts file"));
+                }
+                current.push(c);
+            }
+            ',' if depth == 0 => {
+                if !current.trim().is_empty() {
+                    entries.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if in_string || depth != 0 {
+        return Err("the shortcuts file ends mid-entry".to_string());
+    }
+    if !current.trim().is_empty() {
+        entries.push(current.trim().to_string());
+    }
+    Ok(entries)
+}
@@ -0,0 +1,163 @@
+//! Radio details of the hotspot's associated clients.
+//!
+//! `iw dev <if> station dump` lists each station the access point has
+//! associated, with the bitrates it negotiated and the signal it's heard at
+//! (RSSI); the band is the AP's own,
+//! from `iw dev <if> info`. Both work the same whether NetworkManager or
+//! hostapd runs the AP, and need no privileges.
+
+use std::collections::HashMap;
+use std::process::Command;
+
+/// How a client is connected to the access point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Radio {
+    /// The AP's frequency, which every client on it shares
+    pub frequency_mhz: Option<u32>,
+    /// Bitrate of the last frame sent to the client, rounded to Mbit/s
+    pub tx_rate_mbps: Option<u32>,
+    /// WiFi generation of that rate: 4 (HT), 5 (VHT), 6 (HE) or 7 (EHT); `None`
+    /// for legacy rates
+    pub generation: Option<u8>,
+    /// Signal of the client's frames at the AP, in dBm
+    pub signal_dbm: Option<i32>,
+}
+
+/// Signal bars: 4 from -55 dBm, then one fewer per step down; below the last
+/// step a client is at the edge of range
+const SIGNAL_STEPS: [i32; 4] = [-85, -75, -67, -55];
+
+impl Radio {
+    /// "5 GHz · 867 Mbit/s (WiFi 5)", leaving out what isn't known
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(band) = self.frequency_mhz.and_then(band_label) {
+            parts.push(band.to_string());
+        }
+        if let Some(rate) = self.tx_rate_mbps {
+            parts.push(match self.generation {
+                Some(generation) => format!("{rate} Mbit/s (WiFi {generation})"),
+                None => format!("{rate} Mbit/s"),
+            });
+        }
+        parts.join(" · ")
+    }
+
+    /// 0–4 bars for the signal, if the driver reports it
+    pub fn signal_bars(&self) -> Option<usize> {
+        let dbm = self.signal_dbm?;
+        Some(SIGNAL_STEPS.iter().filter(|&&step| dbm >= step).count())
+    }
+}
+
+/// "▂▄▆█" with the bars `bars` doesn't reach drawn as "▁"
+pub fn bars_glyph(bars: usize) -> String {
+    ["▂", "▄", "▆", "█"]
+        .iter()
+        .enumerate()
+        .map(|(i, bar)| if i < bars { *bar } else { "▁" })
+        .collect()
+}
+
+/// One station from `iw dev <if> station dump`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Station {
+    /// Lowercase MAC address
+    pub mac: String,
+    pub tx_rate_mbps: Option<u32>,
+    pub generation: Option<u8>,
+    pub signal_dbm: Option<i32>,
+}
+
+/// MAC (lowercase) → radio details of the stations associated on `interface`;
+/// empty if `iw` isn't installed or the driver doesn't report stations
+pub fn radios(interface: &str) -> HashMap<String, Radio> {
+    let iw = |args: &[&str]| {
+        Command::new("iw")
+            .args(["dev", interface])
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let Some(dump) = iw(&["station", "dump"]) else {
+        return HashMap::new();
+    };
+    let frequency_mhz = iw(&["info"]).and_then(|info| parse_frequency(&info));
+    parse_station_dump(&dump)
+        .into_iter()
+        .map(|station| {
+            let radio = Radio {
+                frequency_mhz,
+                tx_rate_mbps: station.tx_rate_mbps,
+                generation: station.generation,
+                signal_dbm: station.signal_dbm,
+            };
+            (station.mac, radio)
+        })
+        .collect()
+}
+
+/// Stations in `iw dev <if> station dump` output:
+///
+/// ```text
+/// Station 3c:22:fb:12:34:56 (on wlan0)
+///         signal:         -52 [-54, -56] dBm
+///         tx bitrate:     866.7 MBit/s VHT-MCS 9 80MHz short GI VHT-NSS 2
+/// ```
+pub fn parse_station_dump(text: &str) -> Vec<Station> {
+    let mut stations: Vec<Station> = Vec::new();
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("Station ") {
+            let mac = rest.split_whitespace().next().unwrap_or_default().to_lowercase();
+            stations.push(Station { mac, tx_rate_mbps: None, generation: None, signal_dbm: None });
+            continue;
+        }
+        let (Some(station), Some((key, value))) = (stations.last_mut(), line.split_once(':')) else {
+            continue;
+        };
+        match key.trim() {
+            "tx bitrate" => {
+                let mut words = value.split_whitespace();
+                station.tx_rate_mbps =
+                    words.next().and_then(|rate| rate.parse::<f64>().ok()).map(|rate| rate.round() as u32);
+                station.generation = words.find_map(generation);
+            }
+            // The combined signal; per-chain values follow in brackets
+            "signal" => station.signal_dbm = value.split_whitespace().next().and_then(|dbm| dbm.parse().ok()),
+            _ => {}
+        }
+    }
+    stations
+}
+
+/// The AP's frequency from `iw dev <if> info`: "channel 36 (5180 MHz), ..."
+pub fn parse_frequency(text: &str) -> Option<u32> {
+    text.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("channel "))
+        .and_then(|rest| rest.split_once('(')?.1.split_whitespace().next()?.parse().ok())
+}
+
+/// "2.4 GHz", "5 GHz", "6 GHz" or "60 GHz" for a frequency in MHz
+pub fn band_label(mhz: u32) -> Option<&'static str> {
+    match mhz {
+        2400..=2500 => Some("2.4 GHz"),
+        4900..=5925 => Some("5 GHz"),
+        5926..=7125 => Some("6 GHz"),
+        57000..=71000 => Some("60 GHz"),
+        _ => None,
+    }
+}
+
+/// WiFi generation named by a rate's modulation token
+fn generation(token: &str) -> Option<u8> {
+    match token {
+        "EHT-MCS" => Some(7),
+        "HE-MCS" => Some(6),
+        "VHT-MCS" => Some(5),
+        "MCS" => Some(4),
+        _ => None,
+    }
+}
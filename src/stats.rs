@@ -0,0 +1,41 @@
+//! The popup's one-line summary of a running hotspot:
+//! "3 clients · 48 Mbit/s · 1h 12m".
+
+use std::time::Duration;
+
+use crate::schedule;
+
+/// Bits per second, e.g. "48 Mbit/s" or "850 kbit/s"
+pub fn format_bitrate(bits_per_sec: u64) -> String {
+    const UNITS: &[&str] = &["bit/s", "kbit/s", "Mbit/s", "Gbit/s"];
+    let mut value = bits_per_sec as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 10.0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Bits per second from two byte counter readings `elapsed` apart; `None`
+/// if the counter went backwards (the interface was recreated)
+pub fn bitrate(previous: u64, current: u64, elapsed: Duration) -> Option<u64> {
+    let bytes = current.checked_sub(previous)?;
+    let secs = elapsed.as_secs_f64();
+    (secs > 0.0).then(|| (bytes as f64 * 8.0 / secs) as u64)
+}
+
+/// The stats line; throughput and uptime are left out when unknown
+pub fn summary(clients: usize, bits_per_sec: Option<u64>, uptime: Option<Duration>) -> String {
+    let mut parts = vec![match clients {
+        1 => "1 client".to_string(),
+        n => format!("{n} clients"),
+    }];
+    parts.extend(bits_per_sec.map(format_bitrate));
+    parts.extend(uptime.map(|up| schedule::format_duration((up.as_secs() / 60) as u32)));
+    parts.join(" · ")
+}
@@ -0,0 +1,48 @@
+//! Restarting a long-running task that panics.
+//!
+//! The applet's background loop owns the polling, the session D-Bus service
+//! and every command the popup sends. If it panicked, the popup would stop
+//! getting events for good while still looking alive, so it runs under
+//! `supervise`, which starts it again and backs off if it keeps crashing.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// Longest wait between restarts of a task that keeps crashing
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A task that ran this long before crashing starts over at the shortest wait
+const STABLE_RUN: Duration = Duration::from_secs(300);
+
+/// Run `task` until it returns, starting it again whenever it panics.
+/// `on_crash` gets the panic message and the number of crashes in a row;
+/// the wait before each restart doubles from `backoff` up to `MAX_BACKOFF`.
+pub fn supervise(mut task: impl FnMut(), mut on_crash: impl FnMut(&str, u32), backoff: Duration) {
+    let mut crashes = 0u32;
+    loop {
+        let started = Instant::now();
+        let Err(payload) = panic::catch_unwind(AssertUnwindSafe(&mut task)) else {
+            return;
+        };
+        if started.elapsed() >= STABLE_RUN {
+            crashes = 0;
+        }
+        crashes += 1;
+        on_crash(&panic_message(payload.as_ref()), crashes);
+        std::thread::sleep(restart_delay(backoff, crashes));
+    }
+}
+
+/// Wait before restarting after `crashes` crashes in a row
+pub fn restart_delay(backoff: Duration, crashes: u32) -> Duration {
+    backoff.saturating_mul(1 << crashes.saturating_sub(1).min(16)).min(MAX_BACKOFF)
+}
+
+/// The message `panic!` was given, if it was a string
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
@@ -0,0 +1,156 @@
+//! Daily or weekly data quotas for the hotspot.
+//!
+//! Traffic through the hotspot interface is added up per quota period and
+//! kept in the user's state dir, so the count survives restarts of the
+//! hotspot and the applet. A period starts at the configured reset time
+//! (weekly ones on Monday); the local UTC offset at the time of the check is
+//! used, so a period spanning a DST change is an hour longer or shorter.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::schedule;
+
+/// Share of the quota at which the user is warned
+pub const WARN_FRACTION: f64 = 0.8;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    Daily,
+    Weekly,
+}
+
+impl QuotaPeriod {
+    /// The config value, `None` for "off"
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Daily => "Daily",
+            Self::Weekly => "Weekly",
+        }
+    }
+}
+
+/// A quota that's on, with its limit and reset time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub period: QuotaPeriod,
+    pub limit_bytes: u64,
+    /// Minutes after local midnight
+    pub reset_minute: u32,
+}
+
+impl Quota {
+    /// The configured quota, if one is set
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let period = QuotaPeriod::from_config(&config.quota_period)?;
+        (config.quota_mb > 0).then(|| Self {
+            period,
+            limit_bytes: config.quota_mb * 1_000_000,
+            reset_minute: schedule::parse_time(&config.quota_reset_time).unwrap_or(0),
+        })
+    }
+}
+
+/// Bytes used in the current period
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Unix time the period began
+    pub period_start: u64,
+    pub bytes: u64,
+    /// Whether the 80% warning was shown for this period
+    pub warned: bool,
+}
+
+impl Usage {
+    /// Count `bytes` toward the period starting at `period_start`, starting
+    /// over if that's a new one. Returns true when this crosses the warning
+    /// threshold for the first time in the period.
+    pub fn add(&mut self, bytes: u64, quota: &Quota, period_start: u64) -> bool {
+        if period_start != self.period_start {
+            *self = Self { period_start, ..Self::default() };
+        }
+        self.bytes = self.bytes.saturating_add(bytes);
+        if !self.warned && self.fraction(quota) >= WARN_FRACTION {
+            self.warned = true;
+            return true;
+        }
+        false
+    }
+
+    /// Bytes counted in the period starting at `period_start`; none if the
+    /// stored count is from an earlier one
+    pub fn used(&self, period_start: u64) -> u64 {
+        if period_start == self.period_start { self.bytes } else { 0 }
+    }
+
+    /// Share of the quota used, from 0 (can exceed 1)
+    pub fn fraction(&self, quota: &Quota) -> f64 {
+        self.bytes as f64 / quota.limit_bytes.max(1) as f64
+    }
+}
+
+/// Unix time the period containing `now` started, given the local time of
+/// day in seconds and the weekday (0 = Monday) at `now`
+pub fn period_start(now: u64, local_secs_of_day: u64, weekday: u32, quota: &Quota) -> u64 {
+    let midnight = now - local_secs_of_day;
+    let mut start = midnight + u64::from(quota.reset_minute) * 60;
+    if start > now {
+        start -= SECS_PER_DAY;
+    }
+    match quota.period {
+        QuotaPeriod::Daily => start,
+        QuotaPeriod::Weekly => {
+            // Days since the Monday whose reset time has passed
+            let start_weekday = if start < midnight { (weekday + 6) % 7 } else { weekday };
+            start - u64::from(start_weekday) * SECS_PER_DAY
+        }
+    }
+}
+
+/// `period_start` for the current local time
+pub fn current_period_start(now: u64, quota: &Quota) -> u64 {
+    let time = now as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return period_start(now, now % SECS_PER_DAY, 0, quota);
+    }
+    let secs_of_day = (tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec) as u64;
+    // tm_wday counts from Sunday
+    period_start(now, secs_of_day, ((tm.tm_wday + 6) % 7) as u32, quota)
+}
+
+pub fn load() -> Usage {
+    usage_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(usage: &Usage) {
+    let Some(path) = usage_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(usage) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+fn usage_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("cosmic-hotspot").join("usage.json"))
+}
@@ -0,0 +1,79 @@
+//! Wake-on-LAN for hotspot clients.
+//!
+//! A magic packet is 6 bytes of 0xff followed by the target MAC address 16
+//! times. It's sent as a UDP broadcast on the hotspot subnet, from the
+//! gateway address so it leaves through the hotspot interface; no privileges
+//! are needed. Devices that were seen on the hotspot are remembered by MAC
+//! address, so one that has gone to sleep and dropped off the client list can
+//! still be woken.
+
+use std::net::{Ipv4Addr, UdpSocket};
+
+use crate::hotspot;
+
+/// The usual "discard" port WoL tools send to
+pub const PORT: u16 = 9;
+/// How many devices are remembered; the least recently joined go first
+pub const KNOWN_CLIENTS_LIMIT: usize = 32;
+
+/// Parse "aa:bb:cc:dd:ee:ff" (or with dashes)
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], String> {
+    let invalid = || format!("Invalid MAC address '{mac}'");
+    let parts: Vec<&str> = mac.trim().split([':', '-']).collect();
+    if parts.len() != 6 {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 6];
+    for (byte, part) in bytes.iter_mut().zip(parts) {
+        if part.len() != 2 {
+            return Err(invalid());
+        }
+        *byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+pub fn magic_packet(mac: [u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    packet
+}
+
+/// The broadcast address of the subnet a gateway like "192.168.44.1/24" is in
+pub fn broadcast_address(gateway_ip: &str) -> Option<Ipv4Addr> {
+    let (gateway, prefix) = hotspot::parse_gateway_ip(gateway_ip)?;
+    let host_mask = u32::MAX.checked_shr(prefix).unwrap_or(0);
+    Some(Ipv4Addr::from(u32::from(gateway) | host_mask))
+}
+
+/// Send a magic packet for `mac` on the hotspot subnet
+pub fn wake(mac: &str, gateway_ip: &str) -> Result<(), String> {
+    let packet = magic_packet(parse_mac(mac)?);
+    let (gateway, _) = hotspot::parse_gateway_ip(gateway_ip)
+        .ok_or_else(|| format!("Invalid gateway IP '{gateway_ip}'"))?;
+    let broadcast = broadcast_address(gateway_ip).unwrap_or(Ipv4Addr::BROADCAST);
+    let socket = UdpSocket::bind((gateway, 0))
+        .map_err(|e| format!("Can't send from {gateway} (is the hotspot running?): {e}"))?;
+    socket.set_broadcast(true).map_err(|e| e.to_string())?;
+    socket
+        .send_to(&packet, (broadcast, PORT))
+        .map_err(|e| format!("Failed to send the wake-up packet: {e}"))?;
+    Ok(())
+}
+
+/// Add newly seen MAC addresses to `known`, most recent last, keeping at
+/// most `KNOWN_CLIENTS_LIMIT`. Returns whether anything was added.
+pub fn remember<'a>(known: &mut Vec<String>, macs: impl IntoIterator<Item = &'a str>) -> bool {
+    let mut added = false;
+    for mac in macs {
+        if !known.iter().any(|k| k.eq_ignore_ascii_case(mac)) {
+            known.push(mac.to_lowercase());
+            added = true;
+        }
+    }
+    let excess = known.len().saturating_sub(KNOWN_CLIENTS_LIMIT);
+    known.drain(..excess);
+    added
+}
@@ -0,0 +1,46 @@
+//! NetworkManager's journal kept per start attempt.
+
+use cosmic_hotspot::activation_log;
+use cosmic_hotspot::run_state::{ActivationLog, RunState};
+
+#[test]
+fn keeps_the_last_lines() {
+    let log = "Oct 16 10:00:01.000 NetworkManager[812]: <info> device (wlan0): Activation: starting\n\
+               \n\
+               Oct 16 10:00:02.000 NetworkManager[812]: <warn> device (wlan0): Activation: failed\n\
+               Oct 16 10:00:02.100 wpa_supplicant[640]: wlan0: Failed to start AP\n";
+    assert_eq!(
+        activation_log::last_lines(log, 2),
+        [
+            "Oct 16 10:00:02.000 NetworkManager[812]: <warn> device (wlan0): Activation: failed",
+            "Oct 16 10:00:02.100 wpa_supplicant[640]: wlan0: Failed to start AP",
+        ]
+    );
+    // Blank lines don't count
+    assert_eq!(activation_log::last_lines(log, 10).len(), 3);
+    assert!(activation_log::last_lines("", 10).is_empty());
+}
+
+#[test]
+fn details_show_only_a_failed_attempt() {
+    let lines = vec!["wlan0: Failed to start AP".to_string()];
+    let failed = RunState {
+        activation_log: Some(ActivationLog { at: 1_000, succeeded: false, lines: lines.clone() }),
+        ..RunState::default()
+    };
+    assert_eq!(failed.failed_activation_log(), Some(lines.as_slice()));
+
+    let started = RunState {
+        activation_log: Some(ActivationLog { at: 1_000, succeeded: true, lines }),
+        ..RunState::default()
+    };
+    assert_eq!(started.failed_activation_log(), None);
+    assert_eq!(RunState::default().failed_activation_log(), None);
+}
+
+#[test]
+fn older_run_state_files_still_load() {
+    let json = r#"{"started_at":1000,"last_error":null}"#;
+    let state: RunState = serde_json::from_str(json).unwrap();
+    assert_eq!(state.activation_log, None);
+}
@@ -0,0 +1,135 @@
+//! Tests for AP+client concurrency detection and the adapter details parsed
+//! from `iw phy` output, and for matching the autostart adapter id.
+
+use cosmic_hotspot::adapters::{
+    format_channels, is_adapter_id, matches_adapter_id, parse_bands, parse_max_ap_clients,
+    supports_concurrent_ap_sta,
+};
+
+const INTEL_AX200: &str = "\
+Wiphy phy0
+\tmax # scan SSIDs: 20
+\tvalid interface combinations:
+\t\t * #{ managed } <= 1, #{ AP, P2P-client, P2P-GO } <= 1, #{ P2P-device } <= 1,
+\t\t   total <= 3, #channels <= 2
+\tHT Capability overrides:
+\t\t * MCS: ff ff ff ff ff ff ff ff ff ff
+";
+
+const STA_ONLY: &str = "\
+Wiphy phy1
+\tvalid interface combinations:
+\t\t * #{ managed } <= 1, #{ P2P-client, P2P-GO } <= 1,
+\t\t   total <= 2, #channels <= 1
+";
+
+const SHARED_GROUP: &str = "\
+Wiphy phy2
+\tvalid interface combinations:
+\t\t * #{ managed, AP } <= 2, total <= 2, #channels <= 1
+";
+
+const SHARED_GROUP_SINGLE: &str = "\
+Wiphy phy3
+\tvalid interface combinations:
+\t\t * #{ managed, AP, mesh point } <= 1, total <= 1, #channels <= 1
+";
+
+const SECOND_COMBINATION: &str = "\
+Wiphy phy4
+\tvalid interface combinations:
+\t\t * #{ IBSS } <= 1, total <= 1, #channels <= 1
+\t\t * #{ managed } <= 2, #{ AP } <= 1,
+\t\t   total <= 3, #channels <= 1, STA/AP BI must match
+\tSupported extended features:
+\t\t* [ VHT_IBSS ]: VHT-IBSS
+";
+
+#[test]
+fn separate_groups_allow_concurrency() {
+    assert!(supports_concurrent_ap_sta(INTEL_AX200));
+}
+
+#[test]
+fn no_ap_group_means_no_concurrency() {
+    assert!(!supports_concurrent_ap_sta(STA_ONLY));
+}
+
+#[test]
+fn shared_group_needs_room_for_both() {
+    assert!(supports_concurrent_ap_sta(SHARED_GROUP));
+    assert!(!supports_concurrent_ap_sta(SHARED_GROUP_SINGLE));
+}
+
+#[test]
+fn any_combination_is_enough() {
+    assert!(supports_concurrent_ap_sta(SECOND_COMBINATION));
+}
+
+#[test]
+fn missing_section_means_unknown() {
+    assert!(!supports_concurrent_ap_sta("Wiphy phy0\n\tmax # scan SSIDs: 4\n"));
+    assert!(!supports_concurrent_ap_sta(""));
+}
+
+#[test]
+fn adapter_ids_are_macs_or_usb_ids() {
+    assert!(is_adapter_id("00:c0:ca:12:34:56"));
+    assert!(is_adapter_id("0e8d:7612"));
+    assert!(is_adapter_id(" 0E8D:7612 "));
+    assert!(!is_adapter_id("wlan0"));
+    assert!(!is_adapter_id("00:c0:ca:12:34"));
+    assert!(!is_adapter_id("0e8d:761"));
+    assert!(!is_adapter_id(""));
+}
+
+#[test]
+fn adapter_id_matches_mac_or_usb_id() {
+    assert!(matches_adapter_id("00:C0:CA:12:34:56", "00:c0:ca:12:34:56\n", None));
+    assert!(matches_adapter_id("0e8d:7612", "00:c0:ca:12:34:56", Some("0e8d:7612")));
+    assert!(!matches_adapter_id("0e8d:7612", "00:c0:ca:12:34:56", None));
+    assert!(!matches_adapter_id("148f:5370", "00:c0:ca:12:34:56", Some("0e8d:7612")));
+}
+
+const BANDS: &str = "\
+Wiphy phy0
+\tBand 1:
+\t\tFrequencies:
+\t\t\t* 2412.0 MHz [1] (22.0 dBm)
+\t\t\t* 2417.0 MHz [2] (22.0 dBm)
+\t\t\t* 2422.0 MHz [3] (22.0 dBm)
+\t\t\t* 2467.0 MHz [12] (22.0 dBm) (no IR)
+\t\t\t* 2484.0 MHz [14] (disabled)
+\tBand 2:
+\t\tFrequencies:
+\t\t\t* 5180 MHz [36] (22.0 dBm)
+\t\t\t* 5260 MHz [52] (22.0 dBm) (no IR, radar detection)
+\tMaximum associated stations in AP mode: 32
+";
+
+#[test]
+fn bands_and_channels_from_phy_info() {
+    let bands = parse_bands(BANDS);
+    assert_eq!(bands.len(), 2);
+    assert_eq!(bands[0].name, "2.4 GHz");
+    assert_eq!(bands[0].ap_channels, [1, 2, 3]);
+    assert_eq!(bands[0].client_only_channels, [12]);
+    assert_eq!(bands[1].name, "5 GHz");
+    assert_eq!(bands[1].ap_channels, [36]);
+    assert_eq!(bands[1].client_only_channels, [52]);
+    assert!(parse_bands(INTEL_AX200).is_empty());
+}
+
+#[test]
+fn max_ap_clients_when_reported() {
+    assert_eq!(parse_max_ap_clients(BANDS), Some(32));
+    assert_eq!(parse_max_ap_clients(INTEL_AX200), None);
+}
+
+#[test]
+fn channel_runs_are_collapsed() {
+    assert_eq!(format_channels(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]), "1-11");
+    assert_eq!(format_channels(&[36, 40, 44, 149, 150]), "36, 40, 44, 149, 150");
+    assert_eq!(format_channels(&[1, 2, 3, 12]), "1-3, 12");
+    assert_eq!(format_channels(&[]), "");
+}
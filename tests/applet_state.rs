@@ -0,0 +1,164 @@
+//! The popup's status logic, driven by scripted event sequences.
+
+use std::time::{Duration, Instant};
+
+use cosmic_hotspot::applet_state::{
+    AppletState, ATTENTION_FRAME, ATTENTION_TICKS, POLL_INTERVAL, PULSE_FRAME, TOGGLE_POLL_INTERVAL,
+};
+use cosmic_hotspot::clients::{ClientInfo, ClientState};
+use cosmic_hotspot::errors::{ErrorKind, HotspotError};
+use cosmic_hotspot::progress::Step;
+
+const HOLD: Duration = Duration::from_secs(5);
+
+fn client(ip: &str, state: ClientState) -> ClientInfo {
+    ClientInfo { ip: ip.to_string(), mac: None, vendor: None, hostname: None, radio: None, state, first_seen: None }
+}
+
+#[test]
+fn start_shows_progress_then_holds_the_result() {
+    let t0 = Instant::now();
+    let mut state = AppletState::new(false, true);
+    assert_eq!(state.status_message, "Inactive");
+
+    state.begin_toggle("Starting...");
+    assert_eq!(state.poll_interval(), TOGGLE_POLL_INTERVAL);
+    state.toggle_started();
+    state.progress(Step::Activating);
+    assert_eq!(state.status_message, "Activating...");
+
+    // Polls while toggling don't replace the progress
+    state.status_update(true, true, &[], &[], false, t0);
+    assert_eq!(state.status_message, "Activating...");
+
+    state.toggle_complete(Ok("Hotspot started".to_string()), HOLD, t0);
+    assert!(!state.is_toggling);
+    assert_eq!(state.poll_interval(), POLL_INTERVAL);
+
+    // Held until the hold runs out, then back to the plain status
+    state.status_update(true, true, &[], &[], false, t0 + HOLD / 2);
+    assert_eq!(state.status_message, "Hotspot started");
+    state.status_update(true, true, &[], &[], false, t0 + HOLD);
+    assert_eq!(state.status_message, "Active");
+}
+
+#[test]
+fn failure_names_the_step_and_can_be_dismissed() {
+    let t0 = Instant::now();
+    let mut state = AppletState::new(false, true);
+    state.toggle_started();
+    state.progress(Step::CreatingProfile);
+    state.toggle_complete(Err(HotspotError::new(ErrorKind::CreateFailed, "bad key-mgmt")), HOLD, t0);
+
+    assert!(state.status_message.starts_with("Creating hotspot profile failed: "));
+    assert_eq!(state.error_detail.as_deref(), Some("bad key-mgmt"));
+    assert!(state.status_held(t0));
+
+    state.dismiss();
+    assert!(!state.status_held(t0));
+    assert_eq!(state.error_detail, None);
+    assert_eq!(state.status_message, "Inactive");
+
+    // A new attempt starts without the old step
+    state.toggle_started();
+    assert_eq!(state.toggle_step, None);
+    assert_eq!(state.status_message, "Starting...");
+}
+
+#[test]
+fn restart_shows_stop_and_start_as_one_operation() {
+    let t0 = Instant::now();
+    let mut state = AppletState::new(true, true);
+
+    state.begin_restart("Restarting...");
+    state.toggle_started();
+    assert_eq!(state.status_message, "Restarting...");
+    state.progress(Step::Deactivating);
+    assert_eq!(state.status_message, "Restarting (stopping): Deactivating...");
+    state.progress(Step::Activating);
+    assert_eq!(state.status_message, "Restarting (starting): Activating...");
+
+    // Still toggling between the stop and the start
+    state.status_update(false, true, &[], &[], false, t0);
+    assert!(state.is_toggling);
+    assert_eq!(state.status_message, "Restarting (starting): Activating...");
+
+    state.toggle_complete(Ok("Restarted: Hotspot started".to_string()), HOLD, t0);
+    assert!(!state.restarting);
+    state.status_update(true, true, &[], &[], false, t0);
+
+    // A plain stop afterwards isn't shown as a restart
+    state.begin_toggle("Stopping...");
+    state.toggle_started();
+    assert_eq!(state.status_message, "Stopping...");
+}
+
+#[test]
+fn joining_client_triggers_attention_but_not_at_startup() {
+    let t0 = Instant::now();
+    let mut state = AppletState::new(true, true);
+    let phone = [client("192.168.44.2", ClientState::Active)];
+    let both = [client("192.168.44.2", ClientState::Active), client("192.168.44.3", ClientState::Active)];
+
+    // Clients already there when the applet starts
+    state.status_update(true, true, &[], &phone, false, t0);
+    assert_eq!(state.attention_ticks, 0);
+
+    state.status_update(true, true, &phone, &both, false, t0);
+    assert_eq!(state.attention_ticks, ATTENTION_TICKS);
+    assert_eq!(state.animation_interval("ripple", false), Some(ATTENTION_FRAME));
+    assert_eq!(state.animation_interval("filled", false), None);
+
+    for _ in 0..ATTENTION_TICKS {
+        state.animation_tick();
+    }
+    assert_eq!(state.attention_ticks, 0);
+    assert_eq!(state.animation_interval("ripple", false), Some(PULSE_FRAME));
+    assert_eq!(state.animation_interval("broadcast", false), None);
+}
+
+#[test]
+fn idle_client_coming_back_or_reduced_motion_stays_calm() {
+    let t0 = Instant::now();
+    let mut state = AppletState::new(true, true);
+    let idle = [client("192.168.44.2", ClientState::Idle)];
+    state.status_update(true, true, &[], &idle, false, t0);
+
+    // Same client waking up isn't a join
+    let awake = [client("192.168.44.2", ClientState::Active)];
+    state.status_update(true, true, &idle, &awake, false, t0);
+    assert_eq!(state.attention_ticks, 0);
+
+    let joined = [client("192.168.44.2", ClientState::Active), client("192.168.44.9", ClientState::Active)];
+    state.status_update(true, true, &awake, &joined, true, t0);
+    assert_eq!(state.attention_ticks, 0);
+    assert_eq!(state.animation_interval("ripple", true), None);
+}
+
+#[test]
+fn stopping_ends_the_animation() {
+    let t0 = Instant::now();
+    let mut state = AppletState::new(true, true);
+    state.status_update(true, true, &[], &[], false, t0);
+    let joined = [client("192.168.44.5", ClientState::Active)];
+    state.status_update(true, true, &[], &joined, false, t0);
+    assert!(state.attention_ticks > 0);
+
+    state.animation_tick();
+    assert_eq!(state.anim_frame, 1);
+    state.status_update(false, true, &joined, &[], false, t0);
+    assert_eq!(state.attention_ticks, 0);
+    assert_eq!(state.animation_interval("ripple", false), None);
+    // Frames don't advance while inactive
+    state.animation_tick();
+    assert_eq!(state.anim_frame, 1);
+    assert_eq!(state.status_message, "Inactive");
+}
+
+#[test]
+fn missing_adapter_is_the_idle_status() {
+    let mut state = AppletState::new(false, false);
+    assert_eq!(state.status_message, "No WiFi adapter found");
+    state.status_update(false, true, &[], &[], false, Instant::now());
+    assert_eq!(state.status_message, "Inactive");
+}
@@ -0,0 +1,30 @@
+//! Tests for counting failed join attempts from wpa_supplicant's log.
+
+use cosmic_hotspot::auth_log::{count_by_device, parse_failures};
+
+const LOG: &str = "\
+wlan0: AP-STA-CONNECTED 11:22:33:44:55:66
+wlan0: AP-STA-POSSIBLE-PSK-MISMATCH AA:BB:CC:DD:EE:FF
+wlan1: AP-STA-POSSIBLE-PSK-MISMATCH 99:99:99:99:99:99
+wlan0: CTRL-EVENT-EAP-STARTED aa:bb:cc:dd:ee:ff
+wlan0: AP-STA-POSSIBLE-PSK-MISMATCH aa:bb:cc:dd:ee:ff
+wlan0: AP-STA-POSSIBLE-PSK-MISMATCH 12:34:56:78:9a:bc
+";
+
+#[test]
+fn only_mismatches_on_the_interface_count() {
+    assert_eq!(
+        parse_failures(LOG, "wlan0"),
+        ["aa:bb:cc:dd:ee:ff", "aa:bb:cc:dd:ee:ff", "12:34:56:78:9a:bc"]
+    );
+    assert_eq!(parse_failures(LOG, "wlan2"), Vec::<String>::new());
+}
+
+#[test]
+fn attempts_are_grouped_per_device() {
+    let attempts = parse_failures(LOG, "wlan0");
+    assert_eq!(
+        count_by_device(&attempts),
+        [("aa:bb:cc:dd:ee:ff".to_string(), 2), ("12:34:56:78:9a:bc".to_string(), 1)]
+    );
+}
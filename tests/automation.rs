@@ -0,0 +1,94 @@
+//! Which profile plug-in autostart and schedules pick.
+
+use cosmic_hotspot::automation::{self, Battery};
+use cosmic_hotspot::config::{Config, Profile};
+
+const MON: usize = 0;
+const SAT: usize = 5;
+
+fn profile(name: &str, days: &str, from: &str, until: &str) -> Profile {
+    Profile {
+        name: name.to_string(),
+        schedule_days: days.to_string(),
+        schedule_from: from.to_string(),
+        schedule_until: until.to_string(),
+        ..Profile::default()
+    }
+}
+
+fn config(profiles: Vec<Profile>) -> Config {
+    Config { profiles, ..Config::default() }
+}
+
+fn at(hours: u32, minutes: u32) -> u32 {
+    hours * 60 + minutes
+}
+
+#[test]
+fn open_window_picks_its_profile() {
+    let config = config(vec![
+        profile("Home", "", "", ""),
+        profile("Work", "mon-fri", "09:00", "17:00"),
+    ]);
+    assert_eq!(automation::scheduled(&config, MON, at(10, 0)).map(|p| p.name.as_str()), Some("Work"));
+    assert_eq!(automation::scheduled(&config, MON, at(18, 0)), None);
+    assert_eq!(automation::scheduled(&config, SAT, at(10, 0)), None);
+}
+
+#[test]
+fn overlapping_windows_go_to_the_first_profile() {
+    let config = config(vec![
+        profile("Work", "mon-fri", "09:00", "17:00"),
+        profile("Lunch", "", "12:00", "13:00"),
+    ]);
+    assert_eq!(automation::scheduled(&config, MON, at(12, 30)).map(|p| p.name.as_str()), Some("Work"));
+    assert_eq!(automation::scheduled(&config, SAT, at(12, 30)).map(|p| p.name.as_str()), Some("Lunch"));
+
+    // Lunch's weekday windows fall inside Work's, so Work's opening is next
+    let next = automation::next(&config, MON, at(18, 0)).expect("something is scheduled");
+    assert_eq!((next.profile.name.as_str(), next.in_minutes), ("Work", at(15, 0)));
+}
+
+#[test]
+fn next_reports_the_soonest_window() {
+    let config = config(vec![
+        profile("Work", "mon-fri", "09:00", "17:00"),
+        profile("Evening", "", "19:00", "22:00"),
+    ]);
+    let next = automation::next(&config, MON, at(18, 0)).unwrap();
+    assert_eq!((next.profile.name.as_str(), next.in_minutes), ("Evening", 60));
+    assert_eq!(automation::summary(&config, MON, at(18, 0)).as_deref(), Some("Next scheduled: Evening in 1h 0m"));
+    assert_eq!(automation::summary(&config, MON, at(20, 0)).as_deref(), Some("Scheduled now: Evening"));
+}
+
+#[test]
+fn plug_in_start_prefers_the_scheduled_profile() {
+    let mut travel = profile("Travel", "", "", "");
+    travel.autostart = true;
+    let mut work = profile("Work", "mon-fri", "09:00", "17:00");
+    work.autostart = true;
+    let mut config = config(vec![travel, work]);
+
+    assert_eq!(automation::autostart_profile(&config, MON, at(10, 0)).map(|p| p.name.as_str()), Some("Work"));
+    assert_eq!(automation::autostart_profile(&config, SAT, at(10, 0)).map(|p| p.name.as_str()), Some("Travel"));
+
+    // Without schedules the popup names the plug-in start profile, once an
+    // adapter is set
+    config.profiles[1] = profile("Work", "", "", "");
+    assert_eq!(automation::summary(&config, MON, at(10, 0)), None);
+    config.autostart_adapter = "0e8d:7612".to_string();
+    assert_eq!(automation::summary(&config, MON, at(10, 0)).as_deref(), Some("Plug-in start uses Travel"));
+}
+
+#[test]
+fn battery_policy_only_applies_when_discharging() {
+    let mut p = Profile::default();
+    let low = Some(Battery { percent: 15, discharging: true });
+    assert!(!automation::battery_blocks(&p, low));
+
+    p.battery_min_percent = 20;
+    assert!(automation::battery_blocks(&p, low));
+    assert!(!automation::battery_blocks(&p, Some(Battery { percent: 15, discharging: false })));
+    assert!(!automation::battery_blocks(&p, Some(Battery { percent: 50, discharging: true })));
+    assert!(!automation::battery_blocks(&p, None));
+}
@@ -0,0 +1,51 @@
+//! Tests for the printable credentials card.
+
+use cosmic_hotspot::card::{default_path, pdf, svg};
+use cosmic_hotspot::qr::{modules, wifi_uri};
+
+#[test]
+fn svg_card_shows_escaped_credentials() {
+    let card = svg("Tom & Jerry's <Net>", "changeme123").unwrap();
+    assert!(card.starts_with("<svg"));
+    assert!(card.contains("Tom &amp; Jerry's &lt;Net&gt;"));
+    assert!(card.contains(">changeme123<"));
+}
+
+#[test]
+fn open_networks_say_so() {
+    assert!(svg("Guest", "").unwrap().contains(">(none)<"));
+}
+
+#[test]
+fn pdf_card_is_a_complete_document() {
+    let card = pdf("Cabin (upstairs)", "p\\ss").unwrap();
+    let text = String::from_utf8_lossy(&card);
+    assert!(text.starts_with("%PDF-1.4\n"));
+    assert!(text.ends_with("%%EOF\n"));
+    assert!(text.contains(r"(Cabin \(upstairs\)) Tj"));
+    assert!(text.contains(r"(p\\ss) Tj"));
+
+    // startxref points at the xref table
+    let offset: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+    assert!(text[offset..].starts_with("xref\n"));
+}
+
+#[test]
+fn pdf_card_replaces_characters_outside_latin1() {
+    let card = pdf("Café 📶", "changeme123").unwrap();
+    // é is one byte in the PDF's Latin-1 encoding
+    assert!(card.windows(8).any(|w| w == b"(Caf\xe9 ?)"));
+}
+
+#[test]
+fn qr_modules_are_square() {
+    let (width, dark) = modules(&wifi_uri("CosmicHotspot", "changeme123")).unwrap();
+    assert_eq!(dark.len(), width * width);
+    assert!(dark.contains(&true) && dark.contains(&false));
+}
+
+#[test]
+fn default_path_is_named_after_the_network() {
+    let path = default_path("Holiday/House", "pdf");
+    assert_eq!(path.file_name().unwrap(), "Holiday_House WiFi card.pdf");
+}
@@ -0,0 +1,61 @@
+//! Tests for channel validation and per-channel congestion.
+
+use cosmic_hotspot::channels::{is_valid, least_busy, spectrum_svg, usage, NearbyAp};
+use cosmic_hotspot::nm_dbus::frequency_channel;
+
+fn ap(channel: u32, signal: u32) -> NearbyAp {
+    NearbyAp { channel, signal }
+}
+
+#[test]
+fn channels_belong_to_their_band() {
+    assert!(is_valid("bg", 0));
+    assert!(is_valid("bg", 6));
+    assert!(!is_valid("bg", 36));
+    assert!(is_valid("a", 36));
+    assert!(!is_valid("a", 6));
+    assert!(!is_valid("a", 37));
+}
+
+#[test]
+fn usage_groups_access_points_per_channel() {
+    let aps = [ap(1, 40), ap(6, 80), ap(6, 30), ap(36, 90)];
+    let usage = usage("bg", &aps);
+    assert_eq!(usage.len(), 13);
+    assert_eq!((usage[0].access_points, usage[0].strongest), (1, 40));
+    assert_eq!((usage[5].access_points, usage[5].strongest), (2, 80));
+    assert_eq!((usage[1].access_points, usage[1].strongest), (0, 0));
+    // 5 GHz access points don't count on 2.4 GHz
+    assert_eq!(usage.iter().map(|u| u.access_points).sum::<usize>(), 3);
+}
+
+#[test]
+fn least_busy_prefers_empty_then_weak_channels() {
+    let busy = usage("bg", &[ap(1, 40), ap(6, 80)]);
+    assert_eq!(least_busy(&busy), Some(2));
+
+    let all: Vec<NearbyAp> = (1..=13).map(|c| ap(c, 90 - c)).collect();
+    assert_eq!(least_busy(&usage("bg", &all)), Some(13));
+}
+
+#[test]
+fn spectrum_has_a_bar_per_channel() {
+    let svg = spectrum_svg(&usage("bg", &[ap(6, 80)]), 6, "rgb(0,0,0)");
+    assert!(svg.starts_with("<svg"));
+    // 13 bars plus the marker under the selected channel
+    assert_eq!(svg.matches("<rect").count(), 14);
+}
+
+#[test]
+fn scan_frequencies_map_to_channels() {
+    assert_eq!(frequency_channel(2412), 1);
+    assert_eq!(frequency_channel(2472), 13);
+    assert_eq!(frequency_channel(2484), 14);
+    assert_eq!(frequency_channel(5180), 36);
+    assert_eq!(frequency_channel(5825), 165);
+    assert_eq!(frequency_channel(5955), 1);
+    assert_eq!(frequency_channel(6115), 33);
+    // Not a WiFi channel
+    assert_eq!(frequency_channel(0), 0);
+    assert_eq!(frequency_channel(2500), 0);
+}
@@ -0,0 +1,125 @@
+//! Client bookkeeping: first-seen times, the D-Bus wire format and list order.
+
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use cosmic_hotspot::clients::{self, Client, ClientInfo, ClientSort, ClientState, ClientTracker, IdleTimer};
+use cosmic_hotspot::stations::Radio;
+
+fn present(ip: &str) -> Client {
+    Client::new(ip, None)
+}
+
+#[test]
+fn first_seen_is_kept_across_polls() {
+    let mut tracker = ClientTracker::new();
+    let grace = Duration::from_secs(60);
+    let first = tracker.update(&[present("192.168.44.10")], grace);
+    let first_seen = first[0].first_seen;
+    assert!(first_seen.is_some());
+
+    std::thread::sleep(Duration::from_millis(10));
+    let later = tracker.update(&[present("192.168.44.10"), present("192.168.44.11")], grace);
+    assert_eq!(later[0].first_seen, first_seen);
+    assert!(later[1].first_seen > first_seen);
+
+    // Idle clients keep theirs too; a client that left and came back starts over
+    let idle = tracker.update(&[present("192.168.44.11")], grace);
+    assert_eq!((idle[0].state, idle[0].first_seen), (ClientState::Idle, first_seen));
+    tracker.clear();
+    let rejoined = tracker.update(&[present("192.168.44.10")], grace);
+    assert!(rejoined[0].first_seen > first_seen);
+}
+
+#[test]
+fn round_trips_through_the_wire_format() {
+    let client = ClientInfo {
+        ip: "192.168.44.17".to_string(),
+        mac: Some("2c:26:17:c0:a8:11".to_string()),
+        // Not sent over the wire, but found again from the MAC
+        vendor: Some("Meta"),
+        hostname: Some("quest".to_string()),
+        // Not sent over the wire
+        radio: None,
+        state: ClientState::Idle,
+        first_seen: Some(UNIX_EPOCH + Duration::from_secs(1_773_650_000)),
+    };
+    assert_eq!(ClientInfo::from_wire(client.to_wire()), client);
+
+    let unknown = ClientInfo { mac: None, vendor: None, hostname: None, state: ClientState::Active, first_seen: None, ..client };
+    assert_eq!(
+        unknown.to_wire(),
+        ("192.168.44.17".to_string(), String::new(), String::new(), "active".to_string(), 0)
+    );
+    assert_eq!(ClientInfo::from_wire(unknown.to_wire()), unknown);
+}
+
+#[test]
+fn connected_time_needs_a_first_seen() {
+    let client = ClientInfo {
+        ip: "192.168.44.17".to_string(),
+        mac: None,
+        vendor: None,
+        hostname: None,
+        radio: None,
+        state: ClientState::Active,
+        first_seen: Some(UNIX_EPOCH + Duration::from_secs(1_000)),
+    };
+    assert_eq!(client.connected_for(UNIX_EPOCH + Duration::from_secs(1_600)), Some(Duration::from_secs(600)));
+    assert_eq!(ClientInfo { first_seen: None, ..client }.connected_for(UNIX_EPOCH), None);
+}
+
+#[test]
+fn idle_timer_fires_after_the_timeout_without_clients() {
+    let timeout = Duration::from_secs(15 * 60);
+    let start = Instant::now();
+    let mut idle = IdleTimer::default();
+
+    assert!(!idle.observe(0, timeout, start));
+    assert!(!idle.observe(0, timeout, start + Duration::from_secs(14 * 60)));
+    assert!(idle.observe(0, timeout, start + timeout));
+    // Counting starts afresh after firing
+    assert!(!idle.observe(0, timeout, start + timeout + Duration::from_secs(1)));
+}
+
+#[test]
+fn idle_timer_restarts_when_a_client_joins() {
+    let timeout = Duration::from_secs(60);
+    let start = Instant::now();
+    let mut idle = IdleTimer::default();
+
+    assert!(!idle.observe(0, timeout, start));
+    assert!(!idle.observe(1, timeout, start + Duration::from_secs(50)));
+    assert!(!idle.observe(0, timeout, start + Duration::from_secs(70)));
+    assert!(idle.observe(0, timeout, start + Duration::from_secs(130)));
+}
+
+#[test]
+fn sorts_by_name_and_signal_with_unknowns_last() {
+    let client = |ip: &str, hostname: Option<&str>, signal_dbm: Option<i32>| ClientInfo {
+        ip: ip.to_string(),
+        mac: None,
+        vendor: None,
+        hostname: hostname.map(str::to_string),
+        radio: Some(Radio { frequency_mhz: None, tx_rate_mbps: None, generation: None, signal_dbm }),
+        state: ClientState::Active,
+        first_seen: None,
+    };
+    let list = vec![
+        client("192.168.44.10", None, Some(-50)),
+        client("192.168.44.11", Some("quest"), None),
+        client("192.168.44.12", Some("Pixel-7"), Some(-70)),
+    ];
+    let order = |sort| {
+        clients::filter_and_sort(&list, "", sort).iter().map(|c| c.ip.as_str()).collect::<Vec<_>>()
+    };
+    assert_eq!(order(ClientSort::Name), ["192.168.44.12", "192.168.44.11", "192.168.44.10"]);
+    assert_eq!(order(ClientSort::Signal), ["192.168.44.10", "192.168.44.12", "192.168.44.11"]);
+
+    // The popup's sort button goes through every order and back
+    let mut sort = ClientSort::Address;
+    for _ in 0..4 {
+        sort = sort.next();
+        assert_eq!(ClientSort::from_config(sort.as_config()), sort);
+    }
+    assert_eq!(sort, ClientSort::Address);
+}
@@ -0,0 +1,28 @@
+//! Copied passwords are offered with the clipboard managers' history hint.
+
+use cosmic::iced::clipboard::mime::AsMimeTypes;
+use cosmic_hotspot::clipboard::{Secret, PASSWORD_HINT_MIME};
+
+fn secret(sensitive: bool) -> Secret {
+    Secret { text: "SSID: Home\nPassword: hunter22".to_string(), sensitive }
+}
+
+#[test]
+fn sensitive_copies_offer_the_password_hint() {
+    let copy = secret(true);
+    assert!(copy.available().iter().any(|m| m == PASSWORD_HINT_MIME));
+    assert_eq!(copy.as_bytes(PASSWORD_HINT_MIME).as_deref(), Some(b"secret".as_slice()));
+    assert_eq!(
+        copy.as_bytes("text/plain;charset=utf-8").as_deref(),
+        Some(b"SSID: Home\nPassword: hunter22".as_slice())
+    );
+    assert_eq!(copy.as_bytes("image/png"), None);
+}
+
+#[test]
+fn plain_copies_are_only_text() {
+    let copy = secret(false);
+    assert!(!copy.available().iter().any(|m| m == PASSWORD_HINT_MIME));
+    assert_eq!(copy.as_bytes(PASSWORD_HINT_MIME), None);
+    assert!(copy.as_bytes("text/plain").is_some());
+}
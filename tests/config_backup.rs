@@ -0,0 +1,56 @@
+//! Reset to defaults keeps a backup that can be restored.
+
+use cosmic_hotspot::config::Config;
+
+#[test]
+fn reset_backs_up_and_restore_undoes_it() {
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-config-backup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", &dir);
+    std::env::set_var("XDG_DATA_HOME", &dir);
+    // No keyring: passwords are sealed with the file key
+    std::env::set_var("DBUS_SESSION_BUS_ADDRESS", "unix:path=/nonexistent/bus");
+
+    // Nothing saved yet, so nothing to back up
+    assert_eq!(Config::reset_to_defaults(), Ok(None));
+    assert!(Config::backups().is_empty());
+
+    let custom = Config {
+        ssid: "Workshop".to_string(),
+        channel: 11,
+        password: "workshop pass".to_string(),
+        repeater_password: "upstream pass".to_string(),
+        ..Config::default()
+    };
+    custom.save().unwrap();
+    let backup = Config::reset_to_defaults().unwrap().expect("backed up");
+    let reset = Config::load();
+    assert_eq!(reset.ssid, Config::default().ssid);
+    assert_eq!((reset.password, reset.repeater_password), (Config::default().password, String::new()));
+    assert_eq!(Config::backups().last(), Some(&backup));
+    // The backup carries the passwords themselves, never in plaintext
+    let content = std::fs::read_to_string(&backup).unwrap();
+    assert!(!content.contains("workshop pass") && !content.contains("keyring:"));
+
+    assert_eq!(Config::restore_backup(None), Ok(backup.clone()));
+    let restored = Config::load();
+    assert_eq!((restored.ssid.as_str(), restored.channel), ("Workshop", 11));
+    assert_eq!(
+        (restored.password.as_str(), restored.repeater_password.as_str()),
+        ("workshop pass", "upstream pass")
+    );
+
+    // Only the newest backups are kept
+    for _ in 0..7 {
+        Config::backup().unwrap();
+    }
+    assert_eq!(Config::backups().len(), 5);
+
+    // A file that wouldn't load is refused
+    let broken = dir.join("broken.json");
+    std::fs::write(&broken, "{ not json").unwrap();
+    assert!(Config::restore_backup(Some(&broken)).is_err());
+    assert_eq!(Config::load().ssid, "Workshop");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,47 @@
+//! Extra DHCP options handed to NetworkManager's shared dnsmasq.
+
+use cosmic_hotspot::config::Profile;
+use cosmic_hotspot::nat::{self, NatRequest};
+
+#[test]
+fn profile_options_are_split_and_validated() {
+    let mut profile = Profile {
+        dhcp_options: "option:ntp-server,192.168.44.1; ;42,10.0.0.1;option:classless-static-route,10.20.0.0/16,192.168.44.2"
+            .to_string(),
+        ..Profile::default()
+    };
+    assert_eq!(
+        profile.dhcp_options().unwrap(),
+        [
+            "option:ntp-server,192.168.44.1",
+            "42,10.0.0.1",
+            "option:classless-static-route,10.20.0.0/16,192.168.44.2",
+        ]
+    );
+    assert!(profile.validate().is_ok());
+
+    // Nothing that could add another dnsmasq directive gets through
+    for bad in ["ntp-server,192.168.44.1", "option:ntp-server,", "0,1.2.3.4", "option:x,a\nserver=8.8.8.8", "option:x,a b"] {
+        profile.dhcp_options = bad.to_string();
+        assert!(profile.validate().is_err(), "{bad:?} should be rejected");
+    }
+    assert!(Profile::default().dhcp_options().unwrap().is_empty());
+}
+
+#[test]
+fn dhcp_options_request_writes_the_dnsmasq_file() {
+    let options = vec!["option:domain-name,lab.local".to_string()];
+    let request = NatRequest::DhcpOptions { options: options.clone() };
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    let rules = request.rules();
+    let write = &rules.last().unwrap().command;
+    assert_eq!(write[0], "sh");
+    assert!(write.contains(&nat::DHCP_OPTIONS_CONF.to_string()));
+    assert_eq!(write.last().unwrap(), "option:domain-name,lab.local");
+    assert_eq!(nat::dhcp_options_conf(&options), "dhcp-option=option:domain-name,lab.local\n");
+
+    let clear = NatRequest::DhcpOptions { options: Vec::new() };
+    assert_eq!(NatRequest::parse(&clear.to_args()).unwrap(), clear);
+    assert_eq!(clear.rules()[0].command, ["rm", "-f", nat::DHCP_OPTIONS_CONF]);
+    assert!(NatRequest::parse(&["dhcp-options".to_string(), "option:x,$(reboot)".to_string()]).is_err());
+}
@@ -0,0 +1,64 @@
+//! Tests for reading client DNS lookups from dnsmasq's query log.
+
+use cosmic_hotspot::dns_log::{by_client, parse_queries, ClientLookups, Lookup};
+use cosmic_hotspot::nat::NatRequest;
+
+const LOG: &str = "\
+started, version 2.90 cachesize 150
+query[A] api.SmartThings.com from 10.42.0.23
+forwarded api.smartthings.com to 192.168.1.1
+reply api.smartthings.com is 52.1.2.3
+query[AAAA] api.smartthings.com from 10.42.0.23
+query[A] time.cloudflare.com from 10.42.0.57
+DHCPACK(wlan0) 10.42.0.57 aa:bb:cc:dd:ee:ff
+query[HTTPS] www.wikipedia.org from 10.42.0.23
+query[A] bogus line
+";
+
+fn lookup(client: &str, name: &str) -> Lookup {
+    Lookup { client: client.to_string(), name: name.to_string() }
+}
+
+#[test]
+fn only_queries_are_parsed() {
+    assert_eq!(
+        parse_queries(LOG),
+        [
+            lookup("10.42.0.23", "api.smartthings.com"),
+            lookup("10.42.0.23", "api.smartthings.com"),
+            lookup("10.42.0.57", "time.cloudflare.com"),
+            lookup("10.42.0.23", "www.wikipedia.org"),
+        ]
+    );
+}
+
+#[test]
+fn lookups_are_grouped_per_client_most_recent_first() {
+    let groups = by_client(&parse_queries(LOG), 8);
+    assert_eq!(
+        groups,
+        [
+            ClientLookups {
+                client: "10.42.0.23".to_string(),
+                names: vec![
+                    ("www.wikipedia.org".to_string(), 1),
+                    ("api.smartthings.com".to_string(), 2),
+                ],
+            },
+            ClientLookups {
+                client: "10.42.0.57".to_string(),
+                names: vec![("time.cloudflare.com".to_string(), 1)],
+            },
+        ]
+    );
+    assert_eq!(by_client(&parse_queries(LOG), 1)[0].names.len(), 1);
+}
+
+#[test]
+fn query_log_request_round_trips() {
+    for enable in [true, false] {
+        let request = NatRequest::QueryLog { enable };
+        assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    }
+    assert!(NatRequest::parse(&["query-log".to_string(), "maybe".to_string()]).is_err());
+}
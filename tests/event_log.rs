@@ -0,0 +1,53 @@
+//! The structured event log behind `--logs` and `GetRecentLogs`.
+
+use cosmic_hotspot::event_log::{self, Entry, Level, MAX_BYTES};
+
+#[test]
+fn parses_entries_and_skips_damaged_lines() {
+    let log = r#"{"at":1700000000,"level":"info","message":"Hotspot started"}
+not json
+{"at":1700000060,"level":"error","message":"Start failed: no secrets"}
+{"at":1700000120,"level":"warn"#;
+    assert_eq!(
+        event_log::parse(log),
+        [
+            Entry { at: 1_700_000_000, level: Level::Info, message: "Hotspot started".to_string() },
+            Entry { at: 1_700_000_060, level: Level::Error, message: "Start failed: no secrets".to_string() },
+        ]
+    );
+}
+
+#[test]
+fn records_follows_and_rotates() {
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-event-log-{}", std::process::id()));
+    std::env::set_var("XDG_STATE_HOME", &dir);
+
+    assert!(event_log::recent(10).is_empty());
+    event_log::record(Level::Info, "Hotspot started");
+    let (_, offset) = event_log::read_from(0);
+    event_log::record(Level::Warning, "Connection sharing repaired");
+
+    // Following picks up only what was added since
+    let (new, _) = event_log::read_from(offset);
+    assert_eq!(new.len(), 1);
+    assert_eq!(new[0].level, Level::Warning);
+
+    let recent = event_log::recent(1);
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0].message, "Connection sharing repaired");
+    assert!(recent[0].format().contains("[warning] Connection sharing repaired"));
+
+    // Past the size limit the file rotates, and older entries stay readable
+    let filler = "x".repeat(1000);
+    for _ in 0..=MAX_BYTES / 1000 {
+        event_log::record(Level::Info, filler.as_str());
+    }
+    event_log::record(Level::Info, "Hotspot stopped");
+    let (after_rotation, _) = event_log::read_from(offset + MAX_BYTES);
+    assert!(after_rotation.len() < (MAX_BYTES / 1000) as usize);
+    let all = event_log::recent(usize::MAX);
+    assert_eq!(all.first().map(|e| e.message.as_str()), Some("Hotspot started"));
+    assert_eq!(all.last().map(|e| e.message.as_str()), Some("Hotspot stopped"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
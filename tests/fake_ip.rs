@@ -0,0 +1,137 @@
+//! Integration tests for the `ip` command construction and output parsing in
+//! `hotspot`, run against scripted fake binaries on PATH.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use cosmic_hotspot::config::{Config, Profile};
+use cosmic_hotspot::hotspot;
+
+/// PATH is process-global, so tests using fake binaries run one at a time
+static PATH_LOCK: Mutex<()> = Mutex::new(());
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// Marks the end of one invocation's arguments in a call log
+const CALL_END: &str = "--END-OF-CALL--";
+
+/// A temporary directory of fake executables prepended to PATH. It also
+/// serves as XDG_RUNTIME_DIR so state files stay out of the real session.
+struct FakeBin {
+    dir: PathBuf,
+    original_path: String,
+    original_runtime_dir: Option<std::ffi::OsString>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl FakeBin {
+    fn new() -> Self {
+        let lock = PATH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!(
+            "cosmic-hotspot-test-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("create fake bin dir");
+
+        let original_path = std::env::var("PATH").unwrap_or_default();
+        std::env::set_var("PATH", format!("{}:{original_path}", dir.display()));
+        let original_runtime_dir = std::env::var_os("XDG_RUNTIME_DIR");
+        std::env::set_var("XDG_RUNTIME_DIR", &dir);
+        Self { dir, original_path, original_runtime_dir, _lock: lock }
+    }
+
+    /// Install a fake command: logs its argv, then runs `body` as sh
+    fn command(&self, name: &str, body: &str) -> &Self {
+        let log = self.log_path(name);
+        let script = format!(
+            "#!/bin/sh\n\
+             for arg in \"$@\"; do printf '%s\\n' \"$arg\" >> '{log}'; done\n\
+             echo '{CALL_END}' >> '{log}'\n\
+             {body}\n",
+            log = log.display(),
+        );
+        let path = self.dir.join(name);
+        std::fs::write(&path, script).expect("write fake command");
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod fake command");
+        self
+    }
+
+    /// Every recorded invocation of `name`, as argument lists
+    fn calls(&self, name: &str) -> Vec<Vec<String>> {
+        let log = std::fs::read_to_string(self.log_path(name)).unwrap_or_default();
+        let mut calls = Vec::new();
+        let mut current = Vec::new();
+        for line in log.lines() {
+            if line == CALL_END {
+                calls.push(std::mem::take(&mut current));
+            } else {
+                current.push(line.to_string());
+            }
+        }
+        calls
+    }
+
+    fn log_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.log"))
+    }
+}
+
+impl Drop for FakeBin {
+    fn drop(&mut self) {
+        std::env::set_var("PATH", &self.original_path);
+        match &self.original_runtime_dir {
+            Some(dir) => std::env::set_var("XDG_RUNTIME_DIR", dir),
+            None => std::env::remove_var("XDG_RUNTIME_DIR"),
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A config that never reaches the privileged helper or real interfaces
+fn test_config() -> Config {
+    Config {
+        hotspot_interface: "fakewlan9".to_string(),
+        internet_interface: "fakeeth9".to_string(),
+        profiles: vec![Profile {
+            nat_strategy: "nm-shared".to_string(),
+            ..Profile::default()
+        }],
+        verify_after_start: false,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn clients_skip_failed_and_malformed_neighbours() {
+    let fake = FakeBin::new();
+    fake.command(
+        "ip",
+        r#"cat <<'OUT'
+192.168.44.23 lladdr aa:bb:cc:dd:ee:01 REACHABLE
+192.168.44.24 lladdr aa:bb:cc:dd:ee:02 STALE
+192.168.44.25  FAILED
+192.168.44.26 lladdr aa:bb:cc:dd:ee:03 FAILED
+truncated
+fe80::1 lladdr aa:bb:cc:dd:ee:04 router DELAY
+
+OUT"#,
+    );
+
+    let clients = hotspot::get_connected_clients(&test_config());
+    let ips: Vec<&str> = clients.iter().map(|c| c.ip.as_str()).collect();
+    assert_eq!(ips, ["192.168.44.23", "192.168.44.24", "fe80::1"]);
+    assert_eq!(clients[0].mac.as_deref(), Some("aa:bb:cc:dd:ee:01"));
+    assert_eq!(fake.calls("ip"), [["neigh", "show", "dev", "fakewlan9"]]);
+}
+
+#[test]
+fn clients_empty_when_ip_fails() {
+    let fake = FakeBin::new();
+    fake.command("ip", "echo 'Cannot find device \"fakewlan9\"' >&2; exit 1");
+
+    // Falls back to /proc/net/arp, which has no entries for the fake interface
+    assert!(hotspot::get_connected_clients(&test_config()).is_empty());
+}
@@ -0,0 +1,1054 @@
+//! Start/stop sequencing in `hotspot`, and the repeater, scan and saved
+//! network lookups, against a scripted NetworkManager served on a private
+//! bus that stands in for the system bus.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use cosmic_hotspot::config::{Config, Profile};
+use cosmic_hotspot::errors::ErrorKind;
+use cosmic_hotspot::{adapters, channels, hotspot, nm_dbus, repeater, saved_networks};
+use zbus::object_server::ObjectServer;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{fdo, interface};
+
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/1";
+/// Activation of the client connection the busy tests put on the device
+const CLIENT_ACTIVE_PATH: &str = "/org/freedesktop/NetworkManager/ActiveConnection/0";
+const DEVICE: &str = "fakewlan9";
+/// Devices listed besides the WiFi adapter: interface, NMDeviceType
+const OTHER_DEVICES: &[(&str, u32)] = &[("fakeeth9", 1), ("fakebr9", 13), ("lo", 14)];
+/// Access point objects served; `State::access_points` fills them in order
+const MAX_ACCESS_POINTS: usize = 6;
+/// NM_WIFI_DEVICE_CAP_AP plus the usual cipher and WPA bits
+const CAPS_WITH_AP: u32 = 0x80 | 0x3f;
+
+/// Connection settings as they cross the bus: setting → property → value
+type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+/// What the fake has been asked to do, and how it should answer
+#[derive(Default)]
+struct State {
+    /// One line per call that changes anything, e.g. "ActivateConnection CosmicHotspot"
+    calls: Vec<String>,
+    /// Saved connections by object path
+    connections: Vec<(String, Saved)>,
+    /// Active connections by object path: id, NMActiveConnectionState
+    active: Vec<(String, String, u32)>,
+    next_path: u32,
+    /// Reject AddConnection
+    fail_add: bool,
+    /// Activations go straight to "deactivated" instead of "activated"
+    fail_activation: bool,
+    /// Client connection active on the device, for the busy tests
+    client_connection: Option<String>,
+    /// Only the WiFi adapter is listed, not `OTHER_DEVICES`
+    no_other_devices: bool,
+    /// Not even the WiFi adapter is listed
+    no_devices: bool,
+    /// The adapter's WirelessCapabilities
+    capabilities: u32,
+    /// What the adapter sees, at most `MAX_ACCESS_POINTS`
+    access_points: Vec<FakeAp>,
+    /// Index into `access_points` the adapter is associated with
+    associated: Option<usize>,
+    /// The adapter's LastScan, moved on by each RequestScan
+    last_scan: i64,
+}
+
+struct Saved {
+    id: String,
+    settings: ConnectionSettings,
+}
+
+#[derive(Clone, Default)]
+struct FakeAp {
+    ssid: String,
+    strength: u8,
+    frequency: u32,
+    /// RsnFlags; 0 for an open network
+    rsn_flags: u32,
+}
+
+/// RsnFlags of a WPA2-Personal and a WPA3-only network
+const RSN_PSK: u32 = 0x100 | 0x8;
+const RSN_SAE: u32 = 0x400 | 0x8;
+
+type Shared = Arc<Mutex<State>>;
+
+fn lock(state: &Shared) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn path(path: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path.to_string()).expect("valid object path")
+}
+
+fn owned(value: Value<'_>) -> OwnedValue {
+    OwnedValue::try_from(value).expect("owned value")
+}
+
+fn value(settings: &ConnectionSettings, setting: &str, property: &str) -> Option<OwnedValue> {
+    settings.get(setting)?.get(property)?.try_clone().ok()
+}
+
+fn string(settings: &ConnectionSettings, setting: &str, property: &str) -> String {
+    value(settings, setting, property).and_then(|v| String::try_from(v).ok()).unwrap_or_default()
+}
+
+fn ssid(settings: &ConnectionSettings) -> String {
+    let bytes = value(settings, "802-11-wireless", "ssid").and_then(|v| Vec::<u8>::try_from(v).ok());
+    String::from_utf8(bytes.unwrap_or_default()).expect("UTF-8 SSID")
+}
+
+/// `settings` with the properties picked by `keep`, as NM hands out
+/// settings without secrets and secrets on their own
+fn filtered(settings: &ConnectionSettings, keep: impl Fn(&str, &str) -> bool) -> ConnectionSettings {
+    settings
+        .iter()
+        .map(|(setting, properties)| {
+            let properties = properties
+                .iter()
+                .filter(|(property, _)| keep(setting, property))
+                .filter_map(|(property, value)| Some((property.clone(), value.try_clone().ok()?)))
+                .collect();
+            (setting.clone(), properties)
+        })
+        .collect()
+}
+
+struct NetworkManager(Shared);
+
+#[interface(name = "org.freedesktop.NetworkManager")]
+impl NetworkManager {
+    fn get_device_by_ip_iface(&self, iface: &str) -> fdo::Result<OwnedObjectPath> {
+        if iface == DEVICE {
+            Ok(path(DEVICE_PATH))
+        } else {
+            Err(fdo::Error::Failed(format!("No device {iface}")))
+        }
+    }
+
+    fn get_devices(&self) -> Vec<OwnedObjectPath> {
+        let state = lock(&self.0);
+        if state.no_devices {
+            return Vec::new();
+        }
+        let others = if state.no_other_devices { 0 } else { OTHER_DEVICES.len() };
+        std::iter::once(path(DEVICE_PATH)).chain((0..others).map(|i| path(&other_device_path(i)))).collect()
+    }
+
+    async fn activate_connection(
+        &self,
+        connection: OwnedObjectPath,
+        _device: OwnedObjectPath,
+        _specific_object: OwnedObjectPath,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let active_path = {
+            let mut state = lock(&self.0);
+            let id = state
+                .connections
+                .iter()
+                .find(|(p, _)| p == connection.as_str())
+                .map(|(_, saved)| saved.id.clone())
+                .ok_or_else(|| fdo::Error::Failed("Unknown connection".to_string()))?;
+            state.calls.push(format!("ActivateConnection {id}"));
+            state.next_path += 1;
+            let active_path = format!("{NM_PATH}/ActiveConnection/{}", state.next_path);
+            let active_state = if state.fail_activation { 4 } else { 2 };
+            state.active.push((active_path.clone(), id, active_state));
+            active_path
+        };
+        server.at(active_path.as_str(), ActiveConnection(self.0.clone(), active_path.clone())).await?;
+        Ok(path(&active_path))
+    }
+
+    fn deactivate_connection(&self, active_connection: OwnedObjectPath) {
+        let mut state = lock(&self.0);
+        if let Some(idx) = state.active.iter().position(|(p, _, _)| p == active_connection.as_str()) {
+            let (_, id, _) = state.active.remove(idx);
+            state.calls.push(format!("DeactivateConnection {id}"));
+        }
+    }
+
+    #[zbus(property)]
+    fn active_connections(&self) -> Vec<OwnedObjectPath> {
+        lock(&self.0).active.iter().map(|(p, _, _)| path(p)).collect()
+    }
+}
+
+struct Settings(Shared);
+
+#[interface(name = "org.freedesktop.NetworkManager.Settings")]
+impl Settings {
+    fn list_connections(&self) -> Vec<OwnedObjectPath> {
+        lock(&self.0).connections.iter().map(|(p, _)| path(p)).collect()
+    }
+
+    fn get_connection_by_uuid(&self, uuid: &str) -> fdo::Result<OwnedObjectPath> {
+        lock(&self.0)
+            .connections
+            .iter()
+            .find(|(_, saved)| string(&saved.settings, "connection", "uuid") == uuid)
+            .map(|(p, _)| path(p))
+            .ok_or_else(|| fdo::Error::Failed(format!("No connection with UUID {uuid}")))
+    }
+
+    async fn add_connection(
+        &self,
+        settings: ConnectionSettings,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let connection_path = {
+            let mut state = lock(&self.0);
+            let id = string(&settings, "connection", "id");
+            state.calls.push(format!("AddConnection {id}"));
+            if state.fail_add {
+                return Err(fdo::Error::InvalidArgs("802-11-wireless.band: invalid".to_string()));
+            }
+            state.save(Saved { id, settings })
+        };
+        server.at(connection_path.as_str(), Connection(self.0.clone(), connection_path.clone())).await?;
+        Ok(path(&connection_path))
+    }
+}
+
+impl State {
+    /// Store a saved connection under a new object path
+    fn save(&mut self, saved: Saved) -> String {
+        self.next_path += 1;
+        let connection_path = format!("{SETTINGS_PATH}/{}", self.next_path);
+        self.connections.push((connection_path.clone(), saved));
+        connection_path
+    }
+}
+
+struct Connection(Shared, String);
+
+#[interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+impl Connection {
+    fn get_settings(&self) -> fdo::Result<ConnectionSettings> {
+        self.with_saved(|saved| filtered(&saved.settings, |_, property| property != "psk"))
+    }
+
+    fn get_secrets(&self, setting: &str) -> fdo::Result<ConnectionSettings> {
+        self.with_saved(|saved| filtered(&saved.settings, |s, property| s == setting && property == "psk"))
+    }
+
+    fn delete(&self) {
+        let mut state = lock(&self.0);
+        if let Some(idx) = state.connections.iter().position(|(p, _)| *p == self.1) {
+            let (_, saved) = state.connections.remove(idx);
+            state.calls.push(format!("Delete {}", saved.id));
+        }
+    }
+}
+
+impl Connection {
+    fn with_saved<T>(&self, read: impl FnOnce(&Saved) -> T) -> fdo::Result<T> {
+        let state = lock(&self.0);
+        let (_, saved) = state
+            .connections
+            .iter()
+            .find(|(p, _)| *p == self.1)
+            .ok_or_else(|| fdo::Error::UnknownObject(self.1.clone()))?;
+        Ok(read(saved))
+    }
+}
+struct ActiveConnection(Shared, String);
+
+#[interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+impl ActiveConnection {
+    #[zbus(property)]
+    fn id(&self) -> String {
+        if self.1 == CLIENT_ACTIVE_PATH {
+            return lock(&self.0).client_connection.clone().unwrap_or_default();
+        }
+        self.active().map(|(_, id, _)| id).unwrap_or_default()
+    }
+
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> String {
+        "802-11-wireless".to_string()
+    }
+
+    /// Gone activations read as deactivated
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        self.active().map_or(4, |(_, _, state)| state)
+    }
+
+    #[zbus(property)]
+    fn devices(&self) -> Vec<OwnedObjectPath> {
+        vec![path(DEVICE_PATH)]
+    }
+}
+
+impl ActiveConnection {
+    fn active(&self) -> Option<(String, String, u32)> {
+        lock(&self.0).active.iter().find(|(p, _, _)| *p == self.1).cloned()
+    }
+}
+
+struct Device(Shared);
+
+#[interface(name = "org.freedesktop.NetworkManager.Device")]
+impl Device {
+    fn disconnect(&self) {
+        let mut state = lock(&self.0);
+        state.calls.push(format!("Disconnect {DEVICE}"));
+        state.client_connection = None;
+    }
+
+    #[zbus(property)]
+    fn interface(&self) -> String {
+        DEVICE.to_string()
+    }
+
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        2
+    }
+
+    /// NM_DEVICE_STATE_ACTIVATED with a client connection, otherwise DISCONNECTED
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        if lock(&self.0).client_connection.is_some() {
+            100
+        } else {
+            30
+        }
+    }
+
+    #[zbus(property)]
+    fn active_connection(&self) -> OwnedObjectPath {
+        path(if lock(&self.0).client_connection.is_some() { CLIENT_ACTIVE_PATH } else { "/" })
+    }
+}
+
+struct Wireless(Shared);
+
+#[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+impl Wireless {
+    fn request_scan(&self, _options: HashMap<String, OwnedValue>) {
+        let mut state = lock(&self.0);
+        state.calls.push(format!("RequestScan {DEVICE}"));
+        state.last_scan += 1000;
+    }
+
+    fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+        (0..lock(&self.0).access_points.len()).map(|i| path(&access_point_path(i))).collect()
+    }
+
+    #[zbus(property)]
+    fn wireless_capabilities(&self) -> u32 {
+        lock(&self.0).capabilities
+    }
+
+    #[zbus(property)]
+    fn last_scan(&self) -> i64 {
+        lock(&self.0).last_scan
+    }
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> OwnedObjectPath {
+        path(&lock(&self.0).associated.map_or("/".to_string(), access_point_path))
+    }
+}
+
+struct AccessPoint(Shared, usize);
+
+#[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+impl AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> Vec<u8> {
+        self.ap().ssid.into_bytes()
+    }
+
+    #[zbus(property)]
+    fn strength(&self) -> u8 {
+        self.ap().strength
+    }
+
+    #[zbus(property)]
+    fn frequency(&self) -> u32 {
+        self.ap().frequency
+    }
+
+    #[zbus(property)]
+    fn flags(&self) -> u32 {
+        u32::from(self.ap().rsn_flags != 0)
+    }
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> u32 {
+        0
+    }
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> u32 {
+        self.ap().rsn_flags
+    }
+}
+
+impl AccessPoint {
+    fn ap(&self) -> FakeAp {
+        lock(&self.0).access_points.get(self.1).cloned().unwrap_or_default()
+    }
+}
+
+fn access_point_path(index: usize) -> String {
+    format!("{NM_PATH}/AccessPoint/{index}")
+}
+
+/// One of `OTHER_DEVICES`, listed but never acted on
+struct OtherDevice(usize);
+
+#[interface(name = "org.freedesktop.NetworkManager.Device")]
+impl OtherDevice {
+    #[zbus(property)]
+    fn interface(&self) -> String {
+        OTHER_DEVICES[self.0].0.to_string()
+    }
+
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        OTHER_DEVICES[self.0].1
+    }
+}
+
+fn other_device_path(index: usize) -> String {
+    format!("{NM_PATH}/Devices/{}", index + 2)
+}
+
+/// The private bus with the fake NetworkManager on it, shared by every test
+/// since the client keeps one system bus connection per process
+struct FakeNm {
+    state: Shared,
+    conn: zbus::blocking::Connection,
+}
+
+/// Tests share the fake's state, so they run one at a time
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("cosmic-hotspot-fake-nm-{}", std::process::id()))
+}
+
+/// Start `dbus-daemon` on a socket in the temp dir; `None` if it isn't installed
+fn start_bus() -> Option<String> {
+    let dir = temp_dir();
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let config = dir.join("bus.conf");
+    std::fs::write(
+        &config,
+        format!(
+            r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-BUS Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>custom</type>
+  <listen>unix:path={}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow user="*"/>
+    <allow own="*"/>
+    <allow send_destination="*"/>
+    <allow receive_sender="*"/>
+  </policy>
+</busconfig>
+"#,
+            dir.join("bus").display()
+        ),
+    )
+    .expect("write bus config");
+
+    // Spawned from a thread that lives as long as the process, which the
+    // daemon then exits with
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut command = Command::new("dbus-daemon");
+        command.arg(format!("--config-file={}", config.display())).args(["--nofork", "--print-address"]);
+        command.stdout(Stdio::piped()).stderr(Stdio::null());
+        unsafe {
+            use std::os::unix::process::CommandExt;
+            command.pre_exec(|| {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM);
+                Ok(())
+            });
+        }
+        let Ok(mut daemon) = command.spawn() else {
+            let _ = tx.send(None);
+            return;
+        };
+        let mut address = String::new();
+        let stdout = daemon.stdout.take().expect("piped stdout");
+        let _ = BufReader::new(stdout).read_line(&mut address);
+        let _ = tx.send(Some(address.trim().to_string()));
+        loop {
+            std::thread::park();
+        }
+    });
+    rx.recv().ok().flatten().filter(|address| !address.is_empty())
+}
+
+fn fake_nm() -> Option<&'static FakeNm> {
+    static FAKE: OnceLock<Option<FakeNm>> = OnceLock::new();
+    FAKE.get_or_init(|| {
+        let Some(address) = start_bus() else {
+            eprintln!("dbus-daemon isn't installed; skipping the fake NetworkManager tests");
+            return None;
+        };
+        // State files and the displaced connection stay out of the real session
+        let dir = temp_dir();
+        for var in ["XDG_RUNTIME_DIR", "XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_STATE_HOME", "XDG_CACHE_HOME"] {
+            std::env::set_var(var, &dir);
+        }
+        std::env::set_var("DBUS_SYSTEM_BUS_ADDRESS", &address);
+
+        let state = Shared::default();
+        let conn = zbus::blocking::connection::Builder::address(address.as_str())
+            .and_then(|b| b.name("org.freedesktop.NetworkManager"))
+            .and_then(|b| b.serve_at(NM_PATH, NetworkManager(state.clone())))
+            .and_then(|b| b.serve_at(SETTINGS_PATH, Settings(state.clone())))
+            .and_then(|b| b.serve_at(DEVICE_PATH, Device(state.clone())))
+            .and_then(|b| b.serve_at(DEVICE_PATH, Wireless(state.clone())))
+            .and_then(|b| b.serve_at(CLIENT_ACTIVE_PATH, ActiveConnection(state.clone(), CLIENT_ACTIVE_PATH.to_string())))
+            .and_then(|b| b.build())
+            .expect("serve the fake NetworkManager");
+        for i in 0..OTHER_DEVICES.len() {
+            conn.object_server().at(other_device_path(i), OtherDevice(i)).expect("serve a device");
+        }
+        for i in 0..MAX_ACCESS_POINTS {
+            conn.object_server().at(access_point_path(i), AccessPoint(state.clone(), i)).expect("serve an access point");
+        }
+        Some(FakeNm { state, conn })
+    })
+    .as_ref()
+}
+
+/// The fake, reset to no connections, and the lock holding other tests off
+fn setup() -> Option<(&'static FakeNm, MutexGuard<'static, ()>)> {
+    let guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let fake = fake_nm()?;
+    *lock(&fake.state) = State { capabilities: CAPS_WITH_AP, ..State::default() };
+    let _ = std::fs::remove_file(temp_dir().join("cosmic-hotspot").join("displaced-connection"));
+    Some((fake, guard))
+}
+
+impl FakeNm {
+    fn calls(&self) -> Vec<String> {
+        lock(&self.state).calls.clone()
+    }
+
+    fn set(&self, change: impl FnOnce(&mut State)) {
+        change(&mut lock(&self.state));
+    }
+
+    /// Save a connection as if another tool had added it
+    fn save(&self, settings: ConnectionSettings) {
+        let id = string(&settings, "connection", "id");
+        let connection_path = lock(&self.state).save(Saved { id, settings });
+        self.conn
+            .object_server()
+            .at(connection_path.as_str(), Connection(self.state.clone(), connection_path.clone()))
+            .expect("serve the connection");
+    }
+
+    /// Settings AddConnection was last given for `id`
+    fn added(&self, id: &str) -> ConnectionSettings {
+        let state = lock(&self.state);
+        let (_, saved) = state.connections.iter().rev().find(|(_, saved)| saved.id == id).expect("connection added");
+        filtered(&saved.settings, |_, _| true)
+    }
+}
+
+/// A saved WiFi connection: `mode` "ap" or "infrastructure", WPA2-Personal
+/// with `psk` if given
+fn wifi_connection(id: &str, ssid: &str, mode: &str, psk: Option<&str>) -> ConnectionSettings {
+    let mut settings = ConnectionSettings::from([
+        (
+            "connection".to_string(),
+            HashMap::from([
+                ("id".to_string(), owned(Value::from(id))),
+                ("uuid".to_string(), owned(Value::from(format!("uuid-{id}")))),
+                ("type".to_string(), owned(Value::from("802-11-wireless"))),
+            ]),
+        ),
+        (
+            "802-11-wireless".to_string(),
+            HashMap::from([
+                ("ssid".to_string(), owned(Value::from(ssid.as_bytes()))),
+                ("mode".to_string(), owned(Value::from(mode))),
+            ]),
+        ),
+    ]);
+    if let Some(psk) = psk {
+        settings.insert(
+            "802-11-wireless-security".to_string(),
+            HashMap::from([
+                ("key-mgmt".to_string(), owned(Value::from("wpa-psk"))),
+                ("psk".to_string(), owned(Value::from(psk))),
+            ]),
+        );
+    }
+    settings
+}
+
+fn ap(ssid: &str, strength: u8, frequency: u32, rsn_flags: u32) -> FakeAp {
+    FakeAp { ssid: ssid.to_string(), strength, frequency, rsn_flags }
+}
+
+
+/// A config that never reaches the privileged helper or real interfaces
+fn test_config() -> Config {
+    Config {
+        hotspot_interface: DEVICE.to_string(),
+        internet_interface: "fakeeth9".to_string(),
+        profiles: vec![Profile {
+            nat_strategy: "nm-shared".to_string(),
+            ..Profile::default()
+        }],
+        verify_after_start: false,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn start_replaces_then_activates_the_connection() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = test_config();
+    // A leftover profile from an earlier run
+    hotspot::start_hotspot(&config).expect("first start");
+    fake.set(|state| {
+        state.calls.clear();
+        state.active.clear();
+    });
+
+    let msg = hotspot::start_hotspot(&config).expect("start succeeds");
+    assert!(msg.contains(&config.ssid), "unexpected message: {msg}");
+    assert_eq!(
+        fake.calls(),
+        ["Delete CosmicHotspot", "AddConnection CosmicHotspot", "ActivateConnection CosmicHotspot"]
+    );
+    let added = fake.added("CosmicHotspot");
+    assert_eq!(string(&added, "connection", "interface-name"), DEVICE);
+    assert_eq!(ssid(&added), config.ssid);
+}
+
+#[test]
+fn failed_add_is_reported_and_not_activated() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.fail_add = true);
+
+    let err = hotspot::start_hotspot(&test_config()).expect_err("start fails");
+    assert_eq!(err.kind, ErrorKind::CreateFailed);
+    assert!(err.detail.contains("802-11-wireless.band"), "message not surfaced: {err}");
+    assert_eq!(fake.calls(), ["AddConnection CosmicHotspot"]);
+}
+
+#[test]
+fn failed_activation_is_reported() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.fail_activation = true);
+
+    let err = hotspot::start_hotspot(&test_config()).expect_err("start fails");
+    assert_eq!(err.kind, ErrorKind::ActivationFailed);
+    assert!(!hotspot::is_hotspot_active(&test_config()));
+}
+
+#[test]
+fn busy_interface_fails_preflight_without_changes() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.client_connection = Some("Home WiFi".to_string()));
+
+    let err = hotspot::start_hotspot(&test_config()).expect_err("busy");
+    assert_eq!(err.kind, ErrorKind::InterfaceBusy);
+    assert_eq!(err.detail, "Home WiFi");
+    assert!(fake.calls().is_empty(), "changed something: {:?}", fake.calls());
+}
+
+#[test]
+fn auto_disconnect_frees_the_adapter_first() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.client_connection = Some("Home WiFi".to_string()));
+    let config = Config { auto_disconnect_client: true, ..test_config() };
+
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    assert_eq!(
+        fake.calls(),
+        ["Disconnect fakewlan9", "AddConnection CosmicHotspot", "ActivateConnection CosmicHotspot"]
+    );
+}
+
+#[test]
+fn stop_deactivates_and_deletes_the_connection() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = test_config();
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    assert!(hotspot::is_hotspot_active(&config));
+    fake.set(|state| state.calls.clear());
+
+    hotspot::stop_hotspot(&config).expect("stop succeeds");
+    assert_eq!(fake.calls(), ["DeactivateConnection CosmicHotspot", "Delete CosmicHotspot"]);
+    assert!(!hotspot::is_hotspot_active(&config));
+}
+
+#[test]
+fn autoconnect_keeps_the_profile_on_stop() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = Config { autoconnect: true, ..test_config() };
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    fake.set(|state| state.calls.clear());
+
+    hotspot::stop_hotspot(&config).expect("stop succeeds");
+    assert_eq!(fake.calls(), ["DeactivateConnection CosmicHotspot"]);
+}
+
+#[test]
+fn other_connections_are_not_the_hotspot() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| {
+        state.client_connection = Some("Home WiFi".to_string());
+        state.active.push((CLIENT_ACTIVE_PATH.to_string(), "Home WiFi".to_string(), 2));
+    });
+    assert!(!hotspot::is_hotspot_active(&test_config()));
+    assert!(hotspot::is_hotspot_active(&Config { connection_name: "Home WiFi".to_string(), ..test_config() }));
+}
+
+/// The gateway address and prefix in the ipv4 setting's address-data
+fn gateway(settings: &ConnectionSettings) -> (String, u32) {
+    let data = value(settings, "ipv4", "address-data").expect("address-data");
+    let addresses = Vec::<HashMap<String, OwnedValue>>::try_from(data).expect("a list of addresses");
+    let address = &addresses[0];
+    let field = |name: &str| address[name].try_clone().expect("cloneable value");
+    (String::try_from(field("address")).expect("address"), u32::try_from(field("prefix")).expect("prefix"))
+}
+
+#[test]
+fn start_adds_a_shared_access_point() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = test_config();
+
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    let added = fake.added("CosmicHotspot");
+    assert_eq!(string(&added, "connection", "type"), "802-11-wireless");
+    assert_eq!(string(&added, "802-11-wireless", "mode"), "ap");
+    assert_eq!(string(&added, "802-11-wireless-security", "psk"), config.password);
+    assert_eq!(string(&added, "ipv4", "method"), "shared");
+    assert_eq!(gateway(&added), ("192.168.44.1".to_string(), 24));
+    assert!(!string(&added, "connection", "uuid").is_empty(), "no UUID given");
+}
+
+#[test]
+fn start_applies_profile_dhcp_range_and_isolation() {
+    let Some((fake, _guard)) = setup() else { return };
+    let mut config = test_config();
+    config.profiles[0].gateway_ip = "10.42.7.1/24".to_string();
+    config.profiles[0].dhcp_range_start = "10.42.7.50".to_string();
+    config.profiles[0].dhcp_range_end = "10.42.7.99".to_string();
+    config.profiles[0].client_isolation = true;
+
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    let added = fake.added("CosmicHotspot");
+    assert_eq!(gateway(&added), ("10.42.7.1".to_string(), 24));
+    assert_eq!(string(&added, "ipv4", "shared-dhcp-range"), "10.42.7.50,10.42.7.99");
+    let isolation = value(&added, "802-11-wireless", "ap-isolation").expect("ap-isolation sent");
+    assert_eq!(i32::try_from(isolation), Ok(1));
+}
+
+#[test]
+fn start_omits_unset_profile_options() {
+    let Some((fake, _guard)) = setup() else { return };
+
+    hotspot::start_hotspot(&test_config()).expect("start succeeds");
+    let added = fake.added("CosmicHotspot");
+    assert!(value(&added, "ipv4", "shared-dhcp-range").is_none());
+    assert!(value(&added, "802-11-wireless", "ap-isolation").is_none());
+}
+
+#[test]
+fn weird_ssid_and_names_pass_through_verbatim() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = Config {
+        ssid: "Dave's \"Hot\" spot; $(rm -rf ~) ☕".to_string(),
+        connection_name: "Dave's Hotspot".to_string(),
+        password: "pa ss:word'\\".to_string(),
+        ..test_config()
+    };
+
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    let added = fake.added("Dave's Hotspot");
+    assert_eq!(ssid(&added), config.ssid);
+    assert_eq!(string(&added, "802-11-wireless-security", "psk"), config.password);
+    assert_eq!(fake.calls().last().map(String::as_str), Some("ActivateConnection Dave's Hotspot"));
+}
+
+#[test]
+fn keyword_like_names_are_trimmed_and_used_as_ids() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = Config { connection_name: "  --uuid  ".to_string(), ..test_config() };
+
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    assert_eq!(fake.calls(), ["AddConnection --uuid", "ActivateConnection --uuid"]);
+}
+
+#[test]
+fn invalid_connection_names_never_reach_networkmanager() {
+    let Some((fake, _guard)) = setup() else { return };
+    let too_long = "x".repeat(65);
+    for name in ["", "   ", "line\nbreak", too_long.as_str()] {
+        let config = Config { connection_name: name.to_string(), ..test_config() };
+        let err = hotspot::start_hotspot(&config).expect_err("invalid name rejected");
+        assert_eq!(err.kind, ErrorKind::InvalidSetting, "name {name:?}");
+    }
+    assert!(fake.calls().is_empty(), "changed something: {:?}", fake.calls());
+}
+
+#[test]
+fn dry_run_executes_nothing() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.client_connection = Some("Home WiFi".to_string()));
+    let config = Config { dry_run: true, auto_disconnect_client: true, ..test_config() };
+
+    assert!(hotspot::start_hotspot(&config).is_ok());
+    assert!(hotspot::stop_hotspot(&config).is_ok());
+    assert!(fake.calls().is_empty(), "changed something: {:?}", fake.calls());
+}
+
+#[test]
+fn busy_interface_can_be_disconnected_first() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.client_connection = Some("Home WiFi".to_string()));
+
+    hotspot::start_hotspot_with(&test_config(), Some(hotspot::BusyResolution::Disconnect)).expect("start succeeds");
+    assert_eq!(
+        fake.calls(),
+        ["Disconnect fakewlan9", "AddConnection CosmicHotspot", "ActivateConnection CosmicHotspot"]
+    );
+}
+
+#[test]
+fn own_hotspot_connection_is_not_busy() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = test_config();
+    fake.set(|state| state.client_connection = Some(config.connection_name.clone()));
+
+    assert!(hotspot::start_hotspot(&config).is_ok());
+}
+
+#[test]
+fn foreign_hotspot_is_detected_and_not_replaced() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.save(wifi_connection("Hotspot", "gnome-laptop", "ap", Some("gnome-pass")));
+    fake.set(|state| state.client_connection = Some("Hotspot".to_string()));
+    let config = Config { auto_disconnect_client: true, ..test_config() };
+
+    let foreign = hotspot::foreign_hotspot(&config).expect("foreign hotspot found");
+    assert_eq!(foreign.connection, "Hotspot");
+    assert_eq!(foreign.ssid, "gnome-laptop");
+
+    // Not even auto-disconnect takes another tool's hotspot down
+    let err = hotspot::start_hotspot(&config).expect_err("foreign hotspot left alone");
+    assert_eq!(err.kind, ErrorKind::ForeignHotspot);
+    assert_eq!(err.detail, "Hotspot");
+    assert!(fake.calls().is_empty(), "changed something: {:?}", fake.calls());
+}
+
+#[test]
+fn replaced_foreign_hotspot_is_not_restored_on_stop() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.save(wifi_connection("Hotspot", "gnome-laptop", "ap", Some("gnome-pass")));
+    fake.set(|state| state.client_connection = Some("Hotspot".to_string()));
+    let config = test_config();
+
+    hotspot::start_hotspot_with(&config, Some(hotspot::BusyResolution::Disconnect)).expect("start succeeds");
+    hotspot::stop_hotspot(&config).expect("stop succeeds");
+    assert!(!fake.calls().iter().any(|call| call == "ActivateConnection Hotspot"), "{:?}", fake.calls());
+}
+
+#[test]
+fn client_connection_is_not_a_foreign_hotspot() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.save(wifi_connection("Home WiFi", "Home-WiFi", "infrastructure", Some("home-pass")));
+    fake.set(|state| state.client_connection = Some("Home WiFi".to_string()));
+
+    assert_eq!(hotspot::foreign_hotspot(&test_config()), None);
+}
+
+#[test]
+fn auto_disconnect_restores_the_client_connection_on_stop() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.save(wifi_connection("Home WiFi", "Home-WiFi", "infrastructure", Some("home-pass")));
+    fake.set(|state| state.client_connection = Some("Home WiFi".to_string()));
+    let config = Config { auto_disconnect_client: true, ..test_config() };
+
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    hotspot::stop_hotspot(&config).expect("stop succeeds");
+    assert_eq!(fake.calls().last().map(String::as_str), Some("ActivateConnection Home WiFi"));
+
+    // Restored only once
+    hotspot::stop_hotspot(&config).expect("second stop succeeds");
+    let restores = fake.calls().iter().filter(|call| *call == "ActivateConnection Home WiFi").count();
+    assert_eq!(restores, 1);
+}
+
+#[test]
+fn stop_without_a_hotspot_succeeds() {
+    let Some((fake, _guard)) = setup() else { return };
+
+    assert_eq!(hotspot::stop_hotspot(&test_config()).as_deref(), Ok("Hotspot stopped"));
+    assert!(fake.calls().is_empty(), "changed something: {:?}", fake.calls());
+}
+
+#[test]
+fn only_an_activated_hotspot_is_active() {
+    let Some((fake, _guard)) = setup() else { return };
+    let config = test_config();
+    hotspot::start_hotspot(&config).expect("start succeeds");
+    // activating, activated, deactivating, deactivated
+    for (state, expected) in [(1, false), (2, true), (3, false), (4, false)] {
+        fake.set(|fake_state| fake_state.active[0].2 = state);
+        assert_eq!(hotspot::is_hotspot_active(&config), expected, "NMActiveConnectionState {state}");
+    }
+}
+
+#[test]
+fn interfaces_are_listed_by_device_type() {
+    let Some((fake, _guard)) = setup() else { return };
+
+    assert_eq!(hotspot::list_wifi_interfaces(), [DEVICE]);
+    assert_eq!(hotspot::list_network_interfaces(), [DEVICE, "fakeeth9"]);
+    assert!(hotspot::has_wifi_hardware());
+
+    fake.set(|state| state.no_devices = true);
+    assert!(hotspot::list_wifi_interfaces().is_empty());
+    assert!(!hotspot::has_wifi_hardware());
+}
+
+#[test]
+fn ap_support_comes_from_the_wireless_capabilities() {
+    let Some((fake, _guard)) = setup() else { return };
+    assert!(adapters::probe(DEVICE).ap_capable);
+
+    fake.set(|state| state.capabilities = 0x3f);
+    assert!(!adapters::probe(DEVICE).ap_capable);
+    // Unknown to NetworkManager isn't ruled out
+    assert!(adapters::probe("fakewlan8").ap_capable);
+}
+
+#[test]
+fn networkmanager_is_running_while_it_owns_its_name() {
+    let Some((_fake, _guard)) = setup() else { return };
+    assert!(nm_dbus::running());
+}
+
+#[test]
+fn scan_lists_each_network_once_strongest_first() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| {
+        state.access_points = vec![
+            ap("Home-WiFi", 40, 2412, RSN_PSK),
+            ap("", 90, 2437, RSN_PSK),
+            ap("Home-WiFi", 70, 5180, RSN_PSK),
+            ap("Library", 55, 2462, 0),
+        ];
+    });
+
+    let networks = repeater::scan(DEVICE);
+    assert_eq!(
+        networks,
+        [
+            repeater::ScannedNetwork { ssid: "Home-WiFi".to_string(), signal: 70, secured: true },
+            repeater::ScannedNetwork { ssid: "Library".to_string(), signal: 55, secured: false },
+        ]
+    );
+    assert_eq!(fake.calls(), ["RequestScan fakewlan9"]);
+}
+
+#[test]
+fn channel_scan_reports_each_access_point_channel() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.access_points = vec![ap("A", 40, 2437, RSN_PSK), ap("", 65, 5180, 0)]);
+
+    assert_eq!(
+        channels::scan(DEVICE),
+        [channels::NearbyAp { channel: 6, signal: 40 }, channels::NearbyAp { channel: 36, signal: 65 }]
+    );
+}
+
+/// A config whose hotspot adapter is busy elsewhere and whose client adapter
+/// is the fake's device
+fn repeater_config(ssid: &str, password: &str) -> Config {
+    Config {
+        hotspot_interface: "fakewlan8".to_string(),
+        internet_interface: DEVICE.to_string(),
+        repeater_mode: true,
+        repeater_ssid: ssid.to_string(),
+        repeater_password: password.to_string(),
+        ..test_config()
+    }
+}
+
+#[test]
+fn repeater_activates_a_saved_upstream_profile() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.save(wifi_connection("Home", "Home-WiFi", "infrastructure", Some("home-pass")));
+
+    repeater::ensure_upstream(&repeater_config("Home-WiFi", "")).expect("joined");
+    assert_eq!(fake.calls(), ["ActivateConnection Home"]);
+}
+
+#[test]
+fn repeater_saves_a_new_upstream_with_its_security() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| state.access_points = vec![ap("Café ☕", 60, 5180, RSN_SAE)]);
+
+    repeater::ensure_upstream(&repeater_config("Café ☕", "upstream-pass")).expect("joined");
+    assert_eq!(fake.calls(), ["AddConnection Café ☕", "ActivateConnection Café ☕"]);
+    let added = fake.added("Café ☕");
+    assert_eq!(ssid(&added), "Café ☕");
+    assert_eq!(string(&added, "802-11-wireless", "mode"), "infrastructure");
+    assert_eq!(string(&added, "802-11-wireless-security", "key-mgmt"), "sae");
+    assert_eq!(string(&added, "802-11-wireless-security", "psk"), "upstream-pass");
+}
+
+#[test]
+fn repeater_leaves_a_joined_upstream_alone() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.set(|state| {
+        state.access_points = vec![ap("Home-WiFi", 60, 2412, RSN_PSK)];
+        state.associated = Some(0);
+    });
+
+    assert_eq!(
+        repeater::client_link(DEVICE),
+        Some(repeater::ClientLink { ssid: "Home-WiFi".to_string(), signal: 60 })
+    );
+    repeater::ensure_upstream(&repeater_config("Home-WiFi", "home-pass")).expect("already joined");
+    assert!(fake.calls().is_empty(), "changed something: {:?}", fake.calls());
+}
+
+#[test]
+fn saved_networks_offer_personal_client_profiles_with_their_password() {
+    let Some((fake, _guard)) = setup() else { return };
+    fake.save(wifi_connection("Home", "Home-WiFi", "infrastructure", Some("home-pass")));
+    fake.save(wifi_connection("Library", "Library", "infrastructure", None));
+    fake.save(wifi_connection("Hotspot", "gnome-laptop", "ap", Some("gnome-pass")));
+
+    let networks = saved_networks::list();
+    assert_eq!(
+        networks,
+        [saved_networks::SavedNetwork {
+            uuid: "uuid-Home".to_string(),
+            name: "Home".to_string(),
+            ssid: "Home-WiFi".to_string(),
+        }]
+    );
+    assert_eq!(saved_networks::password(&networks[0]), Ok("home-pass".to_string()));
+}
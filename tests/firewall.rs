@@ -0,0 +1,74 @@
+//! NAT rules written through firewalld and ufw.
+
+use cosmic_hotspot::hotspot;
+use cosmic_hotspot::nat::{Firewall, NatRequest};
+
+fn request(firewall: Firewall) -> [NatRequest; 3] {
+    let (hotspot, internet) = ("wlan0".to_string(), "eth0".to_string());
+    [
+        NatRequest::Setup { hotspot: hotspot.clone(), internet: internet.clone(), firewall },
+        NatRequest::Teardown { hotspot: hotspot.clone(), internet: internet.clone(), firewall },
+        NatRequest::Check { hotspot, internet, firewall },
+    ]
+}
+
+#[test]
+fn firewall_requests_round_trip() {
+    for firewall in [Firewall::Firewalld, Firewall::Ufw] {
+        assert_eq!(Firewall::parse(firewall.as_str()).unwrap(), firewall);
+        for request in request(firewall) {
+            assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+        }
+    }
+}
+
+#[test]
+fn firewalld_uses_a_zone_and_masquerading_policy() {
+    let [setup, teardown, check] = request(Firewall::Firewalld);
+    let commands: Vec<String> = setup.rules().iter().map(|r| r.command.join(" ")).collect();
+    assert!(commands.iter().all(|c| !c.contains("--direct")));
+    assert!(commands.contains(&"firewall-cmd --permanent --new-zone=cosmic-hotspot".to_string()));
+    assert!(commands.contains(&"firewall-cmd --permanent --policy=cosmic-hotspot --add-masquerade".to_string()));
+    // Clients only reach DHCP and DNS on the host, and the internet's zone beyond it
+    assert!(commands.contains(&"firewall-cmd --permanent --zone=cosmic-hotspot --set-target=default".to_string()));
+    assert!(commands.contains(&"firewall-cmd --permanent --zone=cosmic-hotspot --add-service=dhcp".to_string()));
+    assert!(commands.contains(&"firewall-cmd --permanent --zone=cosmic-hotspot --add-service=dns".to_string()));
+    assert!(commands.iter().any(|c| c.ends_with(r#"--add-rich-rule=rule priority="32767" reject"#)));
+    assert!(commands.iter().all(|c| !c.contains("ACCEPT") || c.contains("--policy=")));
+    assert!(commands.iter().all(|c| !c.contains("ANY")));
+    let egress = setup.rules().into_iter().find(|r| r.command[0] == "sh").unwrap();
+    assert_eq!(egress.command[3..], ["eth0", "cosmic-hotspot"]);
+    assert!(egress.command[2].contains("--get-zone-of-interface=\"$interface\""));
+    // The interface joins the zone only once the reload has loaded it
+    assert_eq!(commands.last().unwrap(), "firewall-cmd --zone=cosmic-hotspot --change-interface=wlan0");
+    let reload = setup.rules().into_iter().find(|r| r.command.ends_with(&["--reload".to_string()])).unwrap();
+    assert!(reload.unless.is_some());
+
+    let removals = teardown.rules();
+    assert_eq!(removals.len(), 1);
+    assert!(removals[0].ignore_failure);
+    assert_eq!(removals[0].command.join(" "), "firewall-cmd --zone=cosmic-hotspot --remove-interface=wlan0");
+    let checks: Vec<String> = check.rules().iter().map(|r| r.command.join(" ")).collect();
+    assert_eq!(checks, [
+        "firewall-cmd --zone=cosmic-hotspot --query-interface=wlan0",
+        "firewall-cmd --policy=cosmic-hotspot --query-masquerade",
+    ]);
+}
+
+#[test]
+fn ufw_rules_route_and_masquerade() {
+    let [setup, teardown, _] = request(Firewall::Ufw);
+    let commands: Vec<String> = setup.rules().iter().map(|r| r.command.join(" ")).collect();
+    assert!(commands.contains(&"ufw route allow in on wlan0 out on eth0".to_string()));
+    assert!(commands.contains(&"iptables -t nat -A POSTROUTING -o eth0 -j MASQUERADE".to_string()));
+    let removals: Vec<String> = teardown.rules().iter().map(|r| r.command.join(" ")).collect();
+    assert!(removals.contains(&"ufw route delete allow in on wlan0 out on eth0".to_string()));
+    assert!(removals.contains(&"ufw delete allow in on wlan0 to any port 67 proto udp".to_string()));
+}
+
+#[test]
+fn reads_ufw_enabled_state() {
+    assert!(hotspot::ufw_enabled("# comment\nENABLED=yes\nLOGLEVEL=low\n"));
+    assert!(!hotspot::ufw_enabled("ENABLED=no\n"));
+    assert!(!hotspot::ufw_enabled(""));
+}
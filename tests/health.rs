@@ -0,0 +1,35 @@
+//! Sharing health monitor: sysctl parsing and recurring repair detection.
+
+use std::time::{Duration, Instant};
+
+use cosmic_hotspot::health::{self, RepairHistory, RECURRING_REPAIRS, RECURRING_WINDOW};
+
+#[test]
+fn reads_the_forwarding_sysctl() {
+    assert!(health::forwarding_enabled("1\n"));
+    assert!(!health::forwarding_enabled("0\n"));
+    assert!(!health::forwarding_enabled(""));
+}
+
+#[test]
+fn warns_once_when_repairs_keep_recurring() {
+    let start = Instant::now();
+    let mut history = RepairHistory::default();
+    let minutes = |m: u64| start + Duration::from_secs(m * 60);
+
+    for i in 0..RECURRING_REPAIRS - 1 {
+        assert!(!history.record(minutes(i as u64)));
+    }
+    assert!(history.record(minutes(RECURRING_REPAIRS as u64)));
+    // Counting starts afresh after the warning
+    assert!(!history.record(minutes(RECURRING_REPAIRS as u64 + 1)));
+}
+
+#[test]
+fn repairs_far_apart_are_not_recurring() {
+    let start = Instant::now();
+    let mut history = RepairHistory::default();
+    for i in 0..RECURRING_REPAIRS as u32 * 2 {
+        assert!(!history.record(start + RECURRING_WINDOW * i));
+    }
+}
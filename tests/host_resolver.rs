@@ -0,0 +1,28 @@
+//! Forwarding clients' DNS through the host's resolver (VPN and split DNS).
+
+use cosmic_hotspot::config::Config;
+use cosmic_hotspot::nat::NatRequest;
+
+#[test]
+fn host_resolver_needs_the_gateway_resolver() {
+    let mut config = Config { host_resolver_dns: true, ..Config::default() };
+    assert!(config.uses_host_resolver());
+    config.profile_mut().dns_server = "1.1.1.1".to_string();
+    assert!(!config.uses_host_resolver());
+    assert!(!Config::default().uses_host_resolver());
+}
+
+#[test]
+fn host_resolver_request_round_trips() {
+    for enable in [true, false] {
+        let request = NatRequest::HostResolver { enable };
+        assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    }
+}
+
+#[test]
+fn setting_changes_the_start_fingerprint() {
+    let config = Config::default();
+    let forwarded = Config { host_resolver_dns: true, ..Config::default() };
+    assert_ne!(config.start_fingerprint(), forwarded.start_fingerprint());
+}
@@ -0,0 +1,58 @@
+//! Following USB adapters that come back under another interface name.
+
+use std::collections::HashMap;
+
+use cosmic_hotspot::config::Config;
+
+/// Lookups over a fixed set of (name, MAC) interfaces
+fn present(interfaces: &[(&str, &str)]) -> (impl Fn(&str) -> Option<String>, impl Fn(&str) -> Option<String>) {
+    let by_name: HashMap<String, String> =
+        interfaces.iter().map(|(n, m)| (n.to_string(), m.to_string())).collect();
+    let by_mac: HashMap<String, String> =
+        interfaces.iter().map(|(n, m)| (m.to_string(), n.to_string())).collect();
+    (move |name: &str| by_name.get(name).cloned(), move |mac: &str| by_mac.get(mac).cloned())
+}
+
+#[test]
+fn records_the_macs_of_present_interfaces() {
+    let mut config = Config {
+        hotspot_interface: "wlx00c0ca123456".to_string(),
+        internet_interface: "wlp2s0".to_string(),
+        ..Config::default()
+    };
+    let (mac_of, find_mac) = present(&[("wlx00c0ca123456", "00:c0:ca:12:34:56"), ("wlp2s0", "a4:c3:f0:00:00:01")]);
+    assert!(config.resolve_interfaces(mac_of, find_mac).is_empty());
+    assert_eq!(config.interface_macs["wlx00c0ca123456"], "00:c0:ca:12:34:56");
+    assert_eq!(config.interface_macs["wlp2s0"], "a4:c3:f0:00:00:01");
+}
+
+#[test]
+fn follows_a_renamed_adapter() {
+    let mut config = Config {
+        hotspot_interface: "wlan1".to_string(),
+        internet_interface: "wlp2s0".to_string(),
+        ..Config::default()
+    };
+    config.interface_macs.insert("wlan1".to_string(), "00:c0:ca:12:34:56".to_string());
+    config.interface_macs.insert("wlp2s0".to_string(), "a4:c3:f0:00:00:01".to_string());
+
+    // Replugged: same MAC, new name
+    let (mac_of, find_mac) = present(&[("wlx00c0ca123456", "00:c0:ca:12:34:56"), ("wlp2s0", "a4:c3:f0:00:00:01")]);
+    let renames = config.resolve_interfaces(mac_of, find_mac);
+    assert_eq!(renames, [("wlan1".to_string(), "wlx00c0ca123456".to_string())]);
+    assert_eq!(config.hotspot_interface, "wlx00c0ca123456");
+    assert_eq!(config.internet_interface, "wlp2s0");
+    // The old name is forgotten
+    assert!(!config.interface_macs.contains_key("wlan1"));
+    assert_eq!(config.interface_macs["wlx00c0ca123456"], "00:c0:ca:12:34:56");
+}
+
+#[test]
+fn leaves_unknown_or_unplugged_adapters_alone() {
+    let mut config = Config { hotspot_interface: "wlan1".to_string(), ..Config::default() };
+    config.interface_macs.insert("wlan1".to_string(), "00:c0:ca:12:34:56".to_string());
+    let (mac_of, find_mac) = present(&[("wlp2s0", "a4:c3:f0:00:00:01")]);
+    assert!(config.resolve_interfaces(mac_of, find_mac).is_empty());
+    assert_eq!(config.hotspot_interface, "wlan1");
+    assert_eq!(config.interface_macs["wlan1"], "00:c0:ca:12:34:56");
+}
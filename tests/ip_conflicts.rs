@@ -0,0 +1,72 @@
+//! Clients using the gateway's address or each other's.
+
+use std::time::{Duration, Instant};
+
+use cosmic_hotspot::ip_conflicts::{parse_arping_replies, parse_neighbours, Conflict, ConflictTracker, CONFLICT_WINDOW};
+
+const NEIGH: &str = "\
+192.168.44.23 lladdr AA:BB:CC:00:00:01 REACHABLE
+192.168.44.40 lladdr aa:bb:cc:00:00:02 STALE
+192.168.44.41  FAILED
+192.168.44.42  INCOMPLETE
+";
+
+const ARPING: &str = "\
+ARPING 192.168.44.1 from 0.0.0.0 wlan0
+Unicast reply from 192.168.44.1 [DE:AD:BE:EF:00:01]  1.482ms
+Sent 1 probes (1 broadcast(s))
+Received 1 response(s)
+";
+
+fn pair(ip: &str, mac: &str) -> (String, String) {
+    (ip.to_string(), mac.to_string())
+}
+
+#[test]
+fn parses_neighbours_and_arping_replies() {
+    assert_eq!(
+        parse_neighbours(NEIGH),
+        vec![pair("192.168.44.23", "aa:bb:cc:00:00:01"), pair("192.168.44.40", "aa:bb:cc:00:00:02")]
+    );
+    assert_eq!(parse_arping_replies(ARPING), vec!["de:ad:be:ef:00:01".to_string()]);
+    assert!(parse_arping_replies("Sent 1 probes\nReceived 0 response(s)\n").is_empty());
+}
+
+#[test]
+fn reports_each_conflict_once_until_it_ages_out() {
+    let mut tracker = ConflictTracker::new();
+    let start = Instant::now();
+    let a = pair("192.168.44.23", "aa:bb:cc:00:00:01");
+    let b = pair("192.168.44.23", "aa:bb:cc:00:00:02");
+
+    assert!(tracker.observe(std::slice::from_ref(&a), "192.168.44.1", &[], start).is_empty());
+    let new = tracker.observe(std::slice::from_ref(&b), "192.168.44.1", &[], start + Duration::from_secs(2));
+    let duplicate = Conflict::Duplicate {
+        ip: "192.168.44.23".to_string(),
+        macs: ["aa:bb:cc:00:00:01".to_string(), "aa:bb:cc:00:00:02".to_string()],
+    };
+    assert_eq!(new, vec![duplicate.clone()]);
+    // Flipping back is the same conflict
+    assert!(tracker.observe(&[a], "192.168.44.1", &[], start + Duration::from_secs(4)).is_empty());
+    assert_eq!(tracker.current(), vec![duplicate]);
+
+    let claimants = ["de:ad:be:ef:00:01".to_string()];
+    let new = tracker.observe(&[], "192.168.44.1", &claimants, start + Duration::from_secs(6));
+    assert!(new[0].message().contains("de:ad:be:ef:00:01"));
+    assert_eq!(tracker.current().len(), 2);
+
+    let later = start + Duration::from_secs(6) + CONFLICT_WINDOW;
+    assert!(tracker.observe(&[], "192.168.44.1", &[], later).is_empty());
+    assert!(tracker.current().is_empty());
+}
+
+#[test]
+fn an_address_reused_long_after_is_no_conflict() {
+    let mut tracker = ConflictTracker::new();
+    let start = Instant::now();
+    tracker.observe(&[pair("192.168.44.23", "aa:bb:cc:00:00:01")], "192.168.44.1", &[], start);
+    let later = start + CONFLICT_WINDOW + Duration::from_secs(1);
+    assert!(tracker
+        .observe(&[pair("192.168.44.23", "aa:bb:cc:00:00:02")], "192.168.44.1", &[], later)
+        .is_empty());
+}
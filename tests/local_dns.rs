@@ -0,0 +1,71 @@
+//! Client names under the hotspot's `.hotspot` domain.
+
+use cosmic_hotspot::clients::{self, ClientInfo, ClientSort, ClientState};
+use cosmic_hotspot::config::Config;
+use cosmic_hotspot::local_dns;
+use cosmic_hotspot::nat::NatRequest;
+
+const LEASES: &str = "\
+1760000000 aa:bb:cc:00:00:17 192.168.44.17 quest 01:aa:bb:cc:00:00:17
+1760000100 aa:bb:cc:00:00:23 192.168.44.23 * *
+1760000200 aa:bb:cc:00:00:31 192.168.44.31 Pixel-7 *
+";
+
+#[test]
+fn hostnames_come_from_the_lease_file() {
+    let hostnames = local_dns::parse_leases(LEASES);
+    assert_eq!(hostnames.len(), 2);
+    assert_eq!(hostnames["192.168.44.17"], "quest");
+    assert_eq!(hostnames["192.168.44.31"], "Pixel-7");
+    assert!(local_dns::parse_leases("garbage\n").is_empty());
+}
+
+#[test]
+fn only_valid_labels_resolve() {
+    assert_eq!(local_dns::dns_name("Pixel-7").as_deref(), Some("pixel-7.hotspot"));
+    assert_eq!(local_dns::dns_name("Bob's iPhone"), None);
+    assert_eq!(local_dns::dns_name("-x"), None);
+    assert_eq!(local_dns::dns_name(""), None);
+}
+
+#[test]
+fn names_need_the_gateway_resolver() {
+    let mut config = Config { local_domain: true, ..Config::default() };
+    assert!(config.serves_local_domain());
+    config.profile_mut().dns_server = "1.1.1.1".to_string();
+    assert!(!config.serves_local_domain());
+
+    for enable in [true, false] {
+        let request = NatRequest::LocalDomain { enable };
+        assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    }
+}
+
+#[test]
+fn client_filter_matches_hostnames() {
+    let list = vec![
+        ClientInfo { ip: "192.168.44.17".into(), mac: None, vendor: None, hostname: Some("quest".into()), radio: None, state: ClientState::Active, first_seen: None },
+        ClientInfo { ip: "192.168.44.23".into(), mac: None, vendor: None, hostname: None, radio: None, state: ClientState::Active, first_seen: None },
+    ];
+    let found = clients::filter_and_sort(&list, "Quest", ClientSort::Address);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].ip, "192.168.44.17");
+}
+
+#[test]
+fn clients_are_shown_by_lease_hostname() {
+    let client = |hostname: Option<&str>| ClientInfo {
+        ip: "192.168.44.31".into(),
+        mac: None,
+        vendor: None,
+        hostname: hostname.map(Into::into),
+        radio: None,
+        state: ClientState::Active,
+        first_seen: None,
+    };
+    assert_eq!(client(Some("Pixel-7")).display_name(false), "Pixel-7 (192.168.44.31)");
+    assert_eq!(client(Some("Pixel-7")).display_name(true), "pixel-7.hotspot (192.168.44.31)");
+    // Not a DNS label, so it only resolves by its lease name
+    assert_eq!(client(Some("Bob's iPhone")).display_name(true), "Bob's iPhone (192.168.44.31)");
+    assert_eq!(client(None).display_name(true), "192.168.44.31");
+}
@@ -0,0 +1,55 @@
+//! Step timings of a start and their Prometheus rendering.
+
+use cosmic_hotspot::metrics;
+use cosmic_hotspot::progress::{self, Step, StepTiming};
+use cosmic_hotspot::run_state::Timings;
+
+#[test]
+fn steps_are_timed_until_the_next_one() {
+    progress::start_timing();
+    progress::report(Step::CreatingProfile);
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    progress::report(Step::Activating);
+    progress::report(Step::ApplyingNat);
+
+    let timings = progress::finish_timing();
+    let steps: Vec<Step> = timings.iter().map(|t| t.step).collect();
+    assert_eq!(steps, [Step::CreatingProfile, Step::Activating, Step::ApplyingNat]);
+    assert!(timings[0].millis >= 20, "{timings:?}");
+    // Taken, so the next start begins empty
+    assert!(progress::finish_timing().is_empty());
+}
+
+#[test]
+fn metrics_cover_state_and_the_last_start() {
+    let timings = Timings {
+        at: 1_760_000_000,
+        succeeded: true,
+        steps: vec![
+            StepTiming { step: Step::CreatingProfile, millis: 120 },
+            StepTiming { step: Step::Activating, millis: 4_250 },
+        ],
+    };
+    assert_eq!(timings.millis(Step::Activating), Some(4_250));
+    assert_eq!(timings.millis(Step::Verifying), None);
+    assert_eq!(timings.total_millis(), 4_370);
+
+    let text = metrics::render(true, 3, Some(&timings));
+    for line in [
+        "# TYPE cosmic_hotspot_up gauge",
+        "cosmic_hotspot_up 1",
+        "cosmic_hotspot_clients 3",
+        "cosmic_hotspot_start_step_seconds{step=\"creating_profile\"} 0.120",
+        "cosmic_hotspot_start_step_seconds{step=\"activating\"} 4.250",
+        "cosmic_hotspot_start_seconds 4.370",
+        "cosmic_hotspot_start_succeeded 1",
+        "cosmic_hotspot_start_timestamp_seconds 1760000000",
+    ] {
+        assert!(text.lines().any(|l| l == line), "missing {line:?} in:\n{text}");
+    }
+
+    // Nothing recorded since boot
+    let text = metrics::render(false, 0, None);
+    assert!(text.contains("cosmic_hotspot_up 0"));
+    assert!(!text.contains("start_seconds"));
+}
@@ -0,0 +1,381 @@
+//! Requests to the privileged helper: argument parsing and the commands
+//! each one runs, plus the values the callers build them from.
+
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+use cosmic_hotspot::adapters::usb_device_name;
+use cosmic_hotspot::config::{ClientPolicy, Config};
+use cosmic_hotspot::hostapd_backend::{access_point, default_dhcp_range};
+use cosmic_hotspot::hotspot::effective_nat_strategy;
+use cosmic_hotspot::nat::{self, ClientAccess, Firewall, NatRequest, HOSTAPD_CTRL_DIR};
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+/// Each rule as the shell line it stands for
+fn described(request: &NatRequest) -> Vec<String> {
+    request.rules().iter().map(|r| r.describe()).collect()
+}
+
+#[test]
+fn iptables_setup_adds_only_missing_rules() {
+    let setup = NatRequest::parse(&args(&["setup", "wlan0", "eth0"])).unwrap();
+    assert_eq!(
+        setup,
+        NatRequest::Setup { hotspot: "wlan0".to_string(), internet: "eth0".to_string(), firewall: Firewall::Iptables }
+    );
+    assert_eq!(NatRequest::parse(&setup.to_args()).unwrap(), setup);
+    assert_eq!(described(&setup), [
+        "sysctl -w net.ipv4.ip_forward=1",
+        "iptables -t nat -C POSTROUTING -o eth0 -j MASQUERADE || iptables -t nat -A POSTROUTING -o eth0 -j MASQUERADE",
+        "iptables -C FORWARD -i wlan0 -o eth0 -j ACCEPT || iptables -A FORWARD -i wlan0 -o eth0 -j ACCEPT",
+        "iptables -C FORWARD -i eth0 -o wlan0 -m state --state RELATED,ESTABLISHED -j ACCEPT \
+         || iptables -A FORWARD -i eth0 -o wlan0 -m state --state RELATED,ESTABLISHED -j ACCEPT",
+    ]);
+}
+
+#[test]
+fn iptables_teardown_and_check_cover_the_same_rules() {
+    let teardown = NatRequest::parse(&args(&["teardown", "wlan0", "eth0", "iptables"])).unwrap();
+    let removals = described(&teardown);
+    assert_eq!(removals.len(), 3);
+    assert!(removals.iter().all(|r| r.starts_with("iptables") && r.contains(" && ") && r.contains(" -D ")));
+
+    let check = NatRequest::parse(&args(&["check", "wlan0", "eth0"])).unwrap();
+    let checks: Vec<Vec<String>> = check.rules().into_iter().map(|r| r.command).collect();
+    assert_eq!(checks[0], args(&["iptables", "-t", "nat", "-C", "POSTROUTING", "-o", "eth0", "-j", "MASQUERADE"]));
+    assert_eq!(checks.len(), 3);
+    assert!(checks.iter().all(|c| c.contains(&"-C".to_string())));
+}
+
+#[test]
+fn nftables_rules_live_in_their_own_table() {
+    let setup = NatRequest::parse(&args(&["setup", "wlan0", "eth0", "nftables"])).unwrap();
+    let commands = described(&setup);
+    assert!(commands.contains(&"nft flush table ip cosmic_hotspot".to_string()));
+    assert!(commands.contains(&"nft add rule ip cosmic_hotspot postrouting oifname eth0 masquerade".to_string()));
+    assert!(commands.contains(&"nft add rule ip cosmic_hotspot forward iifname wlan0 oifname eth0 accept".to_string()));
+
+    let teardown = NatRequest::parse(&args(&["teardown", "wlan0", "eth0", "nftables"])).unwrap();
+    assert_eq!(described(&teardown), ["nft delete table ip cosmic_hotspot || true"]);
+    let check = NatRequest::parse(&args(&["check", "wlan0", "eth0", "nftables"])).unwrap();
+    assert_eq!(described(&check), ["nft list table ip cosmic_hotspot"]);
+}
+
+#[test]
+fn rejects_arguments_that_are_not_plain_values() {
+    for bad in [
+        args(&[]),
+        args(&["setup"]),
+        args(&["setup", "wlan0"]),
+        args(&["setup", "wlan0", "eth0", "iptables", "extra"]),
+        args(&["setup", "wlan0", "eth0", "pf"]),
+        args(&["setup", "-wlan0", "eth0"]),
+        args(&["setup", "wlan0", "eth0;reboot"]),
+        args(&["setup", "wlan0", "eth 0"]),
+        args(&["setup", "", "eth0"]),
+        args(&["teardown", "wlan0", "a-very-long-interface"]),
+        args(&["check", "wlan0", "../eth0"]),
+        args(&["flush", "wlan0", "eth0"]),
+    ] {
+        assert!(NatRequest::parse(&bad).is_err(), "{bad:?} accepted");
+    }
+}
+
+// Hostapd backend: start/stop requests and the config they write
+
+fn hostapd_config() -> Config {
+    Config {
+        backend: "hostapd".to_string(),
+        hotspot_interface: "wlan0".to_string(),
+        ssid: "Cafe".to_string(),
+        password: "correct horse".to_string(),
+        ..Config::default()
+    }
+}
+
+#[test]
+fn start_request_round_trips_and_writes_the_config_first() {
+    let request = NatRequest::parse(&args(&[
+        "hostapd", "start", "wlan0", "Cafe", "correct horse", "wpa3", "a", "0",
+        "192.168.44.1/24", "192.168.44.10", "192.168.44.254",
+    ]))
+    .unwrap();
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+
+    let commands: Vec<String> = request.rules().iter().map(|r| r.command[0].clone()).collect();
+    assert_eq!(commands, ["mkdir", "sh", "nmcli", "ip", "hostapd", "ip", "dnsmasq"]);
+
+    let NatRequest::HostapdStart { ap } = request else { panic!("not a start") };
+    let conf = ap.hostapd_conf();
+    assert!(conf.contains(&"wpa_key_mgmt=SAE".to_string()));
+    assert!(conf.contains(&"ieee80211w=2".to_string()));
+    // Channel 0 means the band's usual default
+    assert!(conf.contains(&"hw_mode=a".to_string()));
+    assert!(conf.contains(&"channel=36".to_string()));
+    assert!(ap.dnsmasq_command().contains(&"--dhcp-range=192.168.44.10,192.168.44.254,12h".to_string()));
+}
+
+#[test]
+fn rejects_values_that_would_break_the_config_file() {
+    let start = |ssid: &str, password: &str| {
+        NatRequest::parse(&args(&[
+            "hostapd", "start", "wlan0", ssid, password, "wpa2", "bg", "6",
+            "192.168.44.1/24", "192.168.44.10", "192.168.44.254",
+        ]))
+    };
+    assert!(start("Cafe", "correct horse").is_ok());
+    assert!(start("Cafe\nctrl_interface=/tmp", "correct horse").is_err());
+    assert!(start("", "correct horse").is_err());
+    assert!(start("Cafe", "short").is_err());
+    assert!(start("Cafe", "pass\nwpa=0 more").is_err());
+    assert!(NatRequest::parse(&args(&["hostapd", "stop", "wlan0; reboot"])).is_err());
+}
+
+#[test]
+fn hands_out_the_range_nm_shared_mode_would() {
+    let gateway = Ipv4Addr::new(192, 168, 44, 1);
+    assert_eq!(default_dhcp_range(gateway, 24), (Ipv4Addr::new(192, 168, 44, 10), Ipv4Addr::new(192, 168, 44, 254)));
+    assert_eq!(default_dhcp_range(gateway, 28), (Ipv4Addr::new(192, 168, 44, 2), Ipv4Addr::new(192, 168, 44, 14)));
+
+    let ap = access_point(&hostapd_config()).unwrap();
+    assert_eq!((ap.gateway, ap.prefix), (gateway, 24));
+    assert_eq!(ap.dhcp_start, Ipv4Addr::new(192, 168, 44, 10));
+    assert!(access_point(&Config { password: "short".to_string(), ..hostapd_config() }).is_err());
+}
+
+#[test]
+fn always_adds_explicit_nat_rules() {
+    let config = hostapd_config();
+    assert_ne!(effective_nat_strategy(&config), "nm-shared");
+    assert_eq!(Config::default().backend, "networkmanager");
+    assert_ne!(config.start_fingerprint(), Config { backend: "networkmanager".to_string(), ..config.clone() }.start_fingerprint());
+}
+
+// WPS push-button
+
+#[test]
+fn wps_requests_drive_hostapd_cli() {
+    let request = NatRequest::parse(&args(&["wps", "on", "wlan0"])).unwrap();
+    assert_eq!(request, NatRequest::Wps { hotspot: "wlan0".to_string(), enable: true });
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    let rules = request.rules();
+    assert_eq!(rules[0].command, ["hostapd_cli", "-p", HOSTAPD_CTRL_DIR, "-i", "wlan0", "wps_pbc"]);
+    let cancel = NatRequest::parse(&args(&["wps", "off", "wlan0"])).unwrap();
+    assert_eq!(cancel.rules()[0].command.last().unwrap(), "wps_cancel");
+    assert!(NatRequest::parse(&args(&["wps", "on", "-wlan0"])).is_err());
+}
+
+#[test]
+fn hostapd_conf_enables_push_button_except_for_wpa3_only() {
+    let config = Config { security: "wpa2".to_string(), ..hostapd_config() };
+    let conf = access_point(&config).unwrap().hostapd_conf();
+    assert!(conf.contains(&format!("ctrl_interface={HOSTAPD_CTRL_DIR}")));
+    assert!(conf.contains(&"wps_state=2".to_string()));
+    assert!(conf.contains(&"config_methods=push_button".to_string()));
+
+    let sae = access_point(&Config { security: "wpa3".to_string(), ..config }).unwrap();
+    assert!(!sae.supports_wps());
+    assert!(sae.hostapd_conf().iter().all(|line| !line.starts_with("wps_")));
+}
+
+// Client access policies and priority
+
+#[test]
+fn port_lists_are_validated() {
+    assert_eq!(
+        nat::parse_port_list("tcp:22, udp:3478,").unwrap(),
+        [("tcp".to_string(), 22), ("udp".to_string(), 3478)]
+    );
+    assert!(nat::parse_port_list("").is_err());
+    assert!(nat::parse_port_list("22").is_err());
+    assert!(nat::parse_port_list("icmp:1").is_err());
+    assert!(nat::parse_port_list("tcp:0").is_err());
+}
+
+#[test]
+fn requests_round_trip_through_helper_arguments() {
+    let request = NatRequest::ClientAccess {
+        hotspot: "wlan0".to_string(),
+        internet: "eth0".to_string(),
+        mac: "aa:bb:cc:dd:ee:ff".to_string(),
+        access: ClientAccess::BlockPorts(vec![("tcp".to_string(), 22), ("udp".to_string(), 53)]),
+        remove: false,
+    };
+    assert_eq!(
+        request.to_args(),
+        args(&["client", "add", "wlan0", "eth0", "aa:bb:cc:dd:ee:ff", "block-ports", "tcp:22,udp:53"])
+    );
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+}
+
+#[test]
+fn bad_client_arguments_are_rejected() {
+    for bad in [
+        args(&["client", "add", "wlan0", "eth0", "not-a-mac", "no-internet"]),
+        args(&["client", "add", "wlan0", "eth0", "aa:bb:cc:dd:ee:ff", "everything"]),
+        args(&["client", "add", "wlan0", "eth0", "aa:bb:cc:dd:ee:ff", "no-internet", "tcp:22"]),
+        args(&["client", "add", "wlan0", "eth0", "aa:bb:cc:dd:ee:ff", "block-ports"]),
+    ] {
+        assert!(NatRequest::parse(&bad).is_err(), "{bad:?} accepted");
+    }
+}
+
+#[test]
+fn web_only_accepts_web_before_dropping_the_rest() {
+    let request = NatRequest::parse(&args(&[
+        "client", "add", "wlan0", "eth0", "AA:BB:CC:DD:EE:FF", "web-only",
+    ]))
+    .unwrap();
+    let rules = request.rules();
+    // Inserted at the top one by one, so the catch-all drop goes in first
+    assert!(rules[0].command.ends_with(&args(&["-j", "DROP"])));
+    assert!(rules[0].command.contains(&"aa:bb:cc:dd:ee:ff".to_string()));
+    assert!(rules[1..].iter().all(|r| r.command.ends_with(&args(&["-j", "ACCEPT"]))));
+    assert!(rules.iter().all(|r| r.command[1] == "-I"));
+}
+
+#[test]
+fn removing_deletes_only_present_rules() {
+    let request = NatRequest::parse(&args(&[
+        "client", "remove", "wlan0", "eth0", "aa:bb:cc:dd:ee:ff", "no-internet",
+    ]))
+    .unwrap();
+    let rules = request.rules();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].command[1], "-D");
+    assert!(rules[0].only_if.is_some());
+}
+
+#[test]
+fn ports_only_matter_for_block_ports() {
+    let policy = ClientPolicy {
+        mac: "aa:bb:cc:dd:ee:ff".to_string(),
+        access: "web-only".to_string(),
+        blocked_ports: "tcp:22".to_string(),
+        ..ClientPolicy::default()
+    };
+    assert_eq!(policy.client_access().unwrap(), Some(ClientAccess::WebOnly));
+    let policy = ClientPolicy { access: "block-ports".to_string(), blocked_ports: String::new(), ..policy };
+    assert!(policy.client_access().is_err());
+}
+
+#[test]
+fn priority_filters_follow_the_mac() {
+    let request = NatRequest::parse(&args(&["priority", "add", "wlan0", "AA:BB:CC:DD:EE:FF"])).unwrap();
+    assert_eq!(request.to_args(), args(&["priority", "add", "wlan0", "aa:bb:cc:dd:ee:ff"]));
+    let rules = request.rules();
+    // The shared prio qdisc may already exist
+    assert!(rules[0].ignore_failure);
+    assert!(rules[1].command.windows(2).any(|w| w == ["dst_mac", "aa:bb:cc:dd:ee:ff"]));
+    assert!(rules[1].command.windows(2).any(|w| w == ["handle", "0xccddeeff"]));
+
+    let removal = NatRequest::parse(&args(&["priority", "remove", "wlan0", "aa:bb:cc:dd:ee:ff"])).unwrap();
+    let rules = removal.rules();
+    assert_eq!(rules.len(), 1);
+    assert!(rules[0].command.windows(2).any(|w| w == ["handle", "0xccddeeff"]));
+    assert!(NatRequest::parse(&args(&["priority", "add", "wlan0", "not-a-mac"])).is_err());
+}
+
+#[test]
+fn a_priority_only_policy_is_kept() {
+    let policy = ClientPolicy { mac: "aa:bb:cc:dd:ee:ff".to_string(), priority: true, ..ClientPolicy::default() };
+    assert!(policy.restricts());
+    assert!(!ClientPolicy { priority: false, ..policy }.restricts());
+}
+
+// Kicking a client off
+
+#[test]
+fn deauth_requests_drive_iw() {
+    let request = NatRequest::parse(&args(&["deauth", "wlan0", "3C:22:FB:12:34:56"])).unwrap();
+    // MACs are normalized to lowercase
+    assert_eq!(
+        request,
+        NatRequest::Deauth { hotspot: "wlan0".to_string(), mac: "3c:22:fb:12:34:56".to_string() }
+    );
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    assert_eq!(request.rules()[0].command, ["iw", "dev", "wlan0", "station", "del", "3c:22:fb:12:34:56"]);
+
+    assert!(NatRequest::parse(&args(&["deauth", "wlan0", "ff:ff:ff:ff:ff"])).is_err());
+    assert!(NatRequest::parse(&args(&["deauth", "wlan0", "all"])).is_err());
+    assert!(NatRequest::parse(&args(&["deauth", "-wlan0", "3c:22:fb:12:34:56"])).is_err());
+}
+
+// Multicast
+
+#[test]
+fn multicast_requests_drive_iw() {
+    let request = NatRequest::parse(&args(&["multicast", "wlan0", "unicast", "24"])).unwrap();
+    assert_eq!(request, NatRequest::Multicast { hotspot: "wlan0".to_string(), to_unicast: true, rate_mbps: 24 });
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+    let rules = request.rules();
+    assert_eq!(rules[0].command, args(&["iw", "dev", "wlan0", "set", "multicast_to_unicast", "on"]));
+    assert_eq!(rules[1].command, args(&["iw", "dev", "wlan0", "set", "mcast_rate", "24"]));
+
+    // The driver's default rate is left alone
+    let reset = NatRequest::parse(&args(&["multicast", "wlan0", "multicast", "0"])).unwrap();
+    assert_eq!(reset.rules().len(), 1);
+    assert_eq!(reset.rules()[0].command.last().unwrap(), "off");
+}
+
+#[test]
+fn rejects_rates_the_radio_cannot_send() {
+    for rate in ["5", "100", "-1", "fast"] {
+        assert!(NatRequest::parse(&args(&["multicast", "wlan0", "unicast", rate])).is_err(), "{rate}");
+    }
+}
+
+// USB autosuspend
+
+#[test]
+fn finds_the_usb_device_of_an_adapter() {
+    let usb = Path::new("/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0");
+    assert_eq!(usb_device_name(usb).as_deref(), Some("1-2"));
+    // Behind a hub
+    let hub = Path::new("/sys/devices/pci0000:00/0000:00:14.0/usb3/3-1/3-1.4/3-1.4:1.0");
+    assert_eq!(usb_device_name(hub).as_deref(), Some("3-1.4"));
+    // Built-in PCI adapters have no USB device
+    assert_eq!(usb_device_name(Path::new("/sys/devices/pci0000:00/0000:00:1c.0/0000:02:00.0")), None);
+}
+
+#[test]
+fn autosuspend_requests_write_power_control() {
+    let hold = NatRequest::parse(&args(&["autosuspend", "off", "1-2"])).unwrap();
+    assert_eq!(hold, NatRequest::UsbAutosuspend { device: "1-2".to_string(), enable: false });
+    assert_eq!(NatRequest::parse(&hold.to_args()).unwrap(), hold);
+    let command = &hold.rules()[0].command;
+    assert!(command.contains(&"/sys/bus/usb/devices/1-2/power/control".to_string()));
+    assert_eq!(command.last().unwrap(), "on");
+
+    let release = NatRequest::parse(&args(&["autosuspend", "on", "3-1.4"])).unwrap();
+    assert_eq!(release.rules()[0].command.last().unwrap(), "auto");
+
+    for device in ["../../etc", "1-", "usb1", "1-2;reboot", "-1"] {
+        assert!(NatRequest::parse(&args(&["autosuspend", "off", device])).is_err(), "{device}");
+    }
+}
+
+#[test]
+fn forwards_and_dns_redirects_stay_inside_the_hotspot_subnet() {
+    let forward = NatRequest::parse(&args(&["forward", "add", "eth0", "tcp", "8080", "192.168.44.20", "80", "192.168.44.1/24"]))
+        .unwrap();
+    assert_eq!(NatRequest::parse(&forward.to_args()).unwrap(), forward);
+    let dns = NatRequest::parse(&args(&["dns", "add", "wlan0", "192.168.44.53", "192.168.44.1/24"])).unwrap();
+    assert_eq!(NatRequest::parse(&dns.to_args()).unwrap(), dns);
+
+    for rejected in [
+        // The LAN, the internet, the subnet's own network and broadcast
+        &["forward", "add", "eth0", "tcp", "8080", "192.168.1.10", "80", "192.168.44.1/24"][..],
+        &["forward", "add", "eth0", "tcp", "8080", "192.168.44.255", "80", "192.168.44.1/24"],
+        &["dns", "add", "wlan0", "1.1.1.1", "192.168.44.1/24"],
+        &["dns", "add", "wlan0", "192.168.44.0", "192.168.44.1/24"],
+        // A "subnet" that covers everything, or none at all
+        &["dns", "add", "wlan0", "1.1.1.1", "0.0.0.0/0"],
+        &["dns", "add", "wlan0", "192.168.44.53"],
+    ] {
+        assert!(NatRequest::parse(&args(rejected)).is_err(), "{rejected:?}");
+    }
+}
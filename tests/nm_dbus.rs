@@ -0,0 +1,141 @@
+//! The hotspot's NetworkManager connection settings and how failed
+//! NetworkManager calls are reported.
+
+use cosmic_hotspot::config::{Config, Profile};
+use cosmic_hotspot::errors::{ErrorKind, HotspotError};
+use cosmic_hotspot::hotspot;
+use cosmic_hotspot::nm_dbus::NmError;
+use cosmic_hotspot::repeater;
+use zbus::zvariant::Value;
+
+/// A config that never reaches the privileged helper or real interfaces
+fn test_config() -> Config {
+    Config {
+        hotspot_interface: "fakewlan9".to_string(),
+        internet_interface: "fakeeth9".to_string(),
+        profiles: vec![Profile {
+            nat_strategy: "nm-shared".to_string(),
+            ..Profile::default()
+        }],
+        verify_after_start: false,
+        ..Config::default()
+    }
+}
+
+fn rejected(name: &str, message: &str) -> NmError {
+    NmError::Rejected { name: name.to_string(), message: message.to_string() }
+}
+
+#[test]
+fn hotspot_connection_is_a_shared_access_point() {
+    let config = test_config();
+    let settings = hotspot::hotspot_settings(&config, "CosmicHotspot");
+
+    assert_eq!(settings["connection"]["id"], Value::from("CosmicHotspot"));
+    assert_eq!(settings["connection"]["type"], Value::from("802-11-wireless"));
+    assert_eq!(settings["connection"]["interface-name"], Value::from("fakewlan9"));
+    assert_eq!(settings["connection"]["autoconnect"], Value::from(false));
+    assert_eq!(settings["802-11-wireless"]["mode"], Value::from("ap"));
+    assert_eq!(settings["802-11-wireless"]["ssid"], Value::from(config.ssid.as_bytes().to_vec()));
+    assert_eq!(settings["802-11-wireless-security"]["psk"], Value::from(config.password.clone()));
+    assert_eq!(settings["ipv4"]["method"], Value::from("shared"));
+    assert_eq!(settings["ipv6"]["method"], Value::from("disabled"));
+
+    let Value::Array(addresses) = &settings["ipv4"]["address-data"] else { panic!("no addresses") };
+    assert_eq!(addresses.len(), 1);
+}
+
+#[test]
+fn profile_dhcp_range_and_isolation_are_applied() {
+    let mut config = test_config();
+    config.profiles[0].gateway_ip = "10.42.7.1/24".to_string();
+    config.profiles[0].dhcp_range_start = "10.42.7.50".to_string();
+    config.profiles[0].dhcp_range_end = "10.42.7.99".to_string();
+    config.profiles[0].client_isolation = true;
+    config.channel = 11;
+
+    let settings = hotspot::hotspot_settings(&config, "CosmicHotspot");
+    assert_eq!(settings["ipv4"]["shared-dhcp-range"], Value::from("10.42.7.50,10.42.7.99"));
+    assert_eq!(settings["802-11-wireless"]["ap-isolation"], Value::from(1i32));
+    assert_eq!(settings["802-11-wireless"]["channel"], Value::from(11u32));
+}
+
+#[test]
+fn unset_profile_options_are_omitted() {
+    let settings = hotspot::hotspot_settings(&test_config(), "CosmicHotspot");
+    assert!(!settings["ipv4"].contains_key("shared-dhcp-range"));
+    assert!(!settings["802-11-wireless"].contains_key("ap-isolation"));
+    // Channel 0 lets NetworkManager choose
+    assert!(!settings["802-11-wireless"].contains_key("channel"));
+}
+
+#[test]
+fn weird_ssid_and_names_pass_through_verbatim() {
+    let config = Config {
+        ssid: "Dave's \"Hot\" spot; $(rm -rf ~) ☕".to_string(),
+        password: "pa ss:word'\\".to_string(),
+        autoconnect: true,
+        ..test_config()
+    };
+
+    let settings = hotspot::hotspot_settings(&config, "Dave's Hotspot");
+    assert_eq!(settings["connection"]["id"], Value::from("Dave's Hotspot"));
+    assert_eq!(settings["connection"]["autoconnect"], Value::from(true));
+    assert_eq!(settings["802-11-wireless"]["ssid"], Value::from(config.ssid.as_bytes().to_vec()));
+    assert_eq!(settings["802-11-wireless-security"]["psk"], Value::from(config.password.clone()));
+}
+
+#[test]
+fn failures_are_classified_by_error_name_and_reason() {
+    let unreachable = HotspotError::from_nm(NmError::Unavailable("no bus".to_string()), ErrorKind::CreateFailed);
+    assert_eq!(unreachable.kind, ErrorKind::NetworkManagerUnavailable);
+
+    let invalid = rejected("org.freedesktop.NetworkManager.Settings.Connection.InvalidProperty", "802-11-wireless.band: invalid");
+    let err = HotspotError::from_nm(invalid, ErrorKind::CreateFailed);
+    assert_eq!(err.kind, ErrorKind::CreateFailed);
+    assert!(err.detail.contains("802-11-wireless.band"), "message not surfaced: {err}");
+
+    let no_device = rejected("org.freedesktop.NetworkManager.ConnectionNotAvailable", "No suitable device found");
+    assert_eq!(HotspotError::from_nm(no_device, ErrorKind::ActivationFailed).kind, ErrorKind::DeviceUnavailable);
+
+    // NMActiveConnectionStateReason 14: device removed; 9: no secrets
+    let removed = HotspotError::from_nm(NmError::ActivationFailed(14), ErrorKind::ActivationFailed);
+    assert_eq!(removed.kind, ErrorKind::DeviceUnavailable);
+    let secrets = HotspotError::from_nm(NmError::ActivationFailed(9), ErrorKind::ActivationFailed);
+    assert_eq!(secrets.kind, ErrorKind::ActivationFailed);
+    assert!(secrets.detail.contains("secrets"), "reason not surfaced: {secrets}");
+}
+
+#[test]
+fn invalid_connection_names_are_rejected_before_networkmanager() {
+    let too_long = "x".repeat(65);
+    for name in ["", "   ", "line\nbreak", too_long.as_str()] {
+        // Dry run, so nothing could change even if the name got through
+        let config = Config { connection_name: name.to_string(), dry_run: true, ..test_config() };
+        let err = hotspot::start_hotspot(&config).expect_err("invalid name rejected");
+        assert_eq!(err.kind, ErrorKind::InvalidSetting, "name {name:?}");
+    }
+}
+
+#[test]
+fn dry_run_starts_and_stops_without_networkmanager() {
+    let config = Config { dry_run: true, ..test_config() };
+    assert!(hotspot::start_hotspot(&config).is_ok());
+    assert!(hotspot::stop_hotspot(&config).is_ok());
+}
+
+#[test]
+fn upstream_connection_matches_the_network_security() {
+    let settings = repeater::upstream_settings("Café ☕", "upstream-pass", false);
+    assert_eq!(settings["connection"]["id"], Value::from("Café ☕"));
+    assert_eq!(settings["802-11-wireless"]["ssid"], Value::from("Café ☕".as_bytes().to_vec()));
+    assert_eq!(settings["802-11-wireless"]["mode"], Value::from("infrastructure"));
+    assert_eq!(settings["802-11-wireless-security"]["key-mgmt"], Value::from("wpa-psk"));
+    assert_eq!(settings["802-11-wireless-security"]["psk"], Value::from("upstream-pass"));
+
+    let sae = repeater::upstream_settings("Home", "upstream-pass", true);
+    assert_eq!(sae["802-11-wireless-security"]["key-mgmt"], Value::from("sae"));
+
+    // Open networks get no security setting at all
+    assert!(!repeater::upstream_settings("Library", "", false).contains_key("802-11-wireless-security"));
+}
@@ -0,0 +1,55 @@
+//! Device manufacturers from the MAC's OUI.
+
+use cosmic_hotspot::clients::Client;
+use cosmic_hotspot::oui;
+
+#[test]
+fn known_prefixes_name_the_maker() {
+    assert_eq!(oui::vendor("3c:22:fb:12:34:56"), Some("Apple"));
+    assert_eq!(oui::vendor("2C-26-17-AB-CD-EF"), Some("Meta"));
+    assert_eq!(oui::vendor("b8:27:eb:00:00:01"), Some("Raspberry Pi"));
+    // Short names win over the system registry's "Apple, Inc."
+    assert_eq!(oui::vendor("00:03:93:00:00:01"), Some("Apple"));
+    assert_eq!(oui::vendor("garbage"), None);
+    assert_eq!(oui::vendor(""), None);
+}
+
+#[test]
+fn private_addresses_have_no_vendor() {
+    // Randomised per network by phones; the locally administered bit is set
+    assert!(oui::is_local(0x02) && oui::is_local(0xda));
+    assert!(!oui::is_local(0x3c));
+    assert_eq!(oui::vendor("da:a1:19:00:00:01"), None);
+}
+
+#[test]
+fn table_lines_parse() {
+    let table = oui::parse_table("# comment\n3c22fb Apple\nB827EB Raspberry Pi\nbroken\n");
+    assert_eq!(table.len(), 2);
+    assert_eq!(table["3C22FB"], "Apple");
+    assert_eq!(table["B827EB"], "Raspberry Pi");
+}
+
+#[test]
+fn registry_lines_parse() {
+    // As in hwdata's and ieee-data's oui.txt
+    let registry = "OUI/MA-L\t\t\tOrganization\n\
+                    00-00-0C   (hex)\t\tCisco Systems, Inc\n\
+                    00000C     (base 16)\t\tCisco Systems, Inc\n\
+                    \t\t\t\t170 West Tasman Drive\n\
+                    \n\
+                    E4956E     (base 16)\t\tIEEE Registration Authority\n\
+                    XYZ123     (base 16)\t\tNot hex\n";
+    let table = oui::parse_registry(registry);
+    assert_eq!(table.len(), 2);
+    assert_eq!(table["00000C"], "Cisco Systems, Inc");
+    assert_eq!(table["E4956E"], "IEEE Registration Authority");
+}
+
+#[test]
+fn clients_carry_their_vendor() {
+    let client = Client::new("192.168.44.17", Some("3C:22:FB:12:34:56"));
+    assert_eq!(client.mac.as_deref(), Some("3c:22:fb:12:34:56"));
+    assert_eq!(client.vendor, Some("Apple"));
+    assert_eq!(Client::new("192.168.44.18", None).vendor, None);
+}
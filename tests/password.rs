@@ -0,0 +1,40 @@
+//! Tests for the weak-password audit and generator.
+
+use cosmic_hotspot::config::Config;
+use cosmic_hotspot::password::{audit, generate, Weakness, MIN_LENGTH};
+
+#[test]
+fn shipped_default_is_flagged() {
+    assert_eq!(audit("changeme123"), Some(Weakness::ShippedDefault));
+}
+
+#[test]
+fn new_installs_get_a_strong_random_password() {
+    let password = Config::default().password;
+    assert_eq!(audit(&password), None);
+    // The same for every default this process builds, so the settings page
+    // shows the password a reset saves
+    assert_eq!(Config::default().password, password);
+}
+
+#[test]
+fn common_passwords_are_flagged_regardless_of_case() {
+    assert_eq!(audit("password123"), Some(Weakness::Common));
+    assert_eq!(audit("QWERTYUIOP"), Some(Weakness::Common));
+}
+
+#[test]
+fn short_passwords_are_flagged() {
+    assert_eq!(audit("xK7p-wq2z"), Some(Weakness::TooShort));
+    assert_eq!(audit("xK7p-wq2z-Lm4"), None);
+}
+
+#[test]
+fn generated_passwords_pass_the_audit() {
+    let a = generate();
+    let b = generate();
+    assert!(a.len() >= MIN_LENGTH);
+    assert_eq!(audit(&a), None);
+    assert_ne!(a, b);
+    assert!(a.chars().all(|c| c.is_ascii_alphanumeric() && !"0O1lI".contains(c)));
+}
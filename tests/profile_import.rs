@@ -0,0 +1,128 @@
+//! Importing profiles from hostapd, wpa_supplicant and NetworkManager configs.
+
+use cosmic_hotspot::config::{Config, DEFAULT_PROFILE};
+use cosmic_hotspot::profile_import::{self, channel_for_frequency};
+
+const HOSTAPD_CONF: &str = "\
+# Hand-rolled AP
+interface=wlx00c0ca123456
+driver=nl80211
+ssid=Workshop
+hw_mode=a
+channel=44
+wpa=2
+wpa_passphrase=correct horse
+wpa_key_mgmt=WPA-PSK SAE
+";
+
+const WPA_SUPPLICANT: &str = r#"
+ctrl_interface=/run/wpa_supplicant
+network={
+    ssid="Home"
+    psk="not the hotspot"
+}
+network={
+    ssid=4c6162
+    mode=2
+    frequency=2437
+    key_mgmt=SAE
+    psk="correct horse"
+}
+"#;
+
+const GNOME_KEYFILE: &str = "\
+[connection]
+id=Hotspot
+type=wifi
+interface-name=wlp2s0
+
+[wifi]
+mode=ap
+ssid=72;105;
+band=bg
+channel=11
+
+[wifi-security]
+key-mgmt=wpa-psk
+psk=correct horse
+
+[ipv4]
+method=shared
+address1=10.42.0.1/24
+";
+
+#[test]
+fn hostapd_conf_maps_radio_and_security() {
+    let imported = profile_import::parse(HOSTAPD_CONF, "Workshop").unwrap();
+    assert_eq!(imported.profile.name, "Workshop");
+    assert_eq!(imported.ssid.as_deref(), Some("Workshop"));
+    assert_eq!(imported.password.as_deref(), Some("correct horse"));
+    assert_eq!(imported.security.as_deref(), Some("wpa2-wpa3"));
+    assert_eq!((imported.band.as_deref(), imported.channel), (Some("a"), Some(44)));
+    assert_eq!(imported.interface.as_deref(), Some("wlx00c0ca123456"));
+    assert!(imported.notes.is_empty());
+}
+
+#[test]
+fn wpa_supplicant_uses_the_ap_block() {
+    let imported = profile_import::parse(WPA_SUPPLICANT, "").unwrap();
+    assert_eq!(imported.profile.name, "Imported");
+    // Unquoted SSIDs are hex
+    assert_eq!(imported.ssid.as_deref(), Some("Lab"));
+    assert_eq!(imported.password.as_deref(), Some("correct horse"));
+    assert_eq!(imported.security.as_deref(), Some("wpa3"));
+    assert_eq!((imported.band.as_deref(), imported.channel), (Some("bg"), Some(6)));
+
+    let raw_key = WPA_SUPPLICANT.replace(r#"psk="correct horse""#, &format!("psk={}", "ab".repeat(32)));
+    let imported = profile_import::parse(&raw_key, "").unwrap();
+    assert_eq!(imported.password, None);
+    assert_eq!(imported.notes.len(), 1);
+
+    let client_only = "network={\n    ssid=\"Home\"\n}\n";
+    assert!(profile_import::parse(client_only, "").is_err());
+}
+
+#[test]
+fn gnome_keyfile_brings_its_subnet_and_name() {
+    let imported = profile_import::parse(GNOME_KEYFILE, "").unwrap();
+    assert_eq!(imported.profile.name, "Hotspot");
+    assert_eq!(imported.profile.gateway_ip, "10.42.0.1/24");
+    // Byte-list SSIDs are decoded
+    assert_eq!(imported.ssid.as_deref(), Some("Hi"));
+    assert_eq!(imported.security.as_deref(), Some("wpa2"));
+    assert_eq!(imported.channel, Some(11));
+
+    let client = GNOME_KEYFILE.replace("mode=ap", "mode=infrastructure");
+    assert!(profile_import::parse(&client, "").is_err());
+    let bad_subnet = GNOME_KEYFILE.replace("10.42.0.1/24", "10.42.0.1/40");
+    assert!(profile_import::parse(&bad_subnet, "").is_err());
+    assert!(profile_import::parse("just some text", "").is_err());
+}
+
+#[test]
+fn applying_adds_an_active_profile_without_clobbering_others() {
+    let mut config = Config::default();
+    let first = profile_import::parse(GNOME_KEYFILE, "").unwrap().apply(&mut config);
+    let second = profile_import::parse(GNOME_KEYFILE, "").unwrap().apply(&mut config);
+    assert_eq!((first.as_str(), second.as_str()), ("Hotspot", "Hotspot 2"));
+    assert_eq!(config.profiles.len(), 3);
+    assert_eq!(config.profiles[0].name, DEFAULT_PROFILE);
+    assert_eq!(config.profile().name, "Hotspot 2");
+    assert_eq!(config.ssid, "Hi");
+    assert_eq!(config.hotspot_interface, "wlp2s0");
+
+    // A channel that isn't valid on the band is left to automatic
+    let mut config = Config::default();
+    let mut imported = profile_import::parse(HOSTAPD_CONF, "").unwrap();
+    imported.band = Some("bg".to_string());
+    imported.apply(&mut config);
+    assert_eq!((config.band.as_str(), config.channel), ("bg", 0));
+}
+
+#[test]
+fn frequencies_map_to_channels() {
+    assert_eq!(channel_for_frequency(2412), Some(("bg", 1)));
+    assert_eq!(channel_for_frequency(2484), Some(("bg", 14)));
+    assert_eq!(channel_for_frequency(5180), Some(("a", 36)));
+    assert_eq!(channel_for_frequency(900), None);
+}
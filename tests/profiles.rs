@@ -0,0 +1,217 @@
+//! Per-profile settings: legacy config migration and validation.
+
+use cosmic_hotspot::config::{Config, Profile, DEFAULT_PROFILE};
+
+#[test]
+fn legacy_config_moves_into_default_profile() {
+    let config = Config::from_json(
+        r#"{"ssid": "Old", "gateway_ip": "10.0.5.1/24", "nat_strategy": "nftables"}"#,
+    )
+    .expect("parses");
+
+    assert_eq!(config.ssid, "Old");
+    assert_eq!(config.profiles.len(), 1);
+    assert_eq!(config.profile().name, DEFAULT_PROFILE);
+    assert_eq!(config.profile().gateway_ip, "10.0.5.1/24");
+    assert_eq!(config.profile().nat_strategy, "nftables");
+}
+
+#[test]
+fn active_profile_is_selected_by_name() {
+    let config = Config::from_json(
+        r#"{
+            "active_profile": "Guest",
+            "profiles": [
+                {"name": "VR", "gateway_ip": "192.168.50.1/24", "nat_strategy": "iptables"},
+                {"name": "Guest", "gateway_ip": "10.10.0.1/24", "client_isolation": true}
+            ]
+        }"#,
+    )
+    .expect("parses");
+
+    let profile = config.profile();
+    assert_eq!(profile.name, "Guest");
+    assert_eq!(profile.gateway_ip, "10.10.0.1/24");
+    assert!(profile.client_isolation);
+    // Unset fields take the defaults
+    assert_eq!(profile.nat_strategy, "auto");
+}
+
+#[test]
+fn stale_active_profile_falls_back_to_first() {
+    let config = Config::from_json(
+        r#"{"active_profile": "Gone", "profiles": [{"name": "VR", "gateway_ip": "192.168.50.1/24"}]}"#,
+    )
+    .expect("parses");
+    assert_eq!(config.profile().name, "VR");
+}
+
+#[test]
+fn empty_profile_list_gets_a_default() {
+    let config = Config::from_json(r#"{"profiles": []}"#).expect("parses");
+    assert_eq!(config.profiles, vec![Profile::default()]);
+}
+
+#[test]
+fn profile_edits_only_touch_the_active_profile() {
+    let mut config = Config::from_json(
+        r#"{"active_profile": "Guest", "profiles": [{"name": "VR"}, {"name": "Guest"}]}"#,
+    )
+    .expect("parses");
+    config.profile_mut().dns_server = "9.9.9.9".to_string();
+
+    assert_eq!(config.profiles[0].dns_server, "");
+    assert_eq!(config.profiles[1].dns_server, "9.9.9.9");
+}
+
+fn profile(gateway: &str, start: &str, end: &str) -> Profile {
+    Profile {
+        gateway_ip: gateway.to_string(),
+        dhcp_range_start: start.to_string(),
+        dhcp_range_end: end.to_string(),
+        ..Profile::default()
+    }
+}
+
+#[test]
+fn dhcp_range_must_sit_inside_the_subnet() {
+    assert!(profile("192.168.44.1/24", "", "").validate().is_ok());
+    assert!(profile("192.168.44.1/24", "192.168.44.10", "192.168.44.50").validate().is_ok());
+    assert!(profile("192.168.44.1/24", "192.168.45.10", "192.168.45.50").validate().is_err());
+    assert!(profile("10.0.0.1/16", "10.0.3.1", "10.0.9.255").validate().is_ok());
+}
+
+#[test]
+fn dhcp_range_needs_both_ends_in_order() {
+    assert!(profile("192.168.44.1/24", "192.168.44.10", "").validate().is_err());
+    assert!(profile("192.168.44.1/24", "192.168.44.50", "192.168.44.10").validate().is_err());
+    assert!(profile("192.168.44.1/24", "not-an-ip", "192.168.44.10").validate().is_err());
+}
+
+#[test]
+fn dns_server_and_strategy_are_checked() {
+    let mut p = Profile {
+        dns_server: "192.168.44.53".to_string(),
+        ..Profile::default()
+    };
+    assert!(p.validate().is_ok());
+    p.dns_server = "dns.example".to_string();
+    assert!(p.validate().is_err());
+    // Only a server on the hotspot's own subnet
+    p.dns_server = "1.1.1.1".to_string();
+    assert!(p.validate().is_err());
+
+    let p = Profile {
+        nat_strategy: "pf".to_string(),
+        ..Profile::default()
+    };
+    assert!(p.validate().is_err());
+}
+
+fn two_profiles() -> Config {
+    let mut config = Config {
+        ssid: "Home".to_string(),
+        password: "home-secret".to_string(),
+        band: "a".to_string(),
+        hotspot_interface: "wlp2s0".to_string(),
+        ..Config::default()
+    };
+    config.add_profile("Travel").expect("adds");
+    config
+}
+
+#[test]
+fn added_profile_starts_from_the_active_settings() {
+    let config = two_profiles();
+    assert_eq!(config.profiles.len(), 2);
+    assert_eq!(config.profile().name, DEFAULT_PROFILE);
+    let network = config.profiles[1].network.as_ref().expect("keeps its network");
+    assert_eq!(network.ssid, "Home");
+    assert_eq!(network.band, "a");
+}
+
+#[test]
+fn switching_swaps_network_settings() {
+    let mut config = two_profiles();
+    config.switch_profile("Travel").unwrap();
+    config.ssid = "Road".to_string();
+    config.password = "road-secret".to_string();
+    config.hotspot_interface = "wlx00c0ca".to_string();
+
+    config.switch_profile(DEFAULT_PROFILE).unwrap();
+    assert_eq!(config.profile().name, DEFAULT_PROFILE);
+    assert_eq!((config.ssid.as_str(), config.password.as_str()), ("Home", "home-secret"));
+    assert_eq!(config.hotspot_interface, "wlp2s0");
+    // The active profile's settings live in the config, not the profile
+    assert!(config.profile().network.is_none());
+
+    config.switch_profile("Travel").unwrap();
+    assert_eq!((config.ssid.as_str(), config.password.as_str()), ("Road", "road-secret"));
+    assert_eq!(config.hotspot_interface, "wlx00c0ca");
+
+    assert!(config.switch_profile("Nowhere").is_err());
+}
+
+#[test]
+fn profile_names_must_be_unique_and_non_empty() {
+    let mut config = two_profiles();
+    assert!(config.add_profile("Travel").is_err());
+    assert!(config.add_profile("  ").is_err());
+    assert!(config.rename_profile("Travel", DEFAULT_PROFILE).is_err());
+}
+
+#[test]
+fn renaming_follows_the_active_profile() {
+    let mut config = two_profiles();
+    config.switch_profile("Travel").unwrap();
+    config.rename_profile("Travel", "Road trip").unwrap();
+
+    assert_eq!(config.profile().name, "Road trip");
+    assert_eq!(config.active_profile, "Road trip");
+}
+
+#[test]
+fn deleting_the_active_profile_switches_away() {
+    let mut config = two_profiles();
+    config.switch_profile("Travel").unwrap();
+    config.ssid = "Road".to_string();
+    config.delete_profile("Travel").unwrap();
+
+    assert_eq!(config.profiles.len(), 1);
+    assert_eq!(config.profile().name, DEFAULT_PROFILE);
+    assert_eq!(config.ssid, "Home");
+    assert!(config.delete_profile(DEFAULT_PROFILE).is_err());
+}
+
+#[test]
+fn legacy_autostart_profile_becomes_a_profile_flag() {
+    let config = Config::from_json(
+        r#"{"autostart_profile": "Guest", "profiles": [{"name": "VR"}, {"name": "Guest"}]}"#,
+    )
+    .expect("parses");
+    assert!(!config.profiles[0].autostart);
+    assert!(config.profiles[1].autostart);
+}
+
+#[test]
+fn profile_idle_timeout_overrides_the_general_one() {
+    let mut config = Config { idle_off_mins: 30, ..Config::default() };
+    assert_eq!(config.idle_timeout_mins(), 30);
+    config.profile_mut().idle_off_override_mins = 5;
+    assert_eq!(config.idle_timeout_mins(), 5);
+}
+
+#[test]
+fn profile_schedule_and_battery_are_checked() {
+    let mut p = Profile::default();
+    assert_eq!(p.schedule(), Ok(None));
+    p.schedule_days = "mon-fri".to_string();
+    p.schedule_from = "09:00".to_string();
+    assert!(p.validate().is_err());
+    p.schedule_until = "17:00".to_string();
+    assert!(p.validate().is_ok());
+    assert!(p.schedule().unwrap().is_some());
+
+    p.battery_min_percent = 120;
+    assert!(p.validate().is_err());
+}
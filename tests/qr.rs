@@ -0,0 +1,27 @@
+//! Tests for the WiFi join QR code contents.
+
+use cosmic_hotspot::qr::{svg, wifi_uri};
+
+#[test]
+fn wpa_network() {
+    assert_eq!(wifi_uri("CosmicHotspot", "changeme123"), "WIFI:T:WPA;S:CosmicHotspot;P:changeme123;;");
+}
+
+#[test]
+fn open_network_has_no_password() {
+    assert_eq!(wifi_uri("Guest", ""), "WIFI:T:nopass;S:Guest;;");
+}
+
+#[test]
+fn special_characters_are_escaped() {
+    assert_eq!(
+        wifi_uri(r#"Bob's "Net"; 2,4:GHz"#, r"a\b;c"),
+        r#"WIFI:T:WPA;S:Bob's \"Net\"\; 2\,4\:GHz;P:a\\b\;c;;"#
+    );
+}
+
+#[test]
+fn renders_an_svg() {
+    let image = svg(&wifi_uri("CosmicHotspot", "changeme123"), 200).unwrap();
+    assert!(image.contains("<svg"));
+}
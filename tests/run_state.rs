@@ -0,0 +1,86 @@
+//! Start time and last error shared through the runtime dir.
+
+use cosmic_hotspot::config::Config;
+use cosmic_hotspot::errors::{ErrorKind, HotspotError};
+use cosmic_hotspot::run_state::{self, RunState};
+
+#[test]
+fn uptime_counts_from_start() {
+    let state = RunState { started_at: Some(1_000), ..RunState::default() };
+    assert_eq!(state.uptime(1_090), Some(90));
+    // A clock that went backwards doesn't underflow
+    assert_eq!(state.uptime(900), Some(0));
+    assert_eq!(RunState::default().uptime(1_090), None);
+}
+
+#[test]
+fn records_starts_failures_and_stops() {
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-run-state-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("XDG_RUNTIME_DIR", &dir);
+
+    assert_eq!(run_state::load(), RunState::default());
+
+    let config = Config::default();
+    run_state::record_start(&Ok("started".to_string()), Some("/ActiveConnection/7".to_string()), config.start_fingerprint());
+    let started = run_state::load();
+    assert!(started.started_at.is_some());
+    assert_eq!(started.activation.as_deref(), Some("/ActiveConnection/7"));
+    assert_eq!(started.last_error, None);
+    assert!(!started.uses_old_settings(&config));
+
+    // A failed restart keeps the start time and records the error
+    let renamed = Config { ssid: "Renamed".to_string(), ..Config::default() };
+    run_state::record_start(
+        &Err(HotspotError::new(ErrorKind::ActivationFailed, "no secrets")),
+        None,
+        renamed.start_fingerprint(),
+    );
+    let failed = run_state::load();
+    assert_eq!(failed.started_at, started.started_at);
+    assert_eq!(failed.activation, started.activation);
+    assert_eq!(failed.last_error.as_ref().map(|e| e.detail.as_str()), Some("no secrets"));
+    // Still running the old SSID
+    assert!(failed.uses_old_settings(&renamed));
+
+    // Brought back up with nmcli and adopted by the applet
+    run_state::record_adopted(500, "/ActiveConnection/9".to_string());
+    let adopted = run_state::load();
+    assert_eq!(adopted.started_at, Some(500));
+    assert_eq!(adopted.activation.as_deref(), Some("/ActiveConnection/9"));
+    // What an adopted hotspot was started with is unknown
+    assert!(!adopted.uses_old_settings(&renamed));
+
+    run_state::record_stop();
+    let stopped = run_state::load();
+    assert_eq!(stopped.started_at, None);
+    assert_eq!(stopped.activation, None);
+    assert!(stopped.last_error.is_some());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn reads_a_process_start_time() {
+    // Command names can contain spaces and parentheses
+    let stat = "1234 (dnsmasq (nm)) S 1 1234 1234 0 -1 4194624 120 0 0 0 3 1 0 0 20 0 1 0 \
+                250000 10874880 230 18446744073709551615 1 1 0 0 0 0 0 4096 92675 0 0 0 17 2 0 0 0 0 0";
+    assert_eq!(cosmic_hotspot::hotspot::process_start_time(stat, 1_700_000_000, 100), Some(1_700_002_500));
+    assert_eq!(cosmic_hotspot::hotspot::process_start_time("garbage", 1_700_000_000, 100), None);
+}
+
+#[test]
+fn only_start_settings_need_a_restart() {
+    let config = Config::default();
+    let state = RunState {
+        started_at: Some(1_000),
+        settings: Some(config.start_fingerprint()),
+        ..RunState::default()
+    };
+    // Popup layout applies without a restart
+    assert!(!state.uses_old_settings(&Config { popup_show_qr: !config.popup_show_qr, ..config.clone() }));
+    assert!(state.uses_old_settings(&Config { password: "a new password".to_string(), ..config.clone() }));
+    assert!(state.uses_old_settings(&Config { channel: 11, ..config.clone() }));
+    // Nothing's running after a stop
+    assert!(!RunState { started_at: None, ..state }.uses_old_settings(&Config { channel: 11, ..config }));
+}
@@ -0,0 +1,83 @@
+//! Saved network listing: which NM profiles can lend the hotspot their credentials.
+
+use std::collections::HashMap;
+
+use cosmic_hotspot::nm_dbus::OwnedSettings;
+use cosmic_hotspot::saved_networks::{self, SavedNetwork};
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Settings as GetSettings returns them for a WiFi connection
+fn wifi(name: &str, ssid: &str, mode: &str, key_mgmt: &str) -> OwnedSettings {
+    let owned = |value: Value<'_>| OwnedValue::try_from(value).expect("owned value");
+    let mut settings = OwnedSettings::new();
+    settings.insert(
+        "connection".to_string(),
+        HashMap::from([
+            ("id".to_string(), owned(Value::from(name))),
+            ("uuid".to_string(), owned(Value::from("0b6c1c7e-6f52-4b2a-9d0e-3c1f1a2b3c4d"))),
+            ("type".to_string(), owned(Value::from("802-11-wireless"))),
+        ]),
+    );
+    settings.insert(
+        "802-11-wireless".to_string(),
+        HashMap::from([
+            ("ssid".to_string(), owned(Value::from(ssid.as_bytes()))),
+            ("mode".to_string(), owned(Value::from(mode))),
+        ]),
+    );
+    if !key_mgmt.is_empty() {
+        settings.insert(
+            "802-11-wireless-security".to_string(),
+            HashMap::from([("key-mgmt".to_string(), owned(Value::from(key_mgmt)))]),
+        );
+    }
+    settings
+}
+
+#[test]
+fn accepts_wpa_personal_client_profiles() {
+    let ssid = |settings: OwnedSettings| saved_networks::personal_network(&settings).map(|n| n.ssid);
+    assert_eq!(ssid(wifi("Home-WiFi", "Home-WiFi", "infrastructure", "wpa-psk")), Some("Home-WiFi".to_string()));
+    assert_eq!(ssid(wifi("Home-WiFi", "Home-WiFi", "infrastructure", "sae")), Some("Home-WiFi".to_string()));
+    // NM leaves the mode out of profiles made before it was a setting
+    assert_eq!(ssid(wifi("Home-WiFi", "Home-WiFi", "", "sae")), Some("Home-WiFi".to_string()));
+    // Our own and other hotspots, enterprise and open networks have nothing to copy
+    assert_eq!(ssid(wifi("Hotspot", "Hotspot", "ap", "wpa-psk")), None);
+    assert_eq!(ssid(wifi("eduroam", "eduroam", "infrastructure", "wpa-eap")), None);
+    assert_eq!(ssid(wifi("Library", "Library", "infrastructure", "")), None);
+}
+
+#[test]
+fn keeps_the_connection_name_and_uuid() {
+    let network = saved_networks::personal_network(&wifi("Caf:e", "Café ☕", "infrastructure", "wpa-psk"))
+        .expect("personal network");
+    assert_eq!(
+        network,
+        SavedNetwork {
+            uuid: "0b6c1c7e-6f52-4b2a-9d0e-3c1f1a2b3c4d".to_string(),
+            name: "Caf:e".to_string(),
+            ssid: "Café ☕".to_string(),
+        }
+    );
+}
+
+#[test]
+fn skips_other_connection_types() {
+    let mut wired = wifi("Wired connection 1", "", "", "wpa-psk");
+    wired.get_mut("connection").expect("connection setting").insert(
+        "type".to_string(),
+        OwnedValue::try_from(Value::from("802-3-ethernet")).expect("owned value"),
+    );
+    assert_eq!(saved_networks::personal_network(&wired), None);
+}
+
+#[test]
+fn label_names_the_connection_when_it_differs() {
+    let network = |name: &str, ssid: &str| SavedNetwork {
+        uuid: String::new(),
+        name: name.to_string(),
+        ssid: ssid.to_string(),
+    };
+    assert_eq!(network("Home-WiFi", "Home-WiFi").label(), "Home-WiFi");
+    assert_eq!(network("Home", "Home-WiFi").label(), "Home-WiFi (Home)");
+}
@@ -0,0 +1,73 @@
+//! Per-client access schedules: parsing, the popup countdown, and the
+//! helper rules.
+
+use cosmic_hotspot::config::ClientPolicy;
+use cosmic_hotspot::nat::NatRequest;
+use cosmic_hotspot::schedule::{self, Schedule};
+
+const MON: usize = 0;
+const FRI: usize = 4;
+const SAT: usize = 5;
+
+fn at(hours: u32, minutes: u32) -> u32 {
+    hours * 60 + minutes
+}
+
+#[test]
+fn days_and_times_parse() {
+    let schedule = Schedule::parse("mon-fri", "07:00", "21:00").unwrap();
+    assert_eq!(schedule.days, [true, true, true, true, true, false, false]);
+    assert_eq!((schedule.from, schedule.until), (at(7, 0), at(21, 0)));
+
+    assert_eq!(Schedule::parse("fri-mon", "", "").unwrap().days, [true, false, false, false, true, true, true]);
+    assert_eq!(Schedule::parse("", "", "").unwrap().days, [true; 7]);
+    assert!(Schedule::parse("funday", "", "").is_err());
+    assert!(Schedule::parse("", "07:00", "").is_err());
+    assert!(Schedule::parse("", "24:00", "07:00").is_err());
+}
+
+#[test]
+fn windows_can_run_past_midnight() {
+    let evening = Schedule::parse("", "18:00", "02:00").unwrap();
+    assert!(evening.allows(MON, at(23, 30)));
+    assert!(evening.allows(MON, at(1, 0)));
+    assert!(!evening.allows(MON, at(12, 0)));
+}
+
+#[test]
+fn countdown_runs_to_the_next_change() {
+    let school_nights = Schedule::parse("mon-fri", "07:00", "21:00").unwrap();
+    assert!(school_nights.allows(MON, at(18, 46)));
+    assert_eq!(school_nights.minutes_until_change(MON, at(18, 46)), Some(at(2, 14)));
+    // Friday 21:00 until Monday 07:00
+    assert!(!school_nights.allows(FRI, at(21, 0)));
+    assert_eq!(school_nights.minutes_until_change(FRI, at(21, 0)), Some(at(58, 0)));
+    assert!(!school_nights.allows(SAT, at(12, 0)));
+
+    assert_eq!(Schedule::parse("", "", "").unwrap().minutes_until_change(MON, 0), None);
+    assert_eq!(schedule::format_duration(at(2, 14)), "2h 14m");
+    assert_eq!(schedule::format_duration(at(58, 0)), "2d 10h");
+}
+
+#[test]
+fn blocked_hours_and_days_become_time_matches() {
+    let request = NatRequest::parse(
+        &["schedule", "add", "wlan0", "eth0", "aa:bb:cc:dd:ee:ff", "mon-fri", "07:00", "21:00"]
+            .map(String::from),
+    )
+    .unwrap();
+    let commands: Vec<String> = request.rules().iter().map(|r| r.command.join(" ")).collect();
+    assert!(commands.iter().any(|c| c.contains("--weekdays Sat,Sun --kerneltz -j DROP")));
+    assert!(commands.iter().any(|c| c.contains("--timestart 21:00 --timestop 07:00 --kerneltz -j DROP")));
+    assert_eq!(NatRequest::parse(&request.to_args()).unwrap(), request);
+}
+
+#[test]
+fn unrestricted_policies_need_not_be_kept() {
+    let mut policy = ClientPolicy { mac: "aa:bb:cc:dd:ee:ff".to_string(), ..ClientPolicy::default() };
+    assert!(!policy.restricts());
+    policy.allowed_until = "21:00".to_string();
+    assert!(policy.schedule().is_err());
+    policy.allowed_from = "07:00".to_string();
+    assert!(policy.restricts());
+}
@@ -0,0 +1,66 @@
+//! The settings hub form is generated from these schemas; check the
+//! annotations it relies on.
+
+use cosmic_hotspot::config::{Config, Profile};
+use serde_json::Value;
+
+const SECTIONS: &[&str] = &[
+    "Network",
+    "Interfaces",
+    "Repeater",
+    "Profile",
+    "Advanced",
+    "Display",
+    "Shortcuts",
+];
+
+fn properties(schema: &schemars::Schema) -> serde_json::Map<String, Value> {
+    schema.as_value()["properties"]
+        .as_object()
+        .expect("schema has properties")
+        .clone()
+}
+
+#[test]
+fn shown_fields_have_a_known_section_and_label() {
+    for schema in [schemars::schema_for!(Config), schemars::schema_for!(Profile)] {
+        for (key, property) in properties(&schema) {
+            let Some(section) = property.get("x-section").and_then(Value::as_str) else {
+                continue;
+            };
+            assert!(SECTIONS.contains(&section), "{key}: unknown section {section}");
+            assert!(property.get("title").is_some(), "{key}: no label");
+        }
+    }
+}
+
+#[test]
+fn internal_state_is_hidden() {
+    let config = properties(&schemars::schema_for!(Config));
+    for key in ["profiles", "active_profile", "client_sort", "interface_macs"] {
+        assert!(config[key].get("x-section").is_none(), "{key} would be shown");
+    }
+    assert!(properties(&schemars::schema_for!(Profile))["name"].get("x-section").is_none());
+}
+
+#[test]
+fn static_options_include_the_default() {
+    let defaults = serde_json::to_value(Config::default()).unwrap();
+    for (key, property) in properties(&schemars::schema_for!(Config)) {
+        let Some(options) = property.get("x-options").and_then(Value::as_array) else {
+            continue;
+        };
+        assert!(
+            options.iter().any(|o| o["value"] == defaults[&key]),
+            "{key}: default {} isn't an option",
+            defaults[&key]
+        );
+    }
+}
+
+#[test]
+fn ranges_become_bounds() {
+    let config = properties(&schemars::schema_for!(Config));
+    assert_eq!(config["client_sweep_interval_secs"]["minimum"], 10);
+    assert_eq!(config["client_sweep_interval_secs"]["maximum"], 3600);
+}
@@ -0,0 +1,117 @@
+//! Password encryption at rest, with the key in the file fallback (no keyring
+//! is reachable: the session bus address points nowhere).
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::Once;
+
+use cosmic_hotspot::config::Config;
+use cosmic_hotspot::secrets;
+
+fn data_dir() -> PathBuf {
+    static SETUP: Once = Once::new();
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-secrets-{}", std::process::id()));
+    SETUP.call_once(|| {
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_var("XDG_DATA_HOME", &dir);
+        std::env::set_var("XDG_CONFIG_HOME", &dir);
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", "unix:path=/nonexistent/bus");
+    });
+    dir
+}
+
+#[test]
+fn round_trip() {
+    data_dir();
+    let sealed = secrets::encrypt("hunter2 with spaces").expect("encrypts");
+    assert!(secrets::is_encrypted(&sealed));
+    assert!(!sealed.contains("hunter2"));
+    assert_eq!(secrets::decrypt(&sealed).unwrap(), "hunter2 with spaces");
+}
+
+#[test]
+fn nonces_differ() {
+    data_dir();
+    assert_ne!(secrets::encrypt("same").unwrap(), secrets::encrypt("same").unwrap());
+}
+
+#[test]
+fn plaintext_and_empty_pass_through() {
+    data_dir();
+    assert_eq!(secrets::encrypt("").unwrap(), "");
+    assert_eq!(secrets::decrypt("changeme123").unwrap(), "changeme123");
+}
+
+#[test]
+fn tampered_value_is_rejected() {
+    data_dir();
+    let sealed = secrets::encrypt("secret").unwrap();
+    // Flip one ciphertext character, well clear of the prefix and padding
+    let at = "enc:v1:".len() + 20;
+    let flipped = if &sealed[at..=at] == "A" { "B" } else { "A" };
+    let tampered = format!("{}{flipped}{}", &sealed[..at], &sealed[at + 1..]);
+    assert!(secrets::decrypt(&tampered).is_err());
+    assert!(secrets::decrypt("enc:v1:AAAA").is_err());
+}
+
+#[test]
+fn key_file_is_private() {
+    let dir = data_dir();
+    secrets::encrypt("x").unwrap();
+    let meta = std::fs::metadata(dir.join("cosmic-hotspot").join("config.key")).expect("key file");
+    assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+    assert_eq!(meta.len(), 32);
+}
+
+#[test]
+fn seal_falls_back_to_encryption_without_keyring() {
+    data_dir();
+    assert!(!secrets::keyring_available());
+    let stored = secrets::seal("password", "changeme123").unwrap();
+    assert!(secrets::is_encrypted(&stored));
+    assert!(!secrets::in_keyring(&stored));
+    assert_eq!(secrets::open("password", &stored).unwrap(), "changeme123");
+    assert_eq!(secrets::seal("password", "").unwrap(), "");
+}
+
+#[test]
+fn keyring_marker_without_keyring_is_an_error() {
+    data_dir();
+    assert!(secrets::in_keyring("keyring:1700000000"));
+    assert!(secrets::open("password", "keyring:1700000000").is_err());
+}
+
+#[test]
+fn unreadable_password_is_written_back_unchanged() {
+    let dir = data_dir();
+    let path = dir.join("cosmic-hotspot").join("config.json");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, r#"{"password": "keyring:1700000001"}"#).unwrap();
+
+    let mut config = Config::load();
+    assert_eq!(config.password, "");
+    // Another unrelated setting changes; the password stays in the keyring
+    config.ssid = "Elsewhere".to_string();
+    config.save().unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("keyring:1700000001"));
+
+    // A newly entered password replaces it
+    let mut config = Config::load();
+    config.password = "fresh password".to_string();
+    config.save().unwrap();
+    let saved = Config::from_json(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(secrets::is_encrypted(&saved.password));
+
+    // Saving again leaves unchanged passwords as they were stored
+    let mut config = Config::load();
+    config.channel = 6;
+    config.save().unwrap();
+    let resaved = Config::from_json(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(resaved.password, saved.password);
+    config.password = "another".to_string();
+    config.save().unwrap();
+    let changed = Config::from_json(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_ne!(changed.password, saved.password);
+    assert_eq!(secrets::open("password", &changed.password).unwrap(), "another");
+}
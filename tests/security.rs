@@ -0,0 +1,31 @@
+//! Security modes mapped to NetworkManager's wireless security properties.
+
+use cosmic_hotspot::config::Config;
+use cosmic_hotspot::hotspot::{security_modes, security_settings};
+use zbus::zvariant::Value;
+
+#[test]
+fn wpa3_requires_protected_management_frames() {
+    // NM's pmf values: 2 = optional, 3 = required
+    assert_eq!(security_settings("wpa3"), [("key-mgmt", Value::from("sae")), ("pmf", Value::from(3i32))]);
+    assert_eq!(security_settings("wpa2-wpa3"), [("key-mgmt", Value::from("wpa-psk")), ("pmf", Value::from(2i32))]);
+    assert_eq!(security_settings("wpa2"), [("key-mgmt", Value::from("wpa-psk"))]);
+}
+
+#[test]
+fn only_hostapd_offers_transition_mode() {
+    // NM would silently host plain WPA2 for it
+    assert_eq!(security_modes("networkmanager"), ["wpa2", "wpa3"]);
+    assert_eq!(security_modes("hostapd"), ["wpa2", "wpa3", "wpa2-wpa3"]);
+}
+
+#[test]
+fn defaults_to_wpa2_and_needs_a_restart_to_change() {
+    let config = Config::default();
+    assert_eq!(config.security, "wpa2");
+    // Config files from before the setting existed keep WPA2
+    let old: Config = serde_json::from_str(r#"{"ssid": "Old"}"#).unwrap();
+    assert_eq!(old.security, "wpa2");
+    let wpa3 = Config { security: "wpa3".to_string(), ..config.clone() };
+    assert_ne!(wpa3.start_fingerprint(), config.start_fingerprint());
+}
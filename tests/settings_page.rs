@@ -0,0 +1,46 @@
+//! Embedding API of the settings page: what `update` reports to the host.
+
+use cosmic_hotspot::settings_page::{self, Message, Options, Output};
+
+#[test]
+fn rejected_save_is_reported_to_the_host() {
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-settings-page-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", &dir);
+
+    let (mut state, _) = settings_page::init_with(Options { restart_on_save: false, ..Options::default() });
+    assert!(!state.options.restart_on_save);
+
+    let (_, output) = settings_page::update(&mut state, Message::GatewayIpChanged("not an address".to_string()));
+    assert_eq!(output, None);
+    assert_eq!(state.status_message, "Unsaved changes");
+
+    let (_, output) = settings_page::update(&mut state, Message::Save);
+    assert!(matches!(output, Some(Output::Error(_))));
+    assert!(state.status_message.starts_with("Error: "));
+    // Reported once, not again on the next message
+    let (_, output) = settings_page::update(&mut state, Message::SsidChanged("Lab".to_string()));
+    assert_eq!(output, None);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn edits_are_unsaved_until_undone() {
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-settings-dirty-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", &dir);
+
+    let (mut state, _) = settings_page::init();
+    assert!(!settings_page::has_unsaved_changes(&state));
+
+    let ssid = state.config.ssid.clone();
+    settings_page::update(&mut state, Message::SsidChanged(format!("{ssid}-lab")));
+    assert!(settings_page::has_unsaved_changes(&state));
+
+    // Typing the old value back leaves nothing to save
+    settings_page::update(&mut state, Message::SsidChanged(ssid));
+    assert!(!settings_page::has_unsaved_changes(&state));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
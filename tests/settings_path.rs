@@ -0,0 +1,74 @@
+//! Dotted-key addressing used by `--settings-set`.
+
+use cosmic_hotspot::settings_path::{self, Segment};
+use serde_json::json;
+
+fn tree() -> serde_json::Value {
+    json!({
+        "ssid": "Cosmic",
+        "client_sweep": false,
+        "client_grace_secs": 60,
+        "profiles": [
+            {"name": "VR", "gateway_ip": "192.168.50.1/24"},
+            {"name": "Guest", "gateway_ip": "10.10.0.1/24"}
+        ],
+        "blocked": ["aa:bb:cc:dd:ee:ff"]
+    })
+}
+
+#[test]
+fn parses_keys_indices_and_append() {
+    assert_eq!(
+        settings_path::parse("profiles[1].name").unwrap(),
+        [Segment::Key("profiles".into()), Segment::Index(1), Segment::Key("name".into())]
+    );
+    assert_eq!(
+        settings_path::parse("filter.denylist[+]").unwrap(),
+        [Segment::Key("filter".into()), Segment::Key("denylist".into()), Segment::Append]
+    );
+    for bad in ["", ".ssid", "a..b", "list[", "list[x]", "list[+].name"] {
+        assert!(settings_path::parse(bad).is_err(), "accepted {bad:?}");
+    }
+}
+
+#[test]
+fn profile_is_addressed_by_name_or_index() {
+    let mut t = tree();
+    settings_path::set(&mut t, "profiles.Guest.gateway_ip", "\"10.20.0.1/24\"").unwrap();
+    settings_path::set(&mut t, "profiles[0].gateway_ip", "192.168.60.1/24").unwrap();
+    assert_eq!(t["profiles"][1]["gateway_ip"], "10.20.0.1/24");
+    assert_eq!(t["profiles"][0]["gateway_ip"], "192.168.60.1/24");
+}
+
+#[test]
+fn values_keep_their_type() {
+    let mut t = tree();
+    settings_path::set(&mut t, "client_sweep", "true").unwrap();
+    settings_path::set(&mut t, "client_grace_secs", "120").unwrap();
+    assert_eq!(t["client_sweep"], true);
+    assert_eq!(t["client_grace_secs"], 120);
+
+    assert!(settings_path::set(&mut t, "client_sweep", "\"yes\"").is_err());
+    assert!(settings_path::set(&mut t, "client_grace_secs", "soon").is_err());
+    // A bare number isn't taken as a string; it has to be quoted
+    assert!(settings_path::set(&mut t, "ssid", "42").is_err());
+}
+
+#[test]
+fn append_adds_to_lists() {
+    let mut t = tree();
+    settings_path::set(&mut t, "blocked[+]", "11:22:33:44:55:66").unwrap();
+    assert_eq!(t["blocked"], json!(["aa:bb:cc:dd:ee:ff", "11:22:33:44:55:66"]));
+    assert!(settings_path::set(&mut t, "blocked[+]", "7").is_err());
+    assert!(settings_path::set(&mut t, "ssid[+]", "x").is_err());
+}
+
+#[test]
+fn unknown_keys_and_entries_are_rejected() {
+    let mut t = tree();
+    assert!(settings_path::set(&mut t, "profiles.Guest.ssdi", "x").is_err());
+    assert!(settings_path::set(&mut t, "profiles.Travel.gateway_ip", "x").is_err());
+    assert!(settings_path::set(&mut t, "profiles[5].gateway_ip", "x").is_err());
+    assert!(settings_path::set(&mut t, "ssid.length", "3").is_err());
+    assert_eq!(t, tree());
+}
@@ -0,0 +1,70 @@
+//! The toggle shortcut in COSMIC's custom shortcuts file.
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use cosmic_hotspot::shortcut;
+
+// Both tests point XDG_CONFIG_HOME at their own directory
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn config_home(name: &str) -> (MutexGuard<'static, ()>, PathBuf, PathBuf) {
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = std::env::temp_dir().join(format!("cosmic-hotspot-shortcut-{name}-{}", std::process::id()));
+    std::env::set_var("XDG_CONFIG_HOME", &dir);
+    let path = dir.join("cosmic/com.system76.CosmicSettings.Shortcuts/v1/custom");
+    (guard, dir, path)
+}
+
+#[test]
+fn keeps_other_shortcuts_and_refuses_files_it_cannot_parse() {
+    let (_guard, dir, path) = config_home("parse");
+
+    // No file yet
+    shortcut::apply("Super+Shift+H").unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().contains("key: \"h\""));
+
+    let other = "(modifiers: [Super], key: \"t\"): Spawn(\"cosmic-term\")";
+    std::fs::write(&path, format!("{{\n    {other},\n}}")).unwrap();
+    shortcut::apply("Ctrl+Alt+F9").unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains(other));
+    assert!(content.contains("key: \"F9\""));
+
+    // Clearing the binding removes only ours
+    shortcut::apply("").unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains(other) && !content.contains("--toggle"));
+
+    let broken = format!("{{\n    {other}");
+    std::fs::write(&path, &broken).unwrap();
+    assert!(shortcut::apply("Super+Shift+H").is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), broken);
+    std::fs::write(&path, "{ (modifiers: [Super], key: \"t\"): Spawn(\"x\"), }) }").unwrap();
+    assert!(shortcut::apply("Super+Shift+H").is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn refuses_a_binding_another_action_already_has() {
+    let (_guard, dir, path) = config_home("conflict");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let other = "(modifiers: [Shift, Super], key: Some(\"t\")): Spawn(\"cosmic-term\")";
+    let original = format!("{{\n    {other},\n}}");
+    std::fs::write(&path, &original).unwrap();
+
+    // Same binding, written with the modifiers the other way round
+    let err = shortcut::apply("Super+Shift+T").unwrap_err();
+    assert!(err.contains("Spawn(\"cosmic-term\")"), "{err}");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+
+    // Rebinding our own toggle is not a conflict
+    shortcut::apply("Super+Shift+H").unwrap();
+    shortcut::apply("Super+Shift+H").unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(content.matches("--toggle").count(), 1);
+    assert!(content.contains(other));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,22 @@
+//! Tests for renaming the SSID when a nearby network already uses it.
+
+use cosmic_hotspot::hotspot::ssid_with_suffix;
+
+#[test]
+fn appends_the_suffix() {
+    assert_eq!(ssid_with_suffix("CosmicHotspot", "3f2a"), "CosmicHotspot-3f2a");
+}
+
+#[test]
+fn shortens_long_names_to_fit_32_bytes() {
+    let renamed = ssid_with_suffix("A-very-long-hotspot-name-indeed!", "3f2a");
+    assert_eq!(renamed, "A-very-long-hotspot-name-in-3f2a");
+    assert!(renamed.len() <= 32);
+}
+
+#[test]
+fn never_splits_a_character() {
+    let renamed = ssid_with_suffix("Café Café Café Café Café Café", "3f2a");
+    assert!(renamed.len() <= 32);
+    assert!(renamed.ends_with("-3f2a"));
+}
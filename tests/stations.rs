@@ -0,0 +1,73 @@
+//! Client band and bitrate from `iw` station dumps.
+
+use cosmic_hotspot::stations::{band_label, bars_glyph, parse_frequency, parse_station_dump, Radio, Station};
+
+const STATION_DUMP: &str = "\
+Station 3C:22:FB:12:34:56 (on wlan1)
+\tinactive time:\t310 ms
+\tsignal:  \t-52 [-54, -56] dBm
+\ttx bitrate:\t866.7 MBit/s VHT-MCS 9 80MHz short GI VHT-NSS 2
+\trx bitrate:\t6.0 MBit/s
+Station 2c:26:17:ab:cd:ef (on wlan1)
+\tsignal:  \t-71 dBm
+\ttx bitrate:\t72.2 MBit/s MCS 7 short GI
+Station 02:00:5e:00:00:01 (on wlan1)
+\ttx bitrate:\t54.0 MBit/s
+";
+
+const IW_INFO: &str = "\
+Interface wlan1
+\tifindex 4
+\ttype AP
+\tchannel 6 (2437 MHz), width: 20 MHz, center1: 2437 MHz
+\ttxpower 20.00 dBm
+";
+
+#[test]
+fn station_dump_gives_rate_generation_and_signal() {
+    assert_eq!(
+        parse_station_dump(STATION_DUMP),
+        [
+            Station { mac: "3c:22:fb:12:34:56".to_string(), tx_rate_mbps: Some(867), generation: Some(5), signal_dbm: Some(-52) },
+            Station { mac: "2c:26:17:ab:cd:ef".to_string(), tx_rate_mbps: Some(72), generation: Some(4), signal_dbm: Some(-71) },
+            // Legacy rates have no generation; some drivers report no signal
+            Station { mac: "02:00:5e:00:00:01".to_string(), tx_rate_mbps: Some(54), generation: None, signal_dbm: None },
+        ]
+    );
+    assert!(parse_station_dump("").is_empty());
+}
+
+#[test]
+fn band_comes_from_the_access_point() {
+    assert_eq!(parse_frequency(IW_INFO), Some(2437));
+    assert_eq!(parse_frequency("Interface wlan1\n\ttype managed\n"), None);
+    assert_eq!(band_label(2437), Some("2.4 GHz"));
+    assert_eq!(band_label(5180), Some("5 GHz"));
+    assert_eq!(band_label(5975), Some("6 GHz"));
+    assert_eq!(band_label(900), None);
+}
+
+#[test]
+fn summary_leaves_out_what_is_unknown() {
+    let quest = Radio { frequency_mhz: Some(2437), tx_rate_mbps: Some(72), generation: Some(4), signal_dbm: None };
+    assert_eq!(quest.summary(), "2.4 GHz · 72 Mbit/s (WiFi 4)");
+    let legacy = Radio { frequency_mhz: None, tx_rate_mbps: Some(54), generation: None, signal_dbm: None };
+    assert_eq!(legacy.summary(), "54 Mbit/s");
+    assert_eq!(Radio { frequency_mhz: None, tx_rate_mbps: None, generation: None, signal_dbm: None }.summary(), "");
+}
+
+#[test]
+fn signal_shows_as_bars() {
+    let at = |dbm| Radio { frequency_mhz: None, tx_rate_mbps: None, generation: None, signal_dbm: Some(dbm) };
+    assert_eq!(at(-40).signal_bars(), Some(4));
+    assert_eq!(at(-55).signal_bars(), Some(4));
+    assert_eq!(at(-60).signal_bars(), Some(3));
+    assert_eq!(at(-71).signal_bars(), Some(2));
+    assert_eq!(at(-80).signal_bars(), Some(1));
+    assert_eq!(at(-90).signal_bars(), Some(0));
+    assert_eq!(Radio { signal_dbm: None, ..at(0) }.signal_bars(), None);
+
+    assert_eq!(bars_glyph(4), "▂▄▆█");
+    assert_eq!(bars_glyph(2), "▂▄▁▁");
+    assert_eq!(bars_glyph(0), "▁▁▁▁");
+}
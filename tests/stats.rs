@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use cosmic_hotspot::stats;
+
+#[test]
+fn bitrates_scale_their_unit() {
+    assert_eq!(stats::format_bitrate(0), "0 bit/s");
+    assert_eq!(stats::format_bitrate(850_000), "850 kbit/s");
+    assert_eq!(stats::format_bitrate(4_300_000), "4.3 Mbit/s");
+    assert_eq!(stats::format_bitrate(48_000_000), "48 Mbit/s");
+    assert_eq!(stats::format_bitrate(1_200_000_000), "1.2 Gbit/s");
+}
+
+#[test]
+fn bitrate_from_counter_readings() {
+    assert_eq!(stats::bitrate(1_000, 13_000_000, Duration::from_secs(2)), Some(51_996_000));
+    // Counter reset: the interface was recreated
+    assert_eq!(stats::bitrate(5_000, 1_000, Duration::from_secs(2)), None);
+    assert_eq!(stats::bitrate(0, 1_000, Duration::ZERO), None);
+}
+
+#[test]
+fn summary_leaves_out_unknowns() {
+    assert_eq!(
+        stats::summary(3, Some(48_000_000), Some(Duration::from_secs(72 * 60))),
+        "3 clients · 48 Mbit/s · 1h 12m"
+    );
+    assert_eq!(stats::summary(1, None, None), "1 client");
+    assert_eq!(stats::summary(0, None, Some(Duration::from_secs(59))), "0 clients · 0m");
+}
@@ -0,0 +1,42 @@
+//! Restarting a background task that panics.
+
+use std::time::Duration;
+
+use cosmic_hotspot::supervisor::{self, MAX_BACKOFF};
+
+#[test]
+fn crashed_task_is_restarted_until_it_returns() {
+    let mut runs = 0;
+    let mut crashes = Vec::new();
+    supervisor::supervise(
+        || {
+            runs += 1;
+            if runs < 3 {
+                panic!("poll {runs} failed");
+            }
+        },
+        |message, count| crashes.push((message.to_string(), count)),
+        Duration::ZERO,
+    );
+    assert_eq!(runs, 3);
+    assert_eq!(crashes, [("poll 1 failed".to_string(), 1), ("poll 2 failed".to_string(), 2)]);
+}
+
+#[test]
+fn restarts_back_off() {
+    let backoff = Duration::from_secs(1);
+    assert_eq!(supervisor::restart_delay(backoff, 1), Duration::from_secs(1));
+    assert_eq!(supervisor::restart_delay(backoff, 2), Duration::from_secs(2));
+    assert_eq!(supervisor::restart_delay(backoff, 4), Duration::from_secs(8));
+    assert_eq!(supervisor::restart_delay(backoff, 40), MAX_BACKOFF);
+}
+
+#[test]
+fn panic_messages_are_recovered() {
+    let payload = std::panic::catch_unwind(|| panic!("static message")).unwrap_err();
+    assert_eq!(supervisor::panic_message(payload.as_ref()), "static message");
+    let payload = std::panic::catch_unwind(|| panic!("formatted {}", 7)).unwrap_err();
+    assert_eq!(supervisor::panic_message(payload.as_ref()), "formatted 7");
+    let payload = std::panic::catch_unwind(|| std::panic::panic_any(7u8)).unwrap_err();
+    assert_eq!(supervisor::panic_message(payload.as_ref()), "unknown panic");
+}
@@ -0,0 +1,73 @@
+//! Data quotas: period boundaries and the 80% warning.
+
+use cosmic_hotspot::usage::{self, Quota, QuotaPeriod, Usage};
+
+const DAY: u64 = 24 * 60 * 60;
+/// Monday 2026-03-16 00:00 UTC
+const MONDAY: u64 = 1_773_619_200;
+
+fn quota(period: QuotaPeriod, reset_minute: u32) -> Quota {
+    Quota { period, limit_bytes: 1_000_000, reset_minute }
+}
+
+#[test]
+fn daily_period_starts_at_the_reset_time() {
+    let q = quota(QuotaPeriod::Daily, 4 * 60);
+    // Tuesday 10:00: started at 04:00 that day
+    let now = MONDAY + DAY + 10 * 3600;
+    assert_eq!(usage::period_start(now, 10 * 3600, 1, &q), MONDAY + DAY + 4 * 3600);
+    // Tuesday 02:00: still the period from Monday 04:00
+    let now = MONDAY + DAY + 2 * 3600;
+    assert_eq!(usage::period_start(now, 2 * 3600, 1, &q), MONDAY + 4 * 3600);
+}
+
+#[test]
+fn weekly_period_starts_on_monday() {
+    let q = quota(QuotaPeriod::Weekly, 4 * 60);
+    // Thursday 12:00
+    let now = MONDAY + 3 * DAY + 12 * 3600;
+    assert_eq!(usage::period_start(now, 12 * 3600, 3, &q), MONDAY + 4 * 3600);
+    // Monday 02:00, before the reset: the previous week's period
+    let now = MONDAY + 7 * DAY + 2 * 3600;
+    assert_eq!(usage::period_start(now, 2 * 3600, 0, &q), MONDAY + 4 * 3600);
+    // Monday 05:00: a new period
+    let now = MONDAY + 7 * DAY + 5 * 3600;
+    assert_eq!(usage::period_start(now, 5 * 3600, 0, &q), MONDAY + 7 * DAY + 4 * 3600);
+}
+
+#[test]
+fn warns_once_per_period_at_80_percent() {
+    let q = quota(QuotaPeriod::Daily, 0);
+    let mut used = Usage::default();
+    assert!(!used.add(700_000, &q, MONDAY));
+    assert!(used.add(100_000, &q, MONDAY));
+    assert!(!used.add(300_000, &q, MONDAY));
+    assert_eq!(used.used(MONDAY), 1_100_000);
+
+    // The next period starts from zero and can warn again
+    assert!(!used.add(100_000, &q, MONDAY + DAY));
+    assert_eq!(used.used(MONDAY + DAY), 100_000);
+    assert!(used.add(800_000, &q, MONDAY + DAY));
+}
+
+#[test]
+fn usage_from_an_earlier_period_does_not_count() {
+    let mut used = Usage::default();
+    used.add(500_000, &quota(QuotaPeriod::Daily, 0), MONDAY);
+    assert_eq!(used.used(MONDAY + DAY), 0);
+}
+
+#[test]
+fn quota_is_off_without_a_period_or_size() {
+    let mut config = cosmic_hotspot::config::Config::default();
+    assert_eq!(Quota::from_config(&config), None);
+    config.quota_period = "weekly".to_string();
+    assert_eq!(Quota::from_config(&config), None);
+    config.quota_mb = 5000;
+    config.quota_reset_time = "04:30".to_string();
+    assert_eq!(Quota::from_config(&config), Some(Quota {
+        period: QuotaPeriod::Weekly,
+        limit_bytes: 5_000_000_000,
+        reset_minute: 4 * 60 + 30,
+    }));
+}
@@ -0,0 +1,42 @@
+use cosmic_hotspot::wol::{self, KNOWN_CLIENTS_LIMIT};
+
+#[test]
+fn parses_mac_addresses() {
+    assert_eq!(wol::parse_mac("aa:bb:cc:00:11:ff").unwrap(), [0xaa, 0xbb, 0xcc, 0x00, 0x11, 0xff]);
+    assert_eq!(wol::parse_mac("AA-BB-CC-00-11-FF").unwrap(), [0xaa, 0xbb, 0xcc, 0x00, 0x11, 0xff]);
+    assert!(wol::parse_mac("aa:bb:cc:00:11").is_err());
+    assert!(wol::parse_mac("aa:bb:cc:00:11:fff").is_err());
+    assert!(wol::parse_mac("aa:bb:cc:00:11:zz").is_err());
+}
+
+#[test]
+fn magic_packet_repeats_the_mac() {
+    let mac = [1, 2, 3, 4, 5, 6];
+    let packet = wol::magic_packet(mac);
+    assert_eq!(packet.len(), 102);
+    assert_eq!(&packet[..6], &[0xff; 6]);
+    assert!(packet[6..].chunks(6).all(|chunk| chunk == mac));
+}
+
+#[test]
+fn broadcast_address_of_the_gateway_subnet() {
+    assert_eq!(wol::broadcast_address("192.168.44.1/24"), Some("192.168.44.255".parse().unwrap()));
+    assert_eq!(wol::broadcast_address("10.42.0.1/16"), Some("10.42.255.255".parse().unwrap()));
+    assert_eq!(wol::broadcast_address("10.42.0.1"), Some("10.42.0.255".parse().unwrap()));
+    assert_eq!(wol::broadcast_address("not an ip"), None);
+}
+
+#[test]
+fn remembers_new_clients_up_to_the_limit() {
+    let mut known = vec!["aa:bb:cc:00:00:01".to_string()];
+    assert!(!wol::remember(&mut known, ["AA:BB:CC:00:00:01"]));
+    assert!(wol::remember(&mut known, ["AA:BB:CC:00:00:02"]));
+    assert_eq!(known, ["aa:bb:cc:00:00:01", "aa:bb:cc:00:00:02"]);
+
+    let many: Vec<String> = (0..KNOWN_CLIENTS_LIMIT).map(|i| format!("02:00:00:00:00:{i:02x}")).collect();
+    wol::remember(&mut known, many.iter().map(String::as_str));
+    assert_eq!(known.len(), KNOWN_CLIENTS_LIMIT);
+    // The oldest are dropped first
+    assert_eq!(known[0], many[0]);
+    assert!(!known.contains(&"aa:bb:cc:00:00:01".to_string()));
+}